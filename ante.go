@@ -0,0 +1,61 @@
+package app
+
+import (
+	"github.com/enigmampc/SecretNetwork/types/secp256r1"
+	"github.com/enigmampc/cosmos-sdk/codec"
+	sdk "github.com/enigmampc/cosmos-sdk/types"
+	sdkerrors "github.com/enigmampc/cosmos-sdk/types/errors"
+	"github.com/enigmampc/cosmos-sdk/x/auth"
+	"github.com/tendermint/tendermint/crypto"
+	"github.com/tendermint/tendermint/crypto/ed25519"
+	"github.com/tendermint/tendermint/crypto/multisig"
+)
+
+// SigVerificationGasConsumer extends auth.DefaultSigVerificationGasConsumer with the key types
+// the enclave can verify: ed25519 and secp256r1 signers, also as part of a multisig.
+func SigVerificationGasConsumer(
+	meter sdk.GasMeter, sig []byte, pubkey crypto.PubKey, params auth.Params,
+) error {
+	switch pubkey := pubkey.(type) {
+	case ed25519.PubKeyEd25519:
+		meter.ConsumeGas(params.SigVerifyCostED25519, "ante verify: ed25519")
+		return nil
+
+	case secp256r1.PubKey:
+		// Verifying secp256r1 costs about the same as secp256k1
+		meter.ConsumeGas(params.SigVerifyCostSecp256k1, "ante verify: secp256r1")
+		return nil
+
+	case multisig.PubKeyMultisigThreshold:
+		var multisignature multisig.Multisignature
+		if err := codec.Cdc.UnmarshalBinaryBare(sig, &multisignature); err != nil {
+			return sdkerrors.Wrap(sdkerrors.ErrUnauthorized, "malformed multisignature")
+		}
+
+		return consumeMultisignatureVerificationGas(meter, multisignature, pubkey, params)
+
+	default:
+		return auth.DefaultSigVerificationGasConsumer(meter, sig, pubkey, params)
+	}
+}
+
+// Same as the one in x/auth, except that the keys in the multisig are charged by SigVerificationGasConsumer
+func consumeMultisignatureVerificationGas(
+	meter sdk.GasMeter, sig multisig.Multisignature, pubkey multisig.PubKeyMultisigThreshold, params auth.Params,
+) error {
+	size := sig.BitArray.Size()
+	sigIndex := 0
+	for i := 0; i < size; i++ {
+		if sig.BitArray.GetIndex(i) {
+			if sigIndex >= len(sig.Sigs) || i >= len(pubkey.PubKeys) {
+				return sdkerrors.Wrap(sdkerrors.ErrUnauthorized, "malformed multisignature")
+			}
+			if err := SigVerificationGasConsumer(meter, sig.Sigs[sigIndex], pubkey.PubKeys[i], params); err != nil {
+				return err
+			}
+			sigIndex++
+		}
+	}
+
+	return nil
+}
@@ -6,6 +6,7 @@ import (
 	"os"
 	"path/filepath"
 
+	"github.com/enigmampc/SecretNetwork/types/secp256r1"
 	"github.com/enigmampc/SecretNetwork/x/compute"
 	reg "github.com/enigmampc/SecretNetwork/x/registration"
 	"github.com/enigmampc/SecretNetwork/x/tokenswap"
@@ -93,6 +94,7 @@ func MakeCodec() *codec.Codec {
 	codec.RegisterCrypto(cdc)
 	codec.RegisterEvidences(cdc)
 	authvesting.RegisterCodec(cdc)
+	secp256r1.RegisterCodec(cdc)
 
 	return cdc.Seal()
 }
@@ -200,6 +202,7 @@ func NewSecretNetworkApp(
 	crisisSubspace := app.paramsKeeper.Subspace(crisis.DefaultParamspace)
 	evidenceSubspace := app.paramsKeeper.Subspace(evidence.DefaultParamspace)
 	tokenswapSubspace := app.paramsKeeper.Subspace(tokenswap.DefaultParamspace)
+	computeSubspace := app.paramsKeeper.Subspace(compute.DefaultParamspace)
 	// The AccountKeeper handles address -> account lookups
 	app.accountKeeper = auth.NewAccountKeeper(
 		app.cdc,
@@ -304,8 +307,9 @@ func NewSecretNetworkApp(
 	app.computeKeeper = compute.NewKeeper(
 		app.cdc,
 		keys[compute.StoreKey],
+		computeSubspace,
 		app.accountKeeper, &app.bankKeeper, &app.govKeeper, &app.distrKeeper, &app.mintKeeper, &stakingKeeper,
-		computeRouter, computeDir, wasmConfig, supportedFeatures, nil, nil)
+		app.regKeeper, computeRouter, computeDir, wasmConfig, supportedFeatures, nil, nil)
 
 	// register the staking hooks
 	// NOTE: stakingKeeper above is passed by reference, so that it will contain these hooks
@@ -393,7 +397,7 @@ func NewSecretNetworkApp(
 		auth.NewAnteHandler(
 			app.accountKeeper,
 			app.supplyKeeper,
-			auth.DefaultSigVerificationGasConsumer,
+			SigVerificationGasConsumer,
 		),
 	)
 	app.SetEndBlocker(app.EndBlocker)
@@ -23,6 +23,7 @@ import (
 )
 
 const flagReset = "reset"
+const flagDcap = "dcap"
 
 func InitAttestation(
 	_ *server.Context, _ *codec.Codec) *cobra.Command {
@@ -64,6 +65,14 @@ blockchain. Writes the certificate in DER format to ~/attestation_cert
 				}
 			}
 
+			if viper.GetBool(flagDcap) {
+				_, err := api.CreateAttestationReportDcap()
+				if err != nil {
+					return fmt.Errorf("failed to create attestation report: %w", err)
+				}
+				return nil
+			}
+
 			spidFile, err := Asset("spid.txt")
 			if err != nil {
 				return fmt.Errorf("failed to initialize enclave: %w", err)
@@ -82,6 +91,7 @@ blockchain. Writes the certificate in DER format to ~/attestation_cert
 		},
 	}
 	cmd.Flags().Bool(flagReset, false, "Optional flag to regenerate the enclave registration key")
+	cmd.Flags().Bool(flagDcap, false, "Optional flag to attest with DCAP instead of EPID, for platforms that only support DCAP")
 
 	return cmd
 }
@@ -118,8 +128,9 @@ blockchain. Writes the certificate in DER format to ~/attestation_cert
 				return fmt.Errorf("failed to initialize enclave: %w", err)
 			}
 
-			// the master key of the generated certificate is returned here
-			masterKey, err := api.InitBootstrap(spidFile, apiKeyFile)
+			// the master key of the generated certificate is returned here.
+			// The seed is bound to the chain id and the attestation policy of the genesis file
+			masterKey, err := api.InitBootstrap(spidFile, apiKeyFile, genDoc.ChainID, regGenState.AttestationPolicy)
 			if err != nil {
 				return fmt.Errorf("failed to initialize enclave: %w", err)
 			}
@@ -218,7 +229,7 @@ func ParseCert(_ *server.Context, _ *codec.Codec) *cobra.Command {
 	return cmd
 }
 
-func ConfigureSecret(_ *server.Context, _ *codec.Codec) *cobra.Command {
+func ConfigureSecret(ctx *server.Context, cdc *codec.Codec) *cobra.Command {
 	cmd := &cobra.Command{
 		Use: "configure-secret [master-cert] [seed]",
 		Short: "After registration is successful, configure the secret node with the credentials file and the encrypted" +
@@ -238,9 +249,19 @@ func ConfigureSecret(_ *server.Context, _ *codec.Codec) *cobra.Command {
 				return fmt.Errorf("invalid encrypted seed format (requires hex string of length 96 without 0x prefix)")
 			}
 
+			// The enclave only accepts the seed if it was created for this chain, with this
+			// attestation policy
+			appState, genDoc, err := genutil.GenesisStateFromGenFile(cdc, ctx.Config.GenesisFile())
+			if err != nil {
+				return fmt.Errorf("failed to read the chain id from the genesis file: %w", err)
+			}
+			regGenState := reg.GetGenesisStateFromAppState(cdc, appState)
+
 			cfg := reg.SeedConfig{
-				EncryptedKey: seed,
-				MasterCert:   base64.StdEncoding.EncodeToString(cert),
+				EncryptedKey:      seed,
+				MasterCert:        base64.StdEncoding.EncodeToString(cert),
+				ChainID:           genDoc.ChainID,
+				AttestationPolicy: regGenState.AttestationPolicy,
 			}
 
 			cfgBytes, err := json.Marshal(&cfg)
@@ -570,22 +570,25 @@ export class RestClient {
     for (const l of logs) {
       for (const e of l.events) {
         if (e.type === "wasm") {
-          for (const nonce of nonces) {
-            let nonceOk = false;
-            for (const a of e.attributes) {
-              try {
-                a.key = Encoding.fromUtf8(await this.enigmautils.decrypt(Encoding.fromBase64(a.key), nonce));
-                nonceOk = true;
-              } catch (e) {}
+          for (const a of e.attributes) {
+            if (a.key === "contract_address") {
+              continue;
+            }
+            // Attributes the contract made public are not encrypted, so none of the nonces decrypts them
+            for (const nonce of nonces) {
+              let key: string;
               try {
+                key = Encoding.fromUtf8(await this.enigmautils.decrypt(Encoding.fromBase64(a.key), nonce));
+              } catch (e) {
+                continue;
+              }
+              a.key = key;
+              if (a.value !== "") {
                 a.value = Encoding.fromUtf8(
                   await this.enigmautils.decrypt(Encoding.fromBase64(a.value), nonce),
                 );
-                nonceOk = true;
-              } catch (e) {}
-            }
-            if (nonceOk) {
-              continue;
+              }
+              break;
             }
           }
         }
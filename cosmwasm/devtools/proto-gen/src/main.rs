@@ -0,0 +1,28 @@
+//! Generates `wasmi-runtime/src/proto` from the .proto files of the repo.
+//!
+//! The enclave can't run prost-build in a build script, since its dependencies are patched to
+//! their SGX forks, so the generated code is checked in. Run it with `make proto-gen` in
+//! `wasmi-runtime` after changing any of the .proto files.
+
+use std::io::Result;
+use std::path::Path;
+
+const PROTOS: &[&str] = &[
+    "proto/secret/compute/v1beta1/msg.proto",
+    "third_party/proto/cosmos/tx/v1beta1/tx.proto",
+];
+
+fn main() -> Result<()> {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let repo_root = manifest_dir.join("../../..");
+    let out_dir = manifest_dir.join("../../packages/wasmi-runtime/src/proto");
+
+    let protos: Vec<_> = PROTOS.iter().map(|proto| repo_root.join(proto)).collect();
+    let includes = [repo_root.join("proto"), repo_root.join("third_party/proto")];
+
+    prost_build::Config::new()
+        .out_dir(out_dir)
+        // The enclave can't depend on prost-types, so Any is generated along with the rest
+        .compile_well_known_types()
+        .compile_protos(&protos, &includes)
+}
@@ -0,0 +1,31 @@
+//! Types of SGX quotes and of the attestation reports that vouch for them, along with the parts of
+//! their parsing that only need the raw bytes.
+//!
+//! The enclave uses this crate to authenticate registering nodes, and `attestation-verifier` uses
+//! it to explain the outcome ahead of time, so both judge a certificate by the same code. It is
+//! `no_std` for the sake of the enclave. Checking signatures and reading the JSON of the reports
+//! needs crates that the enclave takes from their SGX ports, so that part is left to each user.
+#![no_std]
+
+extern crate alloc;
+
+mod quote;
+mod status;
+
+use core::fmt;
+
+pub use quote::{
+    SgxEcdsaQuoteAkType, SgxEnclaveReport, SgxEpidQuoteSigType, SgxQuote, SgxQuoteVersion,
+    ENCLAVE_REPORT_SIZE,
+};
+pub use status::{AdvisoryIDs, SgxQuoteStatus, WHITELISTED_ADVISORIES};
+
+/// Why a quote or an enclave report couldn't be parsed
+#[derive(Debug, PartialEq)]
+pub struct ParseError(pub &'static str);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
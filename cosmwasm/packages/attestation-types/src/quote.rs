@@ -0,0 +1,327 @@
+//! Types that contain information about SGX quotes.
+//! The layout follows the Intel SGX developer reference, and version 4 of the Attestation Service
+//! API, which returns the body of EPID quotes.
+//! https://api.trustedservices.intel.com/documents/sgx-attestation-api-spec.pdf
+use crate::ParseError;
+
+/// Size of the report of an enclave, as embedded in quotes
+pub const ENCLAVE_REPORT_SIZE: usize = 384;
+
+/// A report generated by an enclave that contains measurement, identity and
+/// other data related to enclave.
+///
+/// # Note
+///
+/// Do not confuse `SgxEnclaveReport` with `AttestationReport`.
+/// `SgxEnclaveReport` is generated by SGX hardware and endorsed by Quoting
+/// Enclave through local attestation. The endorsed `SgxEnclaveReport` is an
+/// `SgxQuote`. The quote is then sent to some attestation service (IAS or
+/// DCAP-based AS). The endorsed `SgxQuote` is an attestation report signed by
+/// attestation service's private key, a.k.a., `EndorsedAttestationReport`.
+#[derive(Debug)]
+pub struct SgxEnclaveReport {
+    /// Security version number of host system's CPU
+    pub cpu_svn: [u8; 16],
+    /// Misc select bits for the target enclave. Reserved for future function
+    /// extension.
+    pub misc_select: u32,
+    /// Attributes of the enclave, for example, whether the enclave is running
+    /// in debug mode.
+    pub attributes: [u8; 16],
+    /// Measurement value of the enclave
+    pub mr_enclave: [u8; 32],
+    /// Measurement value of the public key that verified the enclave
+    pub mr_signer: [u8; 32],
+    /// Product ID of the enclave
+    pub isv_prod_id: u16,
+    /// Security version number of the enclave
+    pub isv_svn: u16,
+    /// Set of data used for communication between enclave and target enclave.
+    /// Registration certificates carry the public key of the node in the first half.
+    pub report_data: [u8; 64],
+}
+
+/// Reads `n` bytes from `bytes` at `pos`, and advances `pos` past them
+fn take<'a>(bytes: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8], ParseError> {
+    if bytes.len() >= *pos + n {
+        let ret = &bytes[*pos..*pos + n];
+        *pos += n;
+        Ok(ret)
+    } else {
+        Err(ParseError("quote is too short"))
+    }
+}
+
+fn take_u16(bytes: &[u8], pos: &mut usize) -> Result<u16, ParseError> {
+    Ok(u16::from_le_bytes(take_array(bytes, pos)?))
+}
+
+fn take_array<T: Default + AsMut<[u8]>>(bytes: &[u8], pos: &mut usize) -> Result<T, ParseError> {
+    let mut array = T::default();
+    let len = array.as_mut().len();
+    array.as_mut().copy_from_slice(take(bytes, pos, len)?);
+    Ok(array)
+}
+
+impl SgxEnclaveReport {
+    /// Parse bytes of report into `SgxEnclaveReport`.
+    pub fn parse_from(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut pos: usize = 0;
+        let pos = &mut pos;
+
+        // Start parsing report by bytes following specifications. Don't
+        // transmute directly, since there may cause endianness issue.
+        // off 48, size 16
+        let cpu_svn = take_array(bytes, pos)?;
+        // off 64, size 4
+        let misc_select = u32::from_le_bytes(take_array(bytes, pos)?);
+        // off 68, size 28
+        let _reserved = take(bytes, pos, 28)?;
+        // off 96, size 16
+        let attributes = take_array(bytes, pos)?;
+        // off 112, size 32
+        let mr_enclave = take_array(bytes, pos)?;
+        // off 144, size 32
+        let _reserved = take(bytes, pos, 32)?;
+        // off 176, size 32
+        let mr_signer = take_array(bytes, pos)?;
+        // off 208, size 96
+        let _reserved = take(bytes, pos, 96)?;
+        // off 304, size 2
+        let isv_prod_id = take_u16(bytes, pos)?;
+        // off 306, size 2
+        let isv_svn = take_u16(bytes, pos)?;
+        // off 308, size 60
+        let _reserved = take(bytes, pos, 60)?;
+        // off 368, size 64
+        let mut report_data = [0u8; 64];
+        report_data.copy_from_slice(take(bytes, pos, 64)?);
+
+        if *pos != bytes.len() {
+            return Err(ParseError("enclave report has the wrong size"));
+        };
+
+        Ok(SgxEnclaveReport {
+            cpu_svn,
+            misc_select,
+            attributes,
+            mr_enclave,
+            mr_signer,
+            isv_prod_id,
+            isv_svn,
+            report_data,
+        })
+    }
+
+    /// Returns whether the enclave was launched in debug mode, which lets the host read its memory
+    pub fn is_debug(&self) -> bool {
+        self.attributes[0] & 0x02 != 0
+    }
+}
+
+/// SGX Quote structure version
+#[derive(Debug, PartialEq)]
+pub enum SgxQuoteVersion {
+    /// EPID quote version
+    V1(SgxEpidQuoteSigType),
+    /// EPID quote version
+    V2(SgxEpidQuoteSigType),
+    /// ECDSA quote version
+    V3(SgxEcdsaQuoteAkType),
+}
+
+/// Intel EPID attestation signature type
+#[derive(Debug, PartialEq)]
+pub enum SgxEpidQuoteSigType {
+    Unlinkable,
+    Linkable,
+}
+
+/// ECDSA attestation key type
+#[derive(Debug, PartialEq)]
+pub enum SgxEcdsaQuoteAkType {
+    /// ECDSA-256-with-P-256 curve
+    P256_256,
+    /// ECDSA-384-with-P-384 curve
+    P384_384,
+}
+
+/// An application that hosts an enclave can ask the enclave to produce a report
+/// (`SgxEnclaveReport`) and then pass this report to a platform service
+/// (Quoting Enclave) to produce a type of credential that reflects the enclave
+/// and platform state. The quote can be passed to entities off the platform,
+/// and verified using Intel EPID signature verification techniques.
+///
+/// This is the body of the quote, as returned by IAS in `isvEnclaveQuoteBody`.
+#[derive(Debug)]
+pub struct SgxQuote {
+    /// Version of the quote structure
+    pub version: SgxQuoteVersion,
+    /// ID of the Intel EPID group of the platform belongs to
+    pub gid: u32,
+    /// Security version number of Quoting Enclave
+    pub isv_svn_qe: u16,
+    /// Security version number of PCE
+    pub isv_svn_pce: u16,
+    /// Vendor ID of Quoting Enclave
+    pub qe_vendor_id: [u8; 16],
+    /// User data
+    pub user_data: [u8; 20],
+    /// Report generated by the enclave
+    pub isv_enclave_report: SgxEnclaveReport,
+}
+
+impl SgxQuote {
+    /// Parse from bytes to `SgxQuote`.
+    pub fn parse_from(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut pos: usize = 0;
+        let pos = &mut pos;
+
+        // off 0, size 2 + 2
+        let version = take_u16(bytes, pos)?;
+        let sign_type = take_u16(bytes, pos)?;
+        let version = match (version, sign_type) {
+            (1, 0) => SgxQuoteVersion::V1(SgxEpidQuoteSigType::Unlinkable),
+            (1, 1) => SgxQuoteVersion::V1(SgxEpidQuoteSigType::Linkable),
+            (2, 0) => SgxQuoteVersion::V2(SgxEpidQuoteSigType::Unlinkable),
+            (2, 1) => SgxQuoteVersion::V2(SgxEpidQuoteSigType::Linkable),
+            (3, 2) => SgxQuoteVersion::V3(SgxEcdsaQuoteAkType::P256_256),
+            (3, 3) => SgxQuoteVersion::V3(SgxEcdsaQuoteAkType::P384_384),
+            (1, _) | (2, _) => return Err(ParseError("invalid EPID quote signature type")),
+            (3, _) => return Err(ParseError("invalid ECDSA quote attestation key type")),
+            _ => return Err(ParseError("unknown quote version")),
+        };
+        // off 4, size 4
+        let gid = u32::from_le_bytes(take_array(bytes, pos)?);
+        // off 8, size 2
+        let isv_svn_qe = take_u16(bytes, pos)?;
+        // off 10, size 2
+        let isv_svn_pce = take_u16(bytes, pos)?;
+        // off 12, size 16
+        let qe_vendor_id = take_array(bytes, pos)?;
+        // off 28, size 20
+        let user_data = take_array(bytes, pos)?;
+        // off 48, size 384
+        let isv_enclave_report =
+            SgxEnclaveReport::parse_from(take(bytes, pos, ENCLAVE_REPORT_SIZE)?)?;
+
+        if *pos != bytes.len() {
+            return Err(ParseError("quote has the wrong size"));
+        };
+
+        Ok(Self {
+            version,
+            gid,
+            isv_svn_qe,
+            isv_svn_pce,
+            qe_vendor_id,
+            user_data,
+            isv_enclave_report,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// `isvEnclaveQuoteBody` of a report returned by IAS for a group out of date platform
+    const QUOTE_BODY: &str = "AgABAC8LAAAKAAkAAAAAAK1zRQOIpndiP4IhlnW2AkwAAAAA\
+                              AAAAAAAAAAAAAAAABQ4CBf+AAAAAAAAAAAAAAAAAAAAAAAAA\
+                              AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABwAAAAAAAAAHAAAA\
+                              AAAAADMKqRCjd2eA4gAmrj2sB68OWpMfhPH4MH27hZAvWGlT\
+                              AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACD1xnn\
+                              ferKFHD2uvYqTXdDA8iZ22kCD5xw7h38CMfOngAAAAAAAAAA\
+                              AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
+                              AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
+                              AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
+                              AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
+                              AAAAAAAAAADYIY9k0MVmCdIDUuFLf/2bGIHAfPjO9nvC7fgz\
+                              rQedeA3WW4dFeI6oe+RCLdV3XYD1n6lEZjITOzPPLWDxulGz";
+
+    #[test]
+    fn sgx_quote_parse_from() {
+        let quote_raw = base64::decode(QUOTE_BODY).unwrap();
+        let sgx_quote = SgxQuote::parse_from(quote_raw.as_slice()).unwrap();
+
+        assert_eq!(
+            sgx_quote.version,
+            SgxQuoteVersion::V2(SgxEpidQuoteSigType::Linkable)
+        );
+        assert_eq!(sgx_quote.gid, 2863);
+        assert_eq!(sgx_quote.isv_svn_qe, 10);
+        assert_eq!(sgx_quote.isv_svn_pce, 9);
+        assert_eq!(
+            sgx_quote.qe_vendor_id,
+            [0, 0, 0, 0, 173, 115, 69, 3, 136, 166, 119, 98, 63, 130, 33, 150]
+        );
+        assert_eq!(
+            sgx_quote.user_data,
+            [117, 182, 2, 76, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let isv_enclave_report = sgx_quote.isv_enclave_report;
+        assert_eq!(
+            isv_enclave_report.cpu_svn,
+            [5, 14, 2, 5, 255, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(isv_enclave_report.misc_select, 0);
+        assert_eq!(
+            isv_enclave_report.attributes,
+            [7, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0]
+        );
+        // INIT, DEBUG and MODE64BIT
+        assert!(isv_enclave_report.is_debug());
+        assert_eq!(
+            isv_enclave_report.mr_enclave,
+            [
+                51, 10, 169, 16, 163, 119, 103, 128, 226, 0, 38, 174, 61, 172, 7, 175, 14, 90, 147,
+                31, 132, 241, 248, 48, 125, 187, 133, 144, 47, 88, 105, 83
+            ]
+        );
+        assert_eq!(
+            isv_enclave_report.mr_signer,
+            [
+                131, 215, 25, 231, 125, 234, 202, 20, 112, 246, 186, 246, 42, 77, 119, 67, 3, 200,
+                153, 219, 105, 2, 15, 156, 112, 238, 29, 252, 8, 199, 206, 158
+            ]
+        );
+        assert_eq!(isv_enclave_report.isv_prod_id, 0);
+        assert_eq!(isv_enclave_report.isv_svn, 0);
+        assert_eq!(
+            isv_enclave_report.report_data[..],
+            [
+                216, 33, 143, 100, 208, 197, 102, 9, 210, 3, 82, 225, 75, 127, 253, 155, 24, 129,
+                192, 124, 248, 206, 246, 123, 194, 237, 248, 51, 173, 7, 157, 120, 13, 214, 91,
+                135, 69, 120, 142, 168, 123, 228, 66, 45, 213, 119, 93, 128, 245, 159, 169, 68,
+                102, 50, 19, 59, 51, 207, 45, 96, 241, 186, 81, 179
+            ][..]
+        );
+    }
+
+    #[test]
+    fn sgx_quote_parse_from_wrong_size() {
+        let mut quote_raw = base64::decode(QUOTE_BODY).unwrap();
+        quote_raw.push(0);
+        assert_eq!(
+            SgxQuote::parse_from(&quote_raw).unwrap_err(),
+            ParseError("quote has the wrong size")
+        );
+
+        quote_raw.truncate(100);
+        assert_eq!(
+            SgxQuote::parse_from(&quote_raw).unwrap_err(),
+            ParseError("quote is too short")
+        );
+    }
+
+    #[test]
+    fn sgx_quote_parse_from_unknown_version() {
+        let mut quote_raw = base64::decode(QUOTE_BODY).unwrap();
+        quote_raw[0] = 4;
+        assert_eq!(
+            SgxQuote::parse_from(&quote_raw).unwrap_err(),
+            ParseError("unknown quote version")
+        );
+    }
+}
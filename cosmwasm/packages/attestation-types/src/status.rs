@@ -0,0 +1,224 @@
+//! The status of a quote, as judged by IAS for EPID quotes, or by the TCB info and QE identity of
+//! the platform for DCAP quotes, and the security advisories that come with it.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use enclave_ffi_types::NodeAuthResult;
+
+/// SGX Quote status
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum SgxQuoteStatus {
+    /// EPID signature of the ISV enclave QUOTE was verified correctly and the
+    /// TCB level of the SGX platform is up-to-date.
+    OK,
+    /// EPID signature of the ISV enclave QUOTE was invalid. The content of the
+    /// QUOTE is not trustworthy.
+    ///
+    /// For DCAP, the signature over the application report is invalid.
+    SignatureInvalid,
+    /// The EPID group has been revoked. When this value is returned, the
+    /// revocation Reason field of the Attestation Verification Report will
+    /// contain revocation reason code for this EPID group as reported in the
+    /// EPID Group CRL. The content of the QUOTE is not trustworthy.
+    GroupRevoked,
+    /// The EPID private key used to sign the QUOTE has been revoked by
+    /// signature. The content of the QUOTE is not trustworthy.
+    SignatureRevoked,
+    /// The EPID private key used to sign the QUOTE has been directly revoked
+    /// (not by signature). The content of the QUOTE is not trustworthy.
+    ///
+    /// For DCAP, the attestation key or platform has been revoked.
+    KeyRevoked,
+    /// SigRL version in ISV enclave QUOTE does not match the most recent
+    /// version of the SigRL. In rare situations, after SP retrieved the SigRL
+    /// from IAS and provided it to the platform, a newer version of the SigRL
+    /// is madeavailable. As a result, the Attestation Verification Report will
+    /// indicate SIGRL_VERSION_MISMATCH. SP can retrieve the most recent version
+    /// of SigRL from the IAS and request the platform to perform remote
+    /// attestation again with the most recent version of SigRL. If the platform
+    /// keeps failing to provide a valid QUOTE matching with the most recent
+    /// version of the SigRL, the content of the QUOTE is not trustworthy.
+    SigrlVersionMismatch,
+    /// The EPID signature of the ISV enclave QUOTE has been verified correctly,
+    /// but the TCB level of SGX platform is outdated (for further details see
+    /// Advisory IDs). The platform has not been identified as compromised and
+    /// thus it is not revoked. It is up to the Service Provider to decide
+    /// whether or not to trust the content of the QUOTE, andwhether or not to
+    /// trust the platform performing the attestation to protect specific
+    /// sensitive information.
+    GroupOutOfDate,
+    /// The EPID signature of the ISV enclave QUOTE has been verified correctly,
+    /// but additional configuration of SGX platform may be needed(for further
+    /// details see Advisory IDs). The platform has not been identified as
+    /// compromised and thus it is not revoked. It is up to the Service Provider
+    /// to decide whether or not to trust the content of the QUOTE, and whether
+    /// or not to trust the platform performing the attestation to protect
+    /// specific sensitive information.
+    ///
+    /// For DCAP, The Quote verification passed and the platform is patched to
+    /// the latest TCB level but additional configuration of the SGX
+    /// platform may be needed.
+    ConfigurationNeeded,
+    /// The EPID signature of the ISV enclave QUOTE has been verified correctly
+    /// but due to certain issues affecting the platform, additional SW
+    /// Hardening in the attesting SGX enclaves may be needed.The relying party
+    /// should evaluate the potential risk of an attack leveraging the relevant
+    /// issues on the attesting enclave, and whether the attesting enclave
+    /// employs adequate software hardening to mitigate the risk.
+    SwHardeningNeeded,
+    /// The EPID signature of the ISV enclave QUOTE has been verified correctly
+    /// but additional configuration for the platform and SW Hardening in the
+    /// attesting SGX enclaves may be needed. The platform has not been
+    /// identified as compromised and thus it is not revoked. It is up to the
+    /// Service Provider to decide whether or not to trust the content of the
+    /// QUOTE. The relying party should also evaluate the potential risk of an
+    /// attack leveraging the relevant issues on the attestation enclave, and
+    /// whether the attesting enclave employs adequate software hardening to
+    /// mitigate the risk.
+    ConfigurationAndSwHardeningNeeded,
+    /// DCAP specific quote status. The Quote is good but TCB level of the
+    /// platform is out of date. The platform needs patching to be at the latest
+    /// TCB level.
+    OutOfDate,
+    /// DCAP specific quote status. The Quote is good but the TCB level of the
+    /// platform is out of date and additional configuration of the SGX Platform
+    /// at its current patching level may be needed. The platform needs patching
+    /// to be at the latest TCB level.
+    OutOfDateConfigurationNeeded,
+    /// Other unknown bad status.
+    UnknownBadStatus,
+}
+
+impl From<&SgxQuoteStatus> for NodeAuthResult {
+    fn from(status: &SgxQuoteStatus) -> Self {
+        match status {
+            SgxQuoteStatus::ConfigurationAndSwHardeningNeeded => {
+                NodeAuthResult::SwHardeningAndConfigurationNeeded
+            }
+            SgxQuoteStatus::ConfigurationNeeded => NodeAuthResult::ConfigurationNeeded,
+            SgxQuoteStatus::GroupOutOfDate => NodeAuthResult::GroupOutOfDate,
+            SgxQuoteStatus::KeyRevoked => NodeAuthResult::KeyRevoked,
+            SgxQuoteStatus::SigrlVersionMismatch => NodeAuthResult::SigrlVersionMismatch,
+            SgxQuoteStatus::SignatureRevoked => NodeAuthResult::SignatureRevoked,
+            SgxQuoteStatus::GroupRevoked => NodeAuthResult::GroupRevoked,
+            _ => NodeAuthResult::BadQuoteStatus,
+        }
+    }
+}
+
+impl From<&str> for SgxQuoteStatus {
+    /// Convert from str status from the report to enum.
+    fn from(status: &str) -> Self {
+        match status {
+            "OK" => SgxQuoteStatus::OK,
+            "SIGNATURE_INVALID" => SgxQuoteStatus::SignatureInvalid,
+            "GROUP_REVOKED" => SgxQuoteStatus::GroupRevoked,
+            "SIGNATURE_REVOKED" => SgxQuoteStatus::SignatureRevoked,
+            "KEY_REVOKED" => SgxQuoteStatus::KeyRevoked,
+            "SIGRL_VERSION_MISMATCH" => SgxQuoteStatus::SigrlVersionMismatch,
+            "GROUP_OUT_OF_DATE" => SgxQuoteStatus::GroupOutOfDate,
+            "OUT_OF_DATE" => SgxQuoteStatus::OutOfDate,
+            "OUT_OF_DATE_CONFIGURATION_NEEDED" => SgxQuoteStatus::OutOfDateConfigurationNeeded,
+            "CONFIGURATION_NEEDED" => SgxQuoteStatus::ConfigurationNeeded,
+            "SW_HARDENING_NEEDED" => SgxQuoteStatus::SwHardeningNeeded,
+            "CONFIGURATION_AND_SW_HARDENING_NEEDED" => {
+                SgxQuoteStatus::ConfigurationAndSwHardeningNeeded
+            }
+            _ => SgxQuoteStatus::UnknownBadStatus,
+        }
+    }
+}
+
+impl SgxQuoteStatus {
+    /// Convert from the TCB status of a DCAP TCB info or QE identity level to enum.
+    pub fn from_tcb_status(status: &str) -> Self {
+        match status {
+            "UpToDate" => SgxQuoteStatus::OK,
+            "SWHardeningNeeded" => SgxQuoteStatus::SwHardeningNeeded,
+            "ConfigurationNeeded" => SgxQuoteStatus::ConfigurationNeeded,
+            "ConfigurationAndSWHardeningNeeded" => {
+                SgxQuoteStatus::ConfigurationAndSwHardeningNeeded
+            }
+            "OutOfDate" => SgxQuoteStatus::OutOfDate,
+            "OutOfDateConfigurationNeeded" => SgxQuoteStatus::OutOfDateConfigurationNeeded,
+            "Revoked" => SgxQuoteStatus::KeyRevoked,
+            _ => SgxQuoteStatus::UnknownBadStatus,
+        }
+    }
+}
+
+/// Advisories that the enclave accepts without an attestation policy, in both production and
+/// development builds
+pub const WHITELISTED_ADVISORIES: &[&str] = &["INTEL-SA-00334", "INTEL-SA-00219"];
+
+/// What the operator of a platform has to do about an advisory, if we know it
+fn advisory_description(id: &str) -> Option<&'static str> {
+    match id {
+        "INTEL-SA-00161" => Some("You must disable hyperthreading in the BIOS"),
+        "INTEL-SA-00289" => Some("You must disable overclocking/undervolting in the BIOS"),
+        _ => None,
+    }
+}
+
+/// Intel security advisories that affect the platform
+#[derive(Debug, Default)]
+pub struct AdvisoryIDs(pub Vec<String>);
+
+impl AdvisoryIDs {
+    /// Returns the advisories that aren't whitelisted, along with what the operator has to do
+    /// about each of them if we know it
+    pub fn vulnerable(&self) -> Vec<(String, Option<&'static str>)> {
+        self.0
+            .iter()
+            .filter(|id| !WHITELISTED_ADVISORIES.contains(&id.as_str()))
+            .map(|id| (id.clone(), advisory_description(id)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn quote_status_from_str() {
+        assert_eq!(SgxQuoteStatus::from("OK"), SgxQuoteStatus::OK);
+        assert_eq!(
+            SgxQuoteStatus::from("GROUP_OUT_OF_DATE"),
+            SgxQuoteStatus::GroupOutOfDate
+        );
+        assert_eq!(
+            SgxQuoteStatus::from("UpToDate"),
+            SgxQuoteStatus::UnknownBadStatus
+        );
+        assert_eq!(
+            SgxQuoteStatus::from_tcb_status("UpToDate"),
+            SgxQuoteStatus::OK
+        );
+        assert_eq!(
+            SgxQuoteStatus::from_tcb_status("Revoked"),
+            SgxQuoteStatus::KeyRevoked
+        );
+    }
+
+    #[test]
+    fn vulnerable_advisories() {
+        let advisories = AdvisoryIDs(vec![
+            "INTEL-SA-00334".to_string(),
+            "INTEL-SA-00161".to_string(),
+            "INTEL-SA-00999".to_string(),
+        ]);
+        assert_eq!(
+            advisories.vulnerable(),
+            vec![
+                (
+                    "INTEL-SA-00161".to_string(),
+                    Some("You must disable hyperthreading in the BIOS")
+                ),
+                ("INTEL-SA-00999".to_string(), None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,81 @@
+/// The root certificate of the Intel Attestation Service, the same one the enclave is built with
+pub const IAS_REPORT_CA: &[u8] =
+    include_bytes!("../../wasmi-runtime/Intel_SGX_Attestation_RootCA.pem");
+
+fn extract_asn1_value(cert: &[u8], oid: &[u8]) -> Option<Vec<u8>> {
+    let mut offset = cert.windows(oid.len()).position(|window| window == oid)?;
+
+    offset += 12; // 11 + TAG (0x04)
+
+    if offset + 2 >= cert.len() {
+        return None;
+    }
+
+    // Obtain Netscape Comment length
+    let mut len = cert[offset] as usize;
+    if len > 0x80 {
+        len = (cert[offset + 1] as usize) * 0x100 + (cert[offset + 2] as usize);
+        offset += 2;
+    }
+
+    // Obtain Netscape Comment
+    offset += 1;
+
+    if offset + len >= cert.len() {
+        return None;
+    }
+
+    Some(cert[offset..offset + len].to_vec())
+}
+
+/// Returns the netscape comment of a registration certificate, which is where the enclave puts
+/// its attestation report.
+///
+/// This looks the comment up the same (somewhat loose) way the enclave does, so that certificates
+/// the enclave can't read aren't reported as valid here.
+pub fn get_netscape_comment(cert_der: &[u8]) -> Option<Vec<u8>> {
+    // Search for Netscape Comment OID
+    let ns_cmt_oid = &[
+        0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x86, 0xF8, 0x42, 0x01, 0x0D,
+    ];
+    extract_asn1_value(cert_der, ns_cmt_oid)
+}
+
+/// Returns `IAS_REPORT_CA` in DER form
+pub fn ias_root_ca_der() -> Vec<u8> {
+    let mut ias_ca_stripped = IAS_REPORT_CA.to_vec();
+    ias_ca_stripped.retain(|&x| x != 0x0d && x != 0x0a);
+    let head_len = "-----BEGIN CERTIFICATE-----".len();
+    let tail_len = "-----END CERTIFICATE-----".len();
+    let full_len = ias_ca_stripped.len();
+    let ias_ca_core: &[u8] = &ias_ca_stripped[head_len..full_len - tail_len];
+    base64::decode_config(ias_ca_core, base64::STANDARD).expect("IAS root CA is not valid PEM")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn netscape_comment_of_hw_cert_is_the_report() {
+        let cert =
+            include_bytes!("../../wasmi-runtime/src/registration/fixtures/attestation_cert_hw_v2");
+        let comment = get_netscape_comment(cert).unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&comment).unwrap();
+        assert!(report["report"].is_string());
+        assert!(report["signature"].is_string());
+        assert!(report["signing_cert"].is_string());
+    }
+
+    #[test]
+    fn netscape_comment_missing() {
+        assert_eq!(get_netscape_comment(&ias_root_ca_der()), None);
+        assert_eq!(get_netscape_comment(&[]), None);
+    }
+
+    #[test]
+    fn ias_root_ca_is_a_trust_anchor() {
+        let der = ias_root_ca_der();
+        assert!(webpki::trust_anchor_util::cert_der_as_trust_anchor(&der).is_ok());
+    }
+}
@@ -0,0 +1,22 @@
+//! Offline verification of node registration certificates.
+//!
+//! A node registers by submitting a self-signed certificate that carries the IAS attestation
+//! report of its enclave. `ecall_authenticate_new_node` only shares the consensus seed with nodes
+//! whose certificate passes its checks. This crate runs the same checks outside of the enclave,
+//! so operators can find out why a certificate would be rejected before they submit it.
+
+mod cert;
+mod policy;
+mod report;
+
+pub use attestation_types::{
+    AdvisoryIDs, ParseError, SgxEnclaveReport, SgxQuote, SgxQuoteStatus, SgxQuoteVersion,
+};
+pub use enclave_ffi_types::NodeAuthResult;
+
+pub use cert::get_netscape_comment;
+pub use policy::{
+    AcceptedAdvisory, AcceptedStatus, AttestationPolicy, Policy, SigningMethod, Verdict,
+    ATTESTATION_POLICY_VERSION, MRSIGNER,
+};
+pub use report::{AttestationReport, EndorsedAttestationReport, Error};
@@ -0,0 +1,167 @@
+use std::fs;
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{App, Arg, ArgGroup};
+
+use attestation_verifier::{
+    AttestationPolicy, AttestationReport, Error, NodeAuthResult, Policy, SigningMethod, Verdict,
+    MRSIGNER,
+};
+
+fn parse_measurement(name: &str, value: &str) -> [u8; 32] {
+    let mut measurement = [0u8; 32];
+    match hex::decode(value.trim_start_matches("0x")) {
+        Ok(bytes) if bytes.len() == 32 => measurement.copy_from_slice(&bytes),
+        _ => {
+            eprintln!("{} must be 32 bytes, hex encoded", name);
+            process::exit(2);
+        }
+    }
+    measurement
+}
+
+fn main() {
+    let matches = App::new("attestation-verifier")
+        .about("Checks whether the enclave would accept a node registration certificate")
+        .arg(
+            Arg::with_name("CERT")
+                .help("Registration certificate in DER form, e.g. attestation_cert.der")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("mr-enclave")
+                .long("mr-enclave")
+                .value_name("HEX")
+                .help("Require the enclave to have this MRENCLAVE, like the network does"),
+        )
+        .arg(
+            Arg::with_name("mr-signer")
+                .long("mr-signer")
+                .value_name("HEX")
+                .min_values(0)
+                .help("Require the enclave to be signed by this key (defaults to the Secret Network signer)"),
+        )
+        .group(ArgGroup::with_name("identity").args(&["mr-enclave", "mr-signer"]))
+        .arg(
+            Arg::with_name("min-isv-svn")
+                .long("min-isv-svn")
+                .value_name("SVN")
+                .help("Reject enclaves with a lower ISVSVN, like an enclave of this version does (defaults to 0)"),
+        )
+        .arg(
+            Arg::with_name("development")
+                .long("development")
+                .help("Apply the rules of enclaves not built for production, which accept outdated platforms"),
+        )
+        .arg(
+            Arg::with_name("policy")
+                .long("policy")
+                .value_name("FILE")
+                .help("Apply the attestation policy of the chain, as found in the registration genesis"),
+        )
+        .arg(
+            Arg::with_name("block-time")
+                .long("block-time")
+                .value_name("SECONDS")
+                .requires("policy")
+                .help("Evaluate the grace periods of the policy at this block time (defaults to now)"),
+        )
+        .get_matches();
+
+    let signing_method = if let Some(mr_enclave) = matches.value_of("mr-enclave") {
+        SigningMethod::MrEnclave(parse_measurement("MRENCLAVE", mr_enclave))
+    } else if matches.is_present("mr-signer") {
+        match matches.value_of("mr-signer") {
+            Some(mr_signer) => SigningMethod::MrSigner(parse_measurement("MRSIGNER", mr_signer)),
+            None => SigningMethod::MrSigner(MRSIGNER),
+        }
+    } else {
+        SigningMethod::None
+    };
+
+    let min_isv_svn = match matches.value_of("min-isv-svn") {
+        Some(min_isv_svn) => min_isv_svn.parse().unwrap_or_else(|_| {
+            eprintln!("ISVSVN must be a number between 0 and 65535");
+            process::exit(2);
+        }),
+        None => 0,
+    };
+
+    let attestation_policy = matches.value_of("policy").map(|path| {
+        let policy = fs::read(path).unwrap_or_else(|e| {
+            eprintln!("Failed to read {}: {}", path, e);
+            process::exit(2);
+        });
+        AttestationPolicy::from_slice(&policy).unwrap_or_else(|e| {
+            eprintln!("Invalid attestation policy {}: {}", path, e);
+            process::exit(2);
+        })
+    });
+
+    let block_time = match matches.value_of("block-time") {
+        Some(block_time) => block_time.parse().unwrap_or_else(|_| {
+            eprintln!("block time must be a number of seconds since the unix epoch");
+            process::exit(2);
+        }),
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|now| now.as_secs())
+            .unwrap_or_default(),
+    };
+
+    let policy = Policy {
+        production: !matches.is_present("development"),
+        signing_method,
+        min_isv_svn,
+        attestation_policy,
+        block_time,
+    };
+
+    let path = matches.value_of("CERT").unwrap();
+    let cert = fs::read(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read {}: {}", path, e);
+        process::exit(2);
+    });
+
+    let verdict = match AttestationReport::from_cert(&cert) {
+        Ok(report) => {
+            let enclave_report = &report.sgx_quote_body.isv_enclave_report;
+            println!("Report signed by IAS at {}", report.timestamp);
+            println!("Quote status:  {:?}", report.sgx_quote_status);
+            println!("Advisories:    {}", report.advisory_ids.0.join(", "));
+            println!("MRENCLAVE:     {}", hex::encode(enclave_report.mr_enclave));
+            println!("MRSIGNER:      {}", hex::encode(enclave_report.mr_signer));
+            println!("ISV product:   {}", enclave_report.isv_prod_id);
+            println!("ISV SVN:       {}", enclave_report.isv_svn);
+            println!("Debug enclave: {}", enclave_report.is_debug());
+            println!("Public key:    {}", hex::encode(report.public_key()));
+            println!();
+            policy.verify_report(&report)
+        }
+        Err(Error::DcapQuote) => {
+            eprintln!(
+                "{} carries a DCAP quote. Only certificates with an IAS report can be checked",
+                path
+            );
+            process::exit(3);
+        }
+        Err(e) => Verdict {
+            result: NodeAuthResult::InvalidCert,
+            notes: vec![e.to_string()],
+        },
+    };
+
+    if verdict.is_accepted() {
+        println!("ACCEPTED");
+    } else {
+        println!("REJECTED ({:?}): {}", verdict.result, verdict.result);
+    }
+    for note in &verdict.notes {
+        println!("  - {}", note);
+    }
+
+    if !verdict.is_accepted() {
+        process::exit(1);
+    }
+}
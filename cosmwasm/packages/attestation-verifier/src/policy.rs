@@ -0,0 +1,622 @@
+//! The checks that `ecall_authenticate_new_node` runs on the report of a registering node.
+//! Keep in sync with `verify_ra_cert`, `verify_quote_status` and `verify_enclave_identity` in
+//! `registration/cert.rs`, and with `registration/policy.rs`.
+use serde::Deserialize;
+
+use enclave_ffi_types::NodeAuthResult;
+
+use attestation_types::{AdvisoryIDs, SgxQuoteStatus};
+
+use crate::report::{AttestationReport, Error};
+
+/// The MRSIGNER that the enclave requires of other enclaves
+pub const MRSIGNER: [u8; 32] = [
+    131, 215, 25, 231, 125, 234, 202, 20, 112, 246, 186, 246, 42, 77, 119, 67, 3, 200, 153, 219,
+    105, 2, 15, 156, 112, 238, 29, 252, 8, 199, 206, 158,
+];
+
+/// How the enclave identifies the enclaves it shares the seed with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SigningMethod {
+    /// The registering enclave must have exactly this MRENCLAVE, and be signed by `MRSIGNER`. This
+    /// is what the network runs with, and the value is that of the enclave of the node doing the
+    /// authentication.
+    MrEnclave([u8; 32]),
+    /// The registering enclave must be signed by this key
+    MrSigner([u8; 32]),
+    /// The identity of the registering enclave is not checked
+    None,
+}
+
+/// The only version of the attestation policy format that the enclave understands
+pub const ATTESTATION_POLICY_VERSION: u32 = 1;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AcceptedStatus {
+    pub status: String,
+    #[serde(default)]
+    pub until: Option<u64>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AcceptedAdvisory {
+    pub id: String,
+    #[serde(default)]
+    pub until: Option<u64>,
+}
+
+/// The attestation policy of the chain, which restricts the compiled-in rules of the enclave.
+/// See the `attestation_policy` of the registration module genesis.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct AttestationPolicy {
+    pub version: u32,
+    #[serde(default)]
+    pub accepted_statuses: Vec<AcceptedStatus>,
+    #[serde(default)]
+    pub accepted_advisories: Vec<AcceptedAdvisory>,
+    #[serde(default)]
+    pub allowed_mr_enclaves: Vec<String>,
+    #[serde(default)]
+    pub min_isv_svn: u16,
+}
+
+impl AttestationPolicy {
+    /// Parses a policy, and rejects it where the enclave would
+    pub fn from_slice(policy: &[u8]) -> Result<Self, String> {
+        let policy: Self = serde_json::from_slice(policy).map_err(|e| e.to_string())?;
+        if policy.version != ATTESTATION_POLICY_VERSION {
+            return Err(format!("unsupported policy version {}", policy.version));
+        }
+        for status in &policy.accepted_statuses {
+            if SgxQuoteStatus::from(status.status.as_str()) == SgxQuoteStatus::UnknownBadStatus {
+                return Err(format!("unknown quote status {}", status.status));
+            }
+        }
+        for mr_enclave in &policy.allowed_mr_enclaves {
+            match hex::decode(mr_enclave) {
+                Ok(decoded) if decoded.len() == 32 => {}
+                _ => return Err(format!("invalid MRENCLAVE {}", mr_enclave)),
+            }
+        }
+        Ok(policy)
+    }
+}
+
+fn is_in_effect(until: Option<u64>, block_time: u64) -> bool {
+    match until {
+        Some(until) => block_time < until,
+        None => true,
+    }
+}
+
+/// The configuration of the enclave that authenticates the node
+#[derive(Debug, Clone)]
+pub struct Policy {
+    /// Whether the enclave was built with the `production` feature, which rejects platforms with
+    /// an outdated TCB and debug enclaves
+    pub production: bool,
+    pub signing_method: SigningMethod,
+    /// The ISVSVN of the enclave doing the authentication. Enclaves with a lower one are rejected
+    pub min_isv_svn: u16,
+    /// The attestation policy of the chain, if it has one
+    pub attestation_policy: Option<AttestationPolicy>,
+    /// The block time at which the grace periods of `attestation_policy` are evaluated
+    pub block_time: u64,
+}
+
+/// Whether the enclave would accept a report, and why
+#[derive(Debug)]
+pub struct Verdict {
+    /// What `ecall_authenticate_new_node` would return for the certificate
+    pub result: NodeAuthResult,
+    /// Details that explain the result, or that the operator should know about even when the
+    /// certificate is accepted
+    pub notes: Vec<String>,
+}
+
+impl Verdict {
+    pub fn is_accepted(&self) -> bool {
+        self.result == NodeAuthResult::Success
+    }
+}
+
+impl Policy {
+    /// Runs all of the checks on a registration certificate.
+    ///
+    /// Certificates that carry a DCAP quote fail with `Error::DcapQuote`: their collateral and
+    /// PCK certificates aren't verified here, so we can't tell what the enclave would decide.
+    pub fn verify_cert(&self, cert: &[u8]) -> Result<Verdict, Error> {
+        match AttestationReport::from_cert(cert) {
+            Ok(report) => Ok(self.verify_report(&report)),
+            Err(Error::DcapQuote) => Err(Error::DcapQuote),
+            Err(e) => Ok(Verdict {
+                result: NodeAuthResult::InvalidCert,
+                notes: vec![e.to_string()],
+            }),
+        }
+    }
+
+    /// Runs the checks on a report that was already verified to be signed by IAS
+    pub fn verify_report(&self, report: &AttestationReport) -> Verdict {
+        let mut notes = vec![];
+        let result = match self
+            .verify_quote_status(report, &mut notes)
+            .and_then(|_| self.verify_enclave_identity(report, &mut notes))
+        {
+            Ok(()) => NodeAuthResult::Success,
+            Err(result) => result,
+        };
+
+        Verdict { result, notes }
+    }
+
+    fn verify_quote_status(
+        &self,
+        report: &AttestationReport,
+        notes: &mut Vec<String>,
+    ) -> Result<(), NodeAuthResult> {
+        self.verify_compiled_in_quote_status(report, notes)?;
+
+        // The attestation policy only adds restrictions
+        match &self.attestation_policy {
+            Some(policy) => self.verify_quote_status_with_policy(policy, report, notes),
+            None => Ok(()),
+        }
+    }
+
+    fn verify_compiled_in_quote_status(
+        &self,
+        report: &AttestationReport,
+        notes: &mut Vec<String>,
+    ) -> Result<(), NodeAuthResult> {
+        let status = report.sgx_quote_status;
+        let vulnerable = report.advisory_ids.vulnerable();
+        let describe_vulnerable = |notes: &mut Vec<String>| {
+            for (id, desc) in &vulnerable {
+                match desc {
+                    Some(desc) => notes.push(format!("{} must be mitigated: {}", id, desc)),
+                    None => notes.push(format!("{} must be mitigated", id)),
+                }
+            }
+        };
+
+        match status {
+            SgxQuoteStatus::OK | SgxQuoteStatus::SwHardeningNeeded => Ok(()),
+            SgxQuoteStatus::GroupOutOfDate if !self.production => {
+                notes.push(
+                    "TCB level of SGX platform service is outdated. You should check for firmware updates"
+                        .to_string(),
+                );
+                describe_vulnerable(notes);
+                Ok(())
+            }
+            SgxQuoteStatus::ConfigurationAndSwHardeningNeeded => {
+                if vulnerable.is_empty() {
+                    Ok(())
+                } else {
+                    notes.push(
+                        "Platform is updated but requires further BIOS configuration".to_string(),
+                    );
+                    describe_vulnerable(notes);
+                    Err(NodeAuthResult::from(&status))
+                }
+            }
+            _ => {
+                notes.push(format!("Quote status {:?} is not allowed", status));
+                describe_vulnerable(notes);
+                Err(NodeAuthResult::from(&status))
+            }
+        }
+    }
+
+    fn verify_quote_status_with_policy(
+        &self,
+        policy: &AttestationPolicy,
+        report: &AttestationReport,
+        notes: &mut Vec<String>,
+    ) -> Result<(), NodeAuthResult> {
+        let status = report.sgx_quote_status;
+        if status != SgxQuoteStatus::OK {
+            let accepted = policy
+                .accepted_statuses
+                .iter()
+                .find(|accepted| SgxQuoteStatus::from(accepted.status.as_str()) == status);
+            match accepted {
+                Some(accepted) if is_in_effect(accepted.until, self.block_time) => {}
+                Some(accepted) => {
+                    notes.push(format!(
+                        "Quote status {:?} was accepted by the attestation policy until {}",
+                        status,
+                        accepted.until.unwrap_or_default()
+                    ));
+                    return Err(NodeAuthResult::from(&status));
+                }
+                None => {
+                    notes.push(format!(
+                        "Quote status {:?} is not accepted by the attestation policy",
+                        status
+                    ));
+                    return Err(NodeAuthResult::from(&status));
+                }
+            }
+        }
+
+        let AdvisoryIDs(advisories) = &report.advisory_ids;
+        let not_accepted: Vec<&String> = advisories
+            .iter()
+            .filter(|id| {
+                !policy.accepted_advisories.iter().any(|accepted| {
+                    accepted.id == **id && is_in_effect(accepted.until, self.block_time)
+                })
+            })
+            .collect();
+        if !not_accepted.is_empty() {
+            for id in not_accepted {
+                notes.push(format!("{} is not accepted by the attestation policy", id));
+            }
+            return Err(NodeAuthResult::AdvisoryNotAccepted);
+        }
+
+        Ok(())
+    }
+
+    fn verify_enclave_identity(
+        &self,
+        report: &AttestationReport,
+        notes: &mut Vec<String>,
+    ) -> Result<(), NodeAuthResult> {
+        let enclave_report = &report.sgx_quote_body.isv_enclave_report;
+
+        if self.production && enclave_report.is_debug() {
+            notes.push("The enclave runs in debug mode".to_string());
+            return Err(NodeAuthResult::DebugEnclave);
+        }
+
+        let expected_mr_signer = match self.signing_method {
+            SigningMethod::MrEnclave(expected) => {
+                if enclave_report.mr_enclave != expected {
+                    notes.push(format!(
+                        "MRENCLAVE {} doesn't match the expected {}",
+                        hex::encode(enclave_report.mr_enclave),
+                        hex::encode(expected)
+                    ));
+                    return Err(NodeAuthResult::MrEnclaveMismatch);
+                }
+                Some(MRSIGNER)
+            }
+            SigningMethod::MrSigner(expected) => Some(expected),
+            SigningMethod::None => {
+                notes.push("The identity of the enclave was not checked".to_string());
+                None
+            }
+        };
+        if let Some(expected) = expected_mr_signer {
+            if enclave_report.mr_signer != expected {
+                notes.push(format!(
+                    "MRSIGNER {} doesn't match the expected {}",
+                    hex::encode(enclave_report.mr_signer),
+                    hex::encode(expected)
+                ));
+                return Err(NodeAuthResult::MrSignerMismatch);
+            }
+        }
+
+        if enclave_report.isv_svn < self.min_isv_svn {
+            notes.push(format!(
+                "ISVSVN {} is lower than that of the authenticating enclave, {}",
+                enclave_report.isv_svn, self.min_isv_svn
+            ));
+            return Err(NodeAuthResult::IsvSvnTooLow);
+        }
+
+        // The attestation policy only adds restrictions
+        if let Some(policy) = &self.attestation_policy {
+            if enclave_report.isv_svn < policy.min_isv_svn {
+                notes.push(format!(
+                    "ISVSVN {} is lower than the {} required by the attestation policy",
+                    enclave_report.isv_svn, policy.min_isv_svn
+                ));
+                return Err(NodeAuthResult::IsvSvnTooLow);
+            }
+
+            let mr_enclave = hex::encode(enclave_report.mr_enclave);
+            if !policy.allowed_mr_enclaves.is_empty()
+                && !policy
+                    .allowed_mr_enclaves
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(&mr_enclave))
+            {
+                notes.push(format!(
+                    "MRENCLAVE {} is not allowed by the attestation policy",
+                    mr_enclave
+                ));
+                return Err(NodeAuthResult::MrEnclaveMismatch);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const HW_CERT: &[u8] =
+        include_bytes!("../../wasmi-runtime/src/registration/fixtures/attestation_cert_hw_v2");
+    const OUT_OF_DATE_CERT: &[u8] = include_bytes!(
+        "../../wasmi-runtime/src/registration/fixtures/attestation_cert_out_of_date.der"
+    );
+    const CONFIG_NEEDED_CERT: &[u8] = include_bytes!(
+        "../../wasmi-runtime/src/registration/fixtures/attestation_cert_sw_config_needed.der"
+    );
+    const SW_CERT: &[u8] =
+        include_bytes!("../../wasmi-runtime/src/registration/fixtures/attestation_cert_sw");
+
+    const PRODUCTION: Policy = Policy {
+        production: true,
+        signing_method: SigningMethod::MrSigner(MRSIGNER),
+        min_isv_svn: 0,
+        attestation_policy: None,
+        block_time: 0,
+    };
+
+    const DEVELOPMENT: Policy = Policy {
+        production: false,
+        signing_method: SigningMethod::MrSigner(MRSIGNER),
+        min_isv_svn: 0,
+        attestation_policy: None,
+        block_time: 0,
+    };
+
+    // The enclave of HW_CERT runs in debug mode, so it's only accepted by development builds
+    fn with_attestation_policy(policy: &str, block_time: u64) -> Policy {
+        Policy {
+            attestation_policy: Some(AttestationPolicy::from_slice(policy.as_bytes()).unwrap()),
+            block_time,
+            ..DEVELOPMENT
+        }
+    }
+
+    #[test]
+    fn valid_cert_is_accepted() {
+        let verdict = DEVELOPMENT.verify_cert(HW_CERT).unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::Success);
+        assert!(verdict.is_accepted());
+    }
+
+    #[test]
+    fn debug_enclave_is_rejected() {
+        let verdict = PRODUCTION.verify_cert(HW_CERT).unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::DebugEnclave);
+    }
+
+    #[test]
+    fn isv_svn_too_low() {
+        let report = AttestationReport::from_cert(HW_CERT).unwrap();
+        let isv_svn = report.sgx_quote_body.isv_enclave_report.isv_svn;
+
+        let policy = Policy {
+            min_isv_svn: isv_svn,
+            ..DEVELOPMENT
+        };
+        assert_eq!(
+            policy.verify_report(&report).result,
+            NodeAuthResult::Success
+        );
+
+        let policy = Policy {
+            min_isv_svn: isv_svn + 1,
+            ..DEVELOPMENT
+        };
+        assert_eq!(
+            policy.verify_report(&report).result,
+            NodeAuthResult::IsvSvnTooLow
+        );
+    }
+
+    #[test]
+    fn mr_enclave_mismatch() {
+        let report = AttestationReport::from_cert(HW_CERT).unwrap();
+        let mr_enclave = report.sgx_quote_body.isv_enclave_report.mr_enclave;
+
+        let policy = Policy {
+            signing_method: SigningMethod::MrEnclave(mr_enclave),
+            ..DEVELOPMENT
+        };
+        assert_eq!(
+            policy.verify_report(&report).result,
+            NodeAuthResult::Success
+        );
+
+        let policy = Policy {
+            signing_method: SigningMethod::MrEnclave([0; 32]),
+            ..DEVELOPMENT
+        };
+        let verdict = policy.verify_report(&report);
+        assert_eq!(verdict.result, NodeAuthResult::MrEnclaveMismatch);
+        assert!(verdict.notes[0].contains(&hex::encode(mr_enclave)));
+    }
+
+    #[test]
+    fn mr_signer_mismatch() {
+        let policy = Policy {
+            signing_method: SigningMethod::MrSigner([0; 32]),
+            ..DEVELOPMENT
+        };
+        assert_eq!(
+            policy.verify_cert(HW_CERT).unwrap().result,
+            NodeAuthResult::MrSignerMismatch
+        );
+    }
+
+    #[test]
+    fn group_out_of_date() {
+        let verdict = PRODUCTION.verify_cert(OUT_OF_DATE_CERT).unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::GroupOutOfDate);
+
+        let verdict = DEVELOPMENT.verify_cert(OUT_OF_DATE_CERT).unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::Success);
+        assert!(verdict.notes[0].contains("outdated"));
+    }
+
+    #[test]
+    fn configuration_needed() {
+        for policy in &[PRODUCTION, DEVELOPMENT] {
+            let verdict = policy.verify_cert(CONFIG_NEEDED_CERT).unwrap();
+            assert_eq!(
+                verdict.result,
+                NodeAuthResult::SwHardeningAndConfigurationNeeded
+            );
+            assert!(verdict.notes.len() > 1);
+        }
+    }
+
+    #[test]
+    fn software_mode_cert_is_invalid() {
+        let verdict = DEVELOPMENT.verify_cert(SW_CERT).unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::InvalidCert);
+        assert!(verdict.notes[0].contains("software mode"));
+    }
+
+    #[test]
+    fn dcap_cert_is_refused() {
+        // the netscape comment extension, holding an endorsed DCAP quote
+        let comment = br#"{"quote":"","collateral":{}}"#;
+        let mut cert = vec![
+            0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x86, 0xF8, 0x42, 0x01, 0x0D,
+        ];
+        cert.push(0x04);
+        cert.push(comment.len() as u8);
+        cert.extend_from_slice(comment);
+        cert.push(0);
+
+        match DEVELOPMENT.verify_cert(&cert) {
+            Err(Error::DcapQuote) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn attestation_policy_grace_period() {
+        let policy = r#"{
+            "version": 1,
+            "accepted_statuses": [{ "status": "GROUP_OUT_OF_DATE", "until": 1600000000 }],
+            "accepted_advisories": [
+                { "id": "INTEL-SA-00161" },
+                { "id": "INTEL-SA-00219" },
+                { "id": "INTEL-SA-00289" },
+                { "id": "INTEL-SA-00320" },
+                { "id": "INTEL-SA-00329" }
+            ],
+            "allowed_mr_enclaves": [
+                "a055e97989897313289616ac116fc5473e14e22fd8a0a159fd0ce165353ecacd"
+            ]
+        }"#;
+
+        let verdict = with_attestation_policy(policy, 1_599_999_999)
+            .verify_cert(OUT_OF_DATE_CERT)
+            .unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::Success);
+
+        let verdict = with_attestation_policy(policy, 1_600_000_000)
+            .verify_cert(OUT_OF_DATE_CERT)
+            .unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::GroupOutOfDate);
+        assert!(verdict
+            .notes
+            .iter()
+            .any(|note| note.contains("until 1600000000")));
+
+        // the policy can't accept what the compiled-in rules reject
+        let verdict = Policy {
+            production: true,
+            ..with_attestation_policy(policy, 1_599_999_999)
+        }
+        .verify_cert(OUT_OF_DATE_CERT)
+        .unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::GroupOutOfDate);
+    }
+
+    #[test]
+    fn attestation_policy_restricts_defaults() {
+        // SW_HARDENING_NEEDED is accepted by default, but not by this policy
+        let policy = with_attestation_policy(r#"{ "version": 1 }"#, 0);
+        assert_eq!(
+            policy.verify_cert(HW_CERT).unwrap().result,
+            NodeAuthResult::BadQuoteStatus
+        );
+
+        let policy = r#"{
+            "version": 1,
+            "accepted_statuses": [{ "status": "SW_HARDENING_NEEDED" }]
+        }"#;
+        let verdict = with_attestation_policy(policy, 0)
+            .verify_cert(HW_CERT)
+            .unwrap();
+        assert_eq!(verdict.result, NodeAuthResult::AdvisoryNotAccepted);
+        assert!(verdict.notes[0].contains("INTEL-SA-00334"));
+
+        let policy = r#"{
+            "version": 1,
+            "accepted_statuses": [{ "status": "SW_HARDENING_NEEDED" }],
+            "accepted_advisories": [{ "id": "INTEL-SA-00334" }],
+            "allowed_mr_enclaves": [
+                "a055e97989897313289616ac116fc5473e14e22fd8a0a159fd0ce165353ecacd"
+            ]
+        }"#;
+        assert_eq!(
+            with_attestation_policy(policy, 0)
+                .verify_cert(HW_CERT)
+                .unwrap()
+                .result,
+            NodeAuthResult::MrEnclaveMismatch
+        );
+
+        let policy = r#"{
+            "version": 1,
+            "accepted_statuses": [{ "status": "SW_HARDENING_NEEDED" }],
+            "accepted_advisories": [{ "id": "INTEL-SA-00334" }],
+            "min_isv_svn": 100
+        }"#;
+        assert_eq!(
+            with_attestation_policy(policy, 0)
+                .verify_cert(HW_CERT)
+                .unwrap()
+                .result,
+            NodeAuthResult::IsvSvnTooLow
+        );
+
+        let policy = r#"{
+            "version": 1,
+            "accepted_statuses": [{ "status": "CONFIGURATION_AND_SW_HARDENING_NEEDED" }],
+            "accepted_advisories": [
+                { "id": "INTEL-SA-00161" },
+                { "id": "INTEL-SA-00219" },
+                { "id": "INTEL-SA-00289" },
+                { "id": "INTEL-SA-00334" }
+            ]
+        }"#;
+        assert_eq!(
+            with_attestation_policy(policy, 0)
+                .verify_cert(CONFIG_NEEDED_CERT)
+                .unwrap()
+                .result,
+            NodeAuthResult::SwHardeningAndConfigurationNeeded
+        );
+    }
+
+    #[test]
+    fn attestation_policy_from_slice() {
+        assert!(AttestationPolicy::from_slice(br#"{"version":1}"#).is_ok());
+        assert!(AttestationPolicy::from_slice(br#"{"version":2}"#).is_err());
+        assert!(AttestationPolicy::from_slice(br#"{"version":1,"grace":1}"#).is_err());
+        assert!(AttestationPolicy::from_slice(
+            br#"{"version":1,"accepted_statuses":[{"status":"MOSTLY_OK"}]}"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,334 @@
+//! Types that contain information about attestation report.
+//! The implementation is based on Attestation Service API version 4.
+//! https://api.trustedservices.intel.com/documents/sgx-attestation-api-spec.pdf
+//!
+//! This mirrors `AttestationReport::from_cert` in `registration/report.rs` of the enclave. The
+//! quote and its status are parsed by `attestation-types`, which the enclave uses as well.
+use std::fmt;
+
+use attestation_types::{AdvisoryIDs, ParseError, SgxQuote, SgxQuoteStatus};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::cert::{get_netscape_comment, ias_root_ca_der};
+
+/// Reasons for which a certificate doesn't carry a valid attestation report
+#[derive(Debug)]
+pub enum Error {
+    /// There is no netscape comment in the certificate
+    MissingReport,
+    /// The netscape comment only holds a public key, which is what enclaves built in software
+    /// mode produce
+    SoftwareModeCertificate,
+    /// The netscape comment holds a DCAP quote rather than a report signed by IAS
+    DcapQuote,
+    /// The report isn't signed by a certificate that chains up to the IAS root
+    UntrustedSigningCert(webpki::Error),
+    /// The signature over the report doesn't match the signing certificate
+    BadSignature(webpki::Error),
+    /// The report was produced by a version of the IAS API that the enclave doesn't accept
+    UnsupportedVersion(u64),
+    /// The report or the quote in it is malformed
+    ReportParseError(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingReport => write!(f, "the certificate has no attestation report"),
+            Error::SoftwareModeCertificate => write!(
+                f,
+                "the certificate was created by an enclave running in software mode"
+            ),
+            Error::DcapQuote => write!(
+                f,
+                "the certificate carries a DCAP quote, which this tool can't verify"
+            ),
+            Error::UntrustedSigningCert(e) => write!(
+                f,
+                "the report signing certificate isn't issued by Intel: {:?}",
+                e
+            ),
+            Error::BadSignature(e) => write!(f, "the report signature is invalid: {:?}", e),
+            Error::UnsupportedVersion(v) => write!(
+                f,
+                "the report was produced by version {} of the IAS API, but only version 4 is accepted",
+                v
+            ),
+            Error::ReportParseError(what) => write!(f, "failed to parse the report: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::ReportParseError(e.0)
+    }
+}
+
+/// An attestation report and the signature of the Intel Attestation Service over it, as stored in
+/// the netscape comment of a registration certificate.
+#[derive(Deserialize)]
+pub struct EndorsedAttestationReport {
+    /// Attestation report generated by the hardware
+    #[serde(deserialize_with = "from_base64")]
+    pub report: Vec<u8>,
+    /// Singature of the report
+    #[serde(deserialize_with = "from_base64")]
+    pub signature: Vec<u8>,
+    /// Certificate matching the signing key of the signature
+    #[serde(deserialize_with = "from_base64")]
+    pub signing_cert: Vec<u8>,
+}
+
+fn from_base64<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let encoded = String::deserialize(deserializer)?;
+    base64::decode(&encoded).map_err(serde::de::Error::custom)
+}
+
+type SignatureAlgorithms = &'static [&'static webpki::SignatureAlgorithm];
+static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[
+    &webpki::ECDSA_P256_SHA256,
+    &webpki::ECDSA_P256_SHA384,
+    &webpki::ECDSA_P384_SHA256,
+    &webpki::ECDSA_P384_SHA384,
+    &webpki::RSA_PSS_2048_8192_SHA256_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA384_LEGACY_KEY,
+    &webpki::RSA_PSS_2048_8192_SHA512_LEGACY_KEY,
+    &webpki::RSA_PKCS1_2048_8192_SHA256,
+    &webpki::RSA_PKCS1_2048_8192_SHA384,
+    &webpki::RSA_PKCS1_2048_8192_SHA512,
+    &webpki::RSA_PKCS1_3072_8192_SHA384,
+];
+
+/// The time at which the enclave validates the IAS signing certificate (04.11.23, dd.mm.yy).
+/// The enclave doesn't use the system time here, so neither do we.
+const VERIFICATION_TIME: u64 = 1_699_088_856;
+
+/// An IAS attestation report whose signature chains up to the Intel root
+#[derive(Debug)]
+pub struct AttestationReport {
+    /// When IAS produced the report
+    pub timestamp: String,
+    /// Quote status
+    pub sgx_quote_status: SgxQuoteStatus,
+    /// Content of the quote
+    pub sgx_quote_body: SgxQuote,
+    pub platform_info_blob: Option<Vec<u8>>,
+    pub advisory_ids: AdvisoryIDs,
+}
+
+impl AttestationReport {
+    /// Extracts the attestation report from a registration certificate, and verifies that it's
+    /// signed by IAS. The report is checked exactly as the enclave checks it in
+    /// `AttestationReport::from_cert`.
+    pub fn from_cert(cert: &[u8]) -> Result<Self, Error> {
+        let payload = get_netscape_comment(cert).ok_or(Error::MissingReport)?;
+
+        let report: EndorsedAttestationReport = match serde_json::from_slice(&payload) {
+            Ok(report) => report,
+            Err(_) => return Err(classify_payload(&payload)),
+        };
+
+        Self::from_endorsed_report(&report)
+    }
+
+    /// Verifies the signature of IAS over a report and parses it
+    pub fn from_endorsed_report(report: &EndorsedAttestationReport) -> Result<Self, Error> {
+        // Verify report's signature - aka intel's signing cert
+        let signing_cert = webpki::EndEntityCert::from(&report.signing_cert)
+            .map_err(|_| Error::ReportParseError("malformed signing certificate"))?;
+
+        let ias_cert = ias_root_ca_der();
+        let trust_anchors = [
+            webpki::trust_anchor_util::cert_der_as_trust_anchor(&ias_cert)
+                .expect("IAS root CA is not a valid trust anchor"),
+        ];
+        let chain: Vec<&[u8]> = vec![&ias_cert];
+
+        signing_cert
+            .verify_is_valid_tls_server_cert(
+                SUPPORTED_SIG_ALGS,
+                &webpki::TLSServerTrustAnchors(&trust_anchors),
+                &chain,
+                webpki::Time::from_seconds_since_unix_epoch(VERIFICATION_TIME),
+            )
+            .map_err(Error::UntrustedSigningCert)?;
+
+        // Verify the signature against the signing cert
+        signing_cert
+            .verify_signature(
+                &webpki::RSA_PKCS1_2048_8192_SHA256,
+                &report.report,
+                &report.signature,
+            )
+            .map_err(Error::BadSignature)?;
+
+        // Verify and extract information from attestation report
+        let attn_report: Value = serde_json::from_slice(&report.report)
+            .map_err(|_| Error::ReportParseError("report is not valid JSON"))?;
+
+        // Verify API version is supported
+        let version = attn_report["version"]
+            .as_u64()
+            .ok_or(Error::ReportParseError("missing version"))?;
+        if version != 4 {
+            return Err(Error::UnsupportedVersion(version));
+        };
+
+        let timestamp = attn_report["timestamp"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+
+        let platform_info_blob = match attn_report["platformInfoBlob"].as_str() {
+            Some(blob) => Some(
+                hex::decode(blob)
+                    .map_err(|_| Error::ReportParseError("malformed platform info blob"))?,
+            ),
+            None => None,
+        };
+
+        // Get quote status
+        let sgx_quote_status = attn_report["isvEnclaveQuoteStatus"]
+            .as_str()
+            .map(SgxQuoteStatus::from)
+            .ok_or(Error::ReportParseError("missing quote status"))?;
+
+        // Get quote body
+        let sgx_quote_body = {
+            let quote_encoded = attn_report["isvEnclaveQuoteBody"]
+                .as_str()
+                .ok_or(Error::ReportParseError("missing quote body"))?;
+            let quote_raw = base64::decode(quote_encoded.as_bytes())
+                .map_err(|_| Error::ReportParseError("quote body is not valid base64"))?;
+            SgxQuote::parse_from(quote_raw.as_slice())?
+        };
+
+        // Get advisories
+        let advisories: Vec<String> = serde_json::from_value(attn_report["advisoryIDs"].clone())
+            .map_err(|_| Error::ReportParseError("missing advisory IDs"))?;
+
+        Ok(Self {
+            timestamp,
+            sgx_quote_status,
+            sgx_quote_body,
+            platform_info_blob,
+            advisory_ids: AdvisoryIDs(advisories),
+        })
+    }
+
+    /// The public key of the node, which the enclave encrypts the seed to
+    pub fn public_key(&self) -> &[u8] {
+        &self.sgx_quote_body.isv_enclave_report.report_data[0..32]
+    }
+}
+
+/// Explains what a netscape comment that isn't an IAS report holds instead
+fn classify_payload(payload: &[u8]) -> Error {
+    if base64::decode(payload).is_ok() {
+        return Error::SoftwareModeCertificate;
+    }
+
+    if let Ok(json) = serde_json::from_slice::<Value>(payload) {
+        if json.get("quote").is_some() && json.get("collateral").is_some() {
+            return Error::DcapQuote;
+        }
+    }
+
+    Error::ReportParseError("unrecognized netscape comment")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use attestation_types::SgxQuoteVersion;
+
+    const HW_CERT: &[u8] =
+        include_bytes!("../../wasmi-runtime/src/registration/fixtures/attestation_cert_hw_v2");
+    const OUT_OF_DATE_CERT: &[u8] = include_bytes!(
+        "../../wasmi-runtime/src/registration/fixtures/attestation_cert_out_of_date.der"
+    );
+    const CONFIG_NEEDED_CERT: &[u8] = include_bytes!(
+        "../../wasmi-runtime/src/registration/fixtures/attestation_cert_sw_config_needed.der"
+    );
+    const SW_CERT: &[u8] =
+        include_bytes!("../../wasmi-runtime/src/registration/fixtures/attestation_cert_sw");
+    const V3_CERT: &[u8] =
+        include_bytes!("../../wasmi-runtime/src/registration/fixtures/tls_ra_cert_v3.der");
+
+    #[test]
+    fn from_cert_valid() {
+        let report = AttestationReport::from_cert(HW_CERT).unwrap();
+        assert!(matches!(
+            report.sgx_quote_body.version,
+            SgxQuoteVersion::V2(_)
+        ));
+        assert_eq!(
+            report.sgx_quote_body.isv_enclave_report.mr_signer,
+            crate::MRSIGNER
+        );
+        assert_eq!(report.public_key().len(), 32);
+    }
+
+    #[test]
+    fn from_cert_quote_status() {
+        let report = AttestationReport::from_cert(OUT_OF_DATE_CERT).unwrap();
+        assert_eq!(report.sgx_quote_status, SgxQuoteStatus::GroupOutOfDate);
+
+        let report = AttestationReport::from_cert(CONFIG_NEEDED_CERT).unwrap();
+        assert_eq!(
+            report.sgx_quote_status,
+            SgxQuoteStatus::ConfigurationAndSwHardeningNeeded
+        );
+        assert!(!report.advisory_ids.0.is_empty());
+    }
+
+    #[test]
+    fn from_cert_api_version_not_compatible() {
+        // this certificate is old enough that even the encoding of the endorsed report differs
+        match AttestationReport::from_cert(V3_CERT) {
+            Err(Error::ReportParseError(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn from_cert_software_mode() {
+        match AttestationReport::from_cert(SW_CERT) {
+            Err(Error::SoftwareModeCertificate) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn from_cert_tampered_report() {
+        let payload = get_netscape_comment(HW_CERT).unwrap();
+        let mut report: EndorsedAttestationReport = serde_json::from_slice(&payload).unwrap();
+        let last = report.report.len() - 2;
+        report.report[last] ^= 1;
+
+        match AttestationReport::from_endorsed_report(&report) {
+            Err(Error::BadSignature(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn from_cert_untrusted_signer() {
+        let payload = get_netscape_comment(HW_CERT).unwrap();
+        let mut report: EndorsedAttestationReport = serde_json::from_slice(&payload).unwrap();
+        // a certificate that is valid, but isn't issued by the IAS root
+        report.signing_cert = ias_root_ca_der();
+
+        match AttestationReport::from_endorsed_report(&report) {
+            Err(Error::UntrustedSigningCert(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
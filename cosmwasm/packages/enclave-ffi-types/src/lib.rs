@@ -4,8 +4,10 @@
 mod types;
 
 pub use types::{
-    Ctx, EnclaveBuffer, EnclaveError, HandleResult, InitResult, NodeAuthResult, OcallReturn,
-    QueryResult, UntrustedVmError, UserSpaceBuffer, HealthCheckResult
+    Ctx, DeserializationTarget, EnclaveBuffer, EnclaveError, FlushGas, GasProfile, HandleResult,
+    InitResult, MigrateResult, ModuleCacheStats, NodeAuthResult, OcallFailure, OcallKind,
+    OcallReturn, QueryBatchResult, QueryResult, ReissueResult, UntrustedVmError, UserSpaceBuffer,
+    HealthCheckResult
 };
 
 pub const ENCRYPTED_SEED_SIZE: usize = 48;
@@ -48,19 +48,77 @@ impl Ctx {
     }
 }
 
+/// The ocalls the enclave makes while running a contract
+/// cbindgen:prefix-with-name
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum OcallKind {
+    #[display(fmt = "read_db")]
+    ReadDb,
+    #[display(fmt = "scan_db")]
+    ScanDb,
+    #[display(fmt = "flush_db")]
+    FlushDb,
+    #[display(fmt = "query_chain")]
+    QueryChain,
+    #[display(fmt = "allocate")]
+    Allocate,
+}
+
+/// The stage at which an ocall failed
+/// cbindgen:prefix-with-name
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum OcallFailure {
+    /// We failed to call the ocall due to an SGX fault
+    #[display(fmt = "sgx failed to call it")]
+    Sgx,
+    /// The host returned an error. This can be a VmError thrown during the execution of the ocall,
+    /// or an issue that prevented the ocall from running correctly, such as a failed ecall during an ocall.
+    #[display(fmt = "the host returned an error")]
+    Host,
+    /// The host panicked while executing the ocall
+    #[display(fmt = "the host panicked")]
+    HostPanic,
+    /// The contract called the ocall with invalid arguments, e.g. an iterator that doesn't exist
+    #[display(fmt = "the contract passed invalid arguments")]
+    InvalidArguments,
+}
+
+/// The data that the enclave failed to deserialize
+/// cbindgen:prefix-with-name
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Display, PartialEq, Eq)]
+pub enum DeserializationTarget {
+    #[display(fmt = "env")]
+    Env,
+    #[display(fmt = "signature info")]
+    SigInfo,
+    #[display(fmt = "sign doc")]
+    SignDoc,
+    #[display(fmt = "address")]
+    Address,
+    #[display(fmt = "code hash")]
+    CodeHash,
+    #[display(fmt = "base64 data")]
+    Base64,
+    #[display(fmt = "contract output")]
+    ContractOutput,
+}
+
 /// This type represents the possible error conditions that can be encountered in the enclave
 /// cbindgen:prefix-with-name
 #[repr(C)]
 #[derive(Debug, Display)]
 pub enum EnclaveError {
-    /// An ocall failed to execute. This can happen because of three scenarios:
-    /// 1. A VmError was thrown during the execution of the ocall. In this case, `vm_error` will be non-null.
-    /// 2. An error happened that prevented the ocall from running correctly. This can happen because of
-    ///    caught memory-handling issues, or a failed ecall during an ocall. `vm_error` will be null.
-    /// 3. We failed to call the ocall due to an SGX fault. `vm_error` will be null.
-    // TODO should we split these three cases for better diagnostics?
-    #[display(fmt = "failed to execute ocall")]
-    FailedOcall { vm_error: UntrustedVmError },
+    /// An ocall failed to execute. `failure` tells at which stage, and when a VmError was thrown during
+    /// the execution of the ocall, `vm_error` will be non-null.
+    #[display(fmt = "failed to execute ocall {}: {}", ocall, failure)]
+    FailedOcall {
+        ocall: OcallKind,
+        failure: OcallFailure,
+        vm_error: UntrustedVmError,
+    },
     #[display(fmt = "failed to validate transaction")]
     ValidationFailure,
     // Problems with the module binary
@@ -77,6 +135,9 @@ pub enum EnclaveError {
     /// Fail to inject gas metering
     #[display(fmt = "failed to inject gas metering")]
     FailedGasMeteringInjection,
+    /// The gas schedule could not be parsed, or contained costs out of the allowed bounds
+    #[display(fmt = "invalid gas schedule")]
+    InvalidGasSchedule,
     #[display(fmt = "internal error during execution")]
     InternalError,
     // runtime issues with the module
@@ -120,10 +181,19 @@ pub enum EnclaveError {
     FailedSeal,
     #[display(fmt = "failed to unseal data")]
     FailedUnseal,
+    /// The enclave doesn't have the consensus seed of this epoch (yet)
+    #[display(fmt = "consensus seed of epoch {} is not available", epoch)]
+    SeedEpochUnavailable { epoch: u32 },
+    /// The host asked for an epoch older than one the chain already moved to
+    #[display(fmt = "consensus seed epoch {} is older than the active epoch", epoch)]
+    SeedEpochOutdated { epoch: u32 },
     #[display(fmt = "failed to authenticate secret contract")]
     FailedContractAuthentication,
-    #[display(fmt = "failed to deserialize data")]
-    FailedToDeserialize,
+    /// Someone other than the admin of the contract tried to migrate it
+    #[display(fmt = "only the admin of the contract can migrate it")]
+    UnauthorizedMigration,
+    #[display(fmt = "failed to deserialize {}", target)]
+    FailedToDeserialize { target: DeserializationTarget },
     #[display(fmt = "failed to serialize data")]
     FailedToSerialize,
     #[display(fmt = "failed to encrypt data")]
@@ -142,8 +212,12 @@ pub enum EnclaveError {
     NotImplemented,
     #[display(fmt = "failed to verify transaction signature")]
     FailedTxVerification,
+    #[display(fmt = "failed to verify query permit")]
+    FailedQueryPermitVerification,
     #[display(fmt = "contract tried to write to storage during a query")]
     UnauthorizedWrite,
+    #[display(fmt = "contract requested randomness outside of a block")]
+    RandomnessUnavailable,
 
     // serious issues
     /// The host was caught trying to disrupt the enclave.
@@ -209,6 +283,18 @@ pub enum NodeAuthResult {
         fmt = "Unexpected panic during node authentication. Certificate may be malformed or invalid"
     )]
     Panic,
+    #[display(fmt = "The attestation policy is malformed or of an unsupported version")]
+    InvalidAttestationPolicy,
+    #[display(fmt = "The platform is affected by a security advisory which is not allowed")]
+    AdvisoryNotAccepted,
+    #[display(
+        fmt = "The attestation policy doesn't allow handing the seed over to another enclave"
+    )]
+    UpgradeNotAllowed,
+    #[display(fmt = "The registering enclave runs in debug mode")]
+    DebugEnclave,
+    #[display(fmt = "The security version (ISVSVN) of the registering enclave is too low")]
+    IsvSvnTooLow,
 }
 
 /// This type represents the possible error conditions that can be encountered in the
@@ -288,6 +374,10 @@ pub enum InitResult {
         output: UserSpaceBuffer,
         /// The contract_key for this contract.
         contract_key: [u8; 64],
+        /// A proof that the contract key is valid in the current epoch of the consensus seed
+        contract_key_proof: [u8; 32],
+        /// A proof that the sender of the init is the admin of the contract
+        admin_proof: [u8; 32],
     },
     Failure {
         /// The error that happened in the enclave
@@ -295,6 +385,64 @@ pub enum InitResult {
     },
 }
 
+/// A breakdown of the gas used by a contract execution.
+/// Only enclaves built with the `gas-profiling` feature fill this in. Otherwise it's all zeros.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GasProfile {
+    /// Gas used by wasm opcodes, not including memory growth
+    pub wasm: u64,
+    /// Gas used by growing the wasm memory
+    pub memory_growth: u64,
+    /// Gas used by reading from storage, including iteration
+    pub storage_read: u64,
+    /// Gas used by writing to storage
+    pub storage_write: u64,
+    /// Gas used by removing keys from storage
+    pub storage_remove: u64,
+    /// Gas used by `canonicalize_address` and `humanize_address`
+    pub address_conversion: u64,
+    /// Gas used by queries to other contracts and modules
+    pub query_chain: u64,
+    /// Gas used by `random`
+    pub random: u64,
+    /// Gas used by `secp256k1_verify`, `ed25519_verify` and `ed25519_batch_verify`
+    pub signature_verification: u64,
+}
+
+/// The gas used by the host to apply a batch of storage operations, by kind of operation.
+/// This is returned from ocall_flush_db.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlushGas {
+    pub read: u64,
+    pub write: u64,
+    pub remove: u64,
+}
+
+/// This struct is returned from ecall_query_batch.
+/// The result of each query in the batch is returned separately.
+/// cbindgen:prefix-with-name
+#[repr(C)]
+pub enum QueryBatchResult {
+    Success,
+    Failure {
+        /// The error that prevented the batch from running
+        err: EnclaveError,
+    },
+}
+
+/// The counters of the enclave's cache of prepared modules.
+/// This struct is returned from ecall_get_module_cache_stats.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ModuleCacheStats {
+    /// The amount of times a prepared module was found in the cache
+    pub hits: u64,
+    /// The amount of times a module had to be prepared
+    pub misses: u64,
+}
+
 /// This struct is returned from ecall_handle.
 /// cbindgen:prefix-with-name
 #[repr(C)]
@@ -302,6 +450,26 @@ pub enum HandleResult {
     Success {
         /// A pointer to the output of the calculation
         output: UserSpaceBuffer,
+        /// The gas used by the calculation, by category
+        gas_profile: GasProfile,
+    },
+    Failure {
+        /// The error that happened in the enclave
+        err: EnclaveError,
+    },
+}
+
+/// This struct is returned from ecall_migrate.
+/// cbindgen:prefix-with-name
+#[repr(C)]
+pub enum MigrateResult {
+    Success {
+        /// A pointer to the output of the calculation
+        output: UserSpaceBuffer,
+        /// A proof that the contract key is valid for the new code of the contract
+        contract_key_proof: [u8; 32],
+        /// A proof that the admin of the contract can migrate it from the new code
+        admin_proof: [u8; 32],
     },
     Failure {
         /// The error that happened in the enclave
@@ -316,6 +484,24 @@ pub enum QueryResult {
     Success {
         /// A pointer to the output of the calculation
         output: UserSpaceBuffer,
+        /// The gas used by the calculation, by category
+        gas_profile: GasProfile,
+    },
+    Failure {
+        /// The error that happened in the enclave
+        err: EnclaveError,
+    },
+}
+
+/// This struct is returned from ecall_reissue_contract_proofs.
+/// cbindgen:prefix-with-name
+#[repr(C)]
+pub enum ReissueResult {
+    Success {
+        /// The proof of the contract key in the new epoch of the consensus seed
+        contract_key_proof: [u8; 32],
+        /// The admin proof in the new epoch, or all zeros if no admin proof was given
+        admin_proof: [u8; 32],
     },
     Failure {
         /// The error that happened in the enclave
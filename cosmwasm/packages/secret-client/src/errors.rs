@@ -0,0 +1,37 @@
+use std::fmt;
+
+/// Reasons for which a message can't be encrypted, or an output can't be decrypted
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The code hash isn't the hex encoded sha256 of a contract
+    InvalidCodeHash,
+    /// The message is too short to hold a nonce, a public key and a SIV tag
+    MessageTooShort(usize),
+    /// The ciphertext wasn't encrypted with the key of this keypair and nonce, or was modified
+    DecryptionFailed,
+    /// A field that should hold base64 doesn't
+    InvalidBase64(&'static str),
+    /// A decrypted log attribute isn't valid UTF-8
+    InvalidUtf8,
+    /// The output isn't shaped like the outputs of the enclave
+    MalformedOutput(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidCodeHash => write!(f, "the code hash must be 64 hex characters"),
+            Error::MessageTooShort(len) => write!(
+                f,
+                "an encrypted message of {} bytes is too short to be parsed",
+                len
+            ),
+            Error::DecryptionFailed => write!(f, "failed to decrypt the ciphertext"),
+            Error::InvalidBase64(what) => write!(f, "the {} isn't valid base64", what),
+            Error::InvalidUtf8 => write!(f, "the decrypted log attribute isn't valid UTF-8"),
+            Error::MalformedOutput(what) => write!(f, "malformed contract output: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,76 @@
+use hkdf::Hkdf;
+use rand_core::OsRng;
+use sha2::Sha256;
+use x25519_dalek::{PublicKey as DalekPublicKey, StaticSecret};
+
+pub const KEY_SIZE: usize = 32;
+
+/// An x25519 public key, like the consensus IO exchange key of the network
+pub type PublicKey = [u8; KEY_SIZE];
+/// The nonce of a transaction or query, which every key derived for it depends on
+pub type IoNonce = [u8; 32];
+/// The AES-SIV key that the messages and outputs of a transaction or query are encrypted with
+pub type EncryptionKey = [u8; KEY_SIZE];
+
+// Bitcoin halving block hash https://www.blockchain.com/btc/block/000000000000000000024bead8df69990852c202db0e0097c1a12ea637d7e96d
+// Must be the same as the KDF_SALT of the enclave
+const KDF_SALT: [u8; 32] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x4b, 0xea, 0xd8, 0xdf, 0x69, 0x99,
+    0x08, 0x52, 0xc2, 0x02, 0xdb, 0x0e, 0x00, 0x97, 0xc1, 0xa1, 0x2e, 0xa6, 0x37, 0xd7, 0xe9, 0x6d,
+];
+
+/// The x25519 keypair of a user.
+///
+/// The public key is sent along with every message, and the enclave combines it with the consensus
+/// IO exchange key to encrypt the output. Keep the secret to be able to decrypt the output later.
+#[derive(Clone)]
+pub struct KeyPair {
+    secret: StaticSecret,
+    public: DalekPublicKey,
+}
+
+impl KeyPair {
+    pub fn generate() -> Self {
+        Self::from_secret(StaticSecret::new(&mut OsRng))
+    }
+
+    pub fn from_secret_key(secret_key: [u8; KEY_SIZE]) -> Self {
+        Self::from_secret(StaticSecret::from(secret_key))
+    }
+
+    fn from_secret(secret: StaticSecret) -> Self {
+        let public = DalekPublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    /// The secret key, clamped as x25519 requires. It can differ from the bytes the keypair was
+    /// created from, but it gives the same keypair.
+    pub fn secret_key(&self) -> [u8; KEY_SIZE] {
+        self.secret.to_bytes()
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        *self.public.as_bytes()
+    }
+
+    /// Derives the key of a transaction or query the same way `calc_encryption_key` does in the
+    /// enclave: HKDF-SHA256 over the x25519 shared secret followed by the nonce.
+    pub fn encryption_key(
+        &self,
+        consensus_io_pubkey: &PublicKey,
+        nonce: &IoNonce,
+    ) -> EncryptionKey {
+        let shared_secret = self
+            .secret
+            .diffie_hellman(&DalekPublicKey::from(*consensus_io_pubkey));
+
+        let mut ikm = shared_secret.as_bytes().to_vec();
+        ikm.extend_from_slice(nonce);
+
+        let mut key = [0u8; KEY_SIZE];
+        Hkdf::<Sha256>::new(Some(&KDF_SALT), &ikm)
+            .expand(&[], &mut key)
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        key
+    }
+}
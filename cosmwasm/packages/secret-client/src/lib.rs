@@ -0,0 +1,21 @@
+//! Client side of the IO encryption of Secret Contracts.
+//!
+//! Messages to contracts are encrypted with AES-SIV, under a key that only the user and the
+//! enclave can derive: HKDF over the x25519 shared secret of the user's keypair and the consensus
+//! IO exchange key, and a fresh nonce. The enclave encrypts the output of the contract under the
+//! same key. This crate builds the messages the enclave accepts, and decrypts the outputs, without
+//! depending on the enclave or the SGX SDK.
+//!
+//! The test vectors in `testdata/io_vectors.json` are checked against this crate and the enclave.
+
+mod errors;
+mod keys;
+mod msg;
+mod output;
+
+#[cfg(test)]
+mod test_vectors;
+
+pub use errors::Error;
+pub use keys::{EncryptionKey, IoNonce, KeyPair, PublicKey, KEY_SIZE};
+pub use msg::{code_hash, Encryptor, SecretMessage, HEX_ENCODED_HASH_SIZE};
@@ -0,0 +1,133 @@
+use aes_siv::siv::Aes128Siv;
+use rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+use crate::errors::Error;
+use crate::keys::{EncryptionKey, IoNonce, KeyPair, PublicKey};
+
+/// The length of the code hash that is prepended to every message, which `validate_msg` checks
+pub const HEX_ENCODED_HASH_SIZE: usize = 64;
+
+// 32 bytes of nonce, 32 bytes of public key and 16+ bytes of encrypted data, rounded up the same
+// way as in `SecretMessage::from_slice` of the enclave
+const MIN_MESSAGE_SIZE: usize = 82;
+
+/// The hex encoded sha256 of a contract, which is the code hash the enclave expects
+pub fn code_hash(wasm: &[u8]) -> String {
+    hex::encode(Sha256::digest(wasm))
+}
+
+/// An encrypted message, as the enclave receives it in `init`, `handle` and `query`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SecretMessage {
+    pub nonce: IoNonce,
+    pub user_public_key: PublicKey,
+    pub msg: Vec<u8>,
+}
+
+impl SecretMessage {
+    pub fn from_slice(msg: &[u8]) -> Result<Self, Error> {
+        if msg.len() < MIN_MESSAGE_SIZE {
+            return Err(Error::MessageTooShort(msg.len()));
+        }
+
+        let mut nonce = [0u8; 32];
+        nonce.copy_from_slice(&msg[0..32]);
+
+        let mut user_public_key = [0u8; 32];
+        user_public_key.copy_from_slice(&msg[32..64]);
+
+        Ok(SecretMessage {
+            nonce,
+            user_public_key,
+            msg: msg[64..].to_vec(),
+        })
+    }
+
+    /// The nonce, the public key and the ciphertext, in the order the enclave reads them
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut packed_msg = self.nonce.to_vec();
+        packed_msg.extend_from_slice(&self.user_public_key);
+        packed_msg.extend_from_slice(&self.msg);
+        packed_msg
+    }
+}
+
+/// Encrypts messages to contracts, and decrypts what the enclave encrypted for them.
+///
+/// Every message gets its own nonce. Keep the nonce of a transaction or query, its output can
+/// only be decrypted with it.
+pub struct Encryptor {
+    key_pair: KeyPair,
+    consensus_io_pubkey: PublicKey,
+}
+
+impl Encryptor {
+    /// `consensus_io_pubkey` is the consensus IO exchange key of the network, which the enclave
+    /// encrypts with.
+    pub fn new(key_pair: KeyPair, consensus_io_pubkey: PublicKey) -> Self {
+        Self {
+            key_pair,
+            consensus_io_pubkey,
+        }
+    }
+
+    pub fn key_pair(&self) -> &KeyPair {
+        &self.key_pair
+    }
+
+    pub fn encryption_key(&self, nonce: &IoNonce) -> EncryptionKey {
+        self.key_pair
+            .encryption_key(&self.consensus_io_pubkey, nonce)
+    }
+
+    /// Encrypts a message to a contract with the given code hash, under a random nonce
+    pub fn encrypt(&self, code_hash: &str, msg: &[u8]) -> Result<SecretMessage, Error> {
+        let mut nonce = [0u8; 32];
+        OsRng.fill_bytes(&mut nonce);
+        self.encrypt_with_nonce(nonce, code_hash, msg)
+    }
+
+    /// Encrypts a message to a contract with the given code hash. Never reuse a nonce.
+    pub fn encrypt_with_nonce(
+        &self,
+        nonce: IoNonce,
+        code_hash: &str,
+        msg: &[u8],
+    ) -> Result<SecretMessage, Error> {
+        if code_hash.len() != HEX_ENCODED_HASH_SIZE || hex::decode(code_hash).is_err() {
+            return Err(Error::InvalidCodeHash);
+        }
+
+        let mut plaintext = code_hash.as_bytes().to_vec();
+        plaintext.extend_from_slice(msg);
+
+        Ok(SecretMessage {
+            nonce,
+            user_public_key: self.key_pair.public_key(),
+            msg: encrypt_siv(&self.encryption_key(&nonce), &plaintext),
+        })
+    }
+
+    /// Decrypts anything the enclave encrypted with the key of this nonce
+    pub fn decrypt(&self, nonce: &IoNonce, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+        decrypt_siv(&self.encryption_key(nonce), ciphertext)
+    }
+}
+
+// The enclave encrypts with a single empty header as the associated data
+const SIV_AD: &[&[u8]] = &[&[]];
+
+fn encrypt_siv(key: &EncryptionKey, plaintext: &[u8]) -> Vec<u8> {
+    let mut cipher = Aes128Siv::new((*key).into());
+    cipher
+        .encrypt(SIV_AD, plaintext)
+        .expect("AES-SIV encryption doesn't fail")
+}
+
+fn decrypt_siv(key: &EncryptionKey, ciphertext: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut cipher = Aes128Siv::new((*key).into());
+    cipher
+        .decrypt(SIV_AD, ciphertext)
+        .map_err(|_| Error::DecryptionFailed)
+}
@@ -0,0 +1,148 @@
+//! Reverses `encrypt_output` of the enclave.
+//!
+//! The output of a contract is JSON shaped like `{"Ok": ...}` or `{"Err": ...}`. The enclave
+//! encrypts its parts in place, so that the output stays valid JSON:
+//!
+//! * An error becomes `{"generic_err":{"msg":<base64 of the encrypted error JSON>}}`.
+//! * The base64 result of a query is encrypted and base64 encoded again.
+//! * The key and value of every log attribute that isn't public are encrypted and base64 encoded.
+//! * `data` holds the encrypted base64 text of the original data.
+//! * The `msg` of every `wasm` message becomes a new encrypted message, under the same nonce.
+//!
+//! Bank, staking and other messages are left as they are.
+
+use serde_json::{Map, Value};
+
+use crate::errors::Error;
+use crate::keys::IoNonce;
+use crate::msg::{Encryptor, SecretMessage, HEX_ENCODED_HASH_SIZE};
+
+impl Encryptor {
+    /// Decrypts a whole output of `init`, `handle` or `query`, and returns it as the contract
+    /// produced it. `callback_sig` of `wasm` messages is kept as it is.
+    pub fn decrypt_output(&self, nonce: &IoNonce, output: &[u8]) -> Result<Value, Error> {
+        let mut output: Value = serde_json::from_slice(output)
+            .map_err(|_| Error::MalformedOutput("the output isn't JSON"))?;
+        let output_obj = output
+            .as_object_mut()
+            .ok_or(Error::MalformedOutput("the output isn't an object"))?;
+
+        if let Some(err) = output_obj.get_mut("Err") {
+            *err = self.decrypt_error(nonce, err)?;
+        } else if let Some(ok) = output_obj.get_mut("Ok") {
+            match ok {
+                Value::String(result) => {
+                    *result = base64::encode(self.decrypt_query_result(nonce, result)?);
+                }
+                Value::Object(result) => self.decrypt_contract_result(nonce, result)?,
+                _ => return Err(Error::MalformedOutput("unexpected type of \"Ok\"")),
+            }
+        } else {
+            return Err(Error::MalformedOutput("neither \"Ok\" nor \"Err\" is set"));
+        }
+
+        Ok(output)
+    }
+
+    /// Decrypts the error of a failed execution, from its `generic_err` envelope
+    pub fn decrypt_error(&self, nonce: &IoNonce, err: &Value) -> Result<Value, Error> {
+        let msg = err
+            .pointer("/generic_err/msg")
+            .and_then(Value::as_str)
+            .ok_or(Error::MalformedOutput("the error has no generic_err.msg"))?;
+        let plaintext = self.decrypt_base64(nonce, msg, "error")?;
+
+        serde_json::from_slice(&plaintext)
+            .map_err(|_| Error::MalformedOutput("the decrypted error isn't JSON"))
+    }
+
+    /// Decrypts the result of a query, which the enclave returns as base64
+    pub fn decrypt_query_result(&self, nonce: &IoNonce, result: &str) -> Result<Vec<u8>, Error> {
+        let plaintext = self.decrypt_base64(nonce, result, "query result")?;
+        base64::decode(&plaintext).map_err(|_| Error::InvalidBase64("decrypted query result"))
+    }
+
+    /// Decrypts the key or the value of an encrypted log attribute
+    pub fn decrypt_log_attribute(&self, nonce: &IoNonce, attribute: &str) -> Result<String, Error> {
+        let plaintext = self.decrypt_base64(nonce, attribute, "log attribute")?;
+        String::from_utf8(plaintext).map_err(|_| Error::InvalidUtf8)
+    }
+
+    /// Decrypts the `data` of an `init` or `handle`, given as the raw bytes the chain returns
+    pub fn decrypt_data(&self, nonce: &IoNonce, data: &[u8]) -> Result<Vec<u8>, Error> {
+        let plaintext = self.decrypt(nonce, data)?;
+        base64::decode(&plaintext).map_err(|_| Error::InvalidBase64("decrypted data"))
+    }
+
+    fn decrypt_base64(
+        &self,
+        nonce: &IoNonce,
+        ciphertext: &str,
+        what: &'static str,
+    ) -> Result<Vec<u8>, Error> {
+        let ciphertext = base64::decode(ciphertext).map_err(|_| Error::InvalidBase64(what))?;
+        self.decrypt(nonce, &ciphertext)
+    }
+
+    fn decrypt_contract_result(
+        &self,
+        nonce: &IoNonce,
+        result: &mut Map<String, Value>,
+    ) -> Result<(), Error> {
+        if let Some(Value::Array(messages)) = result.get_mut("messages") {
+            for msg in messages {
+                // Both `execute` and `instantiate` are encrypted
+                if let Some(Value::Object(wasm)) = msg.get_mut("wasm") {
+                    for wasm_msg in wasm.values_mut() {
+                        self.decrypt_wasm_msg(wasm_msg)?;
+                    }
+                }
+            }
+        }
+
+        if let Some(Value::Array(log)) = result.get_mut("log") {
+            for attribute in log {
+                // Missing in the output of contracts built before public attributes existed
+                let encrypted = attribute
+                    .get("encrypted")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(true);
+                if !encrypted {
+                    continue;
+                }
+                for field in &["key", "value"] {
+                    if let Some(Value::String(text)) = attribute.get_mut(*field) {
+                        *text = self.decrypt_log_attribute(nonce, text)?;
+                    }
+                }
+            }
+        }
+
+        if let Some(Value::String(data)) = result.get_mut("data") {
+            let ciphertext =
+                base64::decode(data.as_str()).map_err(|_| Error::InvalidBase64("data"))?;
+            *data = base64::encode(self.decrypt_data(nonce, &ciphertext)?);
+        }
+
+        Ok(())
+    }
+
+    // Restores the original `msg`, which the enclave prefixed with `callback_code_hash`. The enclave
+    // encrypts it under the nonce of the transaction, which the message carries.
+    fn decrypt_wasm_msg(&self, wasm_msg: &mut Value) -> Result<(), Error> {
+        let msg = match wasm_msg.get_mut("msg") {
+            Some(Value::String(msg)) => msg,
+            _ => return Err(Error::MalformedOutput("a wasm message has no msg")),
+        };
+
+        let packed = base64::decode(msg.as_str()).map_err(|_| Error::InvalidBase64("msg"))?;
+        let secret_msg = SecretMessage::from_slice(&packed)?;
+        let plaintext = self.decrypt(&secret_msg.nonce, &secret_msg.msg)?;
+        if plaintext.len() < HEX_ENCODED_HASH_SIZE {
+            return Err(Error::MalformedOutput("a wasm message has no code hash"));
+        }
+
+        *msg = base64::encode(&plaintext[HEX_ENCODED_HASH_SIZE..]);
+        Ok(())
+    }
+}
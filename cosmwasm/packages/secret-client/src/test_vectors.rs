@@ -0,0 +1,264 @@
+//! Checks the crate against `testdata/io_vectors.json`, which the enclave is tested with as well
+
+use serde_json::{json, Value};
+
+use crate::{code_hash, Encryptor, Error, KeyPair, SecretMessage, HEX_ENCODED_HASH_SIZE};
+
+const IO_VECTORS: &str = include_str!("../testdata/io_vectors.json");
+
+struct Vectors(Value);
+
+impl Vectors {
+    fn load() -> Self {
+        Vectors(serde_json::from_str(IO_VECTORS).unwrap())
+    }
+
+    fn str(&self, name: &str) -> &str {
+        self.0[name].as_str().unwrap()
+    }
+
+    fn bytes(&self, name: &str) -> Vec<u8> {
+        hex::decode(self.str(name)).unwrap()
+    }
+
+    fn key(&self, name: &str) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&self.bytes(name));
+        key
+    }
+
+    fn encryptor(&self) -> Encryptor {
+        Encryptor::new(
+            KeyPair::from_secret_key(self.key("user_private_key")),
+            self.key("consensus_io_public_key"),
+        )
+    }
+
+    fn outputs(&self) -> &Vec<Value> {
+        self.0["outputs"].as_array().unwrap()
+    }
+}
+
+#[test]
+fn key_pairs_match_vectors() {
+    let vectors = Vectors::load();
+
+    let user = KeyPair::from_secret_key(vectors.key("user_private_key"));
+    assert_eq!(user.public_key(), vectors.key("user_public_key"));
+    assert_eq!(user.secret_key(), vectors.key("user_private_key"));
+
+    let consensus = KeyPair::from_secret_key(vectors.key("consensus_io_private_key"));
+    assert_eq!(
+        consensus.public_key(),
+        vectors.key("consensus_io_public_key")
+    );
+}
+
+#[test]
+fn encryption_key_is_shared_with_the_enclave() {
+    let vectors = Vectors::load();
+    let nonce = vectors.key("nonce");
+
+    let encryptor = vectors.encryptor();
+    assert_eq!(
+        encryptor.encryption_key(&nonce),
+        vectors.key("encryption_key")
+    );
+
+    // The enclave derives the key from the other side of the exchange
+    let consensus = KeyPair::from_secret_key(vectors.key("consensus_io_private_key"));
+    assert_eq!(
+        consensus.encryption_key(&vectors.key("user_public_key"), &nonce),
+        vectors.key("encryption_key")
+    );
+}
+
+#[test]
+fn encrypt_matches_vectors() {
+    let vectors = Vectors::load();
+
+    let secret_msg = vectors
+        .encryptor()
+        .encrypt_with_nonce(
+            vectors.key("nonce"),
+            vectors.str("code_hash"),
+            vectors.str("msg").as_bytes(),
+        )
+        .unwrap();
+
+    assert_eq!(secret_msg.nonce, vectors.key("nonce"));
+    assert_eq!(secret_msg.user_public_key, vectors.key("user_public_key"));
+    assert_eq!(secret_msg.to_vec(), vectors.bytes("secret_message"));
+    assert_eq!(
+        SecretMessage::from_slice(&vectors.bytes("secret_message")).unwrap(),
+        secret_msg
+    );
+}
+
+#[test]
+fn decrypted_message_starts_with_code_hash() {
+    let vectors = Vectors::load();
+    let encryptor = vectors.encryptor();
+
+    let secret_msg = SecretMessage::from_slice(&vectors.bytes("secret_message")).unwrap();
+    let plaintext = encryptor
+        .decrypt(&secret_msg.nonce, &secret_msg.msg)
+        .unwrap();
+
+    let (hash, msg) = plaintext.split_at(HEX_ENCODED_HASH_SIZE);
+    assert_eq!(hash, vectors.str("code_hash").as_bytes());
+    assert_eq!(msg, vectors.str("msg").as_bytes());
+}
+
+#[test]
+fn decrypt_output_matches_vectors() {
+    let vectors = Vectors::load();
+    let encryptor = vectors.encryptor();
+    let nonce = vectors.key("nonce");
+
+    for output in vectors.outputs() {
+        let encrypted = serde_json::to_vec(&output["encrypted"]).unwrap();
+        assert_eq!(
+            encryptor.decrypt_output(&nonce, &encrypted).unwrap(),
+            output["plaintext"]
+        );
+    }
+}
+
+#[test]
+fn decrypt_output_parts() {
+    let vectors = Vectors::load();
+    let encryptor = vectors.encryptor();
+    let nonce = vectors.key("nonce");
+    let outputs = vectors.outputs();
+
+    let handle = &outputs[0]["encrypted"]["Ok"];
+    assert_eq!(
+        encryptor
+            .decrypt_log_attribute(&nonce, handle["log"][0]["value"].as_str().unwrap())
+            .unwrap(),
+        "transfer"
+    );
+    let data = base64::decode(handle["data"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        encryptor.decrypt_data(&nonce, &data).unwrap(),
+        br#"{"status":"success"}"#.to_vec()
+    );
+
+    let err = &outputs[2]["encrypted"]["Err"];
+    assert_eq!(
+        encryptor.decrypt_error(&nonce, err).unwrap(),
+        json!({"generic_err":{"msg":"insufficient funds"}})
+    );
+
+    let query = outputs[3]["encrypted"]["Ok"].as_str().unwrap();
+    assert_eq!(
+        encryptor.decrypt_query_result(&nonce, query).unwrap(),
+        br#"{"balance":{"amount":"108"}}"#.to_vec()
+    );
+}
+
+// `enclave_data` was encrypted by the aes-siv version the enclave uses, which isn't ours
+#[test]
+fn decrypt_data_encrypted_by_the_enclave() {
+    let vectors = Vectors::load();
+    let encryptor = vectors.encryptor();
+    let data = &vectors.0["enclave_data"];
+
+    let ciphertext = hex::decode(data["ciphertext"].as_str().unwrap()).unwrap();
+    assert_eq!(
+        encryptor
+            .decrypt_data(&vectors.key("nonce"), &ciphertext)
+            .unwrap(),
+        data["plaintext"].as_str().unwrap().as_bytes().to_vec()
+    );
+}
+
+#[test]
+fn decrypt_with_other_nonce_fails() {
+    let vectors = Vectors::load();
+    let encryptor = vectors.encryptor();
+
+    let encrypted = serde_json::to_vec(&vectors.outputs()[3]["encrypted"]).unwrap();
+    assert_eq!(
+        encryptor
+            .decrypt_output(&[0u8; 32], &encrypted)
+            .unwrap_err(),
+        Error::DecryptionFailed
+    );
+}
+
+#[test]
+fn decrypt_output_restores_wasm_messages() {
+    let vectors = Vectors::load();
+    let encryptor = vectors.encryptor();
+    let nonce = vectors.key("nonce");
+    let callback_code_hash = code_hash(b"callee");
+
+    // What the enclave makes of a message to another contract
+    let secret_msg = encryptor
+        .encrypt_with_nonce(nonce, &callback_code_hash, br#"{"release":{}}"#)
+        .unwrap();
+    let encrypted_log = &vectors.outputs()[0]["encrypted"]["Ok"]["log"][0];
+    let encrypted = json!({"Ok":{
+        "messages": [{"wasm":{"execute":{
+            "contract_addr": "secret1callee",
+            "callback_code_hash": callback_code_hash,
+            "msg": base64::encode(secret_msg.to_vec()),
+            "send": [],
+            "callback_sig": [1, 2, 3],
+        }}}],
+        "log": [{"key": encrypted_log["key"], "value": encrypted_log["value"]}],
+        "data": null,
+    }});
+
+    let output = encryptor
+        .decrypt_output(&nonce, &serde_json::to_vec(&encrypted).unwrap())
+        .unwrap();
+
+    let execute = &output["Ok"]["messages"][0]["wasm"]["execute"];
+    assert_eq!(execute["msg"], base64::encode(r#"{"release":{}}"#));
+    assert_eq!(execute["callback_code_hash"], callback_code_hash);
+    assert_eq!(execute["callback_sig"], json!([1, 2, 3]));
+    // Attributes without `encrypted` come from older contracts, which encrypt all of them
+    assert_eq!(
+        output["Ok"]["log"][0],
+        json!({"key": "action", "value": "transfer"})
+    );
+}
+
+#[test]
+fn encrypt_rejects_invalid_code_hash() {
+    let encryptor = Encryptor::new(KeyPair::generate(), KeyPair::generate().public_key());
+
+    assert_eq!(
+        encryptor.encrypt("1234", b"{}").unwrap_err(),
+        Error::InvalidCodeHash
+    );
+    assert_eq!(
+        encryptor.encrypt(&"zz".repeat(32), b"{}").unwrap_err(),
+        Error::InvalidCodeHash
+    );
+}
+
+#[test]
+fn encrypt_uses_fresh_nonces() {
+    let encryptor = Encryptor::new(KeyPair::generate(), KeyPair::generate().public_key());
+    let hash = code_hash(b"contract");
+
+    let first = encryptor.encrypt(&hash, b"{}").unwrap();
+    let second = encryptor.encrypt(&hash, b"{}").unwrap();
+    assert_ne!(first.nonce, second.nonce);
+    assert_ne!(first.msg, second.msg);
+
+    let plaintext = encryptor.decrypt(&first.nonce, &first.msg).unwrap();
+    assert_eq!(plaintext, format!("{}{{}}", hash).into_bytes());
+}
+
+#[test]
+fn secret_message_from_short_slice_fails() {
+    assert_eq!(
+        SecretMessage::from_slice(&[0u8; 81]).unwrap_err(),
+        Error::MessageTooShort(81)
+    );
+}
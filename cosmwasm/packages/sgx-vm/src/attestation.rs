@@ -20,11 +20,39 @@ extern "C" {
         api_key: *const u8,
         api_key_len: u32,
     ) -> sgx_status_t;
+    pub fn ecall_get_attestation_report_dcap(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+    ) -> sgx_status_t;
     pub fn ecall_authenticate_new_node(
         eid: sgx_enclave_id_t,
         retval: *mut NodeAuthResult,
         cert: *const u8,
         cert_len: u32,
+        policy: *const u8,
+        policy_len: u32,
+        block_time: u64,
+        seed: &mut [u8; ENCRYPTED_SEED_SIZE],
+    ) -> sgx_status_t;
+    pub fn ecall_authenticate_new_node_for_epoch(
+        eid: sgx_enclave_id_t,
+        retval: *mut NodeAuthResult,
+        cert: *const u8,
+        cert_len: u32,
+        policy: *const u8,
+        policy_len: u32,
+        block_time: u64,
+        epoch: u32,
+        seed: &mut [u8; ENCRYPTED_SEED_SIZE],
+    ) -> sgx_status_t;
+    pub fn ecall_export_seed_for_upgrade(
+        eid: sgx_enclave_id_t,
+        retval: *mut NodeAuthResult,
+        cert: *const u8,
+        cert_len: u32,
+        policy: *const u8,
+        policy_len: u32,
+        epoch: u32,
         seed: &mut [u8; ENCRYPTED_SEED_SIZE],
     ) -> sgx_status_t;
 }
@@ -65,6 +93,27 @@ pub extern "C" fn ocall_get_ias_socket(ret_fd: *mut c_int) -> sgx_status_t {
     sgx_status_t::SGX_SUCCESS
 }
 
+/// The CRL of the Intel SGX Root CA isn't served by the PCS, but by Intel's certificates host
+#[no_mangle]
+pub extern "C" fn ocall_get_sgx_certificates_socket(ret_fd: *mut c_int) -> sgx_status_t {
+    let port = 443;
+    let hostname = "certificates.trustedservices.intel.com";
+    let addr = lookup_ipv4(hostname, port);
+    let sock = match TcpStream::connect(&addr) {
+        Ok(sock) => sock,
+        Err(e) => {
+            warn!("Failed to connect to {}: {:?}", hostname, e);
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+    };
+
+    unsafe {
+        *ret_fd = sock.into_raw_fd();
+    }
+
+    sgx_status_t::SGX_SUCCESS
+}
+
 #[no_mangle]
 pub extern "C" fn ocall_get_quote(
     p_sigrl: *const u8,
@@ -117,6 +166,94 @@ pub extern "C" fn ocall_get_quote(
     ret
 }
 
+#[cfg(feature = "dcap")]
+extern "C" {
+    // from libsgx-dcap-ql
+    fn sgx_qe_get_target_info(p_qe_target_info: *mut sgx_target_info_t) -> sgx_quote3_error_t;
+    fn sgx_qe_get_quote_size(p_quote_size: *mut u32) -> sgx_quote3_error_t;
+    fn sgx_qe_get_quote(
+        p_app_report: *const sgx_report_t,
+        quote_size: u32,
+        p_quote: *mut u8,
+    ) -> sgx_quote3_error_t;
+}
+
+#[cfg(feature = "dcap")]
+#[no_mangle]
+pub extern "C" fn ocall_get_qe_target_info(ret_ti: *mut sgx_target_info_t) -> sgx_status_t {
+    trace!("Entering ocall_get_qe_target_info");
+    let ret = unsafe { sgx_qe_get_target_info(ret_ti) };
+
+    if ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
+        warn!("sgx_qe_get_target_info returned {:?}", ret);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    sgx_status_t::SGX_SUCCESS
+}
+
+#[cfg(feature = "dcap")]
+#[no_mangle]
+pub extern "C" fn ocall_get_dcap_quote(
+    p_report: *const sgx_report_t,
+    p_quote: *mut u8,
+    maxlen: u32,
+    p_quote_len: *mut u32,
+) -> sgx_status_t {
+    trace!("Entering ocall_get_dcap_quote");
+
+    let mut real_quote_len: u32 = 0;
+
+    let ret = unsafe { sgx_qe_get_quote_size(&mut real_quote_len as *mut u32) };
+
+    if ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
+        warn!("sgx_qe_get_quote_size returned {:?}", ret);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    trace!("quote size = {}", real_quote_len);
+    unsafe {
+        *p_quote_len = real_quote_len;
+    }
+
+    if real_quote_len > maxlen {
+        warn!(
+            "DCAP quote of {} bytes does not fit in the enclave buffer",
+            real_quote_len
+        );
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let ret = unsafe { sgx_qe_get_quote(p_report, real_quote_len, p_quote) };
+
+    if ret != sgx_quote3_error_t::SGX_QL_SUCCESS {
+        warn!("sgx_qe_get_quote returned {:?}", ret);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    sgx_status_t::SGX_SUCCESS
+}
+
+// The enclave always imports the DCAP ocalls, so builds without DCAP support still have to define them
+#[cfg(not(feature = "dcap"))]
+#[no_mangle]
+pub extern "C" fn ocall_get_qe_target_info(_ret_ti: *mut sgx_target_info_t) -> sgx_status_t {
+    warn!("This node was built without DCAP support");
+    sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED
+}
+
+#[cfg(not(feature = "dcap"))]
+#[no_mangle]
+pub extern "C" fn ocall_get_dcap_quote(
+    _p_report: *const sgx_report_t,
+    _p_quote: *mut u8,
+    _maxlen: u32,
+    _p_quote_len: *mut u32,
+) -> sgx_status_t {
+    warn!("This node was built without DCAP support");
+    sgx_status_t::SGX_ERROR_FEATURE_NOT_SUPPORTED
+}
+
 #[no_mangle]
 pub extern "C" fn ocall_get_update_info(
     platform_blob: *const sgx_platform_info_t,
@@ -153,8 +290,31 @@ pub fn create_attestation_report_u(spid: &[u8], api_key: &[u8]) -> SgxResult<()>
     Ok(())
 }
 
+pub fn create_attestation_report_dcap_u() -> SgxResult<()> {
+    let enclave = get_enclave()?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let status = unsafe { ecall_get_attestation_report_dcap(eid, &mut retval) };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(())
+}
+
+/// Authenticates a registering node, and returns the seed encrypted for it.
+/// `policy` is the attestation policy of the chain (may be empty), and `block_time` the time of
+/// the current block in seconds.
 pub fn untrusted_get_encrypted_seed(
     cert: &[u8],
+    policy: &[u8],
+    block_time: u64,
 ) -> SgxResult<Result<[u8; ENCRYPTED_SEED_SIZE], NodeAuthResult>> {
     let enclave = get_enclave()?;
     let eid = enclave.geteid();
@@ -166,6 +326,9 @@ pub fn untrusted_get_encrypted_seed(
             &mut retval,
             cert.as_ptr(),
             cert.len() as u32,
+            policy.as_ptr(),
+            policy.len() as u32,
+            block_time,
             &mut seed,
         )
     };
@@ -186,6 +349,77 @@ pub fn untrusted_get_encrypted_seed(
     Ok(Ok(seed))
 }
 
+pub fn untrusted_get_encrypted_seed_for_epoch(
+    cert: &[u8],
+    policy: &[u8],
+    block_time: u64,
+    epoch: u32,
+) -> SgxResult<Result<[u8; ENCRYPTED_SEED_SIZE], NodeAuthResult>> {
+    let enclave = get_enclave()?;
+    let eid = enclave.geteid();
+    let mut retval = NodeAuthResult::Success;
+    let mut seed = [0u8; ENCRYPTED_SEED_SIZE];
+    let status = unsafe {
+        ecall_authenticate_new_node_for_epoch(
+            eid,
+            &mut retval,
+            cert.as_ptr(),
+            cert.len() as u32,
+            policy.as_ptr(),
+            policy.len() as u32,
+            block_time,
+            epoch,
+            &mut seed,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != NodeAuthResult::Success {
+        return Ok(Err(retval));
+    }
+
+    Ok(Ok(seed))
+}
+
+/// Hands the seed of `epoch` over to an upgraded enclave, whose attestation certificate is `cert`.
+/// The MRENCLAVE of the new enclave must be in the `allowed_mr_enclaves` of `policy`, which must be
+/// the attestation policy of the chain.
+pub fn untrusted_export_seed_for_upgrade(
+    cert: &[u8],
+    policy: &[u8],
+    epoch: u32,
+) -> SgxResult<Result<[u8; ENCRYPTED_SEED_SIZE], NodeAuthResult>> {
+    let enclave = get_enclave()?;
+    let eid = enclave.geteid();
+    let mut retval = NodeAuthResult::Success;
+    let mut seed = [0u8; ENCRYPTED_SEED_SIZE];
+    let status = unsafe {
+        ecall_export_seed_for_upgrade(
+            eid,
+            &mut retval,
+            cert.as_ptr(),
+            cert.len() as u32,
+            policy.as_ptr(),
+            policy.len() as u32,
+            epoch,
+            &mut seed,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != NodeAuthResult::Success {
+        return Ok(Err(retval));
+    }
+
+    Ok(Ok(seed))
+}
+
 #[cfg(test)]
 mod test {
     use crate::attestation::retry_quote;
@@ -15,6 +15,7 @@ use crate::instance::Instance;
 use crate::modules::FileSystemCache;
 */
 use crate::traits::{Api, Extern, Querier, Storage};
+use crate::wasmi::module_cache_stats;
 
 const WASM_DIR: &str = "wasm";
 /*
@@ -22,9 +23,13 @@ const MODULES_DIR: &str = "modules";
 */
 
 #[derive(Debug, Default, Clone)]
-struct Stats {
-    hits_module: u32,
-    misses: u32,
+pub struct Stats {
+    pub hits_module: u32,
+    pub misses: u32,
+    /// Hits of the enclave's cache of prepared modules, which is shared by all caches
+    pub hits_enclave_module: u64,
+    /// Misses of the enclave's cache of prepared modules, which is shared by all caches
+    pub misses_enclave_module: u64,
 }
 
 pub struct CosmCache<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static> {
@@ -106,6 +111,16 @@ where
         }
     }
 
+    /// Returns the cache statistics, including those of the enclave's module cache
+    pub fn stats(&self) -> VmResult<Stats> {
+        let enclave_stats = module_cache_stats()?;
+        Ok(Stats {
+            hits_enclave_module: enclave_stats.hits,
+            misses_enclave_module: enclave_stats.misses,
+            ..self.stats.clone()
+        })
+    }
+
     /// Returns an Instance tied to a previously saved Wasm.
     /// Depending on availability, this is either generated from a cached instance, a cached module or Wasm code.
     pub fn get_instance(
@@ -367,6 +382,28 @@ mod test {
         assert_eq!(1, msgs.len());
     }
 
+    #[test]
+    fn stats_include_enclave_module_cache() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut cache = unsafe { CosmCache::new(tmp_dir.path(), default_features()).unwrap() };
+        let id = cache.save_wasm(CONTRACT).unwrap();
+        let before = cache.stats().unwrap();
+
+        // The second instance runs the same code, so the enclave should reuse the prepared module
+        for _ in 0..2 {
+            let deps = mock_dependencies(20, &[]);
+            let mut instance = cache.get_instance(&id, deps, TESTING_GAS_LIMIT).unwrap();
+            let env = mock_env("creator", &coins(1000, "earth"));
+            let msg = r#"{"verifier": "verifies", "beneficiary": "benefits"}"#.as_bytes();
+            call_init::<_, _, _, Empty>(&mut instance, &env, msg)
+                .unwrap()
+                .unwrap();
+        }
+
+        let after = cache.stats().unwrap();
+        assert!(after.hits_enclave_module > before.hits_enclave_module);
+    }
+
     #[test]
     fn use_multiple_cached_instances_of_same_contract() {
         let tmp_dir = TempDir::new().unwrap();
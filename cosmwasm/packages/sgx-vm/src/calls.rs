@@ -119,18 +119,46 @@ pub fn call_handle_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'sta
     instance.call_handle(env, msg, sig_info)
 }
 
-/// Calls Wasm export "migrate" and returns raw data from the contract.
+/// Like `call_init_raw`, but the contract gets no randomness.
+/// The storage should be thrown away afterwards, since the contract writes to it as usual.
+/// This is meant for estimating the gas an init would use, which is reported by the instance as usual.
+pub fn call_simulate_init_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
+    instance: &mut Instance<S, A, Q>,
+    env: &[u8],
+    msg: &[u8],
+    sig_info: &[u8],
+) -> VmResult<Vec<u8>> {
+    instance.set_storage_readonly(false);
+    instance.call_simulate_init(env, msg, sig_info)
+}
+
+/// Like `call_handle_raw`, but the contract gets no randomness.
+/// The storage should be thrown away afterwards, since the contract writes to it as usual.
+/// This is meant for estimating the gas a handle would use, which is reported by the instance as usual.
+pub fn call_simulate_handle_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
+    instance: &mut Instance<S, A, Q>,
+    env: &[u8],
+    msg: &[u8],
+    sig_info: &[u8],
+) -> VmResult<Vec<u8>> {
+    instance.set_storage_readonly(false);
+    instance.call_simulate_handle(env, msg, sig_info)
+}
+
+/// Calls Wasm export "migrate" and returns raw data from the contract, along with the proofs
+/// that the contract key is valid for the new code and that the admin can migrate it from there.
 /// The result is length limited to prevent abuse but otherwise unchecked.
 pub fn call_migrate_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
     instance: &mut Instance<S, A, Q>,
     env: &[u8],
     msg: &[u8],
-) -> VmResult<Vec<u8>> {
+    sig_info: &[u8],
+) -> VmResult<(Vec<u8>, [u8; 32], [u8; 32])> {
     instance.set_storage_readonly(false);
     /*
     call_raw(instance, "migrate", &[env, msg], MAX_LENGTH_MIGRATE)
     */
-    instance.call_migrate(env, msg)
+    instance.call_migrate(env, msg, sig_info)
 }
 
 /// Calls Wasm export "query" and returns raw data from the contract.
@@ -146,6 +174,22 @@ pub fn call_query_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'stat
     instance.call_query(msg)
 }
 
+/// Calls Wasm export "query" of several instances in a single call into the enclave.
+/// The queries share `gas_limit`. Returns the raw data of each query, and the total gas used.
+pub fn call_query_batch_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
+    queries: &mut [(&mut Instance<S, A, Q>, &[u8])],
+    gas_limit: u64,
+) -> VmResult<(Vec<VmResult<Vec<u8>>>, u64)> {
+    for (instance, _) in queries.iter_mut() {
+        instance.set_storage_readonly(true);
+    }
+    let queries: Vec<(&Instance<S, A, Q>, &[u8])> = queries
+        .iter()
+        .map(|(instance, msg)| (&**instance, *msg))
+        .collect();
+    Instance::call_query_batch(&queries, gas_limit)
+}
+
 #[cfg(not(feature = "default-enclave"))]
 fn call_raw<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static>(
     instance: &mut Instance<S, A, Q>,
@@ -15,6 +15,11 @@ const SUPPORTED_IMPORTS: &[&str] = &[
     "env.canonicalize_address",
     "env.humanize_address",
     "env.query_chain",
+    #[cfg(feature = "random")]
+    "env.random",
+    "env.secp256k1_verify",
+    "env.ed25519_verify",
+    "env.ed25519_batch_verify",
     #[cfg(feature = "iterator")]
     "env.db_scan",
     #[cfg(feature = "iterator")]
@@ -342,12 +347,34 @@ mod test {
             (import "env" "db_remove" (func (param i32) (result i32)))
             (import "env" "canonicalize_address" (func (param i32 i32) (result i32)))
             (import "env" "humanize_address" (func (param i32 i32) (result i32)))
+            (import "env" "secp256k1_verify" (func (param i32 i32 i32) (result i32)))
+            (import "env" "ed25519_verify" (func (param i32 i32 i32) (result i32)))
+            (import "env" "ed25519_batch_verify" (func (param i32 i32 i32) (result i32)))
         )"#,
         )
         .unwrap();
         check_wasm_imports(&deserialize_buffer(&wasm).unwrap()).unwrap();
     }
 
+    #[test]
+    fn check_wasm_imports_random() {
+        let wasm = wat2wasm(r#"(module (import "env" "random" (func (result i32))))"#).unwrap();
+        let result = check_wasm_imports(&deserialize_buffer(&wasm).unwrap());
+
+        #[cfg(feature = "random")]
+        result.unwrap();
+        #[cfg(not(feature = "random"))]
+        match result {
+            Err(VmError::StaticValidationErr { msg, .. }) => {
+                assert!(
+                    msg.starts_with("Wasm contract requires unsupported import: \"env.random\"")
+                );
+            }
+            Err(e) => panic!("Unexpected error {:?}", e),
+            Ok(_) => panic!("Didn't reject wasm with the random import"),
+        }
+    }
+
     #[test]
     fn test_check_wasm_imports_of_old_contract() {
         let module = deserialize_buffer(CONTRACT_0_7).unwrap();
@@ -1,3 +1,5 @@
+#![cfg_attr(feature = "mock-enclave", allow(unused))]
+
 use std::{
     env,
     path::{Path, PathBuf},
@@ -11,6 +13,9 @@ use sgx_urts::SgxEnclave;
 use lazy_static::lazy_static;
 use log::*;
 
+#[cfg(not(feature = "mock-enclave"))]
+pub type Enclave = SgxEnclave;
+
 static ENCLAVE_FILE: &str = "librust_cosmwasm_enclave.signed.so";
 
 #[cfg(feature = "production")]
@@ -80,6 +85,7 @@ fn init_enclave() -> SgxResult<SgxEnclave> {
     )
 }
 
+#[cfg(not(feature = "mock-enclave"))]
 lazy_static! {
     static ref SGX_ENCLAVE: SgxResult<SgxEnclave> = init_enclave();
 }
@@ -87,6 +93,28 @@ lazy_static! {
 /// Use this method when trying to get access to the enclave.
 /// You can unwrap the result when you are certain that the enclave
 /// must have been initialized if you even reached that point in the code.
-pub fn get_enclave() -> SgxResult<&'static SgxEnclave> {
+#[cfg(not(feature = "mock-enclave"))]
+pub fn get_enclave() -> SgxResult<&'static Enclave> {
     SGX_ENCLAVE.as_ref().map_err(|status| *status)
 }
+
+/// Stands in for the enclave when the contract runtime is linked into this process.
+/// Calls into it go to `wasmi::mock_imports` instead of through the SGX SDK.
+#[cfg(feature = "mock-enclave")]
+pub struct Enclave;
+
+#[cfg(feature = "mock-enclave")]
+impl Enclave {
+    pub fn geteid(&self) -> sgx_types::sgx_enclave_id_t {
+        0
+    }
+}
+
+#[cfg(feature = "mock-enclave")]
+static MOCK_ENCLAVE: Enclave = Enclave;
+
+/// The mock enclave is always available
+#[cfg(feature = "mock-enclave")]
+pub fn get_enclave() -> SgxResult<&'static Enclave> {
+    Ok(&MOCK_ENCLAVE)
+}
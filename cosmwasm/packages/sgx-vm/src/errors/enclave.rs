@@ -37,11 +37,69 @@ impl From<enclave_ffi_types::EnclaveError> for VmError {
     fn from(error: enclave_ffi_types::EnclaveError) -> Self {
         match error {
             enclave_ffi_types::EnclaveError::OutOfGas => VmError::GasDepletion,
-            enclave_ffi_types::EnclaveError::FailedOcall { vm_error }
+            enclave_ffi_types::EnclaveError::FailedOcall { vm_error, .. }
                 if !vm_error.ptr.is_null() =>
             // This error is boxed during ocalls.
             unsafe { *Box::<VmError>::from_raw(vm_error.ptr as *mut _) }
+            enclave_ffi_types::EnclaveError::FailedOcall { ocall, failure, .. } => {
+                VmError::ocall_err(ocall, failure)
+            }
+            enclave_ffi_types::EnclaveError::FailedToDeserialize { target } => {
+                VmError::enclave_parse_err(target)
+            }
             other => EnclaveError::enclave_err(other).into(),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use enclave_ffi_types::{DeserializationTarget, OcallFailure, OcallKind, UntrustedVmError};
+
+    #[test]
+    fn failed_ocall_without_vm_error_becomes_ocall_err() {
+        let error: VmError = enclave_ffi_types::EnclaveError::FailedOcall {
+            ocall: OcallKind::QueryChain,
+            failure: OcallFailure::Sgx,
+            vm_error: UntrustedVmError::default(),
+        }
+        .into();
+        match error {
+            VmError::OcallErr { ocall, failure, .. } => {
+                assert_eq!(ocall, OcallKind::QueryChain);
+                assert_eq!(failure, OcallFailure::Sgx);
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn failed_ocall_with_vm_error_unboxes_it() {
+        let vm_error = Box::new(VmError::write_access_denied());
+        let error: VmError = enclave_ffi_types::EnclaveError::FailedOcall {
+            ocall: OcallKind::FlushDb,
+            failure: OcallFailure::Host,
+            vm_error: UntrustedVmError::new(Box::into_raw(vm_error) as *mut _),
+        }
+        .into();
+        match error {
+            VmError::WriteAccessDenied { .. } => {}
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn failed_to_deserialize_becomes_enclave_parse_err() {
+        let error: VmError = enclave_ffi_types::EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Env,
+        }
+        .into();
+        match error {
+            VmError::EnclaveParseErr { target, .. } => {
+                assert_eq!(target, DeserializationTarget::Env)
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use snafu::Snafu;
 use std::fmt::{Debug, Display};
 
+use enclave_ffi_types::{DeserializationTarget, OcallFailure, OcallKind};
+
 use super::communication_error::CommunicationError;
 // use crate::backends::InsufficientGasLeft;
 use crate::ffi::FfiError;
@@ -94,6 +96,17 @@ pub enum VmError {
     GasDepletion,
     #[snafu(display("Must not call a writing storage function in this context."))]
     WriteAccessDenied { backtrace: snafu::Backtrace },
+    #[snafu(display("Enclave failed to execute ocall {}: {}", ocall, failure))]
+    OcallErr {
+        ocall: OcallKind,
+        failure: OcallFailure,
+        backtrace: snafu::Backtrace,
+    },
+    #[snafu(display("Enclave failed to deserialize {}", target))]
+    EnclaveParseErr {
+        target: DeserializationTarget,
+        backtrace: snafu::Backtrace,
+    },
 
     #[snafu(display("Enclave: {}", source))]
     EnclaveErr {
@@ -177,6 +190,14 @@ impl VmError {
     pub(crate) fn write_access_denied() -> Self {
         WriteAccessDenied {}.build()
     }
+
+    pub(crate) fn ocall_err(ocall: OcallKind, failure: OcallFailure) -> Self {
+        OcallErr { ocall, failure }.build()
+    }
+
+    pub(crate) fn enclave_parse_err(target: DeserializationTarget) -> Self {
+        EnclaveParseErr { target }.build()
+    }
 }
 
 impl From<CommunicationError> for VmError {
@@ -397,4 +418,27 @@ mod test {
             e => panic!("Unexpected error: {:?}", e),
         }
     }
+
+    #[test]
+    fn ocall_err_works() {
+        let error = VmError::ocall_err(OcallKind::ReadDb, OcallFailure::HostPanic);
+        match error {
+            VmError::OcallErr { ocall, failure, .. } => {
+                assert_eq!(ocall, OcallKind::ReadDb);
+                assert_eq!(failure, OcallFailure::HostPanic);
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn enclave_parse_err_works() {
+        let error = VmError::enclave_parse_err(DeserializationTarget::SigInfo);
+        match error {
+            VmError::EnclaveParseErr { target, .. } => {
+                assert_eq!(target, DeserializationTarget::SigInfo)
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+    }
 }
@@ -35,10 +35,15 @@ use crate::imports::{
 use crate::imports::{do_next, do_scan};
 use crate::memory::{get_memory_info, read_region, write_region};
 */
+#[cfg(feature = "trace")]
+use crate::trace::Recording;
+use crate::trace::TracedCall;
 use crate::traits::{Api, Extern, Querier, Storage};
 
 use crate::enclave::get_enclave;
 use crate::wasmi::Module;
+use cosmwasm_std::Binary;
+use enclave_ffi_types::GasProfile;
 
 /*
 const WASM_PAGE_SIZE: u64 = 64 * 1024;
@@ -66,6 +71,8 @@ pub struct Instance<S: Storage + 'static, A: Api + 'static, Q: Querier + 'static
     */
     inner: Module<S, Q>,
     pub api: A,
+    /// The breakdown of the gas used by the last handle or query call
+    gas_profile: GasProfile,
     /*
     pub required_features: HashSet<String>,
     */
@@ -197,6 +204,7 @@ where
         Instance {
             inner: module,
             api: deps.api,
+            gas_profile: GasProfile::default(),
             /*
             required_features,
             */
@@ -265,6 +273,12 @@ where
         }
     }
 
+    /// Returns a breakdown of the gas used by the last handle or query call.
+    /// This is only reported by enclaves built with the `gas-profiling` feature, and is empty otherwise.
+    pub fn gas_profile(&self) -> GasProfile {
+        self.gas_profile
+    }
+
     /// Returns the currently remaining gas.
     pub fn get_gas_used(&self) -> u64 {
         get_gas_used(&self.inner)
@@ -330,23 +344,173 @@ where
         Ok(function)
     }
 
+    /// Sets the gas schedule the enclave should meter this instance with.
+    /// An empty schedule means the enclave's default costs are used.
+    pub fn set_gas_schedule(&mut self, gas_schedule: Vec<u8>) {
+        self.inner.set_gas_schedule(gas_schedule);
+    }
+
     pub fn call_init(&mut self, env: &[u8], msg: &[u8], sig_info: &[u8]) -> VmResult<Vec<u8>> {
-        let init_result = self.inner.init(env, msg, sig_info)?;
-        Ok(init_result.into_output())
+        self.traced(
+            || TracedCall::Init {
+                env: Binary::from(env),
+                msg: Binary::from(msg),
+                sig_info: Binary::from(sig_info),
+                simulate: false,
+            },
+            |instance| {
+                let init_result = instance.inner.init(env, msg, sig_info)?;
+                Ok(init_result.into_output())
+            },
+        )
     }
 
     pub fn call_handle(&mut self, env: &[u8], msg: &[u8], sig_info: &[u8]) -> VmResult<Vec<u8>> {
-        let init_result = self.inner.handle(env, msg, sig_info)?;
-        Ok(init_result.into_output())
+        self.traced(
+            || TracedCall::Handle {
+                env: Binary::from(env),
+                msg: Binary::from(msg),
+                sig_info: Binary::from(sig_info),
+                simulate: false,
+            },
+            |instance| {
+                let handle_result = instance.inner.handle(env, msg, sig_info)?;
+                instance.gas_profile = handle_result.gas_profile();
+                Ok(handle_result.into_output())
+            },
+        )
+    }
+
+    pub fn call_simulate_init(
+        &mut self,
+        env: &[u8],
+        msg: &[u8],
+        sig_info: &[u8],
+    ) -> VmResult<Vec<u8>> {
+        self.traced(
+            || TracedCall::Init {
+                env: Binary::from(env),
+                msg: Binary::from(msg),
+                sig_info: Binary::from(sig_info),
+                simulate: true,
+            },
+            |instance| {
+                let init_result = instance.inner.simulate_init(env, msg, sig_info)?;
+                Ok(init_result.into_output())
+            },
+        )
+    }
+
+    pub fn call_simulate_handle(
+        &mut self,
+        env: &[u8],
+        msg: &[u8],
+        sig_info: &[u8],
+    ) -> VmResult<Vec<u8>> {
+        self.traced(
+            || TracedCall::Handle {
+                env: Binary::from(env),
+                msg: Binary::from(msg),
+                sig_info: Binary::from(sig_info),
+                simulate: true,
+            },
+            |instance| {
+                let handle_result = instance.inner.simulate_handle(env, msg, sig_info)?;
+                instance.gas_profile = handle_result.gas_profile();
+                Ok(handle_result.into_output())
+            },
+        )
     }
 
-    pub fn call_migrate(&mut self, _env: &[u8], _msg: &[u8]) -> VmResult<Vec<u8>> {
-        Ok(Vec::new())
+    /// Returns the output of the contract, and the new proofs of the contract key and of its admin
+    pub fn call_migrate(
+        &mut self,
+        env: &[u8],
+        msg: &[u8],
+        sig_info: &[u8],
+    ) -> VmResult<(Vec<u8>, [u8; 32], [u8; 32])> {
+        let mut key_proof = [0u8; 32];
+        let mut admin_proof = [0u8; 32];
+        let output = self.traced(
+            || TracedCall::Migrate {
+                env: Binary::from(env),
+                msg: Binary::from(msg),
+                sig_info: Binary::from(sig_info),
+            },
+            |instance| {
+                let (output, new_key_proof, new_admin_proof) =
+                    instance.inner.migrate(env, msg, sig_info)?.into_parts();
+                key_proof = new_key_proof;
+                admin_proof = new_admin_proof;
+                Ok(output)
+            },
+        )?;
+        Ok((output, key_proof, admin_proof))
     }
 
     pub fn call_query(&mut self, msg: &[u8]) -> VmResult<Vec<u8>> {
-        let init_result = self.inner.query(msg)?;
-        Ok(init_result.into_output())
+        self.traced(
+            || TracedCall::Query {
+                msg: Binary::from(msg),
+            },
+            |instance| {
+                let query_result = instance.inner.query(msg)?;
+                instance.gas_profile = query_result.gas_profile();
+                Ok(query_result.into_output())
+            },
+        )
+    }
+
+    /// Records the execution if tracing is enabled, see the `trace` module
+    #[cfg(feature = "trace")]
+    fn traced<C, F>(&mut self, call: C, execute: F) -> VmResult<Vec<u8>>
+    where
+        C: FnOnce() -> TracedCall,
+        F: FnOnce(&mut Self) -> VmResult<Vec<u8>>,
+    {
+        let recording = match Recording::start() {
+            Some(recording) => recording,
+            None => return execute(self),
+        };
+
+        let gas_used_before = self.inner.gas_used();
+        let output = execute(self);
+        recording.finish(
+            self.inner.bytecode(),
+            self.inner.gas_limit(),
+            self.inner.gas_schedule(),
+            call(),
+            self.inner.gas_used().saturating_sub(gas_used_before),
+            &output,
+        );
+        output
+    }
+
+    #[cfg(not(feature = "trace"))]
+    fn traced<C, F>(&mut self, _call: C, execute: F) -> VmResult<Vec<u8>>
+    where
+        C: FnOnce() -> TracedCall,
+        F: FnOnce(&mut Self) -> VmResult<Vec<u8>>,
+    {
+        execute(self)
+    }
+
+    /// Runs a query on each of the instances in a single call into the enclave.
+    /// The queries share `gas_limit`. Returns the output of each query, and the total gas used.
+    pub fn call_query_batch(
+        queries: &[(&Self, &[u8])],
+        gas_limit: u64,
+    ) -> VmResult<(Vec<VmResult<Vec<u8>>>, u64)> {
+        let modules: Vec<(&Module<S, Q>, &[u8])> = queries
+            .iter()
+            .map(|(instance, msg)| (&instance.inner, *msg))
+            .collect();
+        let (results, used_gas) = Module::query_batch(&modules, gas_limit)?;
+        let outputs = results
+            .into_iter()
+            .map(|result| result.map(|query_result| query_result.into_output()))
+            .collect();
+        Ok((outputs, used_gas))
     }
 }
 
@@ -908,3 +1072,122 @@ mod singlepass_test {
         assert_eq!(query_used, 32070);
     }
 }
+
+#[cfg(test)]
+#[cfg(feature = "mock-enclave")]
+mod mock_enclave_test {
+    use cosmwasm_std::{coins, HumanAddr};
+    use serde_json::{json, Value};
+    use sha2::{Digest, Sha256};
+    use wasmi_runtime_enclave::testing::{callback_signature, decrypt_output, encrypt_msg};
+
+    use crate::calls::{call_handle_raw, call_init_raw, call_query_raw};
+    use crate::errors::{EnclaveError, VmError};
+    use crate::testing::{mock_instance, mock_instance_with_balances};
+
+    static CONTRACT: &[u8] = include_bytes!("../testdata/contract.wasm");
+
+    // The runtime only accepts bech32 addresses
+    const CREATOR: &str = "secret1qyqszqgpqyqszqgpqyqszqgpqyqszqgpsk4hsq";
+    const VERIFIER: &str = "secret1qgpqyqszqgpqyqszqgpqyqszqgpqyqszpjnjmk";
+    const BENEFICIARY: &str = "secret1qvpsxqcrqvpsxqcrqvpsxqcrqvpsxqcrqzjn3h";
+    const CONTRACT_ADDR: &str = "secret1qszqgpqyqszqgpqyqszqgpqyqszqgpqyqj4kkt";
+
+    const NONCE: [u8; 32] = [1; 32];
+    const USER_PUBLIC_KEY: [u8; 32] = [2; 32];
+
+    fn encrypt(msg: &[u8]) -> Vec<u8> {
+        let code_hash = hex::encode(Sha256::digest(CONTRACT));
+        encrypt_msg(&code_hash, msg, NONCE, USER_PUBLIC_KEY).unwrap()
+    }
+
+    fn env(sender: &str, contract_key: Option<&[u8]>) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "block": { "height": 12345, "time": 1571797419, "chain_id": "native-test-chain" },
+            "message": { "sender": sender, "sent_funds": [] },
+            "contract": { "address": CONTRACT_ADDR },
+            "contract_key": contract_key.map(base64::encode),
+        }))
+        .unwrap()
+    }
+
+    /// Signs the message like a calling contract would, so no transaction has to be signed
+    fn sig_info(env: &[u8], msg: &[u8]) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "sign_bytes": "",
+            "signature": {
+                "pub_key": {
+                    "type": "tendermint/PubKeySecp256k1",
+                    "value": "Anm+Zn753LusVaBilc6HCwcCm/zbLc4o2VnygVsW+BeY",
+                },
+                "signature": "",
+            },
+            "callback_sig": base64::encode(callback_signature(env, msg).unwrap()),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn init_handle_and_query_roundtrip() {
+        let contract_addr = HumanAddr::from(CONTRACT_ADDR);
+        let mut instance =
+            mock_instance_with_balances(&CONTRACT, &[(&contract_addr, &coins(1000, "earth"))]);
+
+        let msg = encrypt(
+            json!({ "verifier": VERIFIER, "beneficiary": BENEFICIARY })
+                .to_string()
+                .as_bytes(),
+        );
+        let env_bytes = env(CREATOR, None);
+        let output =
+            call_init_raw(&mut instance, &env_bytes, &msg, &sig_info(&env_bytes, &msg)).unwrap();
+        let (contract_key, output) = output.split_at(64);
+        let (_key_proof, output) = output.split_at(32);
+        let (_admin_proof, output) = output.split_at(32);
+        let output: Value = serde_json::from_slice(output).unwrap();
+        assert_eq!(output["Ok"]["messages"], json!([]));
+
+        let msg = encrypt(br#"{"release":{}}"#);
+        let env_bytes = env(VERIFIER, Some(contract_key));
+        let output =
+            call_handle_raw(&mut instance, &env_bytes, &msg, &sig_info(&env_bytes, &msg)).unwrap();
+        let output: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(
+            output["Ok"]["messages"],
+            json!([{ "bank": { "send": {
+                "from_address": CONTRACT_ADDR,
+                "to_address": BENEFICIARY,
+                "amount": [{ "denom": "earth", "amount": "1000" }],
+            } } }])
+        );
+
+        let mut msg = contract_key.to_vec();
+        msg.extend(encrypt(br#"{"verifier":{}}"#));
+        let output = call_query_raw(&mut instance, &msg).unwrap();
+        let output: Value = serde_json::from_slice(&output).unwrap();
+        let encrypted = base64::decode(output["Ok"].as_str().unwrap()).unwrap();
+        let answer = decrypt_output(&encrypted, NONCE, USER_PUBLIC_KEY).unwrap();
+        assert_eq!(
+            base64::decode(answer).unwrap(),
+            json!({ "verifier": VERIFIER }).to_string().as_bytes()
+        );
+    }
+
+    #[test]
+    fn query_reaches_the_contract_runtime() {
+        let mut instance = mock_instance(&CONTRACT, &[]);
+
+        // The runtime rejects a query that isn't prefixed by the contract key
+        let res = call_query_raw(&mut instance, br#"{"verifier":{}}"#);
+        match res.unwrap_err() {
+            VmError::EnclaveErr {
+                source:
+                    EnclaveError::EnclaveErr {
+                        error: enclave_ffi_types::EnclaveError::FailedFunctionCall,
+                        ..
+                    },
+            } => {}
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+}
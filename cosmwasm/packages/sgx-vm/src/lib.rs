@@ -15,19 +15,34 @@ mod instance;
 // mod modules;
 mod serde;
 pub mod testing;
+mod trace;
 mod traits;
 
 // Secret Network specific modules
+#[cfg(not(feature = "mock-enclave"))]
 mod attestation;
 mod enclave;
+#[cfg(not(feature = "mock-enclave"))]
 mod seed;
 mod wasmi;
 
 #[cfg(feature = "enclave-tests")]
 pub mod enclave_tests;
 
-pub use crate::cache::CosmCache;
-pub use crate::calls::{call_handle_raw, call_init_raw, call_migrate_raw, call_query_raw};
+#[cfg(all(feature = "production", feature = "mock-enclave"))]
+compile_error!("The mock-enclave feature must not be enabled in production builds");
+
+#[cfg(all(feature = "enclave-tests", feature = "mock-enclave"))]
+compile_error!("The enclave tests must run in a real enclave");
+
+#[cfg(all(feature = "production", feature = "trace"))]
+compile_error!("The trace feature must not be enabled in production builds");
+
+pub use crate::cache::{CosmCache, Stats};
+pub use crate::calls::{
+    call_handle_raw, call_init_raw, call_migrate_raw, call_query_batch_raw, call_query_raw,
+    call_simulate_handle_raw, call_simulate_init_raw,
+};
 pub use crate::checksum::Checksum;
 pub use crate::errors::{
     CommunicationError, CommunicationResult, RegionValidationError, RegionValidationResult,
@@ -40,13 +55,27 @@ pub use crate::instance::{GasReport, Instance};
 pub use crate::modules::FileSystemCache;
 */
 pub use crate::serde::{from_slice, to_vec};
+pub use crate::trace::{ExecutionTrace, TraceEvent, TracedCall};
 pub use crate::traits::{Api, Extern, Querier, Storage};
+pub use crate::wasmi::reissue_contract_proofs;
+pub use enclave_ffi_types::GasProfile;
 
 #[cfg(feature = "iterator")]
 pub use crate::traits::StorageIterator;
 
-// Secret Network specific exports
-pub use crate::attestation::{create_attestation_report_u, untrusted_get_encrypted_seed};
+#[cfg(feature = "trace")]
+pub use crate::trace::TRACE_DIR_ENV;
+
+// Secret Network specific exports. The mock enclave only runs contracts
+#[cfg(not(feature = "mock-enclave"))]
+pub use crate::attestation::{
+    create_attestation_report_dcap_u, create_attestation_report_u,
+    untrusted_export_seed_for_upgrade, untrusted_get_encrypted_seed,
+    untrusted_get_encrypted_seed_for_epoch,
+};
+#[cfg(not(feature = "mock-enclave"))]
 pub use crate::seed::{
-    untrusted_health_check, untrusted_init_bootstrap, untrusted_init_node, untrusted_key_gen,
+    untrusted_add_consensus_seed_epoch, untrusted_health_check, untrusted_import_seed_for_upgrade,
+    untrusted_init_bootstrap, untrusted_init_node, untrusted_key_gen,
+    untrusted_rotate_consensus_seed,
 };
@@ -13,6 +13,10 @@ extern "C" {
         master_cert_len: u32,
         encrypted_seed: *const u8,
         encrypted_seed_len: u32,
+        chain_id: *const u8,
+        chain_id_len: u32,
+        policy: *const u8,
+        policy_len: u32,
     ) -> sgx_status_t;
 
     pub fn ecall_init_bootstrap(
@@ -23,6 +27,45 @@ extern "C" {
         spid_len: u32,
         api_key: *const u8,
         api_key_len: u32,
+        chain_id: *const u8,
+        chain_id_len: u32,
+        policy: *const u8,
+        policy_len: u32,
+    ) -> sgx_status_t;
+
+    pub fn ecall_rotate_consensus_seed(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        public_key: &mut [u8; 32],
+        spid: *const u8,
+        spid_len: u32,
+        api_key: *const u8,
+        api_key_len: u32,
+    ) -> sgx_status_t;
+
+    pub fn ecall_add_consensus_seed_epoch(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        master_cert: *const u8,
+        master_cert_len: u32,
+        epoch: u32,
+        encrypted_seed: *const u8,
+        encrypted_seed_len: u32,
+    ) -> sgx_status_t;
+
+    pub fn ecall_import_seed_for_upgrade(
+        eid: sgx_enclave_id_t,
+        retval: *mut sgx_status_t,
+        master_cert: *const u8,
+        master_cert_len: u32,
+        policy: *const u8,
+        policy_len: u32,
+        block_time: u64,
+        epoch: u32,
+        encrypted_seed: *const u8,
+        encrypted_seed_len: u32,
+        chain_id: *const u8,
+        chain_id_len: u32,
     ) -> sgx_status_t;
 
     pub fn ecall_key_gen(
@@ -55,7 +98,12 @@ pub fn untrusted_health_check() -> SgxResult<HealthCheckResult> {
     Ok(ret)
 }
 
-pub fn untrusted_init_node(master_cert: &[u8], encrypted_seed: &[u8]) -> SgxResult<()> {
+pub fn untrusted_init_node(
+    master_cert: &[u8],
+    encrypted_seed: &[u8],
+    chain_id: &[u8],
+    policy: &[u8],
+) -> SgxResult<()> {
     info!("Initializing enclave..");
     let enclave = get_enclave()?;
     info!("Initialized enclave successfully!");
@@ -71,6 +119,10 @@ pub fn untrusted_init_node(master_cert: &[u8], encrypted_seed: &[u8]) -> SgxResu
             master_cert.len() as u32,
             encrypted_seed.as_ptr(),
             encrypted_seed.len() as u32,
+            chain_id.as_ptr(),
+            chain_id.len() as u32,
+            policy.as_ptr(),
+            policy.len() as u32,
         )
     };
 
@@ -107,7 +159,12 @@ pub fn untrusted_key_gen() -> SgxResult<[u8; 32]> {
     Ok(public_key)
 }
 
-pub fn untrusted_init_bootstrap(spid: &[u8], api_key: &[u8]) -> SgxResult<[u8; 32]> {
+pub fn untrusted_init_bootstrap(
+    spid: &[u8],
+    api_key: &[u8],
+    chain_id: &[u8],
+    policy: &[u8],
+) -> SgxResult<[u8; 32]> {
     info!("Hello from just before initializing - untrusted_init_bootstrap");
     let enclave = get_enclave()?;
     info!("Hello from just after initializing - untrusted_init_bootstrap");
@@ -125,6 +182,40 @@ pub fn untrusted_init_bootstrap(spid: &[u8], api_key: &[u8]) -> SgxResult<[u8; 3
             spid.len() as u32,
             api_key.as_ptr(),
             api_key.len() as u32,
+            chain_id.as_ptr(),
+            chain_id.len() as u32,
+            policy.as_ptr(),
+            policy.len() as u32,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if retval != sgx_status_t::SGX_SUCCESS {
+        return Err(retval);
+    }
+
+    Ok(public_key)
+}
+
+/// Creates the seed of a new epoch, and returns the seed exchange public key of the epoch
+pub fn untrusted_rotate_consensus_seed(spid: &[u8], api_key: &[u8]) -> SgxResult<[u8; 32]> {
+    let enclave = get_enclave()?;
+
+    let eid = enclave.geteid();
+    let mut retval = sgx_status_t::SGX_SUCCESS;
+    let mut public_key = [0u8; 32];
+    let status = unsafe {
+        ecall_rotate_consensus_seed(
+            eid,
+            &mut retval,
+            &mut public_key,
+            spid.as_ptr(),
+            spid.len() as u32,
+            api_key.as_ptr(),
+            api_key.len() as u32,
         )
     };
 
@@ -138,3 +229,79 @@ pub fn untrusted_init_bootstrap(spid: &[u8], api_key: &[u8]) -> SgxResult<[u8; 3
 
     Ok(public_key)
 }
+
+pub fn untrusted_add_consensus_seed_epoch(
+    master_cert: &[u8],
+    epoch: u32,
+    encrypted_seed: &[u8],
+) -> SgxResult<()> {
+    let enclave = get_enclave()?;
+
+    let eid = enclave.geteid();
+    let mut ret = sgx_status_t::SGX_SUCCESS;
+
+    let status = unsafe {
+        ecall_add_consensus_seed_epoch(
+            eid,
+            &mut ret,
+            master_cert.as_ptr(),
+            master_cert.len() as u32,
+            epoch,
+            encrypted_seed.as_ptr(),
+            encrypted_seed.len() as u32,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+
+    Ok(())
+}
+
+/// Loads a seed that the previous enclave exported with `untrusted_export_seed_for_upgrade`.
+/// `master_cert` is verified with `policy`, and epochs must be imported in order.
+pub fn untrusted_import_seed_for_upgrade(
+    master_cert: &[u8],
+    policy: &[u8],
+    block_time: u64,
+    epoch: u32,
+    encrypted_seed: &[u8],
+    chain_id: &[u8],
+) -> SgxResult<()> {
+    let enclave = get_enclave()?;
+
+    let eid = enclave.geteid();
+    let mut ret = sgx_status_t::SGX_SUCCESS;
+
+    let status = unsafe {
+        ecall_import_seed_for_upgrade(
+            eid,
+            &mut ret,
+            master_cert.as_ptr(),
+            master_cert.len() as u32,
+            policy.as_ptr(),
+            policy.len() as u32,
+            block_time,
+            epoch,
+            encrypted_seed.as_ptr(),
+            encrypted_seed.len() as u32,
+            chain_id.as_ptr(),
+            chain_id.len() as u32,
+        )
+    };
+
+    if status != sgx_status_t::SGX_SUCCESS {
+        return Err(status);
+    }
+
+    if ret != sgx_status_t::SGX_SUCCESS {
+        return Err(ret);
+    }
+
+    Ok(())
+}
@@ -4,6 +4,7 @@ mod calls;
 mod instance;
 mod mock;
 mod querier;
+mod replay;
 mod storage;
 
 /*
@@ -17,6 +18,7 @@ pub use mock::{
     mock_dependencies, mock_dependencies_with_balances, mock_env, MockApi, MOCK_CONTRACT_ADDR,
 };
 pub use querier::MockQuerier;
+pub use replay::{replay, replay_backend, ReplayQuerier, ReplayStorage};
 #[cfg(feature = "iterator")]
 pub use storage::MockIterator;
 pub use storage::MockStorage;
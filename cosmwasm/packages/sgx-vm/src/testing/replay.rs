@@ -0,0 +1,383 @@
+//! Replays execution traces, which are recorded with the `trace` feature.
+//!
+//! The storage and querier of a replay don't hold any state. They answer every request of the
+//! enclave with the next recorded event, so the contract sees exactly what it saw when the trace
+//! was recorded. A request that doesn't match the recorded one fails the replay.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[cfg(feature = "iterator")]
+use cosmwasm_std::Order;
+use cosmwasm_std::{Binary, StdResult, SystemResult};
+
+use crate::calls::{
+    call_handle_raw, call_init_raw, call_migrate_raw, call_query_raw, call_simulate_handle_raw,
+    call_simulate_init_raw,
+};
+use crate::checksum::Checksum;
+use crate::errors::{VmError, VmResult};
+use crate::ffi::{FfiError, FfiResult, GasInfo};
+use crate::instance::Instance;
+use crate::trace::{ExecutionTrace, TraceEvent, TracedCall};
+#[cfg(feature = "iterator")]
+use crate::traits::StorageIterator;
+use crate::traits::{Extern, Querier, Storage};
+
+use super::mock::MockApi;
+
+const CANONICAL_LENGTH: usize = 20;
+
+/// The events of a trace that were not replayed yet
+#[derive(Debug)]
+struct Events {
+    remaining: VecDeque<TraceEvent>,
+    replayed: usize,
+}
+
+type SharedEvents = Rc<RefCell<Events>>;
+
+fn shared_events(events: Vec<TraceEvent>) -> SharedEvents {
+    Rc::new(RefCell::new(Events {
+        remaining: events.into(),
+        replayed: 0,
+    }))
+}
+
+/// Takes the next event, if `matches` accepts it. `request` describes what the enclave asked for.
+/// Both are shown in the encoding of trace files, so a divergence can be looked up in the trace.
+fn next_event<F>(events: &SharedEvents, request: &str, matches: F) -> Result<TraceEvent, FfiError>
+where
+    F: FnOnce(&TraceEvent) -> bool,
+{
+    let mut events = events.borrow_mut();
+    let index = events.replayed;
+    match events.remaining.front() {
+        Some(event) if matches(event) => {
+            events.replayed += 1;
+            Ok(events.remaining.pop_front().unwrap())
+        }
+        Some(event) => Err(FfiError::unknown(format!(
+            "Replay diverged at event {}: expected {}, got {}",
+            index,
+            serde_json::to_string(event).unwrap_or_default(),
+            request
+        ))),
+        None => Err(FfiError::unknown(format!(
+            "Replay diverged at event {}: the trace ended, got {}",
+            index, request
+        ))),
+    }
+}
+
+fn unit_result(result: Result<(), String>, gas_used: u64) -> FfiResult<()> {
+    (
+        result.map_err(FfiError::unknown),
+        GasInfo::with_externally_used(gas_used),
+    )
+}
+
+/// A storage that answers with the storage events of a trace
+#[derive(Debug)]
+pub struct ReplayStorage {
+    events: SharedEvents,
+}
+
+impl Storage for ReplayStorage {
+    fn get(&self, key: &[u8]) -> FfiResult<Option<Vec<u8>>> {
+        let request = format!("a read of {}", Binary::from(key));
+        match next_event(
+            &self.events,
+            &request,
+            |event| matches!(event, TraceEvent::Get { key: k, .. } if k.as_slice() == key),
+        ) {
+            Ok(TraceEvent::Get {
+                result, gas_used, ..
+            }) => (
+                result
+                    .map(|value| value.map(Into::into))
+                    .map_err(FfiError::unknown),
+                GasInfo::with_externally_used(gas_used),
+            ),
+            Ok(_) => unreachable!(),
+            Err(err) => (Err(err), GasInfo::free()),
+        }
+    }
+
+    /// The enclave keeps its own index of the keys, so it never scans the storage of the host
+    #[cfg(feature = "iterator")]
+    fn range<'a>(
+        &'a self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> FfiResult<Box<dyn StorageIterator + 'a>> {
+        (
+            Err(FfiError::unknown(format!(
+                "Replay got a scan from {} to {} in {:?} order, which the enclave never makes",
+                Binary::from(start.unwrap_or_default()),
+                Binary::from(end.unwrap_or_default()),
+                order
+            ))),
+            GasInfo::free(),
+        )
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) -> FfiResult<()> {
+        let request = format!(
+            "a write of {} to {}",
+            Binary::from(value),
+            Binary::from(key)
+        );
+        match next_event(&self.events, &request, |event| {
+            matches!(event, TraceEvent::Set { key: k, value: v, .. }
+                if k.as_slice() == key && v.as_slice() == value)
+        }) {
+            Ok(TraceEvent::Set {
+                result, gas_used, ..
+            }) => unit_result(result, gas_used),
+            Ok(_) => unreachable!(),
+            Err(err) => (Err(err), GasInfo::free()),
+        }
+    }
+
+    fn remove(&mut self, key: &[u8]) -> FfiResult<()> {
+        let request = format!("a removal of {}", Binary::from(key));
+        match next_event(
+            &self.events,
+            &request,
+            |event| matches!(event, TraceEvent::Remove { key: k, .. } if k.as_slice() == key),
+        ) {
+            Ok(TraceEvent::Remove {
+                result, gas_used, ..
+            }) => unit_result(result, gas_used),
+            Ok(_) => unreachable!(),
+            Err(err) => (Err(err), GasInfo::free()),
+        }
+    }
+}
+
+/// A querier that answers with the query events of a trace
+#[derive(Debug)]
+pub struct ReplayQuerier {
+    events: SharedEvents,
+}
+
+impl Querier for ReplayQuerier {
+    fn query_raw(
+        &self,
+        request: &[u8],
+        gas_limit: u64,
+    ) -> FfiResult<SystemResult<StdResult<Binary>>> {
+        let description = format!(
+            "the query {} with a gas limit of {}",
+            Binary::from(request),
+            gas_limit
+        );
+        match next_event(&self.events, &description, |event| {
+            matches!(event, TraceEvent::Query { request: r, gas_limit: l, .. }
+                if r.as_slice() == request && *l == gas_limit)
+        }) {
+            Ok(TraceEvent::Query {
+                result, gas_used, ..
+            }) => {
+                let response = result.map_err(FfiError::unknown).and_then(|response| {
+                    crate::serde::from_slice(response.as_slice())
+                        .map_err(|err| FfiError::unknown(err.to_string()))
+                });
+                (response, GasInfo::with_externally_used(gas_used))
+            }
+            Ok(_) => unreachable!(),
+            Err(err) => (Err(err), GasInfo::free()),
+        }
+    }
+}
+
+/// Creates a storage and a querier that replay `events` together, in order.
+pub fn replay_backend(events: Vec<TraceEvent>) -> (ReplayStorage, ReplayQuerier) {
+    let events = shared_events(events);
+    (
+        ReplayStorage {
+            events: events.clone(),
+        },
+        ReplayQuerier { events },
+    )
+}
+
+/// Runs the recorded call of `trace` on `wasm` again, and returns its output.
+///
+/// This fails if `wasm` is not the code the trace was recorded with, if the execution asks the
+/// host for anything else than what was recorded, or if it doesn't ask for everything that was.
+/// The output is returned even if it differs from the recorded one, so it can be compared.
+pub fn replay(wasm: &[u8], trace: &ExecutionTrace) -> VmResult<Vec<u8>> {
+    let checksum = Checksum::generate(wasm).to_hex();
+    if checksum != trace.code_checksum {
+        return Err(VmError::generic_err(format!(
+            "The trace was recorded with the code {}, not {}",
+            trace.code_checksum, checksum
+        )));
+    }
+
+    let (storage, querier) = replay_backend(trace.events.clone());
+    let events = storage.events.clone();
+    let deps = Extern {
+        storage,
+        api: MockApi::new(CANONICAL_LENGTH),
+        querier,
+    };
+    let mut instance = Instance::from_code(wasm, deps, trace.gas_limit)?;
+    instance.set_gas_schedule(trace.gas_schedule.as_slice().to_vec());
+
+    let output = match &trace.call {
+        TracedCall::Init {
+            env,
+            msg,
+            sig_info,
+            simulate,
+        } => {
+            let (env, msg, sig_info) = (env.as_slice(), msg.as_slice(), sig_info.as_slice());
+            if *simulate {
+                call_simulate_init_raw(&mut instance, env, msg, sig_info)
+            } else {
+                call_init_raw(&mut instance, env, msg, sig_info)
+            }
+        }
+        TracedCall::Handle {
+            env,
+            msg,
+            sig_info,
+            simulate,
+        } => {
+            let (env, msg, sig_info) = (env.as_slice(), msg.as_slice(), sig_info.as_slice());
+            if *simulate {
+                call_simulate_handle_raw(&mut instance, env, msg, sig_info)
+            } else {
+                call_handle_raw(&mut instance, env, msg, sig_info)
+            }
+        }
+        TracedCall::Migrate { env, msg, sig_info } => call_migrate_raw(
+            &mut instance,
+            env.as_slice(),
+            msg.as_slice(),
+            sig_info.as_slice(),
+        )
+        .map(|(output, _key_proof, _admin_proof)| output),
+        TracedCall::Query { msg } => call_query_raw(&mut instance, msg.as_slice()),
+    }?;
+
+    let events = events.borrow();
+    if !events.remaining.is_empty() {
+        return Err(VmError::generic_err(format!(
+            "Replay diverged at event {}: the execution ended, expected {}",
+            events.replayed,
+            serde_json::to_string(&events.remaining[0]).unwrap_or_default()
+        )));
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn events() -> Vec<TraceEvent> {
+        vec![
+            TraceEvent::Get {
+                key: Binary::from(b"config"),
+                result: Ok(Some(Binary::from(b"{}"))),
+                gas_used: 10,
+            },
+            TraceEvent::Set {
+                key: Binary::from(b"count"),
+                value: Binary::from(b"1"),
+                result: Ok(()),
+                gas_used: 20,
+            },
+            TraceEvent::Remove {
+                key: Binary::from(b"old"),
+                result: Err("disk full".to_string()),
+                gas_used: 30,
+            },
+        ]
+    }
+
+    #[test]
+    fn storage_answers_with_the_recorded_events() {
+        let (mut storage, _querier) = replay_backend(events());
+
+        let (result, gas_info) = storage.get(b"config");
+        assert_eq!(result.unwrap(), Some(b"{}".to_vec()));
+        assert_eq!(gas_info.externally_used, 10);
+
+        let (result, gas_info) = storage.set(b"count", b"1");
+        result.unwrap();
+        assert_eq!(gas_info.externally_used, 20);
+
+        let (result, gas_info) = storage.remove(b"old");
+        match result.unwrap_err() {
+            FfiError::Unknown { msg, .. } => assert_eq!(msg.unwrap(), "disk full"),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        assert_eq!(gas_info.externally_used, 30);
+    }
+
+    #[test]
+    fn storage_fails_on_divergence() {
+        let (mut storage, _querier) = replay_backend(events());
+
+        storage.get(b"config").0.unwrap();
+        // The value differs from the recorded one
+        let (result, gas_info) = storage.set(b"count", b"2");
+        match result.unwrap_err() {
+            FfiError::Unknown { msg, .. } => assert!(msg.unwrap().starts_with(
+                "Replay diverged at event 1: expected {\"set\":{\"key\":\"Y291bnQ=\""
+            )),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+        assert_eq!(gas_info.externally_used, 0);
+
+        // The divergent request doesn't use up the event
+        storage.set(b"count", b"1").0.unwrap();
+        storage.remove(b"old").0.unwrap_err();
+        match storage.get(b"config").0.unwrap_err() {
+            FfiError::Unknown { msg, .. } => assert_eq!(
+                msg.unwrap(),
+                "Replay diverged at event 3: the trace ended, got a read of Y29uZmln"
+            ),
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn querier_answers_with_the_recorded_response() {
+        let request = br#"{"wasm":{"raw":{"contract_addr":"contract","key":"Y291bnQ="}}}"#;
+        let response: SystemResult<StdResult<Binary>> = Ok(Ok(Binary::from(b"1")));
+        let (storage, querier) = replay_backend(vec![TraceEvent::Query {
+            request: Binary::from(&request[..]),
+            gas_limit: 1000,
+            result: Ok(Binary::from(crate::serde::to_vec(&response).unwrap())),
+            gas_used: 40,
+        }]);
+
+        // Storage and querier replay the same events
+        storage.get(b"count").0.unwrap_err();
+        querier.query_raw(request, 999).0.unwrap_err();
+
+        let (result, gas_info) = querier.query_raw(request, 1000);
+        assert_eq!(result.unwrap(), response);
+        assert_eq!(gas_info.externally_used, 40);
+    }
+
+    #[test]
+    #[cfg(feature = "iterator")]
+    fn storage_rejects_scans() {
+        let (storage, _querier) = replay_backend(vec![]);
+
+        assert!(storage
+            .range(Some(b"a"), Some(b"c"), Order::Ascending)
+            .0
+            .is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Execution traces, which let executions be replayed offline with `testing::replay`.
+//!
+//! With the `trace` feature, every init, handle, migrate and query writes a trace to the directory
+//! in `SECRET_TRACE_DIR`, if it's set. A trace holds the inputs of the call, and every answer the
+//! host gave the enclave during it, in order. Replaying those answers runs the contract exactly as
+//! it ran on-chain.
+//!
+//! Traces hold nothing the host doesn't see anyway. Messages, queries and storage are encrypted by
+//! the enclave, so they are recorded as ciphertext. For the same reason, a trace can only be
+//! replayed by an enclave that has the same seed as the one that recorded it.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Binary;
+
+use crate::errors::{VmError, VmResult};
+
+#[cfg(feature = "trace")]
+mod recorder;
+
+#[cfg(feature = "trace")]
+pub use recorder::TRACE_DIR_ENV;
+#[cfg(feature = "trace")]
+pub(crate) use recorder::{record, Recording};
+
+/// The call into the enclave that a trace starts with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TracedCall {
+    Init {
+        env: Binary,
+        msg: Binary,
+        sig_info: Binary,
+        simulate: bool,
+    },
+    Handle {
+        env: Binary,
+        msg: Binary,
+        sig_info: Binary,
+        simulate: bool,
+    },
+    Migrate {
+        env: Binary,
+        msg: Binary,
+        sig_info: Binary,
+    },
+    Query {
+        msg: Binary,
+    },
+}
+
+/// A request of the enclave, and the answer of the host.
+/// Errors of the host are only kept as their message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceEvent {
+    Get {
+        key: Binary,
+        result: Result<Option<Binary>, String>,
+        gas_used: u64,
+    },
+    Set {
+        key: Binary,
+        value: Binary,
+        result: Result<(), String>,
+        gas_used: u64,
+    },
+    Remove {
+        key: Binary,
+        result: Result<(), String>,
+        gas_used: u64,
+    },
+    /// The response is kept serialized, as the enclave gets it
+    Query {
+        request: Binary,
+        gas_limit: u64,
+        result: Result<Binary, String>,
+        gas_used: u64,
+    },
+}
+
+/// Everything needed to replay an execution
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ExecutionTrace {
+    /// The checksum of the contract code, in hex
+    pub code_checksum: String,
+    pub gas_limit: u64,
+    /// The serialized gas schedule, empty for the enclave's defaults
+    pub gas_schedule: Binary,
+    pub call: TracedCall,
+    pub events: Vec<TraceEvent>,
+    pub gas_used: u64,
+    pub output: Result<Binary, String>,
+}
+
+impl ExecutionTrace {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> VmResult<Self> {
+        let data = fs::read(path.as_ref()).map_err(|err| {
+            VmError::generic_err(format!(
+                "Failed to read the trace {}: {}",
+                path.as_ref().display(),
+                err
+            ))
+        })?;
+        crate::serde::from_slice(&data)
+    }
+}
@@ -0,0 +1,242 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::*;
+
+use cosmwasm_std::{Binary, StdResult, SystemResult};
+
+use crate::checksum::Checksum;
+use crate::errors::{VmError, VmResult};
+use crate::ffi::FfiResult;
+
+use super::{ExecutionTrace, TraceEvent, TracedCall};
+
+/// The directory traces are written to. Nothing is recorded when it's not set.
+pub const TRACE_DIR_ENV: &str = "SECRET_TRACE_DIR";
+
+thread_local! {
+    /// The events of every execution that is being recorded on this thread, innermost last.
+    /// Executions nest when a contract queries another contract.
+    static RECORDINGS: RefCell<Vec<Vec<TraceEvent>>> = RefCell::new(Vec::new());
+}
+
+/// Tells traces that were written in the same millisecond apart
+static TRACE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+impl TraceEvent {
+    pub(crate) fn get(key: &[u8], result: &FfiResult<Option<Vec<u8>>>) -> Self {
+        TraceEvent::Get {
+            key: Binary::from(key),
+            result: copy_result(&result.0, |value| value.as_deref().map(Binary::from)),
+            gas_used: result.1.externally_used,
+        }
+    }
+
+    pub(crate) fn set(key: &[u8], value: &[u8], result: &FfiResult<()>) -> Self {
+        TraceEvent::Set {
+            key: Binary::from(key),
+            value: Binary::from(value),
+            result: copy_result(&result.0, |_| ()),
+            gas_used: result.1.externally_used,
+        }
+    }
+
+    pub(crate) fn remove(key: &[u8], result: &FfiResult<()>) -> Self {
+        TraceEvent::Remove {
+            key: Binary::from(key),
+            result: copy_result(&result.0, |_| ()),
+            gas_used: result.1.externally_used,
+        }
+    }
+
+    pub(crate) fn query(
+        request: &[u8],
+        gas_limit: u64,
+        result: &FfiResult<SystemResult<StdResult<Binary>>>,
+    ) -> Self {
+        TraceEvent::Query {
+            request: Binary::from(request),
+            gas_limit,
+            result: match &result.0 {
+                Ok(response) => crate::serde::to_vec(response)
+                    .map(Binary::from)
+                    .map_err(|err| err.to_string()),
+                Err(err) => Err(err.to_string()),
+            },
+            gas_used: result.1.externally_used,
+        }
+    }
+}
+
+fn copy_result<T, U, E, F>(result: &Result<T, E>, copy: F) -> Result<U, String>
+where
+    E: ToString,
+    F: FnOnce(&T) -> U,
+{
+    result.as_ref().map(copy).map_err(ToString::to_string)
+}
+
+/// Adds an event to the execution that is being recorded on this thread, if any.
+/// The event is only created when it's recorded.
+pub(crate) fn record<F: FnOnce() -> TraceEvent>(event: F) {
+    RECORDINGS.with(|recordings| {
+        if let Some(events) = recordings.borrow_mut().last_mut() {
+            events.push(event());
+        }
+    });
+}
+
+/// An execution that is being recorded. It's written to the trace directory when it's finished.
+pub(crate) struct Recording {
+    dir: PathBuf,
+}
+
+impl Recording {
+    /// Starts recording the events of an execution, if `SECRET_TRACE_DIR` is set
+    pub(crate) fn start() -> Option<Self> {
+        let dir = std::env::var_os(TRACE_DIR_ENV)?;
+        RECORDINGS.with(|recordings| recordings.borrow_mut().push(Vec::new()));
+        Some(Recording {
+            dir: PathBuf::from(dir),
+        })
+    }
+
+    pub(crate) fn finish(
+        self,
+        code: &[u8],
+        gas_limit: u64,
+        gas_schedule: &[u8],
+        call: TracedCall,
+        gas_used: u64,
+        output: &VmResult<Vec<u8>>,
+    ) {
+        let events = RECORDINGS
+            .with(|recordings| recordings.borrow_mut().pop())
+            .unwrap_or_default();
+
+        let trace = ExecutionTrace {
+            code_checksum: Checksum::generate(code).to_hex(),
+            gas_limit,
+            gas_schedule: Binary::from(gas_schedule),
+            call,
+            events,
+            gas_used,
+            output: copy_result(output, |output| Binary::from(output.as_slice())),
+        };
+
+        if let Err(err) = write_trace(&self.dir, &trace) {
+            warn!("Failed to write the execution trace: {}", err);
+        }
+    }
+}
+
+fn write_trace(dir: &Path, trace: &ExecutionTrace) -> VmResult<()> {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    let counter = TRACE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let call = match trace.call {
+        TracedCall::Init { .. } => "init",
+        TracedCall::Handle { .. } => "handle",
+        TracedCall::Migrate { .. } => "migrate",
+        TracedCall::Query { .. } => "query",
+    };
+    let path = dir.join(format!(
+        "{}-{}-{}-{}.json",
+        millis,
+        counter,
+        &trace.code_checksum[..16],
+        call
+    ));
+
+    let data = crate::serde::to_vec(trace)?;
+    fs::create_dir_all(dir)
+        .and_then(|_| fs::write(&path, data))
+        .map_err(|err| VmError::generic_err(format!("{}: {}", path.display(), err)))?;
+    debug!("Wrote the execution trace {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::{FfiError, GasInfo};
+
+    fn recorded_events<F: FnOnce()>(run: F) -> Vec<TraceEvent> {
+        RECORDINGS.with(|recordings| recordings.borrow_mut().push(Vec::new()));
+        run();
+        RECORDINGS
+            .with(|recordings| recordings.borrow_mut().pop())
+            .unwrap()
+    }
+
+    fn get(key: &[u8]) -> TraceEvent {
+        TraceEvent::get(key, &(Ok(None), GasInfo::with_externally_used(1)))
+    }
+
+    #[test]
+    fn record_is_ignored_when_not_recording() {
+        record(|| panic!("the event should not be created"));
+    }
+
+    #[test]
+    fn record_goes_to_the_innermost_execution() {
+        let mut inner = vec![];
+        let outer = recorded_events(|| {
+            record(|| get(b"outer"));
+            inner = recorded_events(|| record(|| get(b"inner")));
+        });
+
+        assert_eq!(outer, vec![get(b"outer")]);
+        assert_eq!(inner, vec![get(b"inner")]);
+    }
+
+    #[test]
+    fn events_keep_the_host_errors() {
+        let event = TraceEvent::set(
+            b"key",
+            b"value",
+            &(
+                Err(FfiError::unknown("no space left")),
+                GasInfo::with_externally_used(3),
+            ),
+        );
+        assert_eq!(
+            event,
+            TraceEvent::Set {
+                key: Binary::from(b"key"),
+                value: Binary::from(b"value"),
+                result: Err("Unknown error during FFI call: Some(\"no space left\")".to_string()),
+                gas_used: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn traces_round_trip_through_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let trace = ExecutionTrace {
+            code_checksum: Checksum::generate(b"code").to_hex(),
+            gas_limit: 1000,
+            gas_schedule: Binary::default(),
+            call: TracedCall::Query {
+                msg: Binary::from(b"{}"),
+            },
+            events: vec![get(b"key")],
+            gas_used: 600,
+            output: Err("out of gas".to_string()),
+        };
+
+        write_trace(dir.path(), &trace).unwrap();
+
+        let files: Vec<_> = fs::read_dir(dir.path()).unwrap().collect();
+        assert_eq!(files.len(), 1);
+        let path = files[0].as_ref().unwrap().path();
+        assert!(path.to_string_lossy().ends_with("-query.json"));
+        assert_eq!(ExecutionTrace::from_file(path).unwrap(), trace);
+    }
+}
@@ -1,7 +1,11 @@
-use enclave_ffi_types::{Ctx, EnclaveBuffer, OcallReturn, UntrustedVmError, UserSpaceBuffer};
+use enclave_ffi_types::{
+    Ctx, EnclaveBuffer, FlushGas, OcallReturn, UntrustedVmError, UserSpaceBuffer,
+};
 use std::ffi::c_void;
 
 use crate::context::{with_querier_from_context, with_storage_from_context};
+#[cfg(feature = "trace")]
+use crate::trace::{record, TraceEvent};
 use crate::{Querier, Storage, VmError, VmResult};
 use cosmwasm_std::{Binary, StdResult, SystemResult};
 
@@ -121,56 +125,20 @@ pub extern "C" fn ocall_query_chain(
         .unwrap_or(OcallReturn::Panic)
 }
 
-/// Remove a key from the contracts key-value store.
+/// Apply a batch of storage operations that the enclave buffered, in order.
 #[no_mangle]
-pub extern "C" fn ocall_remove_db(
+pub extern "C" fn ocall_flush_db(
     context: Ctx,
     vm_error: *mut UntrustedVmError,
-    gas_used: *mut u64,
-    key: *const u8,
-    key_len: usize,
-) -> OcallReturn {
-    let key = unsafe { std::slice::from_raw_parts(key, key_len) };
-
-    let implementation = unsafe { get_implementations_from_context(&context).remove_db };
-
-    // We explicitly ignore this potential panic here because we have no way of handling it at the moment.
-    // In the future, if we see that panics do occur here, we should add a way to report this to the enclave.
-    // TODO add logging if we fail to write
-    std::panic::catch_unwind(|| match implementation(context, key) {
-        Ok(gas_cost) => {
-            unsafe { *gas_used = gas_cost };
-            OcallReturn::Success
-        }
-        Err(err) => {
-            unsafe { store_vm_error(err, vm_error) };
-            OcallReturn::Failure
-        }
-    })
-    // This will happen only when `catch_unwind` returns `Err`, which indicates a caught panic
-    .unwrap_or(OcallReturn::Panic)
-}
-
-/// Write a value to the contracts key-value store.
-#[no_mangle]
-pub extern "C" fn ocall_write_db(
-    context: Ctx,
-    vm_error: *mut UntrustedVmError,
-    gas_used: *mut u64,
-    key: *const u8,
-    key_len: usize,
-    value: *const u8,
-    value_len: usize,
+    gas_used: *mut FlushGas,
+    ops: *const u8,
+    ops_len: usize,
 ) -> OcallReturn {
-    let key = unsafe { std::slice::from_raw_parts(key, key_len) };
-    let value = unsafe { std::slice::from_raw_parts(value, value_len) };
+    let ops = unsafe { std::slice::from_raw_parts(ops, ops_len) };
 
-    let implementation = unsafe { get_implementations_from_context(&context).write_db };
+    let implementation = unsafe { get_implementations_from_context(&context).flush_db };
 
-    // We explicitly ignore this potential panic here because we have no way of handling it at the moment.
-    // In the future, if we see that panics do occur here, we should add a way to report this to the enclave.
-    // TODO add logging if we fail to write
-    std::panic::catch_unwind(|| match implementation(context, key, value) {
+    std::panic::catch_unwind(|| match implementation(context, ops) {
         Ok(gas_cost) => {
             unsafe { *gas_used = gas_cost };
             OcallReturn::Success
@@ -207,8 +175,7 @@ struct ExportImplementations {
         query: &[u8],
         gas_limit: u64,
     ) -> VmResult<(SystemResult<StdResult<Binary>>, u64)>,
-    remove_db: fn(context: Ctx, key: &[u8]) -> VmResult<u64>,
-    write_db: fn(context: Ctx, key: &[u8], value: &[u8]) -> VmResult<u64>,
+    flush_db: fn(context: Ctx, ops: &[u8]) -> VmResult<FlushGas>,
 }
 
 impl ExportImplementations {
@@ -220,8 +187,7 @@ impl ExportImplementations {
         Self {
             read_db: ocall_read_db_impl::<S, Q>,
             query_chain: ocall_query_chain_impl::<S, Q>,
-            remove_db: ocall_remove_db_impl::<S, Q>,
-            write_db: ocall_write_db_impl::<S, Q>,
+            flush_db: ocall_flush_db_impl::<S, Q>,
         }
     }
 }
@@ -260,7 +226,10 @@ where
     Q: Querier,
 {
     with_storage_from_context::<S, Q, _, _>(&mut context, |storage: &mut S| {
-        let (ffi_result, gas_info) = storage.get(key);
+        let result = storage.get(key);
+        #[cfg(feature = "trace")]
+        record(|| TraceEvent::get(key, &result));
+        let (ffi_result, gas_info) = result;
         ffi_result
             .map(|value| (value, gas_info.externally_used))
             .map_err(Into::into)
@@ -277,35 +246,137 @@ where
     Q: Querier,
 {
     with_querier_from_context::<S, Q, _, _>(&mut context, |querier: &mut Q| {
-        let (ffi_result, gas_info) = querier.query_raw(query, gas_limit);
+        let result = querier.query_raw(query, gas_limit);
+        #[cfg(feature = "trace")]
+        record(|| TraceEvent::query(query, gas_limit, &result));
+        let (ffi_result, gas_info) = result;
         ffi_result
             .map(|system_result| (system_result, gas_info.externally_used))
             .map_err(Into::into)
     })
 }
 
-fn ocall_remove_db_impl<S, Q>(mut context: Ctx, key: &[u8]) -> VmResult<u64>
+/// The kinds of operations the enclave sends in a flush
+const OP_READ: u8 = 0;
+const OP_WRITE: u8 = 1;
+const OP_REMOVE: u8 = 2;
+
+fn ocall_flush_db_impl<S, Q>(mut context: Ctx, ops: &[u8]) -> VmResult<FlushGas>
 where
     S: Storage,
     Q: Querier,
 {
     with_storage_from_context::<S, Q, _, _>(&mut context, |storage: &mut S| {
-        let (ffi_result, gas_info) = storage.remove(key);
-        ffi_result
-            .and(Ok(gas_info.externally_used))
-            .map_err(Into::into)
+        apply_storage_ops(storage, ops)
     })
 }
 
-fn ocall_write_db_impl<S, Q>(mut context: Ctx, key: &[u8], value: &[u8]) -> VmResult<u64>
-where
-    S: Storage,
-    Q: Querier,
-{
-    with_storage_from_context::<S, Q, _, _>(&mut context, |storage: &mut S| {
-        let (ffi_result, gas_info) = storage.set(key, value);
-        ffi_result
-            .and(Ok(gas_info.externally_used))
-            .map_err(Into::into)
-    })
+/// Operations are encoded as `op || key_len || key`, followed by `value_len || value` for writes.
+/// The lengths are big endian u32 values.
+///
+/// Reads are only applied so the storage charges gas for them, as the enclave already had the values.
+fn apply_storage_ops<S: Storage>(storage: &mut S, mut ops: &[u8]) -> VmResult<FlushGas> {
+    fn take_chunk<'a>(ops: &mut &'a [u8]) -> VmResult<&'a [u8]> {
+        if ops.len() < 4 {
+            return Err(VmError::generic_err("Got malformed storage operations"));
+        }
+        let (len, rest) = ops.split_at(4);
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        if rest.len() < len {
+            return Err(VmError::generic_err("Got malformed storage operations"));
+        }
+        let (chunk, rest) = rest.split_at(len);
+        *ops = rest;
+        Ok(chunk)
+    }
+
+    let mut gas_used = FlushGas::default();
+    while let Some((&op, rest)) = ops.split_first() {
+        ops = rest;
+        let key = take_chunk(&mut ops)?;
+        match op {
+            OP_READ => {
+                let result = storage.get(key);
+                #[cfg(feature = "trace")]
+                record(|| TraceEvent::get(key, &result));
+                let (ffi_result, gas_info) = result;
+                ffi_result?;
+                gas_used.read = gas_used.read.saturating_add(gas_info.externally_used);
+            }
+            OP_WRITE => {
+                let value = take_chunk(&mut ops)?;
+                let result = storage.set(key, value);
+                #[cfg(feature = "trace")]
+                record(|| TraceEvent::set(key, value, &result));
+                let (ffi_result, gas_info) = result;
+                ffi_result?;
+                gas_used.write = gas_used.write.saturating_add(gas_info.externally_used);
+            }
+            OP_REMOVE => {
+                let result = storage.remove(key);
+                #[cfg(feature = "trace")]
+                record(|| TraceEvent::remove(key, &result));
+                let (ffi_result, gas_info) = result;
+                ffi_result?;
+                gas_used.remove = gas_used.remove.saturating_add(gas_info.externally_used);
+            }
+            other => {
+                return Err(VmError::generic_err(format!(
+                    "Got an unknown storage operation: {}",
+                    other
+                )))
+            }
+        }
+    }
+
+    Ok(gas_used)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockStorage;
+
+    fn encode(op: u8, key: &[u8], value: Option<&[u8]>) -> Vec<u8> {
+        let mut data = vec![op];
+        data.extend_from_slice(&(key.len() as u32).to_be_bytes());
+        data.extend_from_slice(key);
+        if let Some(value) = value {
+            data.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            data.extend_from_slice(value);
+        }
+        data
+    }
+
+    #[test]
+    fn apply_storage_ops_works() {
+        let mut storage = MockStorage::new();
+        let mut ops = vec![];
+        ops.extend(encode(OP_WRITE, b"foo", Some(b"bar")));
+        ops.extend(encode(OP_WRITE, b"foo", Some(b"baz")));
+        ops.extend(encode(OP_READ, b"foo", None));
+        ops.extend(encode(OP_WRITE, b"gone", Some(b"soon")));
+        ops.extend(encode(OP_REMOVE, b"gone", None));
+
+        let gas_used = apply_storage_ops(&mut storage, &ops).unwrap();
+
+        assert_eq!(storage.get(b"foo").0.unwrap(), Some(b"baz".to_vec()));
+        assert_eq!(storage.get(b"gone").0.unwrap(), None);
+        // MockStorage charges by the length of the keys and values
+        assert_eq!(gas_used.read, 3);
+        assert_eq!(gas_used.write, 6 + 6 + 8);
+        assert_eq!(gas_used.remove, 4);
+    }
+
+    #[test]
+    fn apply_storage_ops_rejects_malformed_input() {
+        let mut storage = MockStorage::new();
+
+        let truncated = encode(OP_WRITE, b"foo", Some(b"bar"));
+        let truncated = &truncated[..truncated.len() - 1];
+        assert!(apply_storage_ops(&mut storage, truncated).is_err());
+
+        let unknown = encode(7, b"foo", None);
+        assert!(apply_storage_ops(&mut storage, &unknown).is_err());
+    }
 }
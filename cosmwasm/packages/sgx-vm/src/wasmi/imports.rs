@@ -1,6 +1,9 @@
 //! This file should be autogenerated based on the headers created from the .edl file.
 
-use enclave_ffi_types::{Ctx, EnclaveBuffer, HandleResult, InitResult, QueryResult};
+use enclave_ffi_types::{
+    Ctx, EnclaveBuffer, HandleResult, InitResult, MigrateResult, ModuleCacheStats,
+    QueryBatchResult, QueryResult, ReissueResult,
+};
 use sgx_types::{sgx_enclave_id_t, sgx_status_t};
 
 extern "C" {
@@ -19,6 +22,8 @@ extern "C" {
         context: Ctx,
         gas_limit: u64,
         used_gas: *mut u64,
+        gas_schedule: *const u8,
+        gas_schedule_len: usize,
         contract: *const u8,
         contract_len: usize,
         env: *const u8,
@@ -36,6 +41,46 @@ extern "C" {
         context: Ctx,
         gas_limit: u64,
         used_gas: *mut u64,
+        gas_schedule: *const u8,
+        gas_schedule_len: usize,
+        contract: *const u8,
+        contract_len: usize,
+        env: *const u8,
+        env_len: usize,
+        msg: *const u8,
+        msg_len: usize,
+        sig_info: *const u8,
+        sig_info_len: usize,
+    ) -> sgx_status_t;
+
+    /// Like `ecall_init`, but for estimating the gas, on a storage that is thrown away afterwards
+    pub fn ecall_simulate_init(
+        eid: sgx_enclave_id_t,
+        retval: *mut InitResult,
+        context: Ctx,
+        gas_limit: u64,
+        used_gas: *mut u64,
+        gas_schedule: *const u8,
+        gas_schedule_len: usize,
+        contract: *const u8,
+        contract_len: usize,
+        env: *const u8,
+        env_len: usize,
+        msg: *const u8,
+        msg_len: usize,
+        sig_info: *const u8,
+        sig_info_len: usize,
+    ) -> sgx_status_t;
+
+    /// Like `ecall_handle`, but for estimating the gas, on a storage that is thrown away afterwards
+    pub fn ecall_simulate_handle(
+        eid: sgx_enclave_id_t,
+        retval: *mut HandleResult,
+        context: Ctx,
+        gas_limit: u64,
+        used_gas: *mut u64,
+        gas_schedule: *const u8,
+        gas_schedule_len: usize,
         contract: *const u8,
         contract_len: usize,
         env: *const u8,
@@ -53,9 +98,59 @@ extern "C" {
         context: Ctx,
         gas_limit: u64,
         used_gas: *mut u64,
+        gas_schedule: *const u8,
+        gas_schedule_len: usize,
+        contract: *const u8,
+        contract_len: usize,
+        msg: *const u8,
+        msg_len: usize,
+    ) -> sgx_status_t;
+
+    /// Trigger the migrate method in a wasm contract
+    pub fn ecall_migrate(
+        eid: sgx_enclave_id_t,
+        retval: *mut MigrateResult,
+        context: Ctx,
+        gas_limit: u64,
+        used_gas: *mut u64,
+        gas_schedule: *const u8,
+        gas_schedule_len: usize,
         contract: *const u8,
         contract_len: usize,
+        env: *const u8,
+        env_len: usize,
         msg: *const u8,
         msg_len: usize,
+        sig_info: *const u8,
+        sig_info_len: usize,
+    ) -> sgx_status_t;
+
+    /// Trigger the query method of several wasm contracts, with a shared gas limit
+    pub fn ecall_query_batch(
+        eid: sgx_enclave_id_t,
+        retval: *mut QueryBatchResult,
+        contexts: *const Ctx,
+        contexts_len: usize,
+        gas_limit: u64,
+        used_gas: *mut u64,
+        gas_schedule: *const u8,
+        gas_schedule_len: usize,
+        batch: *const u8,
+        batch_len: usize,
+        results: *mut QueryResult,
+    ) -> sgx_status_t;
+
+    /// Issue the proofs of a contract again, for a new epoch of the consensus seed
+    pub fn ecall_reissue_contract_proofs(
+        eid: sgx_enclave_id_t,
+        retval: *mut ReissueResult,
+        env: *const u8,
+        env_len: usize,
+    ) -> sgx_status_t;
+
+    /// Get the counters of the enclave's cache of prepared modules
+    pub fn ecall_get_module_cache_stats(
+        eid: sgx_enclave_id_t,
+        retval: *mut ModuleCacheStats,
     ) -> sgx_status_t;
 }
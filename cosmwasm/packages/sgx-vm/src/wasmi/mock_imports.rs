@@ -0,0 +1,283 @@
+//! Stands in for `imports.rs` with the `mock-enclave` feature.
+//!
+//! The contract runtime of the enclave is linked into this process, so instead of going through the
+//! SGX SDK, every ecall calls the runtime directly. The runtime calls back into the ocalls of this
+//! crate the same way. Each function keeps the signature of the ecall it replaces, and the enclave
+//! id is ignored.
+
+#![allow(clippy::too_many_arguments)]
+
+use enclave_ffi_types::{
+    Ctx, EnclaveBuffer, HandleResult, InitResult, MigrateResult, ModuleCacheStats,
+    QueryBatchResult, QueryResult, ReissueResult,
+};
+use sgx_types::{sgx_enclave_id_t, sgx_status_t};
+
+use wasmi_runtime_enclave::exports;
+
+/// Copy a buffer into the enclave memory space, and receive an opaque pointer to it.
+pub unsafe fn ecall_allocate(
+    _eid: sgx_enclave_id_t,
+    retval: *mut EnclaveBuffer,
+    buffer: *const u8,
+    length: usize,
+) -> sgx_status_t {
+    *retval = exports::ecall_allocate(buffer, length);
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Trigger the init method in a wasm contract
+pub unsafe fn ecall_init(
+    _eid: sgx_enclave_id_t,
+    retval: *mut InitResult,
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> sgx_status_t {
+    *retval = exports::ecall_init(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+    );
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Trigger a handle method in a wasm contract
+pub unsafe fn ecall_handle(
+    _eid: sgx_enclave_id_t,
+    retval: *mut HandleResult,
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> sgx_status_t {
+    *retval = exports::ecall_handle(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+    );
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Like `ecall_init`, but for estimating the gas, on a storage that is thrown away afterwards
+pub unsafe fn ecall_simulate_init(
+    _eid: sgx_enclave_id_t,
+    retval: *mut InitResult,
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> sgx_status_t {
+    *retval = exports::ecall_simulate_init(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+    );
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Like `ecall_handle`, but for estimating the gas, on a storage that is thrown away afterwards
+pub unsafe fn ecall_simulate_handle(
+    _eid: sgx_enclave_id_t,
+    retval: *mut HandleResult,
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> sgx_status_t {
+    *retval = exports::ecall_simulate_handle(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+    );
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Trigger a query method in a wasm contract
+pub unsafe fn ecall_query(
+    _eid: sgx_enclave_id_t,
+    retval: *mut QueryResult,
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+) -> sgx_status_t {
+    *retval = exports::ecall_query(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        msg,
+        msg_len,
+    );
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Trigger the migrate method in a wasm contract
+pub unsafe fn ecall_migrate(
+    _eid: sgx_enclave_id_t,
+    retval: *mut MigrateResult,
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> sgx_status_t {
+    *retval = exports::ecall_migrate(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+    );
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Trigger the query method of several wasm contracts, with a shared gas limit
+pub unsafe fn ecall_query_batch(
+    _eid: sgx_enclave_id_t,
+    retval: *mut QueryBatchResult,
+    contexts: *const Ctx,
+    contexts_len: usize,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    batch: *const u8,
+    batch_len: usize,
+    results: *mut QueryResult,
+) -> sgx_status_t {
+    *retval = exports::ecall_query_batch(
+        contexts,
+        contexts_len,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        batch,
+        batch_len,
+        results,
+    );
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Issue the proofs of a contract again, for a new epoch of the consensus seed
+pub unsafe fn ecall_reissue_contract_proofs(
+    _eid: sgx_enclave_id_t,
+    retval: *mut ReissueResult,
+    env: *const u8,
+    env_len: usize,
+) -> sgx_status_t {
+    *retval = exports::ecall_reissue_contract_proofs(env, env_len);
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Get the counters of the enclave's cache of prepared modules
+pub unsafe fn ecall_get_module_cache_stats(
+    _eid: sgx_enclave_id_t,
+    retval: *mut ModuleCacheStats,
+) -> sgx_status_t {
+    *retval = exports::ecall_get_module_cache_stats();
+    sgx_status_t::SGX_SUCCESS
+}
@@ -1,9 +1,15 @@
 mod exports;
+#[cfg(not(feature = "mock-enclave"))]
 mod imports;
+#[cfg(feature = "mock-enclave")]
+mod mock_imports;
 mod results;
 mod utils;
 mod wrapper;
 
+#[cfg(feature = "mock-enclave")]
+use mock_imports as imports;
+
 pub(crate) use exports::FullContext;
 pub use imports::*;
 pub use wrapper::*;
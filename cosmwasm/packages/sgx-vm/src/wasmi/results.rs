@@ -1,6 +1,8 @@
 use super::exports;
 use crate::VmResult;
-use enclave_ffi_types::{HandleResult, InitResult, QueryResult};
+use enclave_ffi_types::{
+    GasProfile, HandleResult, InitResult, MigrateResult, QueryResult, ReissueResult,
+};
 
 /// This struct is returned from module initialization.
 pub struct InitSuccess {
@@ -8,11 +10,18 @@ pub struct InitSuccess {
     output: Vec<u8>,
     /// The contract_key for this contract.
     contract_key: [u8; 64],
+    /// The proof that the contract key is valid in the current epoch of the consensus seed.
+    contract_key_proof: [u8; 32],
+    /// The proof that the sender of the init is the admin of the contract.
+    admin_proof: [u8; 32],
 }
 
 impl InitSuccess {
+    /// The contract key, then the key proof, then the admin proof, then the output of the execution
     pub fn into_output(self) -> Vec<u8> {
         let mut out_vec = self.contract_key.to_vec();
+        out_vec.extend_from_slice(&self.contract_key_proof);
+        out_vec.extend_from_slice(&self.admin_proof);
         out_vec.extend_from_slice(&self.output);
         out_vec
     }
@@ -23,9 +32,13 @@ pub fn init_result_to_vm_result(other: InitResult) -> VmResult<InitSuccess> {
         InitResult::Success {
             output,
             contract_key,
+            contract_key_proof,
+            admin_proof,
         } => Ok(InitSuccess {
             output: unsafe { exports::recover_buffer(output) }.unwrap_or_else(Vec::new),
             contract_key,
+            contract_key_proof,
+            admin_proof,
         }),
         InitResult::Failure { err } => Err(err.into()),
     }
@@ -35,9 +48,15 @@ pub fn init_result_to_vm_result(other: InitResult) -> VmResult<InitSuccess> {
 pub struct HandleSuccess {
     /// A pointer to the output of the execution
     output: Vec<u8>,
+    /// The gas used by the execution, by category. Empty unless the enclave was built with gas profiling.
+    gas_profile: GasProfile,
 }
 
 impl HandleSuccess {
+    pub fn gas_profile(&self) -> GasProfile {
+        self.gas_profile
+    }
+
     pub fn into_output(self) -> Vec<u8> {
         self.output
     }
@@ -45,8 +64,12 @@ impl HandleSuccess {
 
 pub fn handle_result_to_vm_result(other: HandleResult) -> VmResult<HandleSuccess> {
     match other {
-        HandleResult::Success { output } => Ok(HandleSuccess {
+        HandleResult::Success {
+            output,
+            gas_profile,
+        } => Ok(HandleSuccess {
             output: unsafe { exports::recover_buffer(output) }.unwrap_or_else(Vec::new),
+            gas_profile,
         }),
         HandleResult::Failure { err } => Err(err.into()),
     }
@@ -56,9 +79,15 @@ pub fn handle_result_to_vm_result(other: HandleResult) -> VmResult<HandleSuccess
 pub struct QuerySuccess {
     /// A pointer to the output of the execution
     output: Vec<u8>,
+    /// The gas used by the execution, by category. Empty unless the enclave was built with gas profiling.
+    gas_profile: GasProfile,
 }
 
 impl QuerySuccess {
+    pub fn gas_profile(&self) -> GasProfile {
+        self.gas_profile
+    }
+
     pub fn into_output(self) -> Vec<u8> {
         self.output
     }
@@ -66,9 +95,57 @@ impl QuerySuccess {
 
 pub fn query_result_to_vm_result(other: QueryResult) -> VmResult<QuerySuccess> {
     match other {
-        QueryResult::Success { output } => Ok(QuerySuccess {
+        QueryResult::Success {
+            output,
+            gas_profile,
+        } => Ok(QuerySuccess {
             output: unsafe { exports::recover_buffer(output) }.unwrap_or_else(Vec::new),
+            gas_profile,
         }),
         QueryResult::Failure { err } => Err(err.into()),
     }
 }
+
+/// This struct is returned from a migrate method.
+pub struct MigrateSuccess {
+    /// A pointer to the output of the execution
+    output: Vec<u8>,
+    /// The proof that the contract key is valid for the new code of the contract.
+    contract_key_proof: [u8; 32],
+    /// The proof that the admin of the contract can migrate it from the new code.
+    admin_proof: [u8; 32],
+}
+
+impl MigrateSuccess {
+    /// The output of the execution, and the key proof and admin proof that the host must store
+    pub fn into_parts(self) -> (Vec<u8>, [u8; 32], [u8; 32]) {
+        (self.output, self.contract_key_proof, self.admin_proof)
+    }
+}
+
+pub fn migrate_result_to_vm_result(other: MigrateResult) -> VmResult<MigrateSuccess> {
+    match other {
+        MigrateResult::Success {
+            output,
+            contract_key_proof,
+            admin_proof,
+        } => Ok(MigrateSuccess {
+            output: unsafe { exports::recover_buffer(output) }.unwrap_or_else(Vec::new),
+            contract_key_proof,
+            admin_proof,
+        }),
+        MigrateResult::Failure { err } => Err(err.into()),
+    }
+}
+
+/// The contract key proof and the admin proof of a contract in a new epoch of the consensus seed.
+/// The admin proof is all zeros if none was given.
+pub fn reissue_result_to_vm_result(other: ReissueResult) -> VmResult<([u8; 32], [u8; 32])> {
+    match other {
+        ReissueResult::Success {
+            contract_key_proof,
+            admin_proof,
+        } => Ok((contract_key_proof, admin_proof)),
+        ReissueResult::Failure { err } => Err(err.into()),
+    }
+}
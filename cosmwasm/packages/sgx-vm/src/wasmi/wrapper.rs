@@ -4,21 +4,25 @@ use std::ffi::c_void;
 use std::marker::PhantomData;
 use std::mem::MaybeUninit;
 
+use crate::enclave::Enclave;
 use crate::errors::{EnclaveError, VmResult};
 use crate::{Querier, Storage};
 
-use enclave_ffi_types::{Ctx, EnclaveBuffer, HandleResult, InitResult, QueryResult};
+use enclave_ffi_types::{
+    Ctx, EnclaveBuffer, HandleResult, InitResult, MigrateResult, ModuleCacheStats,
+    QueryBatchResult, QueryResult, ReissueResult,
+};
 
 use sgx_types::{sgx_status_t, SgxResult};
-use sgx_urts::SgxEnclave;
 
 use log::*;
 
 use super::exports::FullContext;
 use super::imports;
 use super::results::{
-    handle_result_to_vm_result, init_result_to_vm_result, query_result_to_vm_result, HandleSuccess,
-    InitSuccess, QuerySuccess,
+    handle_result_to_vm_result, init_result_to_vm_result, migrate_result_to_vm_result,
+    query_result_to_vm_result, reissue_result_to_vm_result, HandleSuccess, InitSuccess,
+    MigrateSuccess, QuerySuccess,
 };
 
 /// This is a safe wrapper for allocating buffers inside the enclave.
@@ -44,6 +48,43 @@ pub(super) fn allocate_enclave_buffer(buffer: &[u8]) -> SgxResult<EnclaveBuffer>
     }
 }
 
+/// Returns the counters of the enclave's cache of prepared modules.
+/// These are shared by all the instances running in the enclave.
+pub fn module_cache_stats() -> VmResult<ModuleCacheStats> {
+    let enclave = crate::enclave::get_enclave().map_err(EnclaveError::sdk_err)?;
+    let mut stats = ModuleCacheStats::default();
+
+    match unsafe { imports::ecall_get_module_cache_stats(enclave.geteid(), &mut stats) } {
+        sgx_status_t::SGX_SUCCESS => Ok(stats),
+        failure_status => Err(EnclaveError::sdk_err(failure_status).into()),
+    }
+}
+
+/// Issues the proofs of a contract again, for a new epoch of the consensus seed.
+/// `env` is the env of the contract, with the new epoch and the proofs of the previous one.
+/// Returns the new contract key proof and admin proof.
+pub fn reissue_contract_proofs(env: &[u8]) -> VmResult<([u8; 32], [u8; 32])> {
+    let enclave = crate::enclave::get_enclave().map_err(EnclaveError::sdk_err)?;
+    let mut reissue_result = MaybeUninit::<ReissueResult>::uninit();
+
+    let status = unsafe {
+        imports::ecall_reissue_contract_proofs(
+            enclave.geteid(),
+            reissue_result.as_mut_ptr(),
+            env.as_ptr(),
+            env.len(),
+        )
+    };
+
+    match status {
+        sgx_status_t::SGX_SUCCESS => {
+            let reissue_result = unsafe { reissue_result.assume_init() };
+            reissue_result_to_vm_result(reissue_result)
+        }
+        failure_status => Err(EnclaveError::sdk_err(failure_status).into()),
+    }
+}
+
 pub struct Module<S, Q>
 where
     S: Storage,
@@ -52,7 +93,9 @@ where
     bytecode: Vec<u8>,
     gas_limit: u64,
     used_gas: u64,
-    enclave: &'static SgxEnclave,
+    /// The serialized gas schedule passed to the enclave. Empty means the enclave's defaults.
+    gas_schedule: Vec<u8>,
+    enclave: &'static Enclave,
     ctx: Ctx,
     finalizer: fn(*mut c_void),
 
@@ -69,7 +112,7 @@ where
     pub fn new(
         bytecode: Vec<u8>,
         gas_limit: u64,
-        enclave: &'static SgxEnclave,
+        enclave: &'static Enclave,
         (data, finalizer): (*mut c_void, fn(*mut c_void)),
     ) -> Self {
         // TODO add validation of this bytecode?
@@ -81,6 +124,7 @@ where
             bytecode,
             gas_limit,
             used_gas: 0,
+            gas_schedule: Vec::new(),
             enclave,
             ctx,
             finalizer,
@@ -110,9 +154,45 @@ where
         self.used_gas
     }
 
+    pub fn set_gas_schedule(&mut self, gas_schedule: Vec<u8>) {
+        self.gas_schedule = gas_schedule;
+    }
+
+    #[cfg(feature = "trace")]
+    pub fn bytecode(&self) -> &[u8] {
+        &self.bytecode
+    }
+
+    #[cfg(feature = "trace")]
+    pub fn gas_schedule(&self) -> &[u8] {
+        &self.gas_schedule
+    }
+
     pub fn init(&mut self, env: &[u8], msg: &[u8], sig_info: &[u8]) -> VmResult<InitSuccess> {
+        self.call_init(false, env, msg, sig_info)
+    }
+
+    /// Like `init`, but meant to run on a storage that is thrown away afterwards.
+    /// The gas used is still charged, so it can be read with `gas_used` afterwards.
+    pub fn simulate_init(
+        &mut self,
+        env: &[u8],
+        msg: &[u8],
+        sig_info: &[u8],
+    ) -> VmResult<InitSuccess> {
+        self.call_init(true, env, msg, sig_info)
+    }
+
+    fn call_init(
+        &mut self,
+        simulate: bool,
+        env: &[u8],
+        msg: &[u8],
+        sig_info: &[u8],
+    ) -> VmResult<InitSuccess> {
         trace!(
-            "init() called with env: {:?} msg: {:?} enclave_id: {:?} gas_left: {}",
+            "init() called with simulate: {} env: {:?} msg: {:?} enclave_id: {:?} gas_left: {}",
+            simulate,
             String::from_utf8_lossy(env),
             String::from_utf8_lossy(msg),
             self.enclave.geteid(),
@@ -122,13 +202,20 @@ where
         let mut init_result = MaybeUninit::<InitResult>::uninit();
         let mut used_gas = 0_u64;
 
+        let ecall = if simulate {
+            imports::ecall_simulate_init
+        } else {
+            imports::ecall_init
+        };
         let status = unsafe {
-            imports::ecall_init(
+            ecall(
                 self.enclave.geteid(),
                 init_result.as_mut_ptr(),
                 self.ctx.unsafe_clone(),
                 self.gas_left(),
                 &mut used_gas,
+                self.gas_schedule.as_ptr(),
+                self.gas_schedule.len(),
                 self.bytecode.as_ptr(),
                 self.bytecode.len(),
                 env.as_ptr(),
@@ -157,8 +244,30 @@ where
     }
 
     pub fn handle(&mut self, env: &[u8], msg: &[u8], sig_info: &[u8]) -> VmResult<HandleSuccess> {
+        self.call_handle(false, env, msg, sig_info)
+    }
+
+    /// Like `handle`, but meant to run on a storage that is thrown away afterwards.
+    /// The gas used is still charged, so it can be read with `gas_used` afterwards.
+    pub fn simulate_handle(
+        &mut self,
+        env: &[u8],
+        msg: &[u8],
+        sig_info: &[u8],
+    ) -> VmResult<HandleSuccess> {
+        self.call_handle(true, env, msg, sig_info)
+    }
+
+    fn call_handle(
+        &mut self,
+        simulate: bool,
+        env: &[u8],
+        msg: &[u8],
+        sig_info: &[u8],
+    ) -> VmResult<HandleSuccess> {
         trace!(
-            "handle() called with env: {:?} msg: {:?} enclave_id: {:?} gas_left: {}",
+            "handle() called with simulate: {} env: {:?} msg: {:?} enclave_id: {:?} gas_left: {}",
+            simulate,
             String::from_utf8_lossy(env),
             String::from_utf8_lossy(msg),
             self.enclave.geteid(),
@@ -168,13 +277,20 @@ where
         let mut handle_result = MaybeUninit::<HandleResult>::uninit();
         let mut used_gas = 0_u64;
 
+        let ecall = if simulate {
+            imports::ecall_simulate_handle
+        } else {
+            imports::ecall_handle
+        };
         let status = unsafe {
-            imports::ecall_handle(
+            ecall(
                 self.enclave.geteid(),
                 handle_result.as_mut_ptr(),
                 self.ctx.unsafe_clone(),
                 self.gas_left(),
                 &mut used_gas,
+                self.gas_schedule.as_ptr(),
+                self.gas_schedule.len(),
                 self.bytecode.as_ptr(),
                 self.bytecode.len(),
                 env.as_ptr(),
@@ -219,6 +335,8 @@ where
                 self.ctx.unsafe_clone(),
                 self.gas_left(),
                 &mut used_gas,
+                self.gas_schedule.as_ptr(),
+                self.gas_schedule.len(),
                 self.bytecode.as_ptr(),
                 self.bytecode.len(),
                 msg.as_ptr(),
@@ -242,6 +360,121 @@ where
         }
     }
 
+    /// Runs a query on each of the modules in a single call into the enclave.
+    /// The queries share `gas_limit`, and are run with the gas schedule of the first module.
+    /// Returns the result of each query, and the total gas used.
+    pub fn query_batch(
+        queries: &[(&Self, &[u8])],
+        gas_limit: u64,
+    ) -> VmResult<(Vec<VmResult<QuerySuccess>>, u64)> {
+        let (first_module, _) = match queries.first() {
+            Some(query) => query,
+            None => return Ok((vec![], 0)),
+        };
+
+        trace!(
+            "query_batch() called with {} queries enclave_id: {:?}",
+            queries.len(),
+            first_module.enclave.geteid()
+        );
+
+        let contexts: Vec<Ctx> = queries
+            .iter()
+            .map(|(module, _)| unsafe { module.ctx.unsafe_clone() })
+            .collect();
+        let batch = encode_query_batch(queries);
+
+        let mut batch_result = MaybeUninit::<QueryBatchResult>::uninit();
+        let mut results: Vec<MaybeUninit<QueryResult>> = Vec::with_capacity(queries.len());
+        results.resize_with(queries.len(), MaybeUninit::uninit);
+        let mut used_gas = 0_u64;
+
+        let status = unsafe {
+            imports::ecall_query_batch(
+                first_module.enclave.geteid(),
+                batch_result.as_mut_ptr(),
+                contexts.as_ptr(),
+                contexts.len(),
+                gas_limit,
+                &mut used_gas,
+                first_module.gas_schedule.as_ptr(),
+                first_module.gas_schedule.len(),
+                batch.as_ptr(),
+                batch.len(),
+                results.as_mut_ptr() as *mut QueryResult,
+            )
+        };
+
+        trace!(
+            "query_batch() returned with gas_used: {} (gas_limit: {})",
+            used_gas,
+            gas_limit
+        );
+
+        match status {
+            sgx_status_t::SGX_SUCCESS => match unsafe { batch_result.assume_init() } {
+                // The enclave writes the result of every query when the batch succeeds
+                QueryBatchResult::Success => Ok((
+                    results
+                        .into_iter()
+                        .map(|result| query_result_to_vm_result(unsafe { result.assume_init() }))
+                        .collect(),
+                    used_gas,
+                )),
+                QueryBatchResult::Failure { err } => Err(err.into()),
+            },
+            failure_status => Err(EnclaveError::sdk_err(failure_status).into()),
+        }
+    }
+
+    pub fn migrate(&mut self, env: &[u8], msg: &[u8], sig_info: &[u8]) -> VmResult<MigrateSuccess> {
+        trace!(
+            "migrate() called with env: {:?} msg: {:?} enclave_id: {:?} gas_left: {}",
+            String::from_utf8_lossy(env),
+            String::from_utf8_lossy(msg),
+            self.enclave.geteid(),
+            self.gas_left()
+        );
+
+        let mut migrate_result = MaybeUninit::<MigrateResult>::uninit();
+        let mut used_gas = 0_u64;
+
+        let status = unsafe {
+            imports::ecall_migrate(
+                self.enclave.geteid(),
+                migrate_result.as_mut_ptr(),
+                self.ctx.unsafe_clone(),
+                self.gas_left(),
+                &mut used_gas,
+                self.gas_schedule.as_ptr(),
+                self.gas_schedule.len(),
+                self.bytecode.as_ptr(),
+                self.bytecode.len(),
+                env.as_ptr(),
+                env.len(),
+                msg.as_ptr(),
+                msg.len(),
+                sig_info.as_ptr(),
+                sig_info.len(),
+            )
+        };
+
+        trace!(
+            "migrate() returned with gas_used: {} (gas_limit: {})",
+            used_gas,
+            self.gas_limit
+        );
+        self.consume_gas(used_gas);
+
+        match status {
+            sgx_status_t::SGX_SUCCESS => {
+                let migrate_result = unsafe { migrate_result.assume_init() };
+                migrate_result_to_vm_result(migrate_result)
+            }
+            failure_status => Err(EnclaveError::sdk_err(failure_status).into()),
+        }
+    }
+
     fn consume_gas(&mut self, used_gas: u64) {
         self.used_gas = self.used_gas.saturating_add(used_gas);
     }
@@ -257,3 +490,39 @@ where
         (self.finalizer)(context_data);
     }
 }
+
+/// Encodes the batch as `code_count || (code_len || code) * code_count`, followed by
+/// a sequence of `code_index || msg_len || msg`, where the numbers are big endian u32 values.
+/// Modules that run the same code only send it once.
+fn encode_query_batch<S, Q>(queries: &[(&Module<S, Q>, &[u8])]) -> Vec<u8>
+where
+    S: Storage,
+    Q: Querier,
+{
+    let mut codes: Vec<&[u8]> = vec![];
+    let mut code_indexes = Vec::with_capacity(queries.len());
+    for (module, _) in queries {
+        let code = module.bytecode.as_slice();
+        let index = match codes.iter().position(|known_code| *known_code == code) {
+            Some(index) => index,
+            None => {
+                codes.push(code);
+                codes.len() - 1
+            }
+        };
+        code_indexes.push(index);
+    }
+
+    let mut batch = (codes.len() as u32).to_be_bytes().to_vec();
+    for code in codes {
+        batch.extend_from_slice(&(code.len() as u32).to_be_bytes());
+        batch.extend_from_slice(code);
+    }
+    for ((_, msg), code_index) in queries.iter().zip(code_indexes) {
+        batch.extend_from_slice(&(code_index as u32).to_be_bytes());
+        batch.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+        batch.extend_from_slice(msg);
+    }
+
+    batch
+}
@@ -0,0 +1,791 @@
+//! An in-memory chain for integration tests of contracts that call each other.
+//!
+//! `App` holds contracts that are native Rust implementations of the contract entry points, and
+//! runs the messages they return the way the compute module does: `WasmMsg::Execute` and
+//! `WasmMsg::Instantiate` call other contracts, `BankMsg::Send` moves funds, and contracts query
+//! each other with `WasmQuery::Smart`.
+//!
+//! Like the enclave, the app rejects messages and queries of contracts whose `callback_code_hash`
+//! isn't the code hash of the contract they're sent to. Only the messages a contract returns are
+//! sent as the contract: a contract can't be the sender of a message from the outside.
+//!
+//! Every call to the app is a transaction. If a contract, or any message it returns, fails, none of
+//! their changes are kept.
+//!
+//! A contract that is running can't be queried, so queries can't reenter a contract.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::addresses::HumanAddr;
+use crate::coins::Coin;
+use crate::encoding::Binary;
+use crate::errors::{StdError, StdResult, SystemError};
+use crate::init_handle::{BankMsg, CosmosMsg, HandleResponse, InitResponse, WasmMsg};
+use crate::mock::{MockApi, MockStorage};
+use crate::query::{AllBalanceResponse, BalanceResponse, BankQuery, QueryRequest, WasmQuery};
+use crate::serde::{from_slice, to_binary, to_vec};
+use crate::traits::{Extern, Querier, QuerierResult};
+use crate::types::{BlockInfo, ContractInfo, Empty, Env, MessageInfo};
+
+/// The dependencies contracts get when they run in an `App`
+pub type AppDeps = Extern<MockStorage, MockApi, AppQuerier>;
+
+/// The entry points of a contract, which get their messages as JSON like the Wasm exports do
+pub trait Contract {
+    fn init(&self, deps: &mut AppDeps, env: Env, msg: &[u8]) -> StdResult<InitResponse>;
+    fn handle(&self, deps: &mut AppDeps, env: Env, msg: &[u8]) -> StdResult<HandleResponse>;
+    fn query(&self, deps: &AppDeps, msg: &[u8]) -> StdResult<Binary>;
+}
+
+/// Implements `Contract` with the `init`, `handle` and `query` functions of a contract crate
+pub struct ContractWrapper<I, H, Q> {
+    init_fn: fn(&mut AppDeps, Env, I) -> StdResult<InitResponse>,
+    handle_fn: fn(&mut AppDeps, Env, H) -> StdResult<HandleResponse>,
+    query_fn: fn(&AppDeps, Q) -> StdResult<Binary>,
+}
+
+impl<I, H, Q> ContractWrapper<I, H, Q> {
+    pub fn new(
+        init_fn: fn(&mut AppDeps, Env, I) -> StdResult<InitResponse>,
+        handle_fn: fn(&mut AppDeps, Env, H) -> StdResult<HandleResponse>,
+        query_fn: fn(&AppDeps, Q) -> StdResult<Binary>,
+    ) -> Self {
+        ContractWrapper {
+            init_fn,
+            handle_fn,
+            query_fn,
+        }
+    }
+}
+
+impl<I, H, Q> Contract for ContractWrapper<I, H, Q>
+where
+    I: DeserializeOwned,
+    H: DeserializeOwned,
+    Q: DeserializeOwned,
+{
+    fn init(&self, deps: &mut AppDeps, env: Env, msg: &[u8]) -> StdResult<InitResponse> {
+        (self.init_fn)(deps, env, from_slice(msg)?)
+    }
+
+    fn handle(&self, deps: &mut AppDeps, env: Env, msg: &[u8]) -> StdResult<HandleResponse> {
+        (self.handle_fn)(deps, env, from_slice(msg)?)
+    }
+
+    fn query(&self, deps: &AppDeps, msg: &[u8]) -> StdResult<Binary> {
+        (self.query_fn)(deps, from_slice(msg)?)
+    }
+}
+
+#[derive(Clone)]
+struct Code {
+    contract: Rc<dyn Contract>,
+    code_hash: String,
+}
+
+#[derive(Clone)]
+struct ContractData {
+    code_id: u64,
+    label: String,
+    /// Taken out of the app while the contract runs
+    storage: Option<MockStorage>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    block: BlockInfo,
+    codes: Vec<Code>,
+    contracts: HashMap<HumanAddr, ContractData>,
+    balances: HashMap<HumanAddr, Vec<Coin>>,
+}
+
+/// A chain with contracts and bank balances, which are all kept in memory
+pub struct App {
+    state: Rc<RefCell<AppState>>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl App {
+    /// Creates an app without contracts or balances, whose block is the one of `mock_env`
+    pub fn new() -> Self {
+        let state = AppState {
+            block: BlockInfo {
+                height: 12_345,
+                time: 1_571_797_419,
+                chain_id: "cosmos-testnet-14002".to_string(),
+            },
+            codes: vec![],
+            contracts: HashMap::new(),
+            balances: HashMap::new(),
+        };
+        App {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    pub fn block(&self) -> BlockInfo {
+        self.state.borrow().block.clone()
+    }
+
+    /// Changes the block the next calls run in, e.g. to let time pass
+    pub fn update_block<F: FnOnce(&mut BlockInfo)>(&mut self, update: F) {
+        update(&mut self.state.borrow_mut().block);
+    }
+
+    /// Sets the balance of an address and returns the old balance
+    pub fn set_balance<U: Into<HumanAddr>>(&mut self, addr: U, balance: Vec<Coin>) -> Vec<Coin> {
+        self.state
+            .borrow_mut()
+            .balances
+            .insert(addr.into(), balance)
+            .unwrap_or_default()
+    }
+
+    pub fn balance<U: Into<HumanAddr>>(&self, addr: U) -> Vec<Coin> {
+        self.state
+            .borrow()
+            .balances
+            .get(&addr.into())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Stores a contract and returns its code id. Every code gets a distinct code hash, which
+    /// messages of other contracts must use in their `callback_code_hash`.
+    pub fn store_code(&mut self, contract: Box<dyn Contract>) -> u64 {
+        let mut state = self.state.borrow_mut();
+        let code_id = state.codes.len() as u64 + 1;
+        state.codes.push(Code {
+            contract: contract.into(),
+            code_hash: format!("{:064x}", code_id),
+        });
+        code_id
+    }
+
+    pub fn code_hash(&self, code_id: u64) -> StdResult<String> {
+        Ok(get_code(&self.state, code_id)?.code_hash)
+    }
+
+    /// Returns the code hash of the code a contract was instantiated from
+    pub fn contract_code_hash(&self, contract_addr: &HumanAddr) -> StdResult<String> {
+        Ok(get_contract_code(&self.state, contract_addr)?.code_hash)
+    }
+
+    /// Instantiates a contract and runs the messages it returns. Returns the new contract address.
+    pub fn instantiate<U: Into<HumanAddr>, T: Serialize>(
+        &mut self,
+        code_id: u64,
+        sender: U,
+        msg: &T,
+        send: &[Coin],
+        label: &str,
+    ) -> StdResult<HumanAddr> {
+        let sender = sender.into();
+        let msg = to_vec(msg)?;
+        reject_contract_sender(&self.state, &sender)?;
+        transaction(&self.state, || {
+            instantiate(&self.state, code_id, &sender, &msg, send, label)
+        })
+    }
+
+    /// Executes a contract and runs the messages it returns. Returns the response of the contract.
+    pub fn execute<U: Into<HumanAddr>, T: Serialize>(
+        &mut self,
+        sender: U,
+        contract_addr: &HumanAddr,
+        msg: &T,
+        send: &[Coin],
+    ) -> StdResult<HandleResponse> {
+        let sender = sender.into();
+        let msg = to_vec(msg)?;
+        reject_contract_sender(&self.state, &sender)?;
+        transaction(&self.state, || {
+            execute(&self.state, &sender, contract_addr, &msg, send)
+        })
+    }
+
+    pub fn query<T: Serialize>(&self, contract_addr: &HumanAddr, msg: &T) -> StdResult<Binary> {
+        query(&self.state, contract_addr, &to_vec(msg)?)
+    }
+}
+
+/// Routes the queries of contracts to the app
+pub struct AppQuerier {
+    state: Rc<RefCell<AppState>>,
+}
+
+impl Querier for AppQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        match request {
+            QueryRequest::Bank(bank_query) => Ok(self.query_bank(&bank_query)),
+            QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr,
+                callback_code_hash,
+                msg,
+            }) => {
+                if !self.state.borrow().contracts.contains_key(&contract_addr) {
+                    return Err(SystemError::NoSuchContract {
+                        addr: contract_addr,
+                    });
+                }
+                Ok(
+                    check_contract_code_hash(&self.state, &contract_addr, &callback_code_hash)
+                        .and_then(|_| query(&self.state, &contract_addr, msg.as_slice())),
+                )
+            }
+            // Contract storage is encrypted, so it can't be read by other contracts
+            QueryRequest::Wasm(WasmQuery::Raw { .. }) => Err(SystemError::UnsupportedRequest {
+                kind: "wasm raw".to_string(),
+            }),
+            QueryRequest::Custom(_) => Err(SystemError::UnsupportedRequest {
+                kind: "custom".to_string(),
+            }),
+            QueryRequest::Staking(_) => Err(SystemError::UnsupportedRequest {
+                kind: "staking".to_string(),
+            }),
+            QueryRequest::Dist(_) => Err(SystemError::UnsupportedRequest {
+                kind: "dist".to_string(),
+            }),
+            QueryRequest::Mint(_) => Err(SystemError::UnsupportedRequest {
+                kind: "mint".to_string(),
+            }),
+            QueryRequest::Gov(_) => Err(SystemError::UnsupportedRequest {
+                kind: "gov".to_string(),
+            }),
+        }
+    }
+}
+
+impl AppQuerier {
+    fn query_bank(&self, request: &BankQuery) -> StdResult<Binary> {
+        let state = self.state.borrow();
+        match request {
+            BankQuery::Balance { address, denom } => {
+                let amount = state
+                    .balances
+                    .get(address)
+                    .and_then(|v| v.iter().find(|c| &c.denom == denom).map(|c| c.amount))
+                    .unwrap_or_default();
+                to_binary(&BalanceResponse {
+                    amount: Coin {
+                        amount,
+                        denom: denom.to_string(),
+                    },
+                })
+            }
+            BankQuery::AllBalances { address } => to_binary(&AllBalanceResponse {
+                amount: state.balances.get(address).cloned().unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+/// Runs `call`, and restores the state of the app if it fails
+fn transaction<T, F>(state: &Rc<RefCell<AppState>>, call: F) -> StdResult<T>
+where
+    F: FnOnce() -> StdResult<T>,
+{
+    let snapshot = state.borrow().clone();
+    let result = call();
+    if result.is_err() {
+        *state.borrow_mut() = snapshot;
+    }
+    result
+}
+
+fn instantiate(
+    state: &Rc<RefCell<AppState>>,
+    code_id: u64,
+    sender: &HumanAddr,
+    msg: &[u8],
+    send: &[Coin],
+    label: &str,
+) -> StdResult<HumanAddr> {
+    let code = get_code(state, code_id)?;
+
+    let contract_addr = {
+        let mut state = state.borrow_mut();
+        if state.contracts.values().any(|c| c.label == label) {
+            return Err(StdError::generic_err(format!(
+                "Label {} is already taken by another contract",
+                label
+            )));
+        }
+        let contract_addr = HumanAddr(format!("contract{}", state.contracts.len() + 1));
+        state.contracts.insert(
+            contract_addr.clone(),
+            ContractData {
+                code_id,
+                label: label.to_string(),
+                storage: Some(MockStorage::new()),
+            },
+        );
+        contract_addr
+    };
+
+    transfer(state, sender, &contract_addr, send)?;
+    let env = contract_env(state, sender, &contract_addr, send, &code.code_hash);
+    let response = with_deps(state, &contract_addr, |deps| {
+        code.contract.init(deps, env, msg)
+    })?;
+    dispatch(state, &contract_addr, response.messages)?;
+    Ok(contract_addr)
+}
+
+fn execute(
+    state: &Rc<RefCell<AppState>>,
+    sender: &HumanAddr,
+    contract_addr: &HumanAddr,
+    msg: &[u8],
+    send: &[Coin],
+) -> StdResult<HandleResponse> {
+    let code = get_contract_code(state, contract_addr)?;
+
+    transfer(state, sender, contract_addr, send)?;
+    let env = contract_env(state, sender, contract_addr, send, &code.code_hash);
+    let response = with_deps(state, contract_addr, |deps| {
+        code.contract.handle(deps, env, msg)
+    })?;
+    dispatch(state, contract_addr, response.messages.clone())?;
+    Ok(response)
+}
+
+fn query(
+    state: &Rc<RefCell<AppState>>,
+    contract_addr: &HumanAddr,
+    msg: &[u8],
+) -> StdResult<Binary> {
+    let code = get_contract_code(state, contract_addr)?;
+    with_deps(state, contract_addr, |deps| code.contract.query(deps, msg))
+}
+
+/// Runs the messages a contract returned, in order
+fn dispatch(
+    state: &Rc<RefCell<AppState>>,
+    contract_addr: &HumanAddr,
+    messages: Vec<CosmosMsg>,
+) -> StdResult<()> {
+    for message in messages {
+        match message {
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address,
+                to_address,
+                amount,
+            }) => {
+                if from_address != *contract_addr {
+                    return Err(StdError::generic_err(format!(
+                        "Contract {} can't send the funds of {}",
+                        contract_addr, from_address
+                    )));
+                }
+                transfer(state, contract_addr, &to_address, &amount)?;
+            }
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: target,
+                callback_code_hash,
+                msg,
+                send,
+            }) => {
+                check_contract_code_hash(state, &target, &callback_code_hash)?;
+                execute(state, contract_addr, &target, msg.as_slice(), &send)?;
+            }
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                code_id,
+                callback_code_hash,
+                msg,
+                send,
+                label,
+            }) => {
+                check_code_hash(&get_code(state, code_id)?, &callback_code_hash)?;
+                instantiate(state, code_id, contract_addr, msg.as_slice(), &send, &label)?;
+            }
+            other => {
+                return Err(StdError::generic_err(format!(
+                    "Unsupported message: {:?}",
+                    other
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Gives a contract its dependencies, with the storage it has in the app
+fn with_deps<T, F>(state: &Rc<RefCell<AppState>>, contract_addr: &HumanAddr, run: F) -> StdResult<T>
+where
+    F: FnOnce(&mut AppDeps) -> StdResult<T>,
+{
+    let storage = state
+        .borrow_mut()
+        .contracts
+        .get_mut(contract_addr)
+        .ok_or_else(|| no_such_contract(contract_addr))?
+        .storage
+        .take()
+        .ok_or_else(|| {
+            StdError::generic_err(format!("Contract {} is already running", contract_addr))
+        })?;
+
+    let mut deps = Extern {
+        storage,
+        api: MockApi::default(),
+        querier: AppQuerier {
+            state: state.clone(),
+        },
+    };
+    let result = run(&mut deps);
+
+    if let Some(contract) = state.borrow_mut().contracts.get_mut(contract_addr) {
+        contract.storage = Some(deps.storage);
+    }
+    result
+}
+
+fn contract_env(
+    state: &Rc<RefCell<AppState>>,
+    sender: &HumanAddr,
+    contract_addr: &HumanAddr,
+    send: &[Coin],
+    code_hash: &str,
+) -> Env {
+    Env {
+        block: state.borrow().block.clone(),
+        message: MessageInfo {
+            sender: sender.clone(),
+            sent_funds: send.to_vec(),
+        },
+        contract: ContractInfo {
+            address: contract_addr.clone(),
+        },
+        contract_key: Some("".to_string()),
+        contract_code_hash: code_hash.to_string(),
+    }
+}
+
+fn transfer(
+    state: &Rc<RefCell<AppState>>,
+    from: &HumanAddr,
+    to: &HumanAddr,
+    amount: &[Coin],
+) -> StdResult<()> {
+    let mut state = state.borrow_mut();
+    for coin in amount {
+        let from_balance = state.balances.entry(from.clone()).or_default();
+        let index = from_balance.iter().position(|c| c.denom == coin.denom);
+        let available = index.map(|i| from_balance[i].amount).unwrap_or_default();
+        let left = (available - coin.amount).map_err(|_| {
+            StdError::generic_err(format!(
+                "Insufficient funds: {} has {}{}, needs {}{}",
+                from, available, coin.denom, coin.amount, coin.denom
+            ))
+        })?;
+        if let Some(i) = index {
+            from_balance[i].amount = left;
+        }
+
+        let to_balance = state.balances.entry(to.clone()).or_default();
+        match to_balance.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(c) => c.amount += coin.amount,
+            None => to_balance.push(coin.clone()),
+        }
+    }
+    Ok(())
+}
+
+/// Contracts only send messages by returning them, which the app dispatches with the contract as
+/// the sender
+fn reject_contract_sender(state: &Rc<RefCell<AppState>>, sender: &HumanAddr) -> StdResult<()> {
+    if state.borrow().contracts.contains_key(sender) {
+        return Err(StdError::generic_err(format!(
+            "{} is a contract, which can only send messages by returning them",
+            sender
+        )));
+    }
+    Ok(())
+}
+
+fn check_contract_code_hash(
+    state: &Rc<RefCell<AppState>>,
+    contract_addr: &HumanAddr,
+    callback_code_hash: &str,
+) -> StdResult<()> {
+    check_code_hash(
+        &get_contract_code(state, contract_addr)?,
+        callback_code_hash,
+    )
+}
+
+fn check_code_hash(code: &Code, callback_code_hash: &str) -> StdResult<()> {
+    if !code.code_hash.eq_ignore_ascii_case(callback_code_hash) {
+        return Err(StdError::generic_err(
+            "Message contains mismatched contract hash",
+        ));
+    }
+    Ok(())
+}
+
+fn get_code(state: &Rc<RefCell<AppState>>, code_id: u64) -> StdResult<Code> {
+    let state = state.borrow();
+    code_id
+        .checked_sub(1)
+        .and_then(|index| state.codes.get(index as usize))
+        .cloned()
+        .ok_or_else(|| StdError::not_found(format!("code {}", code_id)))
+}
+
+fn get_contract_code(state: &Rc<RefCell<AppState>>, contract_addr: &HumanAddr) -> StdResult<Code> {
+    let code_id = state
+        .borrow()
+        .contracts
+        .get(contract_addr)
+        .map(|contract| contract.code_id)
+        .ok_or_else(|| no_such_contract(contract_addr))?;
+    get_code(state, code_id)
+}
+
+fn no_such_contract(contract_addr: &HumanAddr) -> StdError {
+    StdError::not_found(format!("contract {}", contract_addr))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::traits::{ReadonlyStorage, Storage};
+    use crate::{coins, from_binary};
+    use serde::Deserialize;
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum HandleMsg {
+        /// Counts, then runs the messages
+        Run {
+            messages: Vec<CosmosMsg>,
+        },
+        /// Stores the count of another contract as its own
+        CopyCount {
+            contract_addr: HumanAddr,
+            code_hash: String,
+        },
+        Fail {},
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(rename_all = "snake_case")]
+    enum QueryMsg {
+        Count {},
+    }
+
+    fn init(deps: &mut AppDeps, _env: Env, _msg: Empty) -> StdResult<InitResponse> {
+        deps.storage.set(b"count", &to_vec(&0u64)?);
+        Ok(InitResponse::default())
+    }
+
+    fn handle(deps: &mut AppDeps, _env: Env, msg: HandleMsg) -> StdResult<HandleResponse> {
+        let count: u64 = match msg {
+            HandleMsg::Run { messages } => {
+                let count: u64 = from_slice(&deps.storage.get(b"count").unwrap())?;
+                deps.storage.set(b"count", &to_vec(&(count + 1))?);
+                return Ok(HandleResponse {
+                    messages,
+                    log: vec![],
+                    data: None,
+                });
+            }
+            HandleMsg::CopyCount {
+                contract_addr,
+                code_hash,
+            } => deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr,
+                callback_code_hash: code_hash,
+                msg: to_binary(&QueryMsg::Count {})?,
+            }))?,
+            HandleMsg::Fail {} => return Err(StdError::generic_err("failed on purpose")),
+        };
+        deps.storage.set(b"count", &to_vec(&count)?);
+        Ok(HandleResponse::default())
+    }
+
+    fn query(deps: &AppDeps, msg: QueryMsg) -> StdResult<Binary> {
+        match msg {
+            QueryMsg::Count {} => Ok(Binary(deps.storage.get(b"count").unwrap())),
+        }
+    }
+
+    fn count(app: &App, contract_addr: &HumanAddr) -> u64 {
+        from_binary(&app.query(contract_addr, &QueryMsg::Count {}).unwrap()).unwrap()
+    }
+
+    fn run(messages: Vec<CosmosMsg>) -> HandleMsg {
+        HandleMsg::Run { messages }
+    }
+
+    fn call(app: &App, contract_addr: &HumanAddr, msg: &HandleMsg, send: &[Coin]) -> CosmosMsg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            callback_code_hash: app.contract_code_hash(contract_addr).unwrap(),
+            msg: to_binary(msg).unwrap(),
+            send: send.to_vec(),
+        })
+    }
+
+    /// Creates an app with two counters
+    fn setup() -> (App, HumanAddr, HumanAddr) {
+        let mut app = App::new();
+        let code_id = app.store_code(Box::new(ContractWrapper::new(init, handle, query)));
+        let a = app
+            .instantiate(code_id, "creator", &Empty {}, &[], "a")
+            .unwrap();
+        let b = app
+            .instantiate(code_id, "creator", &Empty {}, &[], "b")
+            .unwrap();
+        (app, a, b)
+    }
+
+    #[test]
+    fn execute_runs_the_messages_of_contracts() {
+        let (mut app, a, b) = setup();
+
+        let msg = run(vec![call(&app, &b, &run(vec![]), &[])]);
+        app.execute("user", &a, &msg, &[]).unwrap();
+
+        assert_eq!(count(&app, &a), 1);
+        assert_eq!(count(&app, &b), 1);
+    }
+
+    #[test]
+    fn failed_messages_revert_the_transaction() {
+        let (mut app, a, b) = setup();
+
+        let msg = run(vec![call(&app, &b, &HandleMsg::Fail {}, &[])]);
+        let err = app.execute("user", &a, &msg, &[]).unwrap_err();
+
+        assert_eq!(err, StdError::generic_err("failed on purpose"));
+        assert_eq!(count(&app, &a), 0);
+    }
+
+    #[test]
+    fn messages_must_have_the_code_hash_of_the_contract() {
+        let (mut app, a, b) = setup();
+
+        let msg = run(vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: b.clone(),
+            callback_code_hash: "00".repeat(32),
+            msg: to_binary(&run(vec![])).unwrap(),
+            send: vec![],
+        })]);
+        let err = app.execute("user", &a, &msg, &[]).unwrap_err();
+
+        assert_eq!(
+            err,
+            StdError::generic_err("Message contains mismatched contract hash")
+        );
+        assert_eq!(count(&app, &b), 0);
+    }
+
+    #[test]
+    fn contracts_query_each_other() {
+        let (mut app, a, b) = setup();
+        app.execute("user", &b, &run(vec![]), &[]).unwrap();
+
+        let msg = HandleMsg::CopyCount {
+            contract_addr: b.clone(),
+            code_hash: app.contract_code_hash(&b).unwrap(),
+        };
+        app.execute("user", &a, &msg, &[]).unwrap();
+        assert_eq!(count(&app, &a), 1);
+
+        let msg = HandleMsg::CopyCount {
+            contract_addr: b,
+            code_hash: "wrong".to_string(),
+        };
+        assert!(app.execute("user", &a, &msg, &[]).is_err());
+    }
+
+    #[test]
+    fn funds_move_with_messages() {
+        let (mut app, a, b) = setup();
+        app.set_balance("user", coins(100, "uscrt"));
+
+        let msg = run(vec![
+            call(&app, &b, &run(vec![]), &coins(30, "uscrt")),
+            CosmosMsg::Bank(BankMsg::Send {
+                from_address: a.clone(),
+                to_address: HumanAddr::from("friend"),
+                amount: coins(20, "uscrt"),
+            }),
+        ]);
+        app.execute("user", &a, &msg, &coins(60, "uscrt")).unwrap();
+
+        assert_eq!(app.balance("user"), coins(40, "uscrt"));
+        assert_eq!(app.balance(&a), coins(10, "uscrt"));
+        assert_eq!(app.balance(&b), coins(30, "uscrt"));
+        assert_eq!(app.balance("friend"), coins(20, "uscrt"));
+
+        // a doesn't have enough left, so nothing moves
+        let msg = run(vec![call(&app, &b, &run(vec![]), &coins(11, "uscrt"))]);
+        assert!(app.execute("user", &a, &msg, &coins(0, "uscrt")).is_err());
+        assert_eq!(app.balance(&a), coins(10, "uscrt"));
+        assert_eq!(app.balance(&b), coins(30, "uscrt"));
+    }
+
+    #[test]
+    fn contracts_can_only_be_senders_of_their_own_messages() {
+        let (mut app, a, b) = setup();
+
+        let err = app.execute(&a, &b, &run(vec![]), &[]).unwrap_err();
+        assert_eq!(
+            err,
+            StdError::generic_err(
+                "contract1 is a contract, which can only send messages by returning them"
+            )
+        );
+
+        let msg = run(vec![CosmosMsg::Bank(BankMsg::Send {
+            from_address: b,
+            to_address: HumanAddr::from("thief"),
+            amount: vec![],
+        })]);
+        assert!(app.execute("user", &a, &msg, &[]).is_err());
+    }
+
+    #[test]
+    fn contracts_instantiate_contracts() {
+        let (mut app, a, _) = setup();
+
+        let code_hash = app.code_hash(1).unwrap();
+        let instantiate = |label: &str| {
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                code_id: 1,
+                callback_code_hash: code_hash.clone(),
+                msg: to_binary(&Empty {}).unwrap(),
+                send: vec![],
+                label: label.to_string(),
+            })
+        };
+        let msg = run(vec![instantiate("c")]);
+        app.execute("user", &a, &msg, &[]).unwrap();
+        assert_eq!(count(&app, &HumanAddr::from("contract3")), 0);
+
+        // labels are unique
+        let msg = run(vec![instantiate("a")]);
+        assert!(app.execute("user", &a, &msg, &[]).is_err());
+    }
+}
@@ -22,6 +22,7 @@ pub enum SystemError {
     Unknown {},
     UnsupportedRequest { kind: String },
     ExceededRecursionLimit {},
+    ExceededQueryGasLimit { gas_limit: u64 },
 }
 
 impl std::error::Error for SystemError {}
@@ -47,6 +48,9 @@ impl std::fmt::Display for SystemError {
                 write!(f, "Unsupported query type: {}", kind)
             }
             SystemError::ExceededRecursionLimit {} => write!(f, "Query recursion limit exceeded"),
+            SystemError::ExceededQueryGasLimit { gas_limit } => {
+                write!(f, "Query exceeded its gas limit of {}", gas_limit)
+            }
         }
     }
 }
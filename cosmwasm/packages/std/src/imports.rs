@@ -31,6 +31,11 @@ extern "C" {
     fn canonicalize_address(source: u32, destination: u32) -> u32;
     fn humanize_address(source: u32, destination: u32) -> u32;
 
+    /// These return 0 if the signature is valid, 1 if it isn't, and 2 if any of the inputs is malformed
+    fn secp256k1_verify(message_hash_ptr: u32, signature_ptr: u32, public_key_ptr: u32) -> u32;
+    fn ed25519_verify(message_ptr: u32, signature_ptr: u32, public_key_ptr: u32) -> u32;
+    fn ed25519_batch_verify(messages_ptr: u32, signatures_ptr: u32, public_keys_ptr: u32) -> u32;
+
     /// Executes a query on the chain (import). Not to be confused with the
     /// query export, which queries the state of the contract.
     fn query_chain(request: u32) -> u32;
@@ -120,6 +125,10 @@ impl Iterator for ExternalIterator {
 
     fn next(&mut self) -> Option<Self::Item> {
         let next_result = unsafe { db_next(self.iterator_id) };
+        // A null pointer means the iterator is exhausted. An empty key is a valid key.
+        if next_result == 0 {
+            return None;
+        }
         let kv_region_ptr = next_result as *mut Region;
         let mut kv = unsafe { consume_region(kv_region_ptr) };
 
@@ -130,9 +139,6 @@ impl Iterator for ExternalIterator {
             kv[kv.len() - 2],
             kv[kv.len() - 1],
         ]) as usize;
-        if keylen == 0 {
-            return None;
-        }
 
         kv.truncate(kv.len() - 4);
         let key = kv.split_off(kv.len() - keylen);
@@ -187,6 +193,94 @@ impl Api for ExternalApi {
         let address = unsafe { consume_string_region_written_by_vm(human) };
         Ok(address.into())
     }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> StdResult<bool> {
+        let message_hash_send = build_region(message_hash);
+        let message_hash_send_ptr = &*message_hash_send as *const Region as u32;
+        let signature_send = build_region(signature);
+        let signature_send_ptr = &*signature_send as *const Region as u32;
+        let public_key_send = build_region(public_key);
+        let public_key_send_ptr = &*public_key_send as *const Region as u32;
+
+        let result = unsafe {
+            secp256k1_verify(
+                message_hash_send_ptr,
+                signature_send_ptr,
+                public_key_send_ptr,
+            )
+        };
+        signature_verification_result("secp256k1_verify", result)
+    }
+
+    fn ed25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> StdResult<bool> {
+        let message_send = build_region(message);
+        let message_send_ptr = &*message_send as *const Region as u32;
+        let signature_send = build_region(signature);
+        let signature_send_ptr = &*signature_send as *const Region as u32;
+        let public_key_send = build_region(public_key);
+        let public_key_send_ptr = &*public_key_send as *const Region as u32;
+
+        let result =
+            unsafe { ed25519_verify(message_send_ptr, signature_send_ptr, public_key_send_ptr) };
+        signature_verification_result("ed25519_verify", result)
+    }
+
+    fn ed25519_batch_verify(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[&[u8]],
+        public_keys: &[&[u8]],
+    ) -> StdResult<bool> {
+        let messages = encode_batch(messages);
+        let messages_send = build_region(&messages);
+        let messages_send_ptr = &*messages_send as *const Region as u32;
+        let signatures = encode_batch(signatures);
+        let signatures_send = build_region(&signatures);
+        let signatures_send_ptr = &*signatures_send as *const Region as u32;
+        let public_keys = encode_batch(public_keys);
+        let public_keys_send = build_region(&public_keys);
+        let public_keys_send_ptr = &*public_keys_send as *const Region as u32;
+
+        let result = unsafe {
+            ed25519_batch_verify(messages_send_ptr, signatures_send_ptr, public_keys_send_ptr)
+        };
+        signature_verification_result("ed25519_batch_verify", result)
+    }
+}
+
+/// Batches are sent to the VM as a sequence of `item_len || item`, where `item_len` is a big endian u32
+fn encode_batch(items: &[&[u8]]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(items.iter().map(|item| 4 + item.len()).sum());
+    for item in items {
+        encoded.extend_from_slice(&(item.len() as u32).to_be_bytes());
+        encoded.extend_from_slice(item);
+    }
+    encoded
+}
+
+fn signature_verification_result(import: &str, result: u32) -> StdResult<bool> {
+    match result {
+        0 => Ok(true),
+        1 => Ok(false),
+        2 => Err(StdError::generic_err(format!(
+            "{} errored: malformed input",
+            import
+        ))),
+        code => Err(StdError::generic_err(format!(
+            "{} errored: unknown error code {}",
+            import, code
+        ))),
+    }
 }
 
 /// Takes a pointer to a Region and reads the data into a String.
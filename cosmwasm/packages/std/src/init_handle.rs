@@ -137,10 +137,27 @@ impl<T: Clone + fmt::Debug + PartialEq + JsonSchema> From<WasmMsg> for CosmosMsg
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct LogAttribute {
     pub key: String,
     pub value: String,
+    /// Log attributes are encrypted by default. Public ones can be created with `plaintext_log`.
+    #[serde(default = "encrypted_by_default")]
+    pub encrypted: bool,
+}
+
+fn encrypted_by_default() -> bool {
+    true
+}
+
+impl Default for LogAttribute {
+    fn default() -> Self {
+        LogAttribute {
+            key: String::new(),
+            value: String::new(),
+            encrypted: encrypted_by_default(),
+        }
+    }
 }
 
 /// A shorthand to produce a log attribute
@@ -148,6 +165,17 @@ pub fn log<K: ToString, V: ToString>(key: K, value: V) -> LogAttribute {
     LogAttribute {
         key: key.to_string(),
         value: value.to_string(),
+        encrypted: true,
+    }
+}
+
+/// A shorthand to produce a log attribute that is emitted without encryption,
+/// so anyone can see it and filter on it (e.g. `action=transfer`)
+pub fn plaintext_log<K: ToString, V: ToString>(key: K, value: V) -> LogAttribute {
+    LogAttribute {
+        key: key.to_string(),
+        value: value.to_string(),
+        encrypted: false,
     }
 }
 
@@ -305,6 +333,10 @@ where
         self.log.push(log(key, value));
     }
 
+    pub fn add_plaintext_log<K: ToString, V: ToString>(&mut self, key: K, value: V) {
+        self.log.push(plaintext_log(key, value));
+    }
+
     pub fn add_message<U: Into<CosmosMsg<T>>>(&mut self, msg: U) {
         self.messages.push(msg.into());
     }
@@ -326,6 +358,7 @@ mod test {
         let expeceted = LogAttribute {
             key: "foo".to_string(),
             value: "42".to_string(),
+            encrypted: true,
         };
 
         assert_eq!(log("foo", "42"), expeceted);
@@ -336,6 +369,24 @@ mod test {
         assert_eq!(log("foo", 42), expeceted);
     }
 
+    #[test]
+    fn plaintext_log_works() {
+        let expeceted = LogAttribute {
+            key: "action".to_string(),
+            value: "transfer".to_string(),
+            encrypted: false,
+        };
+
+        assert_eq!(plaintext_log("action", "transfer"), expeceted);
+    }
+
+    #[test]
+    fn log_attributes_are_encrypted_by_default() {
+        let attribute: LogAttribute = from_slice(br#"{"key":"foo","value":"42"}"#).unwrap();
+        assert_eq!(attribute, log("foo", "42"));
+        assert!(LogAttribute::default().encrypted);
+    }
+
     #[test]
     fn can_deser_error_result() {
         let fail = InitResult::Err(StdError::Unauthorized { backtrace: None });
@@ -357,6 +408,7 @@ mod test {
             log: vec![LogAttribute {
                 key: "action".to_string(),
                 value: "release".to_string(),
+                encrypted: true,
             }],
         });
         let bin = to_vec(&send).expect("encode contract result");
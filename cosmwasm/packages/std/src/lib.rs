@@ -11,6 +11,8 @@ mod init_handle;
 mod iterator;
 mod math;
 mod query;
+#[cfg(feature = "random")]
+mod random;
 mod serde;
 mod storage;
 mod traits;
@@ -22,8 +24,9 @@ pub use crate::debug_print::debug_print;
 pub use crate::encoding::Binary;
 pub use crate::errors::{StdError, StdResult, SystemError, SystemResult};
 pub use crate::init_handle::{
-    log, BankMsg, Context, CosmosMsg, GovMsg, HandleResponse, HandleResult, InitResponse,
-    InitResult, LogAttribute, MigrateResponse, MigrateResult, StakingMsg, VoteOption, WasmMsg,
+    log, plaintext_log, BankMsg, Context, CosmosMsg, GovMsg, HandleResponse, HandleResult,
+    InitResponse, InitResult, LogAttribute, MigrateResponse, MigrateResult, StakingMsg, VoteOption,
+    WasmMsg,
 };
 #[cfg(feature = "iterator")]
 pub use crate::iterator::{Order, KV};
@@ -31,9 +34,12 @@ pub use crate::math::{Decimal, Uint128};
 pub use crate::query::{
     AllBalanceResponse, AllDelegationsResponse, BalanceResponse, BankQuery, BondedDenomResponse,
     BondedRatioResponse, Delegation, DistQuery, FullDelegation, GovQuery, InflationResponse,
-    MintQuery, ProposalsResponse, QueryRequest, QueryResponse, QueryResult, RewardsResponse,
-    StakingQuery, UnbondingDelegationsResponse, Validator, ValidatorsResponse, WasmQuery,
+    MintQuery, ProposalsResponse, QueryRequest, QueryResponse, QueryResult, QueryWithPermit,
+    RewardsResponse, StakingQuery, UnbondingDelegationsResponse, Validator, ValidatorsResponse,
+    WasmQuery,
 };
+#[cfg(feature = "random")]
+pub use crate::random::{random_bytes, RANDOM_BYTES_LEN};
 pub use crate::serde::{from_binary, from_slice, to_binary, to_vec};
 pub use crate::storage::MemoryStorage;
 pub use crate::traits::{Api, Extern, Querier, QuerierResult, ReadonlyStorage, Storage};
@@ -56,10 +62,13 @@ pub use crate::imports::{ExternalApi, ExternalQuerier, ExternalStorage};
 // Exposed for testing only
 // Both unit tests and integration tests are compiled to native code, so everything in here does not need to compile to Wasm.
 
+#[cfg(not(target_arch = "wasm32"))]
+mod app;
 #[cfg(not(target_arch = "wasm32"))]
 mod mock;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod testing {
+    pub use crate::app::{App, AppDeps, AppQuerier, Contract, ContractWrapper};
     pub use crate::mock::{
         mock_dependencies, mock_dependencies_with_balances, mock_env, BankQuerier, MockApi,
         MockQuerier, MockQuerierCustomHandlerResult, MockStorage, StakingQuerier,
@@ -1,5 +1,6 @@
 use serde::de::DeserializeOwned;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
 use crate::addresses::{CanonicalAddr, HumanAddr};
 use crate::coins::Coin;
@@ -109,6 +110,39 @@ impl Api for MockApi {
         let human = String::from_utf8(trimmed).map_err(StdError::invalid_utf8)?;
         Ok(HumanAddr(human))
     }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> StdResult<bool> {
+        let message = secp256k1::Message::from_slice(message_hash)
+            .map_err(|_| StdError::generic_err("Invalid input: message hash must be 32 bytes"))?;
+        let signature = secp256k1::Signature::from_compact(signature)
+            .map_err(|_| StdError::generic_err("Invalid input: malformed signature"))?;
+        let public_key = secp256k1::PublicKey::from_slice(public_key)
+            .map_err(|_| StdError::generic_err("Invalid input: malformed public key"))?;
+
+        Ok(secp256k1::Secp256k1::verification_only()
+            .verify(&message, &signature, &public_key)
+            .is_ok())
+    }
+
+    fn ed25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> StdResult<bool> {
+        let signature = ed25519_dalek::Signature::try_from(signature)
+            .map_err(|_| StdError::generic_err("Invalid input: malformed signature"))?;
+        let public_key = ed25519_dalek::PublicKey::from_bytes(public_key)
+            .map_err(|_| StdError::generic_err("Invalid input: malformed public key"))?;
+
+        // Strict verification also rejects weak keys and malleable signatures
+        Ok(public_key.verify_strict(message, &signature).is_ok())
+    }
 }
 
 /// Just set sender and sent funds for the message. The rest uses defaults.
@@ -412,6 +446,88 @@ mod test {
         assert_eq!(a, c);
     }
 
+    // Test vector 2 from RFC 8032, section 7.1
+    const ED25519_MESSAGE: [u8; 1] = [0x72];
+    const ED25519_PUBLIC_KEY: &str =
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const ED25519_SIGNATURE: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn secp256k1_verify_works() {
+        let secp = secp256k1::Secp256k1::signing_only();
+        let secret_key = secp256k1::SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let public_key = secp256k1::PublicKey::from_secret_key(&secp, &secret_key).serialize();
+        let message_hash = [42u8; 32];
+        let signature = secp
+            .sign(
+                &secp256k1::Message::from_slice(&message_hash).unwrap(),
+                &secret_key,
+            )
+            .serialize_compact();
+
+        let api = MockApi::default();
+        assert!(api
+            .secp256k1_verify(&message_hash, &signature, &public_key)
+            .unwrap());
+        assert!(!api
+            .secp256k1_verify(&[43u8; 32], &signature, &public_key)
+            .unwrap());
+        assert!(api
+            .secp256k1_verify(&message_hash[..31], &signature, &public_key)
+            .is_err());
+    }
+
+    #[test]
+    fn ed25519_verify_works() {
+        let public_key = from_hex(ED25519_PUBLIC_KEY);
+        let signature = from_hex(ED25519_SIGNATURE);
+
+        let api = MockApi::default();
+        assert!(api
+            .ed25519_verify(&ED25519_MESSAGE, &signature, &public_key)
+            .unwrap());
+        assert!(!api
+            .ed25519_verify(b"other", &signature, &public_key)
+            .unwrap());
+        assert!(api
+            .ed25519_verify(&ED25519_MESSAGE, &signature[..63], &public_key)
+            .is_err());
+    }
+
+    #[test]
+    fn ed25519_batch_verify_works() {
+        let public_key = from_hex(ED25519_PUBLIC_KEY);
+        let signature = from_hex(ED25519_SIGNATURE);
+        let message: &[u8] = &ED25519_MESSAGE;
+
+        let api = MockApi::default();
+        assert!(api
+            .ed25519_batch_verify(
+                &[message, message],
+                &[&signature, &signature],
+                &[&public_key, &public_key]
+            )
+            .unwrap());
+        assert!(!api
+            .ed25519_batch_verify(
+                &[message, b"other"],
+                &[&signature, &signature],
+                &[&public_key, &public_key]
+            )
+            .unwrap());
+        assert!(api
+            .ed25519_batch_verify(&[message], &[&signature, &signature], &[&public_key])
+            .is_err());
+        assert!(api.ed25519_batch_verify(&[], &[], &[]).unwrap());
+    }
+
     #[test]
     fn flip_addresses() {
         let api = MockApi::new(20);
@@ -125,6 +125,23 @@ impl<T: Clone + fmt::Debug + PartialEq + JsonSchema> From<WasmQuery> for QueryRe
     }
 }
 
+/// A query that was sent with a signed permit. The enclave verifies the permit before the query
+/// reaches the contract, so `signer` can be trusted.
+///
+/// Contracts accept these by adding a `WithPermit(QueryWithPermit<T>)` variant to their `QueryMsg`,
+/// renamed to the reserved key `__secret_with_permit`. The enclave only verifies queries under
+/// that key, and rejects them if they don't carry a valid permit for the chain it runs on.
+/// The enclave doesn't know which contract is queried, so the contract must check that it is in
+/// `allowed_contracts`. Revoking permits by `permit_name` is up to the contract as well.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct QueryWithPermit<T> {
+    pub query: T,
+    pub signer: HumanAddr,
+    pub permit_name: String,
+    pub allowed_contracts: Vec<HumanAddr>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct BalanceResponse {
@@ -285,3 +302,32 @@ pub struct InflationResponse {
 pub struct BondedRatioResponse {
     pub bonded_ratio: String,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::serde::from_slice;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    enum QueryMsg {
+        Balance {},
+        #[serde(rename = "__secret_with_permit")]
+        WithPermit(QueryWithPermit<Box<QueryMsg>>),
+    }
+
+    #[test]
+    fn query_with_permit_parses() {
+        let msg = br#"{"__secret_with_permit":{"query":{"balance":{}},"signer":"secret1signer","permit_name":"balance","allowed_contracts":["secret1contract"]}}"#;
+        let parsed: QueryMsg = from_slice(msg).unwrap();
+        assert_eq!(
+            parsed,
+            QueryMsg::WithPermit(QueryWithPermit {
+                query: Box::new(QueryMsg::Balance {}),
+                signer: HumanAddr::from("secret1signer"),
+                permit_name: "balance".to_string(),
+                allowed_contracts: vec![HumanAddr::from("secret1contract")],
+            })
+        );
+    }
+}
@@ -0,0 +1,68 @@
+/// The amount of bytes returned by every call to `random_bytes`
+pub const RANDOM_BYTES_LEN: usize = 32;
+
+#[cfg(target_arch = "wasm32")]
+mod inner {
+    use super::RANDOM_BYTES_LEN;
+    use crate::memory::{consume_region, Region};
+
+    extern "C" {
+        fn random() -> u32;
+    }
+
+    pub fn random_bytes() -> [u8; RANDOM_BYTES_LEN] {
+        let region_ptr = unsafe { random() };
+        let bytes = unsafe { consume_region(region_ptr as *mut Region) };
+
+        let mut result = [0u8; RANDOM_BYTES_LEN];
+        result.copy_from_slice(&bytes);
+        result
+    }
+}
+
+/// Returns random bytes for the current execution.
+///
+/// The enclave derives them from a secret of the consensus seed, the current block and its
+/// transactions, the address of the contract, and the transaction (or the calling contract) and
+/// message that triggered the execution. So all the validators get the same bytes, but they can't
+/// be predicted outside of an enclave, or before the transaction is included in a block. They
+/// differ between executions in the same block, and every call returns different bytes.
+///
+/// This is only available in `init`, `handle` and `migrate`. Calling it in `query` fails the query,
+/// and calling it while simulating a transaction fails the simulation.
+#[cfg(target_arch = "wasm32")]
+pub use inner::random_bytes;
+
+/// In unit tests there is no enclave, so this returns predictable bytes that change on every call
+#[cfg(not(target_arch = "wasm32"))]
+pub fn random_bytes() -> [u8; RANDOM_BYTES_LEN] {
+    use std::cell::Cell;
+
+    thread_local! {
+        static CALLS: Cell<u64> = Cell::new(0);
+    }
+
+    let calls = CALLS.with(|calls| {
+        let current = calls.get();
+        calls.set(current + 1);
+        current
+    });
+
+    let mut result = [0u8; RANDOM_BYTES_LEN];
+    for chunk in result.chunks_mut(8) {
+        chunk.copy_from_slice(&calls.to_be_bytes());
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn random_bytes_change_on_every_call() {
+        let first = random_bytes();
+        let second = random_bytes();
+        assert_ne!(first, second);
+    }
+}
@@ -8,7 +8,7 @@ use std::ops::{Bound, RangeBounds};
 use crate::iterator::{Order, KV};
 use crate::traits::{ReadonlyStorage, Storage};
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct MemoryStorage {
     data: BTreeMap<Vec<u8>, Vec<u8>>,
 }
@@ -74,7 +74,7 @@ pub trait Storage: ReadonlyStorage {
 /// Api are callbacks to system functions defined outside of the wasm modules.
 /// This is a trait to allow Mocks in the test code.
 ///
-/// Currently it just supports address conversion, we could add eg. crypto functions here.
+/// Currently it supports address conversion and signature verification.
 /// These should all be pure (stateless) functions. If you need state, you probably want
 /// to use the Querier.
 ///
@@ -83,6 +83,65 @@ pub trait Storage: ReadonlyStorage {
 pub trait Api: Copy + Clone + Send {
     fn canonical_address(&self, human: &HumanAddr) -> StdResult<CanonicalAddr>;
     fn human_address(&self, canonical: &CanonicalAddr) -> StdResult<HumanAddr>;
+
+    /// Verifies a secp256k1 signature of a 32 byte message hash.
+    /// The signature is in the 64 byte compact format, and must be normalized to the lower S value.
+    /// The public key may be compressed (33 bytes) or uncompressed (65 bytes).
+    ///
+    /// Returns `Ok(false)` if the signature is not valid, and an error if any of the inputs is malformed.
+    /// The default implementation returns an error, so existing implementations keep compiling.
+    fn secp256k1_verify(
+        &self,
+        _message_hash: &[u8],
+        _signature: &[u8],
+        _public_key: &[u8],
+    ) -> StdResult<bool> {
+        Err(StdError::generic_err(
+            "secp256k1_verify is not supported by this Api",
+        ))
+    }
+
+    /// Verifies an ed25519 signature of a message.
+    /// The signature is 64 bytes long, and the public key is 32 bytes long.
+    ///
+    /// Returns `Ok(false)` if the signature is not valid, and an error if any of the inputs is malformed.
+    /// The default implementation returns an error, so existing implementations keep compiling.
+    fn ed25519_verify(
+        &self,
+        _message: &[u8],
+        _signature: &[u8],
+        _public_key: &[u8],
+    ) -> StdResult<bool> {
+        Err(StdError::generic_err(
+            "ed25519_verify is not supported by this Api",
+        ))
+    }
+
+    /// Verifies that `signatures[i]` is a valid ed25519 signature of `messages[i]` by `public_keys[i]`,
+    /// for all `i`. This saves the overhead of calling into the VM for every signature.
+    ///
+    /// Returns `Ok(true)` only if all the signatures are valid, and an error if any of the inputs is
+    /// malformed, or if the lists have different lengths.
+    /// The default implementation calls `ed25519_verify` for every signature.
+    fn ed25519_batch_verify(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[&[u8]],
+        public_keys: &[&[u8]],
+    ) -> StdResult<bool> {
+        if messages.len() != signatures.len() || signatures.len() != public_keys.len() {
+            return Err(StdError::generic_err(
+                "Invalid input: messages, signatures and public keys must have the same length",
+            ));
+        }
+
+        for ((message, signature), public_key) in messages.iter().zip(signatures).zip(public_keys) {
+            if !self.ed25519_verify(message, signature, public_key)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
 }
 
 /// A short-hand alias for the two-level query result (1. accessing the contract, 2. executing query in the contract)
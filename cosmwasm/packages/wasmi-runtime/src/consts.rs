@@ -51,12 +51,44 @@ lazy_static! {
     pub static ref REGISTRATION_KEY_SEALING_PATH: String = env::var(SCRT_SGX_STORAGE_ENV_VAR)
         .unwrap_or_else(|_| "./.sgx_secrets/".to_string())
         + "new_node_seed_exchange_keypair.sealed";
+    pub static ref CHAIN_ID_SEALING_PATH: String = env::var(SCRT_SGX_STORAGE_ENV_VAR)
+        .unwrap_or_else(|_| "./.sgx_secrets/".to_string())
+        + "chain_id.sealed";
+    pub static ref ATTESTATION_POLICY_SEALING_PATH: String = env::var(SCRT_SGX_STORAGE_ENV_VAR)
+        .unwrap_or_else(|_| "./.sgx_secrets/".to_string())
+        + "attestation_policy.sealed";
+    pub static ref ACTIVE_SEED_EPOCH_SEALING_PATH: String = env::var(SCRT_SGX_STORAGE_ENV_VAR)
+        .unwrap_or_else(|_| "./.sgx_secrets/".to_string())
+        + "active_seed_epoch.sealed";
+}
+
+/// The genesis consensus seed of native builds, which are only used for testing
+#[cfg(feature = "native")]
+pub const NATIVE_CONSENSUS_SEED: [u8; 32] = *b"native-test-consensus-seed-00000";
+
+/// The chain id of native builds, which are only used for testing
+#[cfg(feature = "native")]
+pub const NATIVE_CHAIN_ID: &str = "native-test-chain";
+
+/// The seed of the genesis epoch is sealed at `CONSENSUS_SEED_SEALING_PATH`, every later epoch gets its own file
+pub fn consensus_seed_epoch_sealing_path(epoch: u32) -> String {
+    env::var(SCRT_SGX_STORAGE_ENV_VAR).unwrap_or_else(|_| "./.sgx_secrets/".to_string())
+        + &format!("consensus_seed.{}.sealed", epoch)
+}
+
+pub fn seed_exch_epoch_certificate_save_path(epoch: u32) -> String {
+    format!("node-master-cert.{}.der", epoch)
+}
+
+pub fn io_epoch_certificate_save_path(epoch: u32) -> String {
+    format!("io-master-cert.{}.der", epoch)
 }
 
 pub const CONSENSUS_SEED_EXCHANGE_KEYPAIR_DERIVE_ORDER: u32 = 1;
 pub const CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER: u32 = 2;
 pub const CONSENSUS_STATE_IKM_DERIVE_ORDER: u32 = 3;
 pub const CONSENSUS_CALLBACK_SECRET_DERIVE_ORDER: u32 = 4;
+pub const CONSENSUS_RANDOM_SECRET_DERIVE_ORDER: u32 = 5;
 
 pub const LOG_LEVEL_ENV_VAR: &str = "LOG_LEVEL";
 pub const SCRT_SGX_STORAGE_ENV_VAR: &str = "SCRT_SGX_STORAGE";
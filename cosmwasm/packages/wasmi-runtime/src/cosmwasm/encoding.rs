@@ -4,7 +4,7 @@ use std::fmt;
 
 use serde::{de, ser, Deserialize, Deserializer, Serialize};
 
-use enclave_ffi_types::EnclaveError;
+use enclave_ffi_types::{DeserializationTarget, EnclaveError};
 use log::*;
 use sgx_types::*;
 
@@ -23,7 +23,9 @@ impl Binary {
     pub fn from_base64(encoded: &str) -> Result<Self, EnclaveError> {
         let binary = base64::decode(encoded).map_err(|err| {
             warn!("Failed to decode base64 string: {:?}", err.to_string());
-            EnclaveError::FailedToDeserialize
+            EnclaveError::FailedToDeserialize {
+                target: DeserializationTarget::Base64,
+            }
         })?;
         Ok(Binary(binary))
     }
@@ -2,6 +2,32 @@ pub mod coins;
 pub mod encoding;
 pub mod math;
 pub mod query;
+pub mod sign_doc_direct;
 pub mod std_error;
 pub mod system_error;
 pub mod types;
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use crate::count_failures;
+
+    pub fn run_tests() {
+        println!();
+        let mut failures = 0;
+
+        count_failures!(failures, {
+            sign_doc_direct::tests::test_decode_direct_execute_msg();
+            sign_doc_direct::tests::test_decode_direct_instantiate_msg_skips_other_modules();
+            sign_doc_direct::tests::test_decode_direct_skips_msgs_of_other_senders();
+            sign_doc_direct::tests::test_decode_direct_requires_signer_in_auth_info();
+            sign_doc_direct::tests::test_decode_direct_rejects_malformed_sign_doc();
+            types::tests::test_pubkey_kind_is_chosen_by_type();
+            types::tests::test_pubkey_kind_rejects_key_of_another_type();
+        });
+
+        if failures != 0 {
+            panic!("{}: {} tests failed", file!(), failures);
+        }
+    }
+}
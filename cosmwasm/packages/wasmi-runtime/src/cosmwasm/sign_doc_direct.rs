@@ -0,0 +1,373 @@
+//! Decoding of the messages that are signed in `SIGN_MODE_DIRECT`.
+//!
+//! The messages are generated from the .proto files of the repo, see `crate::proto`.
+
+use log::*;
+use prost::Message;
+
+use enclave_ffi_types::{DeserializationTarget, EnclaveError};
+
+use super::encoding::Binary;
+use super::types::{CanonicalAddr, Coin, HumanAddr, PubKeyKind, SignDocWasmMsg};
+use crate::crypto::traits::PubKey;
+use crate::proto::cosmos::base::v1beta1::Coin as ProtoCoin;
+use crate::proto::cosmos::tx::signing::v1beta1::SignMode;
+use crate::proto::cosmos::tx::v1beta1::{mode_info, AuthInfo, ModeInfo, SignDoc, TxBody};
+use crate::proto::secret::compute::v1beta1::{MsgExecuteContract, MsgInstantiateContract};
+
+pub use crate::proto::google::protobuf::Any;
+
+pub const MSG_EXECUTE_CONTRACT_TYPE_URL: &str = "/secret.compute.v1beta1.MsgExecuteContract";
+pub const MSG_INSTANTIATE_CONTRACT_TYPE_URL: &str =
+    "/secret.compute.v1beta1.MsgInstantiateContract";
+
+/// Decodes the compute messages of a `SIGN_MODE_DIRECT` sign doc into the same form as the
+/// messages of an amino sign doc, so they can go through the same checks.
+///
+/// The `AuthInfo` of the sign doc must list `signer` as a signer in `SIGN_MODE_DIRECT`.
+/// Messages of other modules and of other senders are skipped, since they can never match the
+/// message being verified.
+pub fn decode_wasm_msgs(
+    sign_bytes: &[u8],
+    signer: &PubKeyKind,
+) -> Result<Vec<SignDocWasmMsg>, EnclaveError> {
+    let sign_doc = SignDoc::decode(sign_bytes).map_err(|err| {
+        warn!(
+            "got an error while trying to decode direct sign doc: {}",
+            err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::SignDoc,
+        }
+    })?;
+    let tx_body = TxBody::decode(sign_doc.body_bytes.as_slice()).map_err(|err| {
+        warn!("got an error while trying to decode tx body: {}", err);
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::SignDoc,
+        }
+    })?;
+
+    let auth_info = AuthInfo::decode(sign_doc.auth_info_bytes.as_slice()).map_err(|err| {
+        warn!("got an error while trying to decode auth info: {}", err);
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::SignDoc,
+        }
+    })?;
+
+    verify_signer_info(&auth_info, signer)?;
+
+    let signer_address = signer.get_address();
+    let mut msgs = vec![];
+    for any in tx_body.messages.iter() {
+        let (sender, msg) = match any.type_url.as_str() {
+            MSG_EXECUTE_CONTRACT_TYPE_URL => decode_execute_msg(&any.value)?,
+            MSG_INSTANTIATE_CONTRACT_TYPE_URL => decode_instantiate_msg(&any.value)?,
+            other => {
+                trace!("skipping signed message of type {:?}", other);
+                continue;
+            }
+        };
+
+        if sender != signer_address {
+            trace!(
+                "skipping signed message of sender {:?}, which is not the signer {:?}",
+                sender,
+                signer_address
+            );
+            continue;
+        }
+
+        msgs.push(msg);
+    }
+
+    Ok(msgs)
+}
+
+/// Checks that the signer is listed in the `AuthInfo`, and that it signed in `SIGN_MODE_DIRECT`
+fn verify_signer_info(auth_info: &AuthInfo, signer: &PubKeyKind) -> Result<(), EnclaveError> {
+    let signer_key = signer.proto_any();
+
+    let signer_info = auth_info
+        .signer_infos
+        .iter()
+        .find(|signer_info| signer_info.public_key.as_ref() == Some(&signer_key))
+        .ok_or_else(|| {
+            warn!("the signer of the tx is not listed in its auth info");
+            EnclaveError::FailedTxVerification
+        })?;
+
+    match &signer_info.mode_info {
+        Some(mode_info) if is_direct_mode(mode_info) => Ok(()),
+        other => {
+            warn!(
+                "the signer of the tx didn't sign in SIGN_MODE_DIRECT: {:?}",
+                other
+            );
+            Err(EnclaveError::FailedTxVerification)
+        }
+    }
+}
+
+/// Multisig signers are in `SIGN_MODE_DIRECT` when all their keys are
+fn is_direct_mode(mode_info: &ModeInfo) -> bool {
+    match &mode_info.sum {
+        Some(mode_info::Sum::Single(single)) => single.mode == SignMode::Direct as i32,
+        Some(mode_info::Sum::Multi(multi)) => {
+            !multi.mode_infos.is_empty() && multi.mode_infos.iter().all(is_direct_mode)
+        }
+        None => false,
+    }
+}
+
+fn decode_execute_msg(bytes: &[u8]) -> Result<(CanonicalAddr, SignDocWasmMsg), EnclaveError> {
+    let msg = MsgExecuteContract::decode(bytes).map_err(|err| {
+        warn!(
+            "got an error while trying to decode MsgExecuteContract: {}",
+            err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::SignDoc,
+        }
+    })?;
+
+    let contract =
+        HumanAddr::from_canonical(&CanonicalAddr(Binary(msg.contract))).map_err(|err| {
+            warn!(
+                "got an invalid contract address in MsgExecuteContract: {:?}",
+                err
+            );
+            EnclaveError::FailedToDeserialize {
+                target: DeserializationTarget::Address,
+            }
+        })?;
+
+    Ok((
+        CanonicalAddr(Binary(msg.sender)),
+        SignDocWasmMsg::Execute {
+            contract,
+            msg: Binary(msg.msg).to_base64(),
+            sent_funds: into_coins(msg.sent_funds),
+            callback_sig: non_empty(msg.callback_sig),
+        },
+    ))
+}
+
+fn decode_instantiate_msg(bytes: &[u8]) -> Result<(CanonicalAddr, SignDocWasmMsg), EnclaveError> {
+    let msg = MsgInstantiateContract::decode(bytes).map_err(|err| {
+        warn!(
+            "got an error while trying to decode MsgInstantiateContract: {}",
+            err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::SignDoc,
+        }
+    })?;
+
+    Ok((
+        CanonicalAddr(Binary(msg.sender)),
+        SignDocWasmMsg::Instantiate {
+            code_id: msg.code_id.to_string(),
+            init_msg: Binary(msg.init_msg).to_base64(),
+            init_funds: into_coins(msg.init_funds),
+            label: Some(msg.label),
+            callback_sig: non_empty(msg.callback_sig),
+        },
+    ))
+}
+
+fn into_coins(coins: Vec<ProtoCoin>) -> Vec<Coin> {
+    coins
+        .into_iter()
+        .map(|coin| Coin {
+            denom: coin.denom,
+            amount: coin.amount,
+        })
+        .collect()
+}
+
+/// Protobuf can't tell an empty field from a missing one
+fn non_empty(bytes: Vec<u8>) -> Option<Vec<u8>> {
+    if bytes.is_empty() {
+        None
+    } else {
+        Some(bytes)
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use std::convert::TryFrom;
+
+    use crate::crypto::Ed25519PubKey;
+    use crate::proto::cosmos::tx::v1beta1::SignerInfo;
+
+    fn encode<M: Message>(message: &M) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(message.encoded_len());
+        message.encode(&mut buf).unwrap();
+        buf
+    }
+
+    fn pubkey(key_byte: u8) -> PubKeyKind {
+        PubKeyKind::Ed25519(Ed25519PubKey::try_from(vec![key_byte; 32]).unwrap())
+    }
+
+    fn auth_info(signer: &PubKeyKind, mode: SignMode) -> AuthInfo {
+        AuthInfo {
+            signer_infos: vec![SignerInfo {
+                public_key: Some(signer.proto_any()),
+                mode_info: Some(ModeInfo {
+                    sum: Some(mode_info::Sum::Single(mode_info::Single {
+                        mode: mode as i32,
+                    })),
+                }),
+                sequence: 0,
+            }],
+        }
+    }
+
+    fn sign_bytes(messages: Vec<Any>, auth_info: &AuthInfo) -> Vec<u8> {
+        let tx_body = TxBody {
+            messages,
+            memo: "memo".to_string(),
+            timeout_height: 0,
+        };
+        encode(&SignDoc {
+            body_bytes: encode(&tx_body),
+            auth_info_bytes: encode(auth_info),
+            chain_id: "secret-1".to_string(),
+            account_number: 7,
+        })
+    }
+
+    fn execute_msg(sender: &PubKeyKind, contract: &CanonicalAddr) -> Any {
+        let execute = MsgExecuteContract {
+            sender: sender.get_address().as_slice().to_vec(),
+            contract: contract.as_slice().to_vec(),
+            msg: b"encrypted msg".to_vec(),
+            callback_code_hash: "".to_string(),
+            sent_funds: vec![ProtoCoin {
+                denom: "uscrt".to_string(),
+                amount: "1000".to_string(),
+            }],
+            callback_sig: vec![],
+        };
+        Any {
+            type_url: MSG_EXECUTE_CONTRACT_TYPE_URL.to_string(),
+            value: encode(&execute),
+        }
+    }
+
+    pub fn test_decode_direct_execute_msg() {
+        let signer = pubkey(1);
+        let contract = CanonicalAddr(Binary(vec![0x11; 20]));
+        let bytes = sign_bytes(
+            vec![execute_msg(&signer, &contract)],
+            &auth_info(&signer, SignMode::Direct),
+        );
+
+        let msgs = decode_wasm_msgs(&bytes, &signer).unwrap();
+
+        assert_eq!(
+            msgs,
+            vec![SignDocWasmMsg::Execute {
+                contract: HumanAddr::from_canonical(&contract).unwrap(),
+                msg: Binary(b"encrypted msg".to_vec()).to_base64(),
+                sent_funds: vec![Coin {
+                    denom: "uscrt".to_string(),
+                    amount: "1000".to_string(),
+                }],
+                callback_sig: None,
+            }]
+        );
+    }
+
+    pub fn test_decode_direct_instantiate_msg_skips_other_modules() {
+        let signer = pubkey(1);
+        let instantiate = MsgInstantiateContract {
+            sender: signer.get_address().as_slice().to_vec(),
+            callback_code_hash: "".to_string(),
+            code_id: 3,
+            label: "label".to_string(),
+            init_msg: b"encrypted init msg".to_vec(),
+            init_funds: vec![],
+            callback_sig: vec![],
+        };
+        let bytes = sign_bytes(
+            vec![
+                Any {
+                    type_url: "/cosmos.bank.v1beta1.MsgSend".to_string(),
+                    value: vec![0xff, 0xff],
+                },
+                Any {
+                    type_url: MSG_INSTANTIATE_CONTRACT_TYPE_URL.to_string(),
+                    value: encode(&instantiate),
+                },
+            ],
+            &auth_info(&signer, SignMode::Direct),
+        );
+
+        let msgs = decode_wasm_msgs(&bytes, &signer).unwrap();
+
+        assert_eq!(
+            msgs,
+            vec![SignDocWasmMsg::Instantiate {
+                code_id: "3".to_string(),
+                init_msg: Binary(b"encrypted init msg".to_vec()).to_base64(),
+                init_funds: vec![],
+                label: Some("label".to_string()),
+                callback_sig: None,
+            }]
+        );
+    }
+
+    pub fn test_decode_direct_skips_msgs_of_other_senders() {
+        let signer = pubkey(1);
+        let contract = CanonicalAddr(Binary(vec![0x11; 20]));
+        let bytes = sign_bytes(
+            vec![execute_msg(&pubkey(2), &contract)],
+            &auth_info(&signer, SignMode::Direct),
+        );
+
+        assert_eq!(decode_wasm_msgs(&bytes, &signer).unwrap(), vec![]);
+    }
+
+    pub fn test_decode_direct_requires_signer_in_auth_info() {
+        let signer = pubkey(1);
+        let contract = CanonicalAddr(Binary(vec![0x11; 20]));
+        let msgs = vec![execute_msg(&signer, &contract)];
+
+        let bytes = sign_bytes(msgs.clone(), &auth_info(&pubkey(2), SignMode::Direct));
+        assert!(matches!(
+            decode_wasm_msgs(&bytes, &signer),
+            Err(EnclaveError::FailedTxVerification)
+        ));
+
+        let bytes = sign_bytes(msgs, &auth_info(&signer, SignMode::LegacyAminoJson));
+        assert!(matches!(
+            decode_wasm_msgs(&bytes, &signer),
+            Err(EnclaveError::FailedTxVerification)
+        ));
+    }
+
+    pub fn test_decode_direct_rejects_malformed_sign_doc() {
+        let signer = pubkey(1);
+        assert!(matches!(
+            decode_wasm_msgs(b"\xff\xff\xff", &signer),
+            Err(EnclaveError::FailedToDeserialize { .. })
+        ));
+
+        let bytes = sign_bytes(
+            vec![Any {
+                type_url: MSG_EXECUTE_CONTRACT_TYPE_URL.to_string(),
+                value: vec![0xff, 0xff],
+            }],
+            &auth_info(&signer, SignMode::Direct),
+        );
+        assert!(matches!(
+            decode_wasm_msgs(&bytes, &signer),
+            Err(EnclaveError::FailedToDeserialize { .. })
+        ));
+    }
+}
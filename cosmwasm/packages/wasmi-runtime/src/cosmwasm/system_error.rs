@@ -24,6 +24,7 @@ pub enum SystemError {
     Unknown {},
     UnsupportedRequest { kind: String },
     ExceededRecursionLimit {},
+    ExceededQueryGasLimit { gas_limit: u64 },
 }
 
 pub type SystemResult<T> = Result<T, SystemError>;
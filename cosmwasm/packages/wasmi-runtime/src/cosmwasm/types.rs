@@ -12,11 +12,14 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use super::encoding::Binary;
+use super::sign_doc_direct::Any;
 use crate::consts::BECH32_PREFIX_ACC_ADDR;
 use crate::crypto::multisig::MultisigThresholdPubKey;
 use crate::crypto::secp256k1::Secp256k1PubKey;
+use crate::crypto::secp256r1::Secp256r1PubKey;
 use crate::crypto::traits::PubKey;
 use crate::crypto::CryptoError;
+use crate::crypto::Ed25519PubKey;
 use bech32::{FromBase32, ToBase32};
 use serde_json::Value;
 
@@ -96,6 +99,19 @@ pub struct Env {
     pub contract_key: Option<String>,
     #[serde(default)]
     pub contract_code_hash: String,
+    /// Set for contracts that were migrated, see `generate_contract_key_proof`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_key_proof: Option<String>,
+    /// Only set when migrating, see `generate_contract_admin_proof`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract_admin_proof: Option<String>,
+    /// The epoch of the consensus seed the chain currently uses. Missing means the genesis epoch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed_epoch: Option<u32>,
+    /// The hash of the transactions of the block the call is included in, see
+    /// `generate_random_key`. Missing in queries and simulations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_txs_hash: Option<Binary>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
@@ -302,10 +318,28 @@ impl<T: Clone + fmt::Debug + PartialEq> From<WasmMsg> for CosmosMsg<T> {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+// This should be in correlation with cosmwasm-std/init_handle's LogAttribute
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct LogAttribute {
     pub key: String,
     pub value: String,
+    /// Contracts built before this field existed only have encrypted log attributes
+    #[serde(default = "encrypted_by_default")]
+    pub encrypted: bool,
+}
+
+fn encrypted_by_default() -> bool {
+    true
+}
+
+impl Default for LogAttribute {
+    fn default() -> Self {
+        LogAttribute {
+            key: String::new(),
+            value: String::new(),
+            encrypted: encrypted_by_default(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -342,6 +376,7 @@ pub fn log(key: &str, value: &str) -> LogAttribute {
     LogAttribute {
         key: key.to_string(),
         value: value.to_string(),
+        encrypted: true,
     }
 }
 
@@ -363,16 +398,27 @@ impl CosmosSignature {
     }
 }
 
+/// Serialized in the amino json format, where the type of the key is tagged by its amino name,
+/// e.g. `{"type": "tendermint/PubKeySecp256k1", "value": "<base64 key>"}`.
+/// This is how Go sends the keys, and how wallets sign them into permits.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-#[serde(untagged)]
+#[serde(tag = "type", content = "value")]
 pub enum PubKeyKind {
+    #[serde(rename = "tendermint/PubKeyEd25519")]
+    Ed25519(Ed25519PubKey),
+    #[serde(rename = "tendermint/PubKeySecp256r1")]
+    Secp256r1(Secp256r1PubKey),
+    #[serde(rename = "tendermint/PubKeySecp256k1")]
     Secp256k1(Secp256k1PubKey),
+    #[serde(rename = "tendermint/PubKeyMultisigThreshold")]
     Multisig(MultisigThresholdPubKey),
 }
 
 impl PubKey for PubKeyKind {
     fn get_address(&self) -> CanonicalAddr {
         match self {
+            PubKeyKind::Ed25519(pubkey) => pubkey.get_address(),
+            PubKeyKind::Secp256r1(pubkey) => pubkey.get_address(),
             PubKeyKind::Secp256k1(pubkey) => pubkey.get_address(),
             PubKeyKind::Multisig(pubkey) => pubkey.get_address(),
         }
@@ -380,13 +426,26 @@ impl PubKey for PubKeyKind {
 
     fn bytes(&self) -> Vec<u8> {
         match self {
+            PubKeyKind::Ed25519(pubkey) => pubkey.bytes(),
+            PubKeyKind::Secp256r1(pubkey) => pubkey.bytes(),
             PubKeyKind::Secp256k1(pubkey) => pubkey.bytes(),
             PubKeyKind::Multisig(pubkey) => pubkey.bytes(),
         }
     }
 
+    fn proto_any(&self) -> Any {
+        match self {
+            PubKeyKind::Ed25519(pubkey) => pubkey.proto_any(),
+            PubKeyKind::Secp256r1(pubkey) => pubkey.proto_any(),
+            PubKeyKind::Secp256k1(pubkey) => pubkey.proto_any(),
+            PubKeyKind::Multisig(pubkey) => pubkey.proto_any(),
+        }
+    }
+
     fn verify_bytes(&self, bytes: &[u8], sig: &[u8]) -> Result<(), CryptoError> {
         match self {
+            PubKeyKind::Ed25519(pubkey) => pubkey.verify_bytes(bytes, sig),
+            PubKeyKind::Secp256r1(pubkey) => pubkey.verify_bytes(bytes, sig),
             PubKeyKind::Secp256k1(pubkey) => pubkey.verify_bytes(bytes, sig),
             PubKeyKind::Multisig(pubkey) => pubkey.verify_bytes(bytes, sig),
         }
@@ -409,6 +468,25 @@ pub struct SigInfo {
     pub sign_bytes: Binary,
     pub signature: CosmosSignature,
     pub callback_sig: Option<Binary>,
+    /// The encoding of `sign_bytes`. Older nodes don't send it, and only ever sign amino json.
+    #[serde(default)]
+    pub sign_mode: SignMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SignMode {
+    /// `sign_bytes` is an amino json `SignDoc`
+    #[serde(rename = "SIGN_MODE_LEGACY_AMINO_JSON")]
+    LegacyAminoJson,
+    /// `sign_bytes` is a protobuf `cosmos.tx.v1beta1.SignDoc`
+    #[serde(rename = "SIGN_MODE_DIRECT")]
+    Direct,
+}
+
+impl Default for SignMode {
+    fn default() -> Self {
+        SignMode::LegacyAminoJson
+    }
 }
 
 // This struct is basically the smae as WasmMsg, but serializes/deserializes differently
@@ -431,4 +509,64 @@ pub enum SignDocWasmMsg {
         label: Option<String>,
         callback_sig: Option<Vec<u8>>,
     },
+    #[serde(alias = "wasm/MsgMigrateContract")]
+    Migrate {
+        contract: HumanAddr,
+        code_id: String,
+        /// msg is the json-encoded MigrateMsg struct (as raw Binary)
+        msg: String,
+    },
+}
+
+/// A permit that lets its signer authenticate to contracts in queries, where there is no transaction
+/// sender. Permits are signed offline, as if they were a transaction with a single `query_permit` msg.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct QueryPermit {
+    pub params: PermitParams,
+    pub signature: CosmosSignature,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PermitParams {
+    /// Lets the signer revoke the permit, by asking the contracts to stop accepting this name
+    pub permit_name: String,
+    pub allowed_contracts: Vec<HumanAddr>,
+    pub chain_id: String,
+}
+
+/// The query a contract gets after the enclave verified the permit it was sent with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct QueryWithPermit {
+    pub query: Value,
+    pub signer: HumanAddr,
+    pub permit_name: String,
+    pub allowed_contracts: Vec<HumanAddr>,
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn parse_pubkey(key_type: &str, key: Vec<u8>) -> serde_json::Result<PubKeyKind> {
+        serde_json::from_value(json!({ "type": key_type, "value": Binary(key) }))
+    }
+
+    pub fn test_pubkey_kind_is_chosen_by_type() {
+        let pubkey = parse_pubkey("tendermint/PubKeySecp256k1", vec![2u8; 33]).unwrap();
+        assert!(matches!(pubkey, PubKeyKind::Secp256k1(_)));
+
+        let pubkey = parse_pubkey("tendermint/PubKeyEd25519", vec![2u8; 32]).unwrap();
+        assert!(matches!(pubkey, PubKeyKind::Ed25519(_)));
+    }
+
+    pub fn test_pubkey_kind_rejects_key_of_another_type() {
+        // A compressed key is a valid secp256k1 key, but we only accept uncompressed secp256r1 keys
+        assert!(parse_pubkey("tendermint/PubKeySecp256r1", vec![2u8; 33]).is_err());
+        assert!(parse_pubkey("tendermint/PubKeyEd25519", vec![2u8; 33]).is_err());
+        assert!(parse_pubkey("tendermint/PubKeySr25519", vec![2u8; 32]).is_err());
+    }
 }
@@ -1,3 +1,10 @@
+use std::convert::TryFrom;
+
+use log::*;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
 use super::keys::DhKey;
 
 use super::traits::{AlignedMemory, ExportECKey, EC_256_PRIVATE_KEY_SIZE};
@@ -5,12 +12,22 @@ use sgx_types::sgx_align_ec256_private_t;
 
 // use x25519_dalek;
 
+use crate::cosmwasm::encoding::Binary;
+use crate::cosmwasm::sign_doc_direct::Any;
+use crate::cosmwasm::types::CanonicalAddr;
+use crate::crypto::pubkey_encoding::{amino_encode, to_any, ProtoPubKey};
+use crate::crypto::traits::PubKey;
 use crate::crypto::CryptoError;
 
+#[cfg(not(feature = "native"))]
 use super::rng::rand_slice;
 
 pub const SECRET_KEY_SIZE: usize = 32;
 pub const PUBLIC_KEY_SIZE: usize = 32;
+pub const SIGNATURE_SIZE: usize = 64;
+
+const ED25519_PREFIX: [u8; 4] = [22, 36, 222, 100];
+const ED25519_TYPE_URL: &str = "/cosmos.crypto.ed25519.PubKey";
 
 type AlignedEc256PrivateKey = sgx_align_ec256_private_t;
 
@@ -45,6 +62,7 @@ pub struct KeyPair {
 }
 
 impl KeyPair {
+    #[cfg(not(feature = "native"))]
     pub fn new() -> Result<Self, CryptoError> {
         let mut secret_key = Ed25519PrivateKey::default();
         rand_slice(secret_key.as_mut())?;
@@ -66,6 +84,7 @@ impl KeyPair {
 
         *ss.as_bytes()
     }
+    #[cfg(not(feature = "native"))]
     pub fn get_privkey(&self) -> &[u8; SECRET_KEY_SIZE] {
         self.secret_key.key_ref()
     }
@@ -93,3 +112,181 @@ impl<T: AlignedMemory + ExportECKey> From<T> for KeyPair {
         }
     }
 }
+
+/// Verifies an ed25519 signature, as defined in RFC 8032.
+/// Returns an error if the signature or the public key have the wrong length, and `Ok(false)` if
+/// the signature is not valid.
+pub fn verify_signature(
+    message: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<bool, CryptoError> {
+    if public_key.len() != PUBLIC_KEY_SIZE {
+        return Err(CryptoError::KeyError);
+    }
+    if signature.len() != SIGNATURE_SIZE {
+        return Err(CryptoError::ParsingError);
+    }
+
+    Ok(UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(message, signature)
+        .is_ok())
+}
+
+/// The public key of an account that signs with ed25519.
+/// Serialized as the base64 of the raw 32 bytes of the key.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "Binary", into = "Binary")]
+pub struct Ed25519PubKey(Vec<u8>);
+
+impl TryFrom<Vec<u8>> for Ed25519PubKey {
+    type Error = String;
+
+    fn try_from(key: Vec<u8>) -> Result<Self, Self::Error> {
+        if key.len() != PUBLIC_KEY_SIZE {
+            return Err(format!(
+                "expected {} bytes, got {}",
+                PUBLIC_KEY_SIZE,
+                key.len()
+            ));
+        }
+
+        Ok(Self(key))
+    }
+}
+
+impl TryFrom<Binary> for Ed25519PubKey {
+    type Error = String;
+
+    fn try_from(key: Binary) -> Result<Self, Self::Error> {
+        Self::try_from(key.0)
+    }
+}
+
+impl From<Ed25519PubKey> for Binary {
+    fn from(key: Ed25519PubKey) -> Self {
+        Binary(key.0)
+    }
+}
+
+impl PubKey for Ed25519PubKey {
+    fn get_address(&self) -> CanonicalAddr {
+        // Ref: https://docs.tendermint.com/master/spec/core/encoding.html#key-types
+        CanonicalAddr(Binary::from(&Sha256::digest(&self.0)[..20]))
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        amino_encode(&ED25519_PREFIX, &self.0)
+    }
+
+    fn proto_any(&self) -> Any {
+        to_any(
+            ED25519_TYPE_URL,
+            &ProtoPubKey {
+                key: self.0.clone(),
+            },
+        )
+    }
+
+    fn verify_bytes(&self, bytes: &[u8], sig: &[u8]) -> Result<(), CryptoError> {
+        match verify_signature(bytes, sig, &self.0) {
+            Ok(true) => Ok(()),
+            Ok(false) => {
+                warn!("Failed to verify ed25519 signature for the given transaction");
+                Err(CryptoError::VerificationError)
+            }
+            Err(err) => {
+                warn!("Malformed ed25519 signature or public key: {:?}", err);
+                Err(CryptoError::VerificationError)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    // Test vectors 1 and 2 from RFC 8032, section 7.1
+    const EMPTY_MESSAGE_PUBLIC_KEY: &str =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const EMPTY_MESSAGE_SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+    const ONE_BYTE_MESSAGE_PUBLIC_KEY: &str =
+        "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c";
+    const ONE_BYTE_MESSAGE_SIGNATURE: &str = "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00";
+
+    pub fn test_verify_signature_rfc8032_vectors() {
+        let public_key = hex::decode(EMPTY_MESSAGE_PUBLIC_KEY).unwrap();
+        let signature = hex::decode(EMPTY_MESSAGE_SIGNATURE).unwrap();
+        assert!(verify_signature(b"", &signature, &public_key).unwrap());
+
+        let public_key = hex::decode(ONE_BYTE_MESSAGE_PUBLIC_KEY).unwrap();
+        let signature = hex::decode(ONE_BYTE_MESSAGE_SIGNATURE).unwrap();
+        assert!(verify_signature(&[0x72], &signature, &public_key).unwrap());
+    }
+
+    pub fn test_verify_signature_rejects_wrong_message() {
+        let public_key = hex::decode(ONE_BYTE_MESSAGE_PUBLIC_KEY).unwrap();
+        let signature = hex::decode(ONE_BYTE_MESSAGE_SIGNATURE).unwrap();
+        assert!(!verify_signature(&[0x73], &signature, &public_key).unwrap());
+
+        let other_public_key = hex::decode(EMPTY_MESSAGE_PUBLIC_KEY).unwrap();
+        assert!(!verify_signature(&[0x72], &signature, &other_public_key).unwrap());
+    }
+
+    pub fn test_verify_signature_rejects_malformed_input() {
+        let public_key = hex::decode(EMPTY_MESSAGE_PUBLIC_KEY).unwrap();
+        let signature = hex::decode(EMPTY_MESSAGE_SIGNATURE).unwrap();
+
+        assert!(matches!(
+            verify_signature(b"", &signature[..63], &public_key),
+            Err(CryptoError::ParsingError)
+        ));
+        assert!(matches!(
+            verify_signature(b"", &signature, &public_key[..31]),
+            Err(CryptoError::KeyError)
+        ));
+
+        // S must be smaller than the order of the group
+        let mut non_canonical = signature.clone();
+        non_canonical[63] = 0xff;
+        assert!(!verify_signature(b"", &non_canonical, &public_key).unwrap());
+
+        // Not a point on the curve
+        let mut not_a_point = [0u8; PUBLIC_KEY_SIZE];
+        not_a_point[0] = 2;
+        assert!(!verify_signature(b"", &signature, &not_a_point).unwrap());
+    }
+
+    pub fn test_ed25519_pubkey_verify_bytes() {
+        let public_key =
+            Ed25519PubKey::try_from(hex::decode(EMPTY_MESSAGE_PUBLIC_KEY).unwrap()).unwrap();
+        let signature = hex::decode(EMPTY_MESSAGE_SIGNATURE).unwrap();
+
+        assert!(public_key.verify_bytes(b"", &signature).is_ok());
+        assert!(public_key.verify_bytes(b"other", &signature).is_err());
+        assert!(public_key.verify_bytes(b"", &signature[..63]).is_err());
+    }
+
+    pub fn test_ed25519_pubkey_encoding() {
+        let key_bytes = hex::decode(EMPTY_MESSAGE_PUBLIC_KEY).unwrap();
+        let public_key = Ed25519PubKey::try_from(key_bytes.clone()).unwrap();
+
+        assert_eq!(
+            public_key.get_address().as_slice(),
+            &hex::decode("21fe31dfa154a261626bf854046fd2271b7bed4b").unwrap()[..]
+        );
+
+        let mut amino = vec![22, 36, 222, 100, 32];
+        amino.extend_from_slice(&key_bytes);
+        assert_eq!(public_key.bytes(), amino);
+
+        let any = public_key.proto_any();
+        let mut proto = vec![0x0a, 32];
+        proto.extend_from_slice(&key_bytes);
+        assert_eq!(any.type_url, "/cosmos.crypto.ed25519.PubKey");
+        assert_eq!(any.value, proto);
+
+        assert!(Ed25519PubKey::try_from(vec![0u8; 33]).is_err());
+    }
+}
@@ -1,18 +1,113 @@
+#[cfg(feature = "native")]
+use std::sync::RwLock as SgxRwLock;
+#[cfg(not(feature = "native"))]
+use std::sync::SgxRwLock;
+
 use crate::consts::*;
 use crate::crypto::traits::*;
 use crate::crypto::CryptoError;
-use crate::crypto::{AESKey, KeyPair, Seed};
+use crate::crypto::{sha_256, AESKey, KeyPair, Seed, HASH_SIZE};
 use enclave_ffi_types::EnclaveError;
 use lazy_static::lazy_static;
 use log::*;
 
+/// The consensus seed is versioned by epochs. Epoch 0 is the seed that was created at genesis,
+/// and every rotation of the seed adds a new epoch. Old epochs are kept around, so state that was
+/// encrypted with them can still be read.
+///
+/// Every epoch derives its own keys, so a compromised seed stops being useful once the chain
+/// moves to a new epoch. The keys of the genesis epoch are derived exactly like they were before
+/// there were epochs. Contract keys are stored on chain and never change, but the proofs that
+/// authenticate them are issued with the state key of the active epoch, and are re-issued when
+/// the chain moves to a new one.
 pub struct Keychain {
-    consensus_seed: Option<Seed>,
-    consensus_state_ikm: Option<AESKey>,
-    consensus_seed_exchange_keypair: Option<KeyPair>,
-    consensus_io_exchange_keypair: Option<KeyPair>,
-    consensus_callback_secret: Option<AESKey>,
+    /// Indexed by epoch. New epochs are added to `KEY_MANAGER` while the enclave is running,
+    /// so they are behind a lock
+    epochs: SgxRwLock<Vec<EpochKeys>>,
+    /// The newest epoch the chain has used in a transaction this enclave executed. It only moves
+    /// forward, so the host can't make the enclave go back to the keys of an older epoch
+    active_epoch: SgxRwLock<u32>,
+    #[cfg(not(feature = "native"))]
     registration_key: Option<KeyPair>,
+    /// The hash of the id of the chain the consensus seed belongs to. It is set along with the
+    /// genesis seed, and is authenticated by the seed exchange, so the host can't change it
+    chain_id_hash: Option<[u8; HASH_SIZE]>,
+    /// The hash of the attestation policy the chain was created with, or of an empty policy if it
+    /// has none. It is bound to the seed like the chain id, so the host can't hand the enclave
+    /// another policy than the one of the chain
+    #[cfg(not(feature = "native"))]
+    attestation_policy_hash: Option<[u8; HASH_SIZE]>,
+}
+
+/// The keys that are derived from the consensus seed of a single epoch
+#[derive(Clone, Copy)]
+struct EpochKeys {
+    #[cfg(not(feature = "native"))]
+    seed: Seed,
+    state_ikm: AESKey,
+    #[cfg(not(feature = "native"))]
+    seed_exchange_keypair: KeyPair,
+    io_exchange_keypair: KeyPair,
+    callback_secret: AESKey,
+    random_secret: AESKey,
+}
+
+impl EpochKeys {
+    fn derive(seed: Seed, epoch: usize) -> Self {
+        // consensus_seed_exchange_keypair
+
+        #[cfg(not(feature = "native"))]
+        let seed_exchange_keypair = KeyPair::from(
+            seed.derive_key_from_this(&CONSENSUS_SEED_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes()),
+        );
+        #[cfg(not(feature = "native"))]
+        trace!(
+            "consensus_seed_exchange_keypair of epoch {}: {:?}",
+            epoch,
+            seed_exchange_keypair.get_pubkey()
+        );
+
+        // consensus_io_exchange_keypair
+
+        let io_exchange_keypair = KeyPair::from(
+            seed.derive_key_from_this(&CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes()),
+        );
+        trace!(
+            "consensus_io_exchange_keypair of epoch {}: {:?}",
+            epoch,
+            io_exchange_keypair.get_pubkey()
+        );
+
+        // consensus_state_ikm
+
+        let state_ikm = seed.derive_key_from_this(&CONSENSUS_STATE_IKM_DERIVE_ORDER.to_be_bytes());
+        trace!(
+            "consensus_state_ikm of epoch {}: {:?}",
+            epoch,
+            state_ikm.get()
+        );
+
+        // consensus_callback_secret
+
+        let callback_secret =
+            seed.derive_key_from_this(&CONSENSUS_CALLBACK_SECRET_DERIVE_ORDER.to_be_bytes());
+
+        // consensus_random_secret
+
+        let random_secret =
+            seed.derive_key_from_this(&CONSENSUS_RANDOM_SECRET_DERIVE_ORDER.to_be_bytes());
+
+        EpochKeys {
+            #[cfg(not(feature = "native"))]
+            seed,
+            state_ikm,
+            #[cfg(not(feature = "native"))]
+            seed_exchange_keypair,
+            io_exchange_keypair,
+            callback_secret,
+            random_secret,
+        }
+    }
 }
 
 lazy_static! {
@@ -21,30 +116,81 @@ lazy_static! {
 
 impl Keychain {
     pub fn new() -> Self {
-        let consensus_seed = match Seed::unseal(&CONSENSUS_SEED_SEALING_PATH) {
-            Ok(k) => Some(k),
-            Err(_e) => None,
-        };
+        let epochs = Self::unseal_consensus_seeds()
+            .into_iter()
+            .enumerate()
+            .map(|(epoch, seed)| EpochKeys::derive(seed, epoch))
+            .collect();
 
+        #[cfg(not(feature = "native"))]
         let registration_key = match KeyPair::unseal(&REGISTRATION_KEY_SEALING_PATH) {
             Ok(k) => Some(k),
             Err(_e) => None,
         };
 
-        let mut x = Keychain {
-            consensus_seed,
+        let active_epoch = Self::unseal_active_seed_epoch();
+        let chain_id_hash = Self::unseal_chain_id_hash();
+        #[cfg(not(feature = "native"))]
+        let attestation_policy_hash =
+            <[u8; HASH_SIZE]>::unseal(&ATTESTATION_POLICY_SEALING_PATH).ok();
+
+        Keychain {
+            epochs: SgxRwLock::new(epochs),
+            active_epoch: SgxRwLock::new(active_epoch),
+            #[cfg(not(feature = "native"))]
             registration_key,
-            consensus_state_ikm: None,
-            consensus_seed_exchange_keypair: None,
-            consensus_io_exchange_keypair: None,
-            consensus_callback_secret: None,
-        };
+            chain_id_hash,
+            #[cfg(not(feature = "native"))]
+            attestation_policy_hash,
+        }
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn unseal_consensus_seeds() -> Vec<Seed> {
+        let mut consensus_seeds = vec![];
+        if let Ok(seed) = Seed::unseal(&CONSENSUS_SEED_SEALING_PATH) {
+            consensus_seeds.push(seed);
+
+            // Epochs are always added in order, so the first missing epoch is the end
+            while let Ok(seed) = Seed::unseal(&consensus_seed_epoch_sealing_path(
+                consensus_seeds.len() as u32,
+            )) {
+                consensus_seeds.push(seed);
+            }
+        }
+        consensus_seeds
+    }
 
-        let _ = x.generate_consensus_master_keys();
+    /// Native builds can't get the seed of the network, so they always use a fixed test seed
+    #[cfg(feature = "native")]
+    fn unseal_consensus_seeds() -> Vec<Seed> {
+        let mut seed = Seed::default();
+        seed.as_mut().copy_from_slice(&NATIVE_CONSENSUS_SEED);
+        vec![seed]
+    }
+
+    #[cfg(not(feature = "native"))]
+    fn unseal_active_seed_epoch() -> u32 {
+        u32::unseal(&ACTIVE_SEED_EPOCH_SEALING_PATH).unwrap_or(0)
+    }
+
+    /// Native builds only run a single process, so they keep the active epoch in memory
+    #[cfg(feature = "native")]
+    fn unseal_active_seed_epoch() -> u32 {
+        0
+    }
 
-        x
+    #[cfg(not(feature = "native"))]
+    fn unseal_chain_id_hash() -> Option<[u8; HASH_SIZE]> {
+        <[u8; HASH_SIZE]>::unseal(&CHAIN_ID_SEALING_PATH).ok()
     }
 
+    #[cfg(feature = "native")]
+    fn unseal_chain_id_hash() -> Option<[u8; HASH_SIZE]> {
+        Some(sha_256(NATIVE_CHAIN_ID.as_bytes()))
+    }
+
+    #[cfg(not(feature = "native"))]
     pub fn create_consensus_seed(&mut self) -> Result<(), CryptoError> {
         match Seed::new() {
             Ok(seed) => {
@@ -57,6 +203,22 @@ impl Keychain {
         Ok(())
     }
 
+    /// Creates the seed of a new epoch, and returns the new epoch
+    #[cfg(not(feature = "native"))]
+    pub fn create_consensus_seed_epoch(&self) -> Result<u32, CryptoError> {
+        let epoch = self.next_seed_epoch()?;
+        match Seed::new() {
+            Ok(seed) => {
+                if let Err(_e) = self.add_consensus_seed_epoch(epoch, seed) {
+                    return Err(CryptoError::KeyError);
+                }
+            }
+            Err(err) => return Err(err),
+        };
+        Ok(epoch)
+    }
+
+    #[cfg(not(feature = "native"))]
     pub fn create_registration_key(&mut self) -> Result<(), CryptoError> {
         match KeyPair::new() {
             Ok(key) => {
@@ -70,44 +232,147 @@ impl Keychain {
     }
 
     pub fn is_consensus_seed_set(&self) -> bool {
-        self.consensus_seed.is_some()
+        !self.epochs.read().unwrap().is_empty()
     }
 
-    pub fn get_consensus_state_ikm(&self) -> Result<AESKey, CryptoError> {
-        self.consensus_state_ikm.ok_or_else(|| {
-            error!("Error accessing base_state_key (does not exist, or was not initialized)");
-            CryptoError::ParsingError
-        })
+    /// The newest epoch of the consensus seed this enclave has.
+    /// This is not necessarily the epoch the chain uses, which is passed in the env of every call.
+    pub fn current_seed_epoch(&self) -> Result<u32, CryptoError> {
+        self.next_seed_epoch().map(|epoch| epoch - 1)
     }
 
-    pub fn get_consensus_seed(&self) -> Result<Seed, CryptoError> {
-        self.consensus_seed.ok_or_else(|| {
+    /// The newest epoch of the consensus seed the chain has used, as far as this enclave knows
+    pub fn active_seed_epoch(&self) -> u32 {
+        *self.active_epoch.read().unwrap()
+    }
+
+    /// Records that the chain uses `epoch` now. This must only be called with the epoch of a
+    /// transaction that was executed, and older epochs are ignored.
+    pub fn activate_seed_epoch(&self, epoch: u32) -> Result<(), EnclaveError> {
+        // Held until the epoch is sealed, so the sealed epoch never goes back
+        let mut active_epoch = self.active_epoch.write().unwrap();
+        if epoch <= *active_epoch {
+            return Ok(());
+        }
+
+        #[cfg(not(feature = "native"))]
+        if let Err(e) = epoch.seal(&ACTIVE_SEED_EPOCH_SEALING_PATH) {
+            error!("Error sealing the active seed epoch");
+            return Err(e);
+        }
+        *active_epoch = epoch;
+        Ok(())
+    }
+
+    fn next_seed_epoch(&self) -> Result<u32, CryptoError> {
+        if !self.is_consensus_seed_set() {
             error!("Error accessing consensus_seed (does not exist, or was not initialized)");
-            CryptoError::ParsingError
-        })
+            return Err(CryptoError::ParsingError);
+        }
+        Ok(self.epochs.read().unwrap().len() as u32)
     }
 
+    fn epoch_keys(&self, epoch: u32, name: &str) -> Result<EpochKeys, CryptoError> {
+        self.epochs
+            .read()
+            .unwrap()
+            .get(epoch as usize)
+            .copied()
+            .ok_or_else(|| {
+                error!(
+                    "Error accessing {} of epoch {} (does not exist, or was not initialized)",
+                    name, epoch
+                );
+                CryptoError::ParsingError
+            })
+    }
+
+    pub fn get_consensus_state_ikm_of_epoch(&self, epoch: u32) -> Result<AESKey, CryptoError> {
+        Ok(self.epoch_keys(epoch, "base_state_key")?.state_ikm)
+    }
+
+    #[cfg(not(feature = "native"))]
+    pub fn get_consensus_seed(&self) -> Result<Seed, CryptoError> {
+        self.get_consensus_seed_of_epoch(0)
+    }
+
+    #[cfg(not(feature = "native"))]
+    pub fn get_consensus_seed_of_epoch(&self, epoch: u32) -> Result<Seed, CryptoError> {
+        Ok(self.epoch_keys(epoch, "consensus_seed")?.seed)
+    }
+
+    #[cfg(not(feature = "native"))]
     pub fn seed_exchange_key(&self) -> Result<KeyPair, CryptoError> {
-        self.consensus_seed_exchange_keypair.ok_or_else(|| {
-            error!("Error accessing consensus_seed_exchange_keypair (does not exist, or was not initialized)");
-            CryptoError::ParsingError
-        })
+        self.seed_exchange_key_of_epoch(0)
+    }
+
+    #[cfg(not(feature = "native"))]
+    pub fn seed_exchange_key_of_epoch(&self, epoch: u32) -> Result<KeyPair, CryptoError> {
+        Ok(self
+            .epoch_keys(epoch, "consensus_seed_exchange_keypair")?
+            .seed_exchange_keypair)
     }
 
+    /// The IO key of the genesis epoch, whose certificate is in the genesis of the chain
+    #[cfg(not(feature = "native"))]
     pub fn get_consensus_io_exchange_keypair(&self) -> Result<KeyPair, CryptoError> {
-        self.consensus_io_exchange_keypair.ok_or_else(|| {
-            error!("Error accessing consensus_io_exchange_keypair (does not exist, or was not initialized)");
+        self.get_consensus_io_exchange_keypair_of_epoch(0)
+    }
+
+    pub fn get_consensus_io_exchange_keypair_of_epoch(
+        &self,
+        epoch: u32,
+    ) -> Result<KeyPair, CryptoError> {
+        Ok(self
+            .epoch_keys(epoch, "consensus_io_exchange_keypair")?
+            .io_exchange_keypair)
+    }
+
+    pub fn get_consensus_callback_secret_of_epoch(
+        &self,
+        epoch: u32,
+    ) -> Result<AESKey, CryptoError> {
+        Ok(self
+            .epoch_keys(epoch, "consensus_callback_secret")?
+            .callback_secret)
+    }
+
+    pub fn get_consensus_random_secret_of_epoch(&self, epoch: u32) -> Result<AESKey, CryptoError> {
+        Ok(self
+            .epoch_keys(epoch, "consensus_random_secret")?
+            .random_secret)
+    }
+
+    /// The hash of the id of the chain this enclave belongs to
+    pub fn get_chain_id_hash(&self) -> Result<[u8; HASH_SIZE], CryptoError> {
+        self.chain_id_hash.ok_or_else(|| {
+            error!("Error accessing chain_id_hash (does not exist, or was not initialized)");
             CryptoError::ParsingError
         })
     }
 
-    pub fn get_consensus_callback_secret(&self) -> Result<AESKey, CryptoError> {
-        self.consensus_callback_secret.ok_or_else(|| {
-            error!("Error accessing consensus_callback_secret (does not exist, or was not initialized)");
+    /// The hash of the attestation policy of the chain this enclave belongs to
+    #[cfg(not(feature = "native"))]
+    pub fn get_attestation_policy_hash(&self) -> Result<[u8; HASH_SIZE], CryptoError> {
+        self.attestation_policy_hash.ok_or_else(|| {
+            error!(
+                "Error accessing attestation_policy_hash (does not exist, or was not initialized)"
+            );
             CryptoError::ParsingError
         })
     }
 
+    /// The hashes of the chain id and the attestation policy the consensus seed is bound to. Seeds
+    /// that were created before they were bound to the seed have neither
+    #[cfg(not(feature = "native"))]
+    pub fn get_seed_binding(&self) -> Option<([u8; HASH_SIZE], [u8; HASH_SIZE])> {
+        match (self.chain_id_hash, self.attestation_policy_hash) {
+            (Some(chain_id_hash), Some(policy_hash)) => Some((chain_id_hash, policy_hash)),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(feature = "native"))]
     pub fn get_registration_key(&self) -> Result<KeyPair, CryptoError> {
         self.registration_key.ok_or_else(|| {
             error!("Error accessing registration_key (does not exist, or was not initialized)");
@@ -115,6 +380,7 @@ impl Keychain {
         })
     }
 
+    #[cfg(not(feature = "native"))]
     pub fn set_registration_key(&mut self, kp: KeyPair) -> Result<(), EnclaveError> {
         if let Err(e) = kp.seal(&REGISTRATION_KEY_SEALING_PATH) {
             error!("Error sealing registration key");
@@ -124,93 +390,182 @@ impl Keychain {
         Ok(())
     }
 
-    pub fn set_consensus_seed_exchange_keypair(&mut self, kp: KeyPair) {
-        self.consensus_seed_exchange_keypair = Some(kp)
-    }
-
-    pub fn set_consensus_io_exchange_keypair(&mut self, kp: KeyPair) {
-        self.consensus_io_exchange_keypair = Some(kp)
-    }
-
-    pub fn set_consensus_state_ikm(&mut self, consensus_state_ikm: AESKey) {
-        self.consensus_state_ikm = Some(consensus_state_ikm);
+    /// Sets the chain the consensus seed belongs to. This must only be called with a chain id that
+    /// was created along with the seed, or that was authenticated by the seed exchange
+    #[cfg(not(feature = "native"))]
+    pub fn set_chain_id(&mut self, chain_id: &str) -> Result<(), EnclaveError> {
+        let chain_id_hash = sha_256(chain_id.as_bytes());
+        if let Err(e) = chain_id_hash.seal(&CHAIN_ID_SEALING_PATH) {
+            error!("Error sealing chain_id_hash");
+            return Err(e);
+        }
+        self.chain_id_hash = Some(chain_id_hash);
+        Ok(())
     }
 
-    pub fn set_consensus_callback_secret(&mut self, consensus_callback_secret: AESKey) {
-        self.consensus_callback_secret = Some(consensus_callback_secret);
+    /// Sets the attestation policy of the chain, as its JSON encoding. Like the chain id, this must
+    /// only be called with a policy that was created along with the seed, or that was
+    /// authenticated by the seed exchange
+    #[cfg(not(feature = "native"))]
+    pub fn set_attestation_policy(&mut self, policy: &[u8]) -> Result<(), EnclaveError> {
+        let policy_hash = sha_256(policy);
+        if let Err(e) = policy_hash.seal(&ATTESTATION_POLICY_SEALING_PATH) {
+            error!("Error sealing attestation_policy_hash");
+            return Err(e);
+        }
+        self.attestation_policy_hash = Some(policy_hash);
+        Ok(())
     }
 
-    pub fn set_consensus_seed(&mut self, consensus_seed: Seed) -> Result<(), EnclaveError> {
+    /// Sets the seed of the genesis epoch, and derives its keys
+    #[cfg(not(feature = "native"))]
+    pub fn set_consensus_seed(&self, consensus_seed: Seed) -> Result<(), EnclaveError> {
         if let Err(e) = consensus_seed.seal(&CONSENSUS_SEED_SEALING_PATH) {
             error!("Error sealing consensus_seed");
             return Err(e);
         }
-        self.consensus_seed = Some(consensus_seed);
+        *self.epochs.write().unwrap() = vec![EpochKeys::derive(consensus_seed, 0)];
         Ok(())
     }
 
-    pub fn generate_consensus_master_keys(&mut self) -> Result<(), EnclaveError> {
-        if !self.is_consensus_seed_set() {
-            trace!("Seed not initialized, skipping derivation of enclave keys");
-            return Ok(());
+    /// Adds the seed of a new epoch, and derives its keys. Epochs must be added in order, since
+    /// the seeds of older epochs are needed to read the state that wasn't re-encrypted yet.
+    #[cfg(not(feature = "native"))]
+    pub fn add_consensus_seed_epoch(
+        &self,
+        epoch: u32,
+        consensus_seed: Seed,
+    ) -> Result<(), EnclaveError> {
+        // Held until the epoch is added, so two epochs can't be added at once
+        let mut epochs = self.epochs.write().unwrap();
+
+        if epoch == 0 || epoch as usize != epochs.len() {
+            error!(
+                "Can't add the seed of epoch {}, the next epoch is {}",
+                epoch,
+                epochs.len()
+            );
+            return Err(EnclaveError::SeedEpochUnavailable { epoch });
         }
 
-        // consensus_seed_exchange_keypair
+        if let Err(e) = consensus_seed.seal(&consensus_seed_epoch_sealing_path(epoch)) {
+            error!("Error sealing consensus_seed of epoch {}", epoch);
+            return Err(e);
+        }
+        epochs.push(EpochKeys::derive(consensus_seed, epoch as usize));
+        Ok(())
+    }
+}
 
-        let consensus_seed_exchange_keypair_bytes = self
-            .consensus_seed
-            .unwrap()
-            .derive_key_from_this(&CONSENSUS_SEED_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes());
-        let consensus_seed_exchange_keypair = KeyPair::from(consensus_seed_exchange_keypair_bytes);
-        trace!(
-            "consensus_seed_exchange_keypair: {:?}",
-            consensus_seed_exchange_keypair.get_pubkey()
-        );
-        self.set_consensus_seed_exchange_keypair(consensus_seed_exchange_keypair);
+#[cfg(feature = "test")]
+pub mod tests {
 
-        // consensus_io_exchange_keypair
+    use super::{
+        EpochKeys, Keychain, SgxRwLock, CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER,
+        CONSENSUS_SEED_SEALING_PATH, CONSENSUS_STATE_IKM_DERIVE_ORDER, KEY_MANAGER,
+        REGISTRATION_KEY_SEALING_PATH,
+    };
+    use crate::crypto::traits::Kdf;
+    use crate::crypto::CryptoError;
+    use crate::crypto::{KeyPair, Seed};
 
-        let consensus_io_exchange_keypair_bytes = self
-            .consensus_seed
-            .unwrap()
-            .derive_key_from_this(&CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes());
-        let consensus_io_exchange_keypair = KeyPair::from(consensus_io_exchange_keypair_bytes);
-        trace!(
-            "consensus_io_exchange_keypair: {:?}",
-            consensus_io_exchange_keypair.get_pubkey()
-        );
-        self.set_consensus_io_exchange_keypair(consensus_io_exchange_keypair);
+    fn seed_of(byte: u8) -> Seed {
+        let mut seed = Seed::default();
+        seed.as_mut().copy_from_slice(&[byte; 32]);
+        seed
+    }
 
-        // consensus_state_ikm
+    fn keychain_with_seeds(seeds: &[Seed]) -> Keychain {
+        let epochs = seeds
+            .iter()
+            .enumerate()
+            .map(|(epoch, seed)| EpochKeys::derive(*seed, epoch))
+            .collect();
+
+        Keychain {
+            epochs: SgxRwLock::new(epochs),
+            active_epoch: SgxRwLock::new(0),
+            registration_key: None,
+            chain_id_hash: None,
+            attestation_policy_hash: None,
+        }
+    }
 
-        let consensus_state_ikm = self
-            .consensus_seed
-            .unwrap()
-            .derive_key_from_this(&CONSENSUS_STATE_IKM_DERIVE_ORDER.to_be_bytes());
+    /// A keychain of a node that created its registration key, but doesn't have the seed yet
+    pub fn keychain_with_registration_key(registration_key: KeyPair) -> Keychain {
+        let mut keys = keychain_with_seeds(&[]);
+        keys.registration_key = Some(registration_key);
+        keys
+    }
 
-        trace!("consensus_state_ikm: {:?}", consensus_state_ikm.get());
-        self.set_consensus_state_ikm(consensus_state_ikm);
+    pub fn test_seed_epochs_derive_their_own_keys() {
+        let genesis_seed = seed_of(1);
+        let keys = keychain_with_seeds(&[genesis_seed, seed_of(2), seed_of(3)]);
 
-        let consensus_callback_secret = self
-            .consensus_seed
-            .unwrap()
-            .derive_key_from_this(&CONSENSUS_CALLBACK_SECRET_DERIVE_ORDER.to_be_bytes());
+        assert_eq!(keys.current_seed_epoch().unwrap(), 2);
+
+        // The genesis epoch derives exactly the keys it derived before there were epochs
+        let genesis_ikm =
+            genesis_seed.derive_key_from_this(&CONSENSUS_STATE_IKM_DERIVE_ORDER.to_be_bytes());
+        assert_eq!(
+            keys.get_consensus_state_ikm_of_epoch(0).unwrap().get(),
+            genesis_ikm.get()
+        );
 
-        trace!("consensus_state_ikm: {:?}", consensus_state_ikm.get());
-        self.set_consensus_callback_secret(consensus_callback_secret);
+        let ikm_1 = keys.get_consensus_state_ikm_of_epoch(1).unwrap();
+        let ikm_2 = keys.get_consensus_state_ikm_of_epoch(2).unwrap();
+        assert_ne!(ikm_1.get(), genesis_ikm.get());
+        assert_ne!(ikm_1.get(), ikm_2.get());
+        assert_ne!(
+            keys.seed_exchange_key_of_epoch(1).unwrap().get_pubkey(),
+            keys.seed_exchange_key().unwrap().get_pubkey()
+        );
+        assert!(keys.get_consensus_state_ikm_of_epoch(3).is_err());
+    }
 
-        Ok(())
+    pub fn test_seed_epochs_rotate_io_keys() {
+        let genesis_seed = seed_of(1);
+        let keys = keychain_with_seeds(&[genesis_seed, seed_of(2)]);
+
+        // Users keep encrypting to the same key until they move to the new epoch
+        let genesis_io_key = KeyPair::from(
+            genesis_seed
+                .derive_key_from_this(&CONSENSUS_IO_EXCHANGE_KEYPAIR_DERIVE_ORDER.to_be_bytes()),
+        );
+        assert_eq!(
+            keys.get_consensus_io_exchange_keypair()
+                .unwrap()
+                .get_pubkey(),
+            genesis_io_key.get_pubkey()
+        );
+
+        assert_ne!(
+            keys.get_consensus_io_exchange_keypair_of_epoch(1)
+                .unwrap()
+                .get_pubkey(),
+            genesis_io_key.get_pubkey()
+        );
+        assert_ne!(
+            keys.get_consensus_callback_secret_of_epoch(1)
+                .unwrap()
+                .get(),
+            keys.get_consensus_callback_secret_of_epoch(0)
+                .unwrap()
+                .get()
+        );
+        assert_ne!(
+            keys.get_consensus_random_secret_of_epoch(1).unwrap().get(),
+            keys.get_consensus_random_secret_of_epoch(0).unwrap().get()
+        );
     }
-}
 
-#[cfg(feature = "test")]
-pub mod tests {
+    pub fn test_seed_epochs_must_be_added_in_order() {
+        let keys = keychain_with_seeds(&[seed_of(1)]);
 
-    use super::{
-        Keychain, CONSENSUS_SEED_SEALING_PATH, KEY_MANAGER, REGISTRATION_KEY_SEALING_PATH,
-    };
-    use crate::crypto::CryptoError;
-    use crate::crypto::{KeyPair, Seed};
+        assert!(keys.add_consensus_seed_epoch(0, seed_of(2)).is_err());
+        assert!(keys.add_consensus_seed_epoch(2, seed_of(2)).is_err());
+        assert_eq!(keys.current_seed_epoch().unwrap(), 0);
+    }
 
     // todo: fix test vectors to actually work
     fn test_initial_keychain_state() {
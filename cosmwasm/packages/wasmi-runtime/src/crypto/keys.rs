@@ -1,6 +1,8 @@
+#[cfg(not(feature = "native"))]
 use super::rng::rand_slice;
 use super::traits::{AlignedMemory, ExportECKey, EC_256_PRIVATE_KEY_SIZE};
 
+#[cfg(not(feature = "native"))]
 use crate::crypto::CryptoError;
 
 use crate::crypto::ed25519::Ed25519PrivateKey;
@@ -94,6 +96,7 @@ impl Seed {
         &mut self.key.key.r as &mut [u8; SEED_KEY_SIZE]
     }
 
+    #[cfg(not(feature = "native"))]
     pub fn new() -> Result<Self, CryptoError> {
         let mut seed = Seed::default();
 
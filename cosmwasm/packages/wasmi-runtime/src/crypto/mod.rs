@@ -2,28 +2,38 @@ mod errors;
 pub(crate) mod kdf;
 pub mod key_manager;
 mod keys;
+// Native builds don't create keys or seal anything
+#[cfg(not(feature = "native"))]
 mod storage;
 pub mod traits;
 
 // mod aes_gcm;
 mod aes_siv;
 mod hmac;
+#[cfg(not(feature = "native"))]
 mod rng;
 mod sha;
 
 mod ed25519;
 pub mod multisig;
+mod pubkey_encoding;
 pub mod secp256k1;
+pub mod secp256r1;
 
 pub use errors::CryptoError;
 pub use key_manager::Keychain;
 pub use key_manager::KEY_MANAGER;
 pub use keys::{AESKey, Seed, SymmetricKey, SEED_KEY_SIZE};
 
-pub use ed25519::{Ed25519PublicKey, KeyPair, PUBLIC_KEY_SIZE, SECRET_KEY_SIZE};
+pub use ed25519::{
+    verify_signature as ed25519_verify, Ed25519PubKey, Ed25519PublicKey, KeyPair,
+    PUBLIC_KEY_SIZE, SECRET_KEY_SIZE,
+};
 
 pub use sha::{sha_256, HASH_SIZE};
-pub use traits::{Encryptable, Hmac, Kdf, SIVEncryptable, SealedKey, HMAC_SIGNATURE_SIZE};
+pub use traits::{Hmac, Kdf, SIVEncryptable, HMAC_SIGNATURE_SIZE};
+#[cfg(not(feature = "native"))]
+pub use traits::{Encryptable, SealedKey};
 
 #[cfg(feature = "test")]
 pub mod tests {
@@ -44,6 +54,21 @@ pub mod tests {
             multisig::tests_decode_multisig_signature::test_decode_malformed_sig_only_prefix();
             multisig::tests_decode_multisig_signature::test_decode_sig_length_zero();
             multisig::tests_decode_multisig_signature::test_decode_malformed_sig_wrong_length();
+            ed25519::tests::test_verify_signature_rfc8032_vectors();
+            ed25519::tests::test_verify_signature_rejects_wrong_message();
+            ed25519::tests::test_verify_signature_rejects_malformed_input();
+            secp256k1::tests::test_verify_prehashed();
+            secp256k1::tests::test_verify_prehashed_rejects_malformed_input();
+            ed25519::tests::test_ed25519_pubkey_verify_bytes();
+            ed25519::tests::test_ed25519_pubkey_encoding();
+            secp256r1::tests::test_secp256r1_verify_bytes();
+            secp256r1::tests::test_secp256r1_rejects_high_s();
+            secp256r1::tests::test_secp256r1_encoding();
+            multisig::tests::test_multisig_of_mixed_key_types();
+            multisig::tests::test_multisig_proto_any();
+            key_manager::tests::test_seed_epochs_derive_their_own_keys();
+            key_manager::tests::test_seed_epochs_rotate_io_keys();
+            key_manager::tests::test_seed_epochs_must_be_added_in_order();
         });
 
         if failures != 0 {
@@ -1,7 +1,9 @@
 use log::*;
 
 use crate::cosmwasm::encoding::Binary;
+use crate::cosmwasm::sign_doc_direct::Any;
 use crate::cosmwasm::types::{CanonicalAddr, PubKeyKind};
+use crate::crypto::pubkey_encoding::{to_any, LegacyAminoPubKey};
 use crate::crypto::traits::PubKey;
 use crate::crypto::CryptoError;
 
@@ -10,13 +12,31 @@ use sha2::Digest;
 
 const THRESHOLD_PREFIX: [u8; 5] = [34, 193, 247, 226, 8];
 const GENERIC_PREFIX: u8 = 18;
+const MULTISIG_TYPE_URL: &str = "/cosmos.crypto.multisig.LegacyAminoPubKey";
 
+/// Serialized in amino json, where the threshold is a string and the keys are tagged with their type
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct MultisigThresholdPubKey {
+    #[serde(with = "amino_json_uint")]
     threshold: u8,
     pubkeys: Vec<PubKeyKind>,
 }
 
+/// Amino json encodes unsigned integers as strings
+mod amino_json_uint {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u8, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(D::Error::custom)
+    }
+}
+
 impl PubKey for MultisigThresholdPubKey {
     fn get_address(&self) -> CanonicalAddr {
         // Spec: https://docs.tendermint.com/master/spec/core/encoding.html#key-types
@@ -57,6 +77,20 @@ impl PubKey for MultisigThresholdPubKey {
         encoded
     }
 
+    fn proto_any(&self) -> Any {
+        to_any(
+            MULTISIG_TYPE_URL,
+            &LegacyAminoPubKey {
+                threshold: self.threshold as u32,
+                public_keys: self
+                    .pubkeys
+                    .iter()
+                    .map(|pubkey| pubkey.proto_any())
+                    .collect(),
+            },
+        )
+    }
+
     fn verify_bytes(&self, bytes: &[u8], sig: &[u8]) -> Result<(), CryptoError> {
         debug!("verifying multisig");
         trace!("Sign bytes are: {:?}", bytes);
@@ -159,6 +193,89 @@ fn decode_multisig_signature(raw_blob: &[u8]) -> Result<MultisigSignature, Crypt
     Ok(signatures)
 }
 
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use prost::Message;
+    use serde_json::json;
+
+    // RFC 8032 test vector 1, which signs an empty message
+    const ED25519_PUBLIC_KEY: &str =
+        "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a";
+    const ED25519_SIGNATURE: &str = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+    const SECP256R1_PUBLIC_KEY: &str = "04d8cd12ea5c67f2f8a00c1124893edcfa6754c4d6cede6be13bdf2295c810a97fa5a89d2d2a360c0ca9a4d6c7c9ed4b28d3e199d6627f2e696d689c310a5b0f48";
+    const SECP256R1_SIGNATURE: &str = "2b51e407d63dfae3cb3dd535dd01db1639168d4a182c35ff9f1f79d751e9072f4cc1e383155a287d671e92c797575ea61d2d54e97c0ae1ab9ab9c97ad6245050";
+
+    // This is how the keys arrive from Go, in amino json
+    fn mixed_multisig(threshold: u8) -> MultisigThresholdPubKey {
+        serde_json::from_value(json!({
+            "threshold": threshold.to_string(),
+            "pubkeys": [
+                {
+                    "type": "tendermint/PubKeyEd25519",
+                    "value": base64::encode(hex::decode(ED25519_PUBLIC_KEY).unwrap()),
+                },
+                {
+                    "type": "tendermint/PubKeySecp256r1",
+                    "value": base64::encode(hex::decode(SECP256R1_PUBLIC_KEY).unwrap()),
+                },
+                {
+                    "type": "tendermint/PubKeySecp256k1",
+                    "value": base64::encode(vec![2u8; 33]),
+                },
+            ]
+        }))
+        .unwrap()
+    }
+
+    fn encode_signatures(signatures: &[Vec<u8>]) -> Vec<u8> {
+        let mut blob = vec![0u8; 7];
+        for signature in signatures {
+            blob.push(GENERIC_PREFIX);
+            blob.push(signature.len() as u8);
+            blob.extend_from_slice(signature);
+        }
+        blob
+    }
+
+    pub fn test_multisig_of_mixed_key_types() {
+        let multisig = mixed_multisig(2);
+        assert!(matches!(multisig.pubkeys[0], PubKeyKind::Ed25519(_)));
+        assert!(matches!(multisig.pubkeys[1], PubKeyKind::Secp256r1(_)));
+        assert!(matches!(multisig.pubkeys[2], PubKeyKind::Secp256k1(_)));
+
+        let ed25519_signature = hex::decode(ED25519_SIGNATURE).unwrap();
+        let secp256r1_signature = hex::decode(SECP256R1_SIGNATURE).unwrap();
+
+        let both = encode_signatures(&[ed25519_signature.clone(), secp256r1_signature]);
+        assert!(multisig.verify_bytes(b"", &both).is_ok());
+        assert!(multisig.verify_bytes(b"other", &both).is_err());
+
+        let one = encode_signatures(&[ed25519_signature]);
+        assert!(multisig.verify_bytes(b"", &one).is_err());
+        assert!(mixed_multisig(1).verify_bytes(b"", &one).is_ok());
+    }
+
+    pub fn test_multisig_proto_any() {
+        let multisig = mixed_multisig(2);
+
+        let any = multisig.proto_any();
+        assert_eq!(any.type_url, "/cosmos.crypto.multisig.LegacyAminoPubKey");
+
+        let decoded = LegacyAminoPubKey::decode(any.value.as_slice()).unwrap();
+        assert_eq!(decoded.threshold, 2);
+        assert_eq!(
+            decoded.public_keys,
+            multisig
+                .pubkeys
+                .iter()
+                .map(|pubkey| pubkey.proto_any())
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
 #[cfg(feature = "test")]
 pub mod tests_decode_multisig_signature {
     use crate::crypto::multisig::{decode_multisig_signature, MultisigSignature};
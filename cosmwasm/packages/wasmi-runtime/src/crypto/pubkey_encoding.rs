@@ -0,0 +1,58 @@
+use log::*;
+use prost::Message;
+
+use crate::cosmwasm::sign_doc_direct::Any;
+
+/// The protobuf message of every single key type in the Cosmos SDK,
+/// e.g. cosmos.crypto.secp256k1.PubKey
+#[derive(Clone, PartialEq, Message)]
+pub struct ProtoPubKey {
+    #[prost(bytes, tag = "1")]
+    pub key: Vec<u8>,
+}
+
+/// cosmos.crypto.multisig.LegacyAminoPubKey
+#[derive(Clone, PartialEq, Message)]
+pub struct LegacyAminoPubKey {
+    #[prost(uint32, tag = "1")]
+    pub threshold: u32,
+    #[prost(message, repeated, tag = "2")]
+    pub public_keys: Vec<Any>,
+}
+
+/// Amino encoding of a single key is basically: prefix | leb128 encoded length | ..bytes..
+pub fn amino_encode(prefix: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::<u8>::new();
+    encoded.extend_from_slice(prefix);
+
+    // Length may be more than 1 byte and it is protobuf encoded
+    let mut length = Vec::<u8>::new();
+
+    // This line can't fail since it could only fail if `length` does not have sufficient capacity to encode
+    if prost::encode_length_delimiter(key.len(), &mut length).is_err() {
+        warn!(
+            "Could not encode length delimiter: {:?}. This should not happen",
+            key.len()
+        );
+        return vec![];
+    }
+
+    encoded.extend_from_slice(&length);
+    encoded.extend_from_slice(key);
+
+    encoded
+}
+
+/// Wraps a protobuf message in an `Any` with the given type url
+pub fn to_any<M: Message>(type_url: &str, message: &M) -> Any {
+    let mut value = Vec::with_capacity(message.encoded_len());
+    // This can't fail since it could only fail if `value` does not have sufficient capacity to encode
+    if message.encode(&mut value).is_err() {
+        warn!("Could not encode {}. This should not happen", type_url);
+    }
+
+    Any {
+        type_url: type_url.to_string(),
+        value,
+    }
+}
@@ -1,7 +1,11 @@
+use std::convert::TryFrom;
+
 use log::*;
 
 use crate::cosmwasm::encoding::Binary;
+use crate::cosmwasm::sign_doc_direct::Any;
 use crate::cosmwasm::types::CanonicalAddr;
+use crate::crypto::pubkey_encoding::{amino_encode, to_any, ProtoPubKey};
 use crate::crypto::traits::PubKey;
 use crate::crypto::CryptoError;
 use ripemd160::{Digest, Ripemd160};
@@ -10,11 +14,38 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest as Sha2Digest, Sha256};
 
 const SECP256K1_PREFIX: [u8; 4] = [235, 90, 233, 135];
+const SECP256K1_TYPE_URL: &str = "/cosmos.crypto.secp256k1.PubKey";
+
+pub const COMPRESSED_PUBLIC_KEY_SIZE: usize = 33;
 
 // TODO: Find a way to implement this better. secp256k1 is not ported to sgx, thus does not implement mesalock's serde and cannot be used
+/// Serialized as the base64 of the compressed key, like Go encodes it.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "Binary", into = "Binary")]
 pub struct Secp256k1PubKey(Vec<u8>);
 
+impl TryFrom<Binary> for Secp256k1PubKey {
+    type Error = String;
+
+    fn try_from(key: Binary) -> Result<Self, Self::Error> {
+        if key.len() != COMPRESSED_PUBLIC_KEY_SIZE {
+            return Err(format!(
+                "expected {} bytes, got {}",
+                COMPRESSED_PUBLIC_KEY_SIZE,
+                key.len()
+            ));
+        }
+
+        Ok(Self(key.0))
+    }
+}
+
+impl From<Secp256k1PubKey> for Binary {
+    fn from(key: Secp256k1PubKey) -> Self {
+        Binary(key.0)
+    }
+}
+
 impl PubKey for Secp256k1PubKey {
     fn get_address(&self) -> CanonicalAddr {
         // Ref: https://github.com/tendermint/spec/blob/master/spec/blockchain/encoding.md#secp256k1
@@ -24,26 +55,16 @@ impl PubKey for Secp256k1PubKey {
     }
 
     fn bytes(&self) -> Vec<u8> {
-        // Amino encoding here is basically: prefix | leb128 encoded length | ..bytes..
-        let mut encoded = Vec::<u8>::new();
-        encoded.extend_from_slice(&SECP256K1_PREFIX);
-
-        // Length may be more than 1 byte and it is protobuf encoded
-        let mut length = Vec::<u8>::new();
-
-        // This line can't fail since it could only fail if `length` does not have sufficient capacity to encode
-        if prost::encode_length_delimiter(self.0.len(), &mut length).is_err() {
-            warn!(
-                "Could not encode length delimiter: {:?}. This should not happen",
-                self.0.len()
-            );
-            return vec![];
-        }
-
-        encoded.extend_from_slice(&length);
-        encoded.extend_from_slice(&self.0);
+        amino_encode(&SECP256K1_PREFIX, &self.0)
+    }
 
-        encoded
+    fn proto_any(&self) -> Any {
+        to_any(
+            SECP256K1_TYPE_URL,
+            &ProtoPubKey {
+                key: self.0.clone(),
+            },
+        )
     }
 
     fn verify_bytes(&self, bytes: &[u8], sig: &[u8]) -> Result<(), CryptoError> {
@@ -81,6 +102,84 @@ impl PubKey for Secp256k1PubKey {
     }
 }
 
+/// Verifies an ECDSA signature of a 32 byte message hash. The signature is in the 64 byte compact
+/// format, and must be normalized to the lower S value, like in the Cosmos SDK.
+/// The public key may be compressed or uncompressed.
+/// Returns an error if any of the inputs is malformed, and `Ok(false)` if the signature is not valid.
+pub fn verify_prehashed(
+    message_hash: &[u8],
+    signature: &[u8],
+    public_key: &[u8],
+) -> Result<bool, CryptoError> {
+    let msg = secp256k1::Message::from_slice(message_hash).map_err(|err| {
+        debug!("Malformed message hash: {:?}", err);
+        CryptoError::ParsingError
+    })?;
+    let sec_signature = secp256k1::Signature::from_compact(signature).map_err(|err| {
+        debug!("Malformed signature: {:?}", err);
+        CryptoError::ParsingError
+    })?;
+    let sec_public_key = secp256k1::PublicKey::from_slice(public_key).map_err(|err| {
+        debug!("Malformed public key: {:?}", err);
+        CryptoError::KeyError
+    })?;
+
+    let verifier = Secp256k1::verification_only();
+    Ok(verifier
+        .verify(&msg, &sec_signature, &sec_public_key)
+        .is_ok())
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use secp256k1::{Message, PublicKey, SecretKey};
+
+    fn sign(message_hash: &[u8; 32]) -> (Vec<u8>, Vec<u8>) {
+        let secp = Secp256k1::signing_only();
+        let secret_key = SecretKey::from_slice(&[7u8; 32]).unwrap();
+        let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+        let signature = secp.sign(&Message::from_slice(message_hash).unwrap(), &secret_key);
+
+        (
+            signature.serialize_compact().to_vec(),
+            public_key.serialize().to_vec(),
+        )
+    }
+
+    pub fn test_verify_prehashed() {
+        let message_hash = Sha256::digest(b"message");
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&message_hash);
+        let (signature, public_key) = sign(&hash);
+
+        assert!(verify_prehashed(&hash, &signature, &public_key).unwrap());
+
+        let mut other_hash = hash;
+        other_hash[0] ^= 1;
+        assert!(!verify_prehashed(&other_hash, &signature, &public_key).unwrap());
+    }
+
+    pub fn test_verify_prehashed_rejects_malformed_input() {
+        let hash = [1u8; 32];
+        let (signature, public_key) = sign(&hash);
+
+        assert!(matches!(
+            verify_prehashed(&hash[..31], &signature, &public_key),
+            Err(CryptoError::ParsingError)
+        ));
+        assert!(matches!(
+            verify_prehashed(&hash, &signature[..63], &public_key),
+            Err(CryptoError::ParsingError)
+        ));
+        assert!(matches!(
+            verify_prehashed(&hash, &signature, &public_key[..32]),
+            Err(CryptoError::KeyError)
+        ));
+    }
+}
+
 // TODO: Can we get rid of this comment below?
 
 // use super::keys::SECRET_KEY_SIZE;
@@ -0,0 +1,184 @@
+use std::convert::TryFrom;
+
+use log::*;
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::cosmwasm::encoding::Binary;
+use crate::cosmwasm::sign_doc_direct::Any;
+use crate::cosmwasm::types::CanonicalAddr;
+use crate::crypto::pubkey_encoding::{amino_encode, to_any, ProtoPubKey};
+use crate::crypto::traits::PubKey;
+use crate::crypto::CryptoError;
+
+pub const UNCOMPRESSED_PUBLIC_KEY_SIZE: usize = 65;
+pub const SIGNATURE_SIZE: usize = 64;
+
+// The amino prefix of "tendermint/PubKeySecp256r1", which is registered by types/secp256r1 in Go
+const SECP256R1_PREFIX: [u8; 4] = [140, 23, 94, 113];
+const SECP256R1_PROTO_NAME: &str = "cosmos.crypto.secp256r1.PubKey";
+const SECP256R1_TYPE_URL: &str = "/cosmos.crypto.secp256r1.PubKey";
+
+/// Half of the order of the P-256 group, big endian
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xde, 0x73, 0x7d, 0x56, 0xd3, 0x8b, 0xcf, 0x42, 0x79, 0xdc, 0xe5, 0x61, 0x7e, 0x31, 0x92, 0xa8,
+];
+
+/// The public key of an account that signs with secp256r1 (NIST P-256), e.g. with a hardware key.
+///
+/// The key is kept uncompressed, because ring can only verify signatures of uncompressed keys,
+/// and it is serialized as the base64 of the uncompressed key, like Go encodes it.
+/// Addresses and the protobuf encoding still use the compressed key, like the Cosmos SDK.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(try_from = "Binary", into = "Binary")]
+pub struct Secp256r1PubKey(Vec<u8>);
+
+impl TryFrom<Vec<u8>> for Secp256r1PubKey {
+    type Error = String;
+
+    fn try_from(key: Vec<u8>) -> Result<Self, Self::Error> {
+        if key.len() != UNCOMPRESSED_PUBLIC_KEY_SIZE || key[0] != 0x04 {
+            return Err("expected an uncompressed secp256r1 public key".to_string());
+        }
+
+        Ok(Self(key))
+    }
+}
+
+impl TryFrom<Binary> for Secp256r1PubKey {
+    type Error = String;
+
+    fn try_from(key: Binary) -> Result<Self, Self::Error> {
+        Self::try_from(key.0)
+    }
+}
+
+impl From<Secp256r1PubKey> for Binary {
+    fn from(key: Secp256r1PubKey) -> Self {
+        Binary(key.0)
+    }
+}
+
+impl Secp256r1PubKey {
+    /// The SEC1 compressed form of the key: parity of y | x
+    pub fn compressed(&self) -> Vec<u8> {
+        let mut compressed = Vec::with_capacity(33);
+        compressed.push(0x02 | (self.0[64] & 1));
+        compressed.extend_from_slice(&self.0[1..33]);
+        compressed
+    }
+}
+
+impl PubKey for Secp256r1PubKey {
+    fn get_address(&self) -> CanonicalAddr {
+        // Ref: https://github.com/cosmos/cosmos-sdk/blob/master/docs/architecture/adr-028-public-key-addresses.md
+        // Truncated to 20 bytes, which is the only address length our SDK accepts
+        let type_hash = Sha256::digest(SECP256R1_PROTO_NAME.as_bytes());
+        let address = Sha256::new()
+            .chain(&type_hash)
+            .chain(&self.compressed())
+            .result();
+        CanonicalAddr(Binary(address[..20].to_vec()))
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        // Go registers the uncompressed key with amino
+        amino_encode(&SECP256R1_PREFIX, &self.0)
+    }
+
+    fn proto_any(&self) -> Any {
+        to_any(
+            SECP256R1_TYPE_URL,
+            &ProtoPubKey {
+                key: self.compressed(),
+            },
+        )
+    }
+
+    fn verify_bytes(&self, bytes: &[u8], sig: &[u8]) -> Result<(), CryptoError> {
+        // The signature is r | s, each 32 bytes big endian
+        if sig.len() != SIGNATURE_SIZE {
+            warn!("Malformed signature length: {:?}", sig.len());
+            return Err(CryptoError::VerificationError);
+        }
+
+        // Like the Cosmos SDK, only the lower S value is accepted, so signatures aren't malleable.
+        // Both are big endian and of the same length, so comparing the bytes compares the numbers.
+        if sig[32..] > HALF_ORDER[..] {
+            warn!("Signature is not normalized to the lower S value");
+            return Err(CryptoError::VerificationError);
+        }
+
+        UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &self.0)
+            .verify(bytes, sig)
+            .map_err(|err| {
+                warn!(
+                    "Failed to verify signatures for the given transaction: {:?}",
+                    err
+                );
+                CryptoError::VerificationError
+            })
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    // Generated with a fixed private key, signing "sign me"
+    const PUBLIC_KEY: &str = "04d8cd12ea5c67f2f8a00c1124893edcfa6754c4d6cede6be13bdf2295c810a97fa5a89d2d2a360c0ca9a4d6c7c9ed4b28d3e199d6627f2e696d689c310a5b0f48";
+    const COMPRESSED_PUBLIC_KEY: &str =
+        "02d8cd12ea5c67f2f8a00c1124893edcfa6754c4d6cede6be13bdf2295c810a97f";
+    const SIGNATURE: &str = "5d10ad4b2a3355af53015d1390e7eac8000398a02d184d8514b8f6c0c77126366012dc7b706cee112ee776fe62c15007d95310c768095b2edd270a6f7a3301c2";
+    // The same signature, with S replaced by the order of the group minus S
+    const HIGH_S_SIGNATURE: &str = "5d10ad4b2a3355af53015d1390e7eac8000398a02d184d8514b8f6c0c77126369fed23838f9311efd11889019d3eaff7e393e9e63f0e43561692c0538230238f";
+
+    pub fn public_key() -> Secp256r1PubKey {
+        Secp256r1PubKey::try_from(hex::decode(PUBLIC_KEY).unwrap()).unwrap()
+    }
+
+    pub fn signature() -> Vec<u8> {
+        hex::decode(SIGNATURE).unwrap()
+    }
+
+    pub fn test_secp256r1_verify_bytes() {
+        let public_key = public_key();
+
+        assert!(public_key.verify_bytes(b"sign me", &signature()).is_ok());
+        assert!(public_key.verify_bytes(b"sign you", &signature()).is_err());
+        assert!(public_key
+            .verify_bytes(b"sign me", &signature()[..63])
+            .is_err());
+    }
+
+    pub fn test_secp256r1_rejects_high_s() {
+        let high_s = hex::decode(HIGH_S_SIGNATURE).unwrap();
+
+        assert!(public_key().verify_bytes(b"sign me", &high_s).is_err());
+    }
+
+    pub fn test_secp256r1_encoding() {
+        let public_key = public_key();
+        let compressed = hex::decode(COMPRESSED_PUBLIC_KEY).unwrap();
+
+        assert_eq!(public_key.compressed(), compressed);
+        assert_eq!(
+            public_key.get_address().as_slice(),
+            &hex::decode("06608f8f9f5dfe08fbab2a936e5d6379f3feadfe").unwrap()[..]
+        );
+
+        let mut amino = vec![140, 23, 94, 113, 65];
+        amino.extend_from_slice(&hex::decode(PUBLIC_KEY).unwrap());
+        assert_eq!(public_key.bytes(), amino);
+
+        let any = public_key.proto_any();
+        let mut proto = vec![0x0a, 33];
+        proto.extend_from_slice(&compressed);
+        assert_eq!(any.type_url, "/cosmos.crypto.secp256r1.PubKey");
+        assert_eq!(any.value, proto);
+
+        assert!(Secp256r1PubKey::try_from(compressed).is_err());
+    }
+}
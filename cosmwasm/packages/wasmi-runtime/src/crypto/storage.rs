@@ -1,6 +1,7 @@
 use crate::crypto::ed25519::Ed25519PrivateKey;
+use crate::crypto::traits::ExportECKey;
 use crate::crypto::traits::SealedKey;
-use crate::crypto::{AESKey, KeyPair, Seed, SECRET_KEY_SIZE};
+use crate::crypto::{AESKey, KeyPair, Seed, HASH_SIZE, SECRET_KEY_SIZE};
 use enclave_ffi_types::EnclaveError;
 use log::*;
 use std::io::{Read, Write};
@@ -40,6 +41,34 @@ impl SealedKey for KeyPair {
     }
 }
 
+/// Used for the hash of the chain id, which is sealed next to the seed it was created with
+impl SealedKey for [u8; HASH_SIZE] {
+    fn seal(&self, filepath: &str) -> Result<(), EnclaveError> {
+        seal(self, filepath)
+    }
+
+    fn unseal(filepath: &str) -> Result<Self, EnclaveError> {
+        let buf = open(filepath)?;
+        Ok(*buf.key_ref())
+    }
+}
+
+/// Used for the active epoch of the consensus seed. Sealed files hold 32 bytes, so it's padded
+impl SealedKey for u32 {
+    fn seal(&self, filepath: &str) -> Result<(), EnclaveError> {
+        let mut data = [0u8; 32];
+        data[..4].copy_from_slice(&self.to_be_bytes());
+        seal(&data, filepath)
+    }
+
+    fn unseal(filepath: &str) -> Result<Self, EnclaveError> {
+        let buf = open(filepath)?;
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(&buf.key_ref()[..4]);
+        Ok(u32::from_be_bytes(bytes))
+    }
+}
+
 fn seal(data: &[u8; 32], filepath: &str) -> Result<(), EnclaveError> {
     let mut file = SgxFile::create(filepath).map_err(|_err| EnclaveError::FailedUnseal)?;
 
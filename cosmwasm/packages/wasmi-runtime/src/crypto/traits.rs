@@ -1,11 +1,14 @@
+#[cfg(not(feature = "native"))]
 use enclave_ffi_types::EnclaveError;
 
+use crate::cosmwasm::sign_doc_direct::Any;
 use crate::cosmwasm::types::CanonicalAddr;
 use crate::crypto::CryptoError;
 
 pub const HMAC_SIGNATURE_SIZE: usize = 32;
 pub const EC_256_PRIVATE_KEY_SIZE: usize = 32;
 
+#[cfg(not(feature = "native"))]
 pub trait Encryptable {
     fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError>;
     fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, CryptoError>;
@@ -16,6 +19,7 @@ pub trait SIVEncryptable {
     fn decrypt_siv(&self, plaintext: &[u8], ad: Option<&[&[u8]]>) -> Result<Vec<u8>, CryptoError>;
 }
 
+#[cfg(not(feature = "native"))]
 pub trait SealedKey
 where
     Self: std::marker::Sized,
@@ -24,6 +28,7 @@ where
     fn unseal(filepath: &str) -> Result<Self, EnclaveError>;
 }
 
+#[cfg(not(feature = "native"))]
 pub trait Rng {
     fn rand_slice(buf: &mut [u8]) -> Result<(), CryptoError>;
 }
@@ -45,6 +50,9 @@ pub trait ExportECKey {
 // https://github.com/tendermint/tendermint/blob/v0.33.3/crypto/crypto.go#L22
 pub trait PubKey: PartialEq {
     fn get_address(&self) -> CanonicalAddr;
+    /// The amino encoding of the key
     fn bytes(&self) -> Vec<u8>;
+    /// The protobuf encoding of the key, as it appears in the `AuthInfo` of a transaction
+    fn proto_any(&self) -> Any;
     fn verify_bytes(&self, bytes: &[u8], sig: &[u8]) -> Result<(), CryptoError>;
 }
@@ -3,14 +3,19 @@ use log::*;
 use std::ffi::c_void;
 
 use enclave_ffi_types::{
-    Ctx, EnclaveBuffer, EnclaveError, HandleResult, HealthCheckResult, InitResult, QueryResult,
+    Ctx, EnclaveBuffer, EnclaveError, HandleResult, HealthCheckResult, InitResult, MigrateResult,
+    ModuleCacheStats, QueryBatchResult, QueryResult, ReissueResult,
 };
 use std::panic;
+#[cfg(feature = "native")]
+use std::sync::Mutex as SgxMutex;
+#[cfg(not(feature = "native"))]
 use std::sync::SgxMutex;
 
 use crate::results::{
     result_handle_success_to_handleresult, result_init_success_to_initresult,
-    result_query_success_to_queryresult,
+    result_migrate_success_to_migrateresult, result_query_success_to_queryresult,
+    result_reissue_success_to_reissueresult,
 };
 use crate::{
     oom_handler, recursion_depth,
@@ -107,6 +112,8 @@ pub unsafe extern "C" fn ecall_init(
     context: Ctx,
     gas_limit: u64,
     used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
     contract: *const u8,
     contract_len: usize,
     env: *const u8,
@@ -115,6 +122,81 @@ pub unsafe extern "C" fn ecall_init(
     msg_len: usize,
     sig_info: *const u8,
     sig_info_len: usize,
+) -> InitResult {
+    init_impl(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+        false,
+    )
+}
+
+/// Runs `init` like `ecall_init`, except that the contract gets no randomness and the seed
+/// epoch isn't activated. The host should run it on a store it throws away afterwards.
+/// This returns the gas that executing the message would use, and its (encrypted) output.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_simulate_init(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> InitResult {
+    init_impl(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+        true,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn init_impl(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+    simulate: bool,
 ) -> InitResult {
     let _recursion_guard = match recursion_depth::guard() {
         Ok(rg) => rg,
@@ -154,6 +236,17 @@ pub unsafe extern "C" fn ecall_init(
         return result_init_success_to_initresult(Err(EnclaveError::FailedFunctionCall));
     }
 
+    // An empty gas schedule means the default costs should be used
+    let gas_schedule = if gas_schedule_len == 0 {
+        &[][..]
+    } else {
+        if let Err(_e) = validate_const_ptr(gas_schedule, gas_schedule_len as usize) {
+            error!("Tried to access data outside enclave memory!");
+            return result_init_success_to_initresult(Err(EnclaveError::FailedFunctionCall));
+        }
+        std::slice::from_raw_parts(gas_schedule, gas_schedule_len)
+    };
+
     let contract = std::slice::from_raw_parts(contract, contract_len);
     let env = std::slice::from_raw_parts(env, env_len);
     let msg = std::slice::from_raw_parts(msg, msg_len);
@@ -164,10 +257,12 @@ pub unsafe extern "C" fn ecall_init(
             context,
             gas_limit,
             &mut local_used_gas,
+            gas_schedule,
             contract,
             env,
             msg,
             sig_info,
+            simulate,
         );
         *used_gas = local_used_gas;
         result_init_success_to_initresult(result)
@@ -204,6 +299,82 @@ pub unsafe extern "C" fn ecall_handle(
     context: Ctx,
     gas_limit: u64,
     used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> HandleResult {
+    handle_impl(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+        false,
+    )
+}
+
+/// Runs `handle` like `ecall_handle`, except that the contract gets no randomness and the seed
+/// epoch isn't activated. The host should run it on a store it throws away afterwards.
+/// This returns the gas that executing the message would use, and its (encrypted) output.
+///
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_simulate_handle(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> HandleResult {
+    handle_impl(
+        context,
+        gas_limit,
+        used_gas,
+        gas_schedule,
+        gas_schedule_len,
+        contract,
+        contract_len,
+        env,
+        env_len,
+        msg,
+        msg_len,
+        sig_info,
+        sig_info_len,
+        true,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+unsafe fn handle_impl(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
     contract: *const u8,
     contract_len: usize,
     env: *const u8,
@@ -212,6 +383,7 @@ pub unsafe extern "C" fn ecall_handle(
     msg_len: usize,
     sig_info: *const u8,
     sig_info_len: usize,
+    simulate: bool,
 ) -> HandleResult {
     let _recursion_guard = match recursion_depth::guard() {
         Ok(rg) => rg,
@@ -251,6 +423,17 @@ pub unsafe extern "C" fn ecall_handle(
         return result_handle_success_to_handleresult(Err(EnclaveError::FailedFunctionCall));
     }
 
+    // An empty gas schedule means the default costs should be used
+    let gas_schedule = if gas_schedule_len == 0 {
+        &[][..]
+    } else {
+        if let Err(_e) = validate_const_ptr(gas_schedule, gas_schedule_len as usize) {
+            error!("Tried to access data outside enclave memory!");
+            return result_handle_success_to_handleresult(Err(EnclaveError::FailedFunctionCall));
+        }
+        std::slice::from_raw_parts(gas_schedule, gas_schedule_len)
+    };
+
     let contract = std::slice::from_raw_parts(contract, contract_len);
     let env = std::slice::from_raw_parts(env, env_len);
     let msg = std::slice::from_raw_parts(msg, msg_len);
@@ -261,10 +444,12 @@ pub unsafe extern "C" fn ecall_handle(
             context,
             gas_limit,
             &mut local_used_gas,
+            gas_schedule,
             contract,
             env,
             msg,
             sig_info,
+            simulate,
         );
         *used_gas = local_used_gas;
         result_handle_success_to_handleresult(result)
@@ -301,6 +486,8 @@ pub unsafe extern "C" fn ecall_query(
     context: Ctx,
     gas_limit: u64,
     used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
     contract: *const u8,
     contract_len: usize,
     msg: *const u8,
@@ -336,11 +523,29 @@ pub unsafe extern "C" fn ecall_query(
         return result_query_success_to_queryresult(Err(EnclaveError::FailedFunctionCall));
     }
 
+    // An empty gas schedule means the default costs should be used
+    let gas_schedule = if gas_schedule_len == 0 {
+        &[][..]
+    } else {
+        if let Err(_e) = validate_const_ptr(gas_schedule, gas_schedule_len as usize) {
+            error!("Tried to access data outside enclave memory!");
+            return result_query_success_to_queryresult(Err(EnclaveError::FailedFunctionCall));
+        }
+        std::slice::from_raw_parts(gas_schedule, gas_schedule_len)
+    };
+
     let contract = std::slice::from_raw_parts(contract, contract_len);
     let msg = std::slice::from_raw_parts(msg, msg_len);
     let result = panic::catch_unwind(|| {
         let mut local_used_gas = *used_gas;
-        let result = crate::wasm::query(context, gas_limit, &mut local_used_gas, contract, msg);
+        let result = crate::wasm::query(
+            context,
+            gas_limit,
+            &mut local_used_gas,
+            gas_schedule,
+            contract,
+            msg,
+        );
         *used_gas = local_used_gas;
         result_query_success_to_queryresult(result)
     });
@@ -369,6 +574,261 @@ pub unsafe extern "C" fn ecall_query(
     }
 }
 
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_query_batch(
+    contexts: *const Ctx,
+    contexts_len: usize,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    batch: *const u8,
+    batch_len: usize,
+    results: *mut QueryResult,
+) -> QueryBatchResult {
+    let _recursion_guard = match recursion_depth::guard() {
+        Ok(rg) => rg,
+        Err(err) => {
+            error!("recursion limit exceeded, can not perform query batch!");
+            return QueryBatchResult::Failure { err };
+        }
+    };
+    if let Err(err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler!");
+        return QueryBatchResult::Failure { err };
+    }
+    if let Err(_e) = validate_mut_ptr(used_gas as _, std::mem::size_of::<u64>()) {
+        error!("Tried to access data outside enclave memory!");
+        return QueryBatchResult::Failure {
+            err: EnclaveError::FailedFunctionCall,
+        };
+    }
+    if let Err(_e) = validate_mut_ptr(
+        results as _,
+        contexts_len * std::mem::size_of::<QueryResult>(),
+    ) {
+        error!("Tried to access data outside enclave memory!");
+        return QueryBatchResult::Failure {
+            err: EnclaveError::FailedFunctionCall,
+        };
+    }
+    if let Err(_e) = validate_const_ptr(contexts as _, contexts_len * std::mem::size_of::<Ctx>()) {
+        error!("Tried to access data outside enclave memory!");
+        return QueryBatchResult::Failure {
+            err: EnclaveError::FailedFunctionCall,
+        };
+    }
+    if let Err(_e) = validate_const_ptr(batch, batch_len as usize) {
+        error!("Tried to access data outside enclave memory!");
+        return QueryBatchResult::Failure {
+            err: EnclaveError::FailedFunctionCall,
+        };
+    }
+
+    // An empty gas schedule means the default costs should be used
+    let gas_schedule = if gas_schedule_len == 0 {
+        &[][..]
+    } else {
+        if let Err(_e) = validate_const_ptr(gas_schedule, gas_schedule_len as usize) {
+            error!("Tried to access data outside enclave memory!");
+            return QueryBatchResult::Failure {
+                err: EnclaveError::FailedFunctionCall,
+            };
+        }
+        std::slice::from_raw_parts(gas_schedule, gas_schedule_len)
+    };
+
+    let contexts = std::slice::from_raw_parts(contexts, contexts_len);
+    let batch = std::slice::from_raw_parts(batch, batch_len);
+    let result = panic::catch_unwind(|| {
+        let mut local_used_gas = *used_gas;
+        let result = crate::wasm::query_batch(
+            contexts,
+            gas_limit,
+            &mut local_used_gas,
+            gas_schedule,
+            batch,
+        );
+        *used_gas = local_used_gas;
+
+        match result {
+            Ok(query_results) => {
+                for (index, query_result) in query_results.into_iter().enumerate() {
+                    std::ptr::write(
+                        results.add(index),
+                        result_query_success_to_queryresult(query_result),
+                    );
+                }
+                QueryBatchResult::Success
+            }
+            Err(err) => QueryBatchResult::Failure { err },
+        }
+    });
+
+    if let Err(err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer!");
+        return QueryBatchResult::Failure { err };
+    }
+
+    if let Ok(res) = result {
+        res
+    } else {
+        *used_gas = gas_limit / 2;
+
+        if oom_handler::get_then_clear_oom_happened() {
+            error!("Call ecall_query_batch failed because the enclave ran out of memory!");
+            QueryBatchResult::Failure {
+                err: EnclaveError::OutOfMemory,
+            }
+        } else {
+            error!("Call ecall_query_batch panicked unexpectedly!");
+            QueryBatchResult::Failure {
+                err: EnclaveError::Panic,
+            }
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_migrate(
+    context: Ctx,
+    gas_limit: u64,
+    used_gas: *mut u64,
+    gas_schedule: *const u8,
+    gas_schedule_len: usize,
+    contract: *const u8,
+    contract_len: usize,
+    env: *const u8,
+    env_len: usize,
+    msg: *const u8,
+    msg_len: usize,
+    sig_info: *const u8,
+    sig_info_len: usize,
+) -> MigrateResult {
+    let _recursion_guard = match recursion_depth::guard() {
+        Ok(rg) => rg,
+        Err(err) => {
+            // https://github.com/enigmampc/SecretNetwork/pull/517#discussion_r481924571
+            // I believe that this error condition is currently unreachable.
+            // I think we can safely remove it completely right now, and have
+            // recursion_depth::increment() simply increment the counter with no further checks,
+            // but i wanted to stay on the safe side here, in case something changes in the
+            // future, and we can easily spot that we forgot to add a limit somewhere.
+            error!("recursion limit exceeded, can not perform migrate!");
+            return MigrateResult::Failure { err };
+        }
+    };
+    if let Err(err) = oom_handler::register_oom_handler() {
+        error!("Could not register OOM handler!");
+        return MigrateResult::Failure { err };
+    }
+    if let Err(_e) = validate_mut_ptr(used_gas as _, std::mem::size_of::<u64>()) {
+        error!("Tried to access data outside enclave memory!");
+        return result_migrate_success_to_migrateresult(Err(EnclaveError::FailedFunctionCall));
+    }
+    if let Err(_e) = validate_const_ptr(env, env_len as usize) {
+        error!("Tried to access data outside enclave memory!");
+        return result_migrate_success_to_migrateresult(Err(EnclaveError::FailedFunctionCall));
+    }
+    if let Err(_e) = validate_const_ptr(msg, msg_len as usize) {
+        error!("Tried to access data outside enclave memory!");
+        return result_migrate_success_to_migrateresult(Err(EnclaveError::FailedFunctionCall));
+    }
+    if let Err(_e) = validate_const_ptr(contract, contract_len as usize) {
+        error!("Tried to access data outside enclave memory!");
+        return result_migrate_success_to_migrateresult(Err(EnclaveError::FailedFunctionCall));
+    }
+    if let Err(_e) = validate_const_ptr(sig_info, sig_info_len as usize) {
+        error!("Tried to access data outside enclave memory!");
+        return result_migrate_success_to_migrateresult(Err(EnclaveError::FailedFunctionCall));
+    }
+
+    // An empty gas schedule means the default costs should be used
+    let gas_schedule = if gas_schedule_len == 0 {
+        &[][..]
+    } else {
+        if let Err(_e) = validate_const_ptr(gas_schedule, gas_schedule_len as usize) {
+            error!("Tried to access data outside enclave memory!");
+            return result_migrate_success_to_migrateresult(Err(EnclaveError::FailedFunctionCall));
+        }
+        std::slice::from_raw_parts(gas_schedule, gas_schedule_len)
+    };
+
+    let contract = std::slice::from_raw_parts(contract, contract_len);
+    let env = std::slice::from_raw_parts(env, env_len);
+    let msg = std::slice::from_raw_parts(msg, msg_len);
+    let sig_info = std::slice::from_raw_parts(sig_info, sig_info_len);
+    let result = panic::catch_unwind(|| {
+        let mut local_used_gas = *used_gas;
+        let result = crate::wasm::migrate(
+            context,
+            gas_limit,
+            &mut local_used_gas,
+            gas_schedule,
+            contract,
+            env,
+            msg,
+            sig_info,
+        );
+        *used_gas = local_used_gas;
+        result_migrate_success_to_migrateresult(result)
+    });
+
+    if let Err(err) = oom_handler::restore_safety_buffer() {
+        error!("Could not restore OOM safety buffer!");
+        return MigrateResult::Failure { err };
+    }
+
+    if let Ok(res) = result {
+        res
+    } else {
+        *used_gas = gas_limit / 2;
+
+        if oom_handler::get_then_clear_oom_happened() {
+            error!("Call ecall_migrate failed because the enclave ran out of memory!");
+            MigrateResult::Failure {
+                err: EnclaveError::OutOfMemory,
+            }
+        } else {
+            error!("Call ecall_migrate panicked unexpectedly!");
+            MigrateResult::Failure {
+                err: EnclaveError::Panic,
+            }
+        }
+    }
+}
+
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_reissue_contract_proofs(
+    env: *const u8,
+    env_len: usize,
+) -> ReissueResult {
+    if let Err(_e) = validate_const_ptr(env, env_len as usize) {
+        error!("Tried to access data outside enclave memory!");
+        return result_reissue_success_to_reissueresult(Err(EnclaveError::FailedFunctionCall));
+    }
+
+    let env = std::slice::from_raw_parts(env, env_len);
+    let result = panic::catch_unwind(|| {
+        result_reissue_success_to_reissueresult(crate::wasm::reissue_contract_proofs(env))
+    });
+
+    if let Ok(res) = result {
+        res
+    } else {
+        error!("Call ecall_reissue_contract_proofs panicked unexpectedly!");
+        ReissueResult::Failure {
+            err: EnclaveError::Panic,
+        }
+    }
+}
+
 /// # Safety
 /// Always use protection
 #[no_mangle]
@@ -376,6 +836,13 @@ pub unsafe extern "C" fn ecall_health_check() -> HealthCheckResult {
     HealthCheckResult::Success
 }
 
+/// # Safety
+/// Always use protection
+#[no_mangle]
+pub unsafe extern "C" fn ecall_get_module_cache_stats() -> ModuleCacheStats {
+    crate::wasm::module_cache_stats()
+}
+
 #[cfg(feature = "test")]
 pub mod tests {
     use super::*;
@@ -1,8 +1,14 @@
 //! This file should be autogenerated based on the headers created from the .edl file.
 
-use enclave_ffi_types::{Ctx, EnclaveBuffer, OcallReturn, UntrustedVmError, UserSpaceBuffer};
+use enclave_ffi_types::{
+    Ctx, EnclaveBuffer, FlushGas, OcallReturn, UntrustedVmError, UserSpaceBuffer,
+};
 use sgx_types::*;
 
+#[cfg(feature = "native")]
+pub use native::*;
+
+#[cfg(not(feature = "native"))]
 extern "C" {
     pub fn ocall_allocate(
         retval: *mut UserSpaceBuffer,
@@ -31,27 +37,17 @@ extern "C" {
         query_len: usize,
     ) -> sgx_status_t;
 
-    pub fn ocall_remove_db(
-        retval: *mut OcallReturn,
-        context: Ctx,
-        vm_error: *mut UntrustedVmError,
-        gas_used: *mut u64,
-        key: *const u8,
-        key_len: usize,
-    ) -> sgx_status_t;
-
-    pub fn ocall_write_db(
+    pub fn ocall_flush_db(
         retval: *mut OcallReturn,
         context: Ctx,
         vm_error: *mut UntrustedVmError,
-        gas_used: *mut u64,
-        key: *const u8,
-        key_len: usize,
-        value: *const u8,
-        value_len: usize,
+        gas_used: *mut FlushGas,
+        ops: *const u8,
+        ops_len: usize,
     ) -> sgx_status_t;
 }
 
+#[cfg(not(feature = "native"))]
 extern "C" {
     pub fn ocall_sgx_init_quote(
         ret_val: *mut sgx_status_t,
@@ -59,6 +55,10 @@ extern "C" {
         ret_gid: *mut sgx_epid_group_id_t,
     ) -> sgx_status_t;
     pub fn ocall_get_ias_socket(ret_val: *mut sgx_status_t, ret_fd: *mut i32) -> sgx_status_t;
+    pub fn ocall_get_sgx_certificates_socket(
+        ret_val: *mut sgx_status_t,
+        ret_fd: *mut i32,
+    ) -> sgx_status_t;
     pub fn ocall_get_quote(
         ret_val: *mut sgx_status_t,
         p_sigrl: *const u8,
@@ -72,4 +72,108 @@ extern "C" {
         maxlen: u32,
         p_quote_len: *mut u32,
     ) -> sgx_status_t;
+    pub fn ocall_get_qe_target_info(
+        ret_val: *mut sgx_status_t,
+        ret_ti: *mut sgx_target_info_t,
+    ) -> sgx_status_t;
+    pub fn ocall_get_dcap_quote(
+        ret_val: *mut sgx_status_t,
+        p_report: *const sgx_report_t,
+        p_quote: *mut u8,
+        maxlen: u32,
+        p_quote_len: *mut u32,
+    ) -> sgx_status_t;
+}
+
+/// Native builds are linked into the host process, so their ocalls call the implementations of the
+/// host directly, instead of going through the untrusted bridge that the SDK generates.
+#[cfg(feature = "native")]
+mod native {
+    use super::*;
+
+    mod host {
+        use super::*;
+
+        extern "C" {
+            pub fn ocall_allocate(buffer: *const u8, length: usize) -> UserSpaceBuffer;
+
+            pub fn ocall_read_db(
+                context: Ctx,
+                vm_error: *mut UntrustedVmError,
+                gas_used: *mut u64,
+                value: *mut EnclaveBuffer,
+                key: *const u8,
+                key_len: usize,
+            ) -> OcallReturn;
+
+            pub fn ocall_query_chain(
+                context: Ctx,
+                vm_error: *mut UntrustedVmError,
+                gas_used: *mut u64,
+                gas_limit: u64,
+                value: *mut EnclaveBuffer,
+                query: *const u8,
+                query_len: usize,
+            ) -> OcallReturn;
+
+            pub fn ocall_flush_db(
+                context: Ctx,
+                vm_error: *mut UntrustedVmError,
+                gas_used: *mut FlushGas,
+                ops: *const u8,
+                ops_len: usize,
+            ) -> OcallReturn;
+        }
+    }
+
+    pub unsafe fn ocall_allocate(
+        retval: *mut UserSpaceBuffer,
+        buffer: *const u8,
+        length: usize,
+    ) -> sgx_status_t {
+        *retval = host::ocall_allocate(buffer, length);
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    pub unsafe fn ocall_read_db(
+        retval: *mut OcallReturn,
+        context: Ctx,
+        vm_error: *mut UntrustedVmError,
+        gas_used: *mut u64,
+        value: *mut EnclaveBuffer,
+        key: *const u8,
+        key_len: usize,
+    ) -> sgx_status_t {
+        *retval = host::ocall_read_db(context, vm_error, gas_used, value, key, key_len);
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub unsafe fn ocall_query_chain(
+        retval: *mut OcallReturn,
+        context: Ctx,
+        vm_error: *mut UntrustedVmError,
+        gas_used: *mut u64,
+        gas_limit: u64,
+        value: *mut EnclaveBuffer,
+        query: *const u8,
+        query_len: usize,
+    ) -> sgx_status_t {
+        *retval = host::ocall_query_chain(
+            context, vm_error, gas_used, gas_limit, value, query, query_len,
+        );
+        sgx_status_t::SGX_SUCCESS
+    }
+
+    pub unsafe fn ocall_flush_db(
+        retval: *mut OcallReturn,
+        context: Ctx,
+        vm_error: *mut UntrustedVmError,
+        gas_used: *mut FlushGas,
+        ops: *const u8,
+        ops_len: usize,
+    ) -> sgx_status_t {
+        *retval = host::ocall_flush_db(context, vm_error, gas_used, ops, ops_len);
+        sgx_status_t::SGX_SUCCESS
+    }
 }
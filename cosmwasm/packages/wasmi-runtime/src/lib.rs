@@ -1,6 +1,8 @@
 #![feature(try_reserve)]
-// similar trick to get the IDE to use sgx_tstd even when it doesn't know we're targeting SGX
-#[cfg(not(target_env = "sgx"))]
+#![cfg_attr(feature = "native", feature(alloc_error_hook))]
+// similar trick to get the IDE to use sgx_tstd even when it doesn't know we're targeting SGX.
+// Native builds use the real std instead.
+#[cfg(all(not(target_env = "sgx"), not(feature = "native")))]
 extern crate sgx_tstd as std;
 // This annotation is here to trick the IDE into ignoring the extern crate, and instead pull in sgx_types from our
 // Cargo.toml. By importing sgx_types using `extern crate` but without letting it resolve in Cargo.toml when compiling
@@ -22,19 +24,37 @@ pub mod imports;
 pub mod logger;
 mod oom_handler;
 mod recursion_depth;
+#[cfg(not(feature = "native"))]
 pub mod registration;
 use std::env;
 
 mod consts;
 mod cosmwasm;
 mod crypto;
+mod proto;
 mod results;
+#[cfg(not(feature = "native"))]
 mod storage;
 mod utils;
 mod wasm;
 
 mod tests;
 
+#[cfg(feature = "native")]
+pub use wasm::testing;
+
+#[cfg(all(feature = "production", feature = "gas-profiling"))]
+compile_error!("The gas-profiling feature must not be enabled in production builds");
+
+#[cfg(all(feature = "production", feature = "native"))]
+compile_error!("The native feature must not be enabled in production builds");
+
+#[cfg(all(feature = "sgx", feature = "native"))]
+compile_error!("Native builds must be built without the default features");
+
+#[cfg(not(any(feature = "sgx", feature = "native")))]
+compile_error!("Either the sgx or the native feature must be enabled");
+
 static LOGGER: SimpleLogger = SimpleLogger;
 
 #[cfg(all(feature = "production", feature = "SGX_MODE_HW"))]
@@ -2,9 +2,12 @@ use core::sync::atomic::{AtomicBool, Ordering};
 use enclave_ffi_types::EnclaveError;
 use lazy_static::lazy_static;
 
-#[cfg(not(feature = "production"))]
+#[cfg(all(not(feature = "production"), not(feature = "native")))]
 use std::backtrace::{self, PrintFormat};
 
+#[cfg(feature = "native")]
+use std::sync::Mutex as SgxMutex;
+#[cfg(not(feature = "native"))]
 use std::sync::SgxMutex;
 /// SafetyBuffer is meant to occupy space on the heap, so when a memory
 /// allocation fails we will free this buffer to allow safe panic unwinding
@@ -83,12 +86,13 @@ lazy_static! {
 
 static OOM_HAPPENED: AtomicBool = AtomicBool::new(false);
 
-#[cfg(not(feature = "production"))]
+#[cfg(all(not(feature = "production"), not(feature = "native")))]
 fn enable_backtraces() {
     let _ = backtrace::enable_backtrace("librust_cosmwasm_enclave.signed.so", PrintFormat::Full);
 }
 
-#[cfg(feature = "production")]
+/// Native builds print backtraces like any other process, according to `RUST_BACKTRACE`
+#[cfg(any(feature = "production", feature = "native"))]
 fn enable_backtraces() {}
 
 pub fn register_oom_handler() -> Result<(), EnclaveError> {
@@ -0,0 +1,8 @@
+/// Coin defines a token with a denomination and an amount.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Coin {
+    #[prost(string, tag="1")]
+    pub denom: std::string::String,
+    #[prost(string, tag="2")]
+    pub amount: std::string::String,
+}
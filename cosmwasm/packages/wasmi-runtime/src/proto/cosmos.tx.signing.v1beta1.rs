@@ -0,0 +1,18 @@
+/// SignMode represents a signing mode with its own security guarantees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SignMode {
+    /// SIGN_MODE_UNSPECIFIED specifies an unknown signing mode and will be
+    /// rejected
+    Unspecified = 0,
+    /// SIGN_MODE_DIRECT specifies a signing mode which uses SignDoc and is
+    /// verified with raw bytes from Tx
+    Direct = 1,
+    /// SIGN_MODE_TEXTUAL is a future signing mode that will verify some
+    /// human-readable textual representation on top of the binary representation
+    /// from SIGN_MODE_DIRECT
+    Textual = 2,
+    /// SIGN_MODE_LEGACY_AMINO_JSON is a backwards compatibility mode which uses
+    /// Amino JSON and will be removed in the future
+    LegacyAminoJson = 127,
+}
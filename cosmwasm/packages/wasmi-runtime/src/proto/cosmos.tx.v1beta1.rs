@@ -0,0 +1,105 @@
+/// SignDoc is the type used for generating sign bytes for SIGN_MODE_DIRECT.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignDoc {
+    /// body_bytes is protobuf serialization of a TxBody that matches the
+    /// representation in TxRaw.
+    #[prost(bytes, tag="1")]
+    pub body_bytes: std::vec::Vec<u8>,
+    /// auth_info_bytes is a protobuf serialization of an AuthInfo that matches the
+    /// representation in TxRaw.
+    #[prost(bytes, tag="2")]
+    pub auth_info_bytes: std::vec::Vec<u8>,
+    /// chain_id is the unique identifier of the chain this transaction targets.
+    /// It prevents signed transactions from being used on another chain by an
+    /// attacker
+    #[prost(string, tag="3")]
+    pub chain_id: std::string::String,
+    /// account_number is the account number of the account in state
+    #[prost(uint64, tag="4")]
+    pub account_number: u64,
+}
+/// TxBody is the body of a transaction that all signers sign over.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TxBody {
+    /// messages is a list of messages to be executed. The required signers of
+    /// those messages define the number and order of elements in AuthInfo's
+    /// signer_infos and Tx's signatures. Each required signer address is added to
+    /// the list only the first time it occurs.
+    #[prost(message, repeated, tag="1")]
+    pub messages: ::std::vec::Vec<super::super::super::google::protobuf::Any>,
+    /// memo is any arbitrary memo to be added to the transaction
+    #[prost(string, tag="2")]
+    pub memo: std::string::String,
+    /// timeout is the block height after which this transaction will not
+    /// be processed by the chain
+    #[prost(uint64, tag="3")]
+    pub timeout_height: u64,
+}
+/// AuthInfo describes the fee and signer modes that are used to sign a
+/// transaction.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AuthInfo {
+    /// signer_infos defines the signing modes for the required signers. The number
+    /// and order of elements must match the required signers from TxBody's
+    /// messages. The first element is the primary signer and the one which pays
+    /// the fee.
+    #[prost(message, repeated, tag="1")]
+    pub signer_infos: ::std::vec::Vec<SignerInfo>,
+}
+/// SignerInfo describes the public key and signing mode of a single top-level
+/// signer.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SignerInfo {
+    /// public_key is the public key of the signer. It is optional for accounts
+    /// that already exist in state. If unset, the verifier can use the required
+    /// signer address for this position and lookup the public key.
+    #[prost(message, optional, tag="1")]
+    pub public_key: ::std::option::Option<super::super::super::google::protobuf::Any>,
+    /// mode_info describes the signing mode of the signer and is a nested
+    /// structure to support nested multisig pubkey's
+    #[prost(message, optional, tag="2")]
+    pub mode_info: ::std::option::Option<ModeInfo>,
+    /// sequence is the sequence of the account, which describes the
+    /// number of committed transactions signed by a given address. It is used to
+    /// prevent replay attacks.
+    #[prost(uint64, tag="3")]
+    pub sequence: u64,
+}
+/// ModeInfo describes the signing mode of a single or nested multisig signer.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ModeInfo {
+    /// sum is the oneof that specifies whether this represents a single or nested
+    /// multisig signer
+    #[prost(oneof="mode_info::Sum", tags="1, 2")]
+    pub sum: ::std::option::Option<mode_info::Sum>,
+}
+pub mod mode_info {
+    /// Single is the mode info for a single signer. It is structured as a message
+    /// to allow for additional fields such as locale for SIGN_MODE_TEXTUAL in the
+    /// future
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Single {
+        /// mode is the signing mode of the single signer
+        #[prost(enumeration="super::super::signing::v1beta1::SignMode", tag="1")]
+        pub mode: i32,
+    }
+    /// Multi is the mode info for a multisig public key
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    pub struct Multi {
+        /// mode_infos is the corresponding modes of the signers of the multisig
+        /// which could include nested multisig public keys
+        #[prost(message, repeated, tag="2")]
+        pub mode_infos: ::std::vec::Vec<super::ModeInfo>,
+    }
+    /// sum is the oneof that specifies whether this represents a single or nested
+    /// multisig signer
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Sum {
+        /// single represents a single signer
+        #[prost(message, tag="1")]
+        Single(Single),
+        /// multi represents a nested multisig signer
+        #[prost(message, tag="2")]
+        Multi(Multi),
+    }
+}
@@ -0,0 +1,12 @@
+/// `Any` contains an arbitrary serialized protocol buffer message along with a
+/// URL that describes the type of the serialized message.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Any {
+    /// A URL/resource name that uniquely identifies the type of the serialized
+    /// protocol buffer message.
+    #[prost(string, tag="1")]
+    pub type_url: std::string::String,
+    /// Must be a valid serialized protocol buffer of the above specified type.
+    #[prost(bytes, tag="2")]
+    pub value: std::vec::Vec<u8>,
+}
@@ -0,0 +1,40 @@
+//! The protobuf messages the enclave decodes, generated from the .proto files in `proto` and
+//! `third_party/proto` at the root of the repo by `cosmwasm/devtools/proto-gen`.
+//! Don't edit the generated files, run `make proto-gen` instead.
+
+// The generated types declare every field of the messages, not only the ones the enclave reads
+#![allow(dead_code)]
+
+pub mod cosmos {
+    pub mod base {
+        pub mod v1beta1 {
+            include!("cosmos.base.v1beta1.rs");
+        }
+    }
+
+    pub mod tx {
+        pub mod signing {
+            pub mod v1beta1 {
+                include!("cosmos.tx.signing.v1beta1.rs");
+            }
+        }
+
+        pub mod v1beta1 {
+            include!("cosmos.tx.v1beta1.rs");
+        }
+    }
+}
+
+pub mod google {
+    pub mod protobuf {
+        include!("google.protobuf.rs");
+    }
+}
+
+pub mod secret {
+    pub mod compute {
+        pub mod v1beta1 {
+            include!("secret.compute.v1beta1.rs");
+        }
+    }
+}
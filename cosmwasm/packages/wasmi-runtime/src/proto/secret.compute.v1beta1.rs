@@ -0,0 +1,35 @@
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgInstantiateContract {
+    #[prost(bytes, tag="1")]
+    pub sender: std::vec::Vec<u8>,
+    /// This field is only used for callbacks constructed with this message type
+    #[prost(string, tag="2")]
+    pub callback_code_hash: std::string::String,
+    #[prost(uint64, tag="3")]
+    pub code_id: u64,
+    #[prost(string, tag="4")]
+    pub label: std::string::String,
+    #[prost(bytes, tag="5")]
+    pub init_msg: std::vec::Vec<u8>,
+    #[prost(message, repeated, tag="6")]
+    pub init_funds: ::std::vec::Vec<super::super::super::cosmos::base::v1beta1::Coin>,
+    /// Optional
+    #[prost(bytes, tag="7")]
+    pub callback_sig: std::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MsgExecuteContract {
+    #[prost(bytes, tag="1")]
+    pub sender: std::vec::Vec<u8>,
+    #[prost(bytes, tag="2")]
+    pub contract: std::vec::Vec<u8>,
+    #[prost(bytes, tag="3")]
+    pub msg: std::vec::Vec<u8>,
+    #[prost(string, tag="4")]
+    pub callback_code_hash: std::string::String,
+    #[prost(message, repeated, tag="5")]
+    pub sent_funds: ::std::vec::Vec<super::super::super::cosmos::base::v1beta1::Coin>,
+    /// Optional
+    #[prost(bytes, tag="6")]
+    pub callback_sig: std::vec::Vec<u8>,
+}
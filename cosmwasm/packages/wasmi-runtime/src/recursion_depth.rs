@@ -1,10 +1,14 @@
+#[cfg(feature = "native")]
+use std::sync::Mutex as SgxMutex;
+#[cfg(not(feature = "native"))]
 use std::sync::SgxMutex;
 
 use lazy_static::lazy_static;
 
 use enclave_ffi_types::EnclaveError;
 
-const RECURSION_LIMIT: u8 = 5;
+/// The most instances of WASMI that may be loaded at once, whatever the chain configures
+pub const RECURSION_LIMIT: u8 = 5;
 
 lazy_static! {
     /// This counter tracks the recursion depth of queries,
@@ -28,9 +32,11 @@ fn decrement() {
     *depth = depth.saturating_sub(1);
 }
 
-/// Returns whether or not this is the last possible level of recursion
-pub fn limit_reached() -> bool {
-    *RECURSION_DEPTH.lock().unwrap() == RECURSION_LIMIT
+/// Returns whether the current instance may not start another nested query.
+/// The top level call is at depth 1, so `max_query_depth` is the amount of nested queries allowed under it.
+pub fn limit_reached(max_query_depth: u32) -> bool {
+    let depth = *RECURSION_DEPTH.lock().unwrap();
+    depth == RECURSION_LIMIT || depth as u32 > max_query_depth
 }
 
 pub struct RecursionGuard {
@@ -18,7 +18,7 @@ use sgx_types::{
 };
 
 #[cfg(feature = "SGX_MODE_HW")]
-use sgx_types::{sgx_epid_group_id_t, sgx_quote_nonce_t};
+use sgx_types::{sgx_epid_group_id_t, sgx_quote_nonce_t, sgx_report_body_t};
 
 use std::io::Read;
 #[cfg(feature = "SGX_MODE_HW")]
@@ -35,7 +35,12 @@ use std::vec::Vec;
 
 use crate::crypto::KeyPair;
 #[cfg(feature = "SGX_MODE_HW")]
-use crate::imports::{ocall_get_ias_socket, ocall_get_quote, ocall_sgx_init_quote};
+use crate::imports::{
+    ocall_get_dcap_quote, ocall_get_ias_socket, ocall_get_qe_target_info, ocall_get_quote,
+    ocall_get_sgx_certificates_socket, ocall_sgx_init_quote,
+};
+#[cfg(feature = "SGX_MODE_HW")]
+use crate::registration::dcap::{DcapCollateral, DcapQuote, EndorsedDcapQuote, PckTcb};
 use crate::registration::report::EndorsedAttestationReport;
 
 use super::hex;
@@ -53,6 +58,15 @@ pub const SIGRL_SUFFIX: &str = "/sgx/dev/attestation/v4/sigrl/";
 #[cfg(not(feature = "production"))]
 pub const REPORT_SUFFIX: &str = "/sgx/dev/attestation/v4/report";
 
+// The PCS serves DCAP collateral from the same host as IAS, and has no separate dev environment
+pub const TCB_INFO_SUFFIX: &str = "/sgx/certification/v3/tcb?fmspc=";
+pub const QE_IDENTITY_SUFFIX: &str = "/sgx/certification/v3/qe/identity";
+pub const PCK_CRL_SUFFIX: &str = "/sgx/certification/v3/pckcrl?ca=";
+
+// The CRL of the root CA is served from its CRL distribution point, in DER despite the extension
+pub const CERTIFICATES_HOSTNAME: &str = "certificates.trustedservices.intel.com";
+pub const ROOT_CA_CRL_PATH: &str = "/IntelSGXRootCA.der";
+
 // extra_data size that will store the public key of the attesting node
 const REPORT_DATA_SIZE: usize = 32;
 
@@ -80,6 +94,19 @@ pub fn create_attestation_certificate(
     Ok((key_der, cert_der))
 }
 
+#[cfg(not(feature = "SGX_MODE_HW"))]
+pub fn create_attestation_certificate_dcap(
+    kp: &KeyPair,
+) -> Result<(Vec<u8>, Vec<u8>), sgx_status_t> {
+    // there is no quote in software mode, so this is the same certificate EPID nodes create
+    create_attestation_certificate(
+        kp,
+        sgx_quote_sign_type_t::SGX_UNLINKABLE_SIGNATURE,
+        &[],
+        &[],
+    )
+}
+
 #[cfg(not(feature = "SGX_MODE_HW"))]
 pub fn create_report_with_data(
     target_info: &sgx_target_info_t,
@@ -152,7 +179,36 @@ pub fn create_attestation_certificate(
 }
 
 #[cfg(feature = "SGX_MODE_HW")]
-pub fn get_mr_enclave() -> Result<[u8; 32], sgx_status_t> {
+pub fn create_attestation_certificate_dcap(
+    kp: &KeyPair,
+) -> Result<(Vec<u8>, Vec<u8>), sgx_status_t> {
+    let ecc_handle = SgxEccHandle::new();
+    let _result = ecc_handle.open();
+
+    // use ephemeral key
+    let (prv_k, pub_k) = ecc_handle.create_key_pair().unwrap();
+
+    let endorsed_quote = match create_dcap_quote(&kp.get_pubkey()) {
+        Ok(q) => q,
+        Err(e) => {
+            error!("Error creating DCAP quote");
+            return Err(e);
+        }
+    };
+
+    let payload: String = serde_json::to_string(&endorsed_quote).map_err(|_| {
+        error!("Error serializing quote. May be malformed, or badly encoded");
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })?;
+    let (key_der, cert_der) = super::cert::gen_ecc_cert(payload, &prv_k, &pub_k, &ecc_handle)?;
+    let _result = ecc_handle.close();
+
+    Ok((key_der, cert_der))
+}
+
+/// The body of a report of this enclave, which holds its own identity
+#[cfg(feature = "SGX_MODE_HW")]
+pub fn get_own_report_body() -> Result<sgx_report_body_t, sgx_status_t> {
     let mut ti: sgx_target_info_t = sgx_target_info_t::default();
     let mut eg: sgx_epid_group_id_t = sgx_epid_group_id_t::default();
     let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
@@ -177,8 +233,12 @@ pub fn get_mr_enclave() -> Result<[u8; 32], sgx_status_t> {
 
     let rep = match rsgx_create_report(&ti, &report_data) {
         Ok(r) => {
-            trace!("This enclave MR_ENCLAVE is: {:?}", r.body.mr_enclave.m);
-            r.body.mr_enclave.m
+            trace!(
+                "This enclave MR_ENCLAVE is: {:?}, ISVSVN is: {}",
+                r.body.mr_enclave.m,
+                r.body.isv_svn
+            );
+            r.body
         }
         Err(_e) => {
             error!("Failed to get local MR_ENCLAVE. Corrupted enclave or other unknown error");
@@ -425,6 +485,110 @@ pub fn create_attestation_report(
     })
 }
 
+#[cfg(feature = "SGX_MODE_HW")]
+pub fn create_dcap_quote(pub_k: &[u8; 32]) -> Result<EndorsedDcapQuote, sgx_status_t> {
+    // Workflow:
+    // (1) ocall to get the target_info structure (ti) of the quoting enclave
+    // (2) call sgx_create_report with ti+data, produce an sgx_report_t
+    // (3) ocall to have the quoting enclave turn the report into an ECDSA quote
+    // (4) get the collateral of the platform of the quote from the Intel PCS
+
+    // (1) get ti
+    let mut ti: sgx_target_info_t = sgx_target_info_t::default();
+    let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+
+    let res = unsafe {
+        ocall_get_qe_target_info(
+            &mut rt as *mut sgx_status_t,
+            &mut ti as *mut sgx_target_info_t,
+        )
+    };
+
+    if res != sgx_status_t::SGX_SUCCESS {
+        return Err(res);
+    }
+
+    if rt != sgx_status_t::SGX_SUCCESS {
+        return Err(rt);
+    }
+
+    // (2) Generate the report
+    let mut report_data: sgx_report_data_t = sgx_report_data_t::default();
+    report_data.d[..32].copy_from_slice(pub_k);
+
+    let rep = match rsgx_create_report(&ti, &report_data) {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Report creation => failed {:?}", e);
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+    };
+
+    // (3) Generate the quote. Unlike EPID quotes, these carry the PCK certificate chain
+    const RET_QUOTE_BUF_LEN: u32 = 8192;
+    let mut return_quote_buf: Vec<u8> = vec![0; RET_QUOTE_BUF_LEN as usize];
+    let mut quote_len: u32 = 0;
+
+    let result = unsafe {
+        ocall_get_dcap_quote(
+            &mut rt as *mut sgx_status_t,
+            &rep as *const sgx_report_t,
+            return_quote_buf.as_mut_ptr(),
+            RET_QUOTE_BUF_LEN,
+            &mut quote_len as *mut u32,
+        )
+    };
+
+    if result != sgx_status_t::SGX_SUCCESS {
+        warn!("ocall_get_dcap_quote returned {}", result);
+        return Err(result);
+    }
+
+    if rt != sgx_status_t::SGX_SUCCESS {
+        warn!("ocall_get_dcap_quote returned {}", rt);
+        return Err(rt);
+    }
+
+    if quote_len > RET_QUOTE_BUF_LEN {
+        error!("Quote is larger than expected: {} bytes", quote_len);
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+    return_quote_buf.truncate(quote_len as usize);
+
+    let quote = DcapQuote::parse_from(&return_quote_buf).map_err(|_| {
+        error!("Failed to parse the quote of the quoting enclave");
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })?;
+
+    if quote.isv_enclave_report.report_data[..] != report_data.d[..] {
+        error!("Quote is tampered!");
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    // (4) get the collateral
+    let pck_tcb = PckTcb::from_cert(&quote.pck_cert_chain[0]).map_err(|_| {
+        error!("Failed to parse the PCK certificate of the quote");
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })?;
+
+    // PCK certificates are issued by either the processor or the platform CA, each with its own CRL
+    let pck_ca = match quote.pck_cert_chain.get(1) {
+        Some(cert) if cert.windows(12).any(|name| name == b"Processor CA") => "processor",
+        Some(_) => "platform",
+        None => {
+            error!("The PCK certificate chain of the quote is missing the PCK CA");
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+    };
+
+    let collateral = get_collateral_from_intel(&pck_tcb.fmspc, pck_ca)?;
+
+    Ok(EndorsedDcapQuote {
+        quote: return_quote_buf,
+        collateral,
+    })
+}
+
 #[cfg(feature = "SGX_MODE_HW")]
 fn parse_response_attn_report(resp: &[u8]) -> (String, Vec<u8>, Vec<u8>) {
     trace!("parse_response_attn_report");
@@ -639,6 +803,168 @@ pub fn get_report_from_intel(
     (attn_report, sig, cert)
 }
 
+#[cfg(feature = "SGX_MODE_HW")]
+fn parse_response_collateral(
+    resp: &[u8],
+    issuer_chain_header: &str,
+) -> Result<(Vec<u8>, String), sgx_status_t> {
+    trace!("parse_response_collateral");
+    let mut headers = [httparse::EMPTY_HEADER; 32];
+    let mut respp = httparse::Response::new(&mut headers);
+    let header_len = match respp.parse(resp) {
+        Ok(httparse::Status::Complete(len)) => len,
+        result => {
+            warn!("Failed to parse PCS response: {:?}", result);
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+        }
+    };
+
+    if respp.code != Some(200) {
+        warn!("PCS returned {:?}", respp.code);
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    let mut issuer_chain = String::new();
+    for h in respp.headers.iter() {
+        if h.name.eq_ignore_ascii_case(issuer_chain_header) {
+            issuer_chain = hex::percent_decode(String::from_utf8_lossy(h.value).to_string());
+        }
+    }
+
+    let body = resp[header_len..].to_vec();
+    trace!("PCS response body: {}", String::from_utf8_lossy(&body));
+
+    Ok((body, issuer_chain))
+}
+
+#[cfg(feature = "SGX_MODE_HW")]
+fn get_collateral(
+    get_socket: unsafe extern "C" fn(*mut sgx_status_t, *mut i32) -> sgx_status_t,
+    hostname: &str,
+    path: &str,
+    issuer_chain_header: &str,
+) -> Result<(Vec<u8>, String), sgx_status_t> {
+    let mut rt: sgx_status_t = sgx_status_t::SGX_ERROR_UNEXPECTED;
+    let mut collateral_sock: i32 = 0;
+
+    let res = unsafe {
+        get_socket(
+            &mut rt as *mut sgx_status_t,
+            &mut collateral_sock as *mut i32,
+        )
+    };
+
+    if res != sgx_status_t::SGX_SUCCESS {
+        return Err(res);
+    }
+
+    if rt != sgx_status_t::SGX_SUCCESS {
+        return Err(rt);
+    }
+
+    trace!("get_collateral fd = {:?}", collateral_sock);
+    let config = make_ias_client_config();
+
+    let req = format!(
+        "GET {} HTTP/1.1\r\nHOST: {}\r\nConnection: Close\r\n\r\n",
+        path, hostname
+    );
+
+    trace!("get_collateral: {}", req);
+
+    let dns_name = webpki::DNSNameRef::try_from_ascii_str(hostname).unwrap();
+    let mut sess = rustls::ClientSession::new(&Arc::new(config), dns_name);
+    let mut sock = TcpStream::new(collateral_sock).unwrap();
+    let mut tls = rustls::Stream::new(&mut sess, &mut sock);
+
+    let _result = tls.write(req.as_bytes());
+    let mut plaintext = Vec::new();
+
+    if let Err(e) = tls.read_to_end(&mut plaintext) {
+        warn!("get_collateral tls.read_to_end: {:?}", e);
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    parse_response_collateral(&plaintext, issuer_chain_header)
+}
+
+#[cfg(feature = "SGX_MODE_HW")]
+fn get_from_pcs(path: &str, issuer_chain_header: &str) -> Result<(Vec<u8>, String), sgx_status_t> {
+    get_collateral(
+        ocall_get_ias_socket,
+        DEV_HOSTNAME,
+        path,
+        issuer_chain_header,
+    )
+}
+
+/// The signed JSON collateral is kept as text, since its signature covers the raw body
+#[cfg(feature = "SGX_MODE_HW")]
+fn collateral_to_string(body: Vec<u8>) -> Result<String, sgx_status_t> {
+    String::from_utf8(body).map_err(|_| {
+        warn!("PCS response is not valid UTF-8");
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })
+}
+
+/// CRLs may be served either as PEM or as DER. They are always kept as DER.
+#[cfg(feature = "SGX_MODE_HW")]
+fn crl_to_der(body: Vec<u8>) -> Result<Vec<u8>, sgx_status_t> {
+    const HEADER: &str = "-----BEGIN X509 CRL-----";
+    const FOOTER: &str = "-----END X509 CRL-----";
+
+    let pem = match str::from_utf8(&body) {
+        Ok(pem) if pem.trim_start().starts_with(HEADER) => pem.trim(),
+        _ => return Ok(body),
+    };
+
+    let end = pem.find(FOOTER).ok_or_else(|| {
+        warn!("Malformed PEM CRL");
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })?;
+    let encoded: String = pem[HEADER.len()..end]
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    base64::decode(&encoded).map_err(|_| {
+        warn!("Malformed PEM CRL");
+        sgx_status_t::SGX_ERROR_UNEXPECTED
+    })
+}
+
+/// `pck_ca` is the CA that issued the PCK certificate of the platform, `processor` or `platform`
+#[cfg(feature = "SGX_MODE_HW")]
+pub fn get_collateral_from_intel(
+    fmspc: &[u8],
+    pck_ca: &str,
+) -> Result<DcapCollateral, sgx_status_t> {
+    let tcb_info_path = format!("{}{}", TCB_INFO_SUFFIX, ::hex::encode(fmspc));
+    let (tcb_info, issuer_chain) = get_from_pcs(&tcb_info_path, "SGX-TCB-Info-Issuer-Chain")?;
+
+    // the QE identity is signed by the same TCB signing certificate as the TCB info
+    let (qe_identity, _) = get_from_pcs(QE_IDENTITY_SUFFIX, "SGX-Enclave-Identity-Issuer-Chain")?;
+
+    let pck_crl_path = format!("{}{}", PCK_CRL_SUFFIX, pck_ca);
+    let (pck_crl, _) = get_from_pcs(&pck_crl_path, "SGX-PCK-CRL-Issuer-Chain")?;
+
+    // the root CA signs its own CRL, so there is no issuer chain to look for
+    let (root_ca_crl, _) = get_collateral(
+        ocall_get_sgx_certificates_socket,
+        CERTIFICATES_HOSTNAME,
+        ROOT_CA_CRL_PATH,
+        "",
+    )?;
+
+    Ok(DcapCollateral {
+        tcb_info: collateral_to_string(tcb_info)?,
+        qe_identity: collateral_to_string(qe_identity)?,
+        issuer_chain,
+        pck_crl: crl_to_der(pck_crl)?,
+        root_ca_crl: crl_to_der(root_ca_crl)?,
+    })
+}
+
 #[cfg(feature = "SGX_MODE_HW")]
 fn as_u32_le(array: [u8; 4]) -> u32 {
     (array[0] as u32)
@@ -19,7 +19,7 @@ use std::untrusted::time::SystemTimeEx;
 use yasna::models::ObjectIdentifier;
 
 #[cfg(feature = "SGX_MODE_HW")]
-use super::attestation::get_mr_enclave;
+use super::attestation::get_own_report_body;
 
 use crate::consts::CERTEXPIRYDAYS;
 
@@ -27,7 +27,10 @@ use crate::consts::CERTEXPIRYDAYS;
 use crate::consts::{SigningMethod, MRSIGNER, SIGNING_METHOD};
 
 #[cfg(feature = "SGX_MODE_HW")]
-use super::report::{AttestationReport, SgxQuoteStatus};
+use super::dcap::{verify_dcap_quote, EndorsedDcapQuote, DCAP_ROOT_CA};
+use super::policy::AttestationPolicy;
+#[cfg(feature = "SGX_MODE_HW")]
+use super::report::{AttestationReport, SgxEnclaveReport, SgxQuoteStatus};
 use crate::registration::report::AdvisoryIDs;
 use enclave_ffi_types::NodeAuthResult;
 
@@ -49,6 +52,19 @@ pub enum Error {
     GenericError,
 }
 
+/// The enclave we expect to find in a certificate
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ExpectedEnclave {
+    /// An enclave like this one, as defined by `SIGNING_METHOD`
+    Peer,
+    /// The enclave the seed is handed over to on upgrade. It may be another build, as long as the
+    /// attestation policy allows it, but it can't have a lower ISVSVN than this one
+    UpgradeTarget,
+    /// The enclave that created a seed exchange key, when the seed is handed over to this one on
+    /// upgrade. It may be another build, as long as the attestation policy allows it
+    UpgradeSource,
+}
+
 pub fn gen_ecc_cert(
     payload: String,
     prv_k: &sgx_ec256_private_t,
@@ -273,8 +289,41 @@ pub fn get_ias_auth_config() -> (Vec<u8>, rustls::RootCertStore) {
     (ias_cert_dec, root_store)
 }
 
-#[cfg(not(feature = "SGX_MODE_HW"))]
+fn now() -> Result<u64, NodeAuthResult> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| NodeAuthResult::InvalidCert)?
+        .as_secs())
+}
+
+/// Verify a remote attestation cert off-chain, where there is no block time. DCAP collateral is
+/// checked at the current time instead.
 pub fn verify_ra_cert(cert_der: &[u8]) -> Result<Vec<u8>, NodeAuthResult> {
+    verify_ra_cert_with_policy(cert_der, None, now()?, ExpectedEnclave::Peer)
+}
+
+/// Verify the cert of the enclave the seed is handed over to on upgrade. This happens off-chain,
+/// where there is no block time we could trust, so the policy is applied as if all of its grace
+/// periods were over. DCAP collateral is checked at the current time.
+pub fn verify_upgrade_cert(
+    cert_der: &[u8],
+    policy: &AttestationPolicy,
+) -> Result<Vec<u8>, NodeAuthResult> {
+    verify_ra_cert_with_policy(
+        cert_der,
+        Some(&policy.without_grace_periods()),
+        now()?,
+        ExpectedEnclave::UpgradeTarget,
+    )
+}
+
+#[cfg(not(feature = "SGX_MODE_HW"))]
+pub fn verify_ra_cert_with_policy(
+    cert_der: &[u8],
+    _policy: Option<&AttestationPolicy>,
+    _block_time: u64,
+    _expected: ExpectedEnclave,
+) -> Result<Vec<u8>, NodeAuthResult> {
     let payload = get_netscape_comment(cert_der).map_err(|_err| NodeAuthResult::InvalidCert)?;
 
     let pk = base64::decode(&payload).map_err(|_err| NodeAuthResult::InvalidCert)?;
@@ -286,58 +335,150 @@ pub fn verify_ra_cert(cert_der: &[u8]) -> Result<Vec<u8>, NodeAuthResult> {
 ///
 /// Logic:
 /// 1. Extract public key
-/// 2. Extract netscape comment - where the attestation report is located
+/// 2. Extract netscape comment - where the attestation report (or DCAP quote) is located
 /// 3. Parse the report itself (verify it is signed by intel)
 /// 4. Extract public key from report body
-/// 5. Verify enclave signature (mr enclave/signer)
+/// 5. Verify enclave signature (mr enclave/signer), as `expected`
 ///
+/// If an attestation policy is given, steps 3 and 5 follow it on top of the compiled-in rules,
+/// and its grace periods are evaluated at `block_time`. DCAP certificates, revocation lists and
+/// collateral are checked at `block_time` as well.
 #[cfg(feature = "SGX_MODE_HW")]
-pub fn verify_ra_cert(cert_der: &[u8]) -> Result<Vec<u8>, NodeAuthResult> {
+pub fn verify_ra_cert_with_policy(
+    cert_der: &[u8],
+    policy: Option<&AttestationPolicy>,
+    block_time: u64,
+    expected: ExpectedEnclave,
+) -> Result<Vec<u8>, NodeAuthResult> {
     // Before we reach here, Webpki already verifed the cert is properly signed
 
+    if let Ok(endorsed_quote) = EndorsedDcapQuote::from_cert(cert_der) {
+        return verify_dcap_quote_cert(&endorsed_quote, DCAP_ROOT_CA, policy, block_time, expected);
+    }
+
     let report = AttestationReport::from_cert(cert_der).map_err(|_| NodeAuthResult::InvalidCert)?;
 
     // 2. Verify quote status (mandatory field)
 
-    verify_quote_status(&report.sgx_quote_status, &report.advisroy_ids)?;
-
-    // verify certificate
-    match SIGNING_METHOD {
-        SigningMethod::MRENCLAVE => {
-            let this_mr_enclave = match get_mr_enclave() {
-                Ok(r) => r,
-                Err(_) => {
-                    error!("This should never happen. If you see this, your node isn't working anymore");
-                    return Err(NodeAuthResult::Panic);
-                }
-            };
+    verify_quote_status_with_policy(
+        &report.sgx_quote_status,
+        &report.advisroy_ids,
+        policy,
+        block_time,
+    )?;
 
-            if report.sgx_quote_body.isv_enclave_report.mr_enclave != this_mr_enclave {
-                error!("Got a different mr_enclave than expected. Invalid certificate");
-                warn!(
-                    "received: {:?} \n expected: {:?}",
-                    report.sgx_quote_body.isv_enclave_report.mr_enclave, this_mr_enclave
-                );
-                return Err(NodeAuthResult::MrEnclaveMismatch);
-            }
-        }
-        SigningMethod::MRSIGNER => {
-            if report.sgx_quote_body.isv_enclave_report.mr_signer != MRSIGNER {
-                error!("Got a different mrsigner than expected. Invalid certificate");
-                warn!(
-                    "received: {:?} \n expected: {:?}",
-                    report.sgx_quote_body.isv_enclave_report.mr_signer, MRSIGNER
-                );
-                return Err(NodeAuthResult::MrSignerMismatch);
-            }
-        }
-        SigningMethod::NONE => {}
-    }
+    verify_enclave_identity(&report.sgx_quote_body.isv_enclave_report, policy, expected)?;
 
     let report_public_key = report.sgx_quote_body.isv_enclave_report.report_data[0..32].to_vec();
     Ok(report_public_key)
 }
 
+/// Same as `verify_ra_cert`, for certificates of nodes that attest with DCAP. The quote is
+/// verified by us against the collateral in the certificate, instead of by IAS.
+#[cfg(feature = "SGX_MODE_HW")]
+fn verify_dcap_quote_cert(
+    endorsed_quote: &EndorsedDcapQuote,
+    root_ca: &[u8],
+    policy: Option<&AttestationPolicy>,
+    block_time: u64,
+    expected: ExpectedEnclave,
+) -> Result<Vec<u8>, NodeAuthResult> {
+    let result = verify_dcap_quote(endorsed_quote, root_ca, block_time).map_err(|_| {
+        error!("Failed to verify DCAP quote");
+        NodeAuthResult::InvalidCert
+    })?;
+
+    verify_quote_status_with_policy(&result.status, &result.advisories, policy, block_time)?;
+
+    verify_enclave_identity(&result.quote.isv_enclave_report, policy, expected)?;
+
+    Ok(result.quote.isv_enclave_report.report_data[0..32].to_vec())
+}
+
+#[cfg(feature = "SGX_MODE_HW")]
+fn verify_quote_status_with_policy(
+    quote_status: &SgxQuoteStatus,
+    advisories: &AdvisoryIDs,
+    policy: Option<&AttestationPolicy>,
+    block_time: u64,
+) -> Result<(), NodeAuthResult> {
+    verify_quote_status(quote_status, advisories)?;
+
+    match policy {
+        Some(policy) => policy.verify_quote_status(quote_status, advisories, block_time),
+        None => Ok(()),
+    }
+}
+
+#[cfg(feature = "SGX_MODE_HW")]
+fn verify_enclave_identity(
+    report: &SgxEnclaveReport,
+    policy: Option<&AttestationPolicy>,
+    expected: ExpectedEnclave,
+) -> Result<(), NodeAuthResult> {
+    // Debug enclaves can be inspected by whoever runs them, so they can't be trusted with the seed
+    if cfg!(feature = "production") && report.is_debug() {
+        error!("Got a certificate of a debug enclave. Invalid certificate");
+        return Err(NodeAuthResult::DebugEnclave);
+    }
+
+    let this_report = match get_own_report_body() {
+        Ok(r) => r,
+        Err(_) => {
+            error!("This should never happen. If you see this, your node isn't working anymore");
+            return Err(NodeAuthResult::Panic);
+        }
+    };
+
+    if expected == ExpectedEnclave::Peer
+        && SIGNING_METHOD == SigningMethod::MRENCLAVE
+        && report.mr_enclave != this_report.mr_enclave.m
+    {
+        error!("Got a different mr_enclave than expected. Invalid certificate");
+        warn!(
+            "received: {:?} \n expected: {:?}",
+            report.mr_enclave, this_report.mr_enclave.m
+        );
+        return Err(NodeAuthResult::MrEnclaveMismatch);
+    }
+
+    // Other builds are only ever accepted if they are signed by us
+    if (expected != ExpectedEnclave::Peer || SIGNING_METHOD != SigningMethod::NONE)
+        && report.mr_signer != MRSIGNER
+    {
+        error!("Got a different mrsigner than expected. Invalid certificate");
+        warn!(
+            "received: {:?} \n expected: {:?}",
+            report.mr_signer, MRSIGNER
+        );
+        return Err(NodeAuthResult::MrSignerMismatch);
+    }
+
+    // An enclave of an older version may have vulnerabilities that were fixed since. The enclave
+    // that hands the seed over to us on upgrade is the exception, since that's what we replace
+    if expected != ExpectedEnclave::UpgradeSource && report.isv_svn < this_report.isv_svn {
+        error!(
+            "Got an enclave with ISVSVN {}, which is lower than ours ({}). Invalid certificate",
+            report.isv_svn, this_report.isv_svn
+        );
+        return Err(NodeAuthResult::IsvSvnTooLow);
+    }
+
+    match policy {
+        // The policy decides which other builds are allowed
+        Some(policy)
+            if expected == ExpectedEnclave::Peer || !policy.allowed_mr_enclaves.is_empty() =>
+        {
+            policy.verify_enclave_identity(report)
+        }
+        None if expected == ExpectedEnclave::Peer => Ok(()),
+        _ => {
+            error!("The attestation policy doesn't allow any other build of the enclave");
+            Err(NodeAuthResult::UpgradeNotAllowed)
+        }
+    }
+}
+
 #[cfg(all(feature = "SGX_MODE_HW", feature = "production"))]
 pub fn verify_quote_status(
     quote_status: &SgxQuoteStatus,
@@ -412,12 +553,15 @@ pub fn verify_quote_status(
 pub mod tests {
     use crate::crypto::KeyPair;
 
-    use super::verify_ra_cert;
+    use super::{verify_ra_cert, verify_ra_cert_with_policy, ExpectedEnclave};
+    use crate::registration::policy::AttestationPolicy;
     use crate::registration::report::AttestationReport;
     use enclave_ffi_types::NodeAuthResult;
     use std::io::Read;
     use std::untrusted::fs::File;
 
+    const PEER: ExpectedEnclave = ExpectedEnclave::Peer;
+
     fn tls_ra_cert_der_out_of_date() -> Vec<u8> {
         let mut cert = vec![];
         let mut f = File::open(
@@ -490,6 +634,82 @@ pub mod tests {
         assert_eq!(result, NodeAuthResult::GroupOutOfDate)
     }
 
+    #[cfg(not(feature = "SGX_MODE_HW"))]
+    pub fn test_certificate_with_policy() {}
+
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn test_certificate_with_policy() {
+        // GROUP_OUT_OF_DATE, reported on 20.08.20
+        let tls_ra_cert = tls_ra_cert_der_out_of_date();
+        let policy = AttestationPolicy::from_slice(
+            br#"{
+                "version": 1,
+                "accepted_statuses": [{ "status": "GROUP_OUT_OF_DATE", "until": 1600000000 }],
+                "accepted_advisories": [
+                    { "id": "INTEL-SA-00161" },
+                    { "id": "INTEL-SA-00219" },
+                    { "id": "INTEL-SA-00289" },
+                    { "id": "INTEL-SA-00320" },
+                    { "id": "INTEL-SA-00329" }
+                ],
+                "allowed_mr_enclaves": [
+                    "a055e97989897313289616ac116fc5473e14e22fd8a0a159fd0ce165353ecacd"
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(
+            verify_ra_cert_with_policy(&tls_ra_cert, Some(&policy), 1_599_999_999, PEER).is_ok()
+        );
+
+        let result = verify_ra_cert_with_policy(&tls_ra_cert, Some(&policy), 1_600_000_000, PEER)
+            .expect_err("Certificate should not pass validation after the grace period");
+        assert_eq!(result, NodeAuthResult::GroupOutOfDate);
+
+        // SW_HARDENING_NEEDED is accepted by default, but not by this policy
+        let result = verify_ra_cert_with_policy(&tls_ra_cert_der_valid(), Some(&policy), 0, PEER)
+            .expect_err("Certificate should not pass validation");
+        assert_eq!(result, NodeAuthResult::BadQuoteStatus);
+
+        // the valid certificate is of an enclave that isn't in the list
+
+        let policy = AttestationPolicy::from_slice(
+            br#"{
+                "version": 1,
+                "accepted_statuses": [{ "status": "SW_HARDENING_NEEDED" }],
+                "accepted_advisories": [{ "id": "INTEL-SA-00334" }],
+                "allowed_mr_enclaves": [
+                    "a055e97989897313289616ac116fc5473e14e22fd8a0a159fd0ce165353ecacd"
+                ]
+            }"#,
+        )
+        .unwrap();
+        let result = verify_ra_cert_with_policy(&tls_ra_cert_der_valid(), Some(&policy), 0, PEER)
+            .expect_err("Certificate should not pass validation");
+        assert_eq!(result, NodeAuthResult::MrEnclaveMismatch);
+
+        // the policy can't accept what the compiled-in rules reject
+
+        let policy = AttestationPolicy::from_slice(
+            br#"{
+                "version": 1,
+                "accepted_statuses": [{ "status": "CONFIGURATION_AND_SW_HARDENING_NEEDED" }],
+                "accepted_advisories": [
+                    { "id": "INTEL-SA-00161" },
+                    { "id": "INTEL-SA-00219" },
+                    { "id": "INTEL-SA-00289" },
+                    { "id": "INTEL-SA-00334" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let result =
+            verify_ra_cert_with_policy(&tls_ra_cert_der_sw_config_needed(), Some(&policy), 0, PEER)
+                .expect_err("Certificate should not pass validation");
+        assert_eq!(result, NodeAuthResult::SwHardeningAndConfigurationNeeded);
+    }
+
     pub fn test_certificate_valid() {
         let tls_ra_cert = tls_ra_cert_der_valid();
         let result = verify_ra_cert(&tls_ra_cert).unwrap();
@@ -0,0 +1,965 @@
+//! Types and verification of ECDSA quotes produced with Intel's Data Center Attestation Primitives
+//! (DCAP), for platforms that cannot use EPID attestation through IAS.
+//!
+//! Unlike IAS, nobody verifies a DCAP quote for us. The quote carries the certificate chain of the
+//! platform's PCK (Provisioning Certification Key), and is verified together with collateral
+//! published by the Intel PCS: the TCB info of the platform and the identity of the Quoting
+//! Enclave. Both are signed by the TCB signing certificate, which chains up to the same root as
+//! the PCK certificate. The collateral also includes the revocation lists of the root CA and of the
+//! CA that issued the PCK certificate.
+//!
+//! https://download.01.org/intel-sgx/dcap-1.0/docs/SGX_ECDSA_QuoteGenReference_DCAP_API_Linux_1.0.pdf
+//! https://api.portal.trustedservices.intel.com/documentation
+#![cfg_attr(not(feature = "SGX_MODE_HW"), allow(unused))]
+
+use attestation_types::ENCLAVE_REPORT_SIZE;
+use log::*;
+use num_bigint::BigUint;
+use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_FIXED};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
+use std::str;
+use std::string::String;
+use std::vec::Vec;
+
+use super::cert::get_netscape_comment;
+use super::report::{as_base64, from_base64, AdvisoryIDs, Error, SgxEnclaveReport, SgxQuoteStatus};
+use crate::crypto::sha_256;
+
+pub const DCAP_ROOT_CA: &[u8] =
+    include_bytes!("../../Intel_SGX_Provisioning_Certification_RootCA.der");
+
+const QUOTE_VERSION: u16 = 3;
+const ECDSA_P256_ATTESTATION_KEY: u16 = 2;
+const QUOTE_HEADER_SIZE: usize = 48;
+const ECDSA_SIGNATURE_SIZE: usize = 64;
+const ECDSA_PUBLIC_KEY_SIZE: usize = 64;
+/// Certification data type of a PEM encoded PCK certificate chain
+const PCK_CERT_CHAIN: u16 = 5;
+
+const TCB_INFO_VERSION: u64 = 2;
+const QE_IDENTITY_VERSION: u64 = 2;
+const TCB_COMPONENTS: usize = 16;
+
+const DER_INTEGER: u8 = 0x02;
+const DER_BIT_STRING: u8 = 0x03;
+const DER_UTC_TIME: u8 = 0x17;
+const DER_GENERALIZED_TIME: u8 = 0x18;
+const DER_SEQUENCE: u8 = 0x30;
+/// Explicit tag of the version field of a certificate
+const DER_CERT_VERSION: u8 = 0xA0;
+
+/// OID of the SGX extension of PCK certificates (1.2.840.113741.1.13.1)
+const SGX_EXTENSION_OID: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF8, 0x4D, 0x01, 0x0D, 0x01];
+
+/// Intel uses P-256 for every certificate in the PCK and TCB signing chains
+static SUPPORTED_SIG_ALGS: &[&webpki::SignatureAlgorithm] = &[&webpki::ECDSA_P256_SHA256];
+
+/// Collateral needed to verify a DCAP quote, as served by the Intel PCS (or a PCCS caching it).
+/// The bodies are kept exactly as they were received, since the signatures cover their raw text.
+#[derive(Default, Serialize, Deserialize)]
+pub struct DcapCollateral {
+    /// Body of the PCS `tcb` response: `{"tcbInfo":{..},"signature":".."}`
+    pub tcb_info: String,
+    /// Body of the PCS `qe/identity` response: `{"enclaveIdentity":{..},"signature":".."}`
+    pub qe_identity: String,
+    /// PEM chain of the TCB signing certificate, which signs both of the above
+    pub issuer_chain: String,
+    /// DER CRL of the CA that issued the PCK certificate, from the PCS `pckcrl` endpoint
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    pub pck_crl: Vec<u8>,
+    /// DER CRL of the root CA, which covers the PCK CA and the TCB signing certificate
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    pub root_ca_crl: Vec<u8>,
+}
+
+/// A DCAP quote together with the collateral needed to verify it. This is what gets embedded in
+/// the attestation certificate of nodes that use DCAP, in place of the `EndorsedAttestationReport`
+/// that IAS returns for EPID quotes.
+#[derive(Default, Serialize, Deserialize)]
+pub struct EndorsedDcapQuote {
+    #[serde(serialize_with = "as_base64", deserialize_with = "from_base64")]
+    pub quote: Vec<u8>,
+    pub collateral: DcapCollateral,
+}
+
+impl EndorsedDcapQuote {
+    /// Extract the endorsed quote from an attestation certificate. Fails if the certificate
+    /// carries an IAS report instead.
+    pub fn from_cert(cert: &[u8]) -> Result<Self, Error> {
+        let payload = get_netscape_comment(cert).map_err(|_err| Error::ReportParseError)?;
+        Ok(serde_json::from_slice(&payload)?)
+    }
+}
+
+/// An ECDSA (version 3) quote
+pub struct DcapQuote {
+    /// Security version number of the Quoting Enclave
+    pub qe_svn: u16,
+    /// Security version number of the Provisioning Certification Enclave
+    pub pce_svn: u16,
+    /// Vendor ID of the Quoting Enclave
+    pub qe_vendor_id: [u8; 16],
+    /// Report generated by the attested enclave
+    pub isv_enclave_report: SgxEnclaveReport,
+    /// The header and enclave report, which are signed by the attestation key
+    signed_data: Vec<u8>,
+    /// Signature of `signed_data` by the attestation key
+    isv_signature: Vec<u8>,
+    /// The attestation key, generated by the Quoting Enclave
+    attestation_key: Vec<u8>,
+    /// Report of the Quoting Enclave, which binds the attestation key to the platform
+    pub qe_report: SgxEnclaveReport,
+    qe_report_raw: Vec<u8>,
+    /// Signature of the QE report by the PCK
+    qe_report_signature: Vec<u8>,
+    qe_auth_data: Vec<u8>,
+    /// DER encoded PCK certificate chain, starting with the PCK certificate itself
+    pub pck_cert_chain: Vec<Vec<u8>>,
+}
+
+impl DcapQuote {
+    /// Parse from bytes to `DcapQuote`.
+    pub fn parse_from<'a>(bytes: &'a [u8]) -> Result<Self, Error> {
+        let mut pos: usize = 0;
+        let mut take = |n: usize| -> Result<&'a [u8], Error> {
+            if bytes.len() >= pos + n {
+                let ret = &bytes[pos..pos + n];
+                pos += n;
+                Ok(ret)
+            } else {
+                warn!("DCAP quote parsing error - quote is too short");
+                Err(Error::ReportParseError)
+            }
+        };
+
+        // off 0, size 2
+        let version = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?);
+        // off 2, size 2
+        let attestation_key_type = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?);
+        if version != QUOTE_VERSION || attestation_key_type != ECDSA_P256_ATTESTATION_KEY {
+            warn!(
+                "Unsupported DCAP quote version {} with attestation key type {}",
+                version, attestation_key_type
+            );
+            return Err(Error::ReportParseError);
+        }
+
+        // off 4, size 4
+        let _reserved = take(4)?;
+        // off 8, size 2
+        let qe_svn = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?);
+        // off 10, size 2
+        let pce_svn = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?);
+        // off 12, size 16
+        let qe_vendor_id = <[u8; 16]>::try_from(take(16)?)?;
+        // off 28, size 20
+        let _user_data = take(20)?;
+
+        // off 48, size 384
+        let isv_enclave_report = SgxEnclaveReport::parse_from(take(ENCLAVE_REPORT_SIZE)?)?;
+        let signed_data = bytes[..QUOTE_HEADER_SIZE + ENCLAVE_REPORT_SIZE].to_vec();
+
+        // off 432, size 4
+        let signature_data_len = u32::from_le_bytes(<[u8; 4]>::try_from(take(4)?)?) as usize;
+        if bytes.len() != QUOTE_HEADER_SIZE + ENCLAVE_REPORT_SIZE + 4 + signature_data_len {
+            warn!("DCAP quote parsing error - signature data size different from expected");
+            return Err(Error::ReportParseError);
+        }
+
+        let isv_signature = take(ECDSA_SIGNATURE_SIZE)?.to_vec();
+        let attestation_key = take(ECDSA_PUBLIC_KEY_SIZE)?.to_vec();
+        let qe_report_raw = take(ENCLAVE_REPORT_SIZE)?.to_vec();
+        let qe_report = SgxEnclaveReport::parse_from(&qe_report_raw)?;
+        let qe_report_signature = take(ECDSA_SIGNATURE_SIZE)?.to_vec();
+
+        let qe_auth_data_len = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?) as usize;
+        let qe_auth_data = take(qe_auth_data_len)?.to_vec();
+
+        let cert_data_type = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?);
+        if cert_data_type != PCK_CERT_CHAIN {
+            warn!(
+                "Unsupported DCAP certification data type {}",
+                cert_data_type
+            );
+            return Err(Error::ReportParseError);
+        }
+        let cert_data_len = u32::from_le_bytes(<[u8; 4]>::try_from(take(4)?)?) as usize;
+        let pck_cert_chain = pem_to_der_chain(take(cert_data_len)?)?;
+
+        if pos != bytes.len() {
+            warn!("DCAP quote parsing error - quote size different from expected");
+            return Err(Error::ReportParseError);
+        }
+
+        Ok(Self {
+            qe_svn,
+            pce_svn,
+            qe_vendor_id,
+            isv_enclave_report,
+            signed_data,
+            isv_signature,
+            attestation_key,
+            qe_report,
+            qe_report_raw,
+            qe_report_signature,
+            qe_auth_data,
+            pck_cert_chain,
+        })
+    }
+}
+
+/// The TCB of the attested platform, as certified in the SGX extension of its PCK certificate
+#[derive(Debug, PartialEq)]
+pub struct PckTcb {
+    pub fmspc: Vec<u8>,
+    pub pce_id: Vec<u8>,
+    pub cpu_svn: [u8; TCB_COMPONENTS],
+    pub pce_svn: u16,
+}
+
+impl PckTcb {
+    pub fn from_cert(cert: &[u8]) -> Result<Self, Error> {
+        let mut cpu_svn = [0u8; TCB_COMPONENTS];
+        for (i, svn) in cpu_svn.iter_mut().enumerate() {
+            *svn = der_uint(get_sgx_extension_value(cert, &[0x02, i as u8 + 1])?)? as u8;
+        }
+
+        Ok(Self {
+            fmspc: get_sgx_extension_value(cert, &[0x04])?.to_vec(),
+            pce_id: get_sgx_extension_value(cert, &[0x03])?.to_vec(),
+            cpu_svn,
+            pce_svn: der_uint(get_sgx_extension_value(cert, &[0x02, 0x11])?)? as u16,
+        })
+    }
+}
+
+/// The outcome of verifying a DCAP quote with its collateral
+pub struct DcapVerificationResult {
+    pub quote: DcapQuote,
+    pub status: SgxQuoteStatus,
+    pub advisories: AdvisoryIDs,
+}
+
+/// # Verifies a DCAP quote against its collateral
+///
+/// Logic:
+/// 1. Verify the collateral is current, and signed by the TCB signing certificate, issued by
+///    `root_ca`
+/// 2. Verify the PCK certificate chain of the quote up to `root_ca`
+/// 3. Verify none of the certificates are revoked
+/// 4. Verify the QE report is signed by the PCK, and binds the attestation key
+/// 5. Verify the enclave report is signed by the attestation key
+/// 6. Verify the QE matches the QE identity
+/// 7. Find the TCB status of the platform
+///
+/// Certificates, revocation lists and collateral are all checked at `now`, in seconds since the
+/// unix epoch. On-chain this is the block time, so that every node reaches the same outcome.
+pub fn verify_dcap_quote(
+    endorsed: &EndorsedDcapQuote,
+    root_ca: &[u8],
+    now: u64,
+) -> Result<DcapVerificationResult, Error> {
+    let quote = DcapQuote::parse_from(&endorsed.quote)?;
+    let collateral = &endorsed.collateral;
+
+    // 1. collateral
+    let (tcb_info_body, tcb_info_signature) = split_signed_body(&collateral.tcb_info, "tcbInfo")?;
+    let (qe_identity_body, qe_identity_signature) =
+        split_signed_body(&collateral.qe_identity, "enclaveIdentity")?;
+    let tcb_info: Value = serde_json::from_str(tcb_info_body)?;
+    let qe_identity: Value = serde_json::from_str(qe_identity_body)?;
+
+    verify_collateral_is_current(&tcb_info, "TCB info", now)?;
+    verify_collateral_is_current(&qe_identity, "QE identity", now)?;
+    let time = webpki::Time::from_seconds_since_unix_epoch(now);
+
+    let issuer_chain = pem_to_der_chain(collateral.issuer_chain.as_bytes())?;
+    let tcb_signer = verify_cert_chain(&issuer_chain, root_ca, time)?;
+    verify_raw_signature(&tcb_signer, tcb_info_body.as_bytes(), &tcb_info_signature)?;
+    verify_raw_signature(
+        &tcb_signer,
+        qe_identity_body.as_bytes(),
+        &qe_identity_signature,
+    )?;
+
+    // 2. PCK certificate chain
+    let pck_cert = verify_cert_chain(&quote.pck_cert_chain, root_ca, time)?;
+
+    // 3. revocation
+    let pck_ca = quote.pck_cert_chain.get(1).ok_or_else(|| {
+        warn!("PCK certificate chain is missing the PCK CA");
+        Error::ReportParseError
+    })?;
+    verify_not_revoked(
+        &collateral.root_ca_crl,
+        root_ca,
+        &[pck_ca, &issuer_chain[0]],
+        now,
+    )?;
+    verify_not_revoked(
+        &collateral.pck_crl,
+        pck_ca,
+        &[&quote.pck_cert_chain[0]],
+        now,
+    )?;
+
+    // 4. QE report
+    verify_raw_signature(&pck_cert, &quote.qe_report_raw, &quote.qe_report_signature)?;
+
+    let mut key_and_auth_data = quote.attestation_key.clone();
+    key_and_auth_data.extend_from_slice(&quote.qe_auth_data);
+    if quote.qe_report.report_data[..32] != sha_256(&key_and_auth_data)[..] {
+        warn!("QE report does not match the attestation key");
+        return Err(Error::ReportValidationError);
+    }
+
+    // 5. enclave report
+    let mut attestation_key = vec![0x04];
+    attestation_key.extend_from_slice(&quote.attestation_key);
+    UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, &attestation_key)
+        .verify(&quote.signed_data, &quote.isv_signature)
+        .map_err(|_| {
+            warn!("Quote signature verification failed");
+            Error::ReportValidationError
+        })?;
+
+    // 6. QE identity
+    let qe_status = verify_qe_identity(&quote.qe_report, &qe_identity)?;
+
+    // 7. TCB status
+    let pck_tcb = PckTcb::from_cert(&quote.pck_cert_chain[0])?;
+    let (platform_status, advisories) = get_tcb_status(&pck_tcb, &tcb_info)?;
+
+    // an outdated QE outdates the whole platform, like in Intel's quote verification library
+    let status = match qe_status {
+        SgxQuoteStatus::OK => platform_status,
+        _ => SgxQuoteStatus::OutOfDate,
+    };
+
+    Ok(DcapVerificationResult {
+        quote,
+        status,
+        advisories,
+    })
+}
+
+/// Verify that `now` falls between the `issueDate` and `nextUpdate` of a collateral body
+fn verify_collateral_is_current(body: &Value, name: &str, now: u64) -> Result<(), Error> {
+    let issue_date = json_time(&body["issueDate"])?;
+    let next_update = json_time(&body["nextUpdate"])?;
+
+    if now < issue_date || now >= next_update {
+        warn!(
+            "{} is valid from {} until {}, but the time is {}",
+            name, issue_date, next_update, now
+        );
+        return Err(Error::ReportValidationError);
+    }
+
+    Ok(())
+}
+
+/// Verify that none of `certs` were revoked by `issuer`, according to `crl`. The CRL itself must
+/// be signed by `issuer`, and current at `now`.
+fn verify_not_revoked(crl: &[u8], issuer: &[u8], certs: &[&[u8]], now: u64) -> Result<(), Error> {
+    let crl = Crl::parse_from(crl)?;
+
+    let issuer = webpki::EndEntityCert::from(issuer).map_err(|_| Error::ReportParseError)?;
+    issuer
+        .verify_signature(&webpki::ECDSA_P256_SHA256, crl.tbs, crl.signature)
+        .map_err(|e| {
+            warn!("CRL signature verification error {:?}", e);
+            Error::ReportValidationError
+        })?;
+
+    if now < crl.this_update || now >= crl.next_update {
+        warn!(
+            "CRL is valid from {} until {}, but the time is {}",
+            crl.this_update, crl.next_update, now
+        );
+        return Err(Error::ReportValidationError);
+    }
+
+    for cert in certs {
+        let serial = cert_serial_number(cert)?;
+        if crl.revoked.contains(&serial) {
+            warn!("Certificate {:02x?} has been revoked", serial);
+            return Err(Error::ReportValidationError);
+        }
+    }
+
+    Ok(())
+}
+
+fn verify_qe_identity(
+    qe_report: &SgxEnclaveReport,
+    identity: &Value,
+) -> Result<SgxQuoteStatus, Error> {
+    if identity["version"].as_u64() != Some(QE_IDENTITY_VERSION) || identity["id"] != "QE" {
+        warn!("Unsupported QE identity");
+        return Err(Error::ReportParseError);
+    }
+
+    let mr_signer = json_hex(&identity["mrsigner"])?;
+    let isv_prod_id = identity["isvprodid"]
+        .as_u64()
+        .ok_or(Error::ReportParseError)?;
+    let misc_select = json_u32(&identity["miscselect"])?;
+    let misc_select_mask = json_u32(&identity["miscselectMask"])?;
+    let attributes = json_hex(&identity["attributes"])?;
+    let attributes_mask = json_hex(&identity["attributesMask"])?;
+
+    let masked_attributes: Vec<u8> = qe_report
+        .attributes
+        .iter()
+        .zip(attributes_mask.iter())
+        .map(|(value, mask)| value & mask)
+        .collect();
+
+    if qe_report.mr_signer[..] != mr_signer[..]
+        || u64::from(qe_report.isv_prod_id) != isv_prod_id
+        || qe_report.misc_select & misc_select_mask != misc_select
+        || masked_attributes != attributes
+    {
+        warn!("Quote was not created by a genuine Quoting Enclave");
+        return Err(Error::ReportValidationError);
+    }
+
+    let levels = identity["tcbLevels"]
+        .as_array()
+        .ok_or(Error::ReportParseError)?;
+    for level in levels {
+        let isv_svn = level["tcb"]["isvsvn"]
+            .as_u64()
+            .ok_or(Error::ReportParseError)?;
+        if u64::from(qe_report.isv_svn) >= isv_svn {
+            let status = level["tcbStatus"].as_str().ok_or(Error::ReportParseError)?;
+            return Ok(SgxQuoteStatus::from_tcb_status(status));
+        }
+    }
+
+    Ok(SgxQuoteStatus::UnknownBadStatus)
+}
+
+/// Find the status of the highest TCB level that the platform meets.
+fn get_tcb_status(
+    pck_tcb: &PckTcb,
+    tcb_info: &Value,
+) -> Result<(SgxQuoteStatus, AdvisoryIDs), Error> {
+    if tcb_info["version"].as_u64() != Some(TCB_INFO_VERSION) {
+        warn!("Unsupported TCB info version");
+        return Err(Error::ReportParseError);
+    }
+
+    if json_hex(&tcb_info["fmspc"])? != pck_tcb.fmspc
+        || json_hex(&tcb_info["pceId"])? != pck_tcb.pce_id
+    {
+        warn!("TCB info does not belong to the attested platform");
+        return Err(Error::ReportValidationError);
+    }
+
+    let levels = tcb_info["tcbLevels"]
+        .as_array()
+        .ok_or(Error::ReportParseError)?;
+    for level in levels {
+        let tcb = &level["tcb"];
+
+        let mut meets_level = true;
+        for (i, svn) in pck_tcb.cpu_svn.iter().enumerate() {
+            let level_svn = tcb[format!("sgxtcbcomp{:02}svn", i + 1)]
+                .as_u64()
+                .ok_or(Error::ReportParseError)?;
+            meets_level &= u64::from(*svn) >= level_svn;
+        }
+        let level_pce_svn = tcb["pcesvn"].as_u64().ok_or(Error::ReportParseError)?;
+        meets_level &= u64::from(pck_tcb.pce_svn) >= level_pce_svn;
+
+        if meets_level {
+            let status = level["tcbStatus"].as_str().ok_or(Error::ReportParseError)?;
+            // only present since version 3 of the TCB info
+            let advisories: Vec<String> = match level.get("advisoryIDs") {
+                Some(ids) => serde_json::from_value(ids.clone())?,
+                None => vec![],
+            };
+            return Ok((
+                SgxQuoteStatus::from_tcb_status(status),
+                AdvisoryIDs(advisories),
+            ));
+        }
+    }
+
+    warn!("TCB level of the platform is not recognized");
+    Ok((SgxQuoteStatus::UnknownBadStatus, AdvisoryIDs(vec![])))
+}
+
+/// Verify a DER certificate chain, starting with the end entity certificate, up to `root_ca`
+fn verify_cert_chain<'a>(
+    chain: &'a [Vec<u8>],
+    root_ca: &[u8],
+    time: webpki::Time,
+) -> Result<webpki::EndEntityCert<'a>, Error> {
+    let (leaf, intermediates) = chain.split_first().ok_or_else(|| {
+        warn!("Empty certificate chain");
+        Error::ReportParseError
+    })?;
+
+    let leaf = webpki::EndEntityCert::from(leaf).map_err(|_| Error::ReportParseError)?;
+    let root = webpki::trust_anchor_util::cert_der_as_trust_anchor(root_ca)
+        .map_err(|_| Error::ReportParseError)?;
+    let intermediates: Vec<&[u8]> = intermediates.iter().map(|cert| cert.as_slice()).collect();
+
+    // PCK certificates aren't TLS certificates, but they don't restrict their key usage, and this
+    // is the only chain verification webpki exposes
+    leaf.verify_is_valid_tls_server_cert(
+        SUPPORTED_SIG_ALGS,
+        &webpki::TLSServerTrustAnchors(&[root]),
+        &intermediates,
+        time,
+    )
+    .map_err(|e| {
+        error!("Certificate verification error {:?}", e);
+        Error::ReportValidationError
+    })?;
+
+    Ok(leaf)
+}
+
+/// Verify a raw (r | s) P-256 signature by a certificate
+fn verify_raw_signature(
+    cert: &webpki::EndEntityCert,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), Error> {
+    if signature.len() != ECDSA_SIGNATURE_SIZE {
+        return Err(Error::ReportParseError);
+    }
+
+    let der_signature = yasna::construct_der(|writer| {
+        writer.write_sequence(|writer| {
+            writer
+                .next()
+                .write_biguint(&BigUint::from_bytes_be(&signature[..32]));
+            writer
+                .next()
+                .write_biguint(&BigUint::from_bytes_be(&signature[32..]));
+        });
+    });
+
+    cert.verify_signature(&webpki::ECDSA_P256_SHA256, message, &der_signature)
+        .map_err(|e| {
+            warn!("Signature verification error {:?}", e);
+            Error::ReportValidationError
+        })
+}
+
+/// Split a signed PCS response of the form `{"<field>":<body>,"signature":"<hex>"}` into the raw
+/// text of the body and the signature over it
+fn split_signed_body<'a>(response: &'a str, field: &str) -> Result<(&'a str, Vec<u8>), Error> {
+    let prefix = format!("{{\"{}\":", field);
+    const SIGNATURE_PREFIX: &str = ",\"signature\":\"";
+    const SUFFIX: &str = "\"}";
+
+    let response = response.trim();
+    let signature_start = response.rfind(SIGNATURE_PREFIX).ok_or_else(|| {
+        warn!("Collateral is missing its signature");
+        Error::ReportParseError
+    })?;
+    if !response.starts_with(&prefix)
+        || !response.ends_with(SUFFIX)
+        || signature_start < prefix.len()
+    {
+        warn!("Malformed collateral");
+        return Err(Error::ReportParseError);
+    }
+
+    let body = &response[prefix.len()..signature_start];
+    let signature = hex::decode(
+        &response[signature_start + SIGNATURE_PREFIX.len()..response.len() - SUFFIX.len()],
+    )
+    .map_err(|_| Error::ReportParseError)?;
+
+    Ok((body, signature))
+}
+
+/// Decode a chain of PEM certificates into DER
+fn pem_to_der_chain(pem: &[u8]) -> Result<Vec<Vec<u8>>, Error> {
+    const HEADER: &str = "-----BEGIN CERTIFICATE-----";
+    const FOOTER: &str = "-----END CERTIFICATE-----";
+
+    // the DCAP library includes the terminating NUL of the chain
+    let pem = str::from_utf8(pem)
+        .map_err(|_| Error::ReportParseError)?
+        .trim_end_matches('\0');
+
+    let mut chain = vec![];
+    for block in pem.split(HEADER).skip(1) {
+        let end = block.find(FOOTER).ok_or(Error::ReportParseError)?;
+        let encoded: String = block[..end]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        chain.push(base64::decode(&encoded).map_err(|_| Error::ReportParseError)?);
+    }
+
+    if chain.is_empty() {
+        warn!("No certificates found in PEM chain");
+        return Err(Error::ReportParseError);
+    }
+
+    Ok(chain)
+}
+
+/// Find the value of an entry in the SGX extension of a PCK certificate, by the suffix of its OID
+fn get_sgx_extension_value<'a>(cert: &'a [u8], oid_suffix: &[u8]) -> Result<&'a [u8], Error> {
+    let mut oid = vec![0x06, (SGX_EXTENSION_OID.len() + oid_suffix.len()) as u8];
+    oid.extend_from_slice(SGX_EXTENSION_OID);
+    oid.extend_from_slice(oid_suffix);
+
+    let offset = match cert
+        .windows(oid.len())
+        .position(|window| window == oid.as_slice())
+    {
+        Some(position) => position + oid.len(),
+        None => {
+            warn!("PCK certificate is missing SGX extension {:?}", oid_suffix);
+            return Err(Error::ReportParseError);
+        }
+    };
+
+    // the value is the TLV following the OID. None of the values we read are longer than 127
+    // bytes, so they all use the short length form
+    if offset + 2 > cert.len() {
+        return Err(Error::ReportParseError);
+    }
+    let len = cert[offset + 1] as usize;
+    if len >= 0x80 || offset + 2 + len > cert.len() {
+        return Err(Error::ReportParseError);
+    }
+
+    Ok(&cert[offset + 2..offset + 2 + len])
+}
+
+/// A DER element, split off the front of its input by `der_next`
+struct DerElement<'a> {
+    tag: u8,
+    contents: &'a [u8],
+    /// The whole element, including its tag and length
+    raw: &'a [u8],
+}
+
+/// Split the next DER element off `input`
+fn der_next<'a>(input: &mut &'a [u8]) -> Result<DerElement<'a>, Error> {
+    let bytes = *input;
+    if bytes.len() < 2 {
+        return Err(Error::ReportParseError);
+    }
+
+    let (len, header_len) = match bytes[1] {
+        len if len < 0x80 => (len as usize, 2),
+        long_form @ 0x81..=0x84 => {
+            let len_len = (long_form & 0x7F) as usize;
+            if bytes.len() < 2 + len_len {
+                return Err(Error::ReportParseError);
+            }
+            let len = bytes[2..2 + len_len]
+                .iter()
+                .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+            (len, 2 + len_len)
+        }
+        _ => return Err(Error::ReportParseError),
+    };
+
+    let end = header_len
+        .checked_add(len)
+        .filter(|end| *end <= bytes.len())
+        .ok_or(Error::ReportParseError)?;
+    *input = &bytes[end..];
+
+    Ok(DerElement {
+        tag: bytes[0],
+        contents: &bytes[header_len..end],
+        raw: &bytes[..end],
+    })
+}
+
+/// Split the next DER element off `input`, which must have the `expected` tag
+fn der_expect<'a>(input: &mut &'a [u8], expected: u8) -> Result<DerElement<'a>, Error> {
+    let element = der_next(input)?;
+    if element.tag != expected {
+        return Err(Error::ReportParseError);
+    }
+
+    Ok(element)
+}
+
+/// Decode a DER UTCTime or GeneralizedTime into seconds since the unix epoch
+fn der_time(element: &DerElement) -> Result<u64, Error> {
+    let format = match element.tag {
+        DER_UTC_TIME => "%y%m%d%H%M%SZ",
+        DER_GENERALIZED_TIME => "%Y%m%d%H%M%SZ",
+        _ => return Err(Error::ReportParseError),
+    };
+    let text = str::from_utf8(element.contents).map_err(|_| Error::ReportParseError)?;
+    let time = chrono::NaiveDateTime::parse_from_str(text, format).map_err(|_| {
+        warn!("Failed to parse DER time {}", text);
+        Error::ReportParseError
+    })?;
+
+    Ok(time.timestamp().max(0) as u64)
+}
+
+/// The parts of an X.509 certificate revocation list that we check
+struct Crl<'a> {
+    /// The signed part of the CRL
+    tbs: &'a [u8],
+    /// DER encoded signature over `tbs`
+    signature: &'a [u8],
+    this_update: u64,
+    next_update: u64,
+    /// Contents of the serial numbers of the revoked certificates
+    revoked: Vec<&'a [u8]>,
+}
+
+impl<'a> Crl<'a> {
+    /// Parse from DER. Intel CRLs always carry a next update, so it is required here.
+    fn parse_from(der: &'a [u8]) -> Result<Self, Error> {
+        let mut input = der;
+        let mut fields = der_expect(&mut input, DER_SEQUENCE)?.contents;
+        let tbs = der_expect(&mut fields, DER_SEQUENCE)?;
+        let _signature_algorithm = der_expect(&mut fields, DER_SEQUENCE)?;
+        // the signature is itself DER, so the bit string has no unused bits
+        let signature = match der_expect(&mut fields, DER_BIT_STRING)?
+            .contents
+            .split_first()
+        {
+            Some((0, signature)) => signature,
+            _ => return Err(Error::ReportParseError),
+        };
+
+        let mut tbs_fields = tbs.contents;
+        if tbs_fields.first() == Some(&DER_INTEGER) {
+            let _version = der_next(&mut tbs_fields)?;
+        }
+        let _signature_algorithm = der_expect(&mut tbs_fields, DER_SEQUENCE)?;
+        let _issuer = der_expect(&mut tbs_fields, DER_SEQUENCE)?;
+        let this_update = der_time(&der_next(&mut tbs_fields)?)?;
+        let next_update = der_time(&der_next(&mut tbs_fields)?)?;
+
+        // the list of revoked certificates is left out when it is empty
+        let mut revoked = vec![];
+        if tbs_fields.first() == Some(&DER_SEQUENCE) {
+            let mut entries = der_next(&mut tbs_fields)?.contents;
+            while !entries.is_empty() {
+                let mut entry = der_expect(&mut entries, DER_SEQUENCE)?.contents;
+                revoked.push(der_expect(&mut entry, DER_INTEGER)?.contents);
+            }
+        }
+
+        Ok(Self {
+            tbs: tbs.raw,
+            signature,
+            this_update,
+            next_update,
+            revoked,
+        })
+    }
+}
+
+/// Find the contents of the serial number of a DER certificate
+fn cert_serial_number(cert: &[u8]) -> Result<&[u8], Error> {
+    let mut input = cert;
+    let mut fields = der_expect(&mut input, DER_SEQUENCE)?.contents;
+    let mut tbs_fields = der_expect(&mut fields, DER_SEQUENCE)?.contents;
+
+    let mut serial_number = der_next(&mut tbs_fields)?;
+    if serial_number.tag == DER_CERT_VERSION {
+        serial_number = der_next(&mut tbs_fields)?;
+    }
+    if serial_number.tag != DER_INTEGER {
+        return Err(Error::ReportParseError);
+    }
+
+    Ok(serial_number.contents)
+}
+
+/// Decode the contents of a small non negative DER INTEGER
+fn der_uint(bytes: &[u8]) -> Result<u32, Error> {
+    if bytes.is_empty() || bytes.len() > 5 {
+        return Err(Error::ReportParseError);
+    }
+
+    Ok(bytes
+        .iter()
+        .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte)) as u32)
+}
+
+fn json_hex(value: &Value) -> Result<Vec<u8>, Error> {
+    let encoded = value.as_str().ok_or(Error::ReportParseError)?;
+    hex::decode(encoded).map_err(|_| Error::ReportParseError)
+}
+
+fn json_u32(value: &Value) -> Result<u32, Error> {
+    let encoded = value.as_str().ok_or(Error::ReportParseError)?;
+    u32::from_str_radix(encoded, 16).map_err(|_| Error::ReportParseError)
+}
+
+/// Decode an RFC 3339 date into seconds since the unix epoch
+fn json_time(value: &Value) -> Result<u64, Error> {
+    let encoded = value.as_str().ok_or(Error::ReportParseError)?;
+    let time = chrono::DateTime::parse_from_rfc3339(encoded).map_err(|_| {
+        warn!("Failed to parse collateral date {}", encoded);
+        Error::ReportParseError
+    })?;
+
+    Ok(time.timestamp().max(0) as u64)
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use sgx_tcrypto::SgxEccHandle;
+    use std::io::Read;
+    use std::untrusted::fs::File;
+
+    use super::super::cert::gen_ecc_cert;
+    use super::*;
+
+    fn fixture(name: &str) -> Vec<u8> {
+        let mut data = vec![];
+        let mut f = File::open(format!(
+            "../wasmi-runtime/src/registration/fixtures/dcap/{}",
+            name
+        ))
+        .unwrap();
+        f.read_to_end(&mut data).unwrap();
+
+        data
+    }
+
+    fn fixture_string(name: &str) -> String {
+        String::from_utf8(fixture(name)).unwrap()
+    }
+
+    /// 2021-06-15T00:00:00Z, while all of the fixture collateral is current
+    const NOW: u64 = 1_623_715_200;
+
+    fn endorsed_quote(tcb_info: &str) -> EndorsedDcapQuote {
+        EndorsedDcapQuote {
+            quote: fixture("quote.bin"),
+            collateral: DcapCollateral {
+                tcb_info: fixture_string(tcb_info),
+                qe_identity: fixture_string("qe_identity.json"),
+                issuer_chain: fixture_string("issuer_chain.pem"),
+                pck_crl: fixture("pck_crl.der"),
+                root_ca_crl: fixture("root_ca_crl.der"),
+            },
+        }
+    }
+
+    pub fn test_dcap_quote_parse_from() {
+        let quote = DcapQuote::parse_from(&fixture("quote.bin")).unwrap();
+
+        assert_eq!(quote.qe_svn, 5);
+        assert_eq!(quote.pce_svn, 10);
+        assert_eq!(quote.pck_cert_chain.len(), 3);
+        assert_eq!(
+            quote.isv_enclave_report.mr_enclave.to_vec(),
+            (0..32).collect::<Vec<u8>>()
+        );
+        assert_eq!(quote.isv_enclave_report.report_data[..32], [0xAB; 32]);
+        assert_eq!(quote.qe_report.isv_prod_id, 1);
+
+        let pck_tcb = PckTcb::from_cert(&quote.pck_cert_chain[0]).unwrap();
+        assert_eq!(
+            pck_tcb,
+            PckTcb {
+                fmspc: vec![0x00, 0x90, 0x6e, 0xa1, 0x00, 0x00],
+                pce_id: vec![0x00, 0x00],
+                cpu_svn: [15, 15, 2, 4, 1, 128, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+                pce_svn: 10,
+            }
+        );
+    }
+
+    pub fn test_verify_dcap_quote() {
+        let endorsed = endorsed_quote("tcb_info.json");
+        let result = verify_dcap_quote(&endorsed, &fixture("root_ca.der"), NOW).unwrap();
+
+        assert_eq!(result.status, SgxQuoteStatus::OK);
+        assert!(result.advisories.0.is_empty());
+        assert_eq!(
+            result.quote.isv_enclave_report.report_data[..32],
+            [0xAB; 32]
+        );
+    }
+
+    pub fn test_verify_dcap_quote_out_of_date() {
+        let endorsed = endorsed_quote("tcb_info_out_of_date.json");
+        let result = verify_dcap_quote(&endorsed, &fixture("root_ca.der"), NOW).unwrap();
+
+        assert_eq!(result.status, SgxQuoteStatus::OutOfDate);
+    }
+
+    pub fn test_verify_dcap_quote_from_cert() {
+        let endorsed = endorsed_quote("tcb_info.json");
+        let payload = serde_json::to_string(&endorsed).unwrap();
+
+        let ecc_handle = SgxEccHandle::new();
+        ecc_handle.open().unwrap();
+        let (prv_k, pub_k) = ecc_handle.create_key_pair().unwrap();
+        let (_, cert) = gen_ecc_cert(payload, &prv_k, &pub_k, &ecc_handle).unwrap();
+        ecc_handle.close().unwrap();
+
+        let from_cert = EndorsedDcapQuote::from_cert(&cert).unwrap();
+        assert_eq!(from_cert.quote, endorsed.quote);
+        assert!(verify_dcap_quote(&from_cert, &fixture("root_ca.der"), NOW).is_ok());
+    }
+
+    pub fn test_verify_dcap_quote_wrong_root() {
+        let endorsed = endorsed_quote("tcb_info.json");
+        assert!(verify_dcap_quote(&endorsed, DCAP_ROOT_CA, NOW).is_err());
+    }
+
+    pub fn test_verify_dcap_quote_tampered_report() {
+        let mut endorsed = endorsed_quote("tcb_info.json");
+        // flip a byte of the report data of the enclave
+        endorsed.quote[QUOTE_HEADER_SIZE + 320] ^= 1;
+
+        assert!(verify_dcap_quote(&endorsed, &fixture("root_ca.der"), NOW).is_err());
+    }
+
+    pub fn test_verify_dcap_quote_tampered_collateral() {
+        let mut endorsed = endorsed_quote("tcb_info.json");
+        endorsed.collateral.tcb_info = endorsed
+            .collateral
+            .tcb_info
+            .replace("\"OutOfDate\"", "\"UpToDate\"");
+
+        assert!(verify_dcap_quote(&endorsed, &fixture("root_ca.der"), NOW).is_err());
+    }
+
+    pub fn test_verify_dcap_quote_expired_collateral() {
+        let endorsed = endorsed_quote("tcb_info.json");
+        // 2021-07-01T00:00:00Z, the next update of the collateral
+        let next_update = 1_625_097_600;
+
+        assert!(verify_dcap_quote(&endorsed, &fixture("root_ca.der"), next_update - 1).is_ok());
+        assert!(verify_dcap_quote(&endorsed, &fixture("root_ca.der"), next_update).is_err());
+    }
+
+    pub fn test_verify_dcap_quote_revoked_pck() {
+        let mut endorsed = endorsed_quote("tcb_info.json");
+        endorsed.collateral.pck_crl = fixture("pck_crl_revoked.der");
+
+        assert!(verify_dcap_quote(&endorsed, &fixture("root_ca.der"), NOW).is_err());
+    }
+
+    pub fn test_verify_dcap_quote_crl_wrong_issuer() {
+        let mut endorsed = endorsed_quote("tcb_info.json");
+        // signed by the root CA instead of the PCK CA
+        endorsed.collateral.pck_crl = fixture("root_ca_crl.der");
+
+        assert!(verify_dcap_quote(&endorsed, &fixture("root_ca.der"), NOW).is_err());
+    }
+}
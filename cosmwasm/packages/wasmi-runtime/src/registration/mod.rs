@@ -1,13 +1,19 @@
 pub use attestation::create_attestation_certificate;
-pub use offchain::{ecall_get_attestation_report, ecall_init_bootstrap, ecall_init_node};
+pub use offchain::{
+    ecall_get_attestation_report, ecall_get_attestation_report_dcap, ecall_init_bootstrap,
+    ecall_init_node,
+};
 pub use onchain::ecall_authenticate_new_node;
 
 mod attestation;
 mod cert;
+mod dcap;
 mod hex;
 mod offchain;
 mod onchain;
+mod policy;
 mod report;
+mod upgrade;
 
 mod seed_exchange;
 
@@ -21,11 +27,28 @@ pub mod tests {
         let mut failures = 0;
 
         count_failures!(failures, {
-            report::tests::test_sgx_quote_parse_from();
             report::tests::test_attestation_report_from_cert();
             report::tests::test_attestation_report_from_cert_api_version_not_compatible();
             cert::tests::test_certificate_valid();
             cert::tests::test_certificate_invalid_configuration_needed();
+            cert::tests::test_certificate_with_policy();
+            policy::tests::test_attestation_policy_from_slice();
+            policy::tests::test_attestation_policy_quote_status();
+            policy::tests::test_attestation_policy_grace_periods();
+            policy::tests::test_attestation_policy_enclave_identity();
+            upgrade::tests::test_upgrade_requires_allowed_mr_enclaves();
+            upgrade::tests::test_export_seed_for_upgrade_without_policy();
+            seed_exchange::tests::test_decrypt_seed_with_legacy_authenticated_data();
+            dcap::tests::test_dcap_quote_parse_from();
+            dcap::tests::test_verify_dcap_quote();
+            dcap::tests::test_verify_dcap_quote_out_of_date();
+            dcap::tests::test_verify_dcap_quote_from_cert();
+            dcap::tests::test_verify_dcap_quote_wrong_root();
+            dcap::tests::test_verify_dcap_quote_tampered_report();
+            dcap::tests::test_verify_dcap_quote_tampered_collateral();
+            dcap::tests::test_verify_dcap_quote_expired_collateral();
+            dcap::tests::test_verify_dcap_quote_revoked_pck();
+            dcap::tests::test_verify_dcap_quote_crl_wrong_issuer();
         });
 
         if failures != 0 {
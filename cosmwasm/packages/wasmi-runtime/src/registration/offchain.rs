@@ -12,20 +12,22 @@ use std::slice;
 use enclave_ffi_types::NodeAuthResult;
 
 use crate::consts::{
+    io_epoch_certificate_save_path, seed_exch_epoch_certificate_save_path,
     ATTESTATION_CERTIFICATE_SAVE_PATH, ENCRYPTED_SEED_SIZE, IO_CERTIFICATE_SAVE_PATH,
     SEED_EXCH_CERTIFICATE_SAVE_PATH,
 };
-use crate::crypto::{Keychain, KEY_MANAGER, PUBLIC_KEY_SIZE};
+use crate::crypto::{sha_256, Keychain, Seed, KEY_MANAGER, PUBLIC_KEY_SIZE};
 #[cfg(feature = "SGX_MODE_HW")]
 use crate::registration::report::AttestationReport;
 use crate::storage::write_to_untrusted;
 use crate::utils::{attest_from_key, validate_const_ptr, validate_mut_ptr, validate_mut_slice};
 
-use super::attestation::create_attestation_certificate;
-use super::cert::verify_ra_cert;
+use super::attestation::{create_attestation_certificate, create_attestation_certificate_dcap};
 #[cfg(feature = "SGX_MODE_HW")]
 use super::cert::{ocall_get_update_info, verify_quote_status};
-use super::seed_exchange::decrypt_seed;
+use super::cert::{verify_ra_cert, verify_ra_cert_with_policy, ExpectedEnclave};
+use super::policy::AttestationPolicy;
+use super::seed_exchange::{decrypt_seed, SeedBinding};
 
 ///
 /// `ecall_init_bootstrap`
@@ -34,6 +36,9 @@ use super::seed_exchange::decrypt_seed;
 /// key (seed + pk_io/sk_io). This happens once at the initialization of a chain. Returns the master
 /// public key (pk_io), which is saved on-chain, and used to propagate the seed to registering nodes
 ///
+/// The seed is bound to `chain_id` and to the attestation policy of the chain (empty if it has
+/// none), which are propagated to registering nodes along with the seed
+///
 /// # Safety
 ///  Something should go here
 ///
@@ -44,6 +49,10 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
     spid_len: u32,
     api_key: *const u8,
     api_key_len: u32,
+    chain_id: *const u8,
+    chain_id_len: u32,
+    policy: *const u8,
+    policy_len: u32,
 ) -> sgx_status_t {
     if let Err(_e) = validate_mut_ptr(public_key.as_mut_ptr(), public_key.len()) {
         return sgx_status_t::SGX_ERROR_UNEXPECTED;
@@ -59,13 +68,27 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
     }
     let api_key_slice = slice::from_raw_parts(api_key, api_key_len as usize);
 
+    let chain_id = match read_chain_id(chain_id, chain_id_len) {
+        Ok(chain_id) => chain_id,
+        Err(status) => return status,
+    };
+
+    let policy = match read_attestation_policy(policy, policy_len) {
+        Ok(policy) => policy,
+        Err(status) => return status,
+    };
+
     let mut key_manager = Keychain::new();
 
     if let Err(_e) = key_manager.create_consensus_seed() {
         return sgx_status_t::SGX_ERROR_UNEXPECTED;
     }
 
-    if let Err(_e) = key_manager.generate_consensus_master_keys() {
+    if let Err(_e) = key_manager.set_chain_id(chain_id) {
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    if let Err(_e) = key_manager.set_attestation_policy(policy) {
         return sgx_status_t::SGX_ERROR_UNEXPECTED;
     }
 
@@ -107,6 +130,15 @@ pub unsafe extern "C" fn ecall_init_bootstrap(
 ///
 /// The seed was encrypted using Diffie-Hellman in the function [ecall_get_encrypted_seed]
 ///
+/// `chain_id` and `policy` come from the host, but the seed only decrypts if they match the chain
+/// id and the attestation policy the seed was created with, so they are only sealed after the seed
+/// was decrypted
+///
+/// Nodes of a chain whose seed was created before it was bound to the chain id and the policy got a
+/// seed without them, and their seed config may not have a chain id at all. Their seed decrypts
+/// without the chain id and the policy, which then stay unbound, since the seed exchange didn't
+/// authenticate them
+///
 /// This function happens off-chain, so if we panic for some reason it _can_ be acceptable,
 ///  though probably not recommended
 ///
@@ -119,15 +151,253 @@ pub unsafe extern "C" fn ecall_init_node(
     master_cert_len: u32,
     encrypted_seed: *const u8,
     encrypted_seed_len: u32,
+    chain_id: *const u8,
+    chain_id_len: u32,
+    policy: *const u8,
+    policy_len: u32,
 ) -> sgx_status_t {
-    if let Err(_e) = validate_const_ptr(master_cert, master_cert_len as usize) {
+    let chain_id = if chain_id_len == 0 {
+        None
+    } else {
+        match read_chain_id(chain_id, chain_id_len) {
+            Ok(chain_id) => Some(chain_id),
+            Err(status) => return status,
+        }
+    };
+
+    let policy = match read_attestation_policy(policy, policy_len) {
+        Ok(policy) => policy,
+        Err(status) => return status,
+    };
+
+    let mut key_manager = Keychain::new();
+    let receive = |binding: Option<&SeedBinding>| {
+        receive_seed(
+            &key_manager,
+            master_cert,
+            master_cert_len,
+            encrypted_seed,
+            encrypted_seed_len,
+            binding,
+            None,
+            0,
+        )
+    };
+
+    let mut binding = chain_id.map(|chain_id| (sha_256(chain_id.as_bytes()), sha_256(policy)));
+    let seed = match binding.as_ref().map(|binding| receive(Some(binding))) {
+        Some(Ok(seed)) => seed,
+        _ => {
+            binding = None;
+            match receive(None) {
+                Ok(seed) => seed,
+                Err(status) => return status,
+            }
+        }
+    };
+
+    if let Err(_e) = key_manager.set_consensus_seed(seed) {
         return sgx_status_t::SGX_ERROR_UNEXPECTED;
     }
 
-    if let Err(_e) = validate_const_ptr(encrypted_seed, encrypted_seed_len as usize) {
+    match (chain_id, binding) {
+        (Some(chain_id), Some(_)) => {
+            if let Err(_e) = key_manager.set_chain_id(chain_id) {
+                return sgx_status_t::SGX_ERROR_UNEXPECTED;
+            }
+
+            if let Err(_e) = key_manager.set_attestation_policy(policy) {
+                return sgx_status_t::SGX_ERROR_UNEXPECTED;
+            }
+        }
+        _ => warn!("The seed isn't bound to a chain id and an attestation policy"),
+    }
+
+    sgx_status_t::SGX_SUCCESS
+}
+
+///
+/// `ecall_rotate_consensus_seed`
+///
+/// Creates the seed of a new epoch of the consensus seed, in case the current one is suspected
+/// to be compromised. Returns the seed exchange public key of the new epoch, and saves attestation
+/// certificates of it and of the IO exchange key of the new epoch. The first lets other nodes verify
+/// the seed they receive came from an enclave, the second is what clients encrypt to once the epoch
+/// is active.
+///
+/// The new epoch is then shared with the other nodes using [ecall_authenticate_new_node_for_epoch],
+/// and loaded with [ecall_add_consensus_seed_epoch]. Contracts only start to use it once the chain
+/// passes it in the env of their calls.
+///
+/// # Safety
+///  Something should go here
+///
+#[no_mangle]
+pub unsafe extern "C" fn ecall_rotate_consensus_seed(
+    public_key: &mut [u8; PUBLIC_KEY_SIZE],
+    spid: *const u8,
+    spid_len: u32,
+    api_key: *const u8,
+    api_key_len: u32,
+) -> sgx_status_t {
+    if let Err(_e) = validate_mut_ptr(public_key.as_mut_ptr(), public_key.len()) {
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    if let Err(_e) = validate_const_ptr(spid, spid_len as usize) {
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+    let spid_slice = slice::from_raw_parts(spid, spid_len as usize);
+
+    if let Err(_e) = validate_const_ptr(api_key, api_key_len as usize) {
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+    let api_key_slice = slice::from_raw_parts(api_key, api_key_len as usize);
+
+    let epoch = match KEY_MANAGER.create_consensus_seed_epoch() {
+        Ok(epoch) => epoch,
+        Err(_e) => return sgx_status_t::SGX_ERROR_UNEXPECTED,
+    };
+
+    let io_kp = KEY_MANAGER
+        .get_consensus_io_exchange_keypair_of_epoch(epoch)
+        .unwrap();
+    if let Err(status) = attest_from_key(
+        &io_kp,
+        &io_epoch_certificate_save_path(epoch),
+        spid_slice,
+        api_key_slice,
+    ) {
+        return status;
+    }
+
+    let kp = KEY_MANAGER.seed_exchange_key_of_epoch(epoch).unwrap();
+    if let Err(status) = attest_from_key(
+        &kp,
+        &seed_exch_epoch_certificate_save_path(epoch),
+        spid_slice,
+        api_key_slice,
+    ) {
+        return status;
+    }
+
+    public_key.copy_from_slice(&kp.get_pubkey());
+    trace!(
+        "ecall_rotate_consensus_seed consensus_seed_exchange_keypair public key of epoch {}: {:?}",
+        epoch,
+        &public_key.to_vec()
+    );
+
+    sgx_status_t::SGX_SUCCESS
+}
+
+///
+///  `ecall_add_consensus_seed_epoch`
+///
+/// Loads the seed of a new epoch of the consensus seed. It works like [ecall_init_node], except
+/// `master_cert` is the certificate of the seed exchange key of the new epoch. Epochs must be
+/// added in order.
+///
+/// # Safety
+///  Something should go here
+///
+#[no_mangle]
+pub unsafe extern "C" fn ecall_add_consensus_seed_epoch(
+    master_cert: *const u8,
+    master_cert_len: u32,
+    epoch: u32,
+    encrypted_seed: *const u8,
+    encrypted_seed_len: u32,
+) -> sgx_status_t {
+    let binding = KEY_MANAGER.get_seed_binding();
+    let seed = match receive_seed(
+        &KEY_MANAGER,
+        master_cert,
+        master_cert_len,
+        encrypted_seed,
+        encrypted_seed_len,
+        binding.as_ref(),
+        None,
+        0,
+    ) {
+        Ok(result) => result,
+        Err(status) => return status,
+    };
+
+    if let Err(e) = KEY_MANAGER.add_consensus_seed_epoch(epoch, seed) {
+        error!("Failed to add the seed of epoch {}: {}", epoch, e);
         return sgx_status_t::SGX_ERROR_UNEXPECTED;
     }
 
+    sgx_status_t::SGX_SUCCESS
+}
+
+/// Reads the chain id the host passed in. It must be valid UTF-8, since it is compared to the
+/// chain id of query permits
+pub(super) unsafe fn read_chain_id<'a>(
+    chain_id: *const u8,
+    chain_id_len: u32,
+) -> Result<&'a str, sgx_status_t> {
+    if chain_id_len == 0 {
+        error!("Got an empty chain id");
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+    if let Err(_e) = validate_const_ptr(chain_id, chain_id_len as usize) {
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    std::str::from_utf8(slice::from_raw_parts(chain_id, chain_id_len as usize)).map_err(|_e| {
+        error!("Got a chain id that isn't valid UTF-8");
+        sgx_status_t::SGX_ERROR_INVALID_PARAMETER
+    })
+}
+
+/// Reads the JSON encoded attestation policy the host passed in, which is empty if the chain has
+/// none. A policy that the enclave can't parse is refused, so a chain can't be created with it.
+pub(super) unsafe fn read_attestation_policy<'a>(
+    policy: *const u8,
+    policy_len: u32,
+) -> Result<&'a [u8], sgx_status_t> {
+    if policy_len == 0 {
+        return Ok(&[]);
+    }
+    if let Err(_e) = validate_const_ptr(policy, policy_len as usize) {
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    let policy = slice::from_raw_parts(policy, policy_len as usize);
+    if let Err(e) = AttestationPolicy::from_slice(policy) {
+        error!("Got an invalid attestation policy: {}", e);
+        return Err(sgx_status_t::SGX_ERROR_INVALID_PARAMETER);
+    }
+
+    Ok(policy)
+}
+
+/// Verifies the certificate of the seed exchange key that encrypted the seed, and decrypts the seed.
+/// The certificate is verified with the compiled-in rules. If an attestation policy is given, the
+/// seed is handed over on upgrade, and the certificate may belong to another build that the policy
+/// allows. The seed must have been encrypted for the chain whose id and attestation policy hash to
+/// `binding`, or without either if it is `None`.
+#[allow(clippy::too_many_arguments)]
+pub(super) unsafe fn receive_seed(
+    key_manager: &Keychain,
+    master_cert: *const u8,
+    master_cert_len: u32,
+    encrypted_seed: *const u8,
+    encrypted_seed_len: u32,
+    binding: Option<&SeedBinding>,
+    policy: Option<&AttestationPolicy>,
+    block_time: u64,
+) -> Result<Seed, sgx_status_t> {
+    if let Err(_e) = validate_const_ptr(master_cert, master_cert_len as usize) {
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    if let Err(_e) = validate_const_ptr(encrypted_seed, encrypted_seed_len as usize) {
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
     let cert_slice = slice::from_raw_parts(master_cert, master_cert_len as usize);
 
     if (encrypted_seed_len as usize) != ENCRYPTED_SEED_SIZE {
@@ -135,7 +405,7 @@ pub unsafe extern "C" fn ecall_init_node(
             "Got encrypted seed with the wrong size: {:?}",
             encrypted_seed_len
         );
-        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
     }
 
     let encrypted_seed_slice = slice::from_raw_parts(encrypted_seed, encrypted_seed_len as usize);
@@ -147,10 +417,19 @@ pub unsafe extern "C" fn ecall_init_node(
     let mut target_public_key: [u8; PUBLIC_KEY_SIZE] = [0u8; PUBLIC_KEY_SIZE];
 
     // validate certificate w/ attestation report
-    let pk = match verify_ra_cert(cert_slice) {
+    let verified = match policy {
+        Some(policy) => verify_ra_cert_with_policy(
+            cert_slice,
+            Some(policy),
+            block_time,
+            ExpectedEnclave::UpgradeSource,
+        ),
+        None => verify_ra_cert(cert_slice),
+    };
+    let pk = match verified {
         Err(e) => {
             error!("Error in validating certificate: {:?}", e);
-            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+            return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
         }
         Ok(res) => res,
     };
@@ -161,25 +440,11 @@ pub unsafe extern "C" fn ecall_init_node(
             "Got public key from certificate with the wrong size: {:?}",
             pk.len()
         );
-        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
     }
     target_public_key.copy_from_slice(&pk);
 
-    let mut key_manager = Keychain::new();
-    let seed = match decrypt_seed(&key_manager, target_public_key, encrypted_seed) {
-        Ok(result) => result,
-        Err(status) => return status,
-    };
-
-    if let Err(_e) = key_manager.set_consensus_seed(seed) {
-        return sgx_status_t::SGX_ERROR_UNEXPECTED;
-    }
-
-    if let Err(_e) = key_manager.generate_consensus_master_keys() {
-        return sgx_status_t::SGX_ERROR_UNEXPECTED;
-    }
-
-    sgx_status_t::SGX_SUCCESS
+    decrypt_seed(key_manager, target_public_key, encrypted_seed, binding)
 }
 
 #[no_mangle]
@@ -240,6 +505,37 @@ pub unsafe extern "C" fn ecall_get_attestation_report(
     sgx_status_t::SGX_SUCCESS
 }
 
+/**
+ * `ecall_get_attestation_report_dcap`
+ *
+ * Same as `ecall_get_attestation_report`, for platforms that attest with DCAP instead of EPID. The
+ * certificate contains the ECDSA quote of the enclave, along with the collateral that the chain
+ * needs to verify it.
+ * # Safety
+ * Something should go here
+ */
+#[no_mangle]
+pub unsafe extern "C" fn ecall_get_attestation_report_dcap() -> sgx_status_t {
+    let kp = KEY_MANAGER.get_registration_key().unwrap();
+    trace!(
+        "ecall_get_attestation_report_dcap key pk: {:?}",
+        &kp.get_pubkey().to_vec()
+    );
+    let (_private_key_der, cert) = match create_attestation_certificate_dcap(&kp) {
+        Err(e) => {
+            warn!("Error in create_attestation_certificate_dcap: {:?}", e);
+            return e;
+        }
+        Ok(res) => res,
+    };
+
+    if let Err(status) = write_to_untrusted(cert.as_slice(), ATTESTATION_CERTIFICATE_SAVE_PATH) {
+        return status;
+    }
+
+    sgx_status_t::SGX_SUCCESS
+}
+
 ///
 /// This function generates the registration_key, which is used in the attestation and registration
 /// process
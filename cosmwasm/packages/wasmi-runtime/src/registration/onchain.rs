@@ -7,13 +7,14 @@ use std::panic;
 use enclave_ffi_types::NodeAuthResult;
 
 use crate::consts::ENCRYPTED_SEED_SIZE;
-use crate::crypto::PUBLIC_KEY_SIZE;
+use crate::crypto::{sha_256, KEY_MANAGER, PUBLIC_KEY_SIZE};
 use crate::{
     oom_handler::{self, get_then_clear_oom_happened},
     utils::{validate_const_ptr, validate_mut_ptr},
 };
 
-use super::cert::verify_ra_cert;
+use super::cert::{verify_ra_cert_with_policy, ExpectedEnclave};
+use super::policy::AttestationPolicy;
 use super::seed_exchange::encrypt_seed;
 
 ///
@@ -26,6 +27,16 @@ use super::seed_exchange::encrypt_seed;
 /// The seed is encrypted with a key derived from the secret master key of the chain, and the public
 /// key of the requesting chain
 ///
+/// The certificate is always verified with the compiled-in rules. The attestation policy of the
+/// chain, if it has one, can only add restrictions on top of them. The host passes the policy in,
+/// but it is only used if it hashes to the policy the seed was created with, so the host can't
+/// loosen it. An empty policy means the chain has none.
+///
+/// Grace periods in the policy, and the validity of DCAP collateral, are evaluated at `block_time`,
+/// which must be the time of the block the registration happens in. The enclave can't verify it,
+/// but since the grace periods only end restrictions of the policy, a wrong block time can't make
+/// the enclave accept a certificate that the compiled-in rules reject.
+///
 /// This function happens on-chain, so any panic here might cause the chain to go boom
 ///
 /// # Safety
@@ -34,8 +45,106 @@ use super::seed_exchange::encrypt_seed;
 pub unsafe extern "C" fn ecall_authenticate_new_node(
     cert: *const u8,
     cert_len: u32,
+    policy: *const u8,
+    policy_len: u32,
+    block_time: u64,
     seed: &mut [u8; ENCRYPTED_SEED_SIZE],
 ) -> NodeAuthResult {
+    authenticate_new_node(cert, cert_len, policy, policy_len, block_time, 0, seed)
+}
+
+///
+/// `ecall_authenticate_new_node_for_epoch`
+///
+/// Same as [ecall_authenticate_new_node], but shares the seed of a later epoch of the consensus seed.
+/// Registering nodes call this once for every epoch after genesis.
+///
+/// This is also how the node that rotated the seed distributes a new epoch to the existing nodes,
+/// off-chain, before the chain switches to it. Since every node must have the seed of an epoch
+/// before it is used on-chain, this is deterministic on-chain as well.
+///
+/// # Safety
+/// Safety first
+#[no_mangle]
+pub unsafe extern "C" fn ecall_authenticate_new_node_for_epoch(
+    cert: *const u8,
+    cert_len: u32,
+    policy: *const u8,
+    policy_len: u32,
+    block_time: u64,
+    epoch: u32,
+    seed: &mut [u8; ENCRYPTED_SEED_SIZE],
+) -> NodeAuthResult {
+    authenticate_new_node(cert, cert_len, policy, policy_len, block_time, epoch, seed)
+}
+
+unsafe fn authenticate_new_node(
+    cert: *const u8,
+    cert_len: u32,
+    policy: *const u8,
+    policy_len: u32,
+    block_time: u64,
+    epoch: u32,
+    seed: &mut [u8; ENCRYPTED_SEED_SIZE],
+) -> NodeAuthResult {
+    share_seed(cert, cert_len, epoch, seed, |cert_slice| {
+        let policy = read_chain_attestation_policy(policy, policy_len)?;
+        verify_ra_cert_with_policy(
+            cert_slice,
+            policy.as_ref(),
+            block_time,
+            ExpectedEnclave::Peer,
+        )
+    })
+}
+
+/// Reads the attestation policy the host passed in, which must be the one the seed was created
+/// with. An empty policy means the chain has none.
+///
+/// Seeds that were created before the policy was bound to them can't check this, so their policy is
+/// taken as is. That is safe for registration, since a policy can only restrict the peers the
+/// compiled-in rules accept. Upgrades, where the policy allows other builds, need a bound seed.
+pub(super) unsafe fn read_chain_attestation_policy(
+    policy: *const u8,
+    policy_len: u32,
+) -> Result<Option<AttestationPolicy>, NodeAuthResult> {
+    let policy_slice: &[u8] = if policy_len == 0 {
+        &[]
+    } else {
+        if let Err(_e) = validate_const_ptr(policy, policy_len as usize) {
+            return Err(NodeAuthResult::InvalidInput);
+        }
+        std::slice::from_raw_parts(policy, policy_len as usize)
+    };
+
+    match KEY_MANAGER.get_seed_binding() {
+        Some((_, policy_hash)) if policy_hash == sha_256(policy_slice) => {}
+        None => {}
+        _ => {
+            warn!("Got an attestation policy other than the one the seed was created with");
+            return Err(NodeAuthResult::InvalidAttestationPolicy);
+        }
+    }
+
+    if policy_slice.is_empty() {
+        Ok(None)
+    } else {
+        AttestationPolicy::from_slice(policy_slice).map(Some)
+    }
+}
+
+/// Verifies the certificate with `verify`, which returns the public key in it, and encrypts the
+/// seed of `epoch` to that key
+pub(super) unsafe fn share_seed<F>(
+    cert: *const u8,
+    cert_len: u32,
+    epoch: u32,
+    seed: &mut [u8; ENCRYPTED_SEED_SIZE],
+    verify: F,
+) -> NodeAuthResult
+where
+    F: FnOnce(&[u8]) -> Result<Vec<u8>, NodeAuthResult> + panic::UnwindSafe,
+{
     if let Err(_err) = oom_handler::register_oom_handler() {
         error!("Could not register OOM handler!");
         return NodeAuthResult::MemorySafetyAllocationError;
@@ -51,7 +160,7 @@ pub unsafe extern "C" fn ecall_authenticate_new_node(
 
     let result = panic::catch_unwind(|| -> Result<Vec<u8>, NodeAuthResult> {
         // verify certificate, and return the public key in the extra data of the report
-        let pk = verify_ra_cert(cert_slice)?;
+        let pk = verify(cert_slice)?;
 
         // just make sure the length isn't wrong for some reason (certificate may be malformed)
         if pk.len() != PUBLIC_KEY_SIZE {
@@ -69,8 +178,8 @@ pub unsafe extern "C" fn ecall_authenticate_new_node(
             &target_public_key.to_vec()
         );
 
-        let res: Vec<u8> =
-            encrypt_seed(target_public_key).map_err(|_| NodeAuthResult::SeedEncryptionFailed)?;
+        let res: Vec<u8> = encrypt_seed(target_public_key, epoch)
+            .map_err(|_| NodeAuthResult::SeedEncryptionFailed)?;
 
         Ok(res)
     });
@@ -0,0 +1,389 @@
+//! The attestation policy of the network.
+//!
+//! The quote statuses and advisories we accept and the identity we require of other enclaves are
+//! compiled into the enclave (see `verify_quote_status` and `verify_enclave_identity` in
+//! `cert.rs`). The policy lets the chain restrict them further, e.g. to stop accepting a platform
+//! status once Intel rolled out a TCB recovery, or to require a minimum security version. It can't
+//! loosen them.
+//!
+//! The policy is set in the genesis of the chain and bound to the consensus seed along with the
+//! chain id, so the enclave only uses the policy the chain was created with. It is passed to
+//! `ecall_authenticate_new_node` along with the block time, so every node reaches the same result.
+#![cfg_attr(not(feature = "SGX_MODE_HW"), allow(unused))]
+
+use log::*;
+use serde::Deserialize;
+use std::string::String;
+use std::vec::Vec;
+
+use enclave_ffi_types::NodeAuthResult;
+
+use super::report::{AdvisoryIDs, SgxEnclaveReport, SgxQuoteStatus};
+
+/// The only version of the policy format that this enclave understands. A policy of any other
+/// version is rejected rather than interpreted differently than newer enclaves would.
+pub const ATTESTATION_POLICY_VERSION: u32 = 1;
+
+/// A quote status that is accepted, optionally only until a point in time
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AcceptedStatus {
+    /// The status as reported by IAS, e.g. `GROUP_OUT_OF_DATE`
+    pub status: String,
+    /// Block time (in seconds since the unix epoch) from which the status is no longer accepted
+    #[serde(default)]
+    pub until: Option<u64>,
+}
+
+/// A security advisory that is accepted, optionally only until a point in time
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AcceptedAdvisory {
+    /// The advisory ID, e.g. `INTEL-SA-00334`
+    pub id: String,
+    /// Block time (in seconds since the unix epoch) from which the advisory is no longer accepted
+    #[serde(default)]
+    pub until: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AttestationPolicy {
+    pub version: u32,
+    /// Quote statuses, other than `OK`, that are accepted as long as all of the advisories in the
+    /// report are accepted as well
+    #[serde(default)]
+    pub accepted_statuses: Vec<AcceptedStatus>,
+    #[serde(default)]
+    pub accepted_advisories: Vec<AcceptedAdvisory>,
+    /// Hex encoded MRENCLAVE values that are accepted, on top of the compiled-in identity checks.
+    /// If empty, any MRENCLAVE that passes them is accepted.
+    /// This is also the list of enclaves the seed may be handed over to on upgrade.
+    #[serde(default)]
+    pub allowed_mr_enclaves: Vec<String>,
+    /// The lowest security version (ISVSVN) of enclaves that are accepted. Enclaves with a lower
+    /// version than the one verifying them are never accepted, except for the enclave that hands
+    /// the seed over on upgrade.
+    #[serde(default)]
+    pub min_isv_svn: u16,
+}
+
+fn is_in_effect(until: Option<u64>, block_time: u64) -> bool {
+    match until {
+        Some(until) => block_time < until,
+        None => true,
+    }
+}
+
+impl AttestationPolicy {
+    pub fn from_slice(policy: &[u8]) -> Result<Self, NodeAuthResult> {
+        let policy: Self = serde_json::from_slice(policy).map_err(|err| {
+            warn!("Failed to parse attestation policy: {:?}", err);
+            NodeAuthResult::InvalidAttestationPolicy
+        })?;
+
+        if policy.version != ATTESTATION_POLICY_VERSION {
+            warn!(
+                "Attestation policy version {} is not supported. Expected version {}",
+                policy.version, ATTESTATION_POLICY_VERSION
+            );
+            return Err(NodeAuthResult::InvalidAttestationPolicy);
+        }
+
+        for status in &policy.accepted_statuses {
+            if SgxQuoteStatus::from(status.status.as_str()) == SgxQuoteStatus::UnknownBadStatus {
+                warn!(
+                    "Attestation policy contains unknown status {}",
+                    status.status
+                );
+                return Err(NodeAuthResult::InvalidAttestationPolicy);
+            }
+        }
+
+        for mr_enclave in &policy.allowed_mr_enclaves {
+            match hex::decode(mr_enclave) {
+                Ok(mr_enclave) if mr_enclave.len() == 32 => {}
+                _ => {
+                    warn!(
+                        "Attestation policy contains invalid MRENCLAVE {}",
+                        mr_enclave
+                    );
+                    return Err(NodeAuthResult::InvalidAttestationPolicy);
+                }
+            }
+        }
+
+        Ok(policy)
+    }
+
+    /// Checks the quote status and advisories against the policy. This is on top of
+    /// `verify_quote_status` in `cert.rs`, so a status the policy accepts must be accepted by the
+    /// compiled-in rules as well.
+    /// The policy as it will be once all of its grace periods are over. This is what off-chain
+    /// checks use, since there is no block time they could trust.
+    pub fn without_grace_periods(&self) -> Self {
+        let mut policy = self.clone();
+        policy
+            .accepted_statuses
+            .retain(|accepted| accepted.until.is_none());
+        policy
+            .accepted_advisories
+            .retain(|accepted| accepted.until.is_none());
+        policy
+    }
+
+    pub fn verify_quote_status(
+        &self,
+        quote_status: &SgxQuoteStatus,
+        advisories: &AdvisoryIDs,
+        block_time: u64,
+    ) -> Result<(), NodeAuthResult> {
+        if *quote_status != SgxQuoteStatus::OK {
+            let accepted = self.accepted_statuses.iter().any(|accepted| {
+                SgxQuoteStatus::from(accepted.status.as_str()) == *quote_status
+                    && is_in_effect(accepted.until, block_time)
+            });
+            if !accepted {
+                error!(
+                    "Quote status {:?} is not accepted by the attestation policy",
+                    quote_status
+                );
+                return Err(NodeAuthResult::from(quote_status));
+            }
+        }
+
+        let not_accepted: Vec<&String> = advisories
+            .0
+            .iter()
+            .filter(|id| {
+                !self
+                    .accepted_advisories
+                    .iter()
+                    .any(|accepted| accepted.id == **id && is_in_effect(accepted.until, block_time))
+            })
+            .collect();
+        if !not_accepted.is_empty() {
+            error!(
+                "The following advisories are not accepted by the attestation policy: {:?}",
+                not_accepted
+            );
+            return Err(NodeAuthResult::AdvisoryNotAccepted);
+        }
+
+        Ok(())
+    }
+
+    /// Checks the security version of the enclave, and its MRENCLAVE against the allowed list if
+    /// there is one. This is on top of `verify_enclave_identity` in `cert.rs`.
+    pub fn verify_enclave_identity(&self, report: &SgxEnclaveReport) -> Result<(), NodeAuthResult> {
+        if report.isv_svn < self.min_isv_svn {
+            error!(
+                "ISVSVN {} is lower than the {} required by the attestation policy",
+                report.isv_svn, self.min_isv_svn
+            );
+            return Err(NodeAuthResult::IsvSvnTooLow);
+        }
+
+        if self.allowed_mr_enclaves.is_empty() {
+            return Ok(());
+        }
+
+        let mr_enclave = hex::encode(report.mr_enclave);
+        if !self
+            .allowed_mr_enclaves
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(&mr_enclave))
+        {
+            error!(
+                "MRENCLAVE {} is not allowed by the attestation policy",
+                mr_enclave
+            );
+            return Err(NodeAuthResult::MrEnclaveMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use crate::consts::MRSIGNER;
+
+    const MR_ENCLAVE: &str = "2a04846c7b02f31e8b9298e0a0ebb89b04fdc1f7dba1864f679594ae439158cf";
+
+    fn policy() -> AttestationPolicy {
+        let policy = format!(
+            r#"{{
+                "version": 1,
+                "accepted_statuses": [
+                    {{ "status": "SW_HARDENING_NEEDED" }},
+                    {{ "status": "GROUP_OUT_OF_DATE", "until": 1000 }}
+                ],
+                "accepted_advisories": [
+                    {{ "id": "INTEL-SA-00334" }},
+                    {{ "id": "INTEL-SA-00289", "until": 2000 }}
+                ],
+                "allowed_mr_enclaves": ["{}"],
+                "min_isv_svn": 2
+            }}"#,
+            MR_ENCLAVE.to_uppercase()
+        );
+        AttestationPolicy::from_slice(policy.as_bytes()).unwrap()
+    }
+
+    fn advisories(ids: &[&str]) -> AdvisoryIDs {
+        AdvisoryIDs(ids.iter().map(|id| id.to_string()).collect())
+    }
+
+    fn enclave_report(mr_enclave: &str, isv_svn: u16) -> SgxEnclaveReport {
+        let mut report = SgxEnclaveReport {
+            cpu_svn: [0; 16],
+            misc_select: 0,
+            attributes: [0; 16],
+            mr_enclave: [0; 32],
+            mr_signer: MRSIGNER,
+            isv_prod_id: 0,
+            isv_svn,
+            report_data: [0; 64],
+        };
+        report
+            .mr_enclave
+            .copy_from_slice(&hex::decode(mr_enclave).unwrap());
+        report
+    }
+
+    pub fn test_attestation_policy_from_slice() {
+        let policy = policy();
+        assert_eq!(policy.accepted_statuses.len(), 2);
+        assert_eq!(policy.accepted_advisories[1].until, Some(2000));
+
+        let minimal = AttestationPolicy::from_slice(br#"{"version":1}"#).unwrap();
+        assert!(minimal.accepted_statuses.is_empty());
+        assert!(minimal.allowed_mr_enclaves.is_empty());
+        assert_eq!(minimal.min_isv_svn, 0);
+
+        for invalid in &[
+            r#"{"version":2}"#.to_string(),
+            r#"{"accepted_statuses":[]}"#.to_string(),
+            r#"{"version":1,"grace_period":5}"#.to_string(),
+            r#"{"version":1,"accepted_statuses":[{"status":"MOSTLY_OK"}]}"#.to_string(),
+            r#"{"version":1,"allowed_mr_enclaves":["abcd"]}"#.to_string(),
+            format!(
+                r#"{{"version":1,"allowed_mr_enclaves":["{}zz"]}}"#,
+                &MR_ENCLAVE[2..]
+            ),
+        ] {
+            assert_eq!(
+                AttestationPolicy::from_slice(invalid.as_bytes()).unwrap_err(),
+                NodeAuthResult::InvalidAttestationPolicy,
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    pub fn test_attestation_policy_quote_status() {
+        let policy = policy();
+
+        assert!(policy
+            .verify_quote_status(&SgxQuoteStatus::OK, &advisories(&[]), 0)
+            .is_ok());
+        assert!(policy
+            .verify_quote_status(
+                &SgxQuoteStatus::SwHardeningNeeded,
+                &advisories(&["INTEL-SA-00334"]),
+                5000
+            )
+            .is_ok());
+        assert_eq!(
+            policy.verify_quote_status(
+                &SgxQuoteStatus::ConfigurationAndSwHardeningNeeded,
+                &advisories(&["INTEL-SA-00334"]),
+                0
+            ),
+            Err(NodeAuthResult::SwHardeningAndConfigurationNeeded)
+        );
+        assert_eq!(
+            policy.verify_quote_status(
+                &SgxQuoteStatus::SwHardeningNeeded,
+                &advisories(&["INTEL-SA-00334", "INTEL-SA-00161"]),
+                0
+            ),
+            Err(NodeAuthResult::AdvisoryNotAccepted)
+        );
+    }
+
+    pub fn test_attestation_policy_grace_periods() {
+        let policy = policy();
+        let out_of_date = SgxQuoteStatus::GroupOutOfDate;
+
+        assert!(policy
+            .verify_quote_status(&out_of_date, &advisories(&["INTEL-SA-00289"]), 999)
+            .is_ok());
+        assert_eq!(
+            policy.verify_quote_status(&out_of_date, &advisories(&[]), 1000),
+            Err(NodeAuthResult::GroupOutOfDate)
+        );
+        assert!(policy
+            .verify_quote_status(
+                &SgxQuoteStatus::SwHardeningNeeded,
+                &advisories(&["INTEL-SA-00289"]),
+                1999
+            )
+            .is_ok());
+        assert_eq!(
+            policy.verify_quote_status(
+                &SgxQuoteStatus::SwHardeningNeeded,
+                &advisories(&["INTEL-SA-00289"]),
+                2000
+            ),
+            Err(NodeAuthResult::AdvisoryNotAccepted)
+        );
+
+        // Off-chain, the grace periods are over no matter what time it is
+        let policy = policy.without_grace_periods();
+        assert_eq!(
+            policy.verify_quote_status(&out_of_date, &advisories(&[]), 0),
+            Err(NodeAuthResult::GroupOutOfDate)
+        );
+        assert_eq!(
+            policy.verify_quote_status(
+                &SgxQuoteStatus::SwHardeningNeeded,
+                &advisories(&["INTEL-SA-00289"]),
+                0
+            ),
+            Err(NodeAuthResult::AdvisoryNotAccepted)
+        );
+        assert!(policy
+            .verify_quote_status(
+                &SgxQuoteStatus::SwHardeningNeeded,
+                &advisories(&["INTEL-SA-00334"]),
+                0
+            )
+            .is_ok());
+    }
+
+    pub fn test_attestation_policy_enclave_identity() {
+        let policy = policy();
+
+        assert_eq!(
+            policy.verify_enclave_identity(&enclave_report(MR_ENCLAVE, 2)),
+            Ok(())
+        );
+        assert_eq!(
+            policy.verify_enclave_identity(&enclave_report(&"00".repeat(32), 2)),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+        assert_eq!(
+            policy.verify_enclave_identity(&enclave_report(MR_ENCLAVE, 1)),
+            Err(NodeAuthResult::IsvSvnTooLow)
+        );
+
+        let unrestricted = AttestationPolicy::from_slice(br#"{"version":1}"#).unwrap();
+        assert_eq!(
+            unrestricted.verify_enclave_identity(&enclave_report(&"00".repeat(32), 0)),
+            Ok(())
+        );
+    }
+}
@@ -3,22 +3,51 @@ use sgx_types::{sgx_status_t, SgxResult};
 
 use crate::consts::ENCRYPTED_SEED_SIZE;
 use crate::crypto::{
-    AESKey, Keychain, SIVEncryptable, Seed, KEY_MANAGER, PUBLIC_KEY_SIZE, SEED_KEY_SIZE,
+    AESKey, Keychain, SIVEncryptable, Seed, HASH_SIZE, KEY_MANAGER, PUBLIC_KEY_SIZE, SEED_KEY_SIZE,
 };
 
-pub fn encrypt_seed(new_node_pk: [u8; PUBLIC_KEY_SIZE]) -> SgxResult<Vec<u8>> {
+/// The hashes of the chain id and the attestation policy the seed is bound to
+pub type SeedBinding = ([u8; HASH_SIZE], [u8; HASH_SIZE]);
+
+/// The data the seed is authenticated with. Seeds that were created before they were bound to the
+/// chain id and the attestation policy are authenticated with the public key of the new node only,
+/// like they always were, so nodes of chains that were created back then can still exchange them
+fn authenticated_data<'a>(
+    new_node_pk: &'a [u8],
+    binding: Option<&'a SeedBinding>,
+) -> Vec<&'a [u8]> {
+    let mut authenticated_data: Vec<&[u8]> = vec![new_node_pk];
+    if let Some((chain_id_hash, policy_hash)) = binding {
+        authenticated_data.push(chain_id_hash);
+        authenticated_data.push(policy_hash);
+    }
+    authenticated_data
+}
+
+///
+/// Encrypts the seed of `epoch` for a new node. Every epoch has its own seed exchange key, so the
+/// seed of a new epoch can't be decrypted by anyone who only has the seeds of the previous epochs
+///
+/// The chain id and the attestation policy are authenticated along with the seed, so the new node
+/// learns which chain the seed belongs to and how the chain authenticates nodes from the seed
+/// exchange, and not from its host. If the seed isn't bound to them, neither is the seed the new
+/// node gets
+///
+pub fn encrypt_seed(new_node_pk: [u8; PUBLIC_KEY_SIZE], epoch: u32) -> SgxResult<Vec<u8>> {
+    let seed = KEY_MANAGER
+        .get_consensus_seed_of_epoch(epoch)
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?;
     let shared_enc_key = KEY_MANAGER
-        .seed_exchange_key()
-        .unwrap()
+        .seed_exchange_key_of_epoch(epoch)
+        .map_err(|_| sgx_status_t::SGX_ERROR_UNEXPECTED)?
         .diffie_hellman(&new_node_pk);
+    let binding = KEY_MANAGER.get_seed_binding();
 
-    let mut authenticated_data: Vec<&[u8]> = Vec::default();
-    authenticated_data.push(&new_node_pk);
+    let authenticated_data = authenticated_data(&new_node_pk, binding.as_ref());
     // encrypt the seed using the symmetric key derived in the previous stage
-    let res = match AESKey::new_from_slice(&shared_enc_key).encrypt_siv(
-        KEY_MANAGER.get_consensus_seed().unwrap().as_slice() as &[u8],
-        Some(&authenticated_data),
-    ) {
+    let res = match AESKey::new_from_slice(&shared_enc_key)
+        .encrypt_siv(seed.as_slice() as &[u8], Some(&authenticated_data))
+    {
         Ok(r) => {
             if r.len() != ENCRYPTED_SEED_SIZE {
                 error!(
@@ -36,12 +65,18 @@ pub fn encrypt_seed(new_node_pk: [u8; PUBLIC_KEY_SIZE]) -> SgxResult<Vec<u8>> {
 }
 
 ///
-/// master_pk: [seed_exch_publickey] - Public key that is written on-chain at genesis
+/// master_pk: [seed_exch_publickey] - Public key of the epoch of the seed. The key of the genesis
+/// epoch is written on-chain at genesis
+/// binding: The hashes of the id of the chain the seed is expected to belong to, and of the
+/// attestation policy the chain is expected to have. Decryption fails if the seed was encrypted by
+/// an enclave of another chain, or of a chain with another policy. `None` expects a seed that isn't
+/// bound to either
 ///
 pub fn decrypt_seed(
     key_manager: &Keychain,
     master_pk: [u8; PUBLIC_KEY_SIZE],
     encrypted_seed: [u8; ENCRYPTED_SEED_SIZE],
+    binding: Option<&SeedBinding>,
 ) -> SgxResult<Seed> {
     // create shared encryption key using ECDH
     let shared_enc_key = key_manager
@@ -53,8 +88,7 @@ pub fn decrypt_seed(
 
     // Create AD of encryption
     let my_public_key = key_manager.get_registration_key().unwrap().get_pubkey();
-    let mut authenticated_data: Vec<&[u8]> = Vec::default();
-    authenticated_data.push(&my_public_key);
+    let authenticated_data = authenticated_data(&my_public_key, binding);
 
     // decrypt
     seed.as_mut()
@@ -75,3 +109,68 @@ pub fn decrypt_seed(
         });
     Ok(seed)
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use crate::crypto::key_manager::tests::keychain_with_registration_key;
+    use crate::crypto::{sha_256, KeyPair};
+
+    fn encrypt_for(
+        master_key: &KeyPair,
+        new_node_pk: [u8; PUBLIC_KEY_SIZE],
+        seed: &Seed,
+        binding: Option<&SeedBinding>,
+    ) -> [u8; ENCRYPTED_SEED_SIZE] {
+        let authenticated_data = authenticated_data(&new_node_pk, binding);
+        let encrypted = AESKey::new_from_slice(&master_key.diffie_hellman(&new_node_pk))
+            .encrypt_siv(seed.as_slice() as &[u8], Some(&authenticated_data))
+            .unwrap();
+
+        let mut encrypted_seed = [0u8; ENCRYPTED_SEED_SIZE];
+        encrypted_seed.copy_from_slice(&encrypted);
+        encrypted_seed
+    }
+
+    pub fn test_decrypt_seed_with_legacy_authenticated_data() {
+        let master_key = KeyPair::new().unwrap();
+        let registration_key = KeyPair::new().unwrap();
+        let new_node_pk = registration_key.get_pubkey();
+        let key_manager = keychain_with_registration_key(registration_key);
+        let seed = Seed::new().unwrap();
+        let binding = (sha_256(b"secret-2"), sha_256(b""));
+
+        // Seeds that aren't bound to the chain are authenticated with the public key only, which is
+        // how they were exchanged before the chain id and the policy were bound to them
+        assert_eq!(
+            authenticated_data(&new_node_pk, None),
+            vec![&new_node_pk as &[u8]]
+        );
+
+        let unbound = encrypt_for(&master_key, new_node_pk, &seed, None);
+        let decrypted = decrypt_seed(&key_manager, master_key.get_pubkey(), unbound, None).unwrap();
+        assert_eq!(decrypted.as_slice(), seed.as_slice());
+        assert!(decrypt_seed(
+            &key_manager,
+            master_key.get_pubkey(),
+            unbound,
+            Some(&binding)
+        )
+        .is_err());
+
+        let bound = encrypt_for(&master_key, new_node_pk, &seed, Some(&binding));
+        let decrypted =
+            decrypt_seed(&key_manager, master_key.get_pubkey(), bound, Some(&binding)).unwrap();
+        assert_eq!(decrypted.as_slice(), seed.as_slice());
+        assert!(decrypt_seed(&key_manager, master_key.get_pubkey(), bound, None).is_err());
+
+        let other_chain = (sha_256(b"secret-3"), sha_256(b""));
+        assert!(decrypt_seed(
+            &key_manager,
+            master_key.get_pubkey(),
+            bound,
+            Some(&other_chain)
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//!
+//! Hands the consensus seed over to an upgraded enclave.
+//!
+//! Production enclaves only share the seed with enclaves of their exact MRENCLAVE, so a node that
+//! installs a new enclave build can't get the seed by registering again. Instead, the old enclave
+//! of the node hands its seed over to the new one:
+//!
+//! 1. The new enclave creates a registration key and an attestation certificate of it, the same
+//!    way it does before registration (`ecall_key_gen` and `ecall_get_attestation_report`).
+//! 2. The old enclave verifies that certificate and encrypts the seed of every epoch to it with
+//!    [ecall_export_seed_for_upgrade]. This uses the same seed exchange as registration.
+//! 3. The new enclave verifies the seed exchange certificate of every epoch, decrypts the seeds
+//!    with [ecall_import_seed_for_upgrade] and seals them itself.
+//!
+//! Both enclaves check each other against the `allowed_mr_enclaves` of the attestation policy of
+//! the chain, which must list the old and the new MRENCLAVE (and the MRENCLAVE of the enclave that
+//! created the seed exchange keys, if that was another build). Both enclaves must be signed by
+//! `MRSIGNER`, and the new one can't have a lower ISVSVN than the old one.
+//!
+//! The old enclave only trusts the policy the seed was created with, which it sealed along with
+//! the seed, so the host can't allow other enclaves by passing in another policy. Since there is
+//! no block time it could trust, it also ignores the grace periods of the policy.
+//!
+use log::*;
+use sgx_types::sgx_status_t;
+use std::slice;
+
+use enclave_ffi_types::{NodeAuthResult, ENCRYPTED_SEED_SIZE};
+
+use crate::crypto::{sha_256, Keychain, KEY_MANAGER};
+use crate::utils::validate_const_ptr;
+
+use super::cert::verify_upgrade_cert;
+use super::offchain::{read_chain_id, receive_seed};
+use super::onchain::{read_chain_attestation_policy, share_seed};
+use super::policy::AttestationPolicy;
+
+/// Parses the attestation policy, which must allow specific MRENCLAVE values for an upgrade.
+/// Without such a list the policy defers to the compiled-in `SIGNING_METHOD`, which was never
+/// meant to decide which builds may take over the seed.
+///
+/// This doesn't check that it is the policy of the chain. The caller has to.
+unsafe fn upgrade_policy(
+    policy: *const u8,
+    policy_len: u32,
+) -> Result<AttestationPolicy, NodeAuthResult> {
+    if policy_len == 0 {
+        warn!("Enclave upgrades require an attestation policy");
+        return Err(NodeAuthResult::UpgradeNotAllowed);
+    }
+    if let Err(_e) = validate_const_ptr(policy, policy_len as usize) {
+        return Err(NodeAuthResult::InvalidInput);
+    }
+    let policy = AttestationPolicy::from_slice(slice::from_raw_parts(policy, policy_len as usize))?;
+
+    allows_upgrades(policy)
+}
+
+fn allows_upgrades(policy: AttestationPolicy) -> Result<AttestationPolicy, NodeAuthResult> {
+    if policy.allowed_mr_enclaves.is_empty() {
+        warn!("The attestation policy doesn't allow any MRENCLAVE to be upgraded to");
+        return Err(NodeAuthResult::UpgradeNotAllowed);
+    }
+
+    Ok(policy)
+}
+
+///
+/// `ecall_export_seed_for_upgrade`
+///
+/// Runs in the old enclave. Verifies the attestation certificate of the new enclave, whose
+/// MRENCLAVE must be allowed by the attestation policy, and returns the seed of `epoch` encrypted
+/// for it. This is called once for every epoch the old enclave has.
+///
+/// `policy` must be the attestation policy the seed was created with. Its grace periods are
+/// ignored, and the new enclave can't have a lower ISVSVN than this one. Seeds that were created
+/// before the policy was bound to them can't be exported.
+///
+/// # Safety
+///  Something should go here
+///
+#[no_mangle]
+pub unsafe extern "C" fn ecall_export_seed_for_upgrade(
+    cert: *const u8,
+    cert_len: u32,
+    policy: *const u8,
+    policy_len: u32,
+    epoch: u32,
+    seed: &mut [u8; ENCRYPTED_SEED_SIZE],
+) -> NodeAuthResult {
+    if policy_len == 0 {
+        warn!("Enclave upgrades require an attestation policy");
+        return NodeAuthResult::UpgradeNotAllowed;
+    }
+    // The policy of a seed that isn't bound to one comes from the host, which mustn't decide which
+    // builds get the seed
+    if KEY_MANAGER.get_seed_binding().is_none() {
+        warn!("Enclave upgrades require a seed that is bound to the attestation policy");
+        return NodeAuthResult::UpgradeNotAllowed;
+    }
+
+    // The new enclave gets the seed just like a registering node would, except only the policy
+    // can allow it in
+    share_seed(cert, cert_len, epoch, seed, |cert_slice| {
+        let policy = read_chain_attestation_policy(policy, policy_len)?
+            .ok_or(NodeAuthResult::UpgradeNotAllowed)?;
+        verify_upgrade_cert(cert_slice, &allows_upgrades(policy)?)
+    })
+}
+
+///
+/// `ecall_import_seed_for_upgrade`
+///
+/// Runs in the new enclave. Works like [ecall_init_node] for the genesis epoch and like
+/// [ecall_add_consensus_seed_epoch] for later epochs, except that `master_cert` is verified with
+/// the attestation policy, since it was created by an older build. Epochs must be imported in order.
+///
+/// Like in [ecall_init_node], the seed only decrypts if `chain_id` is the chain the seed belongs
+/// to, and `policy` is the attestation policy the seed was created with.
+///
+/// # Safety
+///  Something should go here
+///
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn ecall_import_seed_for_upgrade(
+    master_cert: *const u8,
+    master_cert_len: u32,
+    policy: *const u8,
+    policy_len: u32,
+    block_time: u64,
+    epoch: u32,
+    encrypted_seed: *const u8,
+    encrypted_seed_len: u32,
+    chain_id: *const u8,
+    chain_id_len: u32,
+) -> sgx_status_t {
+    let parsed_policy = match upgrade_policy(policy, policy_len) {
+        Ok(policy) => policy,
+        Err(e) => {
+            error!("Can't import the seed: {}", e);
+            return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+        }
+    };
+    // upgrade_policy already validated the pointer
+    let policy_slice = slice::from_raw_parts(policy, policy_len as usize);
+    let policy_hash = sha_256(policy_slice);
+
+    let chain_id = match read_chain_id(chain_id, chain_id_len) {
+        Ok(chain_id) => chain_id,
+        Err(status) => return status,
+    };
+    let chain_id_hash = sha_256(chain_id.as_bytes());
+
+    let mut key_manager = Keychain::new();
+    if epoch != 0 && key_manager.get_chain_id_hash().ok() != Some(chain_id_hash) {
+        error!("Can't import the seed of epoch {} for another chain", epoch);
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+    if epoch != 0 && key_manager.get_attestation_policy_hash().ok() != Some(policy_hash) {
+        error!(
+            "Can't import the seed of epoch {} with another attestation policy",
+            epoch
+        );
+        return sgx_status_t::SGX_ERROR_INVALID_PARAMETER;
+    }
+
+    let seed = match receive_seed(
+        &key_manager,
+        master_cert,
+        master_cert_len,
+        encrypted_seed,
+        encrypted_seed_len,
+        Some(&(chain_id_hash, policy_hash)),
+        Some(&parsed_policy),
+        block_time,
+    ) {
+        Ok(result) => result,
+        Err(status) => return status,
+    };
+
+    // Both of these seal the seed with the identity of this enclave
+    if epoch == 0 {
+        if let Err(_e) = key_manager.set_consensus_seed(seed) {
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+
+        if let Err(_e) = key_manager.set_chain_id(chain_id) {
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+
+        if let Err(_e) = key_manager.set_attestation_policy(policy_slice) {
+            return sgx_status_t::SGX_ERROR_UNEXPECTED;
+        }
+    } else if let Err(e) = key_manager.add_consensus_seed_epoch(epoch, seed) {
+        error!("Failed to import the seed of epoch {}: {}", epoch, e);
+        return sgx_status_t::SGX_ERROR_UNEXPECTED;
+    }
+
+    sgx_status_t::SGX_SUCCESS
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::untrusted::fs::File;
+
+    const MR_ENCLAVE: &str = "2a04846c7b02f31e8b9298e0a0ebb89b04fdc1f7dba1864f679594ae439158cf";
+
+    fn upgrade_policy_of(policy: &str) -> Result<AttestationPolicy, NodeAuthResult> {
+        unsafe { upgrade_policy(policy.as_ptr(), policy.len() as u32) }
+    }
+
+    pub fn test_upgrade_requires_allowed_mr_enclaves() {
+        assert_eq!(
+            unsafe { upgrade_policy([].as_ptr(), 0) }.unwrap_err(),
+            NodeAuthResult::UpgradeNotAllowed
+        );
+        assert_eq!(
+            upgrade_policy_of(r#"{"version":1}"#).unwrap_err(),
+            NodeAuthResult::UpgradeNotAllowed
+        );
+        assert_eq!(
+            upgrade_policy_of(r#"{"version":2}"#).unwrap_err(),
+            NodeAuthResult::InvalidAttestationPolicy
+        );
+
+        let policy = upgrade_policy_of(&format!(
+            r#"{{"version":1,"allowed_mr_enclaves":["{}"]}}"#,
+            MR_ENCLAVE
+        ))
+        .unwrap();
+        assert_eq!(policy.allowed_mr_enclaves, vec![MR_ENCLAVE.to_string()]);
+    }
+
+    pub fn test_export_seed_for_upgrade_without_policy() {
+        let mut cert = vec![];
+        let mut f = File::open("../wasmi-runtime/src/registration/fixtures/attestation_cert_hw_v2")
+            .unwrap();
+        f.read_to_end(&mut cert).unwrap();
+        let mut seed = [0u8; ENCRYPTED_SEED_SIZE];
+
+        let result = unsafe {
+            ecall_export_seed_for_upgrade(
+                cert.as_ptr(),
+                cert.len() as u32,
+                [].as_ptr(),
+                0,
+                0,
+                &mut seed,
+            )
+        };
+        assert_eq!(result, NodeAuthResult::UpgradeNotAllowed);
+        assert_eq!(seed, [0u8; ENCRYPTED_SEED_SIZE]);
+    }
+}
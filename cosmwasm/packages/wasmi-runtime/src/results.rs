@@ -1,5 +1,6 @@
 use enclave_ffi_types::{
-    EnclaveError, HandleResult, InitResult, QueryResult, UntrustedVmError, UserSpaceBuffer,
+    EnclaveError, GasProfile, HandleResult, InitResult, MigrateResult, OcallFailure, OcallKind,
+    QueryResult, ReissueResult, UntrustedVmError, UserSpaceBuffer,
 };
 use sgx_types::sgx_status_t;
 
@@ -11,6 +12,10 @@ pub struct InitSuccess {
     pub output: Vec<u8>,
     /// The contract_key of this contract.
     pub contract_key: [u8; 64],
+    /// A proof that the contract key is valid in the current epoch of the consensus seed
+    pub contract_key_proof: [u8; 32],
+    /// A proof that the sender of the init is the admin of the contract
+    pub admin_proof: [u8; 32],
 }
 
 pub fn result_init_success_to_initresult(result: Result<InitSuccess, EnclaveError>) -> InitResult {
@@ -18,6 +23,8 @@ pub fn result_init_success_to_initresult(result: Result<InitSuccess, EnclaveErro
         Ok(InitSuccess {
             output,
             contract_key,
+            contract_key_proof,
+            admin_proof,
         }) => {
             let user_buffer = unsafe {
                 let mut user_buffer = std::mem::MaybeUninit::<UserSpaceBuffer>::uninit();
@@ -26,6 +33,8 @@ pub fn result_init_success_to_initresult(result: Result<InitSuccess, EnclaveErro
                     _ => {
                         return InitResult::Failure {
                             err: EnclaveError::FailedOcall {
+                                ocall: OcallKind::Allocate,
+                                failure: OcallFailure::Sgx,
                                 vm_error: UntrustedVmError::default(),
                             },
                         }
@@ -36,6 +45,8 @@ pub fn result_init_success_to_initresult(result: Result<InitSuccess, EnclaveErro
             InitResult::Success {
                 output: user_buffer,
                 contract_key,
+                contract_key_proof,
+                admin_proof,
             }
         }
         Err(err) => InitResult::Failure { err },
@@ -46,13 +57,18 @@ pub fn result_init_success_to_initresult(result: Result<InitSuccess, EnclaveErro
 pub struct HandleSuccess {
     /// The output of the calculation
     pub output: Vec<u8>,
+    /// The gas used by the calculation, by category
+    pub gas_profile: GasProfile,
 }
 
 pub fn result_handle_success_to_handleresult(
     result: Result<HandleSuccess, EnclaveError>,
 ) -> HandleResult {
     match result {
-        Ok(HandleSuccess { output }) => {
+        Ok(HandleSuccess {
+            output,
+            gas_profile,
+        }) => {
             let user_buffer = unsafe {
                 let mut user_buffer = std::mem::MaybeUninit::<UserSpaceBuffer>::uninit();
                 match ocall_allocate(user_buffer.as_mut_ptr(), output.as_ptr(), output.len()) {
@@ -60,6 +76,8 @@ pub fn result_handle_success_to_handleresult(
                     _ => {
                         return HandleResult::Failure {
                             err: EnclaveError::FailedOcall {
+                                ocall: OcallKind::Allocate,
+                                failure: OcallFailure::Sgx,
                                 vm_error: UntrustedVmError::default(),
                             },
                         }
@@ -69,6 +87,7 @@ pub fn result_handle_success_to_handleresult(
             };
             HandleResult::Success {
                 output: user_buffer,
+                gas_profile,
             }
         }
         Err(err) => HandleResult::Failure { err },
@@ -79,13 +98,18 @@ pub fn result_handle_success_to_handleresult(
 pub struct QuerySuccess {
     /// The output of the calculation
     pub output: Vec<u8>,
+    /// The gas used by the calculation, by category
+    pub gas_profile: GasProfile,
 }
 
 pub fn result_query_success_to_queryresult(
     result: Result<QuerySuccess, EnclaveError>,
 ) -> QueryResult {
     match result {
-        Ok(QuerySuccess { output }) => {
+        Ok(QuerySuccess {
+            output,
+            gas_profile,
+        }) => {
             let user_buffer = unsafe {
                 let mut user_buffer = std::mem::MaybeUninit::<UserSpaceBuffer>::uninit();
                 match ocall_allocate(user_buffer.as_mut_ptr(), output.as_ptr(), output.len()) {
@@ -93,6 +117,8 @@ pub fn result_query_success_to_queryresult(
                     _ => {
                         return QueryResult::Failure {
                             err: EnclaveError::FailedOcall {
+                                ocall: OcallKind::Allocate,
+                                failure: OcallFailure::Sgx,
                                 vm_error: UntrustedVmError::default(),
                             },
                         }
@@ -102,8 +128,77 @@ pub fn result_query_success_to_queryresult(
             };
             QueryResult::Success {
                 output: user_buffer,
+                gas_profile,
             }
         }
         Err(err) => QueryResult::Failure { err },
     }
 }
+
+/// This struct is returned from a migrate method.
+pub struct MigrateSuccess {
+    /// The output of the calculation
+    pub output: Vec<u8>,
+    /// A proof that the contract key is valid for the new code of the contract
+    pub contract_key_proof: [u8; 32],
+    /// A proof that the admin of the contract can migrate it from the new code
+    pub admin_proof: [u8; 32],
+}
+
+pub fn result_migrate_success_to_migrateresult(
+    result: Result<MigrateSuccess, EnclaveError>,
+) -> MigrateResult {
+    match result {
+        Ok(MigrateSuccess {
+            output,
+            contract_key_proof,
+            admin_proof,
+        }) => {
+            let user_buffer = unsafe {
+                let mut user_buffer = std::mem::MaybeUninit::<UserSpaceBuffer>::uninit();
+                match ocall_allocate(user_buffer.as_mut_ptr(), output.as_ptr(), output.len()) {
+                    sgx_status_t::SGX_SUCCESS => { /* continue */ }
+                    _ => {
+                        return MigrateResult::Failure {
+                            err: EnclaveError::FailedOcall {
+                                ocall: OcallKind::Allocate,
+                                failure: OcallFailure::Sgx,
+                                vm_error: UntrustedVmError::default(),
+                            },
+                        }
+                    }
+                }
+                user_buffer.assume_init()
+            };
+            MigrateResult::Success {
+                output: user_buffer,
+                contract_key_proof,
+                admin_proof,
+            }
+        }
+        Err(err) => MigrateResult::Failure { err },
+    }
+}
+
+/// This struct is returned when the proofs of a contract are moved to a new epoch.
+pub struct ReissueSuccess {
+    /// The proof of the contract key in the new epoch
+    pub contract_key_proof: [u8; 32],
+    /// The admin proof in the new epoch, or all zeros if no admin proof was given
+    pub admin_proof: [u8; 32],
+}
+
+pub fn result_reissue_success_to_reissueresult(
+    result: Result<ReissueSuccess, EnclaveError>,
+) -> ReissueResult {
+    match result {
+        Ok(ReissueSuccess {
+            contract_key_proof,
+            admin_proof,
+        }) => ReissueResult::Success {
+            contract_key_proof,
+            admin_proof,
+        },
+        Err(err) => ReissueResult::Failure { err },
+    }
+}
@@ -35,6 +35,7 @@ mod test {
             // This line is commented out because it was creating a file without removing it, which was annoying.
             // crate::registration::tests::run_tests();
             crate::crypto::tests::run_tests();
+            crate::cosmwasm::tests::run_tests();
             crate::wasm::tests::run_tests();
             crate::registration::tests::run_tests();
             crate::logging_tests::run_tests();
@@ -1,13 +1,31 @@
 use log::*;
 
+#[cfg(not(feature = "native"))]
 use sgx_trts::trts::{
     rsgx_lfence, rsgx_raw_is_outside_enclave, rsgx_sfence, rsgx_slice_is_outside_enclave,
 };
 use sgx_types::*;
 
+#[cfg(not(feature = "native"))]
 use crate::crypto::KeyPair;
+#[cfg(not(feature = "native"))]
 use crate::registration::create_attestation_certificate;
+#[cfg(not(feature = "native"))]
 use crate::storage::write_to_untrusted;
+#[cfg(feature = "native")]
+use native_trts::{rsgx_lfence, rsgx_raw_is_outside_enclave, rsgx_sfence};
+
+/// Native builds share their memory with the host, so there is no enclave boundary to check
+#[cfg(feature = "native")]
+mod native_trts {
+    pub fn rsgx_raw_is_outside_enclave(_ptr: *const u8, _len: usize) -> bool {
+        false
+    }
+
+    pub fn rsgx_lfence() {}
+
+    pub fn rsgx_sfence() {}
+}
 
 pub trait UnwrapOrSgxErrorUnexpected {
     type ReturnType;
@@ -53,6 +71,7 @@ pub fn validate_const_ptr(ptr: *const u8, ptr_len: usize) -> SgxResult<()> {
     Ok(())
 }
 
+#[cfg(not(feature = "native"))]
 pub fn validate_mut_slice(mut_slice: &mut [u8]) -> SgxResult<()> {
     if rsgx_slice_is_outside_enclave(mut_slice) {
         warn!("Tried to access memory outside enclave -- rsgx_slice_is_outside_enclave");
@@ -62,6 +81,7 @@ pub fn validate_mut_slice(mut_slice: &mut [u8]) -> SgxResult<()> {
     Ok(())
 }
 
+#[cfg(not(feature = "native"))]
 pub fn attest_from_key(
     kp: &KeyPair,
     save_path: &str,
@@ -3,21 +3,26 @@ use parity_wasm::elements;
 use parity_wasm::elements::Module;
 use wasmi::ModuleInstance;
 
-use enclave_ffi_types::{Ctx, EnclaveError};
+use enclave_ffi_types::{Ctx, DeserializationTarget, EnclaveError};
 
 use crate::cosmwasm::types::{CanonicalAddr, Env, SigInfo};
-use crate::crypto::Ed25519PublicKey;
-use crate::results::{HandleSuccess, InitSuccess, QuerySuccess};
+use crate::crypto::{AESKey, Ed25519PublicKey, HASH_SIZE, KEY_MANAGER};
+use crate::results::{HandleSuccess, InitSuccess, MigrateSuccess, QuerySuccess, ReissueSuccess};
 use crate::wasm::types::{IoNonce, SecretMessage};
 
 use super::contract_validation::{
-    calc_contract_hash, extract_contract_key, generate_encryption_key, validate_contract_key,
-    validate_msg, verify_params, ContractKey, CONTRACT_KEY_LENGTH,
+    calc_contract_hash, extract_contract_admin_proof, extract_contract_code_hash,
+    extract_contract_key, extract_contract_key_proof, extract_seed_epoch,
+    generate_contract_admin_proof, generate_contract_key_proof, generate_encryption_key,
+    generate_random_key, get_consensus_state_ikm, validate_contract_key,
+    validate_contract_key_for_code_hash, validate_msg, verify_params, verify_query_permit,
+    ContractKey, CONTRACT_KEY_LENGTH,
 };
-use super::gas::{gas_rules, WasmCosts};
+use super::gas::{gas_rules, GasSchedule, WasmCosts};
 use super::io::encrypt_output;
 use super::{
     memory::validate_memory,
+    module_cache,
     runtime::{create_builder, ContractInstance, ContractOperation, Engine, WasmiImportResolver},
 };
 
@@ -32,29 +37,36 @@ fn deallocate(pointer: *mut c_void);
 fn init(env_ptr: *mut c_void, msg_ptr: *mut c_void) -> *mut c_void
 fn handle(env_ptr: *mut c_void, msg_ptr: *mut c_void) -> *mut c_void
 fn query(msg_ptr: *mut c_void) -> *mut c_void
+fn migrate(env_ptr: *mut c_void, msg_ptr: *mut c_void) -> *mut c_void
 
-Re `init`, `handle` and `query`: We need to pass `env` & `msg`
+Re `init`, `handle`, `query` and `migrate`: We need to pass `env` & `msg`
 down to the wasm implementations, but because they are buffers
 we need to allocate memory regions inside the VM's instance and copy
 `env` & `msg` into those memory regions inside the VM's instance.
 */
 
 pub fn init(
-    context: Ctx,       // need to pass this to read_db & write_db
-    gas_limit: u64,     // gas limit for this execution
-    used_gas: &mut u64, // out-parameter for gas used in execution
-    contract: &[u8],    // contract wasm bytes
-    env: &[u8],         // blockchain state
-    msg: &[u8],         // probably function call and args
-    sig_info: &[u8],    // info about signature verification
+    context: Ctx,        // need to pass this to read_db & write_db
+    gas_limit: u64,      // gas limit for this execution
+    used_gas: &mut u64,  // out-parameter for gas used in execution
+    gas_schedule: &[u8], // serialized GasSchedule, or empty for the default costs
+    contract: &[u8],     // contract wasm bytes
+    env: &[u8],          // blockchain state
+    msg: &[u8],          // probably function call and args
+    sig_info: &[u8],     // info about signature verification
+    simulate: bool,      // estimate the gas on a store the host throws away
 ) -> Result<InitSuccess, EnclaveError> {
+    let wasm_costs = GasSchedule::parse_costs(gas_schedule)?;
+
     let mut parsed_env: Env = serde_json::from_slice(env).map_err(|err| {
         warn!(
             "got an error while trying to deserialize env input bytes into json {:?}: {}",
             String::from_utf8_lossy(&env),
             err
         );
-        EnclaveError::FailedToDeserialize
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Env,
+        }
     })?;
 
     let canonical_contract_address = CanonicalAddr::from_human(&parsed_env.contract.address).map_err(|err| {
@@ -62,10 +74,18 @@ pub fn init(
             "got an error while trying to deserialize parsed_env.contract.address from bech32 string to bytes {:?}: {}",
             parsed_env.contract.address, err
         );
-        EnclaveError::FailedToDeserialize
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Address,
+        }
     })?;
-    let contract_key =
-        generate_encryption_key(&parsed_env, contract, &(canonical_contract_address.0).0)?;
+    let seed_epoch = extract_seed_epoch(&parsed_env)?;
+
+    let contract_key = generate_encryption_key(
+        &parsed_env,
+        contract,
+        &(canonical_contract_address.0).0,
+        seed_epoch,
+    )?;
     trace!("Init: Contract Key: {:?}", contract_key.to_vec().as_slice());
 
     let parsed_sig_info: SigInfo = serde_json::from_slice(sig_info).map_err(|err| {
@@ -74,10 +94,12 @@ pub fn init(
             String::from_utf8_lossy(&sig_info),
             err
         );
-        EnclaveError::FailedToDeserialize
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::SigInfo,
+        }
     })?;
 
-    let secret_msg = SecretMessage::from_slice(msg)?;
+    let mut secret_msg = SecretMessage::from_slice(msg)?;
     trace!(
         "Init input before decryption: {:?}",
         String::from_utf8_lossy(&msg)
@@ -85,7 +107,34 @@ pub fn init(
 
     verify_params(&parsed_sig_info, &parsed_env, &secret_msg)?;
 
-    let decrypted_msg = secret_msg.decrypt()?;
+    // The sender was just verified, and it's the admin of the contract from now on
+    let admin = CanonicalAddr::from_human(&parsed_env.message.sender).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize parsed_env.message.sender from bech32 string to bytes {:?}: {}",
+            parsed_env.message.sender, err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Address,
+        }
+    })?;
+    let consensus_state_ikm = get_consensus_state_ikm(seed_epoch)?;
+    let contract_hash = calc_contract_hash(contract);
+    // The key is only authenticated by itself in the genesis epoch, so it always gets a proof
+    let contract_key_proof = generate_contract_key_proof(
+        &consensus_state_ikm,
+        &contract_key,
+        &contract_hash,
+        &(canonical_contract_address.0).0,
+    );
+    let admin_proof =
+        generate_contract_admin_proof(&consensus_state_ikm, &contract_key, &contract_hash, &admin);
+
+    // The tx was verified, so the chain moved to this epoch
+    if !simulate {
+        KEY_MANAGER.activate_seed_epoch(seed_epoch)?;
+    }
+
+    let decrypted_msg = secret_msg.decrypt_any_epoch(seed_epoch)?;
 
     let validated_msg = validate_msg(&decrypted_msg, contract)?;
 
@@ -94,17 +143,35 @@ pub fn init(
         String::from_utf8_lossy(&validated_msg)
     );
 
+    // Simulations can't get random bytes, since they would tell what the call is going to get
+    let random_key = if simulate {
+        None
+    } else {
+        generate_random_key(
+            &parsed_env,
+            &parsed_sig_info,
+            &secret_msg,
+            &canonical_contract_address,
+        )?
+    };
+
     let mut engine = start_engine(
         context,
         gas_limit,
         contract,
         &contract_key,
         ContractOperation::Init,
+        wasm_costs,
         secret_msg.nonce,
         secret_msg.user_public_key,
+        secret_msg.io_epoch,
+        random_key,
+        seed_epoch,
     )?;
 
     parsed_env.contract_code_hash = hex::encode(calc_contract_hash(contract));
+    parsed_env.seed_epoch = None;
+    parsed_env.block_txs_hash = None;
 
     let new_env = serde_json::to_vec(&parsed_env).map_err(|err| {
         warn!(
@@ -125,12 +192,7 @@ pub fn init(
         // TODO: copy cosmwasm's structures to enclave
         // TODO: ref: https://github.com/CosmWasm/cosmwasm/blob/b971c037a773bf6a5f5d08a88485113d9b9e8e7b/packages/std/src/init_handle.rs#L129
         // TODO: ref: https://github.com/CosmWasm/cosmwasm/blob/b971c037a773bf6a5f5d08a88485113d9b9e8e7b/packages/std/src/query.rs#L13
-        let output = encrypt_output(
-            output,
-            secret_msg.nonce,
-            secret_msg.user_public_key,
-            &canonical_contract_address,
-        )?;
+        let output = encrypt_output(output, &secret_msg, &canonical_contract_address, seed_epoch)?;
 
         Ok(output)
     })
@@ -145,6 +207,8 @@ pub fn init(
     Ok(InitSuccess {
         output,
         contract_key,
+        contract_key_proof,
+        admin_proof,
     })
 }
 
@@ -152,17 +216,23 @@ pub fn handle(
     context: Ctx,
     gas_limit: u64,
     used_gas: &mut u64,
+    gas_schedule: &[u8],
     contract: &[u8],
     env: &[u8],
     msg: &[u8],
     sig_info: &[u8],
+    simulate: bool,
 ) -> Result<HandleSuccess, EnclaveError> {
+    let wasm_costs = GasSchedule::parse_costs(gas_schedule)?;
+
     let mut parsed_env: Env = serde_json::from_slice(env).map_err(|err| {
         warn!(
             "got an error while trying to deserialize env input bytes into json {:?}: {}",
             env, err
         );
-        EnclaveError::FailedToDeserialize
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Env,
+        }
     })?;
 
     trace!("handle parsed_env: {:?}", parsed_env);
@@ -173,18 +243,21 @@ pub fn handle(
             String::from_utf8_lossy(&sig_info),
             err
         );
-        EnclaveError::FailedToDeserialize
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::SigInfo,
+        }
     })?;
 
-    let secret_msg = SecretMessage::from_slice(msg)?;
+    let mut secret_msg = SecretMessage::from_slice(msg)?;
 
     // Verify env parameters against the signed tx
     verify_params(&parsed_sig_info, &parsed_env, &secret_msg)?;
 
     let contract_key = extract_contract_key(&parsed_env)?;
 
-    let secret_msg = SecretMessage::from_slice(msg)?;
-    let decrypted_msg = secret_msg.decrypt()?;
+    let seed_epoch = extract_seed_epoch(&parsed_env)?;
+
+    let decrypted_msg = secret_msg.decrypt_any_epoch(seed_epoch)?;
 
     let validated_msg = validate_msg(&decrypted_msg, contract)?;
 
@@ -198,32 +271,66 @@ pub fn handle(
             "got an error while trying to deserialize parsed_env.contract.address from bech32 string to bytes {:?}: {}",
             parsed_env.contract.address, err
         );
-        EnclaveError::FailedToDeserialize
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Address,
+        }
     })?;
 
-    if !validate_contract_key(&contract_key, &(canonical_contract_address.0).0, contract) {
+    let contract_key_proof = extract_contract_key_proof(&parsed_env)?;
+
+    if !validate_contract_key(
+        &contract_key,
+        &(canonical_contract_address.0).0,
+        contract,
+        contract_key_proof.as_ref(),
+        seed_epoch,
+    ) {
         warn!("got an error while trying to deserialize output bytes");
         return Err(EnclaveError::FailedContractAuthentication);
     }
 
     trace!("Successfully authenticated the contract!");
 
+    if !simulate {
+        KEY_MANAGER.activate_seed_epoch(seed_epoch)?;
+    }
+
     trace!(
         "Handle: Contract Key: {:?}",
         contract_key.to_vec().as_slice()
     );
 
+    // Simulations can't get random bytes, since they would tell what the call is going to get
+    let random_key = if simulate {
+        None
+    } else {
+        generate_random_key(
+            &parsed_env,
+            &parsed_sig_info,
+            &secret_msg,
+            &canonical_contract_address,
+        )?
+    };
+
     let mut engine = start_engine(
         context,
         gas_limit,
         contract,
         &contract_key,
         ContractOperation::Handle,
+        wasm_costs,
         secret_msg.nonce,
         secret_msg.user_public_key,
+        secret_msg.io_epoch,
+        random_key,
+        seed_epoch,
     )?;
 
     parsed_env.contract_code_hash = hex::encode(calc_contract_hash(contract));
+    parsed_env.seed_epoch = None;
+    parsed_env.block_txs_hash = None;
+    // The proof is meaningless to the contract, so we don't pass it on
+    parsed_env.contract_key_proof = None;
 
     let new_env = serde_json::to_vec(&parsed_env).map_err(|err| {
         warn!(
@@ -247,12 +354,7 @@ pub fn handle(
             "(2) nonce just before encrypt_output: nonce = {:?} pubkey = {:?}",
             secret_msg.nonce, secret_msg.user_public_key
         );
-        let output = encrypt_output(
-            output,
-            secret_msg.nonce,
-            secret_msg.user_public_key,
-            &canonical_contract_address,
-        )?;
+        let output = encrypt_output(output, &secret_msg, &canonical_contract_address, seed_epoch)?;
         Ok(output)
     })
     .map_err(|err| {
@@ -261,16 +363,22 @@ pub fn handle(
     })?;
 
     *used_gas = engine.gas_used();
-    Ok(HandleSuccess { output })
+    Ok(HandleSuccess {
+        output,
+        gas_profile: engine.gas_profile(),
+    })
 }
 
 pub fn query(
     context: Ctx,
     gas_limit: u64,
     used_gas: &mut u64,
+    gas_schedule: &[u8],
     contract: &[u8],
     msg: &[u8],
 ) -> Result<QuerySuccess, EnclaveError> {
+    let wasm_costs = GasSchedule::parse_costs(gas_schedule)?;
+
     if msg.len() < CONTRACT_KEY_LENGTH {
         warn!("Input query is shorter than the minimum expected. Msg is malformed");
         return Err(EnclaveError::FailedFunctionCall);
@@ -286,13 +394,21 @@ pub fn query(
         contract_key.to_vec().as_slice()
     );
 
-    let secret_msg = SecretMessage::from_slice(msg)?;
-    let decrypted_msg = secret_msg.decrypt()?;
+    // Queries don't get the epoch from the chain, but they don't write or send messages either,
+    // so any epoch this node has is fine for reading the query
+    let max_io_epoch = KEY_MANAGER.current_seed_epoch().map_err(|_err| {
+        warn!("Error extracting the current seed epoch");
+        EnclaveError::FailedUnseal
+    })?;
+
+    let mut secret_msg = SecretMessage::from_slice(msg)?;
+    let decrypted_msg = secret_msg.decrypt_any_epoch(max_io_epoch)?;
     trace!(
         "Query input afer decryption: {:?}",
         String::from_utf8_lossy(&decrypted_msg)
     );
     let validated_msg = validate_msg(&decrypted_msg, contract)?;
+    let validated_msg = verify_query_permit(&validated_msg, KEY_MANAGER.get_chain_id_hash().ok())?;
 
     let mut engine = start_engine(
         context,
@@ -300,8 +416,13 @@ pub fn query(
         contract,
         &contract_key,
         ContractOperation::Query,
+        wasm_costs,
         secret_msg.nonce,
         secret_msg.user_public_key,
+        secret_msg.io_epoch,
+        None,
+        // Queries never write, and reads don't depend on the epoch
+        0,
     )?;
 
     let msg_ptr = engine.write_to_memory(&validated_msg)?;
@@ -315,9 +436,9 @@ pub fn query(
 
         let output = encrypt_output(
             output,
-            secret_msg.nonce,
-            secret_msg.user_public_key,
+            &secret_msg,
             &CanonicalAddr(Binary(Vec::new())), // Not used for queries
+            0,                                  // Queries can't send messages
         )?;
         Ok(output)
     })
@@ -327,51 +448,322 @@ pub fn query(
     })?;
 
     *used_gas = engine.gas_used();
-    Ok(QuerySuccess { output })
+    Ok(QuerySuccess {
+        output,
+        gas_profile: engine.gas_profile(),
+    })
 }
 
-fn start_engine(
+pub fn migrate(
     context: Ctx,
     gas_limit: u64,
-    contract: &[u8],
-    contract_key: &ContractKey,
-    operation: ContractOperation,
-    nonce: IoNonce,
-    user_public_key: Ed25519PublicKey,
-) -> Result<Engine, EnclaveError> {
-    info!("Deserializing Wasm contract");
+    used_gas: &mut u64,
+    gas_schedule: &[u8],
+    contract: &[u8], // the wasm bytes of the new code
+    env: &[u8],      // contract_code_hash is the hash of the code the contract is migrating from
+    msg: &[u8],
+    sig_info: &[u8],
+) -> Result<MigrateSuccess, EnclaveError> {
+    let wasm_costs = GasSchedule::parse_costs(gas_schedule)?;
 
-    // Create a parity-wasm module first, so we can inject gas metering to it
-    // (you need a parity-wasm module to use the pwasm-utils crate)
-    let mut p_modlue: Module =
-        elements::deserialize_buffer(contract).map_err(|_| EnclaveError::InvalidWasm)?;
+    let mut parsed_env: Env = serde_json::from_slice(env).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize env input bytes into json {:?}: {}",
+            env, err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Env,
+        }
+    })?;
 
-    info!("Deserialized Wasm contract");
+    trace!("migrate parsed_env: {:?}", parsed_env);
 
-    info!("Validating WASM memory demands");
+    let parsed_sig_info: SigInfo = serde_json::from_slice(sig_info).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize env input bytes into json {:?}: {}",
+            String::from_utf8_lossy(&sig_info),
+            err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::SigInfo,
+        }
+    })?;
 
-    validate_memory(&mut p_modlue)?;
+    let mut secret_msg = SecretMessage::from_slice(msg)?;
 
-    info!("Validated WASM memory demands");
+    // Only the admin can migrate a contract, so the sender must have signed the tx itself.
+    // A callback signature only shows that some contract sent the same message.
+    if parsed_sig_info.callback_sig.is_some() {
+        warn!("Contract migrate with a callback signature");
+        return Err(EnclaveError::UnauthorizedMigration);
+    }
 
-    // Set the gas costs for wasm op-codes (there is an inline stack_height limit in WasmCosts)
-    let wasm_costs = WasmCosts::default();
+    // Verify env parameters against the signed tx
+    verify_params(&parsed_sig_info, &parsed_env, &secret_msg)?;
 
-    // Inject gas metering to pwasm module
-    let contract_module = pwasm_utils::inject_gas_counter(p_modlue, &gas_rules(&wasm_costs))
-        .map_err(|_| EnclaveError::FailedGasMeteringInjection)?;
+    let contract_key = extract_contract_key(&parsed_env)?;
+    let contract_key_proof = extract_contract_key_proof(&parsed_env)?;
+    let admin_proof = extract_contract_admin_proof(&parsed_env)?;
 
-    info!("Trying to create Wasmi module from parity...");
+    let canonical_contract_address = CanonicalAddr::from_human(&parsed_env.contract.address).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize parsed_env.contract.address from bech32 string to bytes {:?}: {}",
+            parsed_env.contract.address, err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Address,
+        }
+    })?;
 
-    // Create a wasmi module from the parity module
-    let module = wasmi::Module::from_parity_wasm_module(contract_module)
-        .map_err(|_err| EnclaveError::InvalidWasm)?;
+    let old_contract_hash = extract_contract_code_hash(&parsed_env)?;
 
-    info!("Created Wasmi module from parity. Now checking for floating points...");
+    let seed_epoch = extract_seed_epoch(&parsed_env)?;
 
-    module
-        .deny_floating_point()
-        .map_err(|_err| EnclaveError::WasmModuleWithFP)?;
+    let consensus_state_ikm = get_consensus_state_ikm(seed_epoch)?;
+
+    // The admin proof was issued by the enclave for the admin and the code the contract runs now,
+    // so it authenticates both the signer of the tx and the code hash the host sent us
+    let sender = CanonicalAddr::from_human(&parsed_env.message.sender).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize parsed_env.message.sender from bech32 string to bytes {:?}: {}",
+            parsed_env.message.sender, err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Address,
+        }
+    })?;
+    let expected_admin_proof = generate_contract_admin_proof(
+        &consensus_state_ikm,
+        &contract_key,
+        &old_contract_hash,
+        &sender,
+    );
+    if expected_admin_proof != admin_proof {
+        warn!("Contract migrate by someone other than its admin, or from other code");
+        return Err(EnclaveError::UnauthorizedMigration);
+    }
+
+    // The key is authenticated against the code the contract is running now, so we know
+    // that the host didn't hand us the key of some other contract
+    if !validate_contract_key_for_code_hash(
+        &contract_key,
+        &(canonical_contract_address.0).0,
+        &old_contract_hash,
+        contract_key_proof.as_ref(),
+        seed_epoch,
+    ) {
+        warn!("got an error while trying to authenticate the migrated contract");
+        return Err(EnclaveError::FailedContractAuthentication);
+    }
+
+    trace!("Successfully authenticated the contract!");
+
+    KEY_MANAGER.activate_seed_epoch(seed_epoch)?;
+
+    let decrypted_msg = secret_msg.decrypt_any_epoch(seed_epoch)?;
+
+    let validated_msg = validate_msg(&decrypted_msg, contract)?;
+
+    trace!(
+        "Migrate input afer decryption: {:?}",
+        String::from_utf8_lossy(&validated_msg)
+    );
+
+    // The contract keeps its key so it can still read the state it wrote before the migration.
+    // We bind the key to the new code instead of re-deriving it.
+    let new_contract_hash = calc_contract_hash(contract);
+    let new_contract_key_proof = generate_contract_key_proof(
+        &consensus_state_ikm,
+        &contract_key,
+        &new_contract_hash,
+        &(canonical_contract_address.0).0,
+    );
+    let new_admin_proof = generate_contract_admin_proof(
+        &consensus_state_ikm,
+        &contract_key,
+        &new_contract_hash,
+        &sender,
+    );
+
+    let random_key = generate_random_key(
+        &parsed_env,
+        &parsed_sig_info,
+        &secret_msg,
+        &canonical_contract_address,
+    )?;
+
+    let mut engine = start_engine(
+        context,
+        gas_limit,
+        contract,
+        &contract_key,
+        ContractOperation::Migrate,
+        wasm_costs,
+        secret_msg.nonce,
+        secret_msg.user_public_key,
+        secret_msg.io_epoch,
+        random_key,
+        seed_epoch,
+    )?;
+
+    parsed_env.contract_code_hash = hex::encode(new_contract_hash);
+    parsed_env.seed_epoch = None;
+    parsed_env.block_txs_hash = None;
+    // The proofs are meaningless to the contract, so we don't pass them on
+    parsed_env.contract_key_proof = None;
+    parsed_env.contract_admin_proof = None;
+
+    let new_env = serde_json::to_vec(&parsed_env).map_err(|err| {
+        warn!(
+            "got an error while trying to serialize parsed_env into bytes {:?}: {}",
+            parsed_env, err
+        );
+        EnclaveError::FailedToSerialize
+    })?;
+
+    let env_ptr = engine.write_to_memory(&new_env)?;
+    let msg_ptr = engine.write_to_memory(&validated_msg)?;
+
+    // This wrapper is used to coalesce all errors in this block to one object
+    // so we can `.map_err()` in one place for all of them
+    let output = coalesce!(EnclaveError, {
+        let vec_ptr = engine.migrate(env_ptr, msg_ptr)?;
+
+        let output = engine.extract_vector(vec_ptr)?;
+
+        let output = encrypt_output(output, &secret_msg, &canonical_contract_address, seed_epoch)?;
+        Ok(output)
+    })
+    .map_err(|err| {
+        *used_gas = engine.gas_used();
+        err
+    })?;
+
+    *used_gas = engine.gas_used();
+    Ok(MigrateSuccess {
+        output,
+        contract_key_proof: new_contract_key_proof,
+        admin_proof: new_admin_proof,
+    })
+}
+
+/// Issues the proofs of a contract again, for a new epoch of the consensus seed.
+///
+/// `env` holds the contract and its proofs of the previous epoch, `message.sender` is the admin
+/// of the contract, and `seed_epoch` is the new epoch. `extract_seed_epoch` only accepts the active
+/// epoch or a newer one, so once the chain moved past an epoch, proofs of the epoch before it can't
+/// be carried over anymore, even if its seed leaks.
+pub fn reissue_contract_proofs(env: &[u8]) -> Result<ReissueSuccess, EnclaveError> {
+    let parsed_env: Env = serde_json::from_slice(env).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize env input bytes into json {:?}: {}",
+            env, err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Env,
+        }
+    })?;
+
+    let seed_epoch = extract_seed_epoch(&parsed_env)?;
+    if seed_epoch == 0 {
+        warn!("Can't issue contract proofs again for the genesis epoch");
+        return Err(EnclaveError::SeedEpochOutdated { epoch: seed_epoch });
+    }
+    let previous_epoch = seed_epoch - 1;
+
+    let contract_key = extract_contract_key(&parsed_env)?;
+    let contract_key_proof = extract_contract_key_proof(&parsed_env)?;
+    let contract_hash = extract_contract_code_hash(&parsed_env)?;
+
+    let canonical_contract_address = CanonicalAddr::from_human(&parsed_env.contract.address).map_err(|err| {
+        warn!(
+            "got an error while trying to deserialize parsed_env.contract.address from bech32 string to bytes {:?}: {}",
+            parsed_env.contract.address, err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Address,
+        }
+    })?;
+
+    if !validate_contract_key_for_code_hash(
+        &contract_key,
+        &(canonical_contract_address.0).0,
+        &contract_hash,
+        contract_key_proof.as_ref(),
+        previous_epoch,
+    ) {
+        warn!("got an error while trying to authenticate the contract in the previous epoch");
+        return Err(EnclaveError::FailedContractAuthentication);
+    }
+
+    let previous_state_ikm = get_consensus_state_ikm(previous_epoch)?;
+    let consensus_state_ikm = get_consensus_state_ikm(seed_epoch)?;
+
+    let new_contract_key_proof = generate_contract_key_proof(
+        &consensus_state_ikm,
+        &contract_key,
+        &contract_hash,
+        &(canonical_contract_address.0).0,
+    );
+
+    // Contracts that were instantiated before there were admin proofs don't have one
+    let new_admin_proof = match parsed_env.contract_admin_proof {
+        None => [0u8; HASH_SIZE],
+        Some(_) => {
+            let admin_proof = extract_contract_admin_proof(&parsed_env)?;
+            let admin = CanonicalAddr::from_human(&parsed_env.message.sender).map_err(|err| {
+                warn!(
+                    "got an error while trying to deserialize parsed_env.message.sender from bech32 string to bytes {:?}: {}",
+                    parsed_env.message.sender, err
+                );
+                EnclaveError::FailedToDeserialize {
+                    target: DeserializationTarget::Address,
+                }
+            })?;
+
+            let expected_admin_proof = generate_contract_admin_proof(
+                &previous_state_ikm,
+                &contract_key,
+                &contract_hash,
+                &admin,
+            );
+            if expected_admin_proof != admin_proof {
+                warn!("got an admin proof of someone other than the admin of the contract");
+                return Err(EnclaveError::FailedContractAuthentication);
+            }
+
+            generate_contract_admin_proof(
+                &consensus_state_ikm,
+                &contract_key,
+                &contract_hash,
+                &admin,
+            )
+        }
+    };
+
+    Ok(ReissueSuccess {
+        contract_key_proof: new_contract_key_proof,
+        admin_proof: new_admin_proof,
+    })
+}
+
+fn start_engine(
+    context: Ctx,
+    gas_limit: u64,
+    contract: &[u8],
+    contract_key: &ContractKey,
+    operation: ContractOperation,
+    wasm_costs: WasmCosts,
+    nonce: IoNonce,
+    user_public_key: Ed25519PublicKey,
+    io_epoch: u32,
+    random_key: Option<AESKey>,
+    seed_epoch: u32,
+) -> Result<Engine, EnclaveError> {
+    let code_hash = calc_contract_hash(contract);
+    let module = module_cache::get_or_prepare(code_hash, &wasm_costs, || {
+        prepare_module(contract, &wasm_costs)
+    })?;
 
     // Create new imports resolver.
     // These are the signatures of rust functions available to invoke from wasm code.
@@ -379,7 +771,7 @@ fn start_engine(
     let imports_builder = create_builder(&resolver);
 
     // Instantiate a module with our imports and assert that there is no `start` function.
-    let module_instance = ModuleInstance::new(&module, &imports_builder).map_err(|err| {
+    let module_instance = ModuleInstance::new(&*module, &imports_builder).map_err(|err| {
         warn!("Error in instantiation: {:?}", err);
         EnclaveError::InvalidWasm
     })?;
@@ -397,7 +789,46 @@ fn start_engine(
         operation,
         nonce,
         user_public_key,
+        io_epoch,
+        random_key,
+        seed_epoch,
     );
 
     Ok(Engine::new(contract_instance, module))
 }
+
+/// Deserializes and validates the contract, and injects gas metering into it
+fn prepare_module(contract: &[u8], wasm_costs: &WasmCosts) -> Result<wasmi::Module, EnclaveError> {
+    info!("Deserializing Wasm contract");
+
+    // Create a parity-wasm module first, so we can inject gas metering to it
+    // (you need a parity-wasm module to use the pwasm-utils crate)
+    let mut p_modlue: Module =
+        elements::deserialize_buffer(contract).map_err(|_| EnclaveError::InvalidWasm)?;
+
+    info!("Deserialized Wasm contract");
+
+    info!("Validating WASM memory demands");
+
+    validate_memory(&mut p_modlue)?;
+
+    info!("Validated WASM memory demands");
+
+    // Inject gas metering to pwasm module
+    let contract_module = pwasm_utils::inject_gas_counter(p_modlue, &gas_rules(wasm_costs))
+        .map_err(|_| EnclaveError::FailedGasMeteringInjection)?;
+
+    info!("Trying to create Wasmi module from parity...");
+
+    // Create a wasmi module from the parity module
+    let module = wasmi::Module::from_parity_wasm_module(contract_module)
+        .map_err(|_err| EnclaveError::InvalidWasm)?;
+
+    info!("Created Wasmi module from parity. Now checking for floating points...");
+
+    module
+        .deny_floating_point()
+        .map_err(|_err| EnclaveError::WasmModuleWithFP)?;
+
+    Ok(module)
+}
@@ -1,14 +1,18 @@
 use log::*;
 
 use crate::cosmwasm::encoding::Binary;
+use crate::cosmwasm::sign_doc_direct;
 use crate::cosmwasm::types::{
-    CanonicalAddr, Coin, CosmosSignature, Env, SigInfo, SignDoc, SignDocWasmMsg,
+    CanonicalAddr, Coin, CosmosSignature, Env, HumanAddr, PermitParams, QueryPermit,
+    QueryWithPermit, SigInfo, SignDoc, SignDocWasmMsg, SignMode,
 };
 use crate::crypto::traits::PubKey;
 use crate::crypto::{sha_256, AESKey, Hmac, Kdf, HASH_SIZE, KEY_MANAGER};
 use crate::wasm::io;
 use crate::wasm::types::SecretMessage;
-use enclave_ffi_types::EnclaveError;
+use enclave_ffi_types::{DeserializationTarget, EnclaveError};
+use serde::Deserialize;
+use serde_json::{json, Value};
 
 pub type ContractKey = [u8; CONTRACT_KEY_LENGTH];
 
@@ -16,12 +20,23 @@ pub const CONTRACT_KEY_LENGTH: usize = HASH_SIZE + HASH_SIZE;
 
 const HEX_ENCODED_HASH_SIZE: usize = HASH_SIZE * 2;
 
+const CONTRACT_KEY_PROOF_SALT: &[u8] = b"contract_key_proof";
+
+const CONTRACT_ADMIN_PROOF_SALT: &[u8] = b"contract_admin_proof";
+
+const RANDOM_KEY_SALT: &[u8] = b"contract_random_key";
+
+/// Queries that are sent with a permit are wrapped in an object with this single key.
+/// The key is reserved, so the enclave never takes over a query a contract defined itself
+const QUERY_PERMIT_KEY: &str = "__secret_with_permit";
+
 pub fn generate_encryption_key(
     env: &Env,
     contract: &[u8],
     contract_address: &[u8],
+    seed_epoch: u32,
 ) -> Result<[u8; CONTRACT_KEY_LENGTH], EnclaveError> {
-    let consensus_state_ikm = KEY_MANAGER.get_consensus_state_ikm().unwrap();
+    let consensus_state_ikm = get_consensus_state_ikm(seed_epoch)?;
 
     let contract_hash = calc_contract_hash(contract);
 
@@ -30,7 +45,9 @@ pub fn generate_encryption_key(
             "got an error while trying to deserialize env.message.sender from bech32 string to bytes {:?}: {}",
             env.message.sender, err
         );
-        EnclaveError::FailedToDeserialize
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Address,
+        }
     })?;
     let snder_address: Vec<u8> = sender_address_u5.iter().map(|x| x.to_u8()).collect();
 
@@ -78,6 +95,78 @@ pub fn extract_contract_key(env: &Env) -> Result<[u8; CONTRACT_KEY_LENGTH], Encl
     Ok(key_as_bytes)
 }
 
+/// Derives the key that the `random` import of a contract derives its output from. Returns `None`
+/// if the env doesn't have the hash of the transactions of the block, which the chain only sets
+/// when the call is included in a block.
+///
+/// The key is derived from a secret of the consensus seed, so it is the same on all the validators
+/// but can't be computed without an enclave. It also depends on what the call was authenticated
+/// with by `verify_params`: the signed transaction, or the callback signature of the calling
+/// contract, and the message itself. So every call gets its own key, even within a block, and the
+/// host can't choose it.
+///
+/// Operators have an enclave, so they could simulate a call with everything else to learn its
+/// random bytes before sending it. The transactions of the block are only fixed once the call was
+/// included, so only the proposer of the block knows them beforehand.
+pub fn generate_random_key(
+    env: &Env,
+    sig_info: &SigInfo,
+    msg: &SecretMessage,
+    contract_address: &CanonicalAddr,
+) -> Result<Option<AESKey>, EnclaveError> {
+    let block_txs_hash = match &env.block_txs_hash {
+        Some(block_txs_hash) => block_txs_hash,
+        None => return Ok(None),
+    };
+
+    let random_secret = KEY_MANAGER
+        .get_consensus_random_secret_of_epoch(extract_seed_epoch(env)?)
+        .map_err(|_err| {
+            warn!("Error extracting consensus_random_secret");
+            EnclaveError::FailedUnseal
+        })?;
+
+    Ok(Some(derive_random_key(
+        &random_secret,
+        env,
+        block_txs_hash.as_slice(),
+        sig_info,
+        msg,
+        contract_address,
+    )))
+}
+
+fn derive_random_key(
+    random_secret: &AESKey,
+    env: &Env,
+    block_txs_hash: &[u8],
+    sig_info: &SigInfo,
+    msg: &SecretMessage,
+    contract_address: &CanonicalAddr,
+) -> AESKey {
+    let mut data = RANDOM_KEY_SALT.to_vec();
+    data.extend_from_slice(&sha_256(env.block.chain_id.as_bytes()));
+    data.extend_from_slice(&env.block.height.to_be_bytes());
+    data.extend_from_slice(&env.block.time.to_be_bytes());
+    data.extend_from_slice(&sha_256(block_txs_hash));
+    data.extend_from_slice(&sha_256(contract_address.as_slice()));
+
+    // The entropy of the call. Transactions are unique per signer, because of their sequence,
+    // and callbacks are signed over the message of the calling contract
+    match &sig_info.callback_sig {
+        Some(callback_sig) => data.extend_from_slice(&sha_256(callback_sig.as_slice())),
+        None => {
+            data.extend_from_slice(&sha_256(sig_info.sign_bytes.as_slice()));
+            data.extend_from_slice(&sha_256(sig_info.signature.get_signature().as_slice()));
+        }
+    }
+    data.extend_from_slice(&msg.nonce);
+    data.extend_from_slice(&msg.user_public_key);
+    data.extend_from_slice(&sha_256(&msg.msg));
+
+    random_secret.derive_key_from_this(&data)
+}
+
 pub fn generate_sender_id(msg_sender: &[u8], block_height: u64) -> [u8; HASH_SIZE] {
     let mut input_data = msg_sender.to_vec();
     input_data.extend_from_slice(&block_height.to_be_bytes());
@@ -106,6 +195,58 @@ pub fn validate_contract_key(
     contract_key: &[u8; CONTRACT_KEY_LENGTH],
     contract_address: &[u8],
     contract_code: &[u8],
+    contract_key_proof: Option<&[u8; HASH_SIZE]>,
+    seed_epoch: u32,
+) -> bool {
+    // calculate contract hash
+    let contract_hash = calc_contract_hash(contract_code);
+
+    validate_contract_key_for_code_hash(
+        contract_key,
+        contract_address,
+        &contract_hash,
+        contract_key_proof,
+        seed_epoch,
+    )
+}
+
+/// Validates that the contract key was generated by the enclave for this contract address and code hash.
+/// A contract that was migrated keeps the key it was instantiated with, so for it we also accept
+/// a proof (generated by the enclave during migration) that binds the original key to the new code hash.
+///
+/// Keys are authenticated with the state key of `seed_epoch`. The authentication code inside the
+/// key is only accepted in the genesis epoch, since it can't be re-issued. In later epochs the key
+/// needs a proof of that epoch, which is issued at init and migrate, and when the epoch changes.
+pub fn validate_contract_key_for_code_hash(
+    contract_key: &[u8; CONTRACT_KEY_LENGTH],
+    contract_address: &[u8],
+    contract_hash: &[u8; HASH_SIZE],
+    contract_key_proof: Option<&[u8; HASH_SIZE]>,
+    seed_epoch: u32,
+) -> bool {
+    // get the enclave key
+    let enclave_key = match get_consensus_state_ikm(seed_epoch) {
+        Ok(enclave_key) => enclave_key,
+        Err(_) => return false,
+    };
+
+    authenticate_contract_key(
+        &enclave_key,
+        contract_key,
+        contract_address,
+        contract_hash,
+        contract_key_proof,
+        seed_epoch == 0,
+    )
+}
+
+fn authenticate_contract_key(
+    enclave_key: &AESKey,
+    contract_key: &[u8; CONTRACT_KEY_LENGTH],
+    contract_address: &[u8],
+    contract_hash: &[u8; HASH_SIZE],
+    contract_key_proof: Option<&[u8; HASH_SIZE]>,
+    is_genesis_epoch: bool,
 ) -> bool {
     // parse contract key -> < signer_id || authentication_code >
     let mut signer_id: [u8; HASH_SIZE] = [0u8; HASH_SIZE];
@@ -114,23 +255,162 @@ pub fn validate_contract_key(
     let mut expected_authentication_id: [u8; HASH_SIZE] = [0u8; HASH_SIZE];
     expected_authentication_id.copy_from_slice(&contract_key[HASH_SIZE..]);
 
-    // calculate contract hash
-    let contract_hash = calc_contract_hash(contract_code);
+    if is_genesis_epoch {
+        // calculate the authentication_id
+        let calculated_authentication_id =
+            generate_contract_id(enclave_key, &signer_id, contract_hash, contract_address);
 
-    // get the enclave key
-    let enclave_key = KEY_MANAGER
-        .get_consensus_state_ikm()
+        if calculated_authentication_id == expected_authentication_id {
+            return true;
+        }
+    }
+
+    match contract_key_proof {
+        Some(proof) => {
+            let calculated_proof = generate_contract_key_proof(
+                enclave_key,
+                contract_key,
+                contract_hash,
+                contract_address,
+            );
+            calculated_proof == *proof
+        }
+        None => false,
+    }
+}
+
+/// Binds an existing contract key to a new code hash. This is generated when a contract is migrated,
+/// and allows the contract to keep its key (and therefore its encrypted state) after the code changes.
+pub fn generate_contract_key_proof(
+    consensus_state_ikm: &AESKey,
+    contract_key: &[u8; CONTRACT_KEY_LENGTH],
+    code_hash: &[u8; HASH_SIZE],
+    contract_address: &[u8],
+) -> [u8; HASH_SIZE] {
+    let proof_key = consensus_state_ikm.derive_key_from_this(CONTRACT_KEY_PROOF_SALT);
+
+    let mut input_data = contract_key.to_vec();
+    input_data.extend_from_slice(code_hash);
+    input_data.extend_from_slice(contract_address);
+    proof_key.sign_sha_256(&input_data)
+}
+
+pub fn extract_contract_key_proof(env: &Env) -> Result<Option<[u8; HASH_SIZE]>, EnclaveError> {
+    match &env.contract_key_proof {
+        None => Ok(None),
+        Some(proof) => decode_proof(proof).map(Some),
+    }
+}
+
+/// Generates the proof that `admin` may migrate the contract with this key, while the contract
+/// runs the code with `code_hash`.
+/// The proof is issued when the contract is instantiated, and again for the new code every time the
+/// contract is migrated. So it also tells migrate which code the contract is migrating from.
+pub fn generate_contract_admin_proof(
+    consensus_state_ikm: &AESKey,
+    contract_key: &[u8; CONTRACT_KEY_LENGTH],
+    code_hash: &[u8; HASH_SIZE],
+    admin: &CanonicalAddr,
+) -> [u8; HASH_SIZE] {
+    let proof_key = consensus_state_ikm.derive_key_from_this(CONTRACT_ADMIN_PROOF_SALT);
+
+    let mut input_data = contract_key.to_vec();
+    input_data.extend_from_slice(code_hash);
+    input_data.extend_from_slice(admin.as_slice());
+    proof_key.sign_sha_256(&input_data)
+}
+
+pub fn extract_contract_admin_proof(env: &Env) -> Result<[u8; HASH_SIZE], EnclaveError> {
+    match &env.contract_admin_proof {
+        None => {
+            warn!("Contract migrate without an admin proof");
+            Err(EnclaveError::FailedContractAuthentication)
+        }
+        Some(proof) => decode_proof(proof),
+    }
+}
+
+/// The hash of the code the contract runs, as the host passed it in `env`
+pub fn extract_contract_code_hash(env: &Env) -> Result<[u8; HASH_SIZE], EnclaveError> {
+    let code_hash = hex::decode(&env.contract_code_hash).map_err(|err| {
+        warn!(
+            "got an error while trying to decode the code hash of the contract {:?}: {}",
+            env.contract_code_hash, err
+        );
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::CodeHash,
+        }
+    })?;
+    if code_hash.len() != HASH_SIZE {
+        warn!("Contract code hash has the wrong length");
+        return Err(EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::CodeHash,
+        });
+    }
+
+    let mut code_hash_as_bytes = [0u8; HASH_SIZE];
+    code_hash_as_bytes.copy_from_slice(&code_hash);
+
+    Ok(code_hash_as_bytes)
+}
+
+fn decode_proof(proof: &str) -> Result<[u8; HASH_SIZE], EnclaveError> {
+    let decoded = base64::decode(proof.as_bytes()).map_err(|err| {
+        warn!(
+            "got an error while trying to decode contract proof {:?}: {}",
+            proof, err
+        );
+        EnclaveError::FailedContractAuthentication
+    })?;
+
+    if decoded.len() != HASH_SIZE {
+        warn!("Contract proof has the wrong length: {}", decoded.len());
+        return Err(EnclaveError::FailedContractAuthentication);
+    }
+
+    let mut proof_as_bytes = [0u8; HASH_SIZE];
+    proof_as_bytes.copy_from_slice(&decoded);
+
+    Ok(proof_as_bytes)
+}
+
+/// Returns the epoch of the consensus seed that new state should be encrypted with.
+///
+/// The epoch comes from the host, so it's only accepted if this enclave has its seed, and if it
+/// isn't older than the active epoch (see `Keychain::activate_seed_epoch`). Otherwise the host
+/// could make the enclave keep using the keys of an epoch the chain has moved on from.
+pub fn extract_seed_epoch(env: &Env) -> Result<u32, EnclaveError> {
+    let epoch = env.seed_epoch.unwrap_or(0);
+
+    // Every node must have the seed of an epoch before the chain switches to it
+    if KEY_MANAGER.get_consensus_state_ikm_of_epoch(epoch).is_err() {
+        warn!(
+            "The chain uses epoch {} of the consensus seed, which this node doesn't have",
+            epoch
+        );
+        return Err(EnclaveError::SeedEpochUnavailable { epoch });
+    }
+
+    let active_epoch = KEY_MANAGER.active_seed_epoch();
+    if epoch < active_epoch {
+        warn!(
+            "Got epoch {} of the consensus seed, but the chain already uses epoch {}",
+            epoch, active_epoch
+        );
+        return Err(EnclaveError::SeedEpochOutdated { epoch });
+    }
+
+    Ok(epoch)
+}
+
+/// The state key of an epoch, which contract keys and their proofs are authenticated with
+pub fn get_consensus_state_ikm(seed_epoch: u32) -> Result<AESKey, EnclaveError> {
+    KEY_MANAGER
+        .get_consensus_state_ikm_of_epoch(seed_epoch)
         .map_err(|_err| {
             warn!("Error extracting consensus_state_key");
-            false
+            EnclaveError::FailedUnseal
         })
-        .unwrap();
-
-    // calculate the authentication_id
-    let calculated_authentication_id =
-        generate_contract_id(&enclave_key, &signer_id, &contract_hash, contract_address);
-
-    calculated_authentication_id == expected_authentication_id
 }
 
 pub fn validate_msg(msg: &[u8], contract_code: &[u8]) -> Result<Vec<u8>, EnclaveError> {
@@ -157,6 +437,125 @@ pub fn validate_msg(msg: &[u8], contract_code: &[u8]) -> Result<Vec<u8>, Enclave
     Ok(msg[HEX_ENCODED_HASH_SIZE..].to_vec())
 }
 
+/// Verifies the permit of a query that was sent with one, and returns the query the contract gets.
+///
+/// A query with a permit looks like `{"__secret_with_permit":{"query":<query>,"permit":<QueryPermit>}}`.
+/// The contract gets `{"__secret_with_permit":<QueryWithPermit>}` instead, so it can trust the
+/// `signer` field. The enclave doesn't know which contract is queried, so checking
+/// `allowed_contracts`, and revoking permits by their name, is left to the contract.
+///
+/// `chain_id_hash` is the hash of the id of the chain this enclave belongs to, and permits of any
+/// other chain are rejected. Without it no permit is accepted.
+///
+/// Any other query is returned as is.
+pub fn verify_query_permit(
+    msg: &[u8],
+    chain_id_hash: Option<[u8; HASH_SIZE]>,
+) -> Result<Vec<u8>, EnclaveError> {
+    let query: Value = match serde_json::from_slice(msg) {
+        Ok(query) => query,
+        Err(_) => return Ok(msg.to_vec()),
+    };
+    let is_permit_query = query
+        .as_object()
+        .map_or(false, |query| query.contains_key(QUERY_PERMIT_KEY));
+    if !is_permit_query {
+        return Ok(msg.to_vec());
+    }
+
+    // Anything that looks like it carries a permit must carry a valid one, otherwise a
+    // contract could be tricked into trusting a `signer` field that was never verified
+    let envelope: PermitEnvelope = serde_json::from_value(query).map_err(|err| {
+        warn!("Got a query with a malformed permit: {}", err);
+        EnclaveError::FailedQueryPermitVerification
+    })?;
+    let PermitQuery { query, permit } = envelope.with_permit;
+
+    // The permit is signed over its chain id, but that only matters if it's the chain we're on
+    if chain_id_hash != Some(sha_256(permit.params.chain_id.as_bytes())) {
+        warn!(
+            "Got a query permit for another chain: {}",
+            permit.params.chain_id
+        );
+        return Err(EnclaveError::FailedQueryPermitVerification);
+    }
+
+    let sign_bytes = permit_sign_bytes(&permit.params)?;
+    let public_key = permit.signature.get_public_key();
+    if let Err(err) =
+        public_key.verify_bytes(&sign_bytes, permit.signature.get_signature().as_slice())
+    {
+        warn!("Query permit signature verification failed: {:?}", err);
+        return Err(EnclaveError::FailedQueryPermitVerification);
+    }
+
+    let signer = HumanAddr::from_canonical(&public_key.get_address()).map_err(|err| {
+        warn!(
+            "Failed to encode the query permit signer as bech32: {}",
+            err
+        );
+        EnclaveError::FailedQueryPermitVerification
+    })?;
+    trace!("Verified a query permit signed by {}", signer);
+
+    let verified_query = json!({
+        QUERY_PERMIT_KEY: QueryWithPermit {
+            query,
+            signer,
+            permit_name: permit.params.permit_name,
+            allowed_contracts: permit.params.allowed_contracts,
+        }
+    });
+    serde_json::to_vec(&verified_query).map_err(|err| {
+        warn!("Failed to serialize the verified query permit: {}", err);
+        EnclaveError::FailedToSerialize
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PermitEnvelope {
+    #[serde(rename = "__secret_with_permit")]
+    with_permit: PermitQuery,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PermitQuery {
+    query: Value,
+    permit: QueryPermit,
+}
+
+/// Rebuilds the bytes the permit was signed over.
+///
+/// This is the sign doc of a transaction with a single `query_permit` msg and an empty fee,
+/// so permits can be signed by any wallet that can sign amino JSON.
+/// `serde_json` sorts object keys, which makes the output canonical.
+fn permit_sign_bytes(params: &PermitParams) -> Result<Vec<u8>, EnclaveError> {
+    let sign_doc = json!({
+        "account_number": "0",
+        "chain_id": params.chain_id,
+        "fee": {
+            "amount": [{ "amount": "0", "denom": "uscrt" }],
+            "gas": "1",
+        },
+        "memo": "",
+        "msgs": [{
+            "type": "query_permit",
+            "value": {
+                "allowed_contracts": params.allowed_contracts,
+                "permit_name": params.permit_name,
+            },
+        }],
+        "sequence": "0",
+    });
+
+    serde_json::to_vec(&sign_doc).map_err(|err| {
+        warn!("Failed to serialize the query permit sign doc: {}", err);
+        EnclaveError::FailedToSerialize
+    })
+}
+
 pub fn verify_params(
     sig_info: &SigInfo,
     env: &Env,
@@ -172,6 +571,7 @@ pub fn verify_params(
                 .or(Err(EnclaveError::FailedToSerialize))?,
             msg,
             &env.message.sent_funds,
+            extract_seed_epoch(env)?,
         ) {
             info!("Message verified! msg.sender is the calling contract");
             return Ok(());
@@ -184,17 +584,33 @@ pub fn verify_params(
             String::from_utf8_lossy(sig_info.sign_bytes.as_slice())
         );
 
-        let sign_doc: SignDoc =
-            serde_json::from_slice(sig_info.sign_bytes.as_slice()).map_err(|err| {
-                warn!(
-                    "got an error while trying to deserialize sign doc bytes into json {:?}: {}",
+        let signed_msgs = match sig_info.sign_mode {
+            SignMode::LegacyAminoJson => {
+                let sign_doc: SignDoc = serde_json::from_slice(sig_info.sign_bytes.as_slice())
+                    .map_err(|err| {
+                        warn!(
+                            "got an error while trying to deserialize sign doc bytes into json {:?}: {}",
+                            sig_info.sign_bytes.as_slice(),
+                            err
+                        );
+                        EnclaveError::FailedToDeserialize {
+                            target: DeserializationTarget::SignDoc,
+                        }
+                    })?;
+
+                trace!("sign doc: {:?}", sign_doc);
+                sign_doc.msgs
+            }
+            SignMode::Direct => {
+                let msgs = sign_doc_direct::decode_wasm_msgs(
                     sig_info.sign_bytes.as_slice(),
-                    err
-                );
-                EnclaveError::FailedToDeserialize
-            })?;
+                    &sig_info.signature.get_public_key(),
+                )?;
 
-        trace!("sign doc: {:?}", sign_doc);
+                trace!("direct sign doc msgs: {:?}", msgs);
+                msgs
+            }
+        };
 
         // This verifies that signatures and sign bytes are self consistent
         sig_info
@@ -209,7 +625,7 @@ pub fn verify_params(
                 EnclaveError::FailedTxVerification
             })?;
 
-        if verify_signature_params(&sign_doc, sig_info, env, msg) {
+        if verify_signature_params(&signed_msgs, sig_info, env, msg) {
             info!("Parameters verified successfully");
             return Ok(());
         }
@@ -225,12 +641,13 @@ fn verify_callback_sig(
     sender: &CanonicalAddr,
     msg: &SecretMessage,
     sent_funds: &[Coin],
+    seed_epoch: u32,
 ) -> bool {
     if callback_signature.is_empty() {
         return false;
     }
 
-    let callback_sig = io::create_callback_signature(sender, msg, sent_funds);
+    let callback_sig = io::create_callback_signature(sender, msg, sent_funds, seed_epoch);
 
     if !callback_signature.eq(callback_sig.as_slice()) {
         trace!(
@@ -255,11 +672,13 @@ fn verify_sender(signature: &CosmosSignature, msg_sender: &CanonicalAddr) -> boo
 }
 
 fn get_verified_msg<'a>(
-    sign_doc: &'a SignDoc,
+    signed_msgs: &'a [SignDocWasmMsg],
     sent_msg: &'a SecretMessage,
 ) -> Option<&'a SignDocWasmMsg> {
-    sign_doc.msgs.iter().find(|&m| match m {
-        SignDocWasmMsg::Execute { msg, .. } | SignDocWasmMsg::Instantiate { init_msg: msg, .. } => {
+    signed_msgs.iter().find(|&m| match m {
+        SignDocWasmMsg::Execute { msg, .. }
+        | SignDocWasmMsg::Instantiate { init_msg: msg, .. }
+        | SignDocWasmMsg::Migrate { msg, .. } => {
             let binary_msg_result = Binary::from_base64(msg);
             if let Ok(binary_msg) = binary_msg_result {
                 return Binary(sent_msg.to_vec()) == binary_msg;
@@ -271,8 +690,9 @@ fn get_verified_msg<'a>(
 }
 
 fn verify_contract(msg: &SignDocWasmMsg, env: &Env) -> bool {
-    // Contract address is relevant only to execute, since during sending an instantiate message the contract address is not yet known
-    if let SignDocWasmMsg::Execute { contract, .. } = msg {
+    // Contract address is relevant only to execute and migrate, since during sending an instantiate message the contract address is not yet known
+    if let SignDocWasmMsg::Execute { contract, .. } | SignDocWasmMsg::Migrate { contract, .. } = msg
+    {
         info!("Verifying contract address..");
         if env.contract.address != *contract {
             trace!(
@@ -294,11 +714,13 @@ fn verify_funds(msg: &SignDocWasmMsg, env: &Env) -> bool {
             init_funds: sent_funds,
             ..
         } => &env.message.sent_funds == sent_funds,
+        // Funds can't be sent along with a migrate message
+        SignDocWasmMsg::Migrate { .. } => env.message.sent_funds.is_empty(),
     }
 }
 
 fn verify_signature_params(
-    sign_doc: &SignDoc,
+    signed_msgs: &[SignDocWasmMsg],
     sig_info: &SigInfo,
     env: &Env,
     sent_msg: &SecretMessage,
@@ -324,13 +746,13 @@ fn verify_signature_params(
     info!("Verifying message..");
     // If msg is not found (is None) then it means message verification failed,
     // since it didn't find a matching signed message
-    let msg = get_verified_msg(sign_doc, sent_msg);
+    let msg = get_verified_msg(signed_msgs, sent_msg);
     if msg.is_none() {
         warn!("Message verification failed!");
         trace!(
             "Message sent to contract {:?} is not equal to any signed messages {:?}",
             sent_msg.to_vec(),
-            sign_doc.msgs
+            signed_msgs
         );
         return false;
     }
@@ -349,3 +771,338 @@ fn verify_signature_params(
 
     true
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+
+    fn permit_params() -> Value {
+        json!({
+            "permit_name": "balance",
+            "allowed_contracts": ["secret1contract"],
+            "chain_id": "secret-2",
+        })
+    }
+
+    fn sign_permit(params: &Value, secret_key: &[u8; 32]) -> Value {
+        let params: PermitParams = serde_json::from_value(params.clone()).unwrap();
+        let sign_bytes = permit_sign_bytes(&params).unwrap();
+
+        let secp = Secp256k1::signing_only();
+        let secret_key = SecretKey::from_slice(secret_key).unwrap();
+        let public_key = PublicKey::from_secret_key(&secp, &secret_key);
+        let message = Message::from_slice(&sha_256(&sign_bytes)).unwrap();
+        let signature = secp.sign(&message, &secret_key);
+
+        json!({
+            "pub_key": {
+                "type": "tendermint/PubKeySecp256k1",
+                "value": Binary(public_key.serialize().to_vec()),
+            },
+            "signature": Binary(signature.serialize_compact().to_vec()),
+        })
+    }
+
+    fn chain_id_hash() -> Option<[u8; HASH_SIZE]> {
+        Some(sha_256(b"secret-2"))
+    }
+
+    fn permit_query(params: Value, signature: Value) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "__secret_with_permit": {
+                "query": { "balance": {} },
+                "permit": { "params": params, "signature": signature },
+            }
+        }))
+        .unwrap()
+    }
+
+    pub fn test_query_permit_is_verified() {
+        let params = permit_params();
+        let signature = sign_permit(&params, &[1u8; 32]);
+
+        let verified =
+            verify_query_permit(&permit_query(params, signature.clone()), chain_id_hash()).unwrap();
+        let verified: Value = serde_json::from_slice(&verified).unwrap();
+
+        let signature: CosmosSignature = serde_json::from_value(signature).unwrap();
+        let signer = HumanAddr::from_canonical(&signature.get_public_key().get_address()).unwrap();
+        assert_eq!(
+            verified,
+            json!({
+                "__secret_with_permit": {
+                    "query": { "balance": {} },
+                    "signer": signer,
+                    "permit_name": "balance",
+                    "allowed_contracts": ["secret1contract"],
+                }
+            })
+        );
+    }
+
+    pub fn test_query_permit_with_wrong_signature_is_rejected() {
+        let signature = sign_permit(&permit_params(), &[1u8; 32]);
+        // The signature doesn't cover these params
+        let mut params = permit_params();
+        params["allowed_contracts"] = json!(["secret1othercontract"]);
+
+        let result = verify_query_permit(&permit_query(params, signature), chain_id_hash());
+        assert!(matches!(
+            result,
+            Err(EnclaveError::FailedQueryPermitVerification)
+        ));
+    }
+
+    pub fn test_query_permit_of_another_chain_is_rejected() {
+        let mut params = permit_params();
+        params["chain_id"] = json!("secret-testnet");
+        let signature = sign_permit(&params, &[1u8; 32]);
+
+        let result = verify_query_permit(
+            &permit_query(params.clone(), signature.clone()),
+            chain_id_hash(),
+        );
+        assert!(matches!(
+            result,
+            Err(EnclaveError::FailedQueryPermitVerification)
+        ));
+
+        // An enclave that doesn't know its chain doesn't accept any permit
+        let result = verify_query_permit(&permit_query(params, signature), None);
+        assert!(matches!(
+            result,
+            Err(EnclaveError::FailedQueryPermitVerification)
+        ));
+    }
+
+    pub fn test_query_permit_without_permit_is_rejected() {
+        let forged = serde_json::to_vec(&json!({
+            "__secret_with_permit": {
+                "query": { "balance": {} },
+                "signer": "secret1victim",
+                "permit_name": "balance",
+                "allowed_contracts": [],
+            }
+        }))
+        .unwrap();
+
+        let result = verify_query_permit(&forged, chain_id_hash());
+        assert!(matches!(
+            result,
+            Err(EnclaveError::FailedQueryPermitVerification)
+        ));
+    }
+
+    pub fn test_query_without_permit_is_unchanged() {
+        for query in &[
+            &br#"{"balance":{"address":"secret1abc"}}"#[..],
+            // Contracts may define queries of their own that look like the permit envelope
+            &br#"{"with_permit":{"query":{"balance":{}},"signer":"secret1abc"}}"#[..],
+            &b"not json"[..],
+        ] {
+            assert_eq!(
+                verify_query_permit(query, chain_id_hash()).unwrap(),
+                query.to_vec()
+            );
+        }
+    }
+
+    fn sig_info_of(sign_bytes: &[u8], signer: &[u8; 32], callback_sig: Option<&[u8]>) -> SigInfo {
+        serde_json::from_value(json!({
+            "sign_bytes": Binary(sign_bytes.to_vec()),
+            "signature": sign_permit(&permit_params(), signer),
+            "callback_sig": callback_sig.map(|sig| Binary(sig.to_vec())),
+        }))
+        .unwrap()
+    }
+
+    pub fn test_random_key_changes_with_every_call() {
+        let random_secret = AESKey::new_from_slice(&[7u8; 32]);
+        let env: Env = serde_json::from_value(json!({
+            "block": { "height": 1, "time": 2, "chain_id": "secret-2" },
+            "message": { "sender": "secret1sender", "sent_funds": [] },
+            "contract": { "address": "secret1contract" },
+            "contract_key": null,
+        }))
+        .unwrap();
+        let msg = SecretMessage {
+            nonce: [1u8; 32],
+            user_public_key: [2u8; 32],
+            msg: b"msg".to_vec(),
+            io_epoch: 0,
+        };
+        let contract = CanonicalAddr(Binary(vec![3u8; 20]));
+        let tx = sig_info_of(b"tx", &[1u8; 32], None);
+
+        let block_txs_hash = [5u8; 32];
+
+        let random_key_in_block =
+            |block_txs_hash: &[u8], sig_info: &SigInfo, msg: &SecretMessage| {
+                *derive_random_key(
+                    &random_secret,
+                    &env,
+                    block_txs_hash,
+                    sig_info,
+                    msg,
+                    &contract,
+                )
+                .get()
+            };
+        let random_key = |sig_info: &SigInfo, msg: &SecretMessage| {
+            random_key_in_block(&block_txs_hash, sig_info, msg)
+        };
+        let key = random_key(&tx, &msg);
+
+        // All the validators derive the same key
+        assert_eq!(random_key(&tx, &msg), key);
+
+        // The same transaction in a block with other transactions
+        assert_ne!(random_key_in_block(&[6u8; 32], &tx, &msg), key);
+
+        // Another transaction in the same block
+        assert_ne!(
+            random_key(&sig_info_of(b"tx 2", &[1u8; 32], None), &msg),
+            key
+        );
+        assert_ne!(random_key(&sig_info_of(b"tx", &[2u8; 32], None), &msg), key);
+
+        // A callback from another contract
+        assert_ne!(
+            random_key(&sig_info_of(b"tx", &[1u8; 32], Some(b"callback")), &msg),
+            key
+        );
+
+        // Another message in the same transaction
+        let other_msg = SecretMessage {
+            nonce: [4u8; 32],
+            user_public_key: msg.user_public_key,
+            msg: msg.msg.clone(),
+            io_epoch: 0,
+        };
+        assert_ne!(random_key(&tx, &other_msg), key);
+    }
+
+    pub fn test_admin_proof_is_bound_to_admin_and_code() {
+        let consensus_state_ikm = AESKey::new_from_slice(&[7u8; 32]);
+        let contract_key = [1u8; CONTRACT_KEY_LENGTH];
+        let code_hash = [2u8; HASH_SIZE];
+        let admin = CanonicalAddr(Binary(vec![3u8; 20]));
+
+        let proof =
+            generate_contract_admin_proof(&consensus_state_ikm, &contract_key, &code_hash, &admin);
+
+        let env_with_proof = |proof: Option<String>| -> Env {
+            let mut env: Env = serde_json::from_value(json!({
+                "block": { "height": 1, "time": 2, "chain_id": "secret-2" },
+                "message": { "sender": "secret1sender", "sent_funds": [] },
+                "contract": { "address": "secret1contract" },
+                "contract_key": null,
+            }))
+            .unwrap();
+            env.contract_admin_proof = proof;
+            env
+        };
+        assert_eq!(
+            extract_contract_admin_proof(&env_with_proof(Some(base64::encode(&proof)))).unwrap(),
+            proof
+        );
+        assert!(extract_contract_admin_proof(&env_with_proof(None)).is_err());
+
+        // Someone else
+        let other = CanonicalAddr(Binary(vec![4u8; 20]));
+        assert_ne!(
+            generate_contract_admin_proof(&consensus_state_ikm, &contract_key, &code_hash, &other),
+            proof
+        );
+
+        // The admin of another contract
+        assert_ne!(
+            generate_contract_admin_proof(
+                &consensus_state_ikm,
+                &[5u8; CONTRACT_KEY_LENGTH],
+                &code_hash,
+                &admin
+            ),
+            proof
+        );
+
+        // The contract, if it ran other code
+        assert_ne!(
+            generate_contract_admin_proof(
+                &consensus_state_ikm,
+                &contract_key,
+                &[6u8; HASH_SIZE],
+                &admin
+            ),
+            proof
+        );
+    }
+
+    pub fn test_contract_key_needs_a_proof_after_genesis() {
+        let genesis_ikm = AESKey::new_from_slice(&[7u8; 32]);
+        let epoch_ikm = AESKey::new_from_slice(&[8u8; 32]);
+        let sender_id = [1u8; HASH_SIZE];
+        let code_hash = [2u8; HASH_SIZE];
+        let address = [3u8; 20];
+
+        let mut contract_key = [0u8; CONTRACT_KEY_LENGTH];
+        contract_key[..HASH_SIZE].copy_from_slice(&sender_id);
+        contract_key[HASH_SIZE..].copy_from_slice(&generate_contract_id(
+            &genesis_ikm,
+            &sender_id,
+            &code_hash,
+            &address,
+        ));
+
+        // The key authenticates itself only in the genesis epoch
+        assert!(authenticate_contract_key(
+            &genesis_ikm,
+            &contract_key,
+            &address,
+            &code_hash,
+            None,
+            true
+        ));
+        assert!(!authenticate_contract_key(
+            &genesis_ikm,
+            &contract_key,
+            &address,
+            &code_hash,
+            None,
+            false
+        ));
+
+        // Later epochs need a proof of the same epoch
+        let proof = generate_contract_key_proof(&epoch_ikm, &contract_key, &code_hash, &address);
+        assert!(authenticate_contract_key(
+            &epoch_ikm,
+            &contract_key,
+            &address,
+            &code_hash,
+            Some(&proof),
+            false
+        ));
+        let old_proof =
+            generate_contract_key_proof(&genesis_ikm, &contract_key, &code_hash, &address);
+        assert!(!authenticate_contract_key(
+            &epoch_ikm,
+            &contract_key,
+            &address,
+            &code_hash,
+            Some(&old_proof),
+            false
+        ));
+
+        // And the proof is only good for the code it was issued for
+        assert!(!authenticate_contract_key(
+            &epoch_ikm,
+            &contract_key,
+            &address,
+            &[4u8; HASH_SIZE],
+            Some(&proof),
+            false
+        ));
+    }
+}
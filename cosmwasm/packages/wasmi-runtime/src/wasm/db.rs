@@ -1,18 +1,37 @@
 use super::contract_validation::ContractKey;
 use super::errors::WasmEngineError;
+use super::key_index::{self, IndexStorage, KeyIterator};
+use super::storage_cache::StorageCache;
 use crate::crypto::{sha_256, AESKey, Kdf, SIVEncryptable, KEY_MANAGER};
-use crate::{exports, imports};
 
-use enclave_ffi_types::{Ctx, EnclaveBuffer, OcallReturn, UntrustedVmError};
+use enclave_ffi_types::Ctx;
 
 use log::*;
-use sgx_types::sgx_status_t;
 
+/// Values and index nodes that were encrypted with a later epoch of the consensus seed than genesis
+/// are tagged with that epoch, as a big endian u32 right before the ciphertext. Values of the genesis
+/// epoch keep the untagged format, so nothing changes before the seed is first rotated.
+///
+/// A tag can't be told apart from the beginning of an untagged ciphertext, so decryption with the
+/// tagged epoch is attempted first, and the genesis epoch is the fallback. SIV is authenticated,
+/// so decryption with the wrong key always fails.
+const EPOCH_TAG_LENGTH: usize = 4;
+
+/// Values whose keys are in the key index are encrypted with this additional data, next to their
+/// AD. Values that were written before storage iteration was supported aren't, and their keys are
+/// added to the index the next time they are written. This way only new keys need to look them up
+/// in the index.
+const INDEXED_VALUE_AD: &[u8] = b"indexed";
+
+/// Values are always encrypted with `seed_epoch`, so values of older epochs are re-encrypted
+/// when they are written.
 pub fn write_encrypted_key(
     key: &[u8],
     value: &[u8],
     context: &Ctx,
+    storage: &mut StorageCache,
     contract_key: &ContractKey,
+    seed_epoch: u32,
 ) -> Result<u64, WasmEngineError> {
     // Get the state key from the key manager
 
@@ -23,28 +42,45 @@ pub fn write_encrypted_key(
         scrambled_field_name
     );
 
-    let (ad, ad_used_gas) = derive_ad_for_field(&scrambled_field_name, &context)?;
+    let (old_value, ad_used_gas) = storage.read(context, &scrambled_field_name)?;
+    let ad = derive_ad_for_field(&scrambled_field_name, old_value.as_deref());
 
-    let encrypted_value = encrypt_key(&scrambled_field_name, value, contract_key, &ad)?;
+    let is_indexed = match &old_value {
+        Some(old_value) => decrypt_key(&scrambled_field_name, old_value, contract_key)?.2,
+        None => false,
+    };
+
+    let encrypted_value = encrypt_key(
+        &scrambled_field_name,
+        value,
+        contract_key,
+        &[&ad, INDEXED_VALUE_AD],
+        seed_epoch,
+    )?;
 
     let mut db_data: Vec<u8> = ad.to_vec();
     db_data.extend_from_slice(encrypted_value.as_slice());
 
-    // Write the new data as concat(ad, encrypted_val)
-    let write_used_gas = write_db(context, &scrambled_field_name, &db_data).map_err(|err| {
-        warn!(
-            "write_db() go an error from ocall_write_db, stopping wasm: {:?}",
-            err
-        );
-        err
-    })?;
+    // Write the new data as concat(ad, encrypted_val).
+    // The write only reaches the host when the storage cache is flushed, but its estimated gas is charged now.
+    let write_used_gas = storage.write(&scrambled_field_name, db_data);
+
+    let mut index_used_gas = 0;
+    if !is_indexed {
+        let mut index = EncryptedIndex::new(context, storage, contract_key, seed_epoch);
+        key_index::insert(&mut index, key)?;
+        index_used_gas = index.gas_used;
+    }
 
-    Ok(ad_used_gas + write_used_gas)
+    Ok(ad_used_gas
+        .saturating_add(write_used_gas)
+        .saturating_add(index_used_gas))
 }
 
 pub fn read_encrypted_key(
     key: &[u8],
     context: &Ctx,
+    storage: &mut StorageCache,
     contract_key: &ContractKey,
 ) -> Result<(Option<Vec<u8>>, u64), WasmEngineError> {
     let scrambled_field_name = field_name_digest(key, contract_key);
@@ -54,12 +90,12 @@ pub fn read_encrypted_key(
         scrambled_field_name
     );
 
-    // Call read_db (this bubbles up to Tendermint via ocalls and FFI to Go code)
+    // Call read_db (this bubbles up to Tendermint via ocalls and FFI to Go code, unless the value is cached)
     // This returns the value from Tendermint
-    match read_db(context, &scrambled_field_name) {
+    match storage.read(context, &scrambled_field_name) {
         Ok((value, gas_used)) => match value {
             Some(value) => match decrypt_key(&scrambled_field_name, &value, contract_key) {
-                Ok(decrypted) => Ok((Some(decrypted), gas_used)),
+                Ok((decrypted, _epoch, _is_indexed)) => Ok((Some(decrypted), gas_used)),
                 // This error case is why we have all the matches here.
                 // If we successfully collected a value, but failed to decrypt it, then we propagate that error.
                 Err(err) => Err(err),
@@ -70,156 +106,136 @@ pub fn read_encrypted_key(
     }
 }
 
+/// The removals only reach the host when the storage cache is flushed, but their estimated gas is
+/// returned right away, for the caller to charge, together with the gas of reading the key index.
 pub fn remove_encrypted_key(
     key: &[u8],
     context: &Ctx,
+    storage: &mut StorageCache,
     contract_key: &ContractKey,
+    seed_epoch: u32,
 ) -> Result<u64, WasmEngineError> {
     let scrambled_field_name = field_name_digest(key, contract_key);
 
     info!("Removing scrambled field name: {:?}", scrambled_field_name);
 
-    // Call remove_db (this bubbles up to Tendermint via ocalls and FFI to Go code)
-    // fn remove_db(context: Ctx, key: &[u8]) {
-    let gas_used = remove_db(context, &scrambled_field_name).map_err(|err| {
-        warn!(
-            "remove_db() got an error from ocall_remove_db, stopping wasm: {:?}",
-            err
-        );
-        err
-    })?;
-    Ok(gas_used)
-}
+    let remove_used_gas = storage.remove(&scrambled_field_name);
 
-pub fn field_name_digest(field_name: &[u8], contract_key: &ContractKey) -> [u8; 32] {
-    let mut data = field_name.to_vec();
-    data.extend_from_slice(contract_key);
+    let mut index = EncryptedIndex::new(context, storage, contract_key, seed_epoch);
+    key_index::remove(&mut index, key)?;
 
-    sha_256(&data)
+    Ok(remove_used_gas.saturating_add(index.gas_used))
 }
 
-/// Safe wrapper around reads from the contract storage
-fn read_db(context: &Ctx, key: &[u8]) -> Result<(Option<Vec<u8>>, u64), WasmEngineError> {
-    let mut ocall_return = OcallReturn::Success;
-    let mut enclave_buffer = std::mem::MaybeUninit::<EnclaveBuffer>::uninit();
-    let mut vm_err = UntrustedVmError::default();
-    let mut gas_used = 0_u64;
-    let value = unsafe {
-        let status = imports::ocall_read_db(
-            (&mut ocall_return) as *mut _,
-            context.unsafe_clone(),
-            (&mut vm_err) as *mut _,
-            (&mut gas_used) as *mut _,
-            enclave_buffer.as_mut_ptr(),
-            key.as_ptr(),
-            key.len(),
-        );
-        match status {
-            sgx_status_t::SGX_SUCCESS => { /* continue */ }
-            error_status => {
-                warn!(
-                    "read_db() got an error from ocall_read_db, stopping wasm: {:?}",
-                    error_status
-                );
-                return Err(WasmEngineError::FailedOcall(vm_err));
-            }
-        }
+/// Returns the next key of an iterator, the gas used by the host to read the key index, and the
+/// number of entries of the index that were decrypted, which the caller should charge for.
+/// The values themselves are read lazily, as the contract iterates over the keys.
+pub fn next_key(
+    iterator: &mut KeyIterator,
+    context: &Ctx,
+    storage: &mut StorageCache,
+    contract_key: &ContractKey,
+    seed_epoch: u32,
+) -> Result<(Option<Vec<u8>>, u64, u64), WasmEngineError> {
+    let mut index = EncryptedIndex::new(context, storage, contract_key, seed_epoch);
+    let (key, entries_read) = iterator.next(&mut index)?;
 
-        match ocall_return {
-            OcallReturn::Success => {
-                let enclave_buffer = enclave_buffer.assume_init();
-                exports::recover_buffer(enclave_buffer)?
-            }
-            OcallReturn::Failure => {
-                return Err(WasmEngineError::FailedOcall(vm_err));
-            }
-            OcallReturn::Panic => return Err(WasmEngineError::Panic),
-        }
-    };
+    Ok((key, index.gas_used, entries_read))
+}
 
-    Ok((value, gas_used))
+/// The nodes of the key index, which are encrypted like the values. The key of a node is used as
+/// its field name and as its AD, so a node can't be moved under another key.
+struct EncryptedIndex<'a> {
+    context: &'a Ctx,
+    storage: &'a mut StorageCache,
+    contract_key: &'a ContractKey,
+    seed_epoch: u32,
+    /// The gas used by the host to read nodes, and the estimated gas of the writes and removals
+    gas_used: u64,
 }
 
-/// Safe wrapper around reads from the contract storage
-fn remove_db(context: &Ctx, key: &[u8]) -> Result<u64, WasmEngineError> {
-    let mut ocall_return = OcallReturn::Success;
-    let mut vm_err = UntrustedVmError::default();
-    let mut gas_used = 0_u64;
-    match unsafe {
-        imports::ocall_remove_db(
-            (&mut ocall_return) as *mut _,
-            context.unsafe_clone(),
-            (&mut vm_err) as *mut _,
-            (&mut gas_used) as *mut _,
-            key.as_ptr(),
-            key.len(),
-        )
-    } {
-        sgx_status_t::SGX_SUCCESS => { /* continue */ }
-        _error_status => return Err(WasmEngineError::FailedOcall(vm_err)),
+impl<'a> EncryptedIndex<'a> {
+    fn new(
+        context: &'a Ctx,
+        storage: &'a mut StorageCache,
+        contract_key: &'a ContractKey,
+        seed_epoch: u32,
+    ) -> Self {
+        Self {
+            context,
+            storage,
+            contract_key,
+            seed_epoch,
+            gas_used: 0,
+        }
     }
+}
 
-    match ocall_return {
-        OcallReturn::Success => Ok(gas_used),
-        OcallReturn::Failure => Err(WasmEngineError::FailedOcall(vm_err)),
-        OcallReturn::Panic => Err(WasmEngineError::Panic),
+impl IndexStorage for EncryptedIndex<'_> {
+    fn read(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, WasmEngineError> {
+        let (value, gas_used) = self.storage.read(self.context, key)?;
+        self.gas_used = self.gas_used.saturating_add(gas_used);
+
+        let contract_key = self.contract_key;
+        value
+            .map(|value| {
+                decrypt_tagged(&value, |epoch, ciphertext| {
+                    get_symmetrical_key(key, contract_key, epoch)?
+                        .decrypt_siv(ciphertext, Some(&[key]))
+                        .ok()
+                })
+                .map(|(node, _epoch)| node)
+                .ok_or_else(|| {
+                    warn!("read_db() failed to decrypt a node of the key index, stopping wasm");
+                    WasmEngineError::DecryptionError
+                })
+            })
+            .transpose()
     }
-}
 
-/// Safe wrapper around writes to the contract storage
-fn write_db(context: &Ctx, key: &[u8], value: &[u8]) -> Result<u64, WasmEngineError> {
-    let mut ocall_return = OcallReturn::Success;
-    let mut vm_err = UntrustedVmError::default();
-    let mut gas_used = 0_u64;
-    match unsafe {
-        imports::ocall_write_db(
-            (&mut ocall_return) as *mut _,
-            context.unsafe_clone(),
-            (&mut vm_err) as *mut _,
-            (&mut gas_used) as *mut _,
-            key.as_ptr(),
-            key.len(),
-            value.as_ptr(),
-            value.len(),
-        )
-    } {
-        sgx_status_t::SGX_SUCCESS => { /* continue */ }
-        _err_status => return Err(WasmEngineError::FailedOcall(vm_err)),
+    fn write(&mut self, key: &[u8], value: &[u8]) -> Result<(), WasmEngineError> {
+        let encrypted = encrypt_key(key, value, self.contract_key, &[key], self.seed_epoch)?;
+        self.gas_used = self
+            .gas_used
+            .saturating_add(self.storage.write(key, encrypted));
+        Ok(())
     }
 
-    match ocall_return {
-        OcallReturn::Success => Ok(gas_used),
-        OcallReturn::Failure => Err(WasmEngineError::FailedOcall(vm_err)),
-        OcallReturn::Panic => Err(WasmEngineError::Panic),
+    fn remove(&mut self, key: &[u8]) {
+        self.gas_used = self.gas_used.saturating_add(self.storage.remove(key));
     }
 }
 
-fn derive_ad_for_field(
-    field_name: &[u8],
-    context: &Ctx,
-) -> Result<([u8; 32], u64), WasmEngineError> {
-    let (old_value, gas_used) = read_db(context, field_name)?;
-    let ad = sha_256(
+pub fn field_name_digest(field_name: &[u8], contract_key: &ContractKey) -> [u8; 32] {
+    let mut data = field_name.to_vec();
+    data.extend_from_slice(contract_key);
+
+    sha_256(&data)
+}
+
+/// Returns the AD to use for the new value of a field, given its current value
+fn derive_ad_for_field(field_name: &[u8], old_value: Option<&[u8]>) -> [u8; 32] {
+    sha_256(
         old_value
-            .as_ref()
             // Extract previous_ad to calculate the new ad (first 32 bytes)
             .map(|old_value| old_value.split_at(32).0)
             // No data exist yet for this state_key_name, so creating a new `ad`
             .unwrap_or(field_name),
-    );
-    Ok((ad, gas_used))
+    )
 }
 
 fn encrypt_key(
     field_name: &[u8],
     value: &[u8],
     contract_key: &ContractKey,
-    ad: &[u8],
+    ad: &[&[u8]],
+    seed_epoch: u32,
 ) -> Result<Vec<u8>, WasmEngineError> {
-    let encryption_key = get_symmetrical_key(field_name, contract_key);
+    let encryption_key = get_symmetrical_key(field_name, contract_key, seed_epoch)
+        .ok_or(WasmEngineError::EncryptionError)?;
 
-    encryption_key
-        .encrypt_siv(&value, Some(&[ad]))
+    let ciphertext = encryption_key
+        .encrypt_siv(&value, Some(ad))
         .map_err(|err| {
             warn!(
                 "write_db() got an error while trying to encrypt the value {:?}, stopping wasm: {:?}",
@@ -227,34 +243,187 @@ fn encrypt_key(
                 err
             );
             WasmEngineError::EncryptionError
-    })
+    })?;
+
+    Ok(tag_with_epoch(seed_epoch, ciphertext))
 }
 
+/// Returns the decrypted value, the epoch it was encrypted with, and whether its key is in the key
+/// index, see `INDEXED_VALUE_AD`
 fn decrypt_key(
     field_name: &[u8],
     value: &[u8],
     contract_key: &ContractKey,
-) -> Result<Vec<u8>, WasmEngineError> {
-    let decryption_key = get_symmetrical_key(field_name, contract_key);
+) -> Result<(Vec<u8>, u32, bool), WasmEngineError> {
+    if value.len() < 32 {
+        warn!("read_db() got a value that is too short to hold its AD");
+        return Err(WasmEngineError::DecryptionError);
+    }
 
     // Slice ad from `value`
     let (ad, encrypted_value) = value.split_at(32);
 
-    decryption_key.decrypt_siv(&encrypted_value, Some(&[ad])).map_err(|err| {
+    decrypt_tagged(encrypted_value, |epoch, ciphertext| {
+        decrypt_value(
+            &get_symmetrical_key(field_name, contract_key, epoch)?,
+            ad,
+            ciphertext,
+        )
+    })
+    .map(|((value, is_indexed), epoch)| (value, epoch, is_indexed))
+    .ok_or_else(|| {
         warn!(
-            "read_db() got an error while trying to decrypt the value for key {:?}, stopping wasm: {:?}",
+            "read_db() got an error while trying to decrypt the value for key {:?}, stopping wasm",
             String::from_utf8_lossy(&field_name),
-            err
         );
         WasmEngineError::DecryptionError
     })
 }
 
-fn get_symmetrical_key(field_name: &[u8], contract_key: &ContractKey) -> AESKey {
-    let consensus_state_ikm = KEY_MANAGER.get_consensus_state_ikm().unwrap();
+/// Returns the plaintext, and whether it was encrypted with `INDEXED_VALUE_AD`
+fn decrypt_value(key: &AESKey, ad: &[u8], ciphertext: &[u8]) -> Option<(Vec<u8>, bool)> {
+    key.decrypt_siv(ciphertext, Some(&[ad, INDEXED_VALUE_AD]))
+        .map(|value| (value, true))
+        .or_else(|_| {
+            key.decrypt_siv(ciphertext, Some(&[ad]))
+                .map(|value| (value, false))
+        })
+        .ok()
+}
+
+fn tag_with_epoch(seed_epoch: u32, ciphertext: Vec<u8>) -> Vec<u8> {
+    if seed_epoch == 0 {
+        return ciphertext;
+    }
+
+    let mut tagged = Vec::with_capacity(EPOCH_TAG_LENGTH + ciphertext.len());
+    tagged.extend_from_slice(&seed_epoch.to_be_bytes());
+    tagged.extend_from_slice(&ciphertext);
+    tagged
+}
+
+/// Decrypts data that may be tagged with an epoch, see `EPOCH_TAG_LENGTH`.
+/// `decrypt` gets the epoch to decrypt with, and the ciphertext of that epoch.
+fn decrypt_tagged<T, F>(data: &[u8], decrypt: F) -> Option<(T, u32)>
+where
+    F: Fn(u32, &[u8]) -> Option<T>,
+{
+    if data.len() > EPOCH_TAG_LENGTH {
+        let (tag, ciphertext) = data.split_at(EPOCH_TAG_LENGTH);
+        let epoch = u32::from_be_bytes([tag[0], tag[1], tag[2], tag[3]]);
+        if epoch != 0 {
+            if let Some(plaintext) = decrypt(epoch, ciphertext) {
+                return Some((plaintext, epoch));
+            }
+        }
+    }
+
+    decrypt(0, data).map(|plaintext| (plaintext, 0))
+}
+
+/// Returns `None` if the enclave doesn't have the seed of `seed_epoch`
+fn get_symmetrical_key(
+    field_name: &[u8],
+    contract_key: &ContractKey,
+    seed_epoch: u32,
+) -> Option<AESKey> {
+    let consensus_state_ikm = KEY_MANAGER
+        .get_consensus_state_ikm_of_epoch(seed_epoch)
+        .ok()?;
 
     // Derive the key to the specific field name
     let mut derivation_data = field_name.to_vec();
     derivation_data.extend_from_slice(contract_key.to_vec().as_slice());
-    consensus_state_ikm.derive_key_from_this(&derivation_data)
+    Some(consensus_state_ikm.derive_key_from_this(&derivation_data))
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    const AD: &[u8] = b"ad";
+
+    fn keys() -> Vec<AESKey> {
+        (1..=3_u8)
+            .map(|byte| AESKey::new_from_slice(&[byte; 32]))
+            .collect()
+    }
+
+    fn decrypt_with(keys: &[AESKey], data: &[u8]) -> Option<(Vec<u8>, u32)> {
+        decrypt_tagged(data, |epoch, ciphertext| {
+            keys.get(epoch as usize)?
+                .decrypt_siv(ciphertext, Some(&[AD]))
+                .ok()
+        })
+    }
+
+    fn encrypt_with(keys: &[AESKey], epoch: u32, plaintext: &[u8]) -> Vec<u8> {
+        let ciphertext = keys[epoch as usize]
+            .encrypt_siv(plaintext, Some(&[AD]))
+            .unwrap();
+        tag_with_epoch(epoch, ciphertext)
+    }
+
+    pub fn test_genesis_epoch_values_are_untagged() {
+        let keys = keys();
+        let ciphertext = keys[0].encrypt_siv(b"value", Some(&[AD])).unwrap();
+
+        assert_eq!(encrypt_with(&keys, 0, b"value"), ciphertext);
+        assert_eq!(
+            decrypt_with(&keys, &ciphertext),
+            Some((b"value".to_vec(), 0))
+        );
+    }
+
+    pub fn test_values_of_later_epochs_are_tagged() {
+        let keys = keys();
+
+        let encrypted = encrypt_with(&keys, 2, b"value");
+        assert_eq!(&encrypted[..EPOCH_TAG_LENGTH], &2_u32.to_be_bytes());
+        assert_eq!(
+            decrypt_with(&keys, &encrypted),
+            Some((b"value".to_vec(), 2))
+        );
+
+        // Every epoch can still be read after the seed was rotated
+        for epoch in 0..3 {
+            let encrypted = encrypt_with(&keys, epoch, b"value");
+            assert_eq!(
+                decrypt_with(&keys, &encrypted),
+                Some((b"value".to_vec(), epoch))
+            );
+        }
+    }
+
+    pub fn test_values_of_unknown_epochs_fail_to_decrypt() {
+        let keys = keys();
+
+        let encrypted = encrypt_with(&keys, 2, b"value");
+        assert_eq!(decrypt_with(&keys[..2], &encrypted), None);
+
+        let mut tampered = encrypt_with(&keys, 1, b"value");
+        tampered[0] ^= 0xff;
+        assert_eq!(decrypt_with(&keys, &tampered), None);
+    }
+
+    pub fn test_values_from_before_the_key_index_are_not_indexed() {
+        let keys = keys();
+        let key = &keys[0];
+
+        let indexed = key
+            .encrypt_siv(b"value", Some(&[AD, INDEXED_VALUE_AD]))
+            .unwrap();
+        assert_eq!(
+            decrypt_value(key, AD, &indexed),
+            Some((b"value".to_vec(), true))
+        );
+
+        let legacy = key.encrypt_siv(b"value", Some(&[AD])).unwrap();
+        assert_eq!(
+            decrypt_value(key, AD, &legacy),
+            Some((b"value".to_vec(), false))
+        );
+
+        assert_eq!(decrypt_value(key, b"other ad", &indexed), None);
+    }
 }
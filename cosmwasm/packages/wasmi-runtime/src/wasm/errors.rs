@@ -1,5 +1,7 @@
 use derive_more::Display;
-use enclave_ffi_types::{EnclaveError, UntrustedVmError};
+use enclave_ffi_types::{
+    DeserializationTarget, EnclaveError, OcallFailure, OcallKind, UntrustedVmError,
+};
 use log::*;
 use wasmi::{Error as InterpreterError, HostError, TrapKind};
 
@@ -9,7 +11,11 @@ use crate::exports::BufferRecoveryError;
 #[non_exhaustive]
 pub enum WasmEngineError {
     #[display(fmt = "FailedOcall")]
-    FailedOcall(UntrustedVmError),
+    FailedOcall {
+        ocall: OcallKind,
+        failure: OcallFailure,
+        vm_error: UntrustedVmError,
+    },
     HostMisbehavior,
     OutOfGas,
     Panic,
@@ -17,7 +23,10 @@ pub enum WasmEngineError {
     EncryptionError,
     DecryptionError,
     SerializationError,
-    DeserializationError,
+    #[display(fmt = "DeserializationError")]
+    DeserializationError {
+        target: DeserializationTarget,
+    },
     // This is for unexpected error while processing base32 data.
     Base32Error,
 
@@ -26,17 +35,43 @@ pub enum WasmEngineError {
     MemoryWriteError,
     /// The contract attempted to write to storage during a query
     UnauthorizedWrite,
+    /// The contract asked for randomness during a query or a simulation, which aren't included in a
+    /// block to derive it from
+    RandomnessUnavailable,
 
     NonExistentImportFunction,
+    /// The contract used an iterator that doesn't exist, or an invalid iteration order.
+    /// Reported as a failed `scan_db` with invalid arguments, the kind of storage iteration.
+    InvalidIterator,
 }
 
 impl HostError for WasmEngineError {}
 
+impl WasmEngineError {
+    pub fn failed_ocall(
+        ocall: OcallKind,
+        failure: OcallFailure,
+        vm_error: UntrustedVmError,
+    ) -> Self {
+        WasmEngineError::FailedOcall {
+            ocall,
+            failure,
+            vm_error,
+        }
+    }
+}
+
 impl From<WasmEngineError> for EnclaveError {
     fn from(engine_err: WasmEngineError) -> Self {
         use WasmEngineError::*;
         match engine_err {
-            FailedOcall(vm_error) => EnclaveError::FailedOcall {
+            FailedOcall {
+                ocall,
+                failure,
+                vm_error,
+            } => EnclaveError::FailedOcall {
+                ocall,
+                failure,
                 vm_error: UntrustedVmError { ptr: vm_error.ptr },
             },
             OutOfGas => EnclaveError::OutOfGas,
@@ -46,7 +81,14 @@ impl From<WasmEngineError> for EnclaveError {
             MemoryReadError => EnclaveError::MemoryReadError,
             MemoryWriteError => EnclaveError::MemoryWriteError,
             UnauthorizedWrite => EnclaveError::UnauthorizedWrite,
+            RandomnessUnavailable => EnclaveError::RandomnessUnavailable,
             HostMisbehavior => EnclaveError::HostMisbehavior,
+            DeserializationError { target } => EnclaveError::FailedToDeserialize { target },
+            InvalidIterator => EnclaveError::FailedOcall {
+                ocall: OcallKind::ScanDb,
+                failure: OcallFailure::InvalidArguments,
+                vm_error: UntrustedVmError::new(core::ptr::null_mut()),
+            },
             // Unexpected WasmEngineError variant
             _other => EnclaveError::Unknown,
         }
@@ -100,3 +142,30 @@ fn trap_kind_to_enclave_error(kind: TrapKind) -> EnclaveError {
         _ => EnclaveError::FailedFunctionCall,
     }
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_engine_errors_keep_their_detail() {
+        let error = EnclaveError::from(WasmEngineError::DeserializationError {
+            target: DeserializationTarget::Base64,
+        });
+        assert!(matches!(
+            error,
+            EnclaveError::FailedToDeserialize {
+                target: DeserializationTarget::Base64
+            }
+        ));
+
+        let error = EnclaveError::from(WasmEngineError::InvalidIterator);
+        assert!(matches!(
+            error,
+            EnclaveError::FailedOcall {
+                ocall: OcallKind::ScanDb,
+                failure: OcallFailure::InvalidArguments,
+                vm_error,
+            } if vm_error.ptr.is_null()
+        ));
+    }
+}
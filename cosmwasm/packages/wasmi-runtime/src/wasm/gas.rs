@@ -1,6 +1,137 @@
+use log::*;
+use serde::{Deserialize, Serialize};
+
+use enclave_ffi_types::EnclaveError;
+
+use crate::recursion_depth::RECURSION_LIMIT;
+
 pub use pwasm_utils::{inject_gas_counter, rules};
 
-/// Wasm cost table
+/// Costs lower than this would let contracts run for free
+const MIN_COST: u32 = 1;
+/// Costs higher than this would make practically every contract run out of gas
+const MAX_COST: u32 = 1 << 20;
+/// Bounds for the native stack limit
+const MIN_STACK_HEIGHT: u32 = 1024;
+const MAX_STACK_HEIGHT: u32 = 64 * 1024;
+/// Nested queries can't go deeper than the amount of instances the enclave is willing to load
+const MAX_QUERY_DEPTH: u32 = RECURSION_LIMIT as u32 - 1;
+
+/// A versioned gas schedule, as set by the chain.
+/// A new version should be added whenever the meaning of the costs changes, or costs are added,
+/// so nodes running different schedules can't disagree on the gas used by a contract.
+/// Enclaves reject versions they don't know, and costs they don't know.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case", tag = "version", content = "costs")]
+pub enum GasSchedule {
+    V1(WasmCostsV1),
+    V2(WasmCosts),
+}
+
+impl GasSchedule {
+    /// Parses and validates the gas schedule passed in by the host.
+    /// An empty schedule means the chain didn't set one, and the default costs are used.
+    pub fn parse_costs(schedule: &[u8]) -> Result<WasmCosts, EnclaveError> {
+        if schedule.is_empty() {
+            return Ok(WasmCosts::default());
+        }
+
+        let schedule: GasSchedule = serde_json::from_slice(schedule).map_err(|err| {
+            warn!(
+                "got an error while trying to deserialize the gas schedule {:?}: {}",
+                String::from_utf8_lossy(schedule),
+                err
+            );
+            EnclaveError::InvalidGasSchedule
+        })?;
+
+        match schedule {
+            GasSchedule::V1(costs) => {
+                costs.validate()?;
+                Ok(costs.into())
+            }
+            GasSchedule::V2(costs) => {
+                costs.validate()?;
+                Ok(costs)
+            }
+        }
+    }
+}
+
+/// The costs of a V1 schedule. Costs that came after V1 get their default value.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WasmCostsV1 {
+    pub regular: u32,
+    pub div: u32,
+    pub mul: u32,
+    pub mem: u32,
+    pub initial_mem: u32,
+    pub grow_mem: u32,
+    pub external_humanize_address: u32,
+    pub external_canonicalize_address: u32,
+    // These were never metered. They are still required and validated, so a V1 schedule is
+    // accepted by exactly the enclaves that accepted it before.
+    pub static_u256: u32,
+    pub static_address: u32,
+    pub memcpy: u32,
+    pub max_stack_height: u32,
+    pub opcodes_mul: u32,
+    pub opcodes_div: u32,
+}
+
+impl WasmCostsV1 {
+    fn validate(&self) -> Result<(), EnclaveError> {
+        validate_costs(&[
+            ("regular", self.regular),
+            ("div", self.div),
+            ("mul", self.mul),
+            ("mem", self.mem),
+            ("static_u256", self.static_u256),
+            ("static_address", self.static_address),
+            ("initial_mem", self.initial_mem),
+            ("grow_mem", self.grow_mem),
+            ("memcpy", self.memcpy),
+            ("opcodes_mul", self.opcodes_mul),
+            ("opcodes_div", self.opcodes_div),
+            ("external_humanize_address", self.external_humanize_address),
+            (
+                "external_canonicalize_address",
+                self.external_canonicalize_address,
+            ),
+        ])?;
+
+        if self.max_stack_height < MIN_STACK_HEIGHT || self.max_stack_height > MAX_STACK_HEIGHT {
+            warn!(
+                "gas schedule max_stack_height is out of bounds: {} (allowed {}..={})",
+                self.max_stack_height, MIN_STACK_HEIGHT, MAX_STACK_HEIGHT
+            );
+            return Err(EnclaveError::InvalidGasSchedule);
+        }
+
+        Ok(())
+    }
+}
+
+impl From<WasmCostsV1> for WasmCosts {
+    fn from(costs: WasmCostsV1) -> Self {
+        WasmCosts {
+            regular: costs.regular,
+            div: costs.div,
+            mul: costs.mul,
+            mem: costs.mem,
+            initial_mem: costs.initial_mem,
+            grow_mem: costs.grow_mem,
+            external_humanize_address: costs.external_humanize_address,
+            external_canonicalize_address: costs.external_canonicalize_address,
+            ..WasmCosts::default()
+        }
+    }
+}
+
+/// Wasm cost table, and the costs of a V2 schedule
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
 pub struct WasmCosts {
     /// Default opcode cost
     pub regular: u32,
@@ -10,26 +141,31 @@ pub struct WasmCosts {
     pub mul: u32,
     /// Memory (load/store) operations multiplier.
     pub mem: u32,
-    /// General static query of U256 value from env-info
-    pub static_u256: u32,
-    /// General static query of Address value from env-info
-    pub static_address: u32,
     /// Memory stipend. Amount of free memory (in 64kb pages) each contract can use for stack.
     pub initial_mem: u32,
     /// Grow memory cost, per page (64kb)
     pub grow_mem: u32,
-    /// Memory copy cost, per byte
-    pub memcpy: u32,
-    /// Max stack height (native WebAssembly stack limiter)
-    pub max_stack_height: u32,
-    /// Cost of wasm opcode is calculated as TABLE_ENTRY_COST * `opcodes_mul` / `opcodes_div`
-    pub opcodes_mul: u32,
-    /// Cost of wasm opcode is calculated as TABLE_ENTRY_COST * `opcodes_mul` / `opcodes_div`
-    pub opcodes_div: u32,
     /// Cost invoking humanize_address from WASM
     pub external_humanize_address: u32,
     /// Cost invoking canonicalize_address from WASM
     pub external_canonicalize_address: u32,
+    /// Cost invoking random from WASM
+    pub external_random: u32,
+    /// Cost of verifying a secp256k1 signature from WASM
+    pub external_secp256k1_verify: u32,
+    /// Cost of verifying an ed25519 signature from WASM, charged for every signature in a batch
+    pub external_ed25519_verify: u32,
+    /// Cost of querying the chain from WASM, on top of the gas used by the query itself.
+    /// It is charged even for malformed queries.
+    pub external_query_chain: u32,
+    /// The share of the gas left, in percent, that a single query may use. A query that runs out of
+    /// it fails with a `SystemError`, and the rest of the gas is left to the caller.
+    pub max_query_gas_percent: u32,
+    /// How many levels of nested queries may be started under a call
+    pub max_query_depth: u32,
+    /// Cost of decrypting a single entry of the key index while the contract iterates over its
+    /// storage. Only the nodes of the index on the way to the next keys are decrypted.
+    pub external_db_scan_entry: u32,
 }
 
 impl Default for WasmCosts {
@@ -39,20 +175,77 @@ impl Default for WasmCosts {
             div: 16,
             mul: 4,
             mem: 2,
-            static_u256: 64,
-            static_address: 40,
             initial_mem: 8192,
             grow_mem: 8192,
-            memcpy: 1,
-            max_stack_height: 64 * 1024, // Assaf: I don't think this goes anywhere
-            opcodes_mul: 3,
-            opcodes_div: 8,
             external_humanize_address: 8192,
             external_canonicalize_address: 8192,
+            external_random: 8192,
+            external_secp256k1_verify: 128 * 1024,
+            external_ed25519_verify: 64 * 1024,
+            external_query_chain: 500,
+            max_query_gas_percent: 100,
+            max_query_depth: MAX_QUERY_DEPTH,
+            external_db_scan_entry: 2048,
         }
     }
 }
 
+impl WasmCosts {
+    /// Makes sure that all the costs are within the bounds the enclave is willing to run with
+    pub fn validate(&self) -> Result<(), EnclaveError> {
+        validate_costs(&[
+            ("regular", self.regular),
+            ("div", self.div),
+            ("mul", self.mul),
+            ("mem", self.mem),
+            ("initial_mem", self.initial_mem),
+            ("grow_mem", self.grow_mem),
+            ("external_humanize_address", self.external_humanize_address),
+            (
+                "external_canonicalize_address",
+                self.external_canonicalize_address,
+            ),
+            ("external_random", self.external_random),
+            ("external_secp256k1_verify", self.external_secp256k1_verify),
+            ("external_ed25519_verify", self.external_ed25519_verify),
+            ("external_query_chain", self.external_query_chain),
+            ("external_db_scan_entry", self.external_db_scan_entry),
+        ])?;
+
+        if self.max_query_gas_percent < 1 || self.max_query_gas_percent > 100 {
+            warn!(
+                "gas schedule max_query_gas_percent is out of bounds: {} (allowed 1..=100)",
+                self.max_query_gas_percent
+            );
+            return Err(EnclaveError::InvalidGasSchedule);
+        }
+
+        if self.max_query_depth > MAX_QUERY_DEPTH {
+            warn!(
+                "gas schedule max_query_depth is out of bounds: {} (allowed 0..={})",
+                self.max_query_depth, MAX_QUERY_DEPTH
+            );
+            return Err(EnclaveError::InvalidGasSchedule);
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_costs(costs: &[(&str, u32)]) -> Result<(), EnclaveError> {
+    for (name, cost) in costs.iter() {
+        if *cost < MIN_COST || *cost > MAX_COST {
+            warn!(
+                "gas schedule cost {} is out of bounds: {} (allowed {}..={})",
+                name, cost, MIN_COST, MAX_COST
+            );
+            return Err(EnclaveError::InvalidGasSchedule);
+        }
+    }
+
+    Ok(())
+}
+
 pub fn gas_rules(wasm_costs: &WasmCosts) -> rules::Set {
     rules::Set::new(wasm_costs.regular, {
         let mut vals = ::std::collections::BTreeMap::new();
@@ -81,6 +274,22 @@ pub fn gas_rules(wasm_costs: &WasmCosts) -> rules::Set {
     .with_grow_cost(wasm_costs.grow_mem)
 }
 
+/// Splits the gas used by wasm code into gas used by opcodes and gas used by growing the memory.
+/// The injected gas counter charges `grow_mem` per page before growing the memory, so
+/// the cost of memory growth follows from the amount of pages the memory grew by.
+/// Returns `(opcodes_gas, memory_growth_gas)`.
+#[cfg(any(feature = "gas-profiling", feature = "test"))]
+pub fn split_memory_growth_gas(
+    wasm_gas_used: u64,
+    grown_pages: u64,
+    wasm_costs: &WasmCosts,
+) -> (u64, u64) {
+    let memory_growth_gas = grown_pages
+        .saturating_mul(wasm_costs.grow_mem as u64)
+        .min(wasm_gas_used);
+    (wasm_gas_used - memory_growth_gas, memory_growth_gas)
+}
+
 #[derive(Debug, Clone)]
 pub struct RuntimeWasmCosts {
     pub write_value: u64,
@@ -107,3 +316,268 @@ pub struct RuntimeGas {
     pub refund: u64,
     pub costs: RuntimeWasmCosts,
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use parity_wasm::builder;
+    use parity_wasm::elements::{Instruction, Instructions, Module, ValueType};
+
+    use enclave_ffi_types::EnclaveError;
+
+    use super::{
+        gas_rules, inject_gas_counter, split_memory_growth_gas, GasSchedule, WasmCosts,
+        WasmCostsV1, MAX_QUERY_DEPTH,
+    };
+
+    /// A module with a single function that uses loads, stores, multiplications and divisions
+    fn test_module() -> Module {
+        builder::module()
+            .function()
+            .signature()
+            .with_param(ValueType::I32)
+            .build()
+            .body()
+            .with_instructions(Instructions::new(vec![
+                Instruction::GetLocal(0),
+                Instruction::I32Const(3),
+                Instruction::I32Mul,
+                Instruction::I32Const(2),
+                Instruction::I32DivU,
+                Instruction::I32Load(2, 0),
+                Instruction::Drop,
+                Instruction::I32Const(0),
+                Instruction::I32Const(1),
+                Instruction::I32Store(2, 0),
+                Instruction::End,
+            ]))
+            .build()
+            .build()
+            .memory()
+            .build()
+            .build()
+    }
+
+    /// Returns the module after gas injection, and the total gas the injected code charges
+    fn meter(costs: &WasmCosts) -> (Module, u64) {
+        let module = inject_gas_counter(test_module(), &gas_rules(costs))
+            .expect("failed to inject gas metering");
+
+        let mut total_gas = 0_u64;
+        for body in module.code_section().unwrap().bodies() {
+            let instructions = body.code().elements();
+            for pair in instructions.windows(2) {
+                if let (Instruction::I32Const(gas), Instruction::Call(_)) = (&pair[0], &pair[1]) {
+                    total_gas += *gas as u64;
+                }
+            }
+        }
+
+        (module, total_gas)
+    }
+
+    fn v1_costs() -> WasmCostsV1 {
+        WasmCostsV1 {
+            regular: 1,
+            div: 16,
+            mul: 4,
+            mem: 2,
+            initial_mem: 8192,
+            grow_mem: 8192,
+            external_humanize_address: 8192,
+            external_canonicalize_address: 8192,
+            static_u256: 64,
+            static_address: 40,
+            memcpy: 1,
+            max_stack_height: 64 * 1024,
+            opcodes_mul: 3,
+            opcodes_div: 8,
+        }
+    }
+
+    fn v1_schedule(costs: &WasmCostsV1) -> Vec<u8> {
+        serde_json::to_vec(&GasSchedule::V1(costs.clone())).unwrap()
+    }
+
+    fn v2_schedule(costs: &WasmCosts) -> Vec<u8> {
+        serde_json::to_vec(&GasSchedule::V2(costs.clone())).unwrap()
+    }
+
+    pub fn test_empty_schedule_is_default() {
+        assert_eq!(GasSchedule::parse_costs(&[]).unwrap(), WasmCosts::default());
+    }
+
+    pub fn test_v1_schedule_uses_default_newer_costs() {
+        let costs = WasmCostsV1 {
+            div: 32,
+            grow_mem: 4096,
+            external_humanize_address: 1000,
+            // Never metered, so it doesn't change the costs
+            opcodes_mul: 5,
+            ..v1_costs()
+        };
+
+        assert_eq!(
+            GasSchedule::parse_costs(&v1_schedule(&costs)).unwrap(),
+            WasmCosts {
+                div: 32,
+                grow_mem: 4096,
+                external_humanize_address: 1000,
+                ..WasmCosts::default()
+            }
+        );
+    }
+
+    pub fn test_v1_schedule_rejects_newer_costs() {
+        // Enclaves that only know V1 reject these, so the newer costs need a V2 schedule
+        for newer_cost in &["external_random", "external_db_scan_entry"] {
+            let mut schedule: serde_json::Value =
+                serde_json::from_slice(&v1_schedule(&v1_costs())).unwrap();
+            schedule["costs"][*newer_cost] = serde_json::json!(1);
+
+            assert!(matches!(
+                GasSchedule::parse_costs(&serde_json::to_vec(&schedule).unwrap()),
+                Err(EnclaveError::InvalidGasSchedule)
+            ));
+        }
+    }
+
+    pub fn test_v2_schedule_roundtrip() {
+        let costs = WasmCosts {
+            div: 32,
+            grow_mem: 4096,
+            external_random: 1000,
+            max_query_depth: 1,
+            ..WasmCosts::default()
+        };
+
+        assert_eq!(
+            GasSchedule::parse_costs(&v2_schedule(&costs)).unwrap(),
+            costs
+        );
+    }
+
+    pub fn test_schedule_rejects_out_of_bounds() {
+        let free_opcodes = WasmCosts {
+            regular: 0,
+            ..WasmCosts::default()
+        };
+        let expensive_memory = WasmCosts {
+            grow_mem: u32::MAX,
+            ..WasmCosts::default()
+        };
+        let no_query_gas = WasmCosts {
+            max_query_gas_percent: 0,
+            ..WasmCosts::default()
+        };
+        let deep_queries = WasmCosts {
+            max_query_depth: MAX_QUERY_DEPTH + 1,
+            ..WasmCosts::default()
+        };
+        let tiny_stack = WasmCostsV1 {
+            max_stack_height: 1,
+            ..v1_costs()
+        };
+        let free_memcpy = WasmCostsV1 {
+            memcpy: 0,
+            ..v1_costs()
+        };
+
+        let schedules = vec![
+            v2_schedule(&free_opcodes),
+            v2_schedule(&expensive_memory),
+            v2_schedule(&no_query_gas),
+            v2_schedule(&deep_queries),
+            v1_schedule(&tiny_stack),
+            v1_schedule(&free_memcpy),
+        ];
+        for schedule in schedules.iter() {
+            assert!(matches!(
+                GasSchedule::parse_costs(schedule),
+                Err(EnclaveError::InvalidGasSchedule)
+            ));
+        }
+    }
+
+    pub fn test_malformed_schedule_rejected() {
+        let schedules: [&[u8]; 5] = [
+            b"not json",
+            br#"{"version":"v3","costs":{}}"#,
+            br#"{"version":"v1","costs":{"regular":1}}"#,
+            br#"{"version":"v2","costs":{"regular":1}}"#,
+            br#"{"version":"v1"}"#,
+        ];
+
+        for schedule in schedules.iter() {
+            assert!(matches!(
+                GasSchedule::parse_costs(schedule),
+                Err(EnclaveError::InvalidGasSchedule)
+            ));
+        }
+
+        // Unknown fields are rejected, so a schedule for a newer version can't be silently misread
+        let mut schedule: serde_json::Value =
+            serde_json::from_slice(&v2_schedule(&WasmCosts::default())).unwrap();
+        schedule["costs"]["unknown_cost"] = serde_json::json!(1);
+        assert!(matches!(
+            GasSchedule::parse_costs(&serde_json::to_vec(&schedule).unwrap()),
+            Err(EnclaveError::InvalidGasSchedule)
+        ));
+
+        // The costs V2 dropped, because they were never metered
+        let mut schedule: serde_json::Value =
+            serde_json::from_slice(&v2_schedule(&WasmCosts::default())).unwrap();
+        schedule["costs"]["opcodes_mul"] = serde_json::json!(3);
+        assert!(matches!(
+            GasSchedule::parse_costs(&serde_json::to_vec(&schedule).unwrap()),
+            Err(EnclaveError::InvalidGasSchedule)
+        ));
+    }
+
+    pub fn test_metering_is_deterministic() {
+        let costs = GasSchedule::parse_costs(&v2_schedule(&WasmCosts::default())).unwrap();
+
+        let (first_module, first_gas) = meter(&costs);
+        let (second_module, second_gas) = meter(&costs);
+
+        assert_eq!(first_gas, second_gas);
+        assert_eq!(first_module, second_module);
+        // The default schedule and explicit schedules with the same costs meter the same way
+        assert_eq!(meter(&GasSchedule::parse_costs(&[]).unwrap()).1, first_gas);
+        assert_eq!(
+            meter(&GasSchedule::parse_costs(&v1_schedule(&v1_costs())).unwrap()).1,
+            first_gas
+        );
+    }
+
+    pub fn test_metering_follows_schedule() {
+        let (_, default_gas) = meter(&WasmCosts::default());
+
+        let expensive_div = WasmCosts {
+            div: WasmCosts::default().div + 10,
+            ..WasmCosts::default()
+        };
+        let (_, div_gas) = meter(&expensive_div);
+        assert_eq!(div_gas, default_gas + 10);
+
+        let expensive_mem = WasmCosts {
+            mem: WasmCosts::default().mem + 5,
+            ..WasmCosts::default()
+        };
+        // The module has one load and one store
+        let (_, mem_gas) = meter(&expensive_mem);
+        assert_eq!(mem_gas, default_gas + 10);
+    }
+
+    pub fn test_memory_growth_is_split_from_wasm_gas() {
+        let costs = WasmCosts::default();
+        let grow_mem = costs.grow_mem as u64;
+
+        assert_eq!(split_memory_growth_gas(1000, 0, &costs), (1000, 0));
+        assert_eq!(
+            split_memory_growth_gas(2 * grow_mem + 1000, 2, &costs),
+            (1000, 2 * grow_mem)
+        );
+        // Never attribute more gas to memory growth than was used in total
+        assert_eq!(split_memory_growth_gas(10, 1, &costs), (0, 10));
+    }
+}
@@ -6,16 +6,30 @@ use super::types::{IoNonce, SecretMessage};
 
 use crate::cosmwasm::encoding::Binary;
 use crate::cosmwasm::types::{CanonicalAddr, Coin, CosmosMsg, WasmMsg, WasmOutput};
-use crate::crypto::{AESKey, Ed25519PublicKey, Kdf, SIVEncryptable, KEY_MANAGER};
-use enclave_ffi_types::EnclaveError;
+use crate::crypto::{AESKey, Ed25519PublicKey, Kdf, KeyPair, SIVEncryptable, KEY_MANAGER};
+use enclave_ffi_types::{DeserializationTarget, EnclaveError};
 use log::*;
 use serde::Serialize;
 use serde_json::json;
 use sha2::Digest;
 
-pub fn calc_encryption_key(nonce: &IoNonce, user_public_key: &Ed25519PublicKey) -> AESKey {
-    let enclave_io_key = KEY_MANAGER.get_consensus_io_exchange_keypair().unwrap();
+pub fn calc_encryption_key(
+    nonce: &IoNonce,
+    user_public_key: &Ed25519PublicKey,
+    io_epoch: u32,
+) -> AESKey {
+    let enclave_io_key = KEY_MANAGER
+        .get_consensus_io_exchange_keypair_of_epoch(io_epoch)
+        .unwrap();
 
+    derive_encryption_key(&enclave_io_key, nonce, user_public_key)
+}
+
+fn derive_encryption_key(
+    enclave_io_key: &KeyPair,
+    nonce: &IoNonce,
+    user_public_key: &Ed25519PublicKey,
+) -> AESKey {
     let tx_encryption_ikm = enclave_io_key.diffie_hellman(user_public_key);
 
     let tx_encryption_key = AESKey::new_from_slice(&tx_encryption_ikm).derive_key_from_this(nonce);
@@ -66,14 +80,26 @@ fn b64_encode(data: &[u8]) -> String {
     base64::encode(data)
 }
 
+/// Encrypts the output for the sender of `input`, with the IO key `input` was encrypted with.
+/// Messages to other contracts are signed with the callback secret of `seed_epoch`.
 pub fn encrypt_output(
     output: Vec<u8>,
-    nonce: IoNonce,
-    user_public_key: Ed25519PublicKey,
+    input: &SecretMessage,
     contract_addr: &CanonicalAddr,
+    seed_epoch: u32,
 ) -> Result<Vec<u8>, EnclaveError> {
-    let key = calc_encryption_key(&nonce, &user_public_key);
+    let key = input.encryption_key();
 
+    encrypt_output_with_key(output, &key, input, contract_addr, seed_epoch)
+}
+
+fn encrypt_output_with_key(
+    output: Vec<u8>,
+    key: &AESKey,
+    input: &SecretMessage,
+    contract_addr: &CanonicalAddr,
+    seed_epoch: u32,
+) -> Result<Vec<u8>, EnclaveError> {
     trace!(
         "Output before encryption: {:?}",
         String::from_utf8_lossy(&output)
@@ -82,36 +108,39 @@ pub fn encrypt_output(
     let mut output: WasmOutput = serde_json::from_slice(&output).map_err(|err| {
         warn!("got an error while trying to deserialize output bytes into json");
         trace!("output: {:?} error: {:?}", output, err);
-        EnclaveError::FailedToDeserialize
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::ContractOutput,
+        }
     })?;
 
     match &mut output {
         WasmOutput::ErrObject { err } => {
-            let encrypted_err = encrypt_serializable(&key, err)?;
+            let encrypted_err = encrypt_serializable(key, err)?;
 
             // Putting the error inside a 'generic_err' envelope, so we can encrypt the error itself
             *err = json!({"generic_err":{"msg":encrypted_err}});
         }
 
         WasmOutput::OkString { ok } => {
-            *ok = encrypt_serializable(&key, ok)?;
+            *ok = encrypt_serializable(key, ok)?;
         }
 
         // Encrypt all Wasm messages (keeps Bank, Staking, etc.. as is)
         WasmOutput::OkObject { ok } => {
             for msg in &mut ok.messages {
                 if let CosmosMsg::Wasm(wasm_msg) = msg {
-                    encrypt_wasm_msg(wasm_msg, nonce, user_public_key, contract_addr)?;
+                    encrypt_wasm_msg(wasm_msg, input, contract_addr, seed_epoch)?;
                 }
             }
 
-            for log in &mut ok.log {
-                log.key = encrypt_preserialized_string(&key, &log.key)?;
-                log.value = encrypt_preserialized_string(&key, &log.value)?;
+            // The contract may choose to make some of the log attributes public
+            for log in ok.log.iter_mut().filter(|log| log.encrypted) {
+                log.key = encrypt_preserialized_string(key, &log.key)?;
+                log.value = encrypt_preserialized_string(key, &log.value)?;
             }
 
             if let Some(data) = &mut ok.data {
-                *data = Binary::from_base64(&encrypt_serializable(&key, data)?)?;
+                *data = Binary::from_base64(&encrypt_serializable(key, data)?)?;
             }
         }
     };
@@ -131,9 +160,9 @@ pub fn encrypt_output(
 
 fn encrypt_wasm_msg(
     wasm_msg: &mut WasmMsg,
-    nonce: IoNonce,
-    user_public_key: Ed25519PublicKey,
+    input: &SecretMessage,
     contract_addr: &CanonicalAddr,
+    seed_epoch: u32,
 ) -> Result<(), EnclaveError> {
     match wasm_msg {
         WasmMsg::Execute {
@@ -155,28 +184,37 @@ fn encrypt_wasm_msg(
 
             let mut msg_to_pass = SecretMessage::from_base64(
                 Binary(hash_appended_msg).to_base64(),
-                nonce,
-                user_public_key,
+                input.nonce,
+                input.user_public_key,
+                input.io_epoch,
             )?;
 
             msg_to_pass.encrypt_in_place()?;
             *msg = Binary::from(msg_to_pass.to_vec().as_slice());
 
-            *callback_sig = Some(create_callback_signature(contract_addr, &msg_to_pass, send));
+            *callback_sig = Some(create_callback_signature(
+                contract_addr,
+                &msg_to_pass,
+                send,
+                seed_epoch,
+            ));
         }
     }
 
     Ok(())
 }
 
+/// The callback secret is always the one of the epoch the chain is at, so a leaked secret of an
+/// older epoch can't be used to forge callbacks
 pub fn create_callback_signature(
     contract_addr: &CanonicalAddr,
     msg_to_sign: &SecretMessage,
     funds_to_send: &[Coin],
+    seed_epoch: u32,
 ) -> Vec<u8> {
     // Hash(Enclave_secret | sender(current contract) | msg_to_pass | sent_funds)
     let mut callback_sig_bytes = KEY_MANAGER
-        .get_consensus_callback_secret()
+        .get_consensus_callback_secret_of_epoch(seed_epoch)
         .unwrap()
         .get()
         .to_vec();
@@ -187,3 +225,115 @@ pub fn create_callback_signature(
 
     sha2::Sha256::digest(callback_sig_bytes.as_slice()).to_vec()
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::io::Read;
+    use std::untrusted::fs::File;
+
+    // Shared with the secret-client crate, which is checked against the same vectors
+    fn io_vectors() -> Value {
+        let mut vectors = vec![];
+        let mut f = File::open("../secret-client/testdata/io_vectors.json").unwrap();
+        f.read_to_end(&mut vectors).unwrap();
+        serde_json::from_slice(&vectors).unwrap()
+    }
+
+    fn vector_bytes(vectors: &Value, name: &str) -> Vec<u8> {
+        hex::decode(vectors[name].as_str().unwrap()).unwrap()
+    }
+
+    fn vector_key(vectors: &Value, name: &str) -> [u8; 32] {
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&vector_bytes(vectors, name));
+        key
+    }
+
+    fn consensus_io_key(vectors: &Value) -> KeyPair {
+        KeyPair::from(AESKey::new_from_slice(&vector_key(
+            vectors,
+            "consensus_io_private_key",
+        )))
+    }
+
+    pub fn test_encryption_key_matches_client_vectors() {
+        let vectors = io_vectors();
+        let enclave_io_key = consensus_io_key(&vectors);
+
+        assert_eq!(
+            enclave_io_key.get_pubkey(),
+            vector_key(&vectors, "consensus_io_public_key")
+        );
+
+        let key = derive_encryption_key(
+            &enclave_io_key,
+            &vector_key(&vectors, "nonce"),
+            &vector_key(&vectors, "user_public_key"),
+        );
+        assert_eq!(key.get(), &vector_key(&vectors, "encryption_key"));
+    }
+
+    pub fn test_client_message_decrypts_with_code_hash() {
+        let vectors = io_vectors();
+        let enclave_io_key = consensus_io_key(&vectors);
+
+        let secret_msg =
+            SecretMessage::from_slice(&vector_bytes(&vectors, "secret_message")).unwrap();
+        let key = derive_encryption_key(
+            &enclave_io_key,
+            &secret_msg.nonce,
+            &secret_msg.user_public_key,
+        );
+        let plaintext = key.decrypt_siv(&secret_msg.msg, None).unwrap();
+
+        let mut expected = vectors["code_hash"].as_str().unwrap().as_bytes().to_vec();
+        expected.extend_from_slice(vectors["msg"].as_str().unwrap().as_bytes());
+        assert_eq!(plaintext, expected);
+    }
+
+    pub fn test_encrypt_data_matches_client_vectors() {
+        let vectors = io_vectors();
+        let key = derive_encryption_key(
+            &consensus_io_key(&vectors),
+            &vector_key(&vectors, "nonce"),
+            &vector_key(&vectors, "user_public_key"),
+        );
+        let data = &vectors["enclave_data"];
+
+        let plaintext = base64::encode(data["plaintext"].as_str().unwrap());
+        let ciphertext = key.encrypt_siv(plaintext.as_bytes(), None).unwrap();
+        assert_eq!(
+            ciphertext,
+            hex::decode(data["ciphertext"].as_str().unwrap()).unwrap()
+        );
+    }
+
+    pub fn test_encrypt_output_matches_client_vectors() {
+        let vectors = io_vectors();
+        let nonce = vector_key(&vectors, "nonce");
+        let user_public_key = vector_key(&vectors, "user_public_key");
+        let key = derive_encryption_key(&consensus_io_key(&vectors), &nonce, &user_public_key);
+        let input = SecretMessage {
+            nonce,
+            user_public_key,
+            msg: vec![],
+            io_epoch: 0,
+        };
+
+        for output in vectors["outputs"].as_array().unwrap() {
+            let encrypted = encrypt_output_with_key(
+                serde_json::to_vec(&output["plaintext"]).unwrap(),
+                &key,
+                &input,
+                &CanonicalAddr::default(),
+                0,
+            )
+            .unwrap();
+
+            let encrypted: Value = serde_json::from_slice(&encrypted).unwrap();
+            assert_eq!(encrypted, output["encrypted"]);
+        }
+    }
+}
@@ -0,0 +1,765 @@
+//! The index of the keys of a contract, which lets the contract iterate over its storage in order.
+//!
+//! The keys are hashed before they reach the host, so the host can't keep them in order for us.
+//! Instead, the plaintext keys are kept in a B+ tree, whose nodes are stored in the contract storage
+//! like any other value, and are encrypted by `IndexStorage`. A scan only reads the nodes on the
+//! path to the leaf it continues from, so it doesn't depend on the size of the whole index.
+//!
+//! Every node holds the hashes of its children, and a header holds the hash of the root. A node
+//! that the host drops, swaps with another node, or replaces with an older version of itself fails
+//! to verify. The host can only roll back the header, and with it the whole index, just like it can
+//! roll back any other value of the contract.
+
+use std::collections::VecDeque;
+
+use log::*;
+
+use super::errors::WasmEngineError;
+use crate::crypto::{sha_256, HASH_SIZE};
+
+/// The key of the header. Nodes are kept under `INDEX_KEY_PREFIX || id`, where the id is a big
+/// endian u64. Field names of values are 32 byte digests, so they can never collide with these.
+const INDEX_KEY_PREFIX: &[u8] = b"\x00idx";
+const HEADER_LENGTH: usize = 8 + HASH_SIZE + 8;
+
+/// A node is split in two when it has more entries than this
+const MAX_NODE_ENTRIES: usize = 64;
+
+const LEAF: u8 = 0;
+const INTERNAL: u8 = 1;
+
+/// Where the nodes of the index are kept. The values are plaintext, it's up to the implementation
+/// to encrypt them.
+pub trait IndexStorage {
+    fn read(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, WasmEngineError>;
+    fn write(&mut self, key: &[u8], value: &[u8]) -> Result<(), WasmEngineError>;
+    fn remove(&mut self, key: &[u8]);
+}
+
+struct Header {
+    root: u64,
+    root_hash: [u8; HASH_SIZE],
+    /// The id of the next node that will be created
+    next_id: u64,
+}
+
+/// Nodes are never empty. They are removed from the tree once their last entry is removed, but
+/// they aren't merged with their siblings when they shrink.
+#[derive(Debug, PartialEq)]
+enum Node {
+    /// The keys, in ascending order
+    Leaf(Vec<Vec<u8>>),
+    /// The children, in ascending order of their keys
+    Internal(Vec<Child>),
+}
+
+#[derive(Debug, PartialEq)]
+struct Child {
+    /// The smallest key under the child
+    min_key: Vec<u8>,
+    id: u64,
+    hash: [u8; HASH_SIZE],
+}
+
+impl Node {
+    fn len(&self) -> usize {
+        match self {
+            Node::Leaf(keys) => keys.len(),
+            Node::Internal(children) => children.len(),
+        }
+    }
+
+    fn min_key(&self) -> &[u8] {
+        match self {
+            Node::Leaf(keys) => &keys[0],
+            Node::Internal(children) => &children[0].min_key,
+        }
+    }
+
+    /// Moves the upper half of the entries to a new node
+    fn split_off(&mut self) -> Node {
+        let at = self.len() / 2;
+        match self {
+            Node::Leaf(keys) => Node::Leaf(keys.split_off(at)),
+            Node::Internal(children) => Node::Internal(children.split_off(at)),
+        }
+    }
+
+    /// Leaves are encoded as `LEAF` followed by `key_len || key` for every key.
+    /// Internal nodes are encoded as `INTERNAL` followed by `key_len || min_key || id || hash` for
+    /// every child. The lengths and ids are big endian.
+    fn encode(&self) -> Vec<u8> {
+        let mut data = vec![];
+        match self {
+            Node::Leaf(keys) => {
+                data.push(LEAF);
+                for key in keys {
+                    data.extend_from_slice(&(key.len() as u32).to_be_bytes());
+                    data.extend_from_slice(key);
+                }
+            }
+            Node::Internal(children) => {
+                data.push(INTERNAL);
+                for child in children {
+                    data.extend_from_slice(&(child.min_key.len() as u32).to_be_bytes());
+                    data.extend_from_slice(&child.min_key);
+                    data.extend_from_slice(&child.id.to_be_bytes());
+                    data.extend_from_slice(&child.hash);
+                }
+            }
+        }
+        data
+    }
+
+    fn decode(data: &[u8]) -> Option<Node> {
+        let (kind, mut data) = data.split_first()?;
+        let node = match *kind {
+            LEAF => {
+                let mut keys = vec![];
+                while !data.is_empty() {
+                    keys.push(take_chunk(&mut data)?.to_vec());
+                }
+                Node::Leaf(keys)
+            }
+            INTERNAL => {
+                let mut children = vec![];
+                while !data.is_empty() {
+                    let min_key = take_chunk(&mut data)?.to_vec();
+                    let id = u64::from_be_bytes(take_array(&mut data)?);
+                    let hash = take_array(&mut data)?;
+                    children.push(Child { min_key, id, hash });
+                }
+                Node::Internal(children)
+            }
+            _ => return None,
+        };
+
+        if node.len() == 0 {
+            return None;
+        }
+        Some(node)
+    }
+}
+
+fn take_chunk<'a>(data: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = u32::from_be_bytes(take_array(data)?) as usize;
+    if data.len() < len {
+        return None;
+    }
+    let (chunk, rest) = data.split_at(len);
+    *data = rest;
+    Some(chunk)
+}
+
+fn take_array<A: Default + AsMut<[u8]>>(data: &mut &[u8]) -> Option<A> {
+    let mut array = A::default();
+    let len = array.as_mut().len();
+    if data.len() < len {
+        return None;
+    }
+    let (chunk, rest) = data.split_at(len);
+    array.as_mut().copy_from_slice(chunk);
+    *data = rest;
+    Some(array)
+}
+
+fn node_key(id: u64) -> Vec<u8> {
+    let mut key = INDEX_KEY_PREFIX.to_vec();
+    key.extend_from_slice(&id.to_be_bytes());
+    key
+}
+
+fn read_header<S: IndexStorage>(storage: &mut S) -> Result<Option<Header>, WasmEngineError> {
+    let data = match storage.read(INDEX_KEY_PREFIX)? {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+
+    let mut rest = data.as_slice();
+    match (
+        take_array(&mut rest),
+        take_array(&mut rest),
+        take_array(&mut rest),
+    ) {
+        (Some(root), Some(root_hash), Some(next_id)) if rest.is_empty() => Ok(Some(Header {
+            root: u64::from_be_bytes(root),
+            root_hash,
+            next_id: u64::from_be_bytes(next_id),
+        })),
+        _ => {
+            warn!("the header of the key index is malformed");
+            Err(WasmEngineError::HostMisbehavior)
+        }
+    }
+}
+
+fn write_header<S: IndexStorage>(storage: &mut S, header: &Header) -> Result<(), WasmEngineError> {
+    let mut data = Vec::with_capacity(HEADER_LENGTH);
+    data.extend_from_slice(&header.root.to_be_bytes());
+    data.extend_from_slice(&header.root_hash);
+    data.extend_from_slice(&header.next_id.to_be_bytes());
+    storage.write(INDEX_KEY_PREFIX, &data)
+}
+
+/// Reads a node, and checks that it's the one its parent (or the header) points to
+fn read_node<S: IndexStorage>(
+    storage: &mut S,
+    id: u64,
+    hash: &[u8; HASH_SIZE],
+) -> Result<Node, WasmEngineError> {
+    let data = storage.read(&node_key(id))?.ok_or_else(|| {
+        warn!("node {} of the key index is missing", id);
+        WasmEngineError::HostMisbehavior
+    })?;
+
+    if sha_256(&data) != *hash {
+        warn!("node {} of the key index doesn't match its hash", id);
+        return Err(WasmEngineError::HostMisbehavior);
+    }
+
+    Node::decode(&data).ok_or_else(|| {
+        warn!("node {} of the key index is malformed", id);
+        WasmEngineError::HostMisbehavior
+    })
+}
+
+/// Writes a node, and returns the entry of its parent that points to it
+fn write_node<S: IndexStorage>(
+    storage: &mut S,
+    id: u64,
+    node: &Node,
+) -> Result<Child, WasmEngineError> {
+    let data = node.encode();
+    storage.write(&node_key(id), &data)?;
+
+    Ok(Child {
+        min_key: node.min_key().to_vec(),
+        id,
+        hash: sha_256(&data),
+    })
+}
+
+/// The position of the child whose keys `key` would be among
+fn child_position(children: &[Child], key: &[u8]) -> usize {
+    children
+        .iter()
+        .take_while(|child| child.min_key.as_slice() <= key)
+        .count()
+        .saturating_sub(1)
+}
+
+/// Adds `key` to the index. Returns whether it was missing.
+pub fn insert<S: IndexStorage>(storage: &mut S, key: &[u8]) -> Result<bool, WasmEngineError> {
+    let mut header = match read_header(storage)? {
+        Some(header) => header,
+        None => {
+            let root = write_node(storage, 0, &Node::Leaf(vec![key.to_vec()]))?;
+            write_header(
+                storage,
+                &Header {
+                    root: root.id,
+                    root_hash: root.hash,
+                    next_id: 1,
+                },
+            )?;
+            return Ok(true);
+        }
+    };
+
+    let root_id = header.root;
+    let root = read_node(storage, root_id, &header.root_hash)?;
+    let mut children = match insert_into(storage, &mut header, root_id, root, key)? {
+        Some(children) => children,
+        None => return Ok(false),
+    };
+
+    // The root was split, so the tree grows by a level
+    if children.len() > 1 {
+        let id = header.next_id;
+        header.next_id += 1;
+        children = vec![write_node(storage, id, &Node::Internal(children))?];
+    }
+    header.root = children[0].id;
+    header.root_hash = children[0].hash;
+    write_header(storage, &header)?;
+
+    Ok(true)
+}
+
+/// Inserts `key` under the node `id`. Returns `None` if the key is already there, or else the
+/// entries the parent should have instead of the one of this node, which are two if it was split.
+fn insert_into<S: IndexStorage>(
+    storage: &mut S,
+    header: &mut Header,
+    id: u64,
+    node: Node,
+    key: &[u8],
+) -> Result<Option<Vec<Child>>, WasmEngineError> {
+    let mut node = match node {
+        Node::Leaf(mut keys) => match keys.binary_search_by(|other| other.as_slice().cmp(key)) {
+            Ok(_) => return Ok(None),
+            Err(position) => {
+                keys.insert(position, key.to_vec());
+                Node::Leaf(keys)
+            }
+        },
+        Node::Internal(mut children) => {
+            let position = child_position(&children, key);
+            let child = read_node(storage, children[position].id, &children[position].hash)?;
+            match insert_into(storage, header, children[position].id, child, key)? {
+                Some(replacement) => {
+                    children.splice(position..=position, replacement);
+                    Node::Internal(children)
+                }
+                None => return Ok(None),
+            }
+        }
+    };
+
+    let mut replacement = vec![];
+    let sibling = if node.len() > MAX_NODE_ENTRIES {
+        Some(node.split_off())
+    } else {
+        None
+    };
+    replacement.push(write_node(storage, id, &node)?);
+    if let Some(sibling) = sibling {
+        let sibling_id = header.next_id;
+        header.next_id += 1;
+        replacement.push(write_node(storage, sibling_id, &sibling)?);
+    }
+
+    Ok(Some(replacement))
+}
+
+/// Removes `key` from the index. Returns whether it was there.
+pub fn remove<S: IndexStorage>(storage: &mut S, key: &[u8]) -> Result<bool, WasmEngineError> {
+    let mut header = match read_header(storage)? {
+        Some(header) => header,
+        None => return Ok(false),
+    };
+
+    let root = read_node(storage, header.root, &header.root_hash)?;
+    match remove_from(storage, header.root, root, key)? {
+        None => return Ok(false),
+        Some(Some(root)) => {
+            header.root_hash = root.hash;
+            write_header(storage, &header)?;
+        }
+        // The last key was removed
+        Some(None) => storage.remove(INDEX_KEY_PREFIX),
+    }
+
+    Ok(true)
+}
+
+/// Removes `key` from under the node `id`. Returns `None` if the key isn't there, or else the
+/// entry the parent should have instead of the one of this node, which is `None` if the node was
+/// removed.
+fn remove_from<S: IndexStorage>(
+    storage: &mut S,
+    id: u64,
+    node: Node,
+    key: &[u8],
+) -> Result<Option<Option<Child>>, WasmEngineError> {
+    let node = match node {
+        Node::Leaf(mut keys) => match keys.binary_search_by(|other| other.as_slice().cmp(key)) {
+            Ok(position) => {
+                keys.remove(position);
+                Node::Leaf(keys)
+            }
+            Err(_) => return Ok(None),
+        },
+        Node::Internal(mut children) => {
+            let position = child_position(&children, key);
+            let child = read_node(storage, children[position].id, &children[position].hash)?;
+            match remove_from(storage, children[position].id, child, key)? {
+                Some(Some(child)) => children[position] = child,
+                Some(None) => {
+                    children.remove(position);
+                }
+                None => return Ok(None),
+            }
+            Node::Internal(children)
+        }
+    };
+
+    if node.len() == 0 {
+        storage.remove(&node_key(id));
+        return Ok(Some(None));
+    }
+    write_node(storage, id, &node).map(|child| Some(Some(child)))
+}
+
+/// Where a scan continues from
+enum Bound<'a> {
+    /// The keys from this one (inclusive) and up, in ascending order
+    From(&'a [u8]),
+    /// The keys below this one (exclusive), or all the keys if there's none, in descending order
+    Before(Option<&'a [u8]>),
+}
+
+/// Returns the keys within `bound` of the first leaf that has any, in the order of the scan, and
+/// the number of entries of the nodes that were read to find them.
+fn seek<S: IndexStorage>(
+    storage: &mut S,
+    bound: &Bound,
+) -> Result<(Vec<Vec<u8>>, u64), WasmEngineError> {
+    let header = match read_header(storage)? {
+        Some(header) => header,
+        None => return Ok((vec![], 0)),
+    };
+
+    let mut entries_read = 0;
+    let root = read_node(storage, header.root, &header.root_hash)?;
+    let keys = seek_in(storage, root, bound, &mut entries_read)?;
+
+    Ok((keys, entries_read))
+}
+
+fn seek_in<S: IndexStorage>(
+    storage: &mut S,
+    node: Node,
+    bound: &Bound,
+    entries_read: &mut u64,
+) -> Result<Vec<Vec<u8>>, WasmEngineError> {
+    *entries_read = entries_read.saturating_add(node.len() as u64);
+
+    let children = match node {
+        Node::Leaf(keys) => {
+            return Ok(match bound {
+                Bound::From(start) => keys
+                    .into_iter()
+                    .filter(|key| key.as_slice() >= *start)
+                    .collect(),
+                Bound::Before(end) => keys
+                    .into_iter()
+                    .rev()
+                    .filter(|key| end.map_or(true, |end| key.as_slice() < end))
+                    .collect(),
+            })
+        }
+        Node::Internal(children) => children,
+    };
+
+    // The children that may have keys within the bound, in the order of the scan. Only the first
+    // one may have none, in which case the next one has.
+    let candidates: Vec<&Child> = match bound {
+        Bound::From(start) => children[child_position(&children, start)..]
+            .iter()
+            .collect(),
+        Bound::Before(end) => {
+            let count = match end {
+                Some(end) => children
+                    .iter()
+                    .take_while(|child| child.min_key.as_slice() < *end)
+                    .count(),
+                None => children.len(),
+            };
+            children[..count].iter().rev().collect()
+        }
+    };
+
+    for child in candidates {
+        let node = read_node(storage, child.id, &child.hash)?;
+        let keys = seek_in(storage, node, bound, entries_read)?;
+        if !keys.is_empty() {
+            return Ok(keys);
+        }
+    }
+
+    Ok(vec![])
+}
+
+/// An iteration over the keys in a range of the index, which reads a leaf at a time.
+///
+/// The keys of a leaf are kept until they are returned, so keys that are added to it in the
+/// meantime are skipped, and keys that are removed from it are still returned.
+pub struct KeyIterator {
+    descending: bool,
+    /// Where the keys that weren't read yet start, see `Bound`. `None` once the range was exhausted.
+    position: Option<Option<Vec<u8>>>,
+    /// The other end of the range, where the iteration stops
+    limit: Option<Vec<u8>>,
+    /// The keys that were read but weren't returned yet
+    keys: VecDeque<Vec<u8>>,
+}
+
+impl KeyIterator {
+    /// Iterates over the keys in `[start, end)`, where `None` means there's no limit
+    pub fn new(start: Option<Vec<u8>>, end: Option<Vec<u8>>, descending: bool) -> Self {
+        let (position, limit) = if descending {
+            (end, start)
+        } else {
+            (start, end)
+        };
+
+        Self {
+            descending,
+            position: Some(position),
+            limit,
+            keys: VecDeque::new(),
+        }
+    }
+
+    /// Returns the next key, and the number of entries of the nodes that were read to find it
+    pub fn next<S: IndexStorage>(
+        &mut self,
+        storage: &mut S,
+    ) -> Result<(Option<Vec<u8>>, u64), WasmEngineError> {
+        let mut entries_read = 0;
+
+        if self.keys.is_empty() {
+            if let Some(position) = self.position.take() {
+                let bound = if self.descending {
+                    Bound::Before(position.as_deref())
+                } else {
+                    Bound::From(position.as_deref().unwrap_or_default())
+                };
+                let (keys, entries) = seek(storage, &bound)?;
+                entries_read = entries;
+
+                // The next leaf starts right after the last key of this one
+                let descending = self.descending;
+                self.position = keys.last().map(|last| {
+                    let mut next = last.clone();
+                    if !descending {
+                        next.push(0);
+                    }
+                    Some(next)
+                });
+                self.keys = keys.into();
+            }
+        }
+
+        let key = match self.keys.pop_front() {
+            Some(key) => key,
+            None => return Ok((None, entries_read)),
+        };
+
+        let in_range = self.limit.as_ref().map_or(true, |limit| {
+            if self.descending {
+                key >= *limit
+            } else {
+                key < *limit
+            }
+        });
+        if !in_range {
+            self.keys.clear();
+            self.position = None;
+            return Ok((None, entries_read));
+        }
+
+        Ok((Some(key), entries_read))
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct PlaintextStorage(BTreeMap<Vec<u8>, Vec<u8>>);
+
+    impl IndexStorage for PlaintextStorage {
+        fn read(&mut self, key: &[u8]) -> Result<Option<Vec<u8>>, WasmEngineError> {
+            Ok(self.0.get(key).cloned())
+        }
+
+        fn write(&mut self, key: &[u8], value: &[u8]) -> Result<(), WasmEngineError> {
+            self.0.insert(key.to_vec(), value.to_vec());
+            Ok(())
+        }
+
+        fn remove(&mut self, key: &[u8]) {
+            self.0.remove(key);
+        }
+    }
+
+    /// Enough keys for the tree to have three levels, inserted out of order
+    fn keys() -> Vec<Vec<u8>> {
+        (0..5000_u32)
+            .map(|i| (i.wrapping_mul(7919) % 5000).to_be_bytes().to_vec())
+            .collect()
+    }
+
+    fn collect(
+        storage: &mut PlaintextStorage,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        descending: bool,
+    ) -> Vec<Vec<u8>> {
+        let mut iterator = KeyIterator::new(
+            start.map(|start| start.to_vec()),
+            end.map(|end| end.to_vec()),
+            descending,
+        );
+        let mut keys = vec![];
+        while let (Some(key), _entries_read) = iterator.next(storage).unwrap() {
+            keys.push(key);
+        }
+        keys
+    }
+
+    fn index_of(keys: &[Vec<u8>]) -> PlaintextStorage {
+        let mut storage = PlaintextStorage::default();
+        for key in keys {
+            assert!(insert(&mut storage, key).unwrap());
+        }
+        storage
+    }
+
+    pub fn test_key_index_is_sorted_and_filtered() {
+        let keys = keys();
+        let mut storage = index_of(&keys);
+
+        let mut sorted = keys;
+        sorted.sort();
+        assert_eq!(collect(&mut storage, None, None, false), sorted);
+
+        let mut reversed = sorted.clone();
+        reversed.reverse();
+        assert_eq!(collect(&mut storage, None, None, true), reversed);
+
+        let start = 1000_u32.to_be_bytes();
+        let end = 3000_u32.to_be_bytes();
+        assert_eq!(
+            collect(&mut storage, Some(&start), Some(&end), false),
+            sorted[1000..3000].to_vec()
+        );
+        assert_eq!(
+            collect(&mut storage, Some(&start), Some(&end), true),
+            reversed[2000..4000].to_vec()
+        );
+        // Bounds that aren't keys themselves
+        assert_eq!(
+            collect(
+                &mut storage,
+                Some(b"\x00\x00\x03"),
+                Some(b"\x00\x00\x04\x00\x01"),
+                false
+            ),
+            sorted[768..1025].to_vec()
+        );
+        assert!(collect(&mut storage, Some(&end), Some(&start), false).is_empty());
+        assert!(collect(&mut storage, Some(b"\x01"), None, false).is_empty());
+    }
+
+    pub fn test_key_index_includes_empty_key() {
+        let mut storage = index_of(&[b"a".to_vec(), b"".to_vec()]);
+
+        assert_eq!(
+            collect(&mut storage, None, None, false),
+            vec![b"".to_vec(), b"a".to_vec()]
+        );
+        assert_eq!(
+            collect(&mut storage, Some(b""), Some(b"a"), true),
+            vec![b"".to_vec()]
+        );
+    }
+
+    pub fn test_key_index_inserts_and_removes_once() {
+        let keys = keys();
+        let mut storage = index_of(&keys);
+
+        assert!(!insert(&mut storage, &keys[0]).unwrap());
+        for key in keys.iter().step_by(2) {
+            assert!(remove(&mut storage, key).unwrap());
+            assert!(!remove(&mut storage, key).unwrap());
+        }
+
+        let mut left: Vec<_> = keys.iter().skip(1).step_by(2).cloned().collect();
+        left.sort();
+        assert_eq!(collect(&mut storage, None, None, false), left);
+
+        // Empty nodes are removed, so nothing is left once all the keys are removed
+        for key in &left {
+            assert!(remove(&mut storage, key).unwrap());
+        }
+        assert!(storage.0.is_empty());
+        assert!(collect(&mut storage, None, None, false).is_empty());
+    }
+
+    pub fn test_key_index_reads_a_leaf_at_a_time() {
+        let mut storage = index_of(&keys());
+
+        let mut iterator = KeyIterator::new(None, None, false);
+        let (_key, entries_read) = iterator.next(&mut storage).unwrap();
+        // The path to the first leaf, not the whole index
+        assert!(entries_read > 0 && entries_read <= 3 * MAX_NODE_ENTRIES as u64);
+        let (_key, entries_read) = iterator.next(&mut storage).unwrap();
+        assert_eq!(entries_read, 0);
+    }
+
+    pub fn test_key_index_detects_host_tampering() {
+        let keys = keys();
+        let storage = index_of(&keys);
+        let node_keys: Vec<Vec<u8>> = storage
+            .0
+            .keys()
+            .filter(|key| key.as_slice() != INDEX_KEY_PREFIX)
+            .cloned()
+            .collect();
+
+        let assert_tampered = |mut storage: PlaintextStorage| {
+            let mut iterator = KeyIterator::new(None, None, false);
+            let result =
+                (0..keys.len() + 1).try_for_each(|_| iterator.next(&mut storage).map(|_| ()));
+            assert!(matches!(result, Err(WasmEngineError::HostMisbehavior)));
+        };
+
+        // A dropped node
+        let mut dropped = PlaintextStorage(storage.0.clone());
+        dropped.0.remove(&node_keys[1]);
+        assert_tampered(dropped);
+
+        // Swapped nodes
+        let mut swapped = PlaintextStorage(storage.0.clone());
+        let first = swapped.0[&node_keys[1]].clone();
+        let second = swapped.0[&node_keys[2]].clone();
+        swapped.0.insert(node_keys[1].clone(), second);
+        swapped.0.insert(node_keys[2].clone(), first);
+        assert_tampered(swapped);
+
+        // An older version of a node, which had a key that was removed since
+        let mut replayed = PlaintextStorage(storage.0.clone());
+        let removed = replayed
+            .0
+            .iter()
+            .find(|(key, value)| key.as_slice() != INDEX_KEY_PREFIX && value[0] == LEAF)
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .unwrap();
+        let leaf_key = Node::decode(&removed.1).unwrap().min_key().to_vec();
+        assert!(remove(&mut replayed, &leaf_key).unwrap());
+        replayed.0.insert(removed.0, removed.1);
+        assert_tampered(replayed);
+    }
+
+    pub fn test_key_index_rejects_malformed_nodes() {
+        let malformed: [&[u8]; 5] = [
+            // no kind
+            &[],
+            // an unknown kind
+            &[2, 0, 0, 0, 0],
+            // an empty node
+            &[LEAF],
+            // a truncated key
+            &[LEAF, 0, 0, 0, 2, b'k'],
+            // a child without a hash
+            &[INTERNAL, 0, 0, 0, 1, b'k', 0, 0, 0, 0, 0, 0, 0, 1],
+        ];
+
+        for data in malformed.iter() {
+            assert_eq!(Node::decode(data), None);
+        }
+
+        let node = Node::Internal(vec![Child {
+            min_key: b"k".to_vec(),
+            id: 1,
+            hash: [7; HASH_SIZE],
+        }]);
+        assert_eq!(Node::decode(&node.encode()), Some(node));
+    }
+}
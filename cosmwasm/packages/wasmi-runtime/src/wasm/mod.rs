@@ -4,12 +4,20 @@ mod db;
 mod errors;
 mod gas;
 mod io;
+mod key_index;
 mod memory;
+mod module_cache;
+mod query_batch;
 mod query_chain;
 mod runtime;
+mod storage_cache;
+#[cfg(feature = "native")]
+pub mod testing;
 mod types;
 
-pub use contract_operations::{handle, init, query};
+pub use contract_operations::{handle, init, migrate, query, reissue_contract_proofs};
+pub use module_cache::stats as module_cache_stats;
+pub use query_batch::query_batch;
 
 #[cfg(feature = "test")]
 pub mod tests {
@@ -22,6 +30,53 @@ pub mod tests {
 
         count_failures!(failures, {
             types::tests::test_new_from_slice();
+            errors::tests::test_engine_errors_keep_their_detail();
+            io::tests::test_encryption_key_matches_client_vectors();
+            io::tests::test_client_message_decrypts_with_code_hash();
+            io::tests::test_encrypt_output_matches_client_vectors();
+            io::tests::test_encrypt_data_matches_client_vectors();
+            contract_validation::tests::test_query_permit_is_verified();
+            contract_validation::tests::test_query_permit_with_wrong_signature_is_rejected();
+            contract_validation::tests::test_query_permit_of_another_chain_is_rejected();
+            contract_validation::tests::test_query_permit_without_permit_is_rejected();
+            contract_validation::tests::test_query_without_permit_is_unchanged();
+            contract_validation::tests::test_random_key_changes_with_every_call();
+            contract_validation::tests::test_admin_proof_is_bound_to_admin_and_code();
+            contract_validation::tests::test_contract_key_needs_a_proof_after_genesis();
+            gas::tests::test_empty_schedule_is_default();
+            gas::tests::test_v1_schedule_uses_default_newer_costs();
+            gas::tests::test_v1_schedule_rejects_newer_costs();
+            gas::tests::test_v2_schedule_roundtrip();
+            gas::tests::test_schedule_rejects_out_of_bounds();
+            gas::tests::test_malformed_schedule_rejected();
+            gas::tests::test_metering_is_deterministic();
+            gas::tests::test_metering_follows_schedule();
+            gas::tests::test_memory_growth_is_split_from_wasm_gas();
+            module_cache::tests::test_lru_cache_counts_hits_and_misses();
+            module_cache::tests::test_lru_cache_evicts_least_recently_used();
+            module_cache::tests::test_lru_cache_replaces_existing_entry();
+            module_cache::tests::test_lru_cache_with_no_capacity_caches_nothing();
+            query_chain::tests::test_query_gas_budget();
+            query_chain::tests::test_check_query_gas();
+            query_batch::tests::test_decode_query_batch();
+            query_batch::tests::test_decode_empty_query_batch();
+            query_batch::tests::test_decode_query_batch_rejects_unknown_code();
+            query_batch::tests::test_decode_query_batch_rejects_truncated_input();
+            storage_cache::tests::test_storage_cache_reads_each_key_from_host_once();
+            storage_cache::tests::test_storage_cache_caches_missing_keys();
+            storage_cache::tests::test_storage_cache_reads_its_own_writes();
+            storage_cache::tests::test_storage_cache_keeps_the_order_of_operations();
+            storage_cache::tests::test_storage_cache_charges_writes_when_buffered();
+            db::tests::test_genesis_epoch_values_are_untagged();
+            db::tests::test_values_of_later_epochs_are_tagged();
+            db::tests::test_values_of_unknown_epochs_fail_to_decrypt();
+            db::tests::test_values_from_before_the_key_index_are_not_indexed();
+            key_index::tests::test_key_index_is_sorted_and_filtered();
+            key_index::tests::test_key_index_includes_empty_key();
+            key_index::tests::test_key_index_inserts_and_removes_once();
+            key_index::tests::test_key_index_reads_a_leaf_at_a_time();
+            key_index::tests::test_key_index_detects_host_tampering();
+            key_index::tests::test_key_index_rejects_malformed_nodes();
             // types::tests::test_msg_decrypt();
         });
 
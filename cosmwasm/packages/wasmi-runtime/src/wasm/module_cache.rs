@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+#[cfg(feature = "native")]
+use std::sync::Mutex as SgxMutex;
+#[cfg(not(feature = "native"))]
+use std::sync::SgxMutex;
+
+use lazy_static::lazy_static;
+use log::*;
+use wasmi::Module;
+
+use enclave_ffi_types::{EnclaveError, ModuleCacheStats};
+
+use crate::crypto::HASH_SIZE;
+
+use super::gas::WasmCosts;
+
+/// The maximum amount of prepared modules that are kept in the enclave.
+/// Prepared modules are big, and the enclave heap is small.
+const MODULE_CACHE_SIZE: usize = 16;
+
+/// Modules are prepared with a specific gas schedule, so it's a part of the key
+type ModuleCacheKey = ([u8; HASH_SIZE], WasmCosts);
+
+lazy_static! {
+    /// This cache holds modules that were already deserialized, validated and injected with
+    /// gas metering, so that contracts that are called often don't have to be prepared again.
+    static ref MODULE_CACHE: SgxMutex<LruCache<ModuleCacheKey, Arc<Module>>> =
+        SgxMutex::new(LruCache::new(MODULE_CACHE_SIZE));
+}
+
+/// Returns the prepared module of the contract with `code_hash`, as prepared for `wasm_costs`.
+/// If it isn't cached, `prepare` is called to create it, and the result is cached.
+pub fn get_or_prepare<F>(
+    code_hash: [u8; HASH_SIZE],
+    wasm_costs: &WasmCosts,
+    prepare: F,
+) -> Result<Arc<Module>, EnclaveError>
+where
+    F: FnOnce() -> Result<Module, EnclaveError>,
+{
+    let key = (code_hash, wasm_costs.clone());
+
+    // The lock is not held while preparing the module, as that takes a while
+    if let Some(module) = MODULE_CACHE.lock().unwrap().get(&key) {
+        trace!(
+            "Found prepared module for code hash {:?}",
+            hex::encode(code_hash)
+        );
+        return Ok(module);
+    }
+
+    let module = Arc::new(prepare()?);
+    MODULE_CACHE.lock().unwrap().insert(key, module.clone());
+
+    Ok(module)
+}
+
+/// Returns the amount of hits and misses of the module cache since the enclave was loaded
+pub fn stats() -> ModuleCacheStats {
+    MODULE_CACHE.lock().unwrap().stats()
+}
+
+/// A simple cache that evicts the least recently used entry when it's full.
+/// The cache is small, so a linear scan is cheaper than maintaining a map alongside the order.
+struct LruCache<K, V> {
+    capacity: usize,
+    /// The least recently used entry is at the front
+    entries: VecDeque<(K, V)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl<K: PartialEq, V: Clone> LruCache<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn position(&self, key: &K) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|(entry_key, _)| entry_key == key)
+    }
+
+    fn get(&mut self, key: &K) -> Option<V> {
+        let entry = self
+            .position(key)
+            .and_then(|index| self.entries.remove(index));
+
+        match entry {
+            Some(entry) => {
+                self.hits = self.hits.saturating_add(1);
+                let value = entry.1.clone();
+                self.entries.push_back(entry);
+                Some(value)
+            }
+            None => {
+                self.misses = self.misses.saturating_add(1);
+                None
+            }
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        // Another call may have prepared the same module in the meantime
+        if let Some(index) = self.position(&key) {
+            self.entries.remove(index);
+        } else if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+
+        self.entries.push_back((key, value));
+    }
+
+    fn stats(&self) -> ModuleCacheStats {
+        ModuleCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::LruCache;
+
+    pub fn test_lru_cache_counts_hits_and_misses() {
+        let mut cache = LruCache::new(2);
+
+        assert_eq!(cache.get(&1), None::<u8>);
+        cache.insert(1, 10);
+        assert_eq!(cache.get(&1), Some(10));
+        assert_eq!(cache.get(&1), Some(10));
+        assert_eq!(cache.get(&2), None);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 2);
+    }
+
+    pub fn test_lru_cache_evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+
+        cache.insert(1, 10);
+        cache.insert(2, 20);
+        // Using 1 makes 2 the least recently used entry
+        assert_eq!(cache.get(&1), Some(10));
+        cache.insert(3, 30);
+
+        assert_eq!(cache.get(&2), None);
+        assert_eq!(cache.get(&1), Some(10));
+        assert_eq!(cache.get(&3), Some(30));
+    }
+
+    pub fn test_lru_cache_replaces_existing_entry() {
+        let mut cache = LruCache::new(2);
+
+        cache.insert(1, 10);
+        cache.insert(2, 20);
+        cache.insert(1, 11);
+
+        // Replacing an entry must not evict another one
+        assert_eq!(cache.get(&1), Some(11));
+        assert_eq!(cache.get(&2), Some(20));
+    }
+
+    pub fn test_lru_cache_with_no_capacity_caches_nothing() {
+        let mut cache = LruCache::new(0);
+
+        cache.insert(1, 10);
+        assert_eq!(cache.get(&1), None);
+    }
+}
@@ -0,0 +1,176 @@
+use log::*;
+
+use enclave_ffi_types::{Ctx, EnclaveError};
+
+use crate::results::QuerySuccess;
+
+use super::contract_operations::query;
+
+/// A batch of queries as sent by the host.
+/// Many queries in a batch usually go to the same contract, so every code is only sent once.
+struct QueryBatch<'a> {
+    codes: Vec<&'a [u8]>,
+    /// The index of the code in `codes`, and the query message
+    queries: Vec<(usize, &'a [u8])>,
+}
+
+/// Runs a batch of queries. The queries share `gas_limit`, and `used_gas` is the total gas they used.
+/// Once the gas runs out, the rest of the queries fail without being executed.
+///
+/// Each query runs against its own context, as queries to different contracts use different storage.
+pub fn query_batch(
+    contexts: &[Ctx],
+    gas_limit: u64,
+    used_gas: &mut u64,
+    gas_schedule: &[u8],
+    batch: &[u8],
+) -> Result<Vec<Result<QuerySuccess, EnclaveError>>, EnclaveError> {
+    let batch = decode_query_batch(batch)?;
+
+    if batch.queries.len() != contexts.len() {
+        warn!(
+            "Got a batch of {} queries with {} contexts",
+            batch.queries.len(),
+            contexts.len()
+        );
+        return Err(EnclaveError::FailedFunctionCall);
+    }
+
+    let mut results = Vec::with_capacity(batch.queries.len());
+    for (context, (code_index, msg)) in contexts.iter().zip(batch.queries) {
+        let gas_left = gas_limit.saturating_sub(*used_gas);
+        if gas_left == 0 {
+            results.push(Err(EnclaveError::OutOfGas));
+            continue;
+        }
+
+        let mut query_used_gas = 0;
+        let result = query(
+            unsafe { context.unsafe_clone() },
+            gas_left,
+            &mut query_used_gas,
+            gas_schedule,
+            batch.codes[code_index],
+            msg,
+        );
+        *used_gas = used_gas.saturating_add(query_used_gas);
+
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+/// The host sends the batch as `code_count || (code_len || code) * code_count`, followed by
+/// a sequence of `code_index || msg_len || msg`, where the numbers are big endian u32 values.
+fn decode_query_batch(mut data: &[u8]) -> Result<QueryBatch, EnclaveError> {
+    fn take_u32(data: &mut &[u8]) -> Result<usize, EnclaveError> {
+        if data.len() < 4 {
+            warn!("Got a malformed query batch");
+            return Err(EnclaveError::FailedFunctionCall);
+        }
+        let (number, rest) = data.split_at(4);
+        *data = rest;
+        Ok(u32::from_be_bytes([number[0], number[1], number[2], number[3]]) as usize)
+    }
+
+    fn take_chunk<'a>(data: &mut &'a [u8]) -> Result<&'a [u8], EnclaveError> {
+        let len = take_u32(data)?;
+        if data.len() < len {
+            warn!("Got a malformed query batch");
+            return Err(EnclaveError::FailedFunctionCall);
+        }
+        let (chunk, rest) = data.split_at(len);
+        *data = rest;
+        Ok(chunk)
+    }
+
+    let code_count = take_u32(&mut data)?;
+    let mut codes = vec![];
+    for _ in 0..code_count {
+        codes.push(take_chunk(&mut data)?);
+    }
+
+    let mut queries = vec![];
+    while !data.is_empty() {
+        let code_index = take_u32(&mut data)?;
+        if code_index >= codes.len() {
+            warn!(
+                "Got a query for code {} in a batch of {} codes",
+                code_index,
+                codes.len()
+            );
+            return Err(EnclaveError::FailedFunctionCall);
+        }
+        let msg = take_chunk(&mut data)?;
+        queries.push((code_index, msg));
+    }
+
+    Ok(QueryBatch { codes, queries })
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use enclave_ffi_types::EnclaveError;
+
+    use super::decode_query_batch;
+
+    fn encode(codes: &[&[u8]], queries: &[(u32, &[u8])]) -> Vec<u8> {
+        let mut data = (codes.len() as u32).to_be_bytes().to_vec();
+        for code in codes {
+            data.extend_from_slice(&(code.len() as u32).to_be_bytes());
+            data.extend_from_slice(code);
+        }
+        for (code_index, msg) in queries {
+            data.extend_from_slice(&code_index.to_be_bytes());
+            data.extend_from_slice(&(msg.len() as u32).to_be_bytes());
+            data.extend_from_slice(msg);
+        }
+        data
+    }
+
+    pub fn test_decode_query_batch() {
+        let data = encode(
+            &[b"code a", b"code b"],
+            &[(1, b"first"), (0, b"second"), (1, b"")],
+        );
+
+        let batch = decode_query_batch(&data).unwrap();
+        assert_eq!(batch.codes, vec![&b"code a"[..], &b"code b"[..]]);
+        assert_eq!(
+            batch.queries,
+            vec![(1, &b"first"[..]), (0, &b"second"[..]), (1, &b""[..])]
+        );
+    }
+
+    pub fn test_decode_empty_query_batch() {
+        let data = encode(&[], &[]);
+
+        let batch = decode_query_batch(&data).unwrap();
+        assert!(batch.codes.is_empty());
+        assert!(batch.queries.is_empty());
+    }
+
+    pub fn test_decode_query_batch_rejects_unknown_code() {
+        let data = encode(&[b"code a"], &[(1, b"msg")]);
+
+        assert!(matches!(
+            decode_query_batch(&data),
+            Err(EnclaveError::FailedFunctionCall)
+        ));
+    }
+
+    pub fn test_decode_query_batch_rejects_truncated_input() {
+        let data = encode(&[b"code a"], &[(0, b"msg")]);
+        // Cut in the middle of the code count, the code, the code index and the message
+        let cuts = [2, 10, 16, data.len() - 1];
+
+        for len in cuts.iter() {
+            assert!(
+                decode_query_batch(&data[..*len]).is_err(),
+                "decoding {} bytes should fail",
+                len
+            );
+        }
+    }
+}
@@ -11,31 +11,44 @@ use crate::cosmwasm::{
     system_error::{SystemError, SystemResult},
 };
 
-use enclave_ffi_types::{Ctx, EnclaveBuffer, OcallReturn, UntrustedVmError};
+use enclave_ffi_types::{
+    Ctx, DeserializationTarget, EnclaveBuffer, OcallFailure, OcallKind, OcallReturn,
+    UntrustedVmError,
+};
 use log::*;
 use sgx_types::sgx_status_t;
 
+/// Returns how much of the gas left a single query may use
+pub fn query_gas_budget(gas_left: u64, max_query_gas_percent: u32) -> u64 {
+    (gas_left as u128 * max_query_gas_percent as u128 / 100) as u64
+}
+
+/// Queries the chain on behalf of a contract. `gas_limit` is the budget of this query, which the host
+/// passes on to the queried contract, so its own nested queries are limited by it as well.
+/// The base cost of the query is charged by the caller, so `gas_used` is only the gas the host used.
+#[allow(clippy::too_many_arguments)]
 pub fn encrypt_and_query_chain(
     query: &[u8],
     context: &Ctx,
     nonce: IoNonce,
     user_public_key: Ed25519PublicKey,
+    io_epoch: u32,
     gas_used: &mut u64,
     gas_limit: u64,
+    max_query_depth: u32,
 ) -> Result<Vec<u8>, WasmEngineError> {
-    if let Some(answer) = check_recursion_limit() {
+    if let Some(answer) = check_recursion_limit(max_query_depth) {
         return serialize_error_response(&answer);
     }
 
     let mut query_struct: QueryRequest = match serde_json::from_slice(query) {
         Ok(query_struct) => query_struct,
         Err(err) => {
-            *gas_used = 500; // Should we charge gas for this to prevent spam?
             return system_error_invalid_request(query, err);
         }
     };
 
-    let is_encrypted = encrypt_query_request(&mut query_struct, nonce, user_public_key)?;
+    let is_encrypted = encrypt_query_request(&mut query_struct, nonce, user_public_key, io_epoch)?;
 
     let encrypted_query = serde_json::to_vec(&query_struct).map_err(|err| {
         // this should never happen
@@ -50,6 +63,10 @@ pub fn encrypt_and_query_chain(
     // Call query_chain (this bubbles up to x/compute via ocalls and FFI to Go code)
     // This returns the answer from x/compute
     let (result, query_used_gas) = query_chain(context, &encrypted_query, gas_limit);
+    if let Some(answer) = check_query_gas(query_used_gas, gas_limit) {
+        *gas_used = gas_limit;
+        return serialize_error_response(&answer);
+    }
     *gas_used = query_used_gas;
     let encrypted_answer_as_vec = result?;
 
@@ -80,7 +97,8 @@ pub fn encrypt_and_query_chain(
         Err(_) => encrypted_answer,
         // normal response from contract
         Ok(Ok(result)) => {
-            let decrypted = decrypt_query_response(query, result.0, nonce, user_public_key)?;
+            let decrypted =
+                decrypt_query_response(query, result.0, nonce, user_public_key, io_epoch)?;
             Ok(Ok(Binary(decrypted)))
         }
         // error response from contract, or critical error in called VM
@@ -97,11 +115,18 @@ pub fn encrypt_and_query_chain(
                             decode from base64: {}",
                             msg, err
                         );
-                        return Err(WasmEngineError::DeserializationError);
+                        return Err(WasmEngineError::DeserializationError {
+                            target: DeserializationTarget::Base64,
+                        });
                     }
                     Ok(error) => {
-                        let decrypted =
-                            decrypt_query_response_error(query, error, nonce, user_public_key)?;
+                        let decrypted = decrypt_query_response_error(
+                            query,
+                            error,
+                            nonce,
+                            user_public_key,
+                            io_epoch,
+                        )?;
                         match serde_json::from_slice::<StdError>(&decrypted) {
                             Ok(answer) => Ok(Err(answer)),
                             Err(err) => {
@@ -167,7 +192,14 @@ fn query_chain(
                     "query_chain() got an error from ocall_query_chain, stopping wasm: {:?}",
                     error_status
                 );
-                return (Err(WasmEngineError::FailedOcall(vm_err)), gas_used);
+                return (
+                    Err(WasmEngineError::failed_ocall(
+                        OcallKind::QueryChain,
+                        OcallFailure::Sgx,
+                        vm_err,
+                    )),
+                    gas_used,
+                );
             }
         }
 
@@ -179,8 +211,26 @@ fn query_chain(
                     Err(err) => return (Err(err.into()), gas_used),
                 }
             }
-            OcallReturn::Failure => return (Err(WasmEngineError::FailedOcall(vm_err)), gas_used),
-            OcallReturn::Panic => return (Err(WasmEngineError::Panic), gas_used),
+            OcallReturn::Failure => {
+                return (
+                    Err(WasmEngineError::failed_ocall(
+                        OcallKind::QueryChain,
+                        OcallFailure::Host,
+                        vm_err,
+                    )),
+                    gas_used,
+                )
+            }
+            OcallReturn::Panic => {
+                return (
+                    Err(WasmEngineError::failed_ocall(
+                        OcallKind::QueryChain,
+                        OcallFailure::HostPanic,
+                        vm_err,
+                    )),
+                    gas_used,
+                )
+            }
         }
     };
 
@@ -191,8 +241,8 @@ fn query_chain(
 ///
 /// We make sure that a recursion limit is in place in order to
 /// mitigate cases where the enclave runs out of memory.
-fn check_recursion_limit() -> Option<SystemResult<StdResult<Binary>>> {
-    if recursion_depth::limit_reached() {
+fn check_recursion_limit(max_query_depth: u32) -> Option<SystemResult<StdResult<Binary>>> {
+    if recursion_depth::limit_reached(max_query_depth) {
         debug!(
             "Recursion limit reached while performing nested queries. Returning error to contract."
         );
@@ -202,6 +252,23 @@ fn check_recursion_limit() -> Option<SystemResult<StdResult<Binary>>> {
     }
 }
 
+/// Check whether the query stayed within its gas budget.
+///
+/// The host reports more gas than the budget when the queried contract ran out of it midway.
+/// The caller is then charged only the budget, and gets an error it can handle,
+/// instead of whatever partial answer came back.
+fn check_query_gas(gas_used: u64, gas_limit: u64) -> Option<SystemResult<StdResult<Binary>>> {
+    if gas_used > gas_limit {
+        debug!(
+            "Query used {} gas out of a budget of {}. Returning error to contract.",
+            gas_used, gas_limit
+        );
+        Some(Err(SystemError::ExceededQueryGasLimit { gas_limit }))
+    } else {
+        None
+    }
+}
+
 fn system_error_invalid_request<T>(request: &[u8], err: T) -> Result<Vec<u8>, WasmEngineError>
 where
     T: std::fmt::Debug + ToString,
@@ -250,6 +317,7 @@ fn encrypt_query_request(
     query_struct: &mut QueryRequest,
     nonce: IoNonce,
     user_public_key: Ed25519PublicKey,
+    io_epoch: u32,
 ) -> Result<bool, WasmEngineError> {
     let mut is_encrypted = false;
 
@@ -269,6 +337,7 @@ fn encrypt_query_request(
             msg: hash_appended_msg,
             user_public_key,
             nonce,
+            io_epoch,
         };
         encrypted_msg.encrypt_in_place().map_err(|err| {
             debug!(
@@ -291,6 +360,7 @@ fn decrypt_query_response(
     response: Vec<u8>,
     nonce: IoNonce,
     user_public_key: Ed25519PublicKey,
+    io_epoch: u32,
 ) -> Result<Vec<u8>, WasmEngineError> {
     // query response returns without nonce and user_public_key appended to it
     // because the sender is supposed to have them already
@@ -298,6 +368,7 @@ fn decrypt_query_response(
         nonce,
         user_public_key,
         msg: response,
+        io_epoch,
     };
 
     let b64_decrypted = as_secret_msg.decrypt().map_err(|err| {
@@ -314,7 +385,9 @@ fn decrypt_query_response(
             "encrypt_and_query_chain() got an answer, managed to decrypt it, then tried to decode the output from base64 to bytes and failed: {:?}",
             err
         );
-        WasmEngineError::DeserializationError
+        WasmEngineError::DeserializationError {
+            target: DeserializationTarget::Base64,
+        }
     })
 }
 
@@ -323,11 +396,13 @@ fn decrypt_query_response_error(
     error: Vec<u8>,
     nonce: IoNonce,
     user_public_key: Ed25519PublicKey,
+    io_epoch: u32,
 ) -> Result<Vec<u8>, WasmEngineError> {
     let error_msg = SecretMessage {
         nonce,
         user_public_key,
         msg: error,
+        io_epoch,
     };
 
     error_msg.decrypt().map_err(|err| {
@@ -339,3 +414,24 @@ fn decrypt_query_response_error(
         WasmEngineError::DecryptionError
     })
 }
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    pub fn test_query_gas_budget() {
+        assert_eq!(query_gas_budget(1000, 100), 1000);
+        assert_eq!(query_gas_budget(1000, 25), 250);
+        assert_eq!(query_gas_budget(999, 50), 499);
+        // No overflow for huge gas limits
+        assert_eq!(query_gas_budget(u64::MAX, 100), u64::MAX);
+    }
+
+    pub fn test_check_query_gas() {
+        assert!(check_query_gas(100, 100).is_none());
+        assert!(matches!(
+            check_query_gas(101, 100),
+            Some(Err(SystemError::ExceededQueryGasLimit { gas_limit: 100 }))
+        ));
+    }
+}
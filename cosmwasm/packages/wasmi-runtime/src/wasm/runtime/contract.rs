@@ -2,21 +2,35 @@ use bech32::{FromBase32, ToBase32};
 use log::*;
 use wasmi::{Error as InterpreterError, MemoryInstance, MemoryRef, ModuleRef, RuntimeValue, Trap};
 
-use enclave_ffi_types::Ctx;
+#[cfg(feature = "gas-profiling")]
+use enclave_ffi_types::GasProfile;
+use enclave_ffi_types::{Ctx, FlushGas};
 
 use crate::consts::BECH32_PREFIX_ACC_ADDR;
-use crate::crypto::Ed25519PublicKey;
+use crate::crypto::{ed25519_verify, secp256k1, AESKey, CryptoError, Ed25519PublicKey, Kdf};
 use crate::wasm::contract_validation::ContractKey;
-use crate::wasm::db::{read_encrypted_key, remove_encrypted_key, write_encrypted_key};
+use crate::wasm::db::{next_key, read_encrypted_key, remove_encrypted_key, write_encrypted_key};
 use crate::wasm::errors::WasmEngineError;
+#[cfg(feature = "gas-profiling")]
+use crate::wasm::gas::split_memory_growth_gas;
+use crate::wasm::gas::WasmCosts;
+use crate::wasm::key_index::KeyIterator;
 use crate::wasm::runtime::traits::WasmiApi;
-use crate::wasm::{gas::WasmCosts, query_chain::encrypt_and_query_chain, types::IoNonce};
+use crate::wasm::storage_cache::StorageCache;
+use crate::wasm::{
+    query_chain::{encrypt_and_query_chain, query_gas_budget},
+    types::IoNonce,
+};
+
+/// These match the values of `cosmwasm_std::Order`
+const ORDER_ASCENDING: i32 = 1;
+const ORDER_DESCENDING: i32 = 2;
 
 pub enum ContractOperation {
     Init,
     Handle,
     Query,
-    // Migrate. // not implemented
+    Migrate,
 }
 
 #[allow(unused)]
@@ -32,6 +46,56 @@ impl ContractOperation {
     fn is_query(&self) -> bool {
         matches!(self, ContractOperation::Query)
     }
+
+    fn is_migrate(&self) -> bool {
+        matches!(self, ContractOperation::Migrate)
+    }
+}
+
+/// The categories of gas used outside of wasmi, as reported in the `GasProfile`
+enum ExternalGas {
+    StorageRead,
+    StorageWrite,
+    StorageRemove,
+    AddressConversion,
+    QueryChain,
+    Random,
+    SignatureVerification,
+}
+
+/// The values returned by the signature verification imports
+const SIGNATURE_VALID: u32 = 0;
+const SIGNATURE_INVALID: u32 = 1;
+/// One of the inputs is not in the expected format, e.g. a public key of the wrong length
+const SIGNATURE_MALFORMED_INPUT: u32 = 2;
+
+fn signature_verification_result(result: Result<bool, CryptoError>) -> RuntimeValue {
+    let code = match result {
+        Ok(true) => SIGNATURE_VALID,
+        Ok(false) => SIGNATURE_INVALID,
+        Err(_) => SIGNATURE_MALFORMED_INPUT,
+    };
+    RuntimeValue::I32(code as i32)
+}
+
+/// Batches are passed in as a sequence of `item_len || item`, where `item_len` is a big endian u32.
+/// Returns `None` if the encoding is malformed.
+fn decode_signature_batch(mut data: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut items = vec![];
+    while !data.is_empty() {
+        if data.len() < 4 {
+            return None;
+        }
+        let (len, rest) = data.split_at(4);
+        let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+        if rest.len() < len {
+            return None;
+        }
+        let (item, rest) = rest.split_at(len);
+        items.push(item);
+        data = rest;
+    }
+    Some(items)
 }
 
 /// SecretContract maps function index to implementation
@@ -51,6 +115,24 @@ pub struct ContractInstance {
     operation: ContractOperation,
     pub user_nonce: IoNonce,
     pub user_public_key: Ed25519PublicKey,
+    /// The epoch of the IO key the user encrypted the input with, which nested queries use as well
+    pub user_io_epoch: u32,
+    /// The iterators opened by `db_scan`. The iterator id is the index in this vector.
+    iterators: Vec<KeyIterator>,
+    /// The gas used externally, by category
+    #[cfg(feature = "gas-profiling")]
+    gas_profile: GasProfile,
+    /// The size of the memory when the instance was created, used to tell how much it grew
+    #[cfg(feature = "gas-profiling")]
+    initial_memory_pages: u64,
+    /// Buffers the storage operations of this execution, to save round trips to the host
+    storage: StorageCache,
+    /// The key `random` derives its output from. Queries don't have one.
+    random_key: Option<AESKey>,
+    /// How many times `random` was called, so every call returns different bytes
+    random_counter: u64,
+    /// The epoch of the consensus seed that new values are encrypted with
+    seed_epoch: u32,
 }
 
 impl ContractInstance {
@@ -64,6 +146,9 @@ impl ContractInstance {
         operation: ContractOperation,
         user_nonce: IoNonce,
         user_public_key: Ed25519PublicKey,
+        user_io_epoch: u32,
+        random_key: Option<AESKey>,
+        seed_epoch: u32,
     ) -> Self {
         let memory = (&*module)
             .export_by_name("memory")
@@ -71,6 +156,8 @@ impl ContractInstance {
             .as_memory()
             .cloned()
             .expect("'memory' export should be of memory type");
+        #[cfg(feature = "gas-profiling")]
+        let initial_memory_pages = memory.current_size().0 as u64;
 
         Self {
             context,
@@ -84,6 +171,16 @@ impl ContractInstance {
             operation,
             user_nonce,
             user_public_key,
+            user_io_epoch,
+            iterators: Vec::new(),
+            #[cfg(feature = "gas-profiling")]
+            gas_profile: GasProfile::default(),
+            #[cfg(feature = "gas-profiling")]
+            initial_memory_pages,
+            storage: StorageCache::new(),
+            random_key,
+            random_counter: 0,
+            seed_epoch,
         }
     }
 
@@ -209,11 +306,65 @@ impl ContractInstance {
     }
 
     /// Track gas used by external services (e.g. storage)
-    fn use_gas_externally(&mut self, gas_amount: u64) -> Result<(), WasmEngineError> {
+    fn use_gas_externally(
+        &mut self,
+        gas_amount: u64,
+        category: ExternalGas,
+    ) -> Result<(), WasmEngineError> {
         self.gas_used_externally = self.gas_used_externally.saturating_add(gas_amount);
+        self.profile_external_gas(gas_amount, category);
+
         self.check_gas_usage()
     }
 
+    /// Adds gas used externally to its category in the gas profile
+    #[cfg(feature = "gas-profiling")]
+    fn profile_external_gas(&mut self, gas_amount: u64, category: ExternalGas) {
+        let profile_entry = match category {
+            ExternalGas::StorageRead => &mut self.gas_profile.storage_read,
+            ExternalGas::StorageWrite => &mut self.gas_profile.storage_write,
+            ExternalGas::StorageRemove => &mut self.gas_profile.storage_remove,
+            ExternalGas::AddressConversion => &mut self.gas_profile.address_conversion,
+            ExternalGas::QueryChain => &mut self.gas_profile.query_chain,
+            ExternalGas::Random => &mut self.gas_profile.random,
+            ExternalGas::SignatureVerification => &mut self.gas_profile.signature_verification,
+        };
+        *profile_entry = profile_entry.saturating_add(gas_amount);
+    }
+
+    /// Without the `gas-profiling` feature the gas profile isn't collected at all
+    #[cfg(not(feature = "gas-profiling"))]
+    fn profile_external_gas(&mut self, _gas_amount: u64, _category: ExternalGas) {}
+
+    /// Sends the buffered storage operations to the host, and charges the gas it used for them
+    /// beyond what was charged when they were buffered
+    pub fn flush_storage(&mut self) -> Result<(), WasmEngineError> {
+        let FlushGas {
+            read,
+            write,
+            remove,
+        } = self.storage.flush(&self.context)?;
+
+        self.use_gas_externally(read, ExternalGas::StorageRead)?;
+        self.use_gas_externally(write, ExternalGas::StorageWrite)?;
+        self.use_gas_externally(remove, ExternalGas::StorageRemove)
+    }
+
+    /// Returns a breakdown of the gas used so far
+    #[cfg(feature = "gas-profiling")]
+    pub fn gas_profile(&self) -> GasProfile {
+        let grown_pages =
+            (self.memory.current_size().0 as u64).saturating_sub(self.initial_memory_pages);
+        let (wasm, memory_growth) =
+            split_memory_growth_gas(self.gas_used, grown_pages, &self.gas_costs);
+
+        GasProfile {
+            wasm,
+            memory_growth,
+            ..self.gas_profile
+        }
+    }
+
     fn check_gas_usage(&self) -> Result<(), WasmEngineError> {
         // Check if new amount is bigger than gas limit
         // If is above the limit, halt execution
@@ -259,9 +410,13 @@ impl WasmiApi for ContractInstance {
 
         // Call read_db (this bubbles up to Tendermint via ocalls and FFI to Go code)
         // This returns the value from Tendermint
-        let (value, gas_used) =
-            read_encrypted_key(&state_key_name, &self.context, &self.contract_key)?;
-        self.use_gas_externally(gas_used)?;
+        let (value, gas_used) = read_encrypted_key(
+            &state_key_name,
+            &self.context,
+            &mut self.storage,
+            &self.contract_key,
+        )?;
+        self.use_gas_externally(gas_used, ExternalGas::StorageRead)?;
 
         let value = match value {
             None => return Ok(Some(RuntimeValue::I32(0))),
@@ -307,9 +462,22 @@ impl WasmiApi for ContractInstance {
             String::from_utf8_lossy(&state_key_name)
         );
 
-        // Call remove_db (this bubbles up to Tendermint via ocalls and FFI to Go code)
-        let gas_used = remove_encrypted_key(&state_key_name, &self.context, &self.contract_key)?;
-        self.use_gas_externally(gas_used)?;
+        // The removal reaches Tendermint when the storage is flushed, the rest of its gas is charged then
+        let used_gas = remove_encrypted_key(
+            &state_key_name,
+            &self.context,
+            &mut self.storage,
+            &self.contract_key,
+            self.seed_epoch,
+        )
+        .map_err(|err| {
+            debug!(
+                "remove_db() error while trying to remove the value from state: {:?}",
+                err
+            );
+            err
+        })?;
+        self.use_gas_externally(used_gas, ExternalGas::StorageRemove)?;
 
         Ok(None)
     }
@@ -345,18 +513,139 @@ impl WasmiApi for ContractInstance {
             String::from_utf8_lossy(&value),
         );
 
-        let used_gas =
-            write_encrypted_key(&state_key_name, &value, &self.context, &self.contract_key)
-                .map_err(|err| {
+        let used_gas = write_encrypted_key(
+            &state_key_name,
+            &value,
+            &self.context,
+            &mut self.storage,
+            &self.contract_key,
+            self.seed_epoch,
+        )
+        .map_err(|err| {
+            debug!(
+                "write_db() error while trying to write the value to state: {:?}",
+                err
+            );
+            err
+        })?;
+        self.use_gas_externally(used_gas, ExternalGas::StorageWrite)?;
+
+        Ok(None)
+    }
+
+    /// Args:
+    /// 1. "start" of the range to iterate over, inclusive (buffer of bytes, or 0 for no limit)
+    /// 2. "end" of the range to iterate over, exclusive (buffer of bytes, or 0 for no limit)
+    /// 3. "order" 1 for ascending, 2 for descending
+    /// Returns an id of the new iterator, to be passed to `db_next`
+    fn db_scan_index(
+        &mut self,
+        start_ptr_ptr: i32,
+        end_ptr_ptr: i32,
+        order: i32,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let start = match start_ptr_ptr {
+            0 => None,
+            ptr => Some(self.extract_vector(ptr as u32).map_err(|err| {
+                debug!("db_scan() error while trying to read start from wasm memory");
+                err
+            })?),
+        };
+        let end = match end_ptr_ptr {
+            0 => None,
+            ptr => Some(self.extract_vector(ptr as u32).map_err(|err| {
+                debug!("db_scan() error while trying to read end from wasm memory");
+                err
+            })?),
+        };
+
+        trace!(
+            "db_scan() was called from WASM code with start: {:?} end: {:?} order: {}",
+            start.as_ref().map(|start| String::from_utf8_lossy(start)),
+            end.as_ref().map(|end| String::from_utf8_lossy(end)),
+            order
+        );
+
+        // The keys are read from the key index as the contract iterates over them
+        let iterator = match order {
+            ORDER_ASCENDING => KeyIterator::new(start, end, false),
+            ORDER_DESCENDING => KeyIterator::new(start, end, true),
+            _ => {
+                debug!("db_scan() got an invalid order: {}", order);
+                return Err(WasmEngineError::InvalidIterator.into());
+            }
+        };
+
+        let iterator_id = self.iterators.len();
+        self.iterators.push(iterator);
+
+        Ok(Some(RuntimeValue::I32(iterator_id as i32)))
+    }
+
+    /// Args:
+    /// 1. "iterator_id" as returned from `db_scan`
+    /// Returns a pointer to a region with `value || key || key_len`, where key_len is a big endian u32.
+    /// When the iterator is exhausted, returns 0 instead of a pointer, so empty keys can be iterated over.
+    fn db_next_index(&mut self, iterator_id: i32) -> Result<Option<RuntimeValue>, Trap> {
+        let mut kv = None;
+
+        loop {
+            let iterator = self
+                .iterators
+                .get_mut(iterator_id as usize)
+                .ok_or_else(|| {
                     debug!(
-                        "write_db() error while trying to write the value to state: {:?}",
-                        err
+                        "db_next() was called with a non-existent iterator {}",
+                        iterator_id
                     );
-                    err
+                    WasmEngineError::InvalidIterator
                 })?;
-        self.use_gas_externally(used_gas)?;
+            let (key, gas_used, entries_read) = next_key(
+                iterator,
+                &self.context,
+                &mut self.storage,
+                &self.contract_key,
+                self.seed_epoch,
+            )?;
+            self.use_gas_externally(gas_used, ExternalGas::StorageRead)?;
+            // Only the nodes of the index on the way to the key are decrypted
+            self.use_gas_externally(
+                entries_read.saturating_mul(self.gas_costs.external_db_scan_entry as u64),
+                ExternalGas::StorageRead,
+            )?;
+
+            let key = match key {
+                None => break,
+                Some(key) => key,
+            };
+
+            let (value, gas_used) =
+                read_encrypted_key(&key, &self.context, &mut self.storage, &self.contract_key)?;
+            self.use_gas_externally(gas_used, ExternalGas::StorageRead)?;
+
+            // The key may have been removed since its leaf of the index was read
+            if let Some(mut value) = value {
+                value.extend_from_slice(&key);
+                value.extend_from_slice(&(key.len() as u32).to_be_bytes());
+                kv = Some(value);
+                break;
+            }
+        }
 
-        Ok(None)
+        let kv = match kv {
+            Some(kv) => kv,
+            None => return Ok(Some(RuntimeValue::I32(0))),
+        };
+
+        let ptr_to_region_in_wasm_vm = self.write_to_memory(&kv).map_err(|err| {
+            debug!(
+                "db_next() error while trying to allocate {} bytes for the result",
+                kv.len(),
+            );
+            err
+        })?;
+
+        Ok(Some(RuntimeValue::I32(ptr_to_region_in_wasm_vm as i32)))
     }
 
     /// Args:
@@ -369,7 +658,10 @@ impl WasmiApi for ContractInstance {
         human_ptr_ptr: i32,
         canonical_ptr_ptr: i32,
     ) -> Result<Option<RuntimeValue>, Trap> {
-        self.use_gas_externally(self.gas_costs.external_canonicalize_address as u64)?;
+        self.use_gas_externally(
+            self.gas_costs.external_canonicalize_address as u64,
+            ExternalGas::AddressConversion,
+        )?;
 
         let human = self.extract_vector(human_ptr_ptr as u32).map_err(|err| {
             debug!(
@@ -464,7 +756,10 @@ impl WasmiApi for ContractInstance {
         canonical_ptr_ptr: i32,
         human_ptr_ptr: i32,
     ) -> Result<Option<RuntimeValue>, Trap> {
-        self.use_gas_externally(self.gas_costs.external_humanize_address as u64)?;
+        self.use_gas_externally(
+            self.gas_costs.external_humanize_address as u64,
+            ExternalGas::AddressConversion,
+        )?;
 
         let canonical = self
             .extract_vector(canonical_ptr_ptr as u32)
@@ -519,6 +814,16 @@ impl WasmiApi for ContractInstance {
             String::from_utf8_lossy(&query_buffer)
         );
 
+        // The queried contract may read our storage, so it has to be up to date
+        self.flush_storage()?;
+
+        // Every query is charged up front, so malformed and rejected queries aren't free
+        self.use_gas_externally(
+            self.gas_costs.external_query_chain as u64,
+            ExternalGas::QueryChain,
+        )?;
+        let gas_budget = query_gas_budget(self.gas_left(), self.gas_costs.max_query_gas_percent);
+
         // Call query_chain (this bubbles up to x/compute via ocalls and FFI to Go code)
         // Returns the value from x/compute
         let mut gas_used: u64 = 0;
@@ -527,8 +832,10 @@ impl WasmiApi for ContractInstance {
             &self.context,
             self.user_nonce,
             self.user_public_key,
+            self.user_io_epoch,
             &mut gas_used,
-            self.gas_left(),
+            gas_budget,
+            self.gas_costs.max_query_depth,
         )?;
 
         trace!(
@@ -537,7 +844,7 @@ impl WasmiApi for ContractInstance {
             String::from_utf8_lossy(&answer)
         );
 
-        self.use_gas_externally(gas_used)?;
+        self.use_gas_externally(gas_used, ExternalGas::QueryChain)?;
 
         let ptr_to_region_in_wasm_vm = self.write_to_memory(&answer).map_err(|err| {
             debug!(
@@ -551,6 +858,179 @@ impl WasmiApi for ContractInstance {
         Ok(Some(RuntimeValue::I32(ptr_to_region_in_wasm_vm as i32)))
     }
 
+    /// Returns a pointer to a region with 32 random bytes.
+    /// All the validators derive the same bytes, see `generate_random_key`.
+    fn random_index(&mut self) -> Result<Option<RuntimeValue>, Trap> {
+        let random_key = match self.random_key {
+            Some(random_key) => random_key,
+            None => {
+                debug!("random() was called from a query or a simulation");
+                return Err(WasmEngineError::RandomnessUnavailable.into());
+            }
+        };
+
+        self.use_gas_externally(self.gas_costs.external_random as u64, ExternalGas::Random)?;
+
+        let random_bytes = random_key.derive_key_from_this(&self.random_counter.to_be_bytes());
+        self.random_counter += 1;
+
+        let ptr_to_region_in_wasm_vm = self.write_to_memory(random_bytes.get()).map_err(|err| {
+            debug!(
+                "random() error while trying to allocate and write the random bytes to the wasm vm"
+            );
+            err
+        })?;
+
+        Ok(Some(RuntimeValue::I32(ptr_to_region_in_wasm_vm as i32)))
+    }
+
+    /// Returns 0 if the signature is valid, 1 if it isn't, and 2 if any of the inputs is malformed
+    fn secp256k1_verify_index(
+        &mut self,
+        message_hash_ptr_ptr: i32,
+        signature_ptr_ptr: i32,
+        public_key_ptr_ptr: i32,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        self.use_gas_externally(
+            self.gas_costs.external_secp256k1_verify as u64,
+            ExternalGas::SignatureVerification,
+        )?;
+
+        let message_hash = self
+            .extract_vector(message_hash_ptr_ptr as u32)
+            .map_err(|err| {
+                debug!(
+                    "secp256k1_verify() error while trying to read message_hash from wasm memory"
+                );
+                err
+            })?;
+        let signature = self
+            .extract_vector(signature_ptr_ptr as u32)
+            .map_err(|err| {
+                debug!("secp256k1_verify() error while trying to read signature from wasm memory");
+                err
+            })?;
+        let public_key = self
+            .extract_vector(public_key_ptr_ptr as u32)
+            .map_err(|err| {
+                debug!("secp256k1_verify() error while trying to read public_key from wasm memory");
+                err
+            })?;
+
+        let result = secp256k1::verify_prehashed(&message_hash, &signature, &public_key);
+        trace!("secp256k1_verify() returned {:?}", result);
+
+        Ok(Some(signature_verification_result(result)))
+    }
+
+    /// Returns 0 if the signature is valid, 1 if it isn't, and 2 if any of the inputs is malformed
+    fn ed25519_verify_index(
+        &mut self,
+        message_ptr_ptr: i32,
+        signature_ptr_ptr: i32,
+        public_key_ptr_ptr: i32,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        self.use_gas_externally(
+            self.gas_costs.external_ed25519_verify as u64,
+            ExternalGas::SignatureVerification,
+        )?;
+
+        let message = self.extract_vector(message_ptr_ptr as u32).map_err(|err| {
+            debug!("ed25519_verify() error while trying to read message from wasm memory");
+            err
+        })?;
+        let signature = self
+            .extract_vector(signature_ptr_ptr as u32)
+            .map_err(|err| {
+                debug!("ed25519_verify() error while trying to read signature from wasm memory");
+                err
+            })?;
+        let public_key = self
+            .extract_vector(public_key_ptr_ptr as u32)
+            .map_err(|err| {
+                debug!("ed25519_verify() error while trying to read public_key from wasm memory");
+                err
+            })?;
+
+        let result = ed25519_verify(&message, &signature, &public_key);
+        trace!("ed25519_verify() returned {:?}", result);
+
+        Ok(Some(signature_verification_result(result)))
+    }
+
+    /// Verifies that `signatures[i]` is a valid signature of `messages[i]` by `public_keys[i]`, for all `i`.
+    /// Returns 0 if all the signatures are valid, and otherwise the result of the first one that isn't.
+    /// Lists of different lengths are malformed input.
+    fn ed25519_batch_verify_index(
+        &mut self,
+        messages_ptr_ptr: i32,
+        signatures_ptr_ptr: i32,
+        public_keys_ptr_ptr: i32,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        // The first signature is charged before the batch is read, so malformed batches aren't free
+        self.use_gas_externally(
+            self.gas_costs.external_ed25519_verify as u64,
+            ExternalGas::SignatureVerification,
+        )?;
+
+        let messages = self
+            .extract_vector(messages_ptr_ptr as u32)
+            .map_err(|err| {
+                debug!(
+                    "ed25519_batch_verify() error while trying to read messages from wasm memory"
+                );
+                err
+            })?;
+        let signatures = self
+            .extract_vector(signatures_ptr_ptr as u32)
+            .map_err(|err| {
+                debug!(
+                    "ed25519_batch_verify() error while trying to read signatures from wasm memory"
+                );
+                err
+            })?;
+        let public_keys = self
+            .extract_vector(public_keys_ptr_ptr as u32)
+            .map_err(|err| {
+                debug!(
+                    "ed25519_batch_verify() error while trying to read public_keys from wasm memory"
+                );
+                err
+            })?;
+
+        let (messages, signatures, public_keys) = match (
+            decode_signature_batch(&messages),
+            decode_signature_batch(&signatures),
+            decode_signature_batch(&public_keys),
+        ) {
+            (Some(messages), Some(signatures), Some(public_keys))
+                if messages.len() == signatures.len() && signatures.len() == public_keys.len() =>
+            {
+                (messages, signatures, public_keys)
+            }
+            _ => {
+                debug!("ed25519_batch_verify() got a malformed batch");
+                return Ok(Some(RuntimeValue::I32(SIGNATURE_MALFORMED_INPUT as i32)));
+            }
+        };
+
+        self.use_gas_externally(
+            (self.gas_costs.external_ed25519_verify as u64)
+                .saturating_mul(signatures.len().saturating_sub(1) as u64),
+            ExternalGas::SignatureVerification,
+        )?;
+
+        for ((message, signature), public_key) in messages.iter().zip(signatures).zip(public_keys) {
+            let result = ed25519_verify(message, signature, public_key);
+            if !matches!(result, Ok(true)) {
+                trace!("ed25519_batch_verify() returned {:?}", result);
+                return Ok(Some(signature_verification_result(result)));
+            }
+        }
+
+        Ok(Some(RuntimeValue::I32(SIGNATURE_VALID as i32)))
+    }
+
     fn gas_index(&mut self, gas_amount: i32) -> Result<Option<RuntimeValue>, Trap> {
         self.use_gas(gas_amount as u64)?;
         Ok(None)
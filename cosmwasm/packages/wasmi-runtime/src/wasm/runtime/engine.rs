@@ -1,10 +1,10 @@
 use log::*;
-use wasmi::{ModuleRef, RuntimeValue};
+use wasmi::{Error as InterpreterError, ModuleRef, RuntimeValue};
 
 use super::contract::ContractInstance;
 use crate::wasm::errors::{wasmi_error_to_enclave_error, WasmEngineError};
 
-use enclave_ffi_types::EnclaveError;
+use enclave_ffi_types::{EnclaveError, GasProfile};
 
 pub struct Engine {
     contract_instance: ContractInstance,
@@ -23,6 +23,18 @@ impl Engine {
         self.contract_instance.gas_used
     }
 
+    /// Returns a breakdown of the gas used by the contract.
+    /// This is only reported by builds with the `gas-profiling` feature, and is empty otherwise.
+    #[cfg(feature = "gas-profiling")]
+    pub fn gas_profile(&self) -> GasProfile {
+        self.contract_instance.gas_profile()
+    }
+
+    #[cfg(not(feature = "gas-profiling"))]
+    pub fn gas_profile(&self) -> GasProfile {
+        GasProfile::default()
+    }
+
     pub fn write_to_memory(&mut self, buffer: &[u8]) -> Result<u32, WasmEngineError> {
         self.contract_instance.write_to_memory(buffer)
     }
@@ -31,21 +43,28 @@ impl Engine {
         self.contract_instance.extract_vector(vec_ptr_ptr)
     }
 
+    /// Sends the storage operations buffered during the call to the host.
+    /// This is done even if the call failed, so the host still charges gas for them.
+    fn finish_call<T>(&mut self, result: Result<T, InterpreterError>) -> Result<T, EnclaveError> {
+        let flushed = self.contract_instance.flush_storage();
+        let value = result.map_err(wasmi_error_to_enclave_error)?;
+        flushed?;
+        Ok(value)
+    }
+
     pub fn init(&mut self, env_ptr: u32, msg_ptr: u32) -> Result<u32, EnclaveError> {
         info!("Invoking init() in wasm");
 
-        match self
-            .module
-            .invoke_export(
-                "init",
-                &[
-                    RuntimeValue::I32(env_ptr as i32),
-                    RuntimeValue::I32(msg_ptr as i32),
-                ],
-                &mut self.contract_instance,
-            )
-            .map_err(wasmi_error_to_enclave_error)?
-        {
+        let result = self.module.invoke_export(
+            "init",
+            &[
+                RuntimeValue::I32(env_ptr as i32),
+                RuntimeValue::I32(msg_ptr as i32),
+            ],
+            &mut self.contract_instance,
+        );
+
+        match self.finish_call(result)? {
             Some(RuntimeValue::I32(offset)) => Ok(offset as u32),
             other => {
                 warn!("init method returned value which wasn't u32: {:?}", other);
@@ -100,18 +119,16 @@ impl Engine {
         //     }
         // }?;
 
-        match self
-            .module
-            .invoke_export(
-                "handle",
-                &[
-                    RuntimeValue::I32(env_ptr as i32),
-                    RuntimeValue::I32(msg_ptr as i32),
-                ],
-                &mut self.contract_instance,
-            )
-            .map_err(wasmi_error_to_enclave_error)?
-        {
+        let result = self.module.invoke_export(
+            "handle",
+            &[
+                RuntimeValue::I32(env_ptr as i32),
+                RuntimeValue::I32(msg_ptr as i32),
+            ],
+            &mut self.contract_instance,
+        );
+
+        match self.finish_call(result)? {
             Some(RuntimeValue::I32(offset)) => Ok(offset as u32),
             other => {
                 warn!("handle method returned value which wasn't u32: {:?}", other);
@@ -120,18 +137,40 @@ impl Engine {
         }
     }
 
+    pub fn migrate(&mut self, env_ptr: u32, msg_ptr: u32) -> Result<u32, EnclaveError> {
+        info!("Invoking migrate() in wasm");
+
+        let result = self.module.invoke_export(
+            "migrate",
+            &[
+                RuntimeValue::I32(env_ptr as i32),
+                RuntimeValue::I32(msg_ptr as i32),
+            ],
+            &mut self.contract_instance,
+        );
+
+        match self.finish_call(result)? {
+            Some(RuntimeValue::I32(offset)) => Ok(offset as u32),
+            other => {
+                warn!(
+                    "migrate method returned value which wasn't u32: {:?}",
+                    other
+                );
+                Err(EnclaveError::FailedFunctionCall)
+            }
+        }
+    }
+
     pub fn query(&mut self, msg_ptr: u32) -> Result<u32, EnclaveError> {
         info!("Invoking query() in wasm");
 
-        match self
-            .module
-            .invoke_export(
-                "query",
-                &[RuntimeValue::I32(msg_ptr as i32)],
-                &mut self.contract_instance,
-            )
-            .map_err(wasmi_error_to_enclave_error)?
-        {
+        let result = self.module.invoke_export(
+            "query",
+            &[RuntimeValue::I32(msg_ptr as i32)],
+            &mut self.contract_instance,
+        );
+
+        match self.finish_call(result)? {
             Some(RuntimeValue::I32(offset)) => Ok(offset as u32),
             other => {
                 warn!("query method returned value which wasn't u32: {:?}", other);
@@ -15,6 +15,12 @@ pub enum HostFunctions {
     HumanizeAddressIndex = 4,
     GasIndex = 5,
     QueryChainIndex = 6,
+    ScanDbIndex = 7,
+    NextDbIndex = 8,
+    RandomIndex = 9,
+    Secp256k1VerifyIndex = 10,
+    Ed25519VerifyIndex = 11,
+    Ed25519BatchVerifyIndex = 12,
     #[cfg(feature = "debug-print")]
     DebugPrintIndex = 254,
     Unknown,
@@ -34,6 +40,18 @@ impl From<usize> for HostFunctions {
             }
             x if x == HostFunctions::GasIndex as usize => HostFunctions::GasIndex,
             x if x == HostFunctions::QueryChainIndex as usize => HostFunctions::QueryChainIndex,
+            x if x == HostFunctions::ScanDbIndex as usize => HostFunctions::ScanDbIndex,
+            x if x == HostFunctions::NextDbIndex as usize => HostFunctions::NextDbIndex,
+            x if x == HostFunctions::RandomIndex as usize => HostFunctions::RandomIndex,
+            x if x == HostFunctions::Secp256k1VerifyIndex as usize => {
+                HostFunctions::Secp256k1VerifyIndex
+            }
+            x if x == HostFunctions::Ed25519VerifyIndex as usize => {
+                HostFunctions::Ed25519VerifyIndex
+            }
+            x if x == HostFunctions::Ed25519BatchVerifyIndex as usize => {
+                HostFunctions::Ed25519BatchVerifyIndex
+            }
             #[cfg(feature = "debug-print")]
             x if x == HostFunctions::DebugPrintIndex as usize => HostFunctions::DebugPrintIndex,
             _ => HostFunctions::Unknown,
@@ -88,6 +106,39 @@ impl Externals for ContractInstance {
 
                 self.write_db_index(key, value)
             }
+            HostFunctions::ScanDbIndex => {
+                let start: i32 = args.nth_checked(0).map_err(|err| {
+                    warn!(
+                        "db_scan() error reading first argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+                let end: i32 = args.nth_checked(1).map_err(|err| {
+                    warn!(
+                        "db_scan() error reading second argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+                let order: i32 = args.nth_checked(2).map_err(|err| {
+                    warn!(
+                        "db_scan() error reading third argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+
+                self.db_scan_index(start, end, order)
+            }
+            HostFunctions::NextDbIndex => {
+                let iterator_id: i32 = args.nth_checked(0).map_err(|err| {
+                    warn!("db_next() error reading argument, stopping wasm: {:?}", err);
+                    err
+                })?;
+
+                self.db_next_index(iterator_id)
+            }
             HostFunctions::CanonicalizeAddressIndex => {
                 let human: i32 = args.nth_checked(0).map_err(|err| {
                     warn!(
@@ -132,6 +183,82 @@ impl Externals for ContractInstance {
 
                 self.query_chain_index(query)
             }
+            HostFunctions::RandomIndex => self.random_index(),
+            HostFunctions::Secp256k1VerifyIndex => {
+                let message_hash: i32 = args.nth_checked(0).map_err(|err| {
+                    warn!(
+                        "secp256k1_verify() error reading first argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+                let signature: i32 = args.nth_checked(1).map_err(|err| {
+                    warn!(
+                        "secp256k1_verify() error reading second argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+                let public_key: i32 = args.nth_checked(2).map_err(|err| {
+                    warn!(
+                        "secp256k1_verify() error reading third argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+
+                self.secp256k1_verify_index(message_hash, signature, public_key)
+            }
+            HostFunctions::Ed25519VerifyIndex => {
+                let message: i32 = args.nth_checked(0).map_err(|err| {
+                    warn!(
+                        "ed25519_verify() error reading first argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+                let signature: i32 = args.nth_checked(1).map_err(|err| {
+                    warn!(
+                        "ed25519_verify() error reading second argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+                let public_key: i32 = args.nth_checked(2).map_err(|err| {
+                    warn!(
+                        "ed25519_verify() error reading third argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+
+                self.ed25519_verify_index(message, signature, public_key)
+            }
+            HostFunctions::Ed25519BatchVerifyIndex => {
+                let messages: i32 = args.nth_checked(0).map_err(|err| {
+                    warn!(
+                        "ed25519_batch_verify() error reading first argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+                let signatures: i32 = args.nth_checked(1).map_err(|err| {
+                    warn!(
+                        "ed25519_batch_verify() error reading second argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+                let public_keys: i32 = args.nth_checked(2).map_err(|err| {
+                    warn!(
+                        "ed25519_batch_verify() error reading third argument, stopping wasm: {:?}",
+                        err
+                    );
+                    err
+                })?;
+
+                self.ed25519_batch_verify_index(messages, signatures, public_keys)
+            }
             HostFunctions::GasIndex => {
                 let gas_amount: i32 = args.nth_checked(0).map_err(|err| {
                     warn!("gas() error reading arguments, stopping wasm: {:?}", err);
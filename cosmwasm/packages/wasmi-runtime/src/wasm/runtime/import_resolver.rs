@@ -44,6 +44,19 @@ impl ModuleImportResolver for WasmiImportResolver {
                 Signature::new(&[ValueType::I32][..], None),
                 HostFunctions::RemoveDbIndex.into(),
             ),
+            // fn db_scan(start: *const c_void, end: *const c_void, order: i32) -> i32;
+            "db_scan" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                HostFunctions::ScanDbIndex.into(),
+            ),
+            // fn db_next(iterator_id: i32) -> i32;
+            "db_next" => FuncInstance::alloc_host(
+                Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
+                HostFunctions::NextDbIndex.into(),
+            ),
             // fn canonicalize_address(human: *const c_void, canonical: *mut c_void) -> i32;
             "canonicalize_address" => FuncInstance::alloc_host(
                 Signature::new(&[ValueType::I32, ValueType::I32][..], Some(ValueType::I32)),
@@ -58,6 +71,35 @@ impl ModuleImportResolver for WasmiImportResolver {
                 Signature::new(&[ValueType::I32][..], Some(ValueType::I32)),
                 HostFunctions::QueryChainIndex.into(),
             ),
+            // fn random() -> u32;
+            "random" => FuncInstance::alloc_host(
+                Signature::new(&[][..], Some(ValueType::I32)),
+                HostFunctions::RandomIndex.into(),
+            ),
+            // fn secp256k1_verify(message_hash: u32, signature: u32, public_key: u32) -> u32;
+            "secp256k1_verify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                HostFunctions::Secp256k1VerifyIndex.into(),
+            ),
+            // fn ed25519_verify(message: u32, signature: u32, public_key: u32) -> u32;
+            "ed25519_verify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                HostFunctions::Ed25519VerifyIndex.into(),
+            ),
+            // fn ed25519_batch_verify(messages: u32, signatures: u32, public_keys: u32) -> u32;
+            "ed25519_batch_verify" => FuncInstance::alloc_host(
+                Signature::new(
+                    &[ValueType::I32, ValueType::I32, ValueType::I32][..],
+                    Some(ValueType::I32),
+                ),
+                HostFunctions::Ed25519BatchVerifyIndex.into(),
+            ),
             #[cfg(feature = "debug-print")]
             "debug_print" => FuncInstance::alloc_host(
                 Signature::new(&[ValueType::I32][..], None),
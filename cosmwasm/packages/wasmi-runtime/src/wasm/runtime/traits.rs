@@ -12,6 +12,15 @@ pub trait WasmiApi {
         value_ptr_ptr: i32,
     ) -> Result<Option<RuntimeValue>, Trap>;
 
+    fn db_scan_index(
+        &mut self,
+        start_ptr_ptr: i32,
+        end_ptr_ptr: i32,
+        order: i32,
+    ) -> Result<Option<RuntimeValue>, Trap>;
+
+    fn db_next_index(&mut self, iterator_id: i32) -> Result<Option<RuntimeValue>, Trap>;
+
     fn canonicalize_address_index(
         &mut self,
         canonical_ptr_ptr: i32,
@@ -26,6 +35,29 @@ pub trait WasmiApi {
 
     fn query_chain_index(&mut self, query_ptr_ptr: i32) -> Result<Option<RuntimeValue>, Trap>;
 
+    fn random_index(&mut self) -> Result<Option<RuntimeValue>, Trap>;
+
+    fn secp256k1_verify_index(
+        &mut self,
+        message_hash_ptr_ptr: i32,
+        signature_ptr_ptr: i32,
+        public_key_ptr_ptr: i32,
+    ) -> Result<Option<RuntimeValue>, Trap>;
+
+    fn ed25519_verify_index(
+        &mut self,
+        message_ptr_ptr: i32,
+        signature_ptr_ptr: i32,
+        public_key_ptr_ptr: i32,
+    ) -> Result<Option<RuntimeValue>, Trap>;
+
+    fn ed25519_batch_verify_index(
+        &mut self,
+        messages_ptr_ptr: i32,
+        signatures_ptr_ptr: i32,
+        public_keys_ptr_ptr: i32,
+    ) -> Result<Option<RuntimeValue>, Trap>;
+
     fn gas_index(&mut self, gas_amount: i32) -> Result<Option<RuntimeValue>, Trap>;
 
     #[cfg(feature = "debug-print")]
@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+
+use log::*;
+use sgx_types::sgx_status_t;
+
+use enclave_ffi_types::{
+    Ctx, EnclaveBuffer, FlushGas, OcallFailure, OcallKind, OcallReturn, UntrustedVmError,
+};
+
+use crate::{exports, imports};
+
+use super::errors::WasmEngineError;
+
+/// The kinds of operations that are sent to the host in a flush
+const OP_READ: u8 = 0;
+const OP_WRITE: u8 = 1;
+const OP_REMOVE: u8 = 2;
+
+/// Writes and removals are charged as soon as they are buffered, with the default gas config of the
+/// Cosmos SDK stores. Writes are charged by the length of the key and value, so the estimate is a
+/// little lower than the real cost, which also counts the prefix the host adds to the key. The rest
+/// is charged when the cache is flushed.
+const WRITE_COST_FLAT: u64 = 2000;
+const WRITE_COST_PER_BYTE: u64 = 30;
+const REMOVE_COST: u64 = 1000;
+
+/// A write-back cache of the contract storage, for the duration of one execution.
+///
+/// Reads that miss the cache go to the host right away. All the other operations are buffered,
+/// and sent to the host in one ocall when the cache is flushed. The host then applies them in
+/// the original order, so it charges exactly the same gas it would have charged for them one by one.
+/// Reads that hit the cache are sent as well, but only for the sake of gas accounting.
+///
+/// Writes and removals return an estimate of their gas, which the caller charges right away, so a
+/// contract can't buffer more of them than its gas pays for. Flushing only returns the gas the host
+/// used on top of the estimates.
+///
+/// The keys and values here are the ones the host sees, i.e. after encryption.
+#[derive(Default)]
+pub struct StorageCache {
+    /// The current value of every key that was read or written. `None` means the key doesn't exist.
+    values: HashMap<Vec<u8>, Option<Vec<u8>>>,
+    /// The encoded operations that were not sent to the host yet
+    pending_ops: Vec<u8>,
+    /// The estimated gas that was already charged for the pending writes and removals
+    charged_gas: FlushGas,
+}
+
+impl StorageCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the value of `key`, and the gas used by the host to read it
+    pub fn read(
+        &mut self,
+        context: &Ctx,
+        key: &[u8],
+    ) -> Result<(Option<Vec<u8>>, u64), WasmEngineError> {
+        self.read_with(key, |key| read_db(context, key))
+    }
+
+    fn read_with<F>(
+        &mut self,
+        key: &[u8],
+        read_from_host: F,
+    ) -> Result<(Option<Vec<u8>>, u64), WasmEngineError>
+    where
+        F: FnOnce(&[u8]) -> Result<(Option<Vec<u8>>, u64), WasmEngineError>,
+    {
+        if let Some(value) = self.values.get(key) {
+            let value = value.clone();
+            self.push_op(OP_READ, key, None);
+            return Ok((value, 0));
+        }
+
+        let (value, gas_used) = read_from_host(key)?;
+        self.values.insert(key.to_vec(), value.clone());
+        Ok((value, gas_used))
+    }
+
+    /// Buffers a write, and returns its estimated gas, which the caller should charge right away
+    pub fn write(&mut self, key: &[u8], value: Vec<u8>) -> u64 {
+        let len = (key.len() + value.len()) as u64;
+        let gas = WRITE_COST_FLAT.saturating_add(len.saturating_mul(WRITE_COST_PER_BYTE));
+        self.push_op(OP_WRITE, key, Some(&value));
+        self.charged_gas.write = self.charged_gas.write.saturating_add(gas);
+        self.values.insert(key.to_vec(), Some(value));
+        gas
+    }
+
+    /// Buffers a removal, and returns its estimated gas, which the caller should charge right away
+    pub fn remove(&mut self, key: &[u8]) -> u64 {
+        self.push_op(OP_REMOVE, key, None);
+        self.charged_gas.remove = self.charged_gas.remove.saturating_add(REMOVE_COST);
+        self.values.insert(key.to_vec(), None);
+        REMOVE_COST
+    }
+
+    /// Sends all the buffered operations to the host, and returns the gas the host used for them
+    /// that wasn't charged yet, i.e. the gas of the cached reads and whatever the writes and removals
+    /// cost beyond their estimate.
+    pub fn flush(&mut self, context: &Ctx) -> Result<FlushGas, WasmEngineError> {
+        self.flush_with(|ops| flush_db(context, ops))
+    }
+
+    fn flush_with<F>(&mut self, flush_to_host: F) -> Result<FlushGas, WasmEngineError>
+    where
+        F: FnOnce(&[u8]) -> Result<FlushGas, WasmEngineError>,
+    {
+        if self.pending_ops.is_empty() {
+            return Ok(FlushGas::default());
+        }
+
+        // The operations are taken out first, so they are never sent twice
+        let ops = std::mem::replace(&mut self.pending_ops, vec![]);
+        let charged_gas = std::mem::take(&mut self.charged_gas);
+        trace!("Flushing {} bytes of storage operations", ops.len());
+
+        let gas_used = flush_to_host(&ops)?;
+        Ok(FlushGas {
+            read: gas_used.read,
+            write: gas_used.write.saturating_sub(charged_gas.write),
+            remove: gas_used.remove.saturating_sub(charged_gas.remove),
+        })
+    }
+
+    /// Operations are encoded as `op || key_len || key`, followed by `value_len || value` for writes.
+    /// The lengths are big endian u32 values.
+    fn push_op(&mut self, op: u8, key: &[u8], value: Option<&[u8]>) {
+        self.pending_ops.push(op);
+        self.pending_ops
+            .extend_from_slice(&(key.len() as u32).to_be_bytes());
+        self.pending_ops.extend_from_slice(key);
+        if let Some(value) = value {
+            self.pending_ops
+                .extend_from_slice(&(value.len() as u32).to_be_bytes());
+            self.pending_ops.extend_from_slice(value);
+        }
+    }
+}
+
+/// Safe wrapper around reads from the contract storage
+fn read_db(context: &Ctx, key: &[u8]) -> Result<(Option<Vec<u8>>, u64), WasmEngineError> {
+    let mut ocall_return = OcallReturn::Success;
+    let mut enclave_buffer = std::mem::MaybeUninit::<EnclaveBuffer>::uninit();
+    let mut vm_err = UntrustedVmError::default();
+    let mut gas_used = 0_u64;
+    let value = unsafe {
+        let status = imports::ocall_read_db(
+            (&mut ocall_return) as *mut _,
+            context.unsafe_clone(),
+            (&mut vm_err) as *mut _,
+            (&mut gas_used) as *mut _,
+            enclave_buffer.as_mut_ptr(),
+            key.as_ptr(),
+            key.len(),
+        );
+        match status {
+            sgx_status_t::SGX_SUCCESS => { /* continue */ }
+            error_status => {
+                warn!(
+                    "read_db() got an error from ocall_read_db, stopping wasm: {:?}",
+                    error_status
+                );
+                return Err(WasmEngineError::failed_ocall(
+                    OcallKind::ReadDb,
+                    OcallFailure::Sgx,
+                    vm_err,
+                ));
+            }
+        }
+
+        match ocall_return {
+            OcallReturn::Success => {
+                let enclave_buffer = enclave_buffer.assume_init();
+                exports::recover_buffer(enclave_buffer)?
+            }
+            OcallReturn::Failure => {
+                return Err(WasmEngineError::failed_ocall(
+                    OcallKind::ReadDb,
+                    OcallFailure::Host,
+                    vm_err,
+                ));
+            }
+            OcallReturn::Panic => {
+                return Err(WasmEngineError::failed_ocall(
+                    OcallKind::ReadDb,
+                    OcallFailure::HostPanic,
+                    vm_err,
+                ))
+            }
+        }
+    };
+
+    Ok((value, gas_used))
+}
+
+/// Safe wrapper around batched writes to the contract storage
+fn flush_db(context: &Ctx, ops: &[u8]) -> Result<FlushGas, WasmEngineError> {
+    let mut ocall_return = OcallReturn::Success;
+    let mut vm_err = UntrustedVmError::default();
+    let mut gas_used = FlushGas::default();
+    match unsafe {
+        imports::ocall_flush_db(
+            (&mut ocall_return) as *mut _,
+            context.unsafe_clone(),
+            (&mut vm_err) as *mut _,
+            (&mut gas_used) as *mut _,
+            ops.as_ptr(),
+            ops.len(),
+        )
+    } {
+        sgx_status_t::SGX_SUCCESS => { /* continue */ }
+        error_status => {
+            warn!(
+                "flush_db() got an error from ocall_flush_db, stopping wasm: {:?}",
+                error_status
+            );
+            return Err(WasmEngineError::failed_ocall(
+                OcallKind::FlushDb,
+                OcallFailure::Sgx,
+                vm_err,
+            ));
+        }
+    }
+
+    match ocall_return {
+        OcallReturn::Success => Ok(gas_used),
+        OcallReturn::Failure => Err(WasmEngineError::failed_ocall(
+            OcallKind::FlushDb,
+            OcallFailure::Host,
+            vm_err,
+        )),
+        OcallReturn::Panic => Err(WasmEngineError::failed_ocall(
+            OcallKind::FlushDb,
+            OcallFailure::HostPanic,
+            vm_err,
+        )),
+    }
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use super::*;
+
+    fn read_from_host(value: Option<&[u8]>) -> impl FnOnce(&[u8]) -> ReadResult + '_ {
+        move |_key| Ok((value.map(|value| value.to_vec()), 10))
+    }
+
+    type ReadResult = Result<(Option<Vec<u8>>, u64), WasmEngineError>;
+
+    fn unreachable_host(_key: &[u8]) -> ReadResult {
+        panic!("the host should not be called")
+    }
+
+    pub fn test_storage_cache_reads_each_key_from_host_once() {
+        let mut cache = StorageCache::new();
+
+        let (value, gas_used) = cache
+            .read_with(b"key", read_from_host(Some(b"value")))
+            .unwrap();
+        assert_eq!(value, Some(b"value".to_vec()));
+        assert_eq!(gas_used, 10);
+        // The first read went to the host, so there is nothing to replay
+        assert!(cache.pending_ops.is_empty());
+
+        let (value, gas_used) = cache.read_with(b"key", unreachable_host).unwrap();
+        assert_eq!(value, Some(b"value".to_vec()));
+        assert_eq!(gas_used, 0);
+        assert_eq!(cache.pending_ops, b"\x00\x00\x00\x00\x03key".to_vec());
+    }
+
+    pub fn test_storage_cache_caches_missing_keys() {
+        let mut cache = StorageCache::new();
+
+        let (value, _) = cache.read_with(b"key", read_from_host(None)).unwrap();
+        assert_eq!(value, None);
+        let (value, _) = cache.read_with(b"key", unreachable_host).unwrap();
+        assert_eq!(value, None);
+    }
+
+    pub fn test_storage_cache_reads_its_own_writes() {
+        let mut cache = StorageCache::new();
+
+        cache.write(b"key", b"value".to_vec());
+        let (value, gas_used) = cache.read_with(b"key", unreachable_host).unwrap();
+        assert_eq!(value, Some(b"value".to_vec()));
+        assert_eq!(gas_used, 0);
+
+        cache.remove(b"key");
+        let (value, _) = cache.read_with(b"key", unreachable_host).unwrap();
+        assert_eq!(value, None);
+    }
+
+    pub fn test_storage_cache_keeps_the_order_of_operations() {
+        let mut cache = StorageCache::new();
+
+        cache.write(b"a", b"1".to_vec());
+        cache.write(b"a", b"22".to_vec());
+        cache.read_with(b"a", unreachable_host).unwrap();
+        cache.remove(b"b");
+
+        let mut expected = vec![];
+        expected.extend_from_slice(b"\x01\x00\x00\x00\x01a\x00\x00\x00\x011");
+        expected.extend_from_slice(b"\x01\x00\x00\x00\x01a\x00\x00\x00\x0222");
+        expected.extend_from_slice(b"\x00\x00\x00\x00\x01a");
+        expected.extend_from_slice(b"\x02\x00\x00\x00\x01b");
+        assert_eq!(cache.pending_ops, expected);
+    }
+
+    pub fn test_storage_cache_charges_writes_when_buffered() {
+        let mut cache = StorageCache::new();
+
+        assert_eq!(
+            cache.write(b"key", b"value".to_vec()),
+            WRITE_COST_FLAT + 8 * WRITE_COST_PER_BYTE
+        );
+        assert_eq!(cache.remove(b"gone"), REMOVE_COST);
+
+        // The host charges a little more for the write, as it prefixes the key
+        let host_gas = FlushGas {
+            read: 0,
+            write: WRITE_COST_FLAT + 20 * WRITE_COST_PER_BYTE,
+            remove: REMOVE_COST,
+        };
+        let gas_used = cache.flush_with(|_ops| Ok(host_gas)).unwrap();
+        assert_eq!(
+            gas_used,
+            FlushGas {
+                read: 0,
+                write: 12 * WRITE_COST_PER_BYTE,
+                remove: 0,
+            }
+        );
+
+        // Only the operations since the last flush are reconciled
+        cache.remove(b"key");
+        let gas_used = cache
+            .flush_with(|_ops| {
+                Ok(FlushGas {
+                    remove: REMOVE_COST,
+                    ..FlushGas::default()
+                })
+            })
+            .unwrap();
+        assert_eq!(gas_used, FlushGas::default());
+    }
+}
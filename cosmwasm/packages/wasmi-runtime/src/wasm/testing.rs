@@ -0,0 +1,71 @@
+//! Helpers for tests that link the runtime natively. They stand in for the client and for the
+//! calling contract, using the keys of the fixed test seed, so they only exist in native builds.
+
+use enclave_ffi_types::{DeserializationTarget, EnclaveError};
+
+use crate::cosmwasm::types::{CanonicalAddr, Env};
+use crate::crypto::{Ed25519PublicKey, SIVEncryptable};
+
+use super::contract_validation::extract_seed_epoch;
+use super::io::create_callback_signature;
+use super::types::{IoNonce, SecretMessage};
+
+/// Encrypts `msg` the way clients do, prefixed by the code hash of the contract it's meant for
+pub fn encrypt_msg(
+    code_hash: &str,
+    msg: &[u8],
+    nonce: IoNonce,
+    user_public_key: Ed25519PublicKey,
+) -> Result<Vec<u8>, EnclaveError> {
+    let mut hash_appended_msg = code_hash.as_bytes().to_vec();
+    hash_appended_msg.extend_from_slice(msg);
+
+    let mut secret_msg = SecretMessage {
+        nonce,
+        user_public_key,
+        msg: hash_appended_msg,
+        io_epoch: 0,
+    };
+    secret_msg.encrypt_in_place()?;
+
+    Ok(secret_msg.to_vec())
+}
+
+/// Decrypts a field of the output for the sender of a message from `encrypt_msg`
+pub fn decrypt_output(
+    output: &[u8],
+    nonce: IoNonce,
+    user_public_key: Ed25519PublicKey,
+) -> Result<Vec<u8>, EnclaveError> {
+    let secret_msg = SecretMessage {
+        nonce,
+        user_public_key,
+        msg: output.to_vec(),
+        io_epoch: 0,
+    };
+
+    secret_msg
+        .encryption_key()
+        .decrypt_siv(&secret_msg.msg, None)
+        .map_err(|_| EnclaveError::DecryptionError)
+}
+
+/// Signs `msg` for the sender and the funds in `env`, like a contract that sends the message.
+/// The enclave accepts this signature instead of the one of a transaction.
+pub fn callback_signature(env: &[u8], msg: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let env: Env = serde_json::from_slice(env).map_err(|_| EnclaveError::FailedToDeserialize {
+        target: DeserializationTarget::Env,
+    })?;
+    let sender = CanonicalAddr::from_human(&env.message.sender).map_err(|_| {
+        EnclaveError::FailedToDeserialize {
+            target: DeserializationTarget::Address,
+        }
+    })?;
+
+    Ok(create_callback_signature(
+        &sender,
+        &SecretMessage::from_slice(msg)?,
+        &env.message.sent_funds,
+        extract_seed_epoch(&env)?,
+    ))
+}
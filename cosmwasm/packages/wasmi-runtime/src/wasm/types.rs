@@ -2,7 +2,7 @@
 
 use crate::crypto::{AESKey, Ed25519PublicKey, SIVEncryptable};
 use crate::wasm::io::calc_encryption_key;
-use enclave_ffi_types::EnclaveError;
+use enclave_ffi_types::{DeserializationTarget, EnclaveError};
 use log::*;
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +13,10 @@ pub struct SecretMessage {
     pub nonce: IoNonce,
     pub user_public_key: Ed25519PublicKey,
     pub msg: Vec<u8>,
+    /// The epoch of the consensus seed whose IO key the message is encrypted with. It isn't part
+    /// of the message, it is found when the message is decrypted
+    #[serde(skip)]
+    pub io_epoch: u32,
 }
 
 impl SecretMessage {
@@ -40,27 +44,55 @@ impl SecretMessage {
         Ok(msg)
     }
 
+    /// Decrypts a message whose IO epoch isn't known yet. Clients encrypt to the IO key of
+    /// whichever epoch they know of, so every epoch up to `max_epoch` is tried, newest first.
+    /// AES-SIV authenticates the message, so only the key it was encrypted with decrypts it.
+    ///
+    /// The epoch that decrypted the message is kept in `io_epoch`, so the output is encrypted
+    /// with the same key.
+    pub fn decrypt_any_epoch(&mut self, max_epoch: u32) -> Result<Vec<u8>, EnclaveError> {
+        for epoch in (0..=max_epoch).rev() {
+            let key = calc_encryption_key(&self.nonce, &self.user_public_key, epoch);
+
+            if let Ok(msg) = key.decrypt_siv(self.msg.as_slice(), None) {
+                trace!("decrypted the msg with the IO key of epoch {}", epoch);
+                self.io_epoch = epoch;
+                return Ok(msg);
+            }
+        }
+
+        error!(
+            "got an error while trying to decrypt the msg with the IO keys of epochs 0 to {}",
+            max_epoch
+        );
+        Err(EnclaveError::DecryptionError)
+    }
+
     pub fn encryption_key(&self) -> AESKey {
-        calc_encryption_key(&self.nonce, &self.user_public_key)
+        calc_encryption_key(&self.nonce, &self.user_public_key, self.io_epoch)
     }
 
     pub fn from_base64(
         msg_b64: String,
         nonce: IoNonce,
         user_public_key: Ed25519PublicKey,
+        io_epoch: u32,
     ) -> Result<Self, EnclaveError> {
         let msg = base64::decode(&msg_b64.to_owned().into_bytes()).map_err(|err| {
             error!(
                 "got an error while trying to decode msg to next contract as base64 {:?}: {:?}",
                 msg_b64, err
             );
-            EnclaveError::FailedToDeserialize
+            EnclaveError::FailedToDeserialize {
+                target: DeserializationTarget::Base64,
+            }
         })?;
 
         Ok(SecretMessage {
             msg,
             nonce,
             user_public_key,
+            io_epoch,
         })
     }
 
@@ -92,6 +124,7 @@ impl SecretMessage {
             nonce,
             user_public_key: user_pubkey,
             msg: msg[64..].to_vec(),
+            io_epoch: 0,
         })
     }
 
@@ -123,6 +156,7 @@ pub mod tests {
             nonce,
             user_public_key,
             msg: msg.as_bytes().to_vec(),
+            io_epoch: 0,
         };
 
         let msg_from_slice = SecretMessage::from_slice(&slice).unwrap();
@@ -143,7 +177,7 @@ pub mod tests {
     //     let user_public_key = [0u8; 32];
     //
     //     let msg = "{\"ok\": \"{\"balance\": \"108\"}\"}";
-    //     let key = calc_encryption_key(&nonce, &user_public_key);
+    //     let key = calc_encryption_key(&nonce, &user_public_key, 0);
     //
     //     let encrypted_msg = key.encrypt_siv(msg.as_bytes(), &[&[]]);
     //
@@ -0,0 +1,348 @@
+#!/usr/bin/env python3
+"""
+Generates the DCAP fixtures in `src/registration/fixtures/dcap`, used by
+`registration::dcap::tests`.
+
+The quote and collateral follow the layout produced by the Intel DCAP quoting
+library and the Intel PCS v3 API, but every key and certificate is generated
+here and chains up to a throwaway test root instead of the Intel SGX Root CA.
+Re-running this script produces a fresh set of keys, so all of the files in
+the fixture directory must be regenerated (and committed) together.
+
+Requires the `cryptography` package.
+"""
+
+import datetime
+import hashlib
+import json
+import os
+import struct
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec
+from cryptography.hazmat.primitives.asymmetric.utils import decode_dss_signature
+from cryptography.x509.oid import NameOID
+
+OUT_DIR = os.path.join(
+    os.path.dirname(os.path.abspath(__file__)), "..", "src", "registration", "fixtures", "dcap"
+)
+
+NOT_BEFORE = datetime.datetime(2020, 1, 1)
+NOT_AFTER = datetime.datetime(2049, 12, 31)
+ISSUE_DATE = "2021-06-01T00:00:00Z"
+NEXT_UPDATE = "2021-07-01T00:00:00Z"
+
+FMSPC = bytes.fromhex("00906ea10000")
+PCE_ID = bytes.fromhex("0000")
+PCE_SVN = 10
+# sgxtcbcomp01svn..sgxtcbcomp16svn of the attesting platform
+CPU_SVN = bytes([15, 15, 2, 4, 1, 128, 6, 0, 0, 0, 0, 0, 0, 0, 0, 0])
+
+ENCLAVE_MR_ENCLAVE = bytes(range(32))
+ENCLAVE_MR_SIGNER = bytes(range(32, 64))
+ENCLAVE_PUBKEY = bytes([0xAB] * 32)
+
+QE_MR_SIGNER = bytes.fromhex(
+    "8c4f5775d796503e96137f77c68a829a0056ac8ded70140b081b094490c57bff"
+)
+QE_ISV_PROD_ID = 1
+QE_ISV_SVN = 5
+
+SGX_EXTENSION_OID = "1.2.840.113741.1.13.1"
+
+
+# --- minimal DER encoding for the SGX PCK certificate extension ---
+
+
+def der_len(n):
+    if n < 0x80:
+        return bytes([n])
+    body = n.to_bytes((n.bit_length() + 7) // 8, "big")
+    return bytes([0x80 | len(body)]) + body
+
+
+def der(tag, body):
+    return bytes([tag]) + der_len(len(body)) + body
+
+
+def der_oid(dotted):
+    parts = [int(p) for p in dotted.split(".")]
+    body = bytes([parts[0] * 40 + parts[1]])
+    for p in parts[2:]:
+        chunk = [p & 0x7F]
+        p >>= 7
+        while p:
+            chunk.insert(0, 0x80 | (p & 0x7F))
+            p >>= 7
+        body += bytes(chunk)
+    return der(0x06, body)
+
+
+def der_int(v):
+    body = v.to_bytes(max(1, (v.bit_length() + 8) // 8), "big")
+    return der(0x02, body)
+
+
+def der_seq(*items):
+    return der(0x30, b"".join(items))
+
+
+def sgx_extension():
+    tcb = [
+        der_seq(der_oid("%s.2.%d" % (SGX_EXTENSION_OID, i + 1)), der_int(svn))
+        for i, svn in enumerate(CPU_SVN)
+    ]
+    tcb.append(der_seq(der_oid(SGX_EXTENSION_OID + ".2.17"), der_int(PCE_SVN)))
+    tcb.append(der_seq(der_oid(SGX_EXTENSION_OID + ".2.18"), der(0x04, CPU_SVN)))
+    return der_seq(
+        der_seq(der_oid(SGX_EXTENSION_OID + ".1"), der(0x04, bytes(16))),
+        der_seq(der_oid(SGX_EXTENSION_OID + ".2"), der_seq(*tcb)),
+        der_seq(der_oid(SGX_EXTENSION_OID + ".3"), der(0x04, PCE_ID)),
+        der_seq(der_oid(SGX_EXTENSION_OID + ".4"), der(0x04, FMSPC)),
+        der_seq(der_oid(SGX_EXTENSION_OID + ".5"), der(0x0A, b"\x00")),
+    )
+
+
+# --- certificates ---
+
+
+def name(cn):
+    return x509.Name([x509.NameAttribute(NameOID.COMMON_NAME, cn)])
+
+
+def make_cert(subject, subject_key, issuer, issuer_key, is_ca, extra=()):
+    builder = (
+        x509.CertificateBuilder()
+        .subject_name(name(subject))
+        .issuer_name(name(issuer))
+        .public_key(subject_key.public_key())
+        .serial_number(x509.random_serial_number())
+        .not_valid_before(NOT_BEFORE)
+        .not_valid_after(NOT_AFTER)
+        .add_extension(x509.BasicConstraints(ca=is_ca, path_length=None), critical=True)
+    )
+    for ext in extra:
+        builder = builder.add_extension(ext, critical=False)
+    return builder.sign(issuer_key, hashes.SHA256())
+
+
+def der_bytes(cert):
+    return cert.public_bytes(serialization.Encoding.DER)
+
+
+def pem_bytes(cert):
+    return cert.public_bytes(serialization.Encoding.PEM)
+
+
+def raw_sign(key, data):
+    r, s = decode_dss_signature(key.sign(data, ec.ECDSA(hashes.SHA256())))
+    return r.to_bytes(32, "big") + s.to_bytes(32, "big")
+
+
+def raw_pubkey(key):
+    point = key.public_key().public_bytes(
+        serialization.Encoding.X962, serialization.PublicFormat.UncompressedPoint
+    )
+    return point[1:]
+
+
+# --- quote ---
+
+
+def report_body(cpu_svn, mr_enclave, mr_signer, isv_prod_id, isv_svn, report_data):
+    body = cpu_svn
+    body += struct.pack("<I", 0)  # misc select
+    body += bytes(28)
+    body += bytes([0x07] + [0] * 7 + [0x07] + [0] * 7)  # attributes
+    body += mr_enclave
+    body += bytes(32)
+    body += mr_signer
+    body += bytes(96)
+    body += struct.pack("<HH", isv_prod_id, isv_svn)
+    body += bytes(60)
+    body += report_data
+    assert len(body) == 384
+    return body
+
+
+def make_quote(pck_key, pck_chain_pem):
+    attestation_key = ec.generate_private_key(ec.SECP256R1())
+
+    header = struct.pack("<HHIHH", 3, 2, 0, QE_ISV_SVN, PCE_SVN)
+    header += bytes.fromhex("939a7233f79c4ca9940a0db3957f0607")  # Intel QE vendor id
+    header += bytes(20)
+    assert len(header) == 48
+
+    isv_report = report_body(
+        CPU_SVN, ENCLAVE_MR_ENCLAVE, ENCLAVE_MR_SIGNER, 0, 0, ENCLAVE_PUBKEY + bytes(32)
+    )
+
+    qe_auth_data = bytes(range(32))
+    ak = raw_pubkey(attestation_key)
+    qe_report = report_body(
+        CPU_SVN,
+        bytes(32),
+        QE_MR_SIGNER,
+        QE_ISV_PROD_ID,
+        QE_ISV_SVN,
+        hashlib.sha256(ak + qe_auth_data).digest() + bytes(32),
+    )
+
+    sig_data = raw_sign(attestation_key, header + isv_report)
+    sig_data += ak
+    sig_data += qe_report
+    sig_data += raw_sign(pck_key, qe_report)
+    sig_data += struct.pack("<H", len(qe_auth_data)) + qe_auth_data
+    sig_data += struct.pack("<HI", 5, len(pck_chain_pem)) + pck_chain_pem
+
+    return header + isv_report + struct.pack("<I", len(sig_data)) + sig_data
+
+
+# --- collateral ---
+
+
+def tcb_level(cpu_svn, pce_svn, status):
+    tcb = {"sgxtcbcomp%02dsvn" % (i + 1): svn for i, svn in enumerate(cpu_svn)}
+    tcb["pcesvn"] = pce_svn
+    return {"tcb": tcb, "tcbDate": "2021-05-12T00:00:00Z", "tcbStatus": status}
+
+
+def signed_body(key, field, value):
+    body = json.dumps(value, separators=(",", ":"))
+    signature = raw_sign(key, body.encode()).hex()
+    return '{"%s":%s,"signature":"%s"}' % (field, body, signature)
+
+
+def tcb_info(key, levels):
+    return signed_body(
+        key,
+        "tcbInfo",
+        {
+            "version": 2,
+            "issueDate": ISSUE_DATE,
+            "nextUpdate": NEXT_UPDATE,
+            "fmspc": FMSPC.hex(),
+            "pceId": PCE_ID.hex(),
+            "tcbType": 0,
+            "tcbEvaluationDataNumber": 10,
+            "tcbLevels": levels,
+        },
+    )
+
+
+def qe_identity(key):
+    return signed_body(
+        key,
+        "enclaveIdentity",
+        {
+            "id": "QE",
+            "version": 2,
+            "issueDate": ISSUE_DATE,
+            "nextUpdate": NEXT_UPDATE,
+            "tcbEvaluationDataNumber": 10,
+            "miscselect": "00000000",
+            "miscselectMask": "FFFFFFFF",
+            "attributes": "03000000000000000000000000000000",
+            "attributesMask": "FBFFFFFFFFFFFFFF0000000000000000",
+            "mrsigner": QE_MR_SIGNER.hex().upper(),
+            "isvprodid": QE_ISV_PROD_ID,
+            "tcbLevels": [
+                {
+                    "tcb": {"isvsvn": QE_ISV_SVN},
+                    "tcbDate": "2021-05-12T00:00:00Z",
+                    "tcbStatus": "UpToDate",
+                }
+            ],
+        },
+    )
+
+
+# --- revocation lists ---
+
+
+def crl(issuer, issuer_key, revoked_serials):
+    builder = (
+        x509.CertificateRevocationListBuilder()
+        .issuer_name(name(issuer))
+        .last_update(datetime.datetime(2021, 6, 1))
+        .next_update(datetime.datetime(2021, 7, 1))
+    )
+    for serial in revoked_serials:
+        builder = builder.add_revoked_certificate(
+            x509.RevokedCertificateBuilder()
+            .serial_number(serial)
+            .revocation_date(datetime.datetime(2021, 5, 1))
+            .build()
+        )
+    return builder.sign(issuer_key, hashes.SHA256()).public_bytes(serialization.Encoding.DER)
+
+
+def write(file_name, data):
+    with open(os.path.join(OUT_DIR, file_name), "wb") as f:
+        f.write(data)
+
+
+def main():
+    root_key = ec.generate_private_key(ec.SECP256R1())
+    root = make_cert("Test SGX Root CA", root_key, "Test SGX Root CA", root_key, True)
+
+    platform_key = ec.generate_private_key(ec.SECP256R1())
+    platform = make_cert("Test SGX PCK Platform CA", platform_key, "Test SGX Root CA", root_key, True)
+
+    pck_key = ec.generate_private_key(ec.SECP256R1())
+    pck = make_cert(
+        "Test SGX PCK Certificate",
+        pck_key,
+        "Test SGX PCK Platform CA",
+        platform_key,
+        False,
+        [x509.UnrecognizedExtension(x509.ObjectIdentifier(SGX_EXTENSION_OID), sgx_extension())],
+    )
+
+    tcb_key = ec.generate_private_key(ec.SECP256R1())
+    tcb_signing = make_cert("Test SGX TCB Signing", tcb_key, "Test SGX Root CA", root_key, False)
+
+    pck_chain = pem_bytes(pck) + pem_bytes(platform) + pem_bytes(root)
+    issuer_chain = pem_bytes(tcb_signing) + pem_bytes(root)
+
+    newer_cpu_svn = bytes([CPU_SVN[0] + 1]) + CPU_SVN[1:]
+    older_cpu_svn = bytes([CPU_SVN[0] - 1]) + CPU_SVN[1:]
+
+    # revokes an unrelated certificate, so the revoked list is not empty
+    unrelated_serial = x509.random_serial_number()
+
+    write("root_ca.der", der_bytes(root))
+    write("root_ca_crl.der", crl("Test SGX Root CA", root_key, [unrelated_serial]))
+    write("pck_crl.der", crl("Test SGX PCK Platform CA", platform_key, [unrelated_serial]))
+    write(
+        "pck_crl_revoked.der",
+        crl("Test SGX PCK Platform CA", platform_key, [unrelated_serial, pck.serial_number]),
+    )
+    write("quote.bin", make_quote(pck_key, pck_chain))
+    write("issuer_chain.pem", issuer_chain)
+    write("qe_identity.json", qe_identity(tcb_key).encode())
+    write(
+        "tcb_info.json",
+        tcb_info(
+            tcb_key,
+            [
+                tcb_level(CPU_SVN, PCE_SVN, "UpToDate"),
+                tcb_level(older_cpu_svn, PCE_SVN, "OutOfDate"),
+            ],
+        ).encode(),
+    )
+    write(
+        "tcb_info_out_of_date.json",
+        tcb_info(
+            tcb_key,
+            [
+                tcb_level(newer_cpu_svn, PCE_SVN, "UpToDate"),
+                tcb_level(CPU_SVN, PCE_SVN, "OutOfDate"),
+            ],
+        ).encode(),
+    )
+
+
+if __name__ == "__main__":
+    main()
@@ -37,7 +37,7 @@ func setupQueueContractWithData(t *testing.T, cache Cache, values ...int) queueD
 	msg := []byte(`{}`)
 
 	igasMeter1 := GasMeter(gasMeter1)
-	res, _, err := Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, 100000000, nil)
+	res, _, err := Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, 100000000, nil, nil)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
 
@@ -45,7 +45,7 @@ func setupQueueContractWithData(t *testing.T, cache Cache, values ...int) queueD
 		// push 17
 		var gasMeter2 GasMeter = NewMockGasMeter(100000000)
 		push := []byte(fmt.Sprintf(`{"enqueue":{"value":%d}}`, value))
-		res, _, err = Handle(cache, id, params, push, &gasMeter2, store, api, &querier, 100000000, nil)
+		res, _, err = Handle(cache, id, params, push, &gasMeter2, store, api, &querier, 100000000, nil, nil)
 		require.NoError(t, err)
 		requireOkResponse(t, res, 0)
 	}
@@ -74,7 +74,7 @@ func TestQueueIterator(t *testing.T) {
 	igasMeter := GasMeter(gasMeter)
 	store := setup.Store(gasMeter)
 	query := []byte(`{"sum":{}}`)
-	data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000)
+	data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000, nil)
 	require.NoError(t, err)
 	var qres types.QueryResponse
 	err = json.Unmarshal(data, &qres)
@@ -84,7 +84,7 @@ func TestQueueIterator(t *testing.T) {
 
 	// query reduce (multiple iterators at once)
 	query = []byte(`{"reducer":{}}`)
-	data, _, err = Query(cache, id, query, &igasMeter, store, api, &querier, 100000000)
+	data, _, err = Query(cache, id, query, &igasMeter, store, api, &querier, 100000000, nil)
 	require.NoError(t, err)
 	var reduced types.QueryResponse
 	err = json.Unmarshal(data, &reduced)
@@ -111,7 +111,7 @@ func TestQueueIteratorRaces(t *testing.T) {
 
 		// query reduce (multiple iterators at once)
 		query := []byte(`{"reducer":{}}`)
-		data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000)
+		data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000, nil)
 		require.NoError(t, err)
 		var reduced types.QueryResponse
 		err = json.Unmarshal(data, &reduced)
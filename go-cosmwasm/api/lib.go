@@ -9,6 +9,7 @@ import "C"
 import (
 	"fmt"
 	"syscall"
+	"unsafe"
 
 	"github.com/enigmampc/SecretNetwork/go-cosmwasm/types"
 )
@@ -37,28 +38,94 @@ func HealthCheck() ([]byte, error) {
 	return receiveVector(res), nil
 }
 
-func InitBootstrap(spid []byte, apiKey []byte) ([]byte, error) {
+// InitBootstrap creates the consensus seed of a new chain, and binds it to chainID and to the
+// attestation policy of the chain, which is empty if it has none
+func InitBootstrap(spid []byte, apiKey []byte, chainID string, policy []byte) ([]byte, error) {
 	errmsg := C.Buffer{}
 	spidSlice := sendSlice(spid)
 	defer freeAfterSend(spidSlice)
 	apiKeySlice := sendSlice(apiKey)
 	defer freeAfterSend(apiKeySlice)
+	chainIDSlice := sendSlice([]byte(chainID))
+	defer freeAfterSend(chainIDSlice)
+	policySlice := sendSlice(policy)
+	defer freeAfterSend(policySlice)
 
-	res, err := C.init_bootstrap(spidSlice, apiKeySlice, &errmsg)
+	res, err := C.init_bootstrap(spidSlice, apiKeySlice, chainIDSlice, policySlice, &errmsg)
 	if err != nil {
 		return nil, errorWithMessage(err, errmsg)
 	}
 	return receiveVector(res), nil
 }
 
-func LoadSeedToEnclave(masterCert []byte, seed []byte) (bool, error) {
+// LoadSeedToEnclave decrypts the consensus seed. The seed only decrypts if it belongs to chainID,
+// and policy is the attestation policy of the chain. Seeds of chains that were created before the
+// seed was bound to them decrypt without either, and chainID may be empty for them.
+func LoadSeedToEnclave(masterCert []byte, seed []byte, chainID string, policy []byte) (bool, error) {
 	pkSlice := sendSlice(masterCert)
 	defer freeAfterSend(pkSlice)
 	seedSlice := sendSlice(seed)
 	defer freeAfterSend(seedSlice)
+	chainIDSlice := sendSlice([]byte(chainID))
+	defer freeAfterSend(chainIDSlice)
+	policySlice := sendSlice(policy)
+	defer freeAfterSend(policySlice)
 	errmsg := C.Buffer{}
 
-	_, err := C.init_node(pkSlice, seedSlice, &errmsg)
+	_, err := C.init_node(pkSlice, seedSlice, chainIDSlice, policySlice, &errmsg)
+	if err != nil {
+		return false, errorWithMessage(err, errmsg)
+	}
+	return true, nil
+}
+
+// RotateConsensusSeed creates the seed of a new epoch, and returns the seed exchange public key of the epoch.
+// The enclave saves the certificates of the seed exchange and IO keys of the epoch, which the chain
+// adds to the seed epochs of x/registration before it activates the epoch.
+func RotateConsensusSeed(spid []byte, apiKey []byte) ([]byte, error) {
+	errmsg := C.Buffer{}
+	spidSlice := sendSlice(spid)
+	defer freeAfterSend(spidSlice)
+	apiKeySlice := sendSlice(apiKey)
+	defer freeAfterSend(apiKeySlice)
+
+	res, err := C.rotate_consensus_seed(spidSlice, apiKeySlice, &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// LoadSeedEpochToEnclave loads the seed of a later epoch than genesis. Epochs must be loaded in order.
+func LoadSeedEpochToEnclave(masterCert []byte, epoch uint32, seed []byte) (bool, error) {
+	pkSlice := sendSlice(masterCert)
+	defer freeAfterSend(pkSlice)
+	seedSlice := sendSlice(seed)
+	defer freeAfterSend(seedSlice)
+	errmsg := C.Buffer{}
+
+	_, err := C.add_consensus_seed_epoch(pkSlice, u32(epoch), seedSlice, &errmsg)
+	if err != nil {
+		return false, errorWithMessage(err, errmsg)
+	}
+	return true, nil
+}
+
+// ImportSeedForUpgrade loads a seed that the previous enclave exported with ExportSeedForUpgrade.
+// masterCert is the seed exchange certificate of the epoch, which is verified with the attestation
+// policy of the chain. Epochs must be imported in order, starting with the genesis epoch.
+func ImportSeedForUpgrade(masterCert []byte, policy []byte, blockTime uint64, epoch uint32, seed []byte, chainID string) (bool, error) {
+	certSlice := sendSlice(masterCert)
+	defer freeAfterSend(certSlice)
+	policySlice := sendSlice(policy)
+	defer freeAfterSend(policySlice)
+	seedSlice := sendSlice(seed)
+	defer freeAfterSend(seedSlice)
+	chainIDSlice := sendSlice([]byte(chainID))
+	defer freeAfterSend(chainIDSlice)
+	errmsg := C.Buffer{}
+
+	_, err := C.import_seed_for_upgrade(certSlice, policySlice, u64(blockTime), u32(epoch), seedSlice, chainIDSlice, &errmsg)
 	if err != nil {
 		return false, errorWithMessage(err, errmsg)
 	}
@@ -118,6 +185,7 @@ func Instantiate(
 	querier *Querier,
 	gasLimit uint64,
 	sigInfo []byte,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
 	id := sendSlice(code_id)
 	defer freeAfterSend(id)
@@ -135,13 +203,63 @@ func Instantiate(
 
 	s := sendSlice(sigInfo)
 	defer freeAfterSend(s)
+	gs := sendSlice(gasSchedule)
+	defer freeAfterSend(gs)
 	a := buildAPI(api)
 	q := buildQuerier(querier)
 	var gasUsed u64
 
 	errmsg := C.Buffer{}
 
-	res, err := C.instantiate(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg, s)
+	res, err := C.instantiate(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg, s, gs)
+	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
+		// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
+		return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), uint64(gasUsed), nil
+}
+
+// SimulateInstantiate runs the contract like Instantiate, except that it gets no randomness. The contract
+// writes to store as usual, so pass a store that is thrown away afterwards.
+// It returns the (encrypted) output and the gas the call would use.
+func SimulateInstantiate(
+	cache Cache,
+	code_id []byte,
+	params []byte,
+	msg []byte,
+	gasMeter *GasMeter,
+	store KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+	sigInfo []byte,
+	gasSchedule []byte,
+) ([]byte, uint64, error) {
+	id := sendSlice(code_id)
+	defer freeAfterSend(id)
+	p := sendSlice(params)
+	defer freeAfterSend(p)
+	m := sendSlice(msg)
+	defer freeAfterSend(m)
+
+	// set up a new stack frame to handle iterators
+	counter := startContract()
+	defer endContract(counter)
+
+	dbState := buildDBState(store, counter)
+	db := buildDB(&dbState, gasMeter)
+
+	s := sendSlice(sigInfo)
+	defer freeAfterSend(s)
+	gs := sendSlice(gasSchedule)
+	defer freeAfterSend(gs)
+	a := buildAPI(api)
+	q := buildQuerier(querier)
+	var gasUsed u64
+
+	errmsg := C.Buffer{}
+
+	res, err := C.simulate_instantiate(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg, s, gs)
 	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
 		// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
 		return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
@@ -160,6 +278,7 @@ func Handle(
 	querier *Querier,
 	gasLimit uint64,
 	sigInfo []byte,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
 	id := sendSlice(code_id)
 	defer freeAfterSend(id)
@@ -176,12 +295,14 @@ func Handle(
 	db := buildDB(&dbState, gasMeter)
 	s := sendSlice(sigInfo)
 	defer freeAfterSend(s)
+	gs := sendSlice(gasSchedule)
+	defer freeAfterSend(gs)
 	a := buildAPI(api)
 	q := buildQuerier(querier)
 	var gasUsed u64
 	errmsg := C.Buffer{}
 
-	res, err := C.handle(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg, s)
+	res, err := C.handle(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg, s, gs)
 	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
 		// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
 		return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
@@ -189,7 +310,10 @@ func Handle(
 	return receiveVector(res), uint64(gasUsed), nil
 }
 
-func Migrate(
+// SimulateHandle runs the contract like Handle, except that it gets no randomness. The contract
+// writes to store as usual, so pass a store that is thrown away afterwards.
+// It returns the (encrypted) output and the gas the call would use.
+func SimulateHandle(
 	cache Cache,
 	code_id []byte,
 	params []byte,
@@ -199,6 +323,8 @@ func Migrate(
 	api *GoAPI,
 	querier *Querier,
 	gasLimit uint64,
+	sigInfo []byte,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
 	id := sendSlice(code_id)
 	defer freeAfterSend(id)
@@ -213,12 +339,16 @@ func Migrate(
 
 	dbState := buildDBState(store, counter)
 	db := buildDB(&dbState, gasMeter)
+	s := sendSlice(sigInfo)
+	defer freeAfterSend(s)
+	gs := sendSlice(gasSchedule)
+	defer freeAfterSend(gs)
 	a := buildAPI(api)
 	q := buildQuerier(querier)
 	var gasUsed u64
 	errmsg := C.Buffer{}
 
-	res, err := C.migrate(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg)
+	res, err := C.simulate_handle(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg, s, gs)
 	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
 		// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
 		return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
@@ -226,6 +356,63 @@ func Migrate(
 	return receiveVector(res), uint64(gasUsed), nil
 }
 
+func Migrate(
+	cache Cache,
+	code_id []byte,
+	params []byte,
+	msg []byte,
+	gasMeter *GasMeter,
+	store KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+	sigInfo []byte,
+	gasSchedule []byte,
+) ([]byte, []byte, []byte, uint64, error) {
+	id := sendSlice(code_id)
+	defer freeAfterSend(id)
+	p := sendSlice(params)
+	defer freeAfterSend(p)
+	m := sendSlice(msg)
+	defer freeAfterSend(m)
+	s := sendSlice(sigInfo)
+	defer freeAfterSend(s)
+	gs := sendSlice(gasSchedule)
+	defer freeAfterSend(gs)
+
+	// set up a new stack frame to handle iterators
+	counter := startContract()
+	defer endContract(counter)
+
+	dbState := buildDBState(store, counter)
+	db := buildDB(&dbState, gasMeter)
+	a := buildAPI(api)
+	q := buildQuerier(querier)
+	var gasUsed u64
+	keyProof := C.Buffer{}
+	adminProof := C.Buffer{}
+	errmsg := C.Buffer{}
+
+	res, err := C.migrate(cache.ptr, id, p, m, db, a, q, u64(gasLimit), &gasUsed, &keyProof, &adminProof, &errmsg, s, gs)
+	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
+		// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
+		return nil, nil, nil, uint64(gasUsed), errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), receiveVector(keyProof), receiveVector(adminProof), uint64(gasUsed), nil
+}
+
+// ReissueContractProofs returns the contract key proof, then the admin proof, in the epoch the params are for
+func ReissueContractProofs(params []byte) ([]byte, error) {
+	p := sendSlice(params)
+	defer freeAfterSend(p)
+	errmsg := C.Buffer{}
+	res, err := C.reissue_contract_proofs(p, &errmsg)
+	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
 func Query(
 	cache Cache,
 	code_id []byte,
@@ -235,11 +422,14 @@ func Query(
 	api *GoAPI,
 	querier *Querier,
 	gasLimit uint64,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
 	id := sendSlice(code_id)
 	defer freeAfterSend(id)
 	m := sendSlice(msg)
 	defer freeAfterSend(m)
+	gs := sendSlice(gasSchedule)
+	defer freeAfterSend(gs)
 
 	// set up a new stack frame to handle iterators
 	counter := startContract()
@@ -252,7 +442,52 @@ func Query(
 	var gasUsed u64
 	errmsg := C.Buffer{}
 
-	res, err := C.query(cache.ptr, id, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg)
+	res, err := C.query(cache.ptr, id, m, db, a, q, u64(gasLimit), &gasUsed, &errmsg, gs)
+	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
+		// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
+		return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), uint64(gasUsed), nil
+}
+
+// QueryBatch runs a batch of queries in a single call into the enclave.
+// `queries` is the JSON encoded batch, and `stores` holds the storage of the queried contract for each query.
+// All the queries share `gasLimit`.
+func QueryBatch(
+	cache Cache,
+	queries []byte,
+	gasMeter *GasMeter,
+	stores []KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+	gasSchedule []byte,
+) ([]byte, uint64, error) {
+	qs := sendSlice(queries)
+	defer freeAfterSend(qs)
+	gs := sendSlice(gasSchedule)
+	defer freeAfterSend(gs)
+
+	// The DBs are passed as an array, which must live in C memory
+	dbsPtr := (*C.DB)(C.malloc(C.size_t(len(stores)+1) * C.size_t(unsafe.Sizeof(C.DB{}))))
+	defer C.free(unsafe.Pointer(dbsPtr))
+	dbs := (*[1 << 20]C.DB)(unsafe.Pointer(dbsPtr))[:len(stores):len(stores)]
+
+	dbStates := make([]DBState, len(stores))
+	for i, store := range stores {
+		// set up a new stack frame to handle iterators
+		counter := startContract()
+		defer endContract(counter)
+
+		dbStates[i] = buildDBState(store, counter)
+		dbs[i] = buildDB(&dbStates[i], gasMeter)
+	}
+	a := buildAPI(api)
+	q := buildQuerier(querier)
+	var gasUsed u64
+	errmsg := C.Buffer{}
+
+	res, err := C.query_batch(cache.ptr, qs, dbsPtr, usize(len(stores)), a, q, u64(gasLimit), &gasUsed, &errmsg, gs)
 	if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
 		// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
 		return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
@@ -285,11 +520,57 @@ func CreateAttestationReport(spid []byte, apiKey []byte) (bool, error) {
 	return true, nil
 }
 
-func GetEncryptedSeed(cert []byte) ([]byte, error) {
+// CreateAttestationReportDcap Send CreateAttestationReportDcap request to enclave
+func CreateAttestationReportDcap() (bool, error) {
+	errmsg := C.Buffer{}
+
+	_, err := C.create_attestation_report_dcap(&errmsg)
+	if err != nil {
+		return false, errorWithMessage(err, errmsg)
+	}
+	return true, nil
+}
+
+// GetEncryptedSeed authenticates a registering node according to the attestation policy of the
+// chain (which may be empty), and returns the consensus seed encrypted for it.
+// blockTime is the time of the current block in seconds, at which the grace periods of the
+// policy are evaluated.
+func GetEncryptedSeed(cert []byte, policy []byte, blockTime uint64) ([]byte, error) {
+	errmsg := C.Buffer{}
+	certSlice := sendSlice(cert)
+	defer freeAfterSend(certSlice)
+	policySlice := sendSlice(policy)
+	defer freeAfterSend(policySlice)
+	res, err := C.get_encrypted_seed(certSlice, policySlice, u64(blockTime), &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+func GetEncryptedSeedForEpoch(cert []byte, policy []byte, blockTime uint64, epoch uint32) ([]byte, error) {
+	errmsg := C.Buffer{}
+	certSlice := sendSlice(cert)
+	defer freeAfterSend(certSlice)
+	policySlice := sendSlice(policy)
+	defer freeAfterSend(policySlice)
+	res, err := C.get_encrypted_seed_for_epoch(certSlice, policySlice, u64(blockTime), u32(epoch), &errmsg)
+	if err != nil {
+		return nil, errorWithMessage(err, errmsg)
+	}
+	return receiveVector(res), nil
+}
+
+// ExportSeedForUpgrade encrypts the seed of an epoch for an upgraded enclave, whose attestation
+// certificate is cert. The MRENCLAVE of the new enclave must be allowed by policy, which must be
+// the attestation policy of the chain.
+func ExportSeedForUpgrade(cert []byte, policy []byte, epoch uint32) ([]byte, error) {
 	errmsg := C.Buffer{}
 	certSlice := sendSlice(cert)
 	defer freeAfterSend(certSlice)
-	res, err := C.get_encrypted_seed(certSlice, &errmsg)
+	policySlice := sendSlice(policy)
+	defer freeAfterSend(policySlice)
+	res, err := C.export_seed_for_upgrade(certSlice, policySlice, u32(epoch), &errmsg)
 	if err != nil {
 		return nil, errorWithMessage(err, errmsg)
 	}
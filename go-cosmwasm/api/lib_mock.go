@@ -33,7 +33,7 @@ func HealthCheck() ([]byte, error) {
 	return nil, nil
 }
 
-func InitBootstrap() ([]byte, error) {
+func InitBootstrap(spid []byte, apiKey []byte, chainID string, policy []byte) ([]byte, error) {
 	//errmsg := C.Buffer{}
 	//
 	//res, err := C.init_bootstrap(&errmsg)
@@ -44,7 +44,7 @@ func InitBootstrap() ([]byte, error) {
 	return nil, nil
 }
 
-func LoadSeedToEnclave(masterCert []byte, seed []byte) (bool, error) {
+func LoadSeedToEnclave(masterCert []byte, seed []byte, chainID string, policy []byte) (bool, error) {
 	//pkSlice := sendSlice(masterCert)
 	//defer freeAfterSend(pkSlice)
 	//seedSlice := sendSlice(seed)
@@ -113,6 +113,7 @@ func Instantiate(
 	querier *Querier,
 	gasLimit uint64,
 	sigInfo []byte,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
 	//id := sendSlice(code_id)
 	//defer freeAfterSend(id)
@@ -134,6 +135,22 @@ func Instantiate(
 	return nil, 0, nil
 }
 
+func SimulateInstantiate(
+	cache Cache,
+	code_id []byte,
+	params []byte,
+	msg []byte,
+	gasMeter *GasMeter,
+	store KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+	sigInfo []byte,
+	gasSchedule []byte,
+) ([]byte, uint64, error) {
+	return nil, 0, nil
+}
+
 func Handle(
 	cache Cache,
 	code_id []byte,
@@ -145,6 +162,7 @@ func Handle(
 	querier *Querier,
 	gasLimit uint64,
 	sigInfo []byte,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
 	//id := sendSlice(code_id)
 	//defer freeAfterSend(id)
@@ -166,7 +184,7 @@ func Handle(
 	return nil, 0, nil
 }
 
-func Migrate(
+func SimulateHandle(
 	cache Cache,
 	code_id []byte,
 	params []byte,
@@ -176,7 +194,25 @@ func Migrate(
 	api *GoAPI,
 	querier *Querier,
 	gasLimit uint64,
+	sigInfo []byte,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
+	return nil, 0, nil
+}
+
+func Migrate(
+	cache Cache,
+	code_id []byte,
+	params []byte,
+	msg []byte,
+	gasMeter *GasMeter,
+	store KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+	sigInfo []byte,
+	gasSchedule []byte,
+) ([]byte, []byte, []byte, uint64, error) {
 	//id := sendSlice(code_id)
 	//defer freeAfterSend(id)
 	//p := sendSlice(params)
@@ -194,7 +230,11 @@ func Migrate(
 	//	return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
 	//}
 	//return receiveVector(res), uint64(gasUsed), nil
-	return nil, 0, nil
+	return nil, nil, nil, 0, nil
+}
+
+func ReissueContractProofs(params []byte) ([]byte, error) {
+	return nil, nil
 }
 
 func Query(
@@ -206,6 +246,7 @@ func Query(
 	api *GoAPI,
 	querier *Querier,
 	gasLimit uint64,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
 	//id := sendSlice(code_id)
 	//defer freeAfterSend(id)
@@ -225,6 +266,28 @@ func Query(
 	return nil, 0, nil
 }
 
+func QueryBatch(
+	cache Cache,
+	queries []byte,
+	gasMeter *GasMeter,
+	stores []KVStore,
+	api *GoAPI,
+	querier *Querier,
+	gasLimit uint64,
+	gasSchedule []byte,
+) ([]byte, uint64, error) {
+	//qs := sendSlice(queries)
+	//defer freeAfterSend(qs)
+	//...
+	//res, err := C.query_batch(cache.ptr, qs, dbsPtr, usize(len(stores)), a, q, u64(gasLimit), &gasUsed, &errmsg)
+	//if err != nil && err.(syscall.Errno) != C.ErrnoValue_Success {
+	//	// Depending on the nature of the error, `gasUsed` will either have a meaningful value, or just 0.
+	//	return nil, uint64(gasUsed), errorWithMessage(err, errmsg)
+	//}
+	//return receiveVector(res), uint64(gasUsed), nil
+	return nil, 0, nil
+}
+
 // KeyGen Send KeyGen request to enclave
 func KeyGen() ([]byte, error) {
 	//errmsg := C.Buffer{}
@@ -246,7 +309,31 @@ func CreateAttestationReport() (bool, error) {
 	return true, nil
 }
 
-func GetEncryptedSeed(cert []byte) ([]byte, error) {
+func CreateAttestationReportDcap() (bool, error) {
+	return true, nil
+}
+
+func RotateConsensusSeed(spid []byte, apiKey []byte) ([]byte, error) {
+	return nil, nil
+}
+
+func LoadSeedEpochToEnclave(masterCert []byte, epoch uint32, seed []byte) (bool, error) {
+	return true, nil
+}
+
+func GetEncryptedSeedForEpoch(cert []byte, policy []byte, blockTime uint64, epoch uint32) ([]byte, error) {
+	return nil, nil
+}
+
+func ExportSeedForUpgrade(cert []byte, policy []byte, epoch uint32) ([]byte, error) {
+	return nil, nil
+}
+
+func ImportSeedForUpgrade(masterCert []byte, policy []byte, blockTime uint64, epoch uint32, seed []byte, chainID string) (bool, error) {
+	return true, nil
+}
+
+func GetEncryptedSeed(cert []byte, policy []byte, blockTime uint64) ([]byte, error) {
 	//errmsg := C.Buffer{}
 	//certSlice := sendSlice(cert)
 	//defer freeAfterSend(certSlice)
@@ -109,7 +109,7 @@ func TestInstantiate(t *testing.T) {
 	require.NoError(t, err)
 	msg := []byte(`{"verifier": "fred", "beneficiary": "bob"}`)
 
-	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter, store, api, &querier, 100000000, nil)
+	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter, store, api, &querier, 100000000, nil, nil)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
 	assert.Equal(t, uint64(0x109a0), cost)
@@ -140,7 +140,7 @@ func TestHandle(t *testing.T) {
 	msg := []byte(`{"verifier": "fred", "beneficiary": "bob"}`)
 
 	start := time.Now()
-	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, 100000000, nil)
+	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, 100000000, nil, nil)
 	diff := time.Now().Sub(start)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
@@ -154,7 +154,7 @@ func TestHandle(t *testing.T) {
 	params, err = json.Marshal(mockEnv("fred"))
 	require.NoError(t, err)
 	start = time.Now()
-	res, cost, err = Handle(cache, id, params, []byte(`{"release":{}}`), &igasMeter2, store, api, &querier, 100000000, nil)
+	res, cost, err = Handle(cache, id, params, []byte(`{"release":{}}`), &igasMeter2, store, api, &querier, 100000000, nil, nil)
 	diff = time.Now().Sub(start)
 	require.NoError(t, err)
 	assert.Equal(t, uint64(0x19c40), cost)
@@ -196,7 +196,7 @@ func TestHandleCpuLoop(t *testing.T) {
 	msg := []byte(`{"verifier": "fred", "beneficiary": "bob"}`)
 
 	start := time.Now()
-	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, 100000000, nil)
+	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, 100000000, nil, nil)
 	diff := time.Now().Sub(start)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
@@ -211,7 +211,7 @@ func TestHandleCpuLoop(t *testing.T) {
 	params, err = json.Marshal(mockEnv("fred"))
 	require.NoError(t, err)
 	start = time.Now()
-	res, cost, err = Handle(cache, id, params, []byte(`{"cpu_loop":{}}`), &igasMeter2, store, api, &querier, maxGas, nil)
+	res, cost, err = Handle(cache, id, params, []byte(`{"cpu_loop":{}}`), &igasMeter2, store, api, &querier, maxGas, nil, nil)
 	diff = time.Now().Sub(start)
 	require.Error(t, err)
 	assert.Equal(t, cost, maxGas)
@@ -236,7 +236,7 @@ func TestHandleStorageLoop(t *testing.T) {
 
 	msg := []byte(`{"verifier": "fred", "beneficiary": "bob"}`)
 
-	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, maxGas, nil)
+	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, maxGas, nil, nil)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
 
@@ -247,7 +247,7 @@ func TestHandleStorageLoop(t *testing.T) {
 	params, err = json.Marshal(mockEnv("fred"))
 	require.NoError(t, err)
 	start := time.Now()
-	res, cost, err = Handle(cache, id, params, []byte(`{"storage_loop":{}}`), &igasMeter2, store, api, &querier, maxGas, nil)
+	res, cost, err = Handle(cache, id, params, []byte(`{"storage_loop":{}}`), &igasMeter2, store, api, &querier, maxGas, nil, nil)
 	diff := time.Now().Sub(start)
 	require.Error(t, err)
 	t.Logf("StorageLoop Time (%d gas): %s\n", cost, diff)
@@ -276,7 +276,7 @@ func TestHandleUserErrorsInApiCalls(t *testing.T) {
 
 	defaultApi := NewMockAPI()
 	msg := []byte(`{"verifier": "fred", "beneficiary": "bob"}`)
-	res, _, err := Instantiate(cache, id, params, msg, &igasMeter1, store, defaultApi, &querier, maxGas, nil)
+	res, _, err := Instantiate(cache, id, params, msg, &igasMeter1, store, defaultApi, &querier, maxGas, nil, nil)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
 
@@ -286,7 +286,7 @@ func TestHandleUserErrorsInApiCalls(t *testing.T) {
 	params, err = json.Marshal(mockEnv("fred"))
 	require.NoError(t, err)
 	failingApi := NewMockFailureAPI()
-	res, _, err = Handle(cache, id, params, []byte(`{"user_errors_in_api_calls":{}}`), &igasMeter2, store, failingApi, &querier, maxGas, nil)
+	res, _, err = Handle(cache, id, params, []byte(`{"user_errors_in_api_calls":{}}`), &igasMeter2, store, failingApi, &querier, maxGas, nil, nil)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
 }
@@ -308,13 +308,13 @@ func TestMigrate(t *testing.T) {
 	require.NoError(t, err)
 	msg := []byte(`{"verifier": "fred", "beneficiary": "bob"}`)
 
-	res, _, err := Instantiate(cache, id, params, msg, &igasMeter, store, api, &querier, 100000000, nil)
+	res, _, err := Instantiate(cache, id, params, msg, &igasMeter, store, api, &querier, 100000000, nil, nil)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
 
 	// verifier is fred
 	query := []byte(`{"verifier":{}}`)
-	data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000)
+	data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000, nil)
 	require.NoError(t, err)
 	var qres types.QueryResponse
 	err = json.Unmarshal(data, &qres)
@@ -326,11 +326,13 @@ func TestMigrate(t *testing.T) {
 	// we use the same code blob as we are testing hackatom self-migration
 	params, err = json.Marshal(mockEnv("fred"))
 	require.NoError(t, err)
-	res, _, err = Migrate(cache, id, params, []byte(`{"verifier":"alice"}`), &igasMeter, store, api, &querier, 100000000)
+	res, keyProof, adminProof, _, err := Migrate(cache, id, params, []byte(`{"verifier":"alice"}`), &igasMeter, store, api, &querier, 100000000, nil, nil)
 	require.NoError(t, err)
+	require.Len(t, keyProof, 32)
+	require.Len(t, adminProof, 32)
 
 	// should update verifier to alice
-	data, _, err = Query(cache, id, query, &igasMeter, store, api, &querier, 100000000)
+	data, _, err = Query(cache, id, query, &igasMeter, store, api, &querier, 100000000, nil)
 	require.NoError(t, err)
 	var qres2 types.QueryResponse
 	err = json.Unmarshal(data, &qres2)
@@ -354,7 +356,7 @@ func TestMultipleInstances(t *testing.T) {
 	params, err := json.Marshal(mockEnv("regen"))
 	require.NoError(t, err)
 	msg := []byte(`{"verifier": "fred", "beneficiary": "bob"}`)
-	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter1, store1, api, &querier, 100000000, nil)
+	res, cost, err := Instantiate(cache, id, params, msg, &igasMeter1, store1, api, &querier, 100000000, nil, nil)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
 	// we now count wasm gas charges and db writes
@@ -367,7 +369,7 @@ func TestMultipleInstances(t *testing.T) {
 	params, err = json.Marshal(mockEnv("chorus"))
 	require.NoError(t, err)
 	msg = []byte(`{"verifier": "mary", "beneficiary": "sue"}`)
-	res, cost, err = Instantiate(cache, id, params, msg, &igasMeter2, store2, api, &querier, 100000000, nil)
+	res, cost, err = Instantiate(cache, id, params, msg, &igasMeter2, store2, api, &querier, 100000000, nil, nil)
 	require.NoError(t, err)
 	requireOkResponse(t, res, 0)
 	assert.Equal(t, uint64(0x1093d), cost)
@@ -431,7 +433,7 @@ func exec(t *testing.T, cache Cache, id []byte, signer types.HumanAddress, store
 	igasMeter := GasMeter(gasMeter)
 	params, err := json.Marshal(mockEnv(signer))
 	require.NoError(t, err)
-	res, cost, err := Handle(cache, id, params, []byte(`{"release":{}}`), &igasMeter, store, api, &querier, 100000000, nil)
+	res, cost, err := Handle(cache, id, params, []byte(`{"release":{}}`), &igasMeter, store, api, &querier, 100000000, nil, nil)
 	require.NoError(t, err)
 	assert.Equal(t, gasExpected, cost)
 
@@ -456,7 +458,7 @@ func TestQuery(t *testing.T) {
 	params, err := json.Marshal(mockEnv("creator"))
 	require.NoError(t, err)
 	msg := []byte(`{"verifier": "fred", "beneficiary": "bob"}`)
-	_, _, err = Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, 100000000, nil)
+	_, _, err = Instantiate(cache, id, params, msg, &igasMeter1, store, api, &querier, 100000000, nil, nil)
 	require.NoError(t, err)
 
 	// invalid query
@@ -464,7 +466,7 @@ func TestQuery(t *testing.T) {
 	igasMeter2 := GasMeter(gasMeter2)
 	store.SetGasMeter(gasMeter2)
 	query := []byte(`{"Raw":{"val":"config"}}`)
-	data, _, err := Query(cache, id, query, &igasMeter2, store, api, &querier, 100000000)
+	data, _, err := Query(cache, id, query, &igasMeter2, store, api, &querier, 100000000, nil)
 	require.NoError(t, err)
 	var badResp types.QueryResponse
 	err = json.Unmarshal(data, &badResp)
@@ -481,7 +483,7 @@ func TestQuery(t *testing.T) {
 	igasMeter3 := GasMeter(gasMeter3)
 	store.SetGasMeter(gasMeter3)
 	query = []byte(`{"verifier":{}}`)
-	data, _, err = Query(cache, id, query, &igasMeter3, store, api, &querier, 100000000)
+	data, _, err = Query(cache, id, query, &igasMeter3, store, api, &querier, 100000000, nil)
 	require.NoError(t, err)
 	var qres types.QueryResponse
 	err = json.Unmarshal(data, &qres)
@@ -564,7 +566,7 @@ func TestHackatomQuerier(t *testing.T) {
 	// make a valid query to the other address
 	query := []byte(`{"other_balance":{"address":"foobar"}}`)
 	// TODO The query happens before the contract is initialized. How is this legal?
-	data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000)
+	data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000, nil)
 	require.NoError(t, err)
 	var qres types.QueryResponse
 	err = json.Unmarshal(data, &qres)
@@ -595,7 +597,7 @@ func TestCustomReflectQuerier(t *testing.T) {
 
 	// make a valid query to the other address
 	query := []byte(`{"reflect_custom":{"text":"small Frys :)"}}`)
-	data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000)
+	data, _, err := Query(cache, id, query, &igasMeter, store, api, &querier, 100000000, nil)
 	require.NoError(t, err)
 	var qres types.QueryResponse
 	err = json.Unmarshal(data, &qres)
@@ -29,4 +29,8 @@ fn main() {
             println!("cargo:rustc-link-lib=dylib=sgx_uae_service");
         }
     }
+
+    if env::var("CARGO_FEATURE_DCAP").is_ok() {
+        println!("cargo:rustc-link-lib=dylib=sgx_dcap_ql");
+    }
 }
@@ -84,6 +84,12 @@ func (w *Wasmer) GetCode(code CodeID) (WasmCode, error) {
 //
 // Under the hood, we may recompile the wasm, use a cached native compile, or even use a cached instance
 // for performance.
+//
+// gasSchedule is the serialized gas schedule of the chain parameters, which the enclave meters the call with.
+// This goes for every call into a contract. An empty schedule makes the enclave use its default costs.
+//
+// Besides the contract key, the enclave returns a proof that the sender is the admin of the contract.
+// It must be stored and passed in env when migrating the contract.
 func (w *Wasmer) Instantiate(
 	code CodeID,
 	env types.Env,
@@ -94,33 +100,80 @@ func (w *Wasmer) Instantiate(
 	gasMeter GasMeter,
 	gasLimit uint64,
 	sigInfo types.VerificationInfo,
-) (*types.InitResponse, []byte, uint64, error) {
+	gasSchedule []byte,
+) (*types.InitResponse, []byte, types.ContractProofs, uint64, error) {
 	paramBin, err := json.Marshal(env)
 	if err != nil {
-		return nil, nil, 0, err
+		return nil, nil, types.ContractProofs{}, 0, err
 	}
 
 	sigInfoBin, err := json.Marshal(sigInfo)
 	if err != nil {
-		return nil, nil, 0, err
+		return nil, nil, types.ContractProofs{}, 0, err
 	}
 
-	data, gasUsed, err := api.Instantiate(w.cache, code, paramBin, initMsg, &gasMeter, store, &goapi, &querier, gasLimit, sigInfoBin)
+	data, gasUsed, err := api.Instantiate(w.cache, code, paramBin, initMsg, &gasMeter, store, &goapi, &querier, gasLimit, sigInfoBin, gasSchedule)
 	if err != nil {
-		return nil, nil, gasUsed, err
+		return nil, nil, types.ContractProofs{}, gasUsed, err
 	}
 
+	// The enclave prepends the contract key, the key proof and the admin proof to the result
 	key := data[0:64]
+	proofs := types.ContractProofs{KeyProof: data[64:96], AdminProof: data[96:128]}
 	var resp types.InitResult
-	err = json.Unmarshal(data[64:], &resp)
+	err = json.Unmarshal(data[128:], &resp)
 	if err != nil {
-		return nil, nil, gasUsed, err
+		return nil, nil, types.ContractProofs{}, gasUsed, err
 	}
 
 	if resp.Err != nil {
-		return nil, nil, gasUsed, fmt.Errorf("%v", resp.Err)
+		return nil, nil, types.ContractProofs{}, gasUsed, fmt.Errorf("%v", resp.Err)
 	}
-	return resp.Ok, key, gasUsed, nil
+	return resp.Ok, key, proofs, gasUsed, nil
+}
+
+// SimulateInstantiate runs Instantiate to estimate the gas it would use.
+// The contract writes to store as usual, so store should be a cache that is never written back.
+func (w *Wasmer) SimulateInstantiate(
+	code CodeID,
+	env types.Env,
+	initMsg []byte,
+	store KVStore,
+	goapi GoAPI,
+	querier Querier,
+	gasMeter GasMeter,
+	gasLimit uint64,
+	sigInfo types.VerificationInfo,
+	gasSchedule []byte,
+) (*types.InitResponse, []byte, types.ContractProofs, uint64, error) {
+	paramBin, err := json.Marshal(env)
+	if err != nil {
+		return nil, nil, types.ContractProofs{}, 0, err
+	}
+
+	sigInfoBin, err := json.Marshal(sigInfo)
+	if err != nil {
+		return nil, nil, types.ContractProofs{}, 0, err
+	}
+
+	data, gasUsed, err := api.SimulateInstantiate(w.cache, code, paramBin, initMsg, &gasMeter, store, &goapi, &querier, gasLimit, sigInfoBin, gasSchedule)
+	if err != nil {
+		return nil, nil, types.ContractProofs{}, gasUsed, err
+	}
+
+	// The enclave prepends the contract key, the key proof and the admin proof to the result
+	key := data[0:64]
+	proofs := types.ContractProofs{KeyProof: data[64:96], AdminProof: data[96:128]}
+	var resp types.InitResult
+	err = json.Unmarshal(data[128:], &resp)
+	if err != nil {
+		return nil, nil, types.ContractProofs{}, gasUsed, err
+	}
+
+	if resp.Err != nil {
+		return nil, nil, types.ContractProofs{}, gasUsed, fmt.Errorf("%v", resp.Err)
+	}
+	return resp.Ok, key, proofs, gasUsed, nil
 }
 
 // Execute calls a given contract. Since the only difference between contracts with the same CodeID is the
@@ -139,6 +192,50 @@ func (w *Wasmer) Execute(
 	gasMeter GasMeter,
 	gasLimit uint64,
 	sigInfo types.VerificationInfo,
+	gasSchedule []byte,
+) (*types.HandleResponse, uint64, error) {
+	paramBin, err := json.Marshal(env)
+	if err != nil {
+		return nil, 0, err
+	}
+	sigInfoBin, err := json.Marshal(sigInfo)
+	if err != nil {
+		return nil, 0, err
+	}
+
+	data, gasUsed, err := api.Handle(w.cache, code, paramBin, executeMsg, &gasMeter, store, &goapi, &querier, gasLimit, sigInfoBin, gasSchedule)
+	if err != nil {
+		return nil, gasUsed, err
+	}
+
+	var resp types.HandleResult
+	err = json.Unmarshal(data, &resp)
+
+	if err != nil {
+		return nil, gasUsed, err
+	}
+
+	if resp.Err != nil {
+		return nil, gasUsed, fmt.Errorf("%v", resp.Err)
+	}
+
+	return resp.Ok, gasUsed, nil
+}
+
+// SimulateExecute runs Execute to estimate the gas it would use.
+// The contract writes to store as usual, so store should be a cache that is never written back.
+// Like Execute, it returns the response of the contract, whose data is encrypted for the sender.
+func (w *Wasmer) SimulateExecute(
+	code CodeID,
+	env types.Env,
+	executeMsg []byte,
+	store KVStore,
+	goapi GoAPI,
+	querier Querier,
+	gasMeter GasMeter,
+	gasLimit uint64,
+	sigInfo types.VerificationInfo,
+	gasSchedule []byte,
 ) (*types.HandleResponse, uint64, error) {
 	paramBin, err := json.Marshal(env)
 	if err != nil {
@@ -149,7 +246,7 @@ func (w *Wasmer) Execute(
 		return nil, 0, err
 	}
 
-	data, gasUsed, err := api.Handle(w.cache, code, paramBin, executeMsg, &gasMeter, store, &goapi, &querier, gasLimit, sigInfoBin)
+	data, gasUsed, err := api.SimulateHandle(w.cache, code, paramBin, executeMsg, &gasMeter, store, &goapi, &querier, gasLimit, sigInfoBin, gasSchedule)
 	if err != nil {
 		return nil, gasUsed, err
 	}
@@ -179,8 +276,9 @@ func (w *Wasmer) Query(
 	querier Querier,
 	gasMeter GasMeter,
 	gasLimit uint64,
+	gasSchedule []byte,
 ) ([]byte, uint64, error) {
-	data, gasUsed, err := api.Query(w.cache, code, queryMsg, &gasMeter, store, &goapi, &querier, gasLimit)
+	data, gasUsed, err := api.Query(w.cache, code, queryMsg, &gasMeter, store, &goapi, &querier, gasLimit, gasSchedule)
 	if err != nil {
 		return nil, gasUsed, err
 	}
@@ -196,12 +294,65 @@ func (w *Wasmer) Query(
 	return resp.Ok, gasUsed, nil
 }
 
+// QueryBatch runs several queries in a single call into the enclave.
+// The queries share gasLimit, and once it runs out the remaining queries fail.
+// stores must hold the storage of the queried contract for each query.
+//
+// A query that fails doesn't fail the batch, its error is returned in its result.
+func (w *Wasmer) QueryBatch(
+	queries []types.BatchQuery,
+	stores []KVStore,
+	goapi GoAPI,
+	querier Querier,
+	gasMeter GasMeter,
+	gasLimit uint64,
+	gasSchedule []byte,
+) ([]types.BatchQueryResult, uint64, error) {
+	if len(queries) != len(stores) {
+		return nil, 0, fmt.Errorf("got %d queries with %d stores", len(queries), len(stores))
+	}
+	batch, err := json.Marshal(queries)
+	if err != nil {
+		return nil, 0, err
+	}
+
+	data, gasUsed, err := api.QueryBatch(w.cache, batch, &gasMeter, stores, &goapi, &querier, gasLimit, gasSchedule)
+	if err != nil {
+		return nil, gasUsed, err
+	}
+
+	var results []types.BatchQueryResult
+	err = json.Unmarshal(data, &results)
+	if err != nil {
+		return nil, gasUsed, err
+	}
+	for i, result := range results {
+		if result.Err != "" {
+			continue
+		}
+		var resp types.QueryResponse
+		err = json.Unmarshal(result.Ok, &resp)
+		if err != nil {
+			return nil, gasUsed, err
+		}
+		if resp.Err != nil {
+			results[i] = types.BatchQueryResult{Err: fmt.Sprintf("%v", resp.Err)}
+		} else {
+			results[i] = types.BatchQueryResult{Ok: resp.Ok}
+		}
+	}
+	return results, gasUsed, nil
+}
+
 // Migrate will migrate an existing contract to a new code binary.
 // This takes storage of the data from the original contract and the CodeID of the new contract that should
 // replace it. This allows it to run a migration step if needed, or return an error if unable to migrate
 // the given data.
 //
 // MigrateMsg has some data on how to perform the migration.
+// The contract keeps its original key, and the returned proofs bind that key and its admin to the new code.
+// The key proof must be stored and passed in env for every following call to the contract.
+// The admin proof replaces the one of the old code, and is only passed when migrating.
 func (w *Wasmer) Migrate(
 	code CodeID,
 	env types.Env,
@@ -211,23 +362,56 @@ func (w *Wasmer) Migrate(
 	querier Querier,
 	gasMeter GasMeter,
 	gasLimit uint64,
-) (*types.MigrateResponse, uint64, error) {
+	sigInfo types.VerificationInfo,
+	gasSchedule []byte,
+) (*types.MigrateResponse, types.ContractProofs, uint64, error) {
 	paramBin, err := json.Marshal(env)
 	if err != nil {
-		return nil, 0, err
+		return nil, types.ContractProofs{}, 0, err
 	}
-	data, gasUsed, err := api.Migrate(w.cache, code, paramBin, migrateMsg, &gasMeter, store, &goapi, &querier, gasLimit)
+
+	sigInfoBin, err := json.Marshal(sigInfo)
 	if err != nil {
-		return nil, gasUsed, err
+		return nil, types.ContractProofs{}, 0, err
+	}
+
+	data, keyProof, adminProof, gasUsed, err := api.Migrate(w.cache, code, paramBin, migrateMsg, &gasMeter, store, &goapi, &querier, gasLimit, sigInfoBin, gasSchedule)
+	if err != nil {
+		return nil, types.ContractProofs{}, gasUsed, err
+	}
+	if len(keyProof) != 32 {
+		return nil, types.ContractProofs{}, gasUsed, fmt.Errorf("expected a contract key proof of 32 bytes, got %d", len(keyProof))
+	}
+	if len(adminProof) != 32 {
+		return nil, types.ContractProofs{}, gasUsed, fmt.Errorf("expected a contract admin proof of 32 bytes, got %d", len(adminProof))
 	}
 
 	var resp types.MigrateResult
 	err = json.Unmarshal(data, &resp)
 	if err != nil {
-		return nil, gasUsed, err
+		return nil, types.ContractProofs{}, gasUsed, err
 	}
 	if resp.Err != nil {
-		return nil, gasUsed, fmt.Errorf("%v", resp.Err)
+		return nil, types.ContractProofs{}, gasUsed, fmt.Errorf("%v", resp.Err)
 	}
-	return resp.Ok, gasUsed, nil
+	return resp.Ok, types.ContractProofs{KeyProof: keyProof, AdminProof: adminProof}, gasUsed, nil
+}
+
+// ReissueContractProofs issues the proofs of a contract again, for the epoch of the consensus seed in env.
+// env must hold the proofs of the contract in the previous epoch, and have the admin of the contract as the sender.
+// The admin proof is all zeros if env has none.
+func (w *Wasmer) ReissueContractProofs(env types.Env) (types.ContractProofs, error) {
+	paramBin, err := json.Marshal(env)
+	if err != nil {
+		return types.ContractProofs{}, err
+	}
+
+	data, err := api.ReissueContractProofs(paramBin)
+	if err != nil {
+		return types.ContractProofs{}, err
+	}
+	if len(data) != 64 {
+		return types.ContractProofs{}, fmt.Errorf("expected contract proofs of 64 bytes, got %d", len(data))
+	}
+	return types.ContractProofs{KeyProof: data[0:32], AdminProof: data[32:64]}, nil
 }
@@ -0,0 +1,92 @@
+use std::convert::TryInto;
+
+use cosmwasm_sgx_vm::{Checksum, VmResult};
+use cosmwasm_std::Binary;
+use serde_json::{json, Value};
+
+use crate::error::Error;
+
+/// Parses a batch of queries, encoded as `[{"code_id": <base64>, "msg": <base64>}, ...]`
+pub fn parse_query_batch(data: &[u8]) -> Result<Vec<(Checksum, Vec<u8>)>, Error> {
+    let queries: Vec<Value> = serde_json::from_slice(data)
+        .map_err(|err| Error::vm_err(format!("invalid query batch: {}", err)))?;
+
+    queries
+        .iter()
+        .map(|query| {
+            let code_id: Checksum = base64_field(query, "code_id")?.as_slice().try_into()?;
+            let msg = base64_field(query, "msg")?;
+            Ok((code_id, msg))
+        })
+        .collect()
+}
+
+fn base64_field(query: &Value, name: &str) -> Result<Vec<u8>, Error> {
+    let field = query
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| Error::empty_arg(name))?;
+    let bytes = Binary::from_base64(field)
+        .map_err(|err| Error::vm_err(format!("invalid query batch {}: {}", name, err)))?;
+    Ok(bytes.into())
+}
+
+/// Serializes the results of a batch of queries as `[{"ok": <base64>} | {"err": <message>}, ...]`
+pub fn serialize_query_batch_results(results: Vec<VmResult<Vec<u8>>>) -> Vec<u8> {
+    let results: Vec<Value> = results
+        .into_iter()
+        .map(|result| match result {
+            Ok(output) => json!({ "ok": Binary(output).to_base64() }),
+            Err(err) => json!({ "err": err.to_string() }),
+        })
+        .collect();
+
+    // Serializing a `Value` can't fail
+    serde_json::to_vec(&results).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_sgx_vm::VmError;
+
+    #[test]
+    fn parse_query_batch_works() {
+        let code_id = [7u8; 32];
+        let data = format!(
+            r#"[{{"code_id":"{}","msg":"{}"}}]"#,
+            Binary(code_id.to_vec()).to_base64(),
+            Binary(b"hello".to_vec()).to_base64()
+        );
+
+        let queries = parse_query_batch(data.as_bytes()).unwrap();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(queries[0].0, Checksum::from(code_id));
+        assert_eq!(queries[0].1, b"hello".to_vec());
+    }
+
+    #[test]
+    fn parse_query_batch_rejects_missing_fields() {
+        let data = br#"[{"msg":"aGVsbG8="}]"#;
+        match parse_query_batch(data).unwrap_err() {
+            Error::EmptyArg { name, .. } => assert_eq!(name, "code_id"),
+            err => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn parse_query_batch_rejects_bad_code_id() {
+        let data = br#"[{"code_id":"aGVsbG8=","msg":"aGVsbG8="}]"#;
+        assert!(parse_query_batch(data).is_err());
+    }
+
+    #[test]
+    fn serialize_query_batch_results_works() {
+        let results = vec![Ok(b"hello".to_vec()), Err(VmError::GasDepletion)];
+        let serialized = serialize_query_batch_results(results);
+        let value: Value = serde_json::from_slice(&serialized).unwrap();
+
+        assert_eq!(value[0]["ok"], "aGVsbG8=");
+        assert_eq!(value[1]["err"], VmError::GasDepletion.to_string());
+    }
+}
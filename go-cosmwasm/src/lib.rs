@@ -1,4 +1,5 @@
 mod api;
+mod batch;
 mod db;
 mod error;
 mod gas_meter;
@@ -16,16 +17,21 @@ use std::convert::TryInto;
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::str::from_utf8;
 
+use crate::batch::{parse_query_batch, serialize_query_batch_results};
 use crate::error::{clear_error, handle_c_error, set_error, Error};
 
 use cosmwasm_sgx_vm::untrusted_init_bootstrap;
 use cosmwasm_sgx_vm::{
-    call_handle_raw, call_init_raw, call_migrate_raw, call_query_raw, features_from_csv, Checksum,
-    CosmCache, Extern,
+    call_handle_raw, call_init_raw, call_migrate_raw, call_query_batch_raw, call_query_raw,
+    call_simulate_handle_raw, call_simulate_init_raw, features_from_csv,
+    reissue_contract_proofs as vm_reissue_contract_proofs, Checksum, CosmCache, Extern,
 };
 use cosmwasm_sgx_vm::{
-    create_attestation_report_u, untrusted_get_encrypted_seed, untrusted_health_check,
-    untrusted_init_node, untrusted_key_gen,
+    create_attestation_report_dcap_u, create_attestation_report_u,
+    untrusted_add_consensus_seed_epoch, untrusted_export_seed_for_upgrade,
+    untrusted_get_encrypted_seed, untrusted_get_encrypted_seed_for_epoch, untrusted_health_check,
+    untrusted_import_seed_for_upgrade, untrusted_init_node, untrusted_key_gen,
+    untrusted_rotate_consensus_seed,
 };
 
 use ctor::ctor;
@@ -63,7 +69,12 @@ pub extern "C" fn get_health_check(err: Option<&mut Buffer>) -> Buffer {
 }
 
 #[no_mangle]
-pub extern "C" fn get_encrypted_seed(cert: Buffer, err: Option<&mut Buffer>) -> Buffer {
+pub extern "C" fn get_encrypted_seed(
+    cert: Buffer,
+    policy: Buffer,
+    block_time: u64,
+    err: Option<&mut Buffer>,
+) -> Buffer {
     trace!("Called get_encrypted_seed");
     let cert_slice = match unsafe { cert.read() } {
         None => {
@@ -72,8 +83,84 @@ pub extern "C" fn get_encrypted_seed(cert: Buffer, err: Option<&mut Buffer>) ->
         }
         Some(r) => r,
     };
+    // chains without an attestation policy send an empty one
+    let policy_slice = unsafe { policy.read() }.unwrap_or_default();
     trace!("Hello from right before untrusted_get_encrypted_seed");
-    match untrusted_get_encrypted_seed(cert_slice) {
+    match untrusted_get_encrypted_seed(cert_slice, policy_slice, block_time) {
+        Err(e) => {
+            // An error happened in the SGX sdk.
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(Err(e)) => {
+            // An error was returned from the enclave.
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(Ok(seed)) => {
+            clear_error();
+            Buffer::from_vec(seed.to_vec())
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn get_encrypted_seed_for_epoch(
+    cert: Buffer,
+    policy: Buffer,
+    block_time: u64,
+    epoch: u32,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    let cert_slice = match unsafe { cert.read() } {
+        None => {
+            set_error(Error::empty_arg("attestation_cert"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    let policy_slice = unsafe { policy.read() }.unwrap_or_default();
+    match untrusted_get_encrypted_seed_for_epoch(cert_slice, policy_slice, block_time, epoch) {
+        Err(e) => {
+            // An error happened in the SGX sdk.
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(Err(e)) => {
+            // An error was returned from the enclave.
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(Ok(seed)) => {
+            clear_error();
+            Buffer::from_vec(seed.to_vec())
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn export_seed_for_upgrade(
+    cert: Buffer,
+    policy: Buffer,
+    epoch: u32,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    let cert_slice = match unsafe { cert.read() } {
+        None => {
+            set_error(Error::empty_arg("attestation_cert"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    // unlike registration, upgrades are only allowed by an attestation policy
+    let policy_slice = match unsafe { policy.read() } {
+        None => {
+            set_error(Error::empty_arg("policy"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+    match untrusted_export_seed_for_upgrade(cert_slice, policy_slice, epoch) {
         Err(e) => {
             // An error happened in the SGX sdk.
             set_error(Error::enclave_err(e.to_string()), err);
@@ -95,6 +182,8 @@ pub extern "C" fn get_encrypted_seed(cert: Buffer, err: Option<&mut Buffer>) ->
 pub extern "C" fn init_bootstrap(
     spid: Buffer,
     api_key: Buffer,
+    chain_id: Buffer,
+    policy: Buffer,
     err: Option<&mut Buffer>,
 ) -> Buffer {
     trace!("Hello from right before init_bootstrap");
@@ -115,7 +204,18 @@ pub extern "C" fn init_bootstrap(
         Some(r) => r,
     };
 
-    match untrusted_init_bootstrap(spid_slice, api_key_slice) {
+    let chain_id_slice = match unsafe { chain_id.read() } {
+        None => {
+            set_error(Error::empty_arg("chain_id"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+
+    // chains without an attestation policy send an empty one
+    let policy_slice = unsafe { policy.read() }.unwrap_or_default();
+
+    match untrusted_init_bootstrap(spid_slice, api_key_slice, chain_id_slice, policy_slice) {
         Err(e) => {
             set_error(Error::enclave_err(e.to_string()), err);
             Buffer::default()
@@ -131,6 +231,8 @@ pub extern "C" fn init_bootstrap(
 pub extern "C" fn init_node(
     master_cert: Buffer,
     encrypted_seed: Buffer,
+    chain_id: Buffer,
+    policy: Buffer,
     err: Option<&mut Buffer>,
 ) -> bool {
     let pk_slice = match unsafe { master_cert.read() } {
@@ -147,8 +249,137 @@ pub extern "C" fn init_node(
         }
         Some(r) => r,
     };
+    // Seed configs of nodes that registered before the seed was bound to the chain have no chain id
+    let chain_id_slice = unsafe { chain_id.read() }.unwrap_or_default();
+    let policy_slice = unsafe { policy.read() }.unwrap_or_default();
+
+    match untrusted_init_node(pk_slice, encrypted_seed_slice, chain_id_slice, policy_slice) {
+        Ok(_) => {
+            clear_error();
+            true
+        }
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            false
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn rotate_consensus_seed(
+    spid: Buffer,
+    api_key: Buffer,
+    err: Option<&mut Buffer>,
+) -> Buffer {
+    let spid_slice = match unsafe { spid.read() } {
+        None => {
+            set_error(Error::empty_arg("spid"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+
+    let api_key_slice = match unsafe { api_key.read() } {
+        None => {
+            set_error(Error::empty_arg("api_key"), err);
+            return Buffer::default();
+        }
+        Some(r) => r,
+    };
+
+    match untrusted_rotate_consensus_seed(spid_slice, api_key_slice) {
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            Buffer::default()
+        }
+        Ok(r) => {
+            clear_error();
+            Buffer::from_vec(r.to_vec())
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn add_consensus_seed_epoch(
+    master_cert: Buffer,
+    epoch: u32,
+    encrypted_seed: Buffer,
+    err: Option<&mut Buffer>,
+) -> bool {
+    let cert_slice = match unsafe { master_cert.read() } {
+        None => {
+            set_error(Error::empty_arg("master_cert"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+    let encrypted_seed_slice = match unsafe { encrypted_seed.read() } {
+        None => {
+            set_error(Error::empty_arg("encrypted_seed"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
 
-    match untrusted_init_node(pk_slice, encrypted_seed_slice) {
+    match untrusted_add_consensus_seed_epoch(cert_slice, epoch, encrypted_seed_slice) {
+        Ok(_) => {
+            clear_error();
+            true
+        }
+        Err(e) => {
+            set_error(Error::enclave_err(e.to_string()), err);
+            false
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn import_seed_for_upgrade(
+    master_cert: Buffer,
+    policy: Buffer,
+    block_time: u64,
+    epoch: u32,
+    encrypted_seed: Buffer,
+    chain_id: Buffer,
+    err: Option<&mut Buffer>,
+) -> bool {
+    let cert_slice = match unsafe { master_cert.read() } {
+        None => {
+            set_error(Error::empty_arg("master_cert"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+    let policy_slice = match unsafe { policy.read() } {
+        None => {
+            set_error(Error::empty_arg("policy"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+    let encrypted_seed_slice = match unsafe { encrypted_seed.read() } {
+        None => {
+            set_error(Error::empty_arg("encrypted_seed"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+    let chain_id_slice = match unsafe { chain_id.read() } {
+        None => {
+            set_error(Error::empty_arg("chain_id"), err);
+            return false;
+        }
+        Some(r) => r,
+    };
+
+    match untrusted_import_seed_for_upgrade(
+        cert_slice,
+        policy_slice,
+        block_time,
+        epoch,
+        encrypted_seed_slice,
+        chain_id_slice,
+    ) {
         Ok(_) => {
             clear_error();
             true
@@ -190,6 +421,16 @@ pub extern "C" fn create_attestation_report(
     true
 }
 
+#[no_mangle]
+pub extern "C" fn create_attestation_report_dcap(err: Option<&mut Buffer>) -> bool {
+    if let Err(status) = create_attestation_report_dcap_u() {
+        set_error(Error::enclave_err(status.to_string()), err);
+        return false;
+    }
+    clear_error();
+    true
+}
+
 fn to_extern(storage: DB, api: GoApi, querier: GoQuerier) -> Extern<DB, GoApi, GoQuerier> {
     Extern {
         storage,
@@ -230,6 +471,10 @@ static MSG_ARG: &str = "msg";
 static PARAMS_ARG: &str = "params";
 static GAS_USED_ARG: &str = "gas_used";
 static SIG_INFO_ARG: &str = "sig_info";
+static QUERIES_ARG: &str = "queries";
+static KEY_PROOF_ARG: &str = "key_proof";
+static ADMIN_PROOF_ARG: &str = "admin_proof";
+static DBS_ARG: &str = "dbs";
 
 fn do_init_cache(
     data_dir: Buffer,
@@ -278,6 +523,14 @@ fn do_create(cache: &mut CosmCache<DB, GoApi, GoQuerier>, wasm: Buffer) -> Resul
     Ok(checksum)
 }
 
+/// The gas schedule is taken from the chain parameters on every call.
+/// An empty buffer means the enclave's default costs are used.
+fn read_gas_schedule(gas_schedule: Buffer) -> Vec<u8> {
+    unsafe { gas_schedule.read() }
+        .map(|s| s.to_vec())
+        .unwrap_or_default()
+}
+
 #[no_mangle]
 pub extern "C" fn get_code(cache: *mut cache_t, id: Buffer, err: Option<&mut Buffer>) -> Buffer {
     let r = match to_cache(cache) {
@@ -310,6 +563,7 @@ pub extern "C" fn instantiate(
     gas_used: Option<&mut u64>,
     err: Option<&mut Buffer>,
     sig_info: Buffer,
+    gas_schedule: Buffer,
 ) -> Buffer {
     let r = match to_cache(cache) {
         Some(c) => catch_unwind(AssertUnwindSafe(move || {
@@ -324,6 +578,8 @@ pub extern "C" fn instantiate(
                 gas_limit,
                 gas_used,
                 sig_info,
+                gas_schedule,
+                false,
             )
         }))
         .unwrap_or_else(|_| Err(Error::panic())),
@@ -333,6 +589,48 @@ pub extern "C" fn instantiate(
     Buffer::from_vec(data)
 }
 
+/// Runs `instantiate` to estimate the gas it would use, and reports it.
+/// The contract writes to `db` as usual, so the caller should pass a store it throws away.
+#[no_mangle]
+pub extern "C" fn simulate_instantiate(
+    cache: *mut cache_t,
+    contract_id: Buffer,
+    params: Buffer,
+    msg: Buffer,
+    db: DB,
+    api: GoApi,
+    querier: GoQuerier,
+    gas_limit: u64,
+    gas_used: Option<&mut u64>,
+    err: Option<&mut Buffer>,
+    sig_info: Buffer,
+    gas_schedule: Buffer,
+) -> Buffer {
+    let r = match to_cache(cache) {
+        Some(c) => catch_unwind(AssertUnwindSafe(move || {
+            do_init(
+                c,
+                contract_id,
+                params,
+                msg,
+                db,
+                api,
+                querier,
+                gas_limit,
+                gas_used,
+                sig_info,
+                gas_schedule,
+                true,
+            )
+        }))
+        .unwrap_or_else(|_| Err(Error::panic())),
+        None => Err(Error::empty_arg(CACHE_ARG)),
+    };
+    let data = handle_c_error(r, err);
+    Buffer::from_vec(data)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn do_init(
     cache: &mut CosmCache<DB, GoApi, GoQuerier>,
     code_id: Buffer,
@@ -344,6 +642,8 @@ fn do_init(
     gas_limit: u64,
     gas_used: Option<&mut u64>,
     sig_info: Buffer,
+    gas_schedule: Buffer,
+    simulate: bool,
 ) -> Result<Vec<u8>, Error> {
     let gas_used = gas_used.ok_or_else(|| Error::empty_arg(GAS_USED_ARG))?;
     let code_id: Checksum = unsafe { code_id.read() }
@@ -355,8 +655,13 @@ fn do_init(
 
     let deps = to_extern(db, api, querier);
     let mut instance = cache.get_instance(&code_id, deps, gas_limit)?;
+    instance.set_gas_schedule(read_gas_schedule(gas_schedule));
     // We only check this result after reporting gas usage and returning the instance into the cache.
-    let res = call_init_raw(&mut instance, params, msg, sig_info);
+    let res = if simulate {
+        call_simulate_init_raw(&mut instance, params, msg, sig_info)
+    } else {
+        call_init_raw(&mut instance, params, msg, sig_info)
+    };
     *gas_used = instance.create_gas_report().used_internally;
     instance.recycle();
     Ok(res?)
@@ -375,11 +680,23 @@ pub extern "C" fn handle(
     gas_used: Option<&mut u64>,
     err: Option<&mut Buffer>,
     sig_info: Buffer,
+    gas_schedule: Buffer,
 ) -> Buffer {
     let r = match to_cache(cache) {
         Some(c) => catch_unwind(AssertUnwindSafe(move || {
             do_handle(
-                c, code_id, params, msg, db, api, querier, gas_limit, gas_used, sig_info,
+                c,
+                code_id,
+                params,
+                msg,
+                db,
+                api,
+                querier,
+                gas_limit,
+                gas_used,
+                sig_info,
+                gas_schedule,
+                false,
             )
         }))
         .unwrap_or_else(|_| Err(Error::panic())),
@@ -389,6 +706,48 @@ pub extern "C" fn handle(
     Buffer::from_vec(data)
 }
 
+/// Runs `handle` to estimate the gas it would use, and reports it.
+/// The contract writes to `db` as usual, so the caller should pass a store it throws away.
+#[no_mangle]
+pub extern "C" fn simulate_handle(
+    cache: *mut cache_t,
+    code_id: Buffer,
+    params: Buffer,
+    msg: Buffer,
+    db: DB,
+    api: GoApi,
+    querier: GoQuerier,
+    gas_limit: u64,
+    gas_used: Option<&mut u64>,
+    err: Option<&mut Buffer>,
+    sig_info: Buffer,
+    gas_schedule: Buffer,
+) -> Buffer {
+    let r = match to_cache(cache) {
+        Some(c) => catch_unwind(AssertUnwindSafe(move || {
+            do_handle(
+                c,
+                code_id,
+                params,
+                msg,
+                db,
+                api,
+                querier,
+                gas_limit,
+                gas_used,
+                sig_info,
+                gas_schedule,
+                true,
+            )
+        }))
+        .unwrap_or_else(|_| Err(Error::panic())),
+        None => Err(Error::empty_arg(CACHE_ARG)),
+    };
+    let data = handle_c_error(r, err);
+    Buffer::from_vec(data)
+}
+
+#[allow(clippy::too_many_arguments)]
 fn do_handle(
     cache: &mut CosmCache<DB, GoApi, GoQuerier>,
     code_id: Buffer,
@@ -400,6 +759,8 @@ fn do_handle(
     gas_limit: u64,
     gas_used: Option<&mut u64>,
     sig_info: Buffer,
+    gas_schedule: Buffer,
+    simulate: bool,
 ) -> Result<Vec<u8>, Error> {
     let gas_used = gas_used.ok_or_else(|| Error::empty_arg(GAS_USED_ARG))?;
     let code_id: Checksum = unsafe { code_id.read() }
@@ -411,8 +772,13 @@ fn do_handle(
 
     let deps = to_extern(db, api, querier);
     let mut instance = cache.get_instance(&code_id, deps, gas_limit)?;
+    instance.set_gas_schedule(read_gas_schedule(gas_schedule));
     // We only check this result after reporting gas usage and returning the instance into the cache.
-    let res = call_handle_raw(&mut instance, params, msg, sig_info);
+    let res = if simulate {
+        call_simulate_handle_raw(&mut instance, params, msg, sig_info)
+    } else {
+        call_handle_raw(&mut instance, params, msg, sig_info)
+    };
     *gas_used = instance.create_gas_report().used_internally;
     instance.recycle();
     Ok(res?)
@@ -429,7 +795,11 @@ pub extern "C" fn migrate(
     querier: GoQuerier,
     gas_limit: u64,
     gas_used: Option<&mut u64>,
+    key_proof: Option<&mut Buffer>,
+    admin_proof: Option<&mut Buffer>,
     err: Option<&mut Buffer>,
+    sig_info: Buffer,
+    gas_schedule: Buffer,
 ) -> Buffer {
     let r = match to_cache(cache) {
         Some(c) => catch_unwind(AssertUnwindSafe(move || {
@@ -443,6 +813,10 @@ pub extern "C" fn migrate(
                 querier,
                 gas_limit,
                 gas_used,
+                key_proof,
+                admin_proof,
+                sig_info,
+                gas_schedule,
             )
         }))
         .unwrap_or_else(|_| Err(Error::panic())),
@@ -452,6 +826,7 @@ pub extern "C" fn migrate(
     Buffer::from_vec(data)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn do_migrate(
     cache: &mut CosmCache<DB, GoApi, GoQuerier>,
     code_id: Buffer,
@@ -462,21 +837,49 @@ fn do_migrate(
     querier: GoQuerier,
     gas_limit: u64,
     gas_used: Option<&mut u64>,
+    key_proof: Option<&mut Buffer>,
+    admin_proof: Option<&mut Buffer>,
+    sig_info: Buffer,
+    gas_schedule: Buffer,
 ) -> Result<Vec<u8>, Error> {
     let gas_used = gas_used.ok_or_else(|| Error::empty_arg(GAS_USED_ARG))?;
+    let key_proof = key_proof.ok_or_else(|| Error::empty_arg(KEY_PROOF_ARG))?;
+    let admin_proof = admin_proof.ok_or_else(|| Error::empty_arg(ADMIN_PROOF_ARG))?;
     let code_id: Checksum = unsafe { code_id.read() }
         .ok_or_else(|| Error::empty_arg(CODE_ID_ARG))?
         .try_into()?;
     let params = unsafe { params.read() }.ok_or_else(|| Error::empty_arg(PARAMS_ARG))?;
     let msg = unsafe { msg.read() }.ok_or_else(|| Error::empty_arg(MSG_ARG))?;
+    let sig_info = unsafe { sig_info.read() }.ok_or_else(|| Error::empty_arg(SIG_INFO_ARG))?;
 
     let deps = to_extern(db, api, querier);
     let mut instance = cache.get_instance(&code_id, deps, gas_limit)?;
+    instance.set_gas_schedule(read_gas_schedule(gas_schedule));
     // We only check this result after reporting gas usage and returning the instance into the cache.
-    let res = call_migrate_raw(&mut instance, params, msg);
+    let res = call_migrate_raw(&mut instance, params, msg, sig_info);
     *gas_used = instance.create_gas_report().used_internally;
     instance.recycle();
-    Ok(res?)
+    let (output, new_key_proof, new_admin_proof) = res?;
+    *key_proof = Buffer::from_vec(new_key_proof.to_vec());
+    *admin_proof = Buffer::from_vec(new_admin_proof.to_vec());
+    Ok(output)
+}
+
+/// Returns the contract key proof, then the admin proof, in the epoch the params are for
+#[no_mangle]
+pub extern "C" fn reissue_contract_proofs(params: Buffer, err: Option<&mut Buffer>) -> Buffer {
+    let r = catch_unwind(AssertUnwindSafe(move || do_reissue_contract_proofs(params)))
+        .unwrap_or_else(|_| Err(Error::panic()));
+    let data = handle_c_error(r, err);
+    Buffer::from_vec(data)
+}
+
+fn do_reissue_contract_proofs(params: Buffer) -> Result<Vec<u8>, Error> {
+    let params = unsafe { params.read() }.ok_or_else(|| Error::empty_arg(PARAMS_ARG))?;
+    let (key_proof, admin_proof) = vm_reissue_contract_proofs(params)?;
+    let mut proofs = key_proof.to_vec();
+    proofs.extend_from_slice(&admin_proof);
+    Ok(proofs)
 }
 
 #[no_mangle]
@@ -490,10 +893,21 @@ pub extern "C" fn query(
     gas_limit: u64,
     gas_used: Option<&mut u64>,
     err: Option<&mut Buffer>,
+    gas_schedule: Buffer,
 ) -> Buffer {
     let r = match to_cache(cache) {
         Some(c) => catch_unwind(AssertUnwindSafe(move || {
-            do_query(c, code_id, msg, db, api, querier, gas_limit, gas_used)
+            do_query(
+                c,
+                code_id,
+                msg,
+                db,
+                api,
+                querier,
+                gas_limit,
+                gas_used,
+                gas_schedule,
+            )
         }))
         .unwrap_or_else(|_| Err(Error::panic())),
         None => Err(Error::empty_arg(CACHE_ARG)),
@@ -502,6 +916,7 @@ pub extern "C" fn query(
     Buffer::from_vec(data)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn do_query(
     cache: &mut CosmCache<DB, GoApi, GoQuerier>,
     code_id: Buffer,
@@ -511,6 +926,7 @@ fn do_query(
     querier: GoQuerier,
     gas_limit: u64,
     gas_used: Option<&mut u64>,
+    gas_schedule: Buffer,
 ) -> Result<Vec<u8>, Error> {
     let gas_used = gas_used.ok_or_else(|| Error::empty_arg(GAS_USED_ARG))?;
     let code_id: Checksum = unsafe { code_id.read() }
@@ -520,6 +936,7 @@ fn do_query(
 
     let deps = to_extern(db, api, querier);
     let mut instance = cache.get_instance(&code_id, deps, gas_limit)?;
+    instance.set_gas_schedule(read_gas_schedule(gas_schedule));
     // We only check this result after reporting gas usage and returning the instance into the cache.
     let res = call_query_raw(&mut instance, msg);
     *gas_used = instance.create_gas_report().used_internally;
@@ -527,6 +944,90 @@ fn do_query(
     Ok(res?)
 }
 
+/// Runs a batch of queries in a single call into the enclave.
+/// `queries` is a JSON array of `{"code_id": <base64>, "msg": <base64>}`, and `dbs` holds the
+/// storage of the queried contract for each query. All the queries share `gas_limit`.
+/// Returns a JSON array with `{"ok": <base64>}` or `{"err": <message>}` for each query.
+#[no_mangle]
+pub extern "C" fn query_batch(
+    cache: *mut cache_t,
+    queries: Buffer,
+    dbs: *const DB,
+    dbs_len: usize,
+    api: GoApi,
+    querier: GoQuerier,
+    gas_limit: u64,
+    gas_used: Option<&mut u64>,
+    err: Option<&mut Buffer>,
+    gas_schedule: Buffer,
+) -> Buffer {
+    let r = match to_cache(cache) {
+        Some(c) => catch_unwind(AssertUnwindSafe(move || {
+            do_query_batch(
+                c,
+                queries,
+                dbs,
+                dbs_len,
+                api,
+                querier,
+                gas_limit,
+                gas_used,
+                gas_schedule,
+            )
+        }))
+        .unwrap_or_else(|_| Err(Error::panic())),
+        None => Err(Error::empty_arg(CACHE_ARG)),
+    };
+    let data = handle_c_error(r, err);
+    Buffer::from_vec(data)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn do_query_batch(
+    cache: &mut CosmCache<DB, GoApi, GoQuerier>,
+    queries: Buffer,
+    dbs: *const DB,
+    dbs_len: usize,
+    api: GoApi,
+    querier: GoQuerier,
+    gas_limit: u64,
+    gas_used: Option<&mut u64>,
+    gas_schedule: Buffer,
+) -> Result<Vec<u8>, Error> {
+    let gas_used = gas_used.ok_or_else(|| Error::empty_arg(GAS_USED_ARG))?;
+    let queries = unsafe { queries.read() }.ok_or_else(|| Error::empty_arg(QUERIES_ARG))?;
+    let queries = parse_query_batch(queries)?;
+    if dbs.is_null() || dbs_len != queries.len() {
+        return Err(Error::empty_arg(DBS_ARG));
+    }
+
+    let gas_schedule = read_gas_schedule(gas_schedule);
+    let mut instances = Vec::with_capacity(queries.len());
+    for (index, (code_id, _)) in queries.iter().enumerate() {
+        // The DBs are owned by the caller, we just take a copy of each one
+        let db = unsafe { std::ptr::read(dbs.add(index)) };
+        let deps = to_extern(db, api, querier.clone());
+        let mut instance = cache.get_instance(code_id, deps, gas_limit)?;
+        instance.set_gas_schedule(gas_schedule.clone());
+        instances.push(instance);
+    }
+
+    let mut batch: Vec<_> = instances
+        .iter_mut()
+        .zip(queries.iter())
+        .map(|(instance, (_, msg))| (instance, msg.as_slice()))
+        .collect();
+    // We only check this result after returning the instances into the cache.
+    let res = call_query_batch_raw(&mut batch, gas_limit);
+    for instance in instances {
+        instance.recycle();
+    }
+
+    let (results, batch_gas_used) = res?;
+    *gas_used = batch_gas_used;
+    Ok(serialize_query_batch_results(results))
+}
+
 #[no_mangle]
 pub extern "C" fn key_gen(err: Option<&mut Buffer>) -> Buffer {
     match untrusted_key_gen() {
@@ -12,10 +12,36 @@ type Env struct {
 	Message  MessageInfo  `json:"message"`
 	Contract ContractInfo `json:"contract"`
 	Key      ContractKey  `json:"contract_key"`
+	// CodeHash is only required when migrating, where it is the hex encoded hash of the code the contract is migrating from
+	CodeHash string `json:"contract_code_hash,omitempty"`
+	// KeyProof authenticates the contract key in the current epoch of the consensus seed. Contracts that were
+	// instantiated in the genesis epoch and never migrated may not have one
+	KeyProof ContractKeyProof `json:"contract_key_proof,omitempty"`
+	// AdminProof is only required when migrating, where it shows the enclave that the sender can migrate the contract,
+	// and when the proofs are re-issued for a new epoch
+	AdminProof ContractAdminProof `json:"contract_admin_proof,omitempty"`
+	// SeedEpoch is the epoch of the consensus seed that new contract state is encrypted with.
+	// It is set by x/compute from the seed epochs in the consensus state of x/registration, and
+	// every node must have loaded the seed of an epoch before it is activated. Zero is the genesis epoch.
+	SeedEpoch uint32 `json:"seed_epoch,omitempty"`
+	// BlockTxsHash is the hash of the transactions of the block (the data hash of its header). The enclave derives
+	// the random bytes of contracts from it, so they can't be known before the transaction is included in a block.
+	BlockTxsHash []byte `json:"block_txs_hash,omitempty"`
 }
 
 type ContractKey string
 
+type ContractKeyProof string
+
+type ContractAdminProof string
+
+// ContractProofs are the proofs the enclave issues for a contract in the current epoch of the consensus seed.
+// They are stored on chain, and passed back to the enclave in the env of the contract.
+type ContractProofs struct {
+	KeyProof   []byte
+	AdminProof []byte
+}
+
 type BlockInfo struct {
 	// block height this transaction is executed
 	Height uint64 `json:"height"`
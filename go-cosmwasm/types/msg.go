@@ -56,6 +56,21 @@ type MigrateResponse struct {
 type LogAttribute struct {
 	Key   string `json:"key"`
 	Value string `json:"value"`
+	// Encrypted is false for attributes the contract chose to make public.
+	// Those are emitted as is, so they can be used for filtering events.
+	Encrypted bool `json:"encrypted"`
+}
+
+// UnmarshalJSON defaults Encrypted to true, like cosmwasm-std does.
+// Contracts built before the field existed never set it, and all of their attributes are encrypted.
+func (a *LogAttribute) UnmarshalJSON(data []byte) error {
+	type logAttribute LogAttribute
+	attr := logAttribute{Encrypted: true}
+	if err := json.Unmarshal(data, &attr); err != nil {
+		return err
+	}
+	*a = LogAttribute(attr)
+	return nil
 }
 
 // CosmosMsg is an rust enum and only (exactly) one of the fields should be set
@@ -0,0 +1,26 @@
+package types
+
+import (
+	"encoding/json"
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+)
+
+func TestLogAttributeIsEncryptedByDefault(t *testing.T) {
+	var attr LogAttribute
+	err := json.Unmarshal([]byte(`{"key":"foo","value":"42"}`), &attr)
+	require.NoError(t, err)
+	assert.Equal(t, LogAttribute{Key: "foo", Value: "42", Encrypted: true}, attr)
+
+	err = json.Unmarshal([]byte(`{"key":"action","value":"transfer","encrypted":false}`), &attr)
+	require.NoError(t, err)
+	assert.Equal(t, LogAttribute{Key: "action", Value: "transfer", Encrypted: false}, attr)
+
+	var logs []LogAttribute
+	err = json.Unmarshal([]byte(`[{"key":"a","value":"1"},{"key":"b","value":"2","encrypted":false}]`), &logs)
+	require.NoError(t, err)
+	assert.True(t, logs[0].Encrypted)
+	assert.False(t, logs[1].Encrypted)
+}
@@ -11,6 +11,18 @@ type QueryResponse struct {
 	Err *StdError `json:"Err,omitempty"`
 }
 
+// BatchQuery is a single query in a batch of queries
+type BatchQuery struct {
+	CodeID []byte `json:"code_id"`
+	Msg    []byte `json:"msg"`
+}
+
+// BatchQueryResult is the result of a single query in a batch of queries
+type BatchQueryResult struct {
+	Ok  []byte `json:"ok,omitempty"`
+	Err string `json:"err,omitempty"`
+}
+
 //-------- Querier -----------
 
 type Querier interface {
@@ -13,6 +13,7 @@ type SystemError struct {
 	Unknown            *Unknown            `json:"unknown,omitempty"`
 	UnsupportedRequest *UnsupportedRequest `json:"unsupported_request,omitempty"`
 	ExceededRecursionLimit	*ExceededRecursionLimit	`json:"exceeded_recursion_limit,omitempty"`
+	ExceededQueryGasLimit  *ExceededQueryGasLimit  `json:"exceeded_query_gas_limit,omitempty"`
 }
 
 var (
@@ -23,6 +24,7 @@ var (
 	_ error = Unknown{}
 	_ error = UnsupportedRequest{}
 	_ error = ExceededRecursionLimit{}
+	_ error = ExceededQueryGasLimit{}
 )
 
 func (a SystemError) Error() string {
@@ -39,6 +41,8 @@ func (a SystemError) Error() string {
 		return a.UnsupportedRequest.Error()
 	case a.ExceededRecursionLimit != nil:
 		return a.ExceededRecursionLimit.Error()
+	case a.ExceededQueryGasLimit != nil:
+		return a.ExceededQueryGasLimit.Error()
 	default:
 		panic("unknown error variant")
 	}
@@ -90,6 +94,14 @@ func (e ExceededRecursionLimit) Error() string {
 	return "unknown system error"
 }
 
+type ExceededQueryGasLimit struct {
+	GasLimit uint64 `json:"gas_limit"`
+}
+
+func (e ExceededQueryGasLimit) Error() string {
+	return fmt.Sprintf("query exceeded its gas limit of %d", e.GasLimit)
+}
+
 // ToSystemError will try to convert the given error to an SystemError.
 // This is important to returning any Go error back to Rust.
 //
@@ -132,6 +144,10 @@ func ToSystemError(err error) *SystemError {
 		return &SystemError{ExceededRecursionLimit: &t}
 	case *ExceededRecursionLimit:
 		return &SystemError{ExceededRecursionLimit: t}
+	case ExceededQueryGasLimit:
+		return &SystemError{ExceededQueryGasLimit: &t}
+	case *ExceededQueryGasLimit:
+		return &SystemError{ExceededQueryGasLimit: t}
 	default:
 		return nil
 	}
@@ -3,8 +3,6 @@ package types
 import (
 	"encoding/json"
 	"strconv"
-
-	"github.com/enigmampc/cosmos-sdk/x/auth"
 )
 
 // HumanAddress is a printable (typically bech32 encoded) address string. Just use it as a label for developers.
@@ -60,8 +58,22 @@ func (o OutOfGasError) Error() string {
 	return "Out of gas"
 }
 
+const (
+	SignModeLegacyAminoJSON = "SIGN_MODE_LEGACY_AMINO_JSON"
+	SignModeDirect          = "SIGN_MODE_DIRECT"
+)
+
+// Signature is the signature of a tx signer, with the public key in amino json,
+// so the enclave can tell the key types apart by their amino name
+type Signature struct {
+	PubKey    json.RawMessage `json:"pub_key"`
+	Signature []byte          `json:"signature"`
+}
+
 type VerificationInfo struct {
-	Bytes             []byte            `json:"sign_bytes"`
-	Signature         auth.StdSignature `json:"signature"`
-	CallbackSignature []byte            `json:"callback_sig"` // Optional
+	Bytes             []byte    `json:"sign_bytes"`
+	Signature         Signature `json:"signature"`
+	CallbackSignature []byte    `json:"callback_sig"` // Optional
+	// SignMode is the encoding of Bytes, the enclave assumes SignModeLegacyAminoJSON when it's empty
+	SignMode string `json:"sign_mode,omitempty"`
 }
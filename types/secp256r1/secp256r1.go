@@ -0,0 +1,101 @@
+// Package secp256r1 implements accounts that sign with secp256r1 (NIST P-256), e.g. with a hardware key.
+//
+// Must be kept in sync with cosmwasm/packages/wasmi-runtime/src/crypto/secp256r1.rs, which verifies
+// the signatures of these keys inside the enclave.
+package secp256r1
+
+import (
+	"bytes"
+	"crypto/ecdsa"
+	"crypto/elliptic"
+	"crypto/sha256"
+	"fmt"
+	"math/big"
+
+	amino "github.com/tendermint/go-amino"
+	"github.com/tendermint/tendermint/crypto"
+)
+
+const (
+	PubKeyAminoName = "tendermint/PubKeySecp256r1"
+	// PubKeyProtoName is hashed into the address, like ADR-028 does
+	PubKeyProtoName = "cosmos.crypto.secp256r1.PubKey"
+
+	// PubKeySize is the size of the uncompressed key
+	PubKeySize = 65
+	// SignatureSize is the size of r | s, each 32 bytes big endian
+	SignatureSize = 64
+)
+
+var cdc = amino.NewCodec()
+
+func init() {
+	RegisterCodec(cdc)
+}
+
+// RegisterCodec registers the secp256r1 public key with amino, so it can be used by accounts
+func RegisterCodec(cdc *amino.Codec) {
+	cdc.RegisterConcrete(PubKey{}, PubKeyAminoName, nil)
+}
+
+var _ crypto.PubKey = PubKey{}
+
+// PubKey is the uncompressed key, which is what the enclave expects
+type PubKey [PubKeySize]byte
+
+// Address is the first 20 bytes of sha256(sha256(PubKeyProtoName) | compressed key)
+func (pubKey PubKey) Address() crypto.Address {
+	typeHash := sha256.Sum256([]byte(PubKeyProtoName))
+	hasher := sha256.New()
+	hasher.Write(typeHash[:])
+	hasher.Write(pubKey.compressed())
+	return crypto.Address(hasher.Sum(nil)[:crypto.AddressSize])
+}
+
+// Bytes is the amino encoding of the key
+func (pubKey PubKey) Bytes() []byte {
+	return cdc.MustMarshalBinaryBare(pubKey)
+}
+
+// VerifyBytes verifies a signature of sha256(msg), only accepting the lower S value like the enclave
+func (pubKey PubKey) VerifyBytes(msg []byte, sig []byte) bool {
+	if len(sig) != SignatureSize {
+		return false
+	}
+
+	x, y := elliptic.Unmarshal(elliptic.P256(), pubKey[:])
+	if x == nil {
+		return false
+	}
+	key := ecdsa.PublicKey{Curve: elliptic.P256(), X: x, Y: y}
+
+	r := new(big.Int).SetBytes(sig[:32])
+	s := new(big.Int).SetBytes(sig[32:])
+	halfOrder := new(big.Int).Rsh(elliptic.P256().Params().N, 1)
+	if s.Cmp(halfOrder) > 0 {
+		return false
+	}
+
+	hash := sha256.Sum256(msg)
+	return ecdsa.Verify(&key, hash[:], r, s)
+}
+
+func (pubKey PubKey) Equals(other crypto.PubKey) bool {
+	otherR1, ok := other.(PubKey)
+	if !ok {
+		return false
+	}
+	return bytes.Equal(pubKey[:], otherR1[:])
+}
+
+func (pubKey PubKey) String() string {
+	return fmt.Sprintf("PubKeySecp256r1{%X}", pubKey[:])
+}
+
+func (pubKey PubKey) compressed() []byte {
+	compressed := make([]byte, 33)
+	// The prefix is 2 for an even y and 3 for an odd y
+	compressed[0] = 2 + pubKey[PubKeySize-1]&1
+	copy(compressed[1:], pubKey[1:33])
+	return compressed
+}
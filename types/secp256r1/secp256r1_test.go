@@ -0,0 +1,43 @@
+package secp256r1
+
+import (
+	"encoding/hex"
+	"testing"
+
+	"github.com/stretchr/testify/require"
+)
+
+// The same vectors as the enclave tests, signing "sign me" with a fixed private key
+const (
+	testPubKey         = "04d8cd12ea5c67f2f8a00c1124893edcfa6754c4d6cede6be13bdf2295c810a97fa5a89d2d2a360c0ca9a4d6c7c9ed4b28d3e199d6627f2e696d689c310a5b0f48"
+	testSignature      = "5d10ad4b2a3355af53015d1390e7eac8000398a02d184d8514b8f6c0c77126366012dc7b706cee112ee776fe62c15007d95310c768095b2edd270a6f7a3301c2"
+	testHighSSignature = "5d10ad4b2a3355af53015d1390e7eac8000398a02d184d8514b8f6c0c77126369fed23838f9311efd11889019d3eaff7e393e9e63f0e43561692c0538230238f"
+)
+
+func testKey(t *testing.T) PubKey {
+	bz, err := hex.DecodeString(testPubKey)
+	require.NoError(t, err)
+	var pubKey PubKey
+	copy(pubKey[:], bz)
+	return pubKey
+}
+
+func TestVerifyBytes(t *testing.T) {
+	pubKey := testKey(t)
+
+	sig, err := hex.DecodeString(testSignature)
+	require.NoError(t, err)
+	require.True(t, pubKey.VerifyBytes([]byte("sign me"), sig))
+	require.False(t, pubKey.VerifyBytes([]byte("sign you"), sig))
+
+	highS, err := hex.DecodeString(testHighSSignature)
+	require.NoError(t, err)
+	require.False(t, pubKey.VerifyBytes([]byte("sign me"), highS))
+}
+
+func TestEncoding(t *testing.T) {
+	pubKey := testKey(t)
+
+	require.Equal(t, "06608f8f9f5dfe08fbab2a936e5d6379f3feadfe", hex.EncodeToString(pubKey.Address()))
+	require.Equal(t, append([]byte{140, 23, 94, 113, 65}, pubKey[:]...), pubKey.Bytes())
+}
@@ -16,6 +16,7 @@ const (
 	TStoreKey                     = types.TStoreKey
 	QuerierRoute                  = types.QuerierRoute
 	RouterKey                     = types.RouterKey
+	DefaultParamspace             = types.DefaultParamspace
 	MaxWasmSize                   = types.MaxWasmSize
 	MaxLabelSize                  = types.MaxLabelSize
 	BuildTagRegexp                = types.BuildTagRegexp
@@ -27,10 +28,13 @@ const (
 	QueryListContractByCode       = keeper.QueryListContractByCode
 	QueryGetContract              = keeper.QueryGetContract
 	QueryGetContractState         = keeper.QueryGetContractState
+	QueryGetContractStates        = keeper.QueryGetContractStates
 	QueryGetCode                  = keeper.QueryGetCode
 	QueryListCode                 = keeper.QueryListCode
 	QueryContractKey              = keeper.QueryContractKey
 	QueryContractAddress          = keeper.QueryContractAddress
+	QuerySimulateInstantiate      = keeper.QuerySimulateInstantiate
+	QuerySimulateExecute          = keeper.QuerySimulateExecute
 	QueryMethodContractStateSmart = keeper.QueryMethodContractStateSmart
 	/*
 		QueryMethodContractStateAll   = keeper.QueryMethodContractStateAll
@@ -41,7 +45,7 @@ const (
 var (
 	// functions aliases
 	// ConvertToProposals        = types.ConvertToProposals
-	// DefaultParams             = types.DefaultParams
+	DefaultParams             = types.DefaultParams
 	RegisterCodec             = types.RegisterCodec
 	ValidateGenesis           = types.ValidateGenesis
 	GetCodeKey                = types.GetCodeKey
@@ -101,11 +105,13 @@ var (
 type (
 	// ProposalType            = types.ProposalType
 	GenesisState            = types.GenesisState
+	Params                  = types.Params
 	Code                    = types.Code
 	Contract                = types.Contract
 	MsgStoreCode            = types.MsgStoreCode
 	MsgInstantiateContract  = types.MsgInstantiateContract
 	MsgExecuteContract      = types.MsgExecuteContract
+	MsgMigrateContract      = types.MsgMigrateContract
 	Model                   = types.Model
 	CodeInfo                = types.CodeInfo
 	ContractInfo            = types.ContractInfo
@@ -125,7 +131,13 @@ type (
 	QueryHandler            = keeper.QueryHandler
 	CustomQuerier           = keeper.CustomQuerier
 	QueryPlugins            = keeper.QueryPlugins
-	// MsgMigrateContract      = types.MsgMigrateContract
 	// MsgUpdateAdmin          = types.MsgUpdateAdmin
 	// MsgClearAdmin           = types.MsgClearAdmin
 )
+
+type (
+	SimulateInstantiateRequest = keeper.SimulateInstantiateRequest
+	SimulateExecuteRequest     = keeper.SimulateExecuteRequest
+	SimulateResponse           = keeper.SimulateResponse
+	SmartQuery                 = keeper.SmartQuery
+)
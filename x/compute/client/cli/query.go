@@ -372,7 +372,7 @@ func GetQueryDecryptTxCmd(cdc *amino.Codec) *cobra.Command {
 				answer.OutputDataAsString = string(dataPlaintext)
 			}
 
-			// decrypt logs
+			// decrypt logs, the attributes the contract made public are left as is
 			answer.OutputLogs = []sdk.StringEvent{}
 			for _, l := range result.Logs {
 				for _, e := range l.Events {
@@ -383,11 +383,13 @@ func GetQueryDecryptTxCmd(cdc *amino.Codec) *cobra.Command {
 								if a.Key != "" {
 									keyCiphertext, err := base64.StdEncoding.DecodeString(a.Key)
 									if err != nil {
-										return fmt.Errorf("error while trying to decode the log key '%s' from base64: %w", a.Key, err)
+										// the contract chose to make this attribute public
+										continue
 									}
 									keyPlaintext, err := wasmCtx.Decrypt(keyCiphertext, nonce)
 									if err != nil {
-										return fmt.Errorf("error while trying to decrypt the log key '%s' from base64: %w", a.Key, err)
+										// a public key that happens to be valid base64
+										continue
 									}
 									a.Key = string(keyPlaintext)
 								}
@@ -21,9 +21,9 @@ func NewHandler(k Keeper) sdk.Handler {
 			return handleInstantiate(ctx, k, &msg)
 		case MsgExecuteContract:
 			return handleExecute(ctx, k, &msg)
+		case MsgMigrateContract:
+			return handleMigration(ctx, k, &msg)
 			/*
-				case MsgMigrateContract:
-					return handleMigration(ctx, k, &msg)
 				case MsgUpdateAdmin:
 					return handleUpdateContractAdmin(ctx, k, &msg)
 				case MsgClearAdmin:
@@ -125,7 +125,6 @@ func handleExecute(ctx sdk.Context, k Keeper, msg *MsgExecuteContract) (*sdk.Res
 	return res, nil
 }
 
-/*
 func handleMigration(ctx sdk.Context, k Keeper, msg *MsgMigrateContract) (*sdk.Result, error) {
 	res, err := k.Migrate(ctx, msg.Contract, msg.Sender, msg.CodeID, msg.MigrateMsg) // for MsgMigrateContract, there is only one signer which is msg.Sender (https://github.com/enigmampc/SecretNetwork/blob/d7813792fa07b93a10f0885eaa4c5e0a0a698854/x/compute/internal/types/msg.go#L228-L230)
 	if err != nil {
@@ -143,6 +142,7 @@ func handleMigration(ctx sdk.Context, k Keeper, msg *MsgMigrateContract) (*sdk.R
 	return res, nil
 }
 
+/*
 func handleUpdateContractAdmin(ctx sdk.Context, k Keeper, msg *MsgUpdateAdmin) (*sdk.Result, error) {
 	if err := k.UpdateContractAdmin(ctx, msg.Contract, msg.Sender, msg.NewAdmin); err != nil {
 		return nil, err
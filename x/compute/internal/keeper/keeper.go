@@ -4,6 +4,7 @@ import (
 	"bytes"
 	"encoding/base64"
 	"encoding/binary"
+	"encoding/hex"
 	"fmt"
 
 	"path/filepath"
@@ -12,6 +13,7 @@ import (
 	distr "github.com/enigmampc/cosmos-sdk/x/distribution"
 	"github.com/enigmampc/cosmos-sdk/x/gov"
 	"github.com/enigmampc/cosmos-sdk/x/mint"
+	paramtypes "github.com/enigmampc/cosmos-sdk/x/params/subspace"
 	"github.com/pkg/errors"
 	"github.com/tendermint/tendermint/crypto"
 	"github.com/tendermint/tendermint/crypto/secp256k1"
@@ -56,6 +58,8 @@ type Keeper struct {
 	cdc           *codec.Codec
 	accountKeeper auth.AccountKeeper
 	bankKeeper    bank.Keeper
+	// seedEpochKeeper decides which epoch of the consensus seed contracts use
+	seedEpochKeeper types.SeedEpochKeeper
 
 	wasmer       wasm.Wasmer
 	queryPlugins QueryPlugins
@@ -63,36 +67,35 @@ type Keeper struct {
 	// queryGasLimit is the max wasm gas that can be spent on executing a query with a contract
 	queryGasLimit uint64
 	// authZPolicy   AuthorizationPolicy
-	//paramSpace    subspace.Subspace
+	paramSpace paramtypes.Subspace
 }
 
 // NewKeeper creates a new contract Keeper instance
 // If customEncoders is non-nil, we can use this to override some of the message handler, especially custom
-func NewKeeper(cdc *codec.Codec, storeKey sdk.StoreKey, accountKeeper auth.AccountKeeper,
+func NewKeeper(cdc *codec.Codec, storeKey sdk.StoreKey, paramSpace paramtypes.Subspace, accountKeeper auth.AccountKeeper,
 	bankKeeper *bank.Keeper, govKeeper *gov.Keeper, distKeeper *distr.Keeper, mintKeeper *mint.Keeper, stakingKeeper *staking.Keeper,
-	router sdk.Router, homeDir string, wasmConfig types.WasmConfig, supportedFeatures string, customEncoders *MessageEncoders, customPlugins *QueryPlugins) Keeper {
+	seedEpochKeeper types.SeedEpochKeeper, router sdk.Router, homeDir string, wasmConfig types.WasmConfig, supportedFeatures string, customEncoders *MessageEncoders, customPlugins *QueryPlugins) Keeper {
 	wasmer, err := wasm.NewWasmer(filepath.Join(homeDir, "wasm"), supportedFeatures, wasmConfig.CacheSize)
 	if err != nil {
 		panic(err)
 	}
 
-	/*
-		// set KeyTable if it has not already been set
-		if !paramSpace.HasKeyTable() {
-			paramSpace = paramSpace.WithKeyTable(types.ParamKeyTable())
-		}
-	*/
+	// set KeyTable if it has not already been set
+	if !paramSpace.HasKeyTable() {
+		paramSpace = paramSpace.WithKeyTable(types.ParamKeyTable())
+	}
 
 	keeper := Keeper{
-		storeKey:      storeKey,
-		cdc:           cdc,
-		wasmer:        *wasmer,
-		accountKeeper: accountKeeper,
-		bankKeeper:    *bankKeeper,
-		messenger:     NewMessageHandler(router, customEncoders),
-		queryGasLimit: wasmConfig.SmartQueryGasLimit,
+		storeKey:        storeKey,
+		cdc:             cdc,
+		wasmer:          *wasmer,
+		accountKeeper:   accountKeeper,
+		bankKeeper:      *bankKeeper,
+		seedEpochKeeper: seedEpochKeeper,
+		messenger:       NewMessageHandler(router, customEncoders),
+		queryGasLimit:   wasmConfig.SmartQueryGasLimit,
 		// authZPolicy:   DefaultAuthorizationPolicy{},
-		//paramSpace:    paramSpace,
+		paramSpace: paramSpace,
 	}
 	keeper.queryPlugins = DefaultQueryPlugins(govKeeper, distKeeper, mintKeeper, bankKeeper, stakingKeeper, &keeper).Merge(customPlugins)
 	return keeper
@@ -111,7 +114,9 @@ func (k Keeper) getInstantiateAccessConfig(ctx sdk.Context) types.AccessType {
 	return a
 }
 
-// GetParams returns the total set of wasm parameters.
+*/
+
+// GetParams returns the total set of compute parameters.
 func (k Keeper) GetParams(ctx sdk.Context) types.Params {
 	var params types.Params
 	k.paramSpace.GetParamSet(ctx, &params)
@@ -121,7 +126,14 @@ func (k Keeper) GetParams(ctx sdk.Context) types.Params {
 func (k Keeper) setParams(ctx sdk.Context, ps types.Params) {
 	k.paramSpace.SetParamSet(ctx, &ps)
 }
-*/
+
+// getGasSchedule returns the gas schedule the enclave should meter contracts with.
+// It's empty, meaning the enclave's default costs, as long as it wasn't set in genesis or by governance.
+func (k Keeper) getGasSchedule(ctx sdk.Context) []byte {
+	var schedule string
+	k.paramSpace.GetIfExists(ctx, types.ParamStoreKeyGasSchedule, &schedule)
+	return []byte(schedule)
+}
 
 // Create uploads and compiles a WASM contract, returning a short identifier for the contract
 func (k Keeper) Create(ctx sdk.Context, creator sdk.AccAddress, wasmCode []byte, source string, builder string) (codeID uint64, err error) {
@@ -190,7 +202,8 @@ func GetSignerSignature(signer exported.Account, tx auth.StdTx) (authtypes.StdSi
 	return authtypes.StdSignature{}, fmt.Errorf("could not find signer signature")
 }
 
-func (k Keeper) GetSignerInfo(ctx sdk.Context, signer sdk.AccAddress) (authtypes.StdSignature, []byte, error) {
+// GetSignerInfo returns the signature of signer in the current tx, the bytes it signed and how they are encoded
+func (k Keeper) GetSignerInfo(ctx sdk.Context, signer sdk.AccAddress) (authtypes.StdSignature, []byte, string, error) {
 	var defaultSignature = authtypes.StdSignature{
 		PubKey:    secp256k1.PubKeySecp256k1{},
 		Signature: []byte{},
@@ -202,23 +215,24 @@ func (k Keeper) GetSignerInfo(ctx sdk.Context, signer sdk.AccAddress) (authtypes
 	txBytes := ctx.TxBytes()
 	err := k.cdc.UnmarshalBinaryLengthPrefixed(txBytes, &tx)
 	if err != nil {
-		return defaultSignature, nil, sdkerrors.Wrap(types.ErrInstantiateFailed, fmt.Sprintf("Unable to decode transaction from bytes: %s", err.Error()))
+		return defaultSignature, nil, "", sdkerrors.Wrap(types.ErrInstantiateFailed, fmt.Sprintf("Unable to decode transaction from bytes: %s", err.Error()))
 	}
 
 	// Get sign bytes for the message creator
 	signerAcc, err := auth.GetSignerAcc(ctx, k.accountKeeper, signer) // for MsgInstantiateContract, there is only one signer which is msg.Sender (https://github.com/enigmampc/SecretNetwork/blob/d7813792fa07b93a10f0885eaa4c5e0a0a698854/x/compute/internal/types/msg.go#L192-L194)
 	if err != nil {
-		return defaultSignature, nil, sdkerrors.Wrap(types.ErrInstantiateFailed, fmt.Sprintf("Unable to retrieve account by address: %s", err.Error()))
+		return defaultSignature, nil, "", sdkerrors.Wrap(types.ErrInstantiateFailed, fmt.Sprintf("Unable to retrieve account by address: %s", err.Error()))
 	}
 
 	signerSig, err := GetSignerSignature(signerAcc, tx)
 	if err != nil {
-		return defaultSignature, nil, sdkerrors.Wrap(types.ErrInstantiateFailed, fmt.Sprintf("Message sender: %v is not found in the tx signer set: %v, callback signature not provided", signer, tx.Signatures))
+		return defaultSignature, nil, "", sdkerrors.Wrap(types.ErrInstantiateFailed, fmt.Sprintf("Message sender: %v is not found in the tx signer set: %v, callback signature not provided", signer, tx.Signatures))
 	}
 
 	signBytes := GetSignBytes(ctx, signerAcc, tx)
 
-	return signerSig, signBytes, nil
+	// Transactions are decoded as an amino StdTx, which is always signed as amino json
+	return signerSig, signBytes, wasmTypes.SignModeLegacyAminoJSON, nil
 }
 
 func (k Keeper) importCode(ctx sdk.Context, codeID uint64, codeInfo types.CodeInfo, wasmCode []byte) error {
@@ -259,17 +273,18 @@ func (k Keeper) Instantiate(ctx sdk.Context, codeID uint64, creator /* , admin *
 		Signature: []byte{},
 	}
 	signBytes := []byte{}
+	signMode := ""
 	var err error
 
 	// If no callback signature - we should send the actual msg sender sign bytes and signature
 	if callbackSig == nil {
-		signerSig, signBytes, err = k.GetSignerInfo(ctx, creator)
+		signerSig, signBytes, signMode, err = k.GetSignerInfo(ctx, creator)
 		if err != nil {
 			return nil, err
 		}
 	}
 
-	verificationInfo := types.NewVerificationInfo(signBytes, signerSig, callbackSig)
+	verificationInfo := types.NewVerificationInfo(signBytes, signMode, signerSig, callbackSig)
 
 	// create contract address
 
@@ -318,6 +333,7 @@ func (k Keeper) Instantiate(ctx sdk.Context, codeID uint64, creator /* , admin *
 
 	// prepare params for contract instantiate call
 	params := types.NewEnv(ctx, creator, deposit, contractAddress, nil)
+	params.SeedEpoch = k.seedEpochKeeper.GetSeedEpoch(ctx)
 
 	// create prefixed data store
 	// 0x03 | contractAddress (sdk.AccAddress)
@@ -331,8 +347,12 @@ func (k Keeper) Instantiate(ctx sdk.Context, codeID uint64, creator /* , admin *
 	}
 
 	// instantiate wasm contract
+	instantiate := k.wasmer.Instantiate
+	if isSimulation(ctx) {
+		instantiate = k.wasmer.SimulateInstantiate
+	}
 	gas := gasForContract(ctx)
-	res, key, gasUsed, err := k.wasmer.Instantiate(codeInfo.CodeHash, params, initMsg, prefixStore, cosmwasmAPI, querier, ctx.GasMeter(), gas, verificationInfo)
+	res, key, proofs, gasUsed, err := instantiate(codeInfo.CodeHash, params, initMsg, prefixStore, cosmwasmAPI, querier, ctx.GasMeter(), gas, verificationInfo, k.getGasSchedule(ctx))
 	consumeGas(ctx, gasUsed)
 	if err != nil {
 		return contractAddress, sdkerrors.Wrap(types.ErrInstantiateFailed, err.Error())
@@ -350,6 +370,7 @@ func (k Keeper) Instantiate(ctx sdk.Context, codeID uint64, creator /* , admin *
 	fmt.Printf("Storing key: %s for account %s\n", key, contractAddress)
 
 	store.Set(types.GetContractEnclaveKey(contractAddress), key)
+	k.setContractProofs(ctx, contractAddress, proofs)
 
 	store.Set(types.GetContractLabelPrefix(label), contractAddress)
 
@@ -362,6 +383,17 @@ func (k Keeper) Instantiate(ctx sdk.Context, codeID uint64, creator /* , admin *
 	return contractAddress, nil
 }
 
+// SimulateInstantiate estimates the gas that Instantiate would use, without committing anything.
+// It returns the address the contract would get, and the gas used out of gasLimit.
+func (k Keeper) SimulateInstantiate(ctx sdk.Context, codeID uint64, creator sdk.AccAddress, initMsg []byte, label string, deposit sdk.Coins, callbackSig []byte, gasLimit uint64) (sdk.AccAddress, uint64, error) {
+	var contractAddress sdk.AccAddress
+	gasUsed, err := runSimulation(ctx, gasLimit, func(simCtx sdk.Context) (err error) {
+		contractAddress, err = k.Instantiate(simCtx, codeID, creator, initMsg, label, deposit, callbackSig)
+		return err
+	})
+	return contractAddress, gasUsed, err
+}
+
 // Execute executes the contract instance
 func (k Keeper) Execute(ctx sdk.Context, contractAddress sdk.AccAddress, caller sdk.AccAddress, msg []byte, coins sdk.Coins, callbackSig []byte) (*sdk.Result, error) {
 	ctx.GasMeter().ConsumeGas(InstanceCost, "Loading CosmWasm module: execute")
@@ -371,16 +403,17 @@ func (k Keeper) Execute(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 		Signature: []byte{},
 	}
 	signBytes := []byte{}
+	signMode := ""
 	var err error
 
 	if callbackSig == nil {
-		signerSig, signBytes, err = k.GetSignerInfo(ctx, caller)
+		signerSig, signBytes, signMode, err = k.GetSignerInfo(ctx, caller)
 		if err != nil {
 			return nil, err
 		}
 	}
 
-	verificationInfo := types.NewVerificationInfo(signBytes, signerSig, callbackSig)
+	verificationInfo := types.NewVerificationInfo(signBytes, signMode, signerSig, callbackSig)
 
 	codeInfo, prefixStore, err := k.contractInstance(ctx, contractAddress)
 	if err != nil {
@@ -404,6 +437,8 @@ func (k Keeper) Execute(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 	contractKey := store.Get(types.GetContractEnclaveKey(contractAddress))
 	fmt.Printf("Contract Execute: Got contract Key for contract %s: %s\n", contractAddress, base64.StdEncoding.EncodeToString(contractKey))
 	params := types.NewEnv(ctx, caller, coins, contractAddress, contractKey)
+	params.SeedEpoch = k.seedEpochKeeper.GetSeedEpoch(ctx)
+	params.KeyProof = k.getContractKeyProof(ctx, contractAddress)
 	fmt.Printf("Contract Execute: key from params %s \n", params.Key)
 
 	// prepare querier
@@ -412,8 +447,12 @@ func (k Keeper) Execute(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 		Plugins: k.queryPlugins,
 	}
 
+	execute := k.wasmer.Execute
+	if isSimulation(ctx) {
+		execute = k.wasmer.SimulateExecute
+	}
 	gas := gasForContract(ctx)
-	res, gasUsed, execErr := k.wasmer.Execute(codeInfo.CodeHash, params, msg, prefixStore, cosmwasmAPI, querier, gasMeter(ctx), gas, verificationInfo)
+	res, gasUsed, execErr := execute(codeInfo.CodeHash, params, msg, prefixStore, cosmwasmAPI, querier, gasMeter(ctx), gas, verificationInfo, k.getGasSchedule(ctx))
 	consumeGas(ctx, gasUsed)
 
 	if execErr != nil {
@@ -441,36 +480,44 @@ func (k Keeper) Execute(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 	}, nil
 }
 
-/*
-// We don't use this function currently. It's here for upstream compatibility
-// Migrate allows to upgrade a contract to a new code with data migration.
-func (k Keeper) Migrate(ctx sdk.Context, contractAddress sdk.AccAddress, caller sdk.AccAddress, newCodeID uint64, msg []byte) (*sdk.Result, error) {
-	return k.migrate(ctx, contractAddress, caller, newCodeID, msg, k.authZPolicy)
+// SimulateExecute estimates the gas that Execute would use, without committing anything.
+// It returns the result of the contract, and the gas used out of gasLimit.
+func (k Keeper) SimulateExecute(ctx sdk.Context, contractAddress sdk.AccAddress, caller sdk.AccAddress, msg []byte, coins sdk.Coins, callbackSig []byte, gasLimit uint64) (*sdk.Result, uint64, error) {
+	var res *sdk.Result
+	gasUsed, err := runSimulation(ctx, gasLimit, func(simCtx sdk.Context) (err error) {
+		res, err = k.Execute(simCtx, contractAddress, caller, msg, coins, callbackSig)
+		return err
+	})
+	return res, gasUsed, err
 }
 
-func (k Keeper) migrate(ctx sdk.Context, contractAddress sdk.AccAddress, caller sdk.AccAddress, newCodeID uint64, msg []byte, authZ AuthorizationPolicy) (*sdk.Result, error) {
+// Migrate allows to upgrade a contract to a new code with data migration.
+// Only the creator of the contract can migrate it, which the enclave enforces with the admin proof
+// it issued when the contract was instantiated. The contract keeps its key, and the enclave
+// returns proofs that bind the key and the admin to the new code.
+func (k Keeper) Migrate(ctx sdk.Context, contractAddress sdk.AccAddress, caller sdk.AccAddress, newCodeID uint64, msg []byte) (*sdk.Result, error) {
 	ctx.GasMeter().ConsumeGas(InstanceCost, "Loading CosmWasm module: migrate")
-	_ = authtypes.StdSignature{
-		PubKey:    secp256k1.PubKeySecp256k1{},
-		Signature: []byte{},
-	}
-	_ = []byte{}
 
-	tx := authtypes.StdTx{}
-	txBytes := ctx.TxBytes()
-	err := k.cdc.UnmarshalBinaryLengthPrefixed(txBytes, &tx)
+	signerSig, signBytes, signMode, err := k.GetSignerInfo(ctx, caller)
 	if err != nil {
-		return &sdk.Result{}, sdkerrors.Wrap(types.ErrInstantiateFailed, fmt.Sprintf("Unable to decode transaction from bytes: %s", err.Error()))
+		return nil, err
 	}
 
+	verificationInfo := types.NewVerificationInfo(signBytes, signMode, signerSig, nil)
+
 	contractInfo := k.GetContractInfo(ctx, contractAddress)
 	if contractInfo == nil {
 		return nil, sdkerrors.Wrap(sdkerrors.ErrInvalidRequest, "unknown contract")
 	}
-	if !authZ.CanModifyContract(contractInfo.Admin, caller) {
+	if !contractInfo.Creator.Equals(caller) {
 		return nil, sdkerrors.Wrap(sdkerrors.ErrUnauthorized, "can not migrate")
 	}
 
+	oldCodeInfo := k.GetCodeInfo(ctx, contractInfo.CodeID)
+	if oldCodeInfo == nil {
+		return nil, sdkerrors.Wrap(types.ErrNotFound, "contract info")
+	}
+
 	newCodeInfo := k.GetCodeInfo(ctx, newCodeID)
 	if newCodeInfo == nil {
 		return nil, sdkerrors.Wrap(sdkerrors.ErrInvalidRequest, "unknown code")
@@ -481,6 +528,13 @@ func (k Keeper) migrate(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 
 	var noDeposit sdk.Coins
 	params := types.NewEnv(ctx, caller, noDeposit, contractAddress, contractKey)
+	params.SeedEpoch = k.seedEpochKeeper.GetSeedEpoch(ctx)
+	// The enclave authenticates the contract key against the code the contract is migrating from
+	params.CodeHash = hex.EncodeToString(oldCodeInfo.CodeHash)
+	params.KeyProof = k.getContractKeyProof(ctx, contractAddress)
+	// The enclave checks that the caller is the admin the proof was issued for, and that the proof
+	// was issued for the code hash above
+	params.AdminProof = k.getContractAdminProof(ctx, contractAddress)
 
 	// prepare querier
 	querier := QueryHandler{
@@ -491,7 +545,7 @@ func (k Keeper) migrate(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 	prefixStoreKey := types.GetContractStorePrefixKey(contractAddress)
 	prefixStore := prefix.NewStore(ctx.KVStore(k.storeKey), prefixStoreKey)
 	gas := gasForContract(ctx)
-	res, gasUsed, err := k.wasmer.Migrate(newCodeInfo.CodeHash, params, msg, &prefixStore, cosmwasmAPI, &querier, gasMeter(ctx), gas)
+	res, proofs, gasUsed, err := k.wasmer.Migrate(newCodeInfo.CodeHash, params, msg, prefixStore, cosmwasmAPI, querier, gasMeter(ctx), gas, verificationInfo, k.getGasSchedule(ctx))
 	consumeGas(ctx, gasUsed)
 	if err != nil {
 		return nil, sdkerrors.Wrap(types.ErrMigrationFailed, err.Error())
@@ -501,8 +555,8 @@ func (k Keeper) migrate(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 	events := types.ParseEvents(res.Log, contractAddress)
 	ctx.EventManager().EmitEvents(events)
 
-	historyEntry := contractInfo.AddMigration(ctx, newCodeID, msg)
-	k.appendToContractHistory(ctx, contractAddress, historyEntry)
+	k.setContractProofs(ctx, contractAddress, proofs)
+	contractInfo.CodeID = newCodeID
 	k.setContractInfo(ctx, contractAddress, contractInfo)
 
 	if err := k.dispatchMessages(ctx, contractAddress, res.Messages); err != nil {
@@ -514,6 +568,105 @@ func (k Keeper) migrate(ctx sdk.Context, contractAddress sdk.AccAddress, caller
 	}, nil
 }
 
+// getContractKeyProof returns the key proof of a contract, or an empty proof for a contract that was instantiated
+// in the genesis epoch before there were key proofs, and never migrated
+func (k Keeper) getContractKeyProof(ctx sdk.Context, contractAddress sdk.AccAddress) wasmTypes.ContractKeyProof {
+	store := ctx.KVStore(k.storeKey)
+	keyProof := store.Get(types.GetContractKeyProofKey(contractAddress))
+	if keyProof == nil {
+		return ""
+	}
+	return wasmTypes.ContractKeyProof(base64.StdEncoding.EncodeToString(keyProof))
+}
+
+// getContractAdminProof returns the admin proof of a contract, or an empty proof for a contract that was
+// instantiated before there were admin proofs
+func (k Keeper) getContractAdminProof(ctx sdk.Context, contractAddress sdk.AccAddress) wasmTypes.ContractAdminProof {
+	store := ctx.KVStore(k.storeKey)
+	adminProof := store.Get(types.GetContractAdminProofKey(contractAddress))
+	if adminProof == nil {
+		return ""
+	}
+	return wasmTypes.ContractAdminProof(base64.StdEncoding.EncodeToString(adminProof))
+}
+
+func (k Keeper) setContractProofs(ctx sdk.Context, contractAddress sdk.AccAddress, proofs wasmTypes.ContractProofs) {
+	store := ctx.KVStore(k.storeKey)
+	store.Set(types.GetContractKeyProofKey(contractAddress), proofs.KeyProof)
+	store.Set(types.GetContractAdminProofKey(contractAddress), proofs.AdminProof)
+}
+
+// ReissueContractProofs moves the proofs of all the contracts to the seed epoch the chain uses.
+// The enclave only accepts contract keys with a proof of the epoch of the call, so this must run at the start of
+// the first block of every epoch, before any contract is called. It does nothing while the chain stays in an epoch.
+func (k Keeper) ReissueContractProofs(ctx sdk.Context) error {
+	epoch := k.seedEpochKeeper.GetSeedEpoch(ctx)
+	for proofsEpoch := k.getProofsSeedEpoch(ctx); proofsEpoch < epoch; proofsEpoch++ {
+		// The enclave only carries proofs over from the previous epoch, so every epoch is issued in order.
+		// The contracts are collected first, since the store must not be written to while it is iterated.
+		var contracts []sdk.AccAddress
+		k.IterateContractInfo(ctx, func(contractAddress sdk.AccAddress, _ types.ContractInfo) bool {
+			contracts = append(contracts, contractAddress)
+			return false
+		})
+
+		for _, contractAddress := range contracts {
+			if err := k.reissueContractProofs(ctx, contractAddress, proofsEpoch+1); err != nil {
+				return err
+			}
+		}
+		k.setProofsSeedEpoch(ctx, proofsEpoch+1)
+	}
+	return nil
+}
+
+func (k Keeper) reissueContractProofs(ctx sdk.Context, contractAddress sdk.AccAddress, epoch uint32) error {
+	contractInfo := k.GetContractInfo(ctx, contractAddress)
+	codeInfo := k.GetCodeInfo(ctx, contractInfo.CodeID)
+	if codeInfo == nil {
+		return sdkerrors.Wrap(types.ErrNotFound, "code info")
+	}
+
+	store := ctx.KVStore(k.storeKey)
+	contractKey := store.Get(types.GetContractEnclaveKey(contractAddress))
+
+	var noDeposit sdk.Coins
+	// The enclave checks the admin proof against the sender, which is the admin of the contract
+	params := types.NewEnv(ctx, contractInfo.Creator, noDeposit, contractAddress, contractKey)
+	params.SeedEpoch = epoch
+	params.CodeHash = hex.EncodeToString(codeInfo.CodeHash)
+	params.KeyProof = k.getContractKeyProof(ctx, contractAddress)
+	params.AdminProof = k.getContractAdminProof(ctx, contractAddress)
+
+	proofs, err := k.wasmer.ReissueContractProofs(params)
+	if err != nil {
+		return sdkerrors.Wrap(types.ErrReissueFailed, err.Error())
+	}
+
+	store.Set(types.GetContractKeyProofKey(contractAddress), proofs.KeyProof)
+	// Contracts without an admin proof can't be migrated, and get an empty admin proof back
+	if params.AdminProof != "" {
+		store.Set(types.GetContractAdminProofKey(contractAddress), proofs.AdminProof)
+	}
+	return nil
+}
+
+// getProofsSeedEpoch returns the seed epoch the proofs of the contracts were issued in
+func (k Keeper) getProofsSeedEpoch(ctx sdk.Context) uint32 {
+	bz := ctx.KVStore(k.storeKey).Get(types.ProofsSeedEpochKey)
+	if bz == nil {
+		return 0
+	}
+	return binary.BigEndian.Uint32(bz)
+}
+
+func (k Keeper) setProofsSeedEpoch(ctx sdk.Context, epoch uint32) {
+	bz := make([]byte, 4)
+	binary.BigEndian.PutUint32(bz, epoch)
+	ctx.KVStore(k.storeKey).Set(types.ProofsSeedEpochKey, bz)
+}
+
+/*
 // UpdateContractAdmin sets the admin value on the ContractInfo. It must be a valid address (use ClearContractAdmin to remove it)
 func (k Keeper) UpdateContractAdmin(ctx sdk.Context, contractAddress sdk.AccAddress, caller sdk.AccAddress, newAdmin sdk.AccAddress) error {
 	return k.setContractAdmin(ctx, contractAddress, caller, newAdmin, k.authZPolicy)
@@ -576,7 +729,7 @@ func (k Keeper) QuerySmart(ctx sdk.Context, contractAddr sdk.AccAddress, req []b
 	// 0x01 | codeID (uint64) -> ContractInfo
 	contractKey := store.Get(types.GetContractEnclaveKey(contractAddr))
 
-	queryResult, gasUsed, qErr := k.wasmer.Query(codeInfo.CodeHash, append(contractKey[:], req[:]...), prefixStore, cosmwasmAPI, querier, gasMeter(ctx), gasForContract(ctx))
+	queryResult, gasUsed, qErr := k.wasmer.Query(codeInfo.CodeHash, append(contractKey[:], req[:]...), prefixStore, cosmwasmAPI, querier, gasMeter(ctx), gasForContract(ctx), k.getGasSchedule(ctx))
 	consumeGas(ctx, gasUsed)
 
 	if qErr != nil {
@@ -585,6 +738,46 @@ func (k Keeper) QuerySmart(ctx sdk.Context, contractAddr sdk.AccAddress, req []b
 	return queryResult, nil
 }
 
+// QuerySmartBatch runs several smart queries in a single call into the enclave.
+// The queries share the gas of ctx, and a query that fails doesn't fail the batch, its error is
+// returned in its result instead.
+func (k Keeper) QuerySmartBatch(ctx sdk.Context, queries []SmartQuery, useDefaultGasLimit bool) ([]wasmTypes.BatchQueryResult, error) {
+	if useDefaultGasLimit {
+		ctx = ctx.WithGasMeter(sdk.NewGasMeter(k.queryGasLimit))
+	}
+	ctx.GasMeter().ConsumeGas(InstanceCost*uint64(len(queries)), "Loading CosmWasm module: query batch")
+
+	store := ctx.KVStore(k.storeKey)
+	batch := make([]wasmTypes.BatchQuery, len(queries))
+	stores := make([]wasm.KVStore, len(queries))
+	for i, query := range queries {
+		codeInfo, prefixStore, err := k.contractInstance(ctx, query.Contract)
+		if err != nil {
+			return nil, err
+		}
+		contractKey := store.Get(types.GetContractEnclaveKey(query.Contract))
+		batch[i] = wasmTypes.BatchQuery{
+			CodeID: codeInfo.CodeHash,
+			Msg:    append(contractKey[:], query.Msg[:]...),
+		}
+		stores[i] = prefixStore
+	}
+
+	// prepare querier
+	querier := QueryHandler{
+		Ctx:     ctx,
+		Plugins: k.queryPlugins,
+	}
+
+	results, gasUsed, qErr := k.wasmer.QueryBatch(batch, stores, cosmwasmAPI, querier, gasMeter(ctx), gasForContract(ctx), k.getGasSchedule(ctx))
+	consumeGas(ctx, gasUsed)
+
+	if qErr != nil {
+		return nil, sdkerrors.Wrap(types.ErrQueryFailed, qErr.Error())
+	}
+	return results, nil
+}
+
 // We don't use this function since we have an encrypted state. It's here for upstream compatibility
 // QueryRaw returns the contract's state for give key. For a `nil` key a empty slice result is returned.
 func (k Keeper) QueryRaw(ctx sdk.Context, contractAddress sdk.AccAddress, key []byte) []types.Model {
@@ -763,6 +956,35 @@ func gasForContract(ctx sdk.Context) uint64 {
 	return remaining
 }
 
+// simulationKey marks the context of a simulation, so the contracts it calls are simulated as well
+type simulationKey struct{}
+
+func isSimulation(ctx sdk.Context) bool {
+	simulate, _ := ctx.Value(simulationKey{}).(bool)
+	return simulate
+}
+
+// runSimulation runs a call on a cache of the state that is never written back, so the contract, its
+// queries and the messages it dispatches all see its writes, but none of them are committed.
+// It returns the gas the call used, which may be more than gasLimit if it ran out of gas.
+func runSimulation(ctx sdk.Context, gasLimit uint64, call func(sdk.Context) error) (gasUsed uint64, err error) {
+	simCtx, _ := ctx.CacheContext()
+	simCtx = simCtx.WithGasMeter(sdk.NewGasMeter(gasLimit)).WithValue(simulationKey{}, true)
+
+	defer func() {
+		if r := recover(); r != nil {
+			outOfGas, ok := r.(sdk.ErrorOutOfGas)
+			if !ok {
+				panic(r)
+			}
+			err = sdkerrors.Wrap(sdkerrors.ErrOutOfGas, outOfGas.Descriptor)
+		}
+		gasUsed = simCtx.GasMeter().GasConsumed()
+	}()
+
+	return 0, call(simCtx)
+}
+
 func consumeGas(ctx sdk.Context, gas uint64) {
 	consumed := (gas / GasMultiplier) + 1
 	ctx.GasMeter().ConsumeGas(consumed, "wasm contract")
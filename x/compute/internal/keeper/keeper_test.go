@@ -48,7 +48,7 @@ func init() {
 	fmt.Printf("This IS spid: %v\n", spid)
 	fmt.Printf("This IS api key: %v\n", apiKey)
 
-	_, err = api.InitBootstrap(spid, apiKey)
+	_, err = api.InitBootstrap(spid, apiKey, "test-chain", nil)
 	if err != nil {
 		panic(fmt.Sprintf("Error initializing the enclave: %v", err))
 	}
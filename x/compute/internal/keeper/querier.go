@@ -14,14 +14,17 @@ import (
 )
 
 const (
-	QueryListContractByCode = "list-contracts-by-code"
-	QueryGetContract        = "contract-info"
-	QueryGetContractState   = "query"
-	QueryGetCode            = "code"
-	QueryListCode           = "list-code"
-	QueryContractAddress    = "label"
-	QueryContractKey        = "contract-key"
-	QueryContractHash       = "contract-hash"
+	QueryListContractByCode  = "list-contracts-by-code"
+	QueryGetContract         = "contract-info"
+	QueryGetContractState    = "query"
+	QueryGetContractStates   = "query-batch"
+	QueryGetCode             = "code"
+	QueryListCode            = "list-code"
+	QueryContractAddress     = "label"
+	QueryContractKey         = "contract-key"
+	QueryContractHash        = "contract-hash"
+	QuerySimulateInstantiate = "simulate-instantiate"
+	QuerySimulateExecute     = "simulate-execute"
 	// QueryContractHistory    = "contract-history"
 )
 
@@ -52,6 +55,8 @@ func NewQuerier(keeper Keeper) sdk.Querier {
 			return queryContractListByCode(ctx, path[1], keeper)
 		case QueryGetContractState:
 			return queryContractState(ctx, path[1], req, keeper)
+		case QueryGetContractStates:
+			return queryContractStates(ctx, req, keeper)
 		case QueryGetCode:
 			return queryCode(ctx, path[1], keeper)
 		case QueryListCode:
@@ -66,6 +71,10 @@ func NewQuerier(keeper Keeper) sdk.Querier {
 			return queryContractKey(ctx, path[1], req, keeper)
 		case QueryContractHash:
 			return queryContractHash(ctx, path[1], req, keeper)
+		case QuerySimulateInstantiate:
+			return querySimulateInstantiate(ctx, req, keeper)
+		case QuerySimulateExecute:
+			return querySimulateExecute(ctx, req, keeper)
 		default:
 			return nil, sdkerrors.Wrap(sdkerrors.ErrUnknownRequest, "unknown data query endpoint")
 		}
@@ -142,6 +151,30 @@ func queryContractState(ctx sdk.Context, bech string, req abci.RequestQuery, kee
 	return keeper.QuerySmart(ctx, contractAddr, req.Data, false)
 }
 
+// SmartQuery is a query of a contract, in the input of the query-batch route
+type SmartQuery struct {
+	Contract sdk.AccAddress `json:"contract"`
+	Msg      []byte         `json:"msg"`
+}
+
+func queryContractStates(ctx sdk.Context, req abci.RequestQuery, keeper Keeper) ([]byte, error) {
+	var queries []SmartQuery
+	if err := json.Unmarshal(req.Data, &queries); err != nil {
+		return nil, sdkerrors.Wrap(sdkerrors.ErrJSONUnmarshal, err.Error())
+	}
+
+	results, err := keeper.QuerySmartBatch(ctx, queries, false)
+	if err != nil {
+		return nil, err
+	}
+
+	bz, err := json.MarshalIndent(results, "", "  ")
+	if err != nil {
+		return nil, sdkerrors.Wrap(sdkerrors.ErrJSONMarshal, err.Error())
+	}
+	return bz, nil
+}
+
 type GetCodeResponse struct {
 	ListCodeResponse
 	// Data is the entire wasm bytecode
@@ -273,3 +306,67 @@ func queryContractHash(ctx sdk.Context, address string, req abci.RequestQuery, k
 
 	return keeper.GetCodeInfo(ctx, res.CodeID).CodeHash, nil
 }
+
+// SimulateInstantiateRequest is the input of the simulate-instantiate route.
+// Tx is the signed transaction that carries Msg, which the enclave verifies Msg against.
+type SimulateInstantiateRequest struct {
+	Msg      types.MsgInstantiateContract `json:"msg"`
+	Tx       []byte                       `json:"tx"`
+	GasLimit uint64                       `json:"gas_limit"`
+}
+
+// SimulateExecuteRequest is the input of the simulate-execute route.
+// Tx is the signed transaction that carries Msg, which the enclave verifies Msg against.
+type SimulateExecuteRequest struct {
+	Msg      types.MsgExecuteContract `json:"msg"`
+	Tx       []byte                   `json:"tx"`
+	GasLimit uint64                   `json:"gas_limit"`
+}
+
+// SimulateResponse is the output of the simulate routes. Address is the address an instantiated
+// contract would get, and Data is the (encrypted) output of an execution.
+type SimulateResponse struct {
+	GasUsed uint64         `json:"gas_used"`
+	Address sdk.AccAddress `json:"address,omitempty"`
+	Data    []byte         `json:"data,omitempty"`
+}
+
+func querySimulateInstantiate(ctx sdk.Context, req abci.RequestQuery, keeper Keeper) ([]byte, error) {
+	var simReq SimulateInstantiateRequest
+	if err := json.Unmarshal(req.Data, &simReq); err != nil {
+		return nil, sdkerrors.Wrap(sdkerrors.ErrJSONUnmarshal, err.Error())
+	}
+	msg := simReq.Msg
+
+	ctx = ctx.WithTxBytes(simReq.Tx)
+	addr, gasUsed, err := keeper.SimulateInstantiate(ctx, msg.CodeID, msg.Sender, msg.InitMsg, msg.Label, msg.InitFunds, msg.CallbackSignature, simReq.GasLimit)
+	if err != nil {
+		return nil, err
+	}
+
+	bz, err := json.MarshalIndent(SimulateResponse{GasUsed: gasUsed, Address: addr}, "", "  ")
+	if err != nil {
+		return nil, sdkerrors.Wrap(sdkerrors.ErrJSONMarshal, err.Error())
+	}
+	return bz, nil
+}
+
+func querySimulateExecute(ctx sdk.Context, req abci.RequestQuery, keeper Keeper) ([]byte, error) {
+	var simReq SimulateExecuteRequest
+	if err := json.Unmarshal(req.Data, &simReq); err != nil {
+		return nil, sdkerrors.Wrap(sdkerrors.ErrJSONUnmarshal, err.Error())
+	}
+	msg := simReq.Msg
+
+	ctx = ctx.WithTxBytes(simReq.Tx)
+	res, gasUsed, err := keeper.SimulateExecute(ctx, msg.Contract, msg.Sender, msg.Msg, msg.SentFunds, msg.CallbackSignature, simReq.GasLimit)
+	if err != nil {
+		return nil, err
+	}
+
+	bz, err := json.MarshalIndent(SimulateResponse{GasUsed: gasUsed, Data: res.Data}, "", "  ")
+	if err != nil {
+		return nil, sdkerrors.Wrap(sdkerrors.ErrJSONMarshal, err.Error())
+	}
+	return bz, nil
+}
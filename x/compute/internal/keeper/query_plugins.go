@@ -23,7 +23,7 @@ type QueryHandler struct {
 
 var _ wasmTypes.Querier = QueryHandler{}
 
-func (q QueryHandler) Query(request wasmTypes.QueryRequest, gasLimit uint64) ([]byte, error) {
+func (q QueryHandler) Query(request wasmTypes.QueryRequest, gasLimit uint64) (res []byte, err error) {
 	// set a limit for a subctx
 	sdkGas := gasLimit / GasMultiplier
 	subctx := q.Ctx.WithGasMeter(sdk.NewGasMeter(sdkGas))
@@ -33,6 +33,17 @@ func (q QueryHandler) Query(request wasmTypes.QueryRequest, gasLimit uint64) ([]
 		q.Ctx.GasMeter().ConsumeGas(subctx.GasMeter().GasConsumed(), "contract sub-query")
 	}()
 
+	// running out of the sub-query budget is an error the calling contract can handle,
+	// the higher level context is still charged for all of it
+	defer func() {
+		if r := recover(); r != nil {
+			if _, ok := r.(sdk.ErrorOutOfGas); !ok {
+				panic(r)
+			}
+			res, err = nil, wasmTypes.ExceededQueryGasLimit{GasLimit: gasLimit}
+		}
+	}()
+
 	// do the query
 	if request.Bank != nil {
 		return q.Plugins.Bank(subctx, request.Bank)
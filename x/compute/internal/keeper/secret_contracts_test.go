@@ -16,6 +16,7 @@ import (
 	cosmwasm "github.com/enigmampc/SecretNetwork/go-cosmwasm/types"
 	sdk "github.com/enigmampc/cosmos-sdk/types"
 	"github.com/stretchr/testify/require"
+	abci "github.com/tendermint/tendermint/abci/types"
 	"github.com/tendermint/tendermint/libs/log"
 )
 
@@ -337,6 +338,40 @@ func initHelperImpl(t *testing.T, keeper Keeper, ctx sdk.Context, codeID uint64,
 	return contractAddress, wasmEvents, cosmwasm.StdError{}
 }
 
+func migrateHelper(t *testing.T, keeper Keeper, ctx sdk.Context, contractAddress sdk.AccAddress, newCodeID uint64, sender sdk.AccAddress, senderPrivKey crypto.PrivKey, migrateMsg string, isErrorEncrypted bool, gas uint64) ([]ContractEvent, cosmwasm.StdError) {
+	// the migrate msg is meant for the new code, so it's encrypted with its hash
+	hashStr := hex.EncodeToString(keeper.GetCodeInfo(ctx, newCodeID).CodeHash)
+
+	msg := types.SecretMsg{
+		CodeHash: []byte(hashStr),
+		Msg:      []byte(migrateMsg),
+	}
+
+	migrateMsgBz, err := wasmCtx.Encrypt(msg.Serialize())
+	require.NoError(t, err)
+	nonce := migrateMsgBz[0:32]
+
+	// create new ctx with the same storage and a gas limit
+	// this is to reset the event manager, so we won't get
+	// events from past calls
+	gasMeter := &WasmCounterGasMeter{0, sdk.NewGasMeter(gas)}
+	ctx = sdk.NewContext(
+		ctx.MultiStore(),
+		ctx.BlockHeader(),
+		ctx.IsCheckTx(),
+		log.NewNopLogger(),
+	).WithGasMeter(gasMeter)
+
+	ctx = PrepareMigrateSignedTx(t, keeper, ctx, sender, senderPrivKey, migrateMsgBz, contractAddress, newCodeID)
+	_, err = keeper.Migrate(ctx, contractAddress, sender, newCodeID, migrateMsgBz)
+
+	if err != nil {
+		return nil, extractInnerError(t, err, nonce, isErrorEncrypted)
+	}
+
+	return getDecryptedWasmEvents(t, ctx, nonce), cosmwasm.StdError{}
+}
+
 func TestCallbackSanity(t *testing.T) {
 	ctx, keeper, tempDir, codeID, codeHash, walletA, privKeyA, _, _ := setupTest(t, "./testdata/test-contract/contract.wasm")
 	defer os.RemoveAll(tempDir)
@@ -425,6 +460,56 @@ func TestSanity(t *testing.T) {
 	require.JSONEq(t, `{"balance":"63"}`, qRes)
 }
 
+func TestQueryBatch(t *testing.T) {
+	ctx, keeper, tempDir, codeID, _, walletA, privKeyA, walletB, _ := setupTest(t, "./testdata/erc20.wasm")
+	defer os.RemoveAll(tempDir)
+
+	// init
+	initMsg := fmt.Sprintf(`{"decimals":10,"initial_balances":[{"address":"%s","amount":"108"},{"address":"%s","amount":"53"}],"name":"ReuvenPersonalRustCoin","symbol":"RPRC"}`, walletA.String(), walletB.String())
+
+	contractAddress, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, initMsg, true, defaultGasForTests)
+	require.Empty(t, initErr)
+
+	inputs := []string{
+		fmt.Sprintf(`{"balance":{"address":"%s"}}`, walletA.String()),
+		fmt.Sprintf(`{"balance":{"address":"%s"}}`, walletB.String()),
+		`{"balance":{"address":"blabla"}}`,
+	}
+	var queries []SmartQuery
+	var nonces [][]byte
+	for _, input := range inputs {
+		queryBz, err := testEncrypt(t, keeper, ctx, contractAddress, 0, []byte(input))
+		require.NoError(t, err)
+		queries = append(queries, SmartQuery{Contract: contractAddress, Msg: queryBz})
+		nonces = append(nonces, queryBz[0:32])
+	}
+	reqBz, err := json.Marshal(queries)
+	require.NoError(t, err)
+
+	ctx = ctx.WithGasMeter(sdk.NewGasMeter(defaultGasForTests))
+	q := NewQuerier(keeper)
+	resBz, err := q(ctx, []string{QueryGetContractStates}, abci.RequestQuery{Data: reqBz})
+	require.NoError(t, err)
+	require.NotZero(t, ctx.GasMeter().GasConsumed())
+
+	var results []cosmwasm.BatchQueryResult
+	require.NoError(t, json.Unmarshal(resBz, &results))
+	require.Equal(t, len(inputs), len(results))
+
+	for i, expected := range []string{`{"balance":"108"}`, `{"balance":"53"}`} {
+		require.Empty(t, results[i].Err)
+		resultPlainBz, err := wasmCtx.Decrypt(results[i].Ok, nonces[i])
+		require.NoError(t, err)
+		resultBz, err := base64.StdEncoding.DecodeString(string(resultPlainBz))
+		require.NoError(t, err)
+		require.JSONEq(t, expected, string(resultBz))
+	}
+
+	// the failed query doesn't fail the others
+	require.Empty(t, results[2].Ok)
+	require.NotEmpty(t, results[2].Err)
+}
+
 func TestInitLogs(t *testing.T) {
 	ctx, keeper, tempDir, codeID, _, walletA, privKeyA, _, _ := setupTest(t, "./testdata/test-contract/contract.wasm")
 	defer os.RemoveAll(tempDir)
@@ -1047,6 +1132,32 @@ func TestState(t *testing.T) {
 	require.Empty(t, data)
 }
 
+func TestSimulateExecuteDoesntCommitState(t *testing.T) {
+	ctx, keeper, tempDir, codeID, _, walletA, privKeyA, _, _ := setupTest(t, "./testdata/test-contract/contract.wasm")
+	defer os.RemoveAll(tempDir)
+
+	contractAddress, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, initErr)
+
+	hashStr := hex.EncodeToString(keeper.GetContractHash(ctx, contractAddress))
+	msg := types.SecretMsg{
+		CodeHash: []byte(hashStr),
+		Msg:      []byte(`{"set_state":{"key":"banana","value":"🍌"}}`),
+	}
+	execMsgBz, err := wasmCtx.Encrypt(msg.Serialize())
+	require.NoError(t, err)
+
+	coins := sdk.NewCoins(sdk.NewInt64Coin("denom", 0))
+	simCtx := PrepareExecSignedTx(t, keeper, ctx, walletA, privKeyA, execMsgBz, contractAddress, coins)
+	_, gasUsed, err := keeper.SimulateExecute(simCtx, contractAddress, walletA, execMsgBz, coins, nil, defaultGasForTests)
+	require.NoError(t, err)
+	require.NotZero(t, gasUsed)
+
+	data, _, execErr := execHelper(t, keeper, ctx, contractAddress, walletA, privKeyA, `{"get_state":{"key":"banana"}}`, true, defaultGasForTests, 0)
+	require.Empty(t, execErr)
+	require.Empty(t, data)
+}
+
 func TestCanonicalizeAddressErrors(t *testing.T) {
 	ctx, keeper, tempDir, codeID, _, walletA, privKeyA, _, _ := setupTest(t, "./testdata/test-contract/contract.wasm")
 	defer os.RemoveAll(tempDir)
@@ -2225,3 +2336,162 @@ func TestCodeHashQueryCallQuery(t *testing.T) {
 		)
 	})
 }
+
+func setupMigrationTest(t *testing.T) (sdk.Context, Keeper, string, uint64, uint64, sdk.AccAddress, crypto.PrivKey, sdk.AccAddress, crypto.PrivKey) {
+	ctx, keeper, tempDir, codeID, _, walletA, privKeyA, walletB, privKeyB := setupTest(t, "./testdata/test-contract/contract.wasm")
+
+	// the same contract built with different features, so that the code hash changes
+	wasmCode, err := ioutil.ReadFile("./testdata/test-contract/contract_with_floats.wasm")
+	require.NoError(t, err)
+	newCodeID, err := keeper.Create(ctx, walletA, wasmCode, "", "")
+	require.NoError(t, err)
+	require.NotEqual(t, keeper.GetCodeInfo(ctx, codeID).CodeHash, keeper.GetCodeInfo(ctx, newCodeID).CodeHash)
+
+	return ctx, keeper, tempDir, codeID, newCodeID, walletA, privKeyA, walletB, privKeyB
+}
+
+func TestMigrateThenExecute(t *testing.T) {
+	ctx, keeper, tempDir, codeID, newCodeID, walletA, privKeyA, _, _ := setupMigrationTest(t)
+	defer os.RemoveAll(tempDir)
+
+	addr, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, initErr)
+
+	_, _, execErr := execHelper(t, keeper, ctx, addr, walletA, privKeyA, `{"set_state":{"key":"banana","value":"🍌"}}`, true, defaultGasForTests, 0)
+	require.Empty(t, execErr)
+
+	migrateEvents, migrateErr := migrateHelper(t, keeper, ctx, addr, newCodeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, migrateErr)
+	require.Equal(t,
+		[]ContractEvent{
+			{
+				{Key: "contract_address", Value: addr.String()},
+				{Key: "migrate", Value: "🦋"},
+			},
+		},
+		migrateEvents,
+	)
+
+	require.Equal(t, newCodeID, keeper.GetContractInfo(ctx, addr).CodeID)
+	require.Len(t, keeper.getContractKeyProof(ctx, addr), 44)
+
+	// the contract runs the new code with its old key, and can still read the state it wrote before
+	data, _, execErr := execHelper(t, keeper, ctx, addr, walletA, privKeyA, `{"get_state":{"key":"banana"}}`, true, defaultGasForTests, 0)
+	require.Empty(t, execErr)
+	require.Equal(t, "🍌", string(data))
+
+	_, _, execErr = execHelper(t, keeper, ctx, addr, walletA, privKeyA, `{"set_state":{"key":"apple","value":"🍎"}}`, true, defaultGasForTests, 0)
+	require.Empty(t, execErr)
+
+	data, _, execErr = execHelper(t, keeper, ctx, addr, walletA, privKeyA, `{"get_state":{"key":"apple"}}`, true, defaultGasForTests, 0)
+	require.Empty(t, execErr)
+	require.Equal(t, "🍎", string(data))
+}
+
+func TestMigrateWritesState(t *testing.T) {
+	ctx, keeper, tempDir, codeID, newCodeID, walletA, privKeyA, _, _ := setupMigrationTest(t)
+	defer os.RemoveAll(tempDir)
+
+	addr, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, initErr)
+
+	_, migrateErr := migrateHelper(t, keeper, ctx, addr, newCodeID, walletA, privKeyA, `{"set_state":{"key":"banana","value":"🍌"}}`, true, defaultGasForTests)
+	require.Empty(t, migrateErr)
+
+	data, _, execErr := execHelper(t, keeper, ctx, addr, walletA, privKeyA, `{"get_state":{"key":"banana"}}`, true, defaultGasForTests, 0)
+	require.Empty(t, execErr)
+	require.Equal(t, "🍌", string(data))
+}
+
+func TestMigrateTwice(t *testing.T) {
+	ctx, keeper, tempDir, codeID, newCodeID, walletA, privKeyA, _, _ := setupMigrationTest(t)
+	defer os.RemoveAll(tempDir)
+
+	addr, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, initErr)
+
+	_, migrateErr := migrateHelper(t, keeper, ctx, addr, newCodeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, migrateErr)
+	firstProof := keeper.getContractKeyProof(ctx, addr)
+
+	// the second migration authenticates the key with the proof of the first one
+	_, migrateErr = migrateHelper(t, keeper, ctx, addr, codeID, walletA, privKeyA, `{"set_state":{"key":"banana","value":"🍌"}}`, true, defaultGasForTests)
+	require.Empty(t, migrateErr)
+	require.Equal(t, codeID, keeper.GetContractInfo(ctx, addr).CodeID)
+	require.NotEqual(t, firstProof, keeper.getContractKeyProof(ctx, addr))
+
+	data, _, execErr := execHelper(t, keeper, ctx, addr, walletA, privKeyA, `{"get_state":{"key":"banana"}}`, true, defaultGasForTests, 0)
+	require.Empty(t, execErr)
+	require.Equal(t, "🍌", string(data))
+}
+
+func TestInstantiateIssuesProofs(t *testing.T) {
+	ctx, keeper, tempDir, codeID, _, walletA, privKeyA, _, _ := setupMigrationTest(t)
+	defer os.RemoveAll(tempDir)
+
+	addr, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, initErr)
+
+	keyProof := keeper.getContractKeyProof(ctx, addr)
+	adminProof := keeper.getContractAdminProof(ctx, addr)
+	require.Len(t, keyProof, 44)
+	require.Len(t, adminProof, 44)
+
+	// the chain stays in the genesis epoch, so the proofs aren't issued again
+	require.NoError(t, keeper.ReissueContractProofs(ctx))
+	require.Equal(t, keyProof, keeper.getContractKeyProof(ctx, addr))
+	require.Equal(t, adminProof, keeper.getContractAdminProof(ctx, addr))
+}
+
+func TestMigrateByNonCreator(t *testing.T) {
+	ctx, keeper, tempDir, codeID, newCodeID, walletA, privKeyA, walletB, privKeyB := setupMigrationTest(t)
+	defer os.RemoveAll(tempDir)
+
+	addr, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, initErr)
+
+	_, migrateErr := migrateHelper(t, keeper, ctx, addr, newCodeID, walletB, privKeyB, `{"nop":{}}`, false, defaultGasForTests)
+	require.NotNil(t, migrateErr.GenericErr)
+	require.Contains(t, migrateErr.GenericErr.Msg, "can not migrate")
+
+	require.Equal(t, codeID, keeper.GetContractInfo(ctx, addr).CodeID)
+	require.Empty(t, keeper.getContractKeyProof(ctx, addr))
+}
+
+func TestMigrateContractError(t *testing.T) {
+	ctx, keeper, tempDir, codeID, newCodeID, walletA, privKeyA, _, _ := setupMigrationTest(t)
+	defer os.RemoveAll(tempDir)
+
+	addr, _, initErr := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, `{"nop":{}}`, true, defaultGasForTests)
+	require.Empty(t, initErr)
+
+	_, migrateErr := migrateHelper(t, keeper, ctx, addr, newCodeID, walletA, privKeyA, `{"contract_error":{"error_type":"generic_err"}}`, true, defaultGasForTests)
+	require.NotNil(t, migrateErr.GenericErr)
+	require.Equal(t, "la la 🤯", migrateErr.GenericErr.Msg)
+
+	// a failed migration leaves the contract on its code, and it keeps working
+	require.Equal(t, codeID, keeper.GetContractInfo(ctx, addr).CodeID)
+	_, _, execErr := execHelper(t, keeper, ctx, addr, walletA, privKeyA, `{"set_state":{"key":"banana","value":"🍌"}}`, true, defaultGasForTests, 0)
+	require.Empty(t, execErr)
+}
+
+func TestGasScheduleParam(t *testing.T) {
+	ctx, keeper, tempDir, codeID, _, walletA, privKeyA, _, _ := setupTest(t, "./testdata/erc20.wasm")
+	defer os.RemoveAll(tempDir)
+
+	initMsg := fmt.Sprintf(`{"decimals":10,"initial_balances":[{"address":"%s","amount":"108"}],"name":"ReuvenPersonalRustCoin","symbol":"RPRC"}`, walletA.String())
+	contractAddress, _, err := initHelper(t, keeper, ctx, codeID, walletA, privKeyA, initMsg, true, defaultGasForTests)
+	require.Empty(t, err)
+
+	balanceQuery := fmt.Sprintf(`{"balance":{"address":"%s"}}`, walletA.String())
+
+	// The schedule is read from the params on every call, and the enclave rejects one without the V1 costs
+	keeper.setParams(ctx, types.Params{GasSchedule: `{"version":"v1","costs":{"regular":1}}`})
+	_, qErr := queryHelper(t, keeper, ctx, contractAddress, balanceQuery, false, defaultGasForTests)
+	require.NotNil(t, qErr.GenericErr)
+
+	keeper.setParams(ctx, types.DefaultParams())
+	qRes, qErr := queryHelper(t, keeper, ctx, contractAddress, balanceQuery, true, defaultGasForTests)
+	require.Empty(t, qErr)
+	require.JSONEq(t, `{"balance":"108"}`, qRes)
+}
@@ -73,6 +73,14 @@ type TestKeepers struct {
 	BankKeeper    bank.Keeper
 }
 
+// genesisSeedEpoch keeps the tests on the genesis epoch of the consensus seed, which is the only
+// one the test enclave has
+type genesisSeedEpoch struct{}
+
+func (genesisSeedEpoch) GetSeedEpoch(ctx sdk.Context) uint32 {
+	return 0
+}
+
 // encoders can be nil to accept the defaults, or set it to override some of the message handlers (like default)
 func CreateTestInput(t *testing.T, isCheckTx bool, tempDir string, supportedFeatures string, encoders *MessageEncoders, queriers *QueryPlugins) (sdk.Context, TestKeepers) {
 	keyContract := sdk.NewKVStoreKey(wasmtypes.StoreKey)
@@ -195,7 +203,7 @@ func CreateTestInput(t *testing.T, isCheckTx bool, tempDir string, supportedFeat
 
 	mintKeeper := mint.NewKeeper(cdc, mintStore, paramsKeeper.Subspace(mint.DefaultParamspace), stakingKeeper, supplyKeeper, auth.FeeCollectorName)
 	mintKeeper.SetMinter(ctx, mint.DefaultInitialMinter())
-	keeper := NewKeeper(cdc, keyContract, accountKeeper, &bk, &govKeeper, &distKeeper, &mintKeeper, &stakingKeeper, router, tempDir, wasmConfig, supportedFeatures, encoders, queriers)
+	keeper := NewKeeper(cdc, keyContract, paramsKeeper.Subspace(wasmtypes.DefaultParamspace), accountKeeper, &bk, &govKeeper, &distKeeper, &mintKeeper, &stakingKeeper, genesisSeedEpoch{}, router, tempDir, wasmConfig, supportedFeatures, encoders, queriers)
 	// add wasm handler so we can loop-back (contracts calling contracts)
 	router.AddRoute(wasmtypes.RouterKey, TestHandler(keeper))
 
@@ -298,3 +306,23 @@ func PrepareExecSignedTx(t *testing.T, keeper Keeper, ctx sdk.Context, sender sd
 
 	return ctx.WithTxBytes(txBytes)
 }
+
+func PrepareMigrateSignedTx(t *testing.T, keeper Keeper, ctx sdk.Context, sender sdk.AccAddress, privKey crypto.PrivKey, encMsg []byte, contract sdk.AccAddress, codeID uint64) sdk.Context {
+	senderAcc, err := auth.GetSignerAcc(ctx, keeper.accountKeeper, sender)
+	require.NoError(t, err)
+
+	tx := authtypes.NewTestTx(ctx, []sdk.Msg{wasmtypes.MsgMigrateContract{
+		Sender:     sender,
+		Contract:   contract,
+		CodeID:     codeID,
+		MigrateMsg: encMsg,
+	}}, []crypto.PrivKey{privKey}, []uint64{senderAcc.GetAccountNumber()}, []uint64{senderAcc.GetSequence() - 1}, authtypes.StdFee{
+		Amount: nil,
+		Gas:    0,
+	})
+
+	txBytes, err := keeper.cdc.MarshalBinaryLengthPrefixed(tx)
+	require.NoError(t, err)
+
+	return ctx.WithTxBytes(txBytes)
+}
@@ -2,8 +2,9 @@ use cosmwasm_storage::PrefixedStorage;
 
 use cosmwasm_std::{
     log, to_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Env, Extern, HandleResponse,
-    HandleResult, HumanAddr, InitResponse, InitResult, Querier, QueryRequest, QueryResult,
-    ReadonlyStorage, StdError, StdResult, Storage, Uint128, WasmMsg, WasmQuery,
+    HandleResult, HumanAddr, InitResponse, InitResult, MigrateResponse, MigrateResult, Querier,
+    QueryRequest, QueryResult, ReadonlyStorage, StdError, StdResult, Storage, Uint128, WasmMsg,
+    WasmQuery,
 };
 
 /////////////////////////////// Messages ///////////////////////////////
@@ -236,6 +237,14 @@ pub enum QueryMsg {
     },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrateMsg {
+    Nop {},
+    SetState { key: String, value: String },
+    ContractError { error_type: String },
+}
+
 /////////////////////////////// Init ///////////////////////////////
 
 pub fn init<S: Storage, A: Api, Q: Querier>(
@@ -1157,6 +1166,28 @@ fn test_canonicalize_address_errors<S: Storage, A: Api, Q: Querier>(
     })
 }
 
+/////////////////////////////// Migrate ///////////////////////////////
+
+pub fn migrate<S: Storage, A: Api, Q: Querier>(
+    deps: &mut Extern<S, A, Q>,
+    _env: Env,
+    msg: MigrateMsg,
+) -> MigrateResult {
+    match msg {
+        MigrateMsg::Nop {} => Ok(MigrateResponse {
+            messages: vec![],
+            log: vec![log("migrate", "🦋")],
+            data: None,
+        }),
+        MigrateMsg::SetState { key, value } => {
+            let mut store = PrefixedStorage::new(b"my_prefix", &mut deps.storage);
+            store.set(key.as_bytes(), value.as_bytes());
+            Ok(MigrateResponse::default())
+        }
+        MigrateMsg::ContractError { error_type } => Err(map_string_to_error(error_type)),
+    }
+}
+
 /////////////////////////////// Query ///////////////////////////////
 
 pub fn query<S: Storage, A: Api, Q: Querier>(
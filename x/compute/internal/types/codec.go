@@ -1,6 +1,7 @@
 package types
 
 import (
+	"github.com/enigmampc/SecretNetwork/types/secp256r1"
 	"github.com/enigmampc/cosmos-sdk/codec"
 	// "github.com/enigmampc/cosmos-sdk/x/supply/exported"
 )
@@ -10,8 +11,8 @@ func RegisterCodec(cdc *codec.Codec) {
 	cdc.RegisterConcrete(MsgStoreCode{}, "wasm/MsgStoreCode", nil)
 	cdc.RegisterConcrete(MsgInstantiateContract{}, "wasm/MsgInstantiateContract", nil)
 	cdc.RegisterConcrete(MsgExecuteContract{}, "wasm/MsgExecuteContract", nil)
+	cdc.RegisterConcrete(MsgMigrateContract{}, "wasm/MsgMigrateContract", nil)
 	/*
-		cdc.RegisterConcrete(MsgMigrateContract{}, "wasm/MsgMigrateContract", nil)
 		cdc.RegisterConcrete(MsgUpdateAdmin{}, "wasm/MsgUpdateAdmin", nil)
 		cdc.RegisterConcrete(MsgClearAdmin{}, "wasm/MsgClearAdmin", nil)
 
@@ -30,5 +31,6 @@ func init() {
 	cdc := codec.New()
 	RegisterCodec(cdc)
 	codec.RegisterCrypto(cdc)
+	secp256r1.RegisterCodec(cdc)
 	ModuleCdc = cdc.Seal()
 }
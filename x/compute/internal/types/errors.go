@@ -54,6 +54,9 @@ var (
 
 	// ErrCreateFailed error for wasm code that has already been uploaded or failed
 	ErrCreateFailed = sdkErrors.Register(DefaultCodespace, 15, "create contract failed")
+
+	// ErrReissueFailed error for contract proofs the enclave failed to issue for a new seed epoch
+	ErrReissueFailed = sdkErrors.Register(DefaultCodespace, 16, "reissue contract proofs failed")
 )
 
 func IsEncryptedErrorCode(code uint32) bool {
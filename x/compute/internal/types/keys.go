@@ -36,8 +36,11 @@ var (
 	ContractStorePrefix = []byte{0x03}
 	SequenceKeyPrefix   = []byte{0x04}
 	// ContractHistoryStorePrefix = []byte{0x05}
-	ContractEnclaveIdPrefix = []byte{0x06}
-	ContractLabelPrefix     = []byte{0x07}
+	ContractEnclaveIdPrefix  = []byte{0x06}
+	ContractLabelPrefix      = []byte{0x07}
+	ContractKeyProofPrefix   = []byte{0x08}
+	ContractAdminProofPrefix = []byte{0x09}
+	ProofsSeedEpochKey       = []byte{0x0a}
 
 	KeyLastCodeID     = append(SequenceKeyPrefix, []byte("lastCodeId")...)
 	KeyLastInstanceID = append(SequenceKeyPrefix, []byte("lastContractId")...)
@@ -63,6 +66,16 @@ func GetContractEnclaveKey(addr sdk.AccAddress) []byte {
 	return append(ContractEnclaveIdPrefix, addr...)
 }
 
+// GetContractKeyProofKey returns the key for the proof that authenticates the contract key in the current seed epoch
+func GetContractKeyProofKey(addr sdk.AccAddress) []byte {
+	return append(ContractKeyProofPrefix, addr...)
+}
+
+// GetContractAdminProofKey returns the key for the proof that lets the admin of a contract migrate it
+func GetContractAdminProofKey(addr sdk.AccAddress) []byte {
+	return append(ContractAdminProofPrefix, addr...)
+}
+
 // GetContractStorePrefixKey returns the store prefix for the WASM contract instance
 func GetContractStorePrefixKey(addr sdk.AccAddress) []byte {
 	return append(ContractStorePrefix, addr...)
@@ -162,12 +162,11 @@ func (msg MsgExecuteContract) GetSigners() []sdk.AccAddress {
 	return []sdk.AccAddress{msg.Sender}
 }
 
-/*
 type MsgMigrateContract struct {
-	Sender     sdk.AccAddress  `json:"sender" yaml:"sender"`
-	Contract   sdk.AccAddress  `json:"contract" yaml:"contract"`
-	CodeID     uint64          `json:"code_id" yaml:"code_id"`
-	MigrateMsg json.RawMessage `json:"msg" yaml:"msg"`
+	Sender     sdk.AccAddress `json:"sender" yaml:"sender"`
+	Contract   sdk.AccAddress `json:"contract" yaml:"contract"`
+	CodeID     uint64         `json:"code_id" yaml:"code_id"`
+	MigrateMsg []byte         `json:"msg" yaml:"msg"`
 }
 
 func (msg MsgMigrateContract) Route() string {
@@ -188,9 +187,11 @@ func (msg MsgMigrateContract) ValidateBasic() error {
 	if err := sdk.VerifyAddressFormat(msg.Contract); err != nil {
 		return sdkerrors.Wrap(err, "contract")
 	}
-	if !json.Valid(msg.MigrateMsg) {
-		return sdkerrors.Wrap(ErrInvalid, "migrate msg json")
-	}
+	/*
+		if !json.Valid(msg.MigrateMsg) {
+			return sdkerrors.Wrap(ErrInvalid, "migrate msg json")
+		}
+	*/
 
 	return nil
 }
@@ -203,6 +204,7 @@ func (msg MsgMigrateContract) GetSigners() []sdk.AccAddress {
 	return []sdk.AccAddress{msg.Sender}
 }
 
+/*
 type MsgUpdateAdmin struct {
 	Sender   sdk.AccAddress `json:"sender" yaml:"sender"`
 	NewAdmin sdk.AccAddress `json:"new_admin" yaml:"new_admin"`
@@ -1,19 +1,99 @@
 package types
 
-/*
 import (
+	"encoding/json"
 	"fmt"
 
-	sdk "github.com/enigmampc/cosmos-sdk/types"
-	sdkerrors "github.com/enigmampc/cosmos-sdk/types/errors"
-	"github.com/enigmampc/cosmos-sdk/x/params"
-	"github.com/pkg/errors"
+	paramtypes "github.com/enigmampc/cosmos-sdk/x/params/subspace"
 	"gopkg.in/yaml.v2"
 )
 
 const (
 	// DefaultParamspace for params keeper
 	DefaultParamspace = ModuleName
+
+	// GasScheduleV1 is the gas schedule every enclave knows. It doesn't have the costs of the
+	// imports that were added later, which get their default cost.
+	GasScheduleV1 = "v1"
+	// GasScheduleV2 has the costs of all the imports. Enclaves that don't know it fail every call
+	// into a contract, so the chain must only switch to it once all the nodes run an enclave that does.
+	GasScheduleV2 = "v2"
+)
+
+var ParamStoreKeyGasSchedule = []byte("gasSchedule")
+
+// Params defines the set of compute parameters.
+type Params struct {
+	// GasSchedule is the JSON encoded gas schedule the enclave meters contracts with, e.g.
+	// {"version":"v2","costs":{...}}. It is passed to the enclave on every call into a contract.
+	// When empty, the enclave uses its default costs.
+	GasSchedule string `json:"gas_schedule" yaml:"gas_schedule"`
+	// UploadAccess                 AccessConfig `json:"code_upload_access" yaml:"code_upload_access"`
+	// DefaultInstantiatePermission AccessType   `json:"instantiate_default_permission" yaml:"instantiate_default_permission"`
+}
+
+// ParamKeyTable returns the parameter key table.
+func ParamKeyTable() paramtypes.KeyTable {
+	return paramtypes.NewKeyTable().RegisterParamSet(&Params{})
+}
+
+// DefaultParams returns default compute parameters
+func DefaultParams() Params {
+	return Params{
+		GasSchedule: "",
+	}
+}
+
+func (p Params) String() string {
+	out, _ := yaml.Marshal(p)
+	return string(out)
+}
+
+// ParamSetPairs returns the parameter set pairs.
+func (p *Params) ParamSetPairs() paramtypes.ParamSetPairs {
+	return paramtypes.ParamSetPairs{
+		paramtypes.NewParamSetPair(ParamStoreKeyGasSchedule, &p.GasSchedule, validateGasSchedule),
+	}
+}
+
+// ValidateBasic performs basic validation on compute parameters
+func (p Params) ValidateBasic() error {
+	return validateGasSchedule(p.GasSchedule)
+}
+
+// validateGasSchedule checks the envelope of the schedule. The costs themselves are checked by the
+// enclave, which fails every call into a contract if they are invalid.
+func validateGasSchedule(i interface{}) error {
+	v, ok := i.(string)
+	if !ok {
+		return fmt.Errorf("invalid parameter type: %T", i)
+	}
+	if v == "" {
+		return nil
+	}
+
+	var schedule struct {
+		Version string                     `json:"version"`
+		Costs   map[string]json.RawMessage `json:"costs"`
+	}
+	if err := json.Unmarshal([]byte(v), &schedule); err != nil {
+		return fmt.Errorf("invalid gas schedule: %s", err)
+	}
+	if schedule.Version != GasScheduleV1 && schedule.Version != GasScheduleV2 {
+		return fmt.Errorf("unknown gas schedule version: %q", schedule.Version)
+	}
+	if len(schedule.Costs) == 0 {
+		return fmt.Errorf("gas schedule has no costs")
+	}
+	return nil
+}
+
+/*
+import (
+	"fmt"
+
+	sdk "github.com/enigmampc/cosmos-sdk/types"
+	sdkerrors "github.com/enigmampc/cosmos-sdk/types/errors"
 )
 
 var ParamStoreKeyUploadAccess = []byte("uploadAccess")
@@ -81,49 +161,6 @@ var (
 	AllowNobody         = AccessConfig{Type: Nobody}
 )
 
-// Params defines the set of wasm parameters.
-type Params struct {
-	UploadAccess                 AccessConfig `json:"code_upload_access" yaml:"code_upload_access"`
-	DefaultInstantiatePermission AccessType   `json:"instantiate_default_permission" yaml:"instantiate_default_permission"`
-}
-
-// ParamKeyTable returns the parameter key table.
-func ParamKeyTable() params.KeyTable {
-	return params.NewKeyTable().RegisterParamSet(&Params{})
-}
-
-// DefaultParams returns default wasm parameters
-func DefaultParams() Params {
-	return Params{
-		UploadAccess:                 AllowEverybody,
-		DefaultInstantiatePermission: Everybody,
-	}
-}
-
-func (p Params) String() string {
-	out, _ := yaml.Marshal(p)
-	return string(out)
-}
-
-// ParamSetPairs returns the parameter set pairs.
-func (p *Params) ParamSetPairs() params.ParamSetPairs {
-	return params.ParamSetPairs{
-		params.NewParamSetPair(ParamStoreKeyUploadAccess, &p.UploadAccess, validateAccessConfig),
-		params.NewParamSetPair(ParamStoreKeyInstantiateAccess, &p.DefaultInstantiatePermission, validateAccessType),
-	}
-}
-
-// ValidateBasic performs basic validation on wasm parameters
-func (p Params) ValidateBasic() error {
-	if err := validateAccessType(p.DefaultInstantiatePermission); err != nil {
-		return errors.Wrap(err, "instantiate default permission")
-	}
-	if err := validateAccessConfig(p.UploadAccess); err != nil {
-		return errors.Wrap(err, "upload access")
-	}
-	return nil
-}
-
 func validateAccessConfig(i interface{}) error {
 	v, ok := i.(AccessConfig)
 	if !ok {
@@ -1,5 +1,58 @@
 package types
 
+import (
+	"testing"
+
+	"github.com/stretchr/testify/assert"
+)
+
+func TestValidateGasScheduleParam(t *testing.T) {
+	specs := map[string]struct {
+		src    string
+		expErr bool
+	}{
+		"empty means the default costs": {
+			src: "",
+		},
+		"v1 schedule": {
+			src: `{"version":"v1","costs":{"regular":1}}`,
+		},
+		"v2 schedule": {
+			src: `{"version":"v2","costs":{"regular":1,"external_random":4096}}`,
+		},
+		"invalid json": {
+			src:    `{"version":"v1","costs":`,
+			expErr: true,
+		},
+		"unknown version": {
+			src:    `{"version":"v3","costs":{"regular":1}}`,
+			expErr: true,
+		},
+		"missing version": {
+			src:    `{"costs":{"regular":1}}`,
+			expErr: true,
+		},
+		"no costs": {
+			src:    `{"version":"v1"}`,
+			expErr: true,
+		},
+		"costs not an object": {
+			src:    `{"version":"v1","costs":[1,2]}`,
+			expErr: true,
+		},
+	}
+	for msg, spec := range specs {
+		t.Run(msg, func(t *testing.T) {
+			err := Params{GasSchedule: spec.src}.ValidateBasic()
+			if spec.expErr {
+				assert.Error(t, err)
+			} else {
+				assert.NoError(t, err)
+			}
+		})
+	}
+}
+
 /*
 import (
 	"encoding/json"
@@ -199,6 +199,12 @@ func NewAbsoluteTxPosition(ctx sdk.Context) *AbsoluteTxPosition {
 	}
 }
 
+// SeedEpochKeeper tells which epoch of the consensus seed the chain is at. It is implemented by
+// x/registration, where the epochs are stored.
+type SeedEpochKeeper interface {
+	GetSeedEpoch(ctx sdk.Context) uint32
+}
+
 // NewEnv initializes the environment for a contract instance
 func NewEnv(ctx sdk.Context, creator sdk.AccAddress, deposit sdk.Coins, contractAddr sdk.AccAddress, contractKey []byte) wasmTypes.Env {
 	// safety checks before casting below
@@ -221,7 +227,8 @@ func NewEnv(ctx sdk.Context, creator sdk.AccAddress, deposit sdk.Coins, contract
 		Contract: wasmTypes.ContractInfo{
 			Address: contractAddr.String(),
 		},
-		Key: wasmTypes.ContractKey(base64.StdEncoding.EncodeToString(contractKey)),
+		Key:          wasmTypes.ContractKey(base64.StdEncoding.EncodeToString(contractKey)),
+		BlockTxsHash: ctx.BlockHeader().DataHash,
 	}
 	return env
 }
@@ -281,10 +288,17 @@ func (m SecretMsg) Serialize() []byte {
 	return append(m.CodeHash, m.Msg...)
 }
 
-func NewVerificationInfo(signBytes []byte, signature auth.StdSignature, callbackSig []byte) wasmTypes.VerificationInfo {
+func NewVerificationInfo(signBytes []byte, signMode string, signature auth.StdSignature, callbackSig []byte) wasmTypes.VerificationInfo {
+	// The enclave reads the type of the public key from its amino json
+	wasmSignature := wasmTypes.Signature{
+		PubKey:    ModuleCdc.MustMarshalJSON(signature.PubKey),
+		Signature: signature.Signature,
+	}
+
 	return wasmTypes.VerificationInfo{
 		Bytes:             signBytes,
-		Signature:         signature,
+		Signature:         wasmSignature,
 		CallbackSignature: callbackSig,
+		SignMode:          signMode,
 	}
 }
@@ -38,7 +38,7 @@ func (AppModuleBasic) RegisterCodec(cdc *codec.Codec) {
 // module.
 func (AppModuleBasic) DefaultGenesis() json.RawMessage {
 	return ModuleCdc.MustMarshalJSON(&GenesisState{
-		// Params: DefaultParams(),
+		Params: DefaultParams(),
 	})
 }
 
@@ -130,7 +130,12 @@ func (am AppModule) ExportGenesis(ctx sdk.Context) json.RawMessage {
 }
 
 // BeginBlock returns the begin blocker for the compute module.
-func (am AppModule) BeginBlock(_ sdk.Context, _ abci.RequestBeginBlock) {}
+// It re-issues the proofs of the contracts when the chain moves to a new seed epoch.
+func (am AppModule) BeginBlock(ctx sdk.Context, _ abci.RequestBeginBlock) {
+	if err := am.keeper.ReissueContractProofs(ctx); err != nil {
+		panic(err)
+	}
+}
 
 // EndBlock returns the end blocker for the compute module. It returns no validator
 // updates.
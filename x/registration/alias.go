@@ -20,6 +20,7 @@ const (
 	EnclaveRegistrationKey = types.EnclaveRegistrationKey
 	QueryEncryptedSeed     = keeper.QueryEncryptedSeed
 	QueryMasterCertificate = keeper.QueryMasterCertificate
+	QuerySeedEpochs        = keeper.QuerySeedEpochs
 	SecretNodeSeedConfig   = types.SecretNodeSeedConfig
 	SecretNodeCfgFolder    = types.SecretNodeCfgFolder
 	EncryptedKeyLength     = types.EncryptedKeyLength
@@ -32,6 +33,7 @@ var (
 	// functions aliases
 	RegisterCodec               = types.RegisterCodec
 	ValidateGenesis             = types.ValidateGenesis
+	ValidateSeedEpochs          = types.ValidateSeedEpochs
 	InitGenesis                 = keeper.InitGenesis
 	ExportGenesis               = keeper.ExportGenesis
 	NewKeeper                   = keeper.NewKeeper
@@ -46,6 +48,7 @@ var (
 	ErrSeedInitFailed       = types.ErrSeedInitFailed
 	RegistrationStorePrefix = types.RegistrationStorePrefix
 	ErrInvalidType          = types.ErrInvalidType
+	ErrInvalidSeedEpoch     = types.ErrInvalidSeedEpoch
 )
 
 type (
@@ -55,4 +58,5 @@ type (
 	SeedConfig        = types.SeedConfig
 	EnclaveApi        = enclave.Api
 	MasterCertificate = types.MasterCertificate
+	SeedEpoch         = types.SeedEpoch
 )
@@ -6,10 +6,10 @@ import (
 
 type Api struct{}
 
-func (Api) LoadSeed(masterCert []byte, seed []byte) (bool, error) {
-	return api.LoadSeedToEnclave(masterCert, seed)
+func (Api) LoadSeed(masterCert []byte, seed []byte, chainID string, policy []byte) (bool, error) {
+	return api.LoadSeedToEnclave(masterCert, seed, chainID, policy)
 }
 
-func (Api) GetEncryptedSeed(masterCert []byte) ([]byte, error) {
-	return api.GetEncryptedSeed(masterCert)
+func (Api) GetEncryptedSeed(masterCert []byte, policy []byte, blockTime uint64) ([]byte, error) {
+	return api.GetEncryptedSeed(masterCert, policy, blockTime)
 }
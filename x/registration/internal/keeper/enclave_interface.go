@@ -1,6 +1,6 @@
 package keeper
 
 type EnclaveInterface interface {
-	LoadSeed(masterCert []byte, seed []byte) (bool, error)
-	GetEncryptedSeed(masterCert []byte) ([]byte, error)
+	LoadSeed(masterCert []byte, seed []byte, chainID string, policy []byte) (bool, error)
+	GetEncryptedSeed(masterCert []byte, policy []byte, blockTime uint64) ([]byte, error)
 }
@@ -18,6 +18,15 @@ func InitGenesis(ctx sdk.Context, keeper Keeper, data types.GenesisState) {
 		for _, storedRegInfo := range data.Registration {
 			keeper.SetRegistrationInfo(ctx, storedRegInfo)
 		}
+		if err := keeper.SetAttestationPolicy(ctx, data.AttestationPolicy); err != nil {
+			panic(err)
+		}
+		if err := types.ValidateSeedEpochs(data.SeedEpochs); err != nil {
+			panic(err)
+		}
+		for _, epoch := range data.SeedEpochs {
+			keeper.SetSeedEpoch(ctx, epoch)
+		}
 	} else {
 		panic("Cannot start without MasterCertificate set")
 	}
@@ -29,6 +38,8 @@ func ExportGenesis(ctx sdk.Context, keeper Keeper) types.GenesisState {
 
 	genState.NodeExchMasterCertificate = *keeper.GetMasterCertificate(ctx, types.MasterNodeKeyId)
 	genState.IoMasterCertificate = *keeper.GetMasterCertificate(ctx, types.MasterIoKeyId)
+	genState.AttestationPolicy = keeper.GetAttestationPolicy(ctx)
+	genState.SeedEpochs = keeper.ListSeedEpochs(ctx)
 
 	keeper.ListRegistrationInfo(ctx, func(pubkey []byte, regInfo types.RegistrationNodeInfo) bool {
 		genState.Registration = append(genState.Registration, regInfo)
@@ -68,3 +68,62 @@ func TestExportGenesis(t *testing.T) {
 	require.Equal(t, string(data.NodeExchMasterCertificate), string(data2.NodeExchMasterCertificate))
 	require.Equal(t, data2.Registration, data2.Registration)
 }
+
+func TestGenesisAttestationPolicy(t *testing.T) {
+	tempDir, err := ioutil.TempDir("", "wasm")
+	require.NoError(t, err)
+	defer os.RemoveAll(tempDir)
+	ctx, keeper := CreateTestInput(t, false, tempDir, true)
+
+	cert, err := ioutil.ReadFile("../../testdata/attestation_cert_sw")
+	require.NoError(t, err)
+
+	policy := []byte(`{"version":1,"accepted_statuses":[{"status":"GROUP_OUT_OF_DATE","until":1600000000}]}`)
+	data := types.GenesisState{
+		Registration:              nil,
+		IoMasterCertificate:       cert,
+		NodeExchMasterCertificate: cert,
+		AttestationPolicy:         policy,
+	}
+
+	InitGenesis(ctx, keeper, data)
+	require.Equal(t, policy, keeper.GetAttestationPolicy(ctx))
+
+	data2 := ExportGenesis(ctx, keeper)
+	require.Equal(t, policy, data2.AttestationPolicy)
+
+	data.AttestationPolicy = []byte(`{"version":2}`)
+	assert.Panics(t, func() { InitGenesis(ctx, keeper, data) }, "Init genesis didn't panic with an invalid policy")
+}
+
+func TestGenesisSeedEpochs(t *testing.T) {
+	tempDir, err := ioutil.TempDir("", "wasm")
+	require.NoError(t, err)
+	defer os.RemoveAll(tempDir)
+	ctx, keeper := CreateTestInput(t, false, tempDir, true)
+
+	cert, err := ioutil.ReadFile("../../testdata/attestation_cert_sw")
+	require.NoError(t, err)
+
+	epochs := []types.SeedEpoch{
+		{Epoch: 1, NodeExchMasterCertificate: cert, IoMasterCertificate: cert, ActivationHeight: 100},
+		{Epoch: 2, NodeExchMasterCertificate: cert, IoMasterCertificate: cert, ActivationHeight: 200},
+	}
+	data := types.GenesisState{
+		Registration:              nil,
+		IoMasterCertificate:       cert,
+		NodeExchMasterCertificate: cert,
+		SeedEpochs:                epochs,
+	}
+
+	InitGenesis(ctx, keeper, data)
+	require.Equal(t, epochs, ExportGenesis(ctx, keeper).SeedEpochs)
+
+	require.Equal(t, uint32(0), keeper.GetSeedEpoch(ctx.WithBlockHeight(99)))
+	require.Equal(t, uint32(1), keeper.GetSeedEpoch(ctx.WithBlockHeight(100)))
+	require.Equal(t, uint32(1), keeper.GetSeedEpoch(ctx.WithBlockHeight(199)))
+	require.Equal(t, uint32(2), keeper.GetSeedEpoch(ctx.WithBlockHeight(200)))
+
+	data.SeedEpochs = epochs[1:]
+	assert.Panics(t, func() { InitGenesis(ctx, keeper, data) }, "Init genesis didn't panic with a missing epoch")
+}
@@ -66,7 +66,7 @@ func InitializeNode(homeDir string, enclave EnclaveInterface) {
 		panic(sdkerrors.Wrap(types.ErrSeedInitFailed, err.Error()))
 	}
 
-	_, err = enclave.LoadSeed(cert, enc)
+	_, err = enclave.LoadSeed(cert, enc, seedCfg.ChainID, seedCfg.AttestationPolicy)
 	if err != nil {
 		panic(sdkerrors.Wrap(types.ErrSeedInitFailed, err.Error()))
 	}
@@ -97,7 +97,8 @@ func (k Keeper) RegisterNode(ctx sdk.Context, certificate ra.Certificate) ([]byt
 			return k.getRegistrationInfo(ctx, publicKey).EncryptedSeed, nil
 		}
 		log.Debug("After getRegistrationInfo")
-		encSeed, err = k.enclave.GetEncryptedSeed(certificate)
+		policy := k.GetAttestationPolicy(ctx)
+		encSeed, err = k.enclave.GetEncryptedSeed(certificate, policy, uint64(ctx.BlockTime().Unix()))
 		log.Debug("After GetEncryptedSeed")
 		if err != nil {
 			// return 0, sdkerrors.Wrap(err, "cosmwasm create")
@@ -157,6 +158,10 @@ func validateSeedParams(config types.SeedConfig) error {
 	if len(config.EncryptedKey) != types.EncryptedKeyLength || !IsHexString(config.EncryptedKey) {
 		return sdkerrors.Wrap(types.ErrSeedValidationParams, "Invalid parameter: `seed` in seed parameters. Did you initialize the node?")
 	}
+
+	if err := types.ValidateAttestationPolicy(config.AttestationPolicy); err != nil {
+		return sdkerrors.Wrap(types.ErrSeedValidationParams, err.Error())
+	}
 	return nil
 }
 
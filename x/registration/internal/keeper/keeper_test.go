@@ -5,6 +5,7 @@ import (
 	eng "github.com/enigmampc/SecretNetwork/types"
 	"github.com/enigmampc/SecretNetwork/x/registration/internal/types"
 	ra "github.com/enigmampc/SecretNetwork/x/registration/remote_attestation"
+	"encoding/json"
 	"github.com/stretchr/testify/require"
 	"io/ioutil"
 	"os"
@@ -45,6 +46,31 @@ func TestNewKeeper_Node(t *testing.T) {
 	require.NotNil(t, regKeeper)
 }
 
+// Nodes that registered before the seed was bound to the chain have no chain id in their seed config
+func TestNewKeeper_LegacyNode(t *testing.T) {
+	tempDir, err := ioutil.TempDir("", "reg")
+	require.NoError(t, err)
+	defer os.RemoveAll(tempDir)
+
+	seedPath := filepath.Join(tempDir, types.SecretNodeCfgFolder, types.SecretNodeSeedConfig)
+
+	err = os.MkdirAll(filepath.Join(tempDir, types.SecretNodeCfgFolder), 0700)
+	require.NoError(t, err)
+
+	var cfg map[string]interface{}
+	err = json.Unmarshal(CreateTestSeedConfig(t), &cfg)
+	require.NoError(t, err)
+	delete(cfg, "chainId")
+	legacyCfg, err := json.Marshal(cfg)
+	require.NoError(t, err)
+
+	err = ioutil.WriteFile(seedPath, legacyCfg, 0700)
+	require.NoError(t, err)
+
+	_, regKeeper := CreateTestInput(t, false, tempDir, false)
+	require.NotNil(t, regKeeper)
+}
+
 func TestKeeper_RegisterationStore(t *testing.T) {
 	tempDir, err := ioutil.TempDir("", "wasm")
 	require.NoError(t, err)
@@ -4,10 +4,10 @@ package mock
 
 type MockEnclaveApi struct{}
 
-func (MockEnclaveApi) LoadSeed(masterCert []byte, seed []byte) (bool, error) {
+func (MockEnclaveApi) LoadSeed(masterCert []byte, seed []byte, chainID string, policy []byte) (bool, error) {
 	return true, nil
 }
 
-func (MockEnclaveApi) GetEncryptedSeed(masterCert []byte) ([]byte, error) {
+func (MockEnclaveApi) GetEncryptedSeed(masterCert []byte, policy []byte, blockTime uint64) ([]byte, error) {
 	return []byte(""), nil
 }
@@ -13,6 +13,7 @@ import (
 const (
 	QueryEncryptedSeed     = "seed"
 	QueryMasterCertificate = "master-cert"
+	QuerySeedEpochs        = "seed-epochs"
 )
 
 // controls error output on querier - set true when testing/debugging
@@ -26,6 +27,8 @@ func NewQuerier(keeper Keeper) sdk.Querier {
 			return queryEncryptedSeed(ctx, path[1], req, keeper)
 		case QueryMasterCertificate:
 			return queryMasterKey(ctx, req, keeper)
+		case QuerySeedEpochs:
+			return querySeedEpochs(ctx, keeper)
 		default:
 			return nil, sdkerrors.Wrap(sdkerrors.ErrUnknownRequest, "unknown data query endpoint")
 		}
@@ -53,6 +56,15 @@ func queryMasterKey(ctx sdk.Context, req abci.RequestQuery, keeper Keeper) ([]by
 	return asBytes, nil
 }
 
+func querySeedEpochs(ctx sdk.Context, keeper Keeper) ([]byte, error) {
+	epochs := keeper.ListSeedEpochs(ctx)
+	if epochs == nil {
+		epochs = []types.SeedEpoch{}
+	}
+
+	return json.Marshal(epochs)
+}
+
 func queryEncryptedSeed(ctx sdk.Context, pubkey string, req abci.RequestQuery, keeper Keeper) ([]byte, error) {
 	pubkeyBytes, err := hex.DecodeString(pubkey)
 	if err != nil {
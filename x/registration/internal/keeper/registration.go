@@ -43,6 +43,27 @@ func (k Keeper) setMasterCertificate(ctx sdk.Context, cert types.MasterCertifica
 	store.Set(types.MasterCertPrefix(certType), k.cdc.MustMarshalBinaryBare(cert))
 }
 
+// GetAttestationPolicy returns the JSON encoded attestation policy of the chain, or nil if the
+// enclave should use its compiled-in rules
+func (k Keeper) GetAttestationPolicy(ctx sdk.Context) []byte {
+	store := ctx.KVStore(k.storeKey)
+	return store.Get(types.AttestationPolicyKey)
+}
+
+func (k Keeper) SetAttestationPolicy(ctx sdk.Context, policy []byte) error {
+	if err := types.ValidateAttestationPolicy(policy); err != nil {
+		return err
+	}
+
+	store := ctx.KVStore(k.storeKey)
+	if len(policy) == 0 {
+		store.Delete(types.AttestationPolicyKey)
+	} else {
+		store.Set(types.AttestationPolicyKey, policy)
+	}
+	return nil
+}
+
 func (k Keeper) isMasterCertificateDefined(ctx sdk.Context, certType string) bool {
 	regInfo := k.GetMasterCertificate(ctx, certType)
 	if regInfo == nil {
@@ -103,3 +124,39 @@ func (k Keeper) isNodeAuthenticated(ctx sdk.Context, publicKey types.NodeID) (bo
 		return true, nil
 	}
 }
+
+func (k Keeper) SetSeedEpoch(ctx sdk.Context, epoch types.SeedEpoch) {
+	store := ctx.KVStore(k.storeKey)
+
+	store.Set(types.SeedEpochKey(epoch.Epoch), k.cdc.MustMarshalBinaryBare(epoch))
+}
+
+// ListSeedEpochs returns the epochs of the consensus seed that were added after genesis, in order
+func (k Keeper) ListSeedEpochs(ctx sdk.Context) []types.SeedEpoch {
+	var epochs []types.SeedEpoch
+
+	prefixStore := prefix.NewStore(ctx.KVStore(k.storeKey), types.SeedEpochPrefix)
+	iter := prefixStore.Iterator(nil, nil)
+	defer iter.Close()
+	for ; iter.Valid(); iter.Next() {
+		var epoch types.SeedEpoch
+		k.cdc.MustUnmarshalBinaryBare(iter.Value(), &epoch)
+		epochs = append(epochs, epoch)
+	}
+
+	return epochs
+}
+
+// GetSeedEpoch returns the epoch of the consensus seed that the chain uses at the current block
+// height, which is passed to the enclave in the env of every contract call. 0 is the genesis epoch.
+func (k Keeper) GetSeedEpoch(ctx sdk.Context) uint32 {
+	var current uint32
+	for _, epoch := range k.ListSeedEpochs(ctx) {
+		if epoch.ActivationHeight > ctx.BlockHeight() {
+			break
+		}
+		current = epoch.Epoch
+	}
+
+	return current
+}
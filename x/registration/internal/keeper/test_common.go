@@ -28,6 +28,7 @@ func CreateTestSeedConfig(t *testing.T) []byte {
 	cfg := types.SeedConfig{
 		EncryptedKey: seed,
 		MasterCert:   base64.StdEncoding.EncodeToString(cert),
+		ChainID:      "test-chain",
 	}
 
 	cfgBytes, err := json.Marshal(&cfg)
@@ -22,4 +22,8 @@ var (
 	ErrInvalidType = sdkErrors.Register(DefaultCodespace, 5, "Type error")
 
 	ErrCertificateInvalid = sdkErrors.Register(DefaultCodespace, 6, "Certificate invalid or does not exist")
+
+	ErrInvalidAttestationPolicy = sdkErrors.Register(DefaultCodespace, 7, "Invalid attestation policy")
+
+	ErrInvalidSeedEpoch = sdkErrors.Register(DefaultCodespace, 8, "Invalid seed epoch")
 )
@@ -5,11 +5,23 @@ type GenesisState struct {
 	Registration              []RegistrationNodeInfo `json:"reg_info"`
 	NodeExchMasterCertificate []byte                 `json:"node_exch_cert"`
 	IoMasterCertificate       []byte                 `json:"io_exch_cert"`
+	// AttestationPolicy is the JSON encoded AttestationPolicy of the chain, or empty to use the
+	// rules compiled into the enclave
+	AttestationPolicy         []byte                 `json:"attestation_policy,omitempty"`
+	// SeedEpochs are the epochs of the consensus seed that were added after genesis, in order
+	SeedEpochs                []SeedEpoch            `json:"seed_epochs,omitempty"`
 }
 
 // ValidateGenesis performs basic validation of supply genesis data returning an
 // error for any failed validation criteria.
 func ValidateGenesis(data GenesisState) error {
+	if err := ValidateAttestationPolicy(data.AttestationPolicy); err != nil {
+		return err
+	}
+
+	if err := ValidateSeedEpochs(data.SeedEpochs); err != nil {
+		return err
+	}
 
 	// todo: do we want to use this, or just fail if they don't exist?
 
@@ -1,5 +1,7 @@
 package types
 
+import "encoding/binary"
+
 const (
 	// ModuleName is the name of the contract module
 	ModuleName = "register"
@@ -21,6 +23,8 @@ const (
 var (
 	RegistrationStorePrefix = []byte{0x01}
 	MasterKeyPrefix         = []byte{0x02}
+	AttestationPolicyKey    = []byte{0x03}
+	SeedEpochPrefix         = []byte{0x04}
 )
 
 func RegistrationKeyPrefix(key []byte) []byte {
@@ -30,3 +34,10 @@ func RegistrationKeyPrefix(key []byte) []byte {
 func MasterCertPrefix(key string) []byte {
 	return append(MasterKeyPrefix, []byte(key)...)
 }
+
+// SeedEpochKey is big endian, so the epochs are iterated in order
+func SeedEpochKey(epoch uint32) []byte {
+	bz := make([]byte, 4)
+	binary.BigEndian.PutUint32(bz, epoch)
+	return append(SeedEpochPrefix, bz...)
+}
@@ -0,0 +1,89 @@
+package types
+
+import (
+	"bytes"
+	"encoding/hex"
+	"encoding/json"
+	"fmt"
+
+	sdkerrors "github.com/enigmampc/cosmos-sdk/types/errors"
+)
+
+// AttestationPolicyVersion is the version of the attestation policy format that the enclave
+// understands. The enclave rejects every registration if the policy is of any other version.
+const AttestationPolicyVersion = 1
+
+var knownQuoteStatuses = map[string]bool{
+	"OK":                                    true,
+	"SIGNATURE_INVALID":                     true,
+	"GROUP_REVOKED":                         true,
+	"SIGNATURE_REVOKED":                     true,
+	"KEY_REVOKED":                           true,
+	"SIGRL_VERSION_MISMATCH":                true,
+	"GROUP_OUT_OF_DATE":                     true,
+	"CONFIGURATION_NEEDED":                  true,
+	"SW_HARDENING_NEEDED":                   true,
+	"CONFIGURATION_AND_SW_HARDENING_NEEDED": true,
+	"OUT_OF_DATE":                           true,
+	"OUT_OF_DATE_CONFIGURATION_NEEDED":      true,
+}
+
+// AcceptedStatus is a quote status that the enclave accepts, until the block time Until (in
+// seconds since the unix epoch) if it is set
+type AcceptedStatus struct {
+	Status string  `json:"status"`
+	Until  *uint64 `json:"until,omitempty"`
+}
+
+// AcceptedAdvisory is an Intel security advisory that the enclave accepts, until the block time
+// Until (in seconds since the unix epoch) if it is set
+type AcceptedAdvisory struct {
+	ID    string  `json:"id"`
+	Until *uint64 `json:"until,omitempty"`
+}
+
+// AttestationPolicy restricts the compiled-in rules by which the enclave authenticates
+// registering nodes. It is handed to the enclave as JSON, exactly as it is stored. The enclave
+// binds the consensus seed to the policy of the genesis file, so it can't be changed afterwards.
+type AttestationPolicy struct {
+	Version            uint32             `json:"version"`
+	AcceptedStatuses   []AcceptedStatus   `json:"accepted_statuses,omitempty"`
+	AcceptedAdvisories []AcceptedAdvisory `json:"accepted_advisories,omitempty"`
+	AllowedMrEnclaves  []string           `json:"allowed_mr_enclaves,omitempty"`
+	MinIsvSvn          uint16             `json:"min_isv_svn,omitempty"`
+}
+
+// ValidateAttestationPolicy checks that the enclave will accept the policy, so that a bad policy
+// doesn't make every registration fail. An empty policy is valid, and means the enclave only uses
+// its compiled-in rules.
+func ValidateAttestationPolicy(bz []byte) error {
+	if len(bz) == 0 {
+		return nil
+	}
+
+	var policy AttestationPolicy
+	decoder := json.NewDecoder(bytes.NewReader(bz))
+	decoder.DisallowUnknownFields()
+	if err := decoder.Decode(&policy); err != nil {
+		return sdkerrors.Wrap(ErrInvalidAttestationPolicy, err.Error())
+	}
+
+	if policy.Version != AttestationPolicyVersion {
+		return sdkerrors.Wrap(ErrInvalidAttestationPolicy, fmt.Sprintf("unsupported version %d", policy.Version))
+	}
+
+	for _, status := range policy.AcceptedStatuses {
+		if !knownQuoteStatuses[status.Status] {
+			return sdkerrors.Wrap(ErrInvalidAttestationPolicy, fmt.Sprintf("unknown quote status %s", status.Status))
+		}
+	}
+
+	for _, mrEnclave := range policy.AllowedMrEnclaves {
+		decoded, err := hex.DecodeString(mrEnclave)
+		if err != nil || len(decoded) != 32 {
+			return sdkerrors.Wrap(ErrInvalidAttestationPolicy, fmt.Sprintf("invalid MRENCLAVE %s", mrEnclave))
+		}
+	}
+
+	return nil
+}
@@ -0,0 +1,36 @@
+package types
+
+import (
+	"testing"
+
+	"github.com/stretchr/testify/require"
+)
+
+func TestValidateAttestationPolicy(t *testing.T) {
+	valid := []string{
+		``,
+		`{"version":1}`,
+		`{
+			"version": 1,
+			"accepted_statuses": [{"status": "SW_HARDENING_NEEDED"}, {"status": "GROUP_OUT_OF_DATE", "until": 1600000000}],
+			"accepted_advisories": [{"id": "INTEL-SA-00334"}],
+			"allowed_mr_enclaves": ["2a04846c7b02f31e8b9298e0a0ebb89b04fdc1f7dba1864f679594ae439158cf"],
+			"min_isv_svn": 2
+		}`,
+	}
+	for _, policy := range valid {
+		require.NoError(t, ValidateAttestationPolicy([]byte(policy)), policy)
+	}
+
+	invalid := []string{
+		`not json`,
+		`{"version":2}`,
+		`{"version":1,"grace_period":5}`,
+		`{"version":1,"accepted_statuses":[{"status":"MOSTLY_OK"}]}`,
+		`{"version":1,"allowed_mr_enclaves":["abcd"]}`,
+		`{"version":1,"min_isv_svn":-1}`,
+	}
+	for _, policy := range invalid {
+		require.Error(t, ValidateAttestationPolicy([]byte(policy)), policy)
+	}
+}
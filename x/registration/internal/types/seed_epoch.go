@@ -0,0 +1,42 @@
+package types
+
+import (
+	"fmt"
+
+	sdkerrors "github.com/enigmampc/cosmos-sdk/types/errors"
+)
+
+// SeedEpoch is an epoch of the consensus seed that was added after genesis, because the seed of
+// the previous epoch was suspected to be compromised. The certificates are the attested seed
+// exchange and IO keys of the epoch, like the master certificates of genesis.
+//
+// Every node must load the seed of the epoch before ActivationHeight. From that height contracts
+// encrypt new state with the epoch and sign their callbacks with it, and clients may encrypt to
+// its IO key.
+type SeedEpoch struct {
+	Epoch                     uint32 `json:"epoch"`
+	NodeExchMasterCertificate []byte `json:"node_exch_cert"`
+	IoMasterCertificate       []byte `json:"io_exch_cert"`
+	ActivationHeight          int64  `json:"activation_height"`
+}
+
+// ValidateSeedEpochs checks that the epochs follow the genesis epoch (0) in order, and that they
+// are activated in order. The enclave can only load epochs in order, and the chain must never go
+// back to an older epoch.
+func ValidateSeedEpochs(epochs []SeedEpoch) error {
+	var lastHeight int64
+	for i, epoch := range epochs {
+		if epoch.Epoch != uint32(i+1) {
+			return sdkerrors.Wrap(ErrInvalidSeedEpoch, fmt.Sprintf("expected epoch %d, got %d", i+1, epoch.Epoch))
+		}
+		if len(epoch.NodeExchMasterCertificate) == 0 || len(epoch.IoMasterCertificate) == 0 {
+			return sdkerrors.Wrap(ErrInvalidSeedEpoch, fmt.Sprintf("epoch %d is missing its certificates", epoch.Epoch))
+		}
+		if epoch.ActivationHeight < lastHeight {
+			return sdkerrors.Wrap(ErrInvalidSeedEpoch, fmt.Sprintf("epoch %d is activated before the epoch before it", epoch.Epoch))
+		}
+		lastHeight = epoch.ActivationHeight
+	}
+
+	return nil
+}
@@ -26,6 +26,13 @@ type NodeID []byte
 type SeedConfig struct {
 	MasterCert   string `json:"pk"`
 	EncryptedKey string `json:"encKey"`
+
+	// ChainID is the chain the seed belongs to. Configs of nodes that registered before the seed was
+	// bound to the chain don't have it, and their seed decrypts without it.
+	ChainID string `json:"chainId,omitempty"`
+
+	// AttestationPolicy is the attestation policy of the chain, as found in the genesis file
+	AttestationPolicy []byte `json:"attestationPolicy,omitempty"`
 }
 
 func (c SeedConfig) Decode() ([]byte, []byte, error) {